@@ -0,0 +1,111 @@
+//! Tick handler conditions over thousands of entities, interpreted and
+//! compiled
+//!
+//! Run with `cargo bench -p pulsive-core --bench conditions`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use pulsive_core::{
+    DefId, Effect, EvalContext, Expr, Model, ModifyOp, Runtime, TickHandler, Value,
+};
+use std::hint::black_box;
+
+const ENTITY_COUNTS: [usize; 2] = [1_000, 10_000];
+
+/// A model with units of varied health and morale
+fn model(units: usize) -> Model {
+    let mut model = Model::with_seed(1);
+    model.set_global("max_hp", 100.0f64);
+    for i in 0..units {
+        let unit = model.entities_mut().create("unit");
+        unit.set("hp", (i % 100) as f64);
+        unit.set("morale", (i % 7) as i64);
+        if i % 3 == 0 {
+            unit.add_flag(DefId::new("veteran"));
+        }
+    }
+    model
+}
+
+/// A typical handler condition: wounded veterans or routing units
+fn condition() -> Expr {
+    let lit = |v: f64| Box::new(Expr::lit(v));
+    Expr::Or(vec![
+        Expr::And(vec![
+            Expr::HasFlag(DefId::new("veteran")),
+            Expr::Lt(
+                Box::new(Expr::Mul(Box::new(Expr::prop("hp")), lit(2.0))),
+                Box::new(Expr::global("max_hp")),
+            ),
+        ]),
+        Expr::Le(
+            Box::new(Expr::Sub(Box::new(Expr::prop("morale")), lit(1.0))),
+            lit(0.0),
+        ),
+    ])
+}
+
+fn bench_conditions(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick_handler_conditions");
+    let expr = condition();
+    let compiled = expr.compile();
+    for units in ENTITY_COUNTS {
+        let mut model = model(units);
+        let params = Default::default();
+
+        group.bench_with_input(BenchmarkId::new("interpreted", units), &units, |b, _| {
+            b.iter(|| {
+                let (entities, globals, rng) = model.eval_refs();
+                let mut matched = 0;
+                for entity in entities.iter() {
+                    let mut ctx =
+                        EvalContext::new(entities, globals, &params, rng).with_target(entity);
+                    if expr.eval(&mut ctx).is_ok_and(|v: Value| v.is_truthy()) {
+                        matched += 1;
+                    }
+                }
+                black_box(matched)
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("compiled", units), &units, |b, _| {
+            b.iter(|| {
+                let (entities, globals, rng) = model.eval_refs();
+                let mut matched = 0;
+                for entity in entities.iter() {
+                    let mut ctx =
+                        EvalContext::new(entities, globals, &params, rng).with_target(entity);
+                    if compiled.eval_condition(&mut ctx).unwrap_or(false) {
+                        matched += 1;
+                    }
+                }
+                black_box(matched)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_runtime_tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("runtime_tick");
+    for units in ENTITY_COUNTS {
+        let mut model = model(units);
+        let mut runtime = Runtime::new();
+        runtime.on_tick(TickHandler {
+            id: DefId::new("rally"),
+            condition: Some(condition()),
+            target_kind: Some(DefId::new("unit")),
+            effects: vec![Effect::ModifyProperty {
+                property: "morale".to_string(),
+                op: ModifyOp::Add,
+                value: Expr::lit(0.0),
+            }],
+            priority: 0,
+        });
+        group.bench_with_input(BenchmarkId::from_parameter(units), &units, |b, _| {
+            b.iter(|| black_box(runtime.tick(&mut model)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_conditions, bench_runtime_tick);
+criterion_main!(benches);
@@ -0,0 +1,400 @@
+//! Expressions compiled to closures
+//!
+//! [`Expr::eval`] walks the expression tree on every evaluation, cloning each
+//! value it reads. Handler conditions run for every matching entity every
+//! tick, so the runtime compiles them once, at registration, into a tree of
+//! closures:
+//!
+//! - Numeric subexpressions compute plain `f64`s, reading properties, globals
+//!   and parameters without cloning them, by names interned at compile time
+//! - Comparisons and logic compute plain `bool`s
+//! - Anything else (entity queries, randomness, curves, history, strings)
+//!   falls back to [`Expr::eval`]
+//!
+//! A [`CompiledExpr`] returns exactly what [`Expr::eval`] does, errors
+//! included, and draws the same random numbers.
+
+use crate::expr::values_equal;
+use crate::{Error, EvalContext, Expr, Result, Symbol, Value};
+use std::fmt;
+use std::sync::Arc;
+
+/// A number, or the type name of a value that is not one
+type Number = std::result::Result<f64, &'static str>;
+
+type ValueFn = Arc<dyn Fn(&mut EvalContext) -> Result<Value> + Send + Sync>;
+type NumberFn = Arc<dyn Fn(&mut EvalContext) -> Result<Number> + Send + Sync>;
+type BoolFn = Arc<dyn Fn(&mut EvalContext) -> Result<bool> + Send + Sync>;
+
+/// An expression compiled for repeated evaluation
+#[derive(Clone)]
+pub struct CompiledExpr {
+    source: Expr,
+    value: ValueFn,
+    truthy: BoolFn,
+}
+
+impl CompiledExpr {
+    /// Get the expression this was compiled from
+    pub fn source(&self) -> &Expr {
+        &self.source
+    }
+
+    /// Evaluate the expression
+    pub fn eval(&self, ctx: &mut EvalContext) -> Result<Value> {
+        (self.value)(ctx)
+    }
+
+    /// Evaluate the expression as a condition, returning whether its value
+    /// is truthy
+    pub fn eval_condition(&self, ctx: &mut EvalContext) -> Result<bool> {
+        (self.truthy)(ctx)
+    }
+}
+
+impl fmt::Debug for CompiledExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CompiledExpr").field(&self.source).finish()
+    }
+}
+
+impl From<Expr> for CompiledExpr {
+    fn from(source: Expr) -> Self {
+        Self {
+            value: value_fn(&source),
+            truthy: bool_fn(&source),
+            source,
+        }
+    }
+}
+
+impl Expr {
+    /// Compile this expression for repeated evaluation
+    pub fn compile(&self) -> CompiledExpr {
+        CompiledExpr::from(self.clone())
+    }
+}
+
+fn type_error(got: &'static str) -> Error {
+    Error::TypeError {
+        expected: "number".to_string(),
+        got: got.to_string(),
+    }
+}
+
+fn number(value: &Value) -> Number {
+    value.as_float().ok_or_else(|| value.type_name())
+}
+
+fn no_target(what: &str) -> Error {
+    Error::EvaluationError(format!("No target entity for {}", what))
+}
+
+/// Compile an expression to a closure computing its value
+fn value_fn(expr: &Expr) -> ValueFn {
+    match expr {
+        Expr::Literal(v) => {
+            let v = v.clone();
+            Arc::new(move |_| Ok(v.clone()))
+        }
+        Expr::Property(name) => {
+            let name = Symbol::new(name);
+            Arc::new(move |ctx| {
+                let entity = ctx.target.ok_or_else(|| no_target("Property access"))?;
+                Ok(entity.get(&name).cloned().unwrap_or(Value::Null))
+            })
+        }
+        Expr::Global(name) => {
+            let name = Symbol::new(name);
+            Arc::new(move |ctx| Ok(ctx.globals.get(&name).cloned().unwrap_or(Value::Null)))
+        }
+        Expr::Param(name) => {
+            let name = Symbol::new(name);
+            Arc::new(move |ctx| Ok(ctx.params.get(&name).cloned().unwrap_or(Value::Null)))
+        }
+        Expr::Add(..)
+        | Expr::Sub(..)
+        | Expr::Mul(..)
+        | Expr::Div(..)
+        | Expr::Mod(..)
+        | Expr::Neg(_)
+        | Expr::Abs(_)
+        | Expr::Min(..)
+        | Expr::Max(..)
+        | Expr::Clamp(..) => {
+            let f = number_fn(expr);
+            Arc::new(move |ctx| Ok(Value::Float(f(ctx)?.map_err(type_error)?)))
+        }
+        Expr::Floor(a) | Expr::Ceil(a) | Expr::Round(a) => {
+            let round: fn(f64) -> f64 = match expr {
+                Expr::Floor(_) => f64::floor,
+                Expr::Ceil(_) => f64::ceil,
+                _ => f64::round,
+            };
+            let a = number_fn(a);
+            Arc::new(move |ctx| Ok(Value::Int(round(a(ctx)?.map_err(type_error)?) as i64)))
+        }
+        Expr::Eq(..)
+        | Expr::Ne(..)
+        | Expr::Lt(..)
+        | Expr::Le(..)
+        | Expr::Gt(..)
+        | Expr::Ge(..)
+        | Expr::And(_)
+        | Expr::Or(_)
+        | Expr::Not(_)
+        | Expr::HasFlag(_)
+        | Expr::HasTag(_) => {
+            let f = bool_fn(expr);
+            Arc::new(move |ctx| Ok(Value::Bool(f(ctx)?)))
+        }
+        Expr::If(cond, then_expr, else_expr) => {
+            let (cond, then_expr, else_expr) =
+                (bool_fn(cond), value_fn(then_expr), value_fn(else_expr));
+            Arc::new(move |ctx| {
+                if cond(ctx)? {
+                    then_expr(ctx)
+                } else {
+                    else_expr(ctx)
+                }
+            })
+        }
+        _ => {
+            let expr = expr.clone();
+            Arc::new(move |ctx| expr.eval(ctx))
+        }
+    }
+}
+
+/// Compile an expression to a closure computing its value as a number
+fn number_fn(expr: &Expr) -> NumberFn {
+    fn binary(a: &Expr, b: &Expr, op: fn(f64, f64) -> f64) -> NumberFn {
+        let (a, b) = (number_fn(a), number_fn(b));
+        Arc::new(move |ctx| {
+            let (x, y) = (a(ctx)?, b(ctx)?);
+            Ok(Ok(op(x.map_err(type_error)?, y.map_err(type_error)?)))
+        })
+    }
+    fn unary(a: &Expr, op: fn(f64) -> f64) -> NumberFn {
+        let a = number_fn(a);
+        Arc::new(move |ctx| Ok(Ok(op(a(ctx)?.map_err(type_error)?))))
+    }
+
+    match expr {
+        Expr::Literal(v) => {
+            let n = number(v);
+            Arc::new(move |_| Ok(n))
+        }
+        Expr::Property(name) => {
+            let name = Symbol::new(name);
+            Arc::new(move |ctx| {
+                let entity = ctx.target.ok_or_else(|| no_target("Property access"))?;
+                Ok(entity.get(&name).map_or(Err("null"), number))
+            })
+        }
+        Expr::Global(name) => {
+            let name = Symbol::new(name);
+            Arc::new(move |ctx| Ok(ctx.globals.get(&name).map_or(Err("null"), number)))
+        }
+        Expr::Param(name) => {
+            let name = Symbol::new(name);
+            Arc::new(move |ctx| Ok(ctx.params.get(&name).map_or(Err("null"), number)))
+        }
+        Expr::Add(a, b) => binary(a, b, |x, y| x + y),
+        Expr::Sub(a, b) => binary(a, b, |x, y| x - y),
+        Expr::Mul(a, b) => binary(a, b, |x, y| x * y),
+        Expr::Mod(a, b) => binary(a, b, |x, y| x % y),
+        Expr::Min(a, b) => binary(a, b, f64::min),
+        Expr::Max(a, b) => binary(a, b, f64::max),
+        Expr::Div(a, b) => {
+            let (a, b) = (number_fn(a), number_fn(b));
+            Arc::new(move |ctx| {
+                let (x, y) = (a(ctx)?, b(ctx)?);
+                // The divisor is checked first, as in Expr::eval
+                let y = y.map_err(type_error)?;
+                if y == 0.0 {
+                    return Err(Error::DivisionByZero);
+                }
+                Ok(Ok(x.map_err(type_error)? / y))
+            })
+        }
+        Expr::Neg(a) => unary(a, |x| -x),
+        Expr::Abs(a) => unary(a, f64::abs),
+        Expr::Floor(a) => unary(a, |x| x.floor() as i64 as f64),
+        Expr::Ceil(a) => unary(a, |x| x.ceil() as i64 as f64),
+        Expr::Round(a) => unary(a, |x| x.round() as i64 as f64),
+        Expr::Clamp(v, min, max) => {
+            let (v, min, max) = (number_fn(v), number_fn(min), number_fn(max));
+            Arc::new(move |ctx| {
+                let (v, min, max) = (v(ctx)?, min(ctx)?, max(ctx)?);
+                let v = v.map_err(type_error)?;
+                Ok(Ok(
+                    v.clamp(min.map_err(type_error)?, max.map_err(type_error)?)
+                ))
+            })
+        }
+        _ => {
+            let f = value_fn(expr);
+            Arc::new(move |ctx| Ok(number(&f(ctx)?)))
+        }
+    }
+}
+
+/// Compile an expression to a closure computing whether its value is truthy
+fn bool_fn(expr: &Expr) -> BoolFn {
+    fn compare(a: &Expr, b: &Expr, cmp: fn(f64, f64) -> bool) -> BoolFn {
+        let (a, b) = (number_fn(a), number_fn(b));
+        Arc::new(move |ctx| {
+            let (x, y) = (a(ctx)?, b(ctx)?);
+            Ok(cmp(x.map_err(type_error)?, y.map_err(type_error)?))
+        })
+    }
+
+    match expr {
+        Expr::Literal(v) => {
+            let truthy = v.is_truthy();
+            Arc::new(move |_| Ok(truthy))
+        }
+        Expr::Lt(a, b) => compare(a, b, |x, y| x < y),
+        Expr::Le(a, b) => compare(a, b, |x, y| x <= y),
+        Expr::Gt(a, b) => compare(a, b, |x, y| x > y),
+        Expr::Ge(a, b) => compare(a, b, |x, y| x >= y),
+        Expr::Eq(a, b) | Expr::Ne(a, b) => {
+            let equal = matches!(expr, Expr::Eq(..));
+            let (a, b) = (value_fn(a), value_fn(b));
+            Arc::new(move |ctx| {
+                let (x, y) = (a(ctx)?, b(ctx)?);
+                Ok(values_equal(&x, &y) == equal)
+            })
+        }
+        Expr::And(exprs) => {
+            let exprs: Vec<BoolFn> = exprs.iter().map(bool_fn).collect();
+            Arc::new(move |ctx| {
+                for expr in &exprs {
+                    if !expr(ctx)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            })
+        }
+        Expr::Or(exprs) => {
+            let exprs: Vec<BoolFn> = exprs.iter().map(bool_fn).collect();
+            Arc::new(move |ctx| {
+                for expr in &exprs {
+                    if expr(ctx)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            })
+        }
+        Expr::Not(a) => {
+            let a = bool_fn(a);
+            Arc::new(move |ctx| Ok(!a(ctx)?))
+        }
+        Expr::HasFlag(flag) => {
+            let flag = flag.clone();
+            Arc::new(move |ctx| {
+                let entity = ctx.target.ok_or_else(|| no_target("HasFlag"))?;
+                Ok(entity.has_flag(&flag))
+            })
+        }
+        Expr::HasTag(tag) => {
+            let tag = tag.clone();
+            Arc::new(move |ctx| {
+                let entity = ctx.target.ok_or_else(|| no_target("HasTag"))?;
+                Ok(entity.has_tag(&tag))
+            })
+        }
+        Expr::If(cond, then_expr, else_expr) => {
+            let (cond, then_expr, else_expr) =
+                (bool_fn(cond), bool_fn(then_expr), bool_fn(else_expr));
+            Arc::new(move |ctx| {
+                if cond(ctx)? {
+                    then_expr(ctx)
+                } else {
+                    else_expr(ctx)
+                }
+            })
+        }
+        _ => {
+            let f = value_fn(expr);
+            Arc::new(move |ctx| Ok(f(ctx)?.is_truthy()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DefId, EntityStore, Rng, ValueMap};
+
+    #[test]
+    fn test_compiled_matches_eval() {
+        let mut entities = EntityStore::new();
+        let unit = entities.create("unit");
+        unit.set("hp", 7i64);
+        unit.set("name", "scout");
+        unit.add_flag(DefId::new("veteran"));
+        let unit = unit.clone();
+        let mut globals = ValueMap::new();
+        globals.insert("max_hp".to_string(), Value::Float(20.0));
+        let params = ValueMap::new();
+
+        let hp = || Box::new(Expr::prop("hp"));
+        let lit = |v: f64| Box::new(Expr::lit(v));
+        let exprs = [
+            Expr::And(vec![
+                Expr::Gt(hp(), lit(5.0)),
+                Expr::Lt(
+                    Box::new(Expr::Mul(hp(), lit(2.0))),
+                    Box::new(Expr::global("max_hp")),
+                ),
+                Expr::HasFlag(DefId::new("veteran")),
+            ]),
+            Expr::Floor(Box::new(Expr::Div(hp(), lit(2.0)))),
+            Expr::Clamp(hp(), lit(0.0), Box::new(Expr::global("max_hp"))),
+            Expr::Eq(Box::new(Expr::prop("name")), Box::new(Expr::lit("scout"))),
+            Expr::If(
+                Box::new(Expr::Not(Box::new(Expr::HasFlag(DefId::new("routed"))))),
+                Box::new(Expr::RandomRange(lit(0.0), hp())),
+                lit(0.0),
+            ),
+            Expr::Or(vec![Expr::prop("missing"), Expr::Param("flag".to_string())]),
+            // Errors: not a number, division by zero, missing target
+            Expr::Add(Box::new(Expr::prop("name")), hp()),
+            Expr::Div(Box::new(Expr::prop("name")), lit(0.0)),
+            Expr::Neg(Box::new(Expr::prop("missing"))),
+        ];
+
+        for expr in &exprs {
+            let compiled = expr.compile();
+            for target in [Some(&unit), None] {
+                let (mut rng_a, mut rng_b, mut rng_c) = (Rng::new(7), Rng::new(7), Rng::new(7));
+                let mut ctx = EvalContext::new(&entities, &globals, &params, &mut rng_a);
+                ctx.target = target;
+                let expected = expr.eval(&mut ctx);
+                let mut ctx = EvalContext::new(&entities, &globals, &params, &mut rng_b);
+                ctx.target = target;
+                let value = compiled.eval(&mut ctx);
+                let mut ctx = EvalContext::new(&entities, &globals, &params, &mut rng_c);
+                ctx.target = target;
+                let truthy = compiled.eval_condition(&mut ctx);
+                assert_eq!(rng_a.state(), rng_c.state());
+                assert_eq!(
+                    format!("{:?}", value),
+                    format!("{:?}", expected),
+                    "{:?}",
+                    expr
+                );
+                assert_eq!(
+                    truthy.ok(),
+                    expected.ok().map(|v| v.is_truthy()),
+                    "{:?}",
+                    expr
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Computed properties derived from other state
+//!
+//! A [`ComputedProperty`] defines an entity property by an expression over
+//! the entity's other properties, flags and globals:
+//!
+//! ```rust,ignore
+//! runtime.register_computed("nation", "power", Expr::parse("army_size * tech_level")?);
+//! ```
+//!
+//! The runtime stores computed values on the entity like any other property,
+//! so expressions, bridges and persistence read them as usual. They are
+//! recomputed at the end of every tick, only for entities whose dependencies
+//! changed, and effects cannot write to them.
+
+use crate::{DefId, Entity, Expr, Value, ValueMap};
+
+/// A property of an entity kind defined by an expression
+#[derive(Debug, Clone)]
+pub struct ComputedProperty {
+    /// Entity kind the property belongs to
+    pub kind: DefId,
+    /// Property name
+    pub property: String,
+    /// Expression evaluated with the entity as target
+    pub expr: Expr,
+    /// State the expression reads
+    dependencies: Dependencies,
+}
+
+/// State read by an expression
+#[derive(Debug, Clone, Default)]
+struct Dependencies {
+    /// Target properties
+    properties: Vec<String>,
+    /// Target flags
+    flags: Vec<DefId>,
+    /// Target tags
+    tags: Vec<String>,
+    /// Globals
+    globals: Vec<String>,
+    /// Whether the expression reads anything else (other entities, RNG,
+    /// history), so it must be recomputed every tick
+    volatile: bool,
+}
+
+impl ComputedProperty {
+    /// Define a property of an entity kind by an expression
+    pub fn new(kind: impl Into<DefId>, property: impl Into<String>, expr: Expr) -> Self {
+        let mut dependencies = Dependencies::default();
+        dependencies.collect(&expr);
+        Self {
+            kind: kind.into(),
+            property: property.into(),
+            expr,
+            dependencies,
+        }
+    }
+
+    /// Target properties the expression reads
+    pub fn dependencies(&self) -> &[String] {
+        &self.dependencies.properties
+    }
+
+    /// Check whether the value must be recomputed every tick
+    pub fn is_volatile(&self) -> bool {
+        self.dependencies.volatile
+    }
+
+    /// Current values of the dependencies of an entity's value
+    ///
+    /// Returns `None` for volatile properties, whose dependencies cannot be
+    /// tracked.
+    pub(crate) fn inputs(&self, entity: &Entity, globals: &ValueMap) -> Option<Vec<Value>> {
+        let deps = &self.dependencies;
+        if deps.volatile {
+            return None;
+        }
+        let properties = deps
+            .properties
+            .iter()
+            .map(|name| entity.get(name).cloned().unwrap_or(Value::Null));
+        let flags = deps
+            .flags
+            .iter()
+            .map(|flag| Value::Bool(entity.has_flag(flag)));
+        let tags = deps.tags.iter().map(|tag| Value::Bool(entity.has_tag(tag)));
+        let globals = deps
+            .globals
+            .iter()
+            .map(|name| globals.get(name).cloned().unwrap_or(Value::Null));
+        Some(properties.chain(flags).chain(tags).chain(globals).collect())
+    }
+}
+
+impl Dependencies {
+    /// Add the state read by an expression
+    fn collect(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Literal(_) | Expr::Param(_) => {}
+            Expr::Property(name) => push_unique(&mut self.properties, name),
+            Expr::Global(name) => push_unique(&mut self.globals, name),
+            Expr::HasFlag(flag) => push_unique(&mut self.flags, flag),
+            Expr::HasTag(tag) => push_unique(&mut self.tags, tag),
+            Expr::EntityProperty(..)
+            | Expr::EntityExists(_)
+            | Expr::CountEntities(_)
+            | Expr::Random
+            | Expr::Income(_)
+            | Expr::Upkeep(_)
+            | Expr::TicksSince(_) => self.volatile = true,
+            Expr::RandomRange(a, b) | Expr::RandomInt(a, b) => {
+                self.volatile = true;
+                self.collect(a);
+                self.collect(b);
+            }
+            Expr::WeightedRandom(exprs) => {
+                self.volatile = true;
+                exprs.iter().for_each(|e| self.collect(e));
+            }
+            Expr::ValueAt(_, e) | Expr::MovingAvg(_, e) => {
+                self.volatile = true;
+                self.collect(e);
+            }
+            Expr::Add(a, b)
+            | Expr::Sub(a, b)
+            | Expr::Mul(a, b)
+            | Expr::Div(a, b)
+            | Expr::Mod(a, b)
+            | Expr::Min(a, b)
+            | Expr::Max(a, b)
+            | Expr::Eq(a, b)
+            | Expr::Ne(a, b)
+            | Expr::Lt(a, b)
+            | Expr::Le(a, b)
+            | Expr::Gt(a, b)
+            | Expr::Ge(a, b) => {
+                self.collect(a);
+                self.collect(b);
+            }
+            Expr::Neg(e)
+            | Expr::Abs(e)
+            | Expr::Floor(e)
+            | Expr::Ceil(e)
+            | Expr::Round(e)
+            | Expr::Not(e)
+            | Expr::Curve { input: e, .. } => self.collect(e),
+            Expr::Clamp(a, b, c) | Expr::If(a, b, c) => {
+                self.collect(a);
+                self.collect(b);
+                self.collect(c);
+            }
+            Expr::And(exprs) | Expr::Or(exprs) | Expr::Concat(exprs) | Expr::Format(_, exprs) => {
+                exprs.iter().for_each(|e| self.collect(e));
+            }
+            Expr::Localized(_, params) => params.iter().for_each(|(_, e)| self.collect(e)),
+        }
+    }
+}
+
+fn push_unique<T: PartialEq + Clone>(items: &mut Vec<T>, item: &T) {
+    if !items.contains(item) {
+        items.push(item.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dependencies() {
+        let power = ComputedProperty::new(
+            "nation",
+            "power",
+            Expr::Mul(
+                Box::new(Expr::prop("army_size")),
+                Box::new(Expr::Add(
+                    Box::new(Expr::prop("tech_level")),
+                    Box::new(Expr::prop("army_size")),
+                )),
+            ),
+        );
+        assert_eq!(power.dependencies(), ["army_size", "tech_level"]);
+        assert!(!power.is_volatile());
+
+        let mut entity = Entity::new(crate::EntityId::new(1), DefId::new("nation"));
+        entity.set("army_size", 10i64);
+        assert_eq!(
+            power.inputs(&entity, &ValueMap::new()),
+            Some(vec![Value::Int(10), Value::Null])
+        );
+
+        let roll = ComputedProperty::new("nation", "roll", Expr::Random);
+        assert!(roll.is_volatile());
+        assert_eq!(roll.inputs(&entity, &ValueMap::new()), None);
+    }
+}
@@ -0,0 +1,110 @@
+//! Value curves and lookup tables
+//!
+//! A [`Curve`] maps a numeric input to an output through a list of key
+//! points, so non-linear relationships (tax efficiency vs. size, difficulty
+//! ramps) can be tuned in data. Curves are registered on the
+//! [`Runtime`](crate::Runtime) and sampled with [`Expr::Curve`](crate::Expr::Curve).
+
+use serde::{Deserialize, Serialize};
+
+/// How a curve interpolates between its points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Interpolation {
+    /// Straight lines between points
+    #[default]
+    Linear,
+    /// Hold each point's value until the next point (a lookup table)
+    Step,
+    /// Cubic Bézier ease between points, flat at each point
+    Bezier,
+}
+
+/// A curve through `(input, output)` key points
+///
+/// Inputs outside the points are clamped to the first or last output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Curve {
+    /// Interpolation between points
+    #[serde(default)]
+    pub interpolation: Interpolation,
+    /// Key points, sorted by input
+    pub points: Vec<(f64, f64)>,
+}
+
+impl Curve {
+    /// Create a curve, sorting its points by input
+    pub fn new(interpolation: Interpolation, mut points: Vec<(f64, f64)>) -> Self {
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self {
+            interpolation,
+            points,
+        }
+    }
+
+    /// Create a piecewise linear curve
+    pub fn linear(points: Vec<(f64, f64)>) -> Self {
+        Self::new(Interpolation::Linear, points)
+    }
+
+    /// Create a step curve (lookup table)
+    pub fn step(points: Vec<(f64, f64)>) -> Self {
+        Self::new(Interpolation::Step, points)
+    }
+
+    /// Create a smooth Bézier curve
+    pub fn bezier(points: Vec<(f64, f64)>) -> Self {
+        Self::new(Interpolation::Bezier, points)
+    }
+
+    /// Sample the curve at an input
+    ///
+    /// A curve without points always returns 0.
+    pub fn sample(&self, x: f64) -> f64 {
+        let (first, last) = match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return 0.0,
+        };
+        if x <= first.0 {
+            return first.1;
+        }
+        if x >= last.0 {
+            return last.1;
+        }
+
+        // First point past x; x lies between it and the previous point
+        let i = self.points.partition_point(|p| p.0 <= x);
+        let (x0, y0) = self.points[i - 1];
+        let (x1, y1) = self.points[i];
+        let t = (x - x0) / (x1 - x0);
+        match self.interpolation {
+            Interpolation::Linear => y0 + (y1 - y0) * t,
+            Interpolation::Step => y0,
+            // Control points (y0, y0, y1, y1)
+            Interpolation::Bezier => y0 + (y1 - y0) * t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample() {
+        let points = vec![(10.0, 1.0), (0.0, 0.0), (20.0, 0.5)];
+        let linear = Curve::linear(points.clone());
+        assert_eq!(linear.sample(-5.0), 0.0);
+        assert_eq!(linear.sample(5.0), 0.5);
+        assert_eq!(linear.sample(15.0), 0.75);
+        assert_eq!(linear.sample(30.0), 0.5);
+
+        let step = Curve::step(points.clone());
+        assert_eq!(step.sample(9.9), 0.0);
+        assert_eq!(step.sample(10.0), 1.0);
+
+        let bezier = Curve::bezier(points);
+        assert_eq!(bezier.sample(5.0), 0.5);
+        assert!(bezier.sample(2.0) < linear.sample(2.0));
+        assert_eq!(Curve::linear(Vec::new()).sample(1.0), 0.0);
+    }
+}
@@ -0,0 +1,181 @@
+//! Deterministic execution guarantees
+//!
+//! Given the same model, the same registered handlers and the same messages,
+//! the runtime produces the same writes, traces and resulting model on every
+//! run and platform. Lockstep netcode and the hub's parallel cores rely on
+//! this. It holds because every ordering the runtime observes is fixed:
+//!
+//! - [`EntityStore`](crate::EntityStore) iterates in ascending
+//!   [`EntityId`](crate::EntityId) order, also by kind, however entities were
+//!   created, inserted or removed, and reuses the lowest free slot for new
+//!   entities
+//! - Handlers run by descending priority, then in registration order
+//! - Within a tick, immediate messages run before normal ones, each lane in
+//!   the order sent, and background messages oldest first
+//! - Entity flags, properties and globals iterate in a fixed order (flags
+//!   sorted by ID, properties and globals in insertion order)
+//! - Randomness comes only from the model's seeded [`Rng`](crate::Rng)
+//!
+//! Hash maps are only used for lookups, never iterated, so their per-process
+//! random seeds cannot leak into execution. The tests below check this by
+//! running the same simulation on several threads, each with its own hash
+//! seeds, and comparing the serialized traces.
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        DefId, Effect, Entity, EntityRef, EventHandler, Expr, Model, ModifyOp, Msg, Runtime,
+        TickHandler,
+    };
+
+    fn handler(id: &str, kind: Option<&str>, priority: i32, effects: Vec<Effect>) -> TickHandler {
+        TickHandler {
+            id: DefId::new(id),
+            condition: None,
+            target_kind: kind.map(DefId::new),
+            effects,
+            priority,
+        }
+    }
+
+    fn random(max: f64) -> Expr {
+        Expr::RandomRange(Box::new(Expr::lit(0.0)), Box::new(Expr::lit(max)))
+    }
+
+    /// A model with units and cities, created in ID order or restored in
+    /// reverse order
+    fn model(reversed: bool) -> Model {
+        let mut source = Model::with_seed(7);
+        for i in 0..20 {
+            let unit = source.entities_mut().create("unit");
+            unit.set("hp", 10.0 + i as f64);
+            if i % 3 == 0 {
+                unit.add_flag(DefId::new("veteran"));
+            }
+            unit.add_flag(DefId::new(if i % 2 == 0 { "red" } else { "blue" }));
+        }
+        for _ in 0..3 {
+            source.entities_mut().create("city").set("food", 5.0f64);
+        }
+        if !reversed {
+            return source;
+        }
+
+        let mut model = Model::with_seed(7);
+        let mut entities: Vec<Entity> = source.entities().iter().cloned().collect();
+        entities.reverse();
+        for entity in entities {
+            model.entities_mut().insert(entity);
+        }
+        model
+    }
+
+    fn runtime() -> Runtime {
+        let mut runtime = Runtime::new();
+        runtime.on_tick(handler(
+            "regen",
+            Some("unit"),
+            0,
+            vec![Effect::ModifyProperty {
+                property: "hp".to_string(),
+                op: ModifyOp::Add,
+                value: random(2.0),
+            }],
+        ));
+        runtime.on_tick(handler(
+            "attrition",
+            Some("unit"),
+            0,
+            vec![Effect::If {
+                condition: Expr::Lt(Box::new(random(1.0)), Box::new(Expr::lit(0.3))),
+                then_effects: vec![Effect::ModifyProperty {
+                    property: "hp".to_string(),
+                    op: ModifyOp::Sub,
+                    value: random(3.0),
+                }],
+                else_effects: vec![Effect::AddFlagFor {
+                    flag: DefId::new("rested"),
+                    duration_ticks: Expr::lit(2i64),
+                }],
+            }],
+        ));
+        runtime.on_tick(handler(
+            "census",
+            None,
+            5,
+            vec![Effect::SetGlobal {
+                property: "units".to_string(),
+                value: Expr::CountEntities(DefId::new("unit")),
+            }],
+        ));
+        runtime.on_event(EventHandler {
+            event_id: DefId::new("reinforce"),
+            condition: None,
+            effects: vec![
+                Effect::SpawnEntity {
+                    kind: DefId::new("unit"),
+                    properties: vec![("hp".to_string(), random(20.0))],
+                },
+                Effect::ForEachEntity {
+                    kind: DefId::new("unit"),
+                    filter: Some(Expr::HasFlag(DefId::new("veteran"))),
+                    effects: vec![Effect::RandomChoice {
+                        choices: vec![
+                            (Expr::lit(1.0), vec![Effect::flag("promoted")]),
+                            (Expr::lit(2.0), vec![Effect::RemoveFlag(DefId::new("red"))]),
+                        ],
+                    }],
+                },
+            ],
+            priority: 0,
+        });
+        runtime.register_computed(
+            "unit",
+            "power",
+            Expr::Mul(Box::new(Expr::prop("hp")), Box::new(Expr::lit(2.0))),
+        );
+        runtime.set_write_logging(true);
+        runtime.set_causality_tracing(true);
+        runtime
+    }
+
+    /// Run the simulation and serialize everything it did
+    fn run(reversed: bool) -> String {
+        let mut model = model(reversed);
+        let mut runtime = runtime();
+        let mut out = String::new();
+        for tick in 0..30 {
+            if tick % 5 == 0 {
+                runtime.send(Msg::event(
+                    "reinforce",
+                    EntityRef::ByDef(DefId::new("city")),
+                    tick,
+                ));
+            }
+            let result = runtime.tick(&mut model);
+            let trace = runtime.take_trace();
+            let writes = runtime.take_write_log();
+            let events = &result.effect_result.emitted_events;
+            out += &ron::to_string(&(trace, writes, events)).expect("serialize");
+        }
+        out + &ron::to_string(&model).expect("serialize")
+    }
+
+    #[test]
+    fn test_same_trace_across_hash_seeds() {
+        // Each thread seeds its hash maps differently
+        let traces: Vec<String> = (0..4)
+            .map(|_| std::thread::spawn(|| run(false)))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|thread| thread.join().expect("simulation thread"))
+            .collect();
+        assert!(traces.iter().all(|trace| trace == &traces[0]));
+        assert_eq!(run(false), traces[0]);
+    }
+
+    #[test]
+    fn test_same_trace_regardless_of_insertion_order() {
+        assert_eq!(run(true), run(false));
+    }
+}
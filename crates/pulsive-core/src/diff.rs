@@ -0,0 +1,204 @@
+//! Structural diffs between model states
+//!
+//! A [`ModelDiff`] records what changed between two models so the target can
+//! be rebuilt from the base. Diffs are computed at entity and global-key
+//! granularity:
+//! - Added or modified entities are stored in full, removed entities by ID,
+//!   and freed entity slots by the ID they will give their next entity
+//! - Added or modified globals are stored by key, removed globals by key
+//! - Clock, RNG and actor contexts are small and always stored in full
+//!
+//! Unchanged entity stores and global maps that still share the same `Arc`
+//! are detected in O(1), so diffs between consecutive ticks are cheap.
+
+use crate::{ActorId, Clock, Context, Entity, EntityId, IndexMap, Model, Rng, Symbol, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+/// The difference between two models
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelDiff {
+    /// Entities added or modified in the target (stored in full)
+    pub changed_entities: Vec<Entity>,
+    /// Entities present in the base but not in the target
+    pub removed_entities: Vec<EntityId>,
+    /// Vacant entity slots of the target that are not vacant with the same
+    /// generation in the base, as the IDs their next entities get
+    vacancies: Vec<EntityId>,
+    /// Globals added or modified in the target
+    pub changed_globals: Vec<(Symbol, Value)>,
+    /// Globals present in the base but not in the target
+    pub removed_globals: Vec<Symbol>,
+    /// Target clock
+    pub time: Clock,
+    /// Target RNG state
+    pub rng: Rng,
+    /// Target actor contexts
+    pub actors: IndexMap<ActorId, Context>,
+}
+
+impl ModelDiff {
+    /// Compute the diff that turns `base` into `target`
+    pub fn between(base: &Model, target: &Model) -> Self {
+        let mut changed_entities = Vec::new();
+        let mut removed_entities = Vec::new();
+        let mut vacancies = Vec::new();
+        if !Arc::ptr_eq(&base.entities_arc(), &target.entities_arc()) {
+            for entity in target.entities().iter() {
+                if base.entities().get(entity.id) != Some(entity) {
+                    changed_entities.push(entity.clone());
+                }
+            }
+            removed_entities = base
+                .entities()
+                .ids()
+                .filter(|id| target.entities().get(*id).is_none())
+                .collect();
+            let base_vacancies: BTreeSet<EntityId> = base.entities().vacancies().collect();
+            vacancies = target
+                .entities()
+                .vacancies()
+                .filter(|id| !base_vacancies.contains(id))
+                .collect();
+        }
+
+        let mut changed_globals = Vec::new();
+        let mut removed_globals = Vec::new();
+        if !Arc::ptr_eq(&base.globals_arc(), &target.globals_arc()) {
+            for (key, value) in target.globals() {
+                if base.globals().get(key) != Some(value) {
+                    changed_globals.push((*key, value.clone()));
+                }
+            }
+            removed_globals = base
+                .globals()
+                .keys()
+                .filter(|key| !target.globals().contains_key(*key))
+                .cloned()
+                .collect();
+        }
+
+        Self {
+            changed_entities,
+            removed_entities,
+            vacancies,
+            changed_globals,
+            removed_globals,
+            time: target.time.clone(),
+            rng: target.rng.clone(),
+            actors: target.actors.clone(),
+        }
+    }
+
+    /// Apply this diff to a model, turning the base into the target
+    ///
+    /// Applying a diff to a model other than its base produces a model with
+    /// the diff's changes layered on top.
+    pub fn apply(&self, model: &mut Model) {
+        if !self.changed_entities.is_empty()
+            || !self.removed_entities.is_empty()
+            || !self.vacancies.is_empty()
+        {
+            let entities = model.entities_mut();
+            for id in &self.removed_entities {
+                entities.remove(*id);
+            }
+            for id in &self.vacancies {
+                entities.vacate(*id);
+            }
+            for entity in &self.changed_entities {
+                entities.insert(entity.clone());
+            }
+        }
+
+        if !self.changed_globals.is_empty() || !self.removed_globals.is_empty() {
+            let globals = model.globals_mut();
+            for key in &self.removed_globals {
+                globals.shift_remove(key);
+            }
+            for (key, value) in &self.changed_globals {
+                globals.insert(*key, value.clone());
+            }
+        }
+
+        model.time = self.time.clone();
+        model.rng = self.rng.clone();
+        model.actors = self.actors.clone();
+    }
+
+    /// Check if the diff has no entity or global changes
+    ///
+    /// Clock, RNG and actors are not considered.
+    pub fn is_empty(&self) -> bool {
+        self.changed_entities.is_empty()
+            && self.removed_entities.is_empty()
+            && self.changed_globals.is_empty()
+            && self.removed_globals.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_roundtrip() {
+        let mut base = Model::new();
+        let kept = base.entities_mut().create("nation").id;
+        let removed = base.entities_mut().create("nation").id;
+        base.set_global("difficulty", 1i64);
+        base.set_global("obsolete", true);
+
+        let mut target = base.clone();
+        target
+            .entities_mut()
+            .get_mut(kept)
+            .unwrap()
+            .set("gold", 50.0f64);
+        target.entities_mut().remove(removed);
+        let added = target.entities_mut().create("province").id;
+        let transient = target.entities_mut().create("army").id;
+        target.entities_mut().remove(transient);
+        target.set_global("difficulty", 2i64);
+        target.globals_mut().shift_remove("obsolete");
+        target.advance_tick();
+
+        let diff = ModelDiff::between(&base, &target);
+        assert_eq!(diff.changed_entities.len(), 2);
+        assert_eq!(diff.removed_entities, vec![removed]);
+        assert_eq!(diff.removed_globals, vec!["obsolete".to_string()]);
+
+        let mut rebuilt = base.clone();
+        diff.apply(&mut rebuilt);
+        assert_eq!(rebuilt.current_tick(), 1);
+        assert_eq!(
+            rebuilt.entities().get(kept).unwrap().get_number("gold"),
+            Some(50.0)
+        );
+        assert!(rebuilt.entities().get(removed).is_none());
+        assert!(rebuilt.entities().get(added).is_some());
+        assert_eq!(rebuilt.get_global("difficulty"), Some(&Value::Int(2)));
+        assert!(rebuilt.get_global("obsolete").is_none());
+
+        // Entity IDs continue where the target left off, freed slots included
+        assert_eq!(
+            rebuilt.entities_mut().create("nation").id,
+            target.entities_mut().create("nation").id
+        );
+    }
+
+    #[test]
+    fn test_diff_shared_state_is_empty() {
+        let mut base = Model::new();
+        base.entities_mut().create("nation");
+        base.set_global("difficulty", 1i64);
+
+        let mut target = base.clone();
+        target.advance_tick();
+
+        let diff = ModelDiff::between(&base, &target);
+        assert!(diff.is_empty());
+        assert_eq!(diff.time.tick, 1);
+    }
+}
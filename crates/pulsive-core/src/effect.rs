@@ -58,13 +58,15 @@ pub enum Effect {
         op: ModifyOp,
         value: Expr,
     },
-    /// Set a property on a specific entity
+    /// Set a property on specific entities, evaluating the value with each
+    /// as target
     SetEntityProperty {
         target: EntityRef,
         property: String,
         value: Expr,
     },
-    /// Modify a numeric property on a specific entity
+    /// Modify a numeric property on specific entities, evaluating the value
+    /// with each as target
     ModifyEntityProperty {
         target: EntityRef,
         property: String,
@@ -85,11 +87,19 @@ pub enum Effect {
     AddFlag(DefId),
     /// Remove a flag from the target entity
     RemoveFlag(DefId),
-    /// Add a flag to a specific entity
+    /// Add a flag to the target entity that expires after a number of ticks
+    AddFlagFor { flag: DefId, duration_ticks: Expr },
+    /// Add a flag to specific entities
     AddEntityFlag { target: EntityRef, flag: DefId },
-    /// Remove a flag from a specific entity
+    /// Remove a flag from specific entities
     RemoveEntityFlag { target: EntityRef, flag: DefId },
 
+    // === Tags ===
+    /// Add a tag to the target entity
+    AddTag(String),
+    /// Remove a tag from the target entity
+    RemoveTag(String),
+
     // === Entity Lifecycle ===
     /// Spawn a new entity
     SpawnEntity {
@@ -98,7 +108,7 @@ pub enum Effect {
     },
     /// Destroy the target entity
     DestroyTarget,
-    /// Destroy a specific entity
+    /// Destroy specific entities
     DestroyEntity(EntityRef),
 
     // === Events ===
@@ -135,6 +145,12 @@ pub enum Effect {
     RandomChoice {
         choices: Vec<(Expr, Vec<Effect>)>, // (weight, effects)
     },
+    /// Run a registered [`EffectTemplate`](crate::EffectTemplate) with
+    /// arguments for its parameters
+    Invoke {
+        template: DefId,
+        args: Vec<(String, Expr)>,
+    },
 
     // === Output ===
     /// Log a message (for debugging)
@@ -148,8 +164,8 @@ pub enum Effect {
     },
 }
 
-/// Log level for debug output
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+/// Log level for debug output, ordered by severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum LogLevel {
     Debug,
     Info,
@@ -189,6 +205,11 @@ impl Effect {
         Effect::AddFlag(flag.into())
     }
 
+    /// Create an add tag effect
+    pub fn tag(tag: impl Into<String>) -> Self {
+        Effect::AddTag(tag.into())
+    }
+
     /// Create a spawn entity effect
     pub fn spawn(kind: impl Into<DefId>) -> Self {
         Effect::SpawnEntity {
@@ -206,6 +227,36 @@ impl Effect {
         }
     }
 
+    /// Split an entity-targeted effect into its target and the same effect
+    /// on the handler's target
+    pub(crate) fn split_target(&self) -> Option<(&EntityRef, Effect)> {
+        match self {
+            Effect::SetEntityProperty {
+                target,
+                property,
+                value,
+            } => Some((target, Effect::set(property.clone(), value.clone()))),
+            Effect::ModifyEntityProperty {
+                target,
+                property,
+                op,
+                value,
+            } => Some((
+                target,
+                Effect::ModifyProperty {
+                    property: property.clone(),
+                    op: op.clone(),
+                    value: value.clone(),
+                },
+            )),
+            Effect::AddEntityFlag { target, flag } => Some((target, Effect::AddFlag(flag.clone()))),
+            Effect::RemoveEntityFlag { target, flag } => {
+                Some((target, Effect::RemoveFlag(flag.clone())))
+            }
+            _ => None,
+        }
+    }
+
     /// Create a sequence of effects
     pub fn seq(effects: Vec<Effect>) -> Self {
         Effect::Sequence(effects)
@@ -219,6 +270,39 @@ impl Effect {
             else_effects: Vec::new(),
         }
     }
+
+    /// Create an invoke effect without arguments
+    pub fn invoke(template: impl Into<DefId>) -> Self {
+        Effect::Invoke {
+            template: template.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Collect the templates this effect and its nested effects invoke
+    pub(crate) fn invoked_templates<'a>(&'a self, invoked: &mut Vec<&'a DefId>) {
+        let nested: Box<dyn Iterator<Item = &Effect>> = match self {
+            Effect::Invoke { template, .. } => {
+                invoked.push(template);
+                return;
+            }
+            Effect::If {
+                then_effects,
+                else_effects,
+                ..
+            } => Box::new(then_effects.iter().chain(else_effects)),
+            Effect::Sequence(effects) | Effect::ForEachEntity { effects, .. } => {
+                Box::new(effects.iter())
+            }
+            Effect::RandomChoice { choices } => {
+                Box::new(choices.iter().flat_map(|(_, effects)| effects))
+            }
+            _ => return,
+        };
+        for effect in nested {
+            effect.invoked_templates(invoked);
+        }
+    }
 }
 
 /// Result of executing an effect
@@ -232,19 +316,42 @@ pub struct EffectResult {
     pub emitted_events: Vec<(DefId, EntityRef, ValueMap)>,
     /// Scheduled events (event, target, delay, params)
     pub scheduled_events: Vec<(DefId, EntityRef, u64, ValueMap)>,
-    /// Log messages
-    pub logs: Vec<(LogLevel, String)>,
+    /// Log records
+    pub logs: Vec<crate::LogRecord>,
     /// Notifications
     pub notifications: Vec<Notification>,
+    /// Writes that violated their property schema
+    pub violations: Vec<crate::SchemaViolation>,
+    /// Messages dropped for going past the cascade depth limit
+    pub cascade_limits: Vec<crate::CascadeLimitExceeded>,
 }
 
 /// A notification to send to the UI
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Notification {
     pub kind: DefId,
     pub title: String,
     pub message: String,
     pub target: EntityRef,
+    /// Title before formatting (e.g. [`Value::localized`](crate::Value::localized)
+    /// text for the UI to translate)
+    pub title_value: crate::Value,
+    /// Message before formatting
+    pub message_value: crate::Value,
+}
+
+impl Notification {
+    /// Format a title or message value
+    ///
+    /// Localized text falls back to its key; null (a failed evaluation)
+    /// is empty.
+    pub(crate) fn text(value: &crate::Value) -> String {
+        match value.as_localized() {
+            Some((key, _)) => key.to_string(),
+            None if value.is_null() => String::new(),
+            None => value.to_string(),
+        }
+    }
 }
 
 impl EffectResult {
@@ -261,6 +368,8 @@ impl EffectResult {
         self.scheduled_events.extend(other.scheduled_events);
         self.logs.extend(other.logs);
         self.notifications.extend(other.notifications);
+        self.violations.extend(other.violations);
+        self.cascade_limits.extend(other.cascade_limits);
     }
 }
 
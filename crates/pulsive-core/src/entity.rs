@@ -1,9 +1,9 @@
 //! Entity types for simulation objects
 
-use crate::{DefId, EntityId, Value, ValueMap};
+use crate::{AsSymbol, DefId, EntityId, Symbol, Tick, Value, ValueMap};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 /// Reference to an entity or a special target
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
@@ -17,6 +17,10 @@ pub enum EntityRef {
     Global,
     /// Reference by definition ID (e.g., "nation:france")
     ByDef(DefId),
+    /// Every entity of a kind
+    Kind(DefId),
+    /// A set of entities by ID
+    Set(Vec<EntityId>),
 }
 
 impl EntityRef {
@@ -25,6 +29,14 @@ impl EntityRef {
         matches!(self, EntityRef::None)
     }
 
+    /// Check if this reference can address several entities
+    ///
+    /// Handlers and entity-targeted effects fan out over every entity a
+    /// multi-target reference matches.
+    pub fn is_multi(&self) -> bool {
+        matches!(self, EntityRef::Kind(_) | EntityRef::Set(_))
+    }
+
     /// Try to get the entity ID if this is a direct reference
     pub fn as_entity_id(&self) -> Option<EntityId> {
         match self {
@@ -41,7 +53,7 @@ impl From<EntityId> for EntityRef {
 }
 
 /// A dynamic entity instance
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Entity {
     /// Unique identifier for this entity
     pub id: EntityId,
@@ -49,8 +61,14 @@ pub struct Entity {
     pub kind: DefId,
     /// Dynamic properties (e.g., {"gold": 100.0, "stability": 2})
     pub properties: ValueMap,
-    /// Active flags/modifiers on this entity
-    pub flags: HashSet<DefId>,
+    /// Active flags/modifiers on this entity, ordered by ID
+    pub flags: BTreeSet<DefId>,
+    /// Ticks at which timed flags expire
+    #[serde(default)]
+    pub flag_expiry: IndexMap<DefId, Tick>,
+    /// Gameplay tags ("flying", "undead"), independent of the kind
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
 }
 
 impl Entity {
@@ -60,27 +78,29 @@ impl Entity {
             id,
             kind: kind.into(),
             properties: ValueMap::new(),
-            flags: HashSet::new(),
+            flags: BTreeSet::new(),
+            flag_expiry: IndexMap::new(),
+            tags: BTreeSet::new(),
         }
     }
 
     /// Get a property value
-    pub fn get(&self, key: &str) -> Option<&Value> {
+    pub fn get<K: AsSymbol + ?Sized>(&self, key: &K) -> Option<&Value> {
         self.properties.get(key)
     }
 
     /// Get a property value or a default
-    pub fn get_or(&self, key: &str, default: Value) -> Value {
+    pub fn get_or<K: AsSymbol + ?Sized>(&self, key: &K, default: Value) -> Value {
         self.properties.get(key).cloned().unwrap_or(default)
     }
 
     /// Set a property value
-    pub fn set(&mut self, key: impl Into<String>, value: impl Into<Value>) {
+    pub fn set(&mut self, key: impl Into<Symbol>, value: impl Into<Value>) {
         self.properties.insert(key.into(), value.into());
     }
 
     /// Remove a property
-    pub fn remove(&mut self, key: &str) -> Option<Value> {
+    pub fn remove<K: AsSymbol + ?Sized>(&mut self, key: &K) -> Option<Value> {
         self.properties.shift_remove(key)
     }
 
@@ -89,18 +109,62 @@ impl Entity {
         self.flags.contains(flag)
     }
 
-    /// Add a flag
+    /// Add a flag (permanent until removed)
     pub fn add_flag(&mut self, flag: impl Into<DefId>) {
-        self.flags.insert(flag.into());
+        let flag = flag.into();
+        self.flag_expiry.shift_remove(&flag);
+        self.flags.insert(flag);
+    }
+
+    /// Add a flag that expires at a tick
+    pub fn add_flag_until(&mut self, flag: impl Into<DefId>, expires_at: Tick) {
+        let flag = flag.into();
+        self.flags.insert(flag.clone());
+        self.flag_expiry.insert(flag, expires_at);
+    }
+
+    /// Get the tick a timed flag expires at (`None` if permanent or absent)
+    pub fn flag_expires_at(&self, flag: &DefId) -> Option<Tick> {
+        self.flag_expiry.get(flag).copied()
+    }
+
+    /// Remove the timed flags expiring by a tick, returning them
+    pub fn expire_flags(&mut self, tick: Tick) -> Vec<DefId> {
+        let expired: Vec<DefId> = self
+            .flag_expiry
+            .iter()
+            .filter(|(_, expires_at)| **expires_at <= tick)
+            .map(|(flag, _)| flag.clone())
+            .collect();
+        for flag in &expired {
+            self.remove_flag(flag);
+        }
+        expired
     }
 
     /// Remove a flag
     pub fn remove_flag(&mut self, flag: &DefId) -> bool {
+        self.flag_expiry.shift_remove(flag);
         self.flags.remove(flag)
     }
 
+    /// Check if entity has a tag
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+
+    /// Add a tag, returning false if the entity already had it
+    pub fn add_tag(&mut self, tag: impl Into<String>) -> bool {
+        self.tags.insert(tag.into())
+    }
+
+    /// Remove a tag, returning false if the entity didn't have it
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        self.tags.remove(tag)
+    }
+
     /// Get a numeric property as f64
-    pub fn get_number(&self, key: &str) -> Option<f64> {
+    pub fn get_number<K: AsSymbol + ?Sized>(&self, key: &K) -> Option<f64> {
         self.properties.get(key).and_then(|v| v.as_float())
     }
 
@@ -112,14 +176,55 @@ impl Entity {
 }
 
 /// Storage for all entities in the system
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+///
+/// Entities live in an arena of slots indexed by [`EntityId::index`], so
+/// lookups are a bounds check and a generation check. Destroying an entity
+/// frees its slot and bumps the slot's generation; creating an entity reuses
+/// the lowest free slot, so stale IDs of destroyed entities never resolve to
+/// the entity reusing their slot.
+///
+/// Iteration, both over all entities and by kind, is in ascending
+/// [`EntityId`] order, whatever order entities were created, inserted or
+/// removed in. Handlers and effects that walk entities therefore run in the
+/// same order on every machine.
+#[derive(Debug, Clone, Default)]
 pub struct EntityStore {
-    /// All entities by ID
-    entities: IndexMap<EntityId, Entity>,
-    /// Next entity ID to assign
-    next_id: u64,
-    /// Index: kind -> entity IDs
-    by_kind: IndexMap<DefId, Vec<EntityId>>,
+    /// Entity slots, by index
+    slots: Vec<Slot>,
+    /// Indices of vacant slots
+    free: BTreeSet<u32>,
+    /// Number of live entities
+    len: usize,
+    /// Index: kind -> entity IDs, in ascending order
+    by_kind: BTreeMap<DefId, Vec<EntityId>>,
+}
+
+/// A slot of the entity arena
+///
+/// Most slots are occupied, so entities are stored inline rather than boxed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+enum Slot {
+    /// A live entity, whose ID holds the slot's generation
+    Occupied(Entity),
+    /// A free slot, with the generation of the next entity to use it
+    Vacant(u32),
+}
+
+impl Slot {
+    fn entity(&self) -> Option<&Entity> {
+        match self {
+            Slot::Occupied(entity) => Some(entity),
+            Slot::Vacant(_) => None,
+        }
+    }
+
+    fn entity_mut(&mut self) -> Option<&mut Entity> {
+        match self {
+            Slot::Occupied(entity) => Some(entity),
+            Slot::Vacant(_) => None,
+        }
+    }
 }
 
 impl EntityStore {
@@ -128,42 +233,166 @@ impl EntityStore {
         Self::default()
     }
 
+    /// Rebuild a store from its slots
+    fn from_slots(slots: Vec<Slot>) -> Self {
+        let mut store = Self::default();
+        for (index, slot) in slots.into_iter().enumerate() {
+            match slot {
+                Slot::Occupied(entity) => store.insert(entity),
+                Slot::Vacant(generation) => {
+                    store.vacate(EntityId::from_parts(index as u32, generation))
+                }
+            }
+        }
+        store
+    }
+
+    /// Copy some of the entities into a new store
+    ///
+    /// The slots of the entities left out stay vacant at their next
+    /// generation, so entities created in the copy never get the ID of one
+    /// left out. IDs not in the store are ignored.
+    pub fn subset(&self, ids: impl IntoIterator<Item = EntityId>) -> Self {
+        let slots = self
+            .slots
+            .iter()
+            .map(|slot| match slot {
+                Slot::Occupied(entity) => Slot::Vacant(entity.id.generation().wrapping_add(1)),
+                Slot::Vacant(generation) => Slot::Vacant(*generation),
+            })
+            .collect();
+        let mut store = Self::from_slots(slots);
+        for id in ids {
+            if let Some(entity) = self.get(id) {
+                store.insert(entity.clone());
+            }
+        }
+        store
+    }
+
     /// Create a new entity and add it to the store
     pub fn create(&mut self, kind: impl Into<DefId>) -> &mut Entity {
-        let id = EntityId::new(self.next_id);
-        self.next_id += 1;
+        let id = match self.free.pop_first() {
+            Some(index) => match self.slots[index as usize] {
+                Slot::Vacant(generation) => EntityId::from_parts(index, generation),
+                Slot::Occupied(_) => unreachable!("free slot {} is occupied", index),
+            },
+            None => EntityId::from_parts(self.slots.len() as u32, 0),
+        };
         let kind = kind.into();
 
-        // Add to kind index
-        self.by_kind.entry(kind.clone()).or_default().push(id);
+        // Add to kind index, keeping ascending order when reusing a slot
+        let ids = self.by_kind.entry(kind.clone()).or_default();
+        match ids.last() {
+            Some(&last) if last > id => {
+                let index = ids.binary_search(&id).unwrap_or_else(|i| i);
+                ids.insert(index, id);
+            }
+            _ => ids.push(id),
+        }
 
         // Create and store entity
-        let entity = Entity::new(id, kind);
-        self.entities.insert(id, entity);
-        self.entities.get_mut(&id).unwrap()
+        let index = id.index() as usize;
+        if index == self.slots.len() {
+            self.slots.push(Slot::Vacant(0));
+        }
+        self.slots[index] = Slot::Occupied(Entity::new(id, kind));
+        self.len += 1;
+        self.slots[index].entity_mut().unwrap()
+    }
+
+    /// Make sure a slot exists, adding vacant slots up to it
+    fn reserve_slot(&mut self, index: u32) {
+        while self.slots.len() <= index as usize {
+            self.free.insert(self.slots.len() as u32);
+            self.slots.push(Slot::Vacant(0));
+        }
+    }
+
+    /// Insert an entity with an existing ID, replacing whatever is in its
+    /// slot
+    ///
+    /// Used when restoring state (e.g. applying a [`crate::ModelDiff`]).
+    /// Slots below the inserted entity's are added as needed, vacant.
+    pub fn insert(&mut self, entity: Entity) {
+        let id = entity.id;
+        self.reserve_slot(id.index());
+        let index = id.index() as usize;
+        let previous = self.slots[index].entity().map(|e| (e.id, e.kind.clone()));
+        if previous.as_ref() != Some(&(id, entity.kind.clone())) {
+            if let Some((previous_id, kind)) = &previous {
+                if let Some(ids) = self.by_kind.get_mut(kind) {
+                    ids.retain(|eid| eid != previous_id);
+                }
+            }
+            let ids = self.by_kind.entry(entity.kind.clone()).or_default();
+            if let Err(position) = ids.binary_search(&id) {
+                ids.insert(position, id);
+            }
+        }
+        if previous.is_none() {
+            self.free.remove(&id.index());
+            self.len += 1;
+        }
+        self.slots[index] = Slot::Occupied(entity);
+    }
+
+    /// Get the IDs the vacant slots will give their next entities, in
+    /// ascending order
+    pub(crate) fn vacancies(&self) -> impl Iterator<Item = EntityId> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| match slot {
+                Slot::Vacant(generation) => Some(EntityId::from_parts(index as u32, *generation)),
+                Slot::Occupied(_) => None,
+            })
+    }
+
+    /// Free a slot, removing any entity in it, and set the ID its next
+    /// entity gets
+    pub(crate) fn vacate(&mut self, next: EntityId) {
+        self.reserve_slot(next.index());
+        let occupant = self.slots[next.index() as usize].entity().map(|e| e.id);
+        if let Some(id) = occupant {
+            self.remove(id);
+        }
+        self.slots[next.index() as usize] = Slot::Vacant(next.generation());
     }
 
     /// Get an entity by ID
     pub fn get(&self, id: EntityId) -> Option<&Entity> {
-        self.entities.get(&id)
+        self.slots
+            .get(id.index() as usize)
+            .and_then(Slot::entity)
+            .filter(|entity| entity.id == id)
     }
 
     /// Get a mutable reference to an entity
     pub fn get_mut(&mut self, id: EntityId) -> Option<&mut Entity> {
-        self.entities.get_mut(&id)
+        self.slots
+            .get_mut(id.index() as usize)
+            .and_then(Slot::entity_mut)
+            .filter(|entity| entity.id == id)
     }
 
-    /// Remove an entity
+    /// Remove an entity, freeing its slot for the next generation
     pub fn remove(&mut self, id: EntityId) -> Option<Entity> {
-        if let Some(entity) = self.entities.shift_remove(&id) {
-            // Remove from kind index
-            if let Some(ids) = self.by_kind.get_mut(&entity.kind) {
-                ids.retain(|&eid| eid != id);
-            }
-            Some(entity)
-        } else {
-            None
+        self.get(id)?;
+        let slot = std::mem::replace(
+            &mut self.slots[id.index() as usize],
+            Slot::Vacant(id.generation().wrapping_add(1)),
+        );
+        self.free.insert(id.index());
+        self.len -= 1;
+        let Slot::Occupied(entity) = slot else {
+            unreachable!("entity {} was just found", id)
+        };
+        // Remove from kind index
+        if let Some(ids) = self.by_kind.get_mut(&entity.kind) {
+            ids.retain(|&eid| eid != id);
         }
+        Some(entity)
     }
 
     /// Get all entities of a given kind
@@ -171,41 +400,69 @@ impl EntityStore {
         self.by_kind
             .get(kind)
             .into_iter()
-            .flat_map(|ids| ids.iter().filter_map(|id| self.entities.get(id)))
+            .flat_map(|ids| ids.iter().filter_map(|id| self.get(*id)))
+    }
+
+    /// Get all entities with a tag, in ascending ID order
+    pub fn by_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a Entity> + 'a {
+        self.iter().filter(move |entity| entity.has_tag(tag))
     }
 
     /// Get all entity IDs
     pub fn ids(&self) -> impl Iterator<Item = EntityId> + '_ {
-        self.entities.keys().copied()
+        self.iter().map(|entity| entity.id)
     }
 
     /// Get all entities
     pub fn iter(&self) -> impl Iterator<Item = &Entity> {
-        self.entities.values()
+        self.slots.iter().filter_map(Slot::entity)
     }
 
     /// Get all entities mutably
     pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Entity> {
-        self.entities.values_mut()
+        self.slots.iter_mut().filter_map(Slot::entity_mut)
     }
 
     /// Get the number of entities
     pub fn len(&self) -> usize {
-        self.entities.len()
+        self.len
     }
 
     /// Check if the store is empty
     pub fn is_empty(&self) -> bool {
-        self.entities.is_empty()
+        self.len == 0
     }
 
     /// Resolve an EntityRef to an Entity
+    ///
+    /// Multi-target references resolve to the first entity they match.
     pub fn resolve(&self, entity_ref: &EntityRef) -> Option<&Entity> {
         match entity_ref {
             EntityRef::None => None,
             EntityRef::Entity(id) => self.get(*id),
             EntityRef::Global => None, // Global has no entity
-            EntityRef::ByDef(def) => self.by_kind(def).next(),
+            EntityRef::ByDef(def) | EntityRef::Kind(def) => self.by_kind(def).next(),
+            EntityRef::Set(ids) => ids.iter().find_map(|id| self.get(*id)),
+        }
+    }
+
+    /// Resolve an EntityRef to the IDs of every entity it matches
+    ///
+    /// Kinds match in ascending ID order and sets in the order given, without
+    /// duplicates. Missing entities are skipped.
+    pub fn resolve_all(&self, entity_ref: &EntityRef) -> Vec<EntityId> {
+        match entity_ref {
+            EntityRef::Kind(kind) => self.by_kind.get(kind).cloned().unwrap_or_default(),
+            EntityRef::Set(ids) => {
+                let mut matched = Vec::with_capacity(ids.len());
+                for id in ids {
+                    if self.get(*id).is_some() && !matched.contains(id) {
+                        matched.push(*id);
+                    }
+                }
+                matched
+            }
+            _ => self.resolve(entity_ref).map(|e| e.id).into_iter().collect(),
         }
     }
 
@@ -215,15 +472,32 @@ impl EntityStore {
             EntityRef::None => None,
             EntityRef::Entity(id) => self.get_mut(*id),
             EntityRef::Global => None,
-            EntityRef::ByDef(def) => {
+            EntityRef::ByDef(def) | EntityRef::Kind(def) => {
                 // Need to get ID first to avoid borrow issues
                 let id = self.by_kind.get(def).and_then(|ids| ids.first()).copied();
                 id.and_then(move |id| self.get_mut(id))
             }
+            EntityRef::Set(ids) => {
+                let id = ids.iter().find(|id| self.get(**id).is_some()).copied();
+                id.and_then(move |id| self.get_mut(id))
+            }
         }
     }
 }
 
+// The store serializes as its slots; the free list and indexes are rebuilt
+impl Serialize for EntityStore {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.slots.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for EntityStore {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<Slot>::deserialize(deserializer).map(Self::from_slots)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -262,4 +536,89 @@ mod tests {
         assert!(store.get(france_id).is_some());
         assert!(store.get(england_id).is_some());
     }
+
+    #[test]
+    fn test_entity_store_insert() {
+        let mut store = EntityStore::new();
+        let mut entity = Entity::new(EntityId::new(5), "nation");
+        entity.set("gold", 10.0f64);
+        store.insert(entity);
+
+        assert_eq!(
+            store.get(EntityId::new(5)).unwrap().get_number("gold"),
+            Some(10.0)
+        );
+        assert_eq!(store.by_kind(&DefId::new("nation")).count(), 1);
+
+        // Slots below the inserted entity's are free for new entities
+        let created = store.create("nation").id;
+        assert_eq!(created, EntityId::new(0));
+        assert_eq!(store.len(), 2);
+
+        // Replacing with a different kind updates the kind index
+        store.insert(Entity::new(EntityId::new(5), "province"));
+        assert_eq!(store.by_kind(&DefId::new("nation")).count(), 1);
+        assert_eq!(store.by_kind(&DefId::new("province")).count(), 1);
+    }
+
+    #[test]
+    fn test_entity_store_order() {
+        let mut store = EntityStore::new();
+        for _ in 0..4 {
+            store.create("unit");
+        }
+        let removed = store.remove(EntityId::new(1)).unwrap();
+        store.insert(removed);
+        store.insert(Entity::new(EntityId::new(2), "building"));
+        store.insert(Entity::new(EntityId::new(2), "unit"));
+
+        let ids: Vec<u64> = store.ids().map(|id| id.raw()).collect();
+        assert_eq!(ids, [0, 1, 2, 3]);
+        let units: Vec<u64> = store
+            .by_kind(&DefId::new("unit"))
+            .map(|e| e.id.raw())
+            .collect();
+        assert_eq!(units, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_entity_store_generations() {
+        let mut store = EntityStore::new();
+        let first = store.create("unit").id;
+        let second = store.create("unit").id;
+        store.get_mut(first).unwrap().set("hp", 10i64);
+        store.remove(first);
+
+        // The freed slot is reused with a new generation
+        let reused = store.create("unit").id;
+        assert_eq!(reused.index(), first.index());
+        assert_eq!(reused.generation(), 1);
+        assert!(store.get(first).is_none());
+        assert!(store.remove(first).is_none());
+        assert_eq!(store.get(reused).unwrap().get("hp"), None);
+        let units: Vec<EntityId> = store.by_kind(&DefId::new("unit")).map(|e| e.id).collect();
+        assert_eq!(units, [reused, second]);
+
+        // Serialization keeps generations and free slots
+        store.remove(second);
+        let restored: EntityStore = ron::from_str(&ron::to_string(&store).unwrap()).unwrap();
+        assert_eq!(restored.ids().collect::<Vec<_>>(), [reused]);
+        assert_eq!(restored.clone().create("unit").id, store.create("unit").id);
+    }
+
+    #[test]
+    fn test_entity_store_subset() {
+        let mut store = EntityStore::new();
+        let ids: Vec<EntityId> = (0..4).map(|_| store.create("unit").id).collect();
+        store.remove(ids[3]);
+
+        let mut subset = store.subset([ids[1], ids[3]]);
+        assert_eq!(subset.ids().collect::<Vec<_>>(), [ids[1]]);
+        assert_eq!(subset.by_kind(&DefId::new("unit")).count(), 1);
+
+        // New entities never take the ID of an entity left out
+        let created = subset.create("unit").id;
+        assert_eq!(created.index(), ids[0].index());
+        assert!(store.get(created).is_none());
+    }
 }
@@ -1,5 +1,6 @@
 //! Error types for pulsive-core
 
+use crate::DefId;
 use thiserror::Error;
 
 /// Core error type
@@ -25,6 +26,19 @@ pub enum Error {
 
     #[error("Evaluation error: {0}")]
     EvaluationError(String),
+
+    #[error("Handler group cycle: {}", format_cycle(.0))]
+    HandlerGroupCycle(Vec<DefId>),
+
+    #[error("Effect template cycle: {}", format_cycle(.0))]
+    EffectTemplateCycle(Vec<DefId>),
+}
+
+/// Format a cycle of IDs as "a -> b -> a"
+fn format_cycle(cycle: &[DefId]) -> String {
+    let mut names: Vec<&str> = cycle.iter().map(|id| id.as_str()).collect();
+    names.extend(cycle.first().map(|id| id.as_str()));
+    names.join(" -> ")
 }
 
 /// Result type alias
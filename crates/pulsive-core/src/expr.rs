@@ -3,8 +3,13 @@
 //! Expressions are loaded from RON scripts and evaluated at runtime
 //! against the current model state.
 
-use crate::{DefId, Entity, EntityRef, EntityStore, Error, Result, Rng, Value, ValueMap};
+use crate::{
+    Curve, DefId, Entity, EntityRef, EntityStore, Error, IndexMap, Resource, Result, Rng,
+    SharedHistory, StateHistory, Tick, Value, ValueMap,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::RwLockReadGuard;
 
 /// An expression that can be evaluated to produce a Value
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,6 +85,8 @@ pub enum Expr {
     // === Entity Queries ===
     /// Check if entity has a flag
     HasFlag(DefId),
+    /// Check if entity has a tag
+    HasTag(String),
     /// Check if entity exists
     EntityExists(EntityRef),
     /// Count entities of a kind
@@ -95,11 +102,41 @@ pub enum Expr {
     /// Weighted random choice (returns index)
     WeightedRandom(Vec<Expr>),
 
+    // === Curves ===
+    /// Sample a registered [`Curve`] at a numeric input
+    Curve {
+        /// Curve ID
+        id: DefId,
+        /// Input value
+        input: Box<Expr>,
+    },
+
+    // === Resources ===
+    /// Income per tick of a registered [`Resource`] for the target
+    Income(DefId),
+    /// Upkeep per tick of a registered [`Resource`] for the target
+    Upkeep(DefId),
+
+    // === History ===
+    /// Ticks since the target entity gained a flag (`Null` without the flag)
+    ///
+    /// Counts back through the attached history, so the result is a lower
+    /// bound when the flag predates the oldest stored state.
+    TicksSince(DefId),
+    /// A target property `offset` ticks ago (`Null` if not in the history)
+    ValueAt(String, Box<Expr>),
+    /// Average of a numeric target property over the last `window` ticks,
+    /// including the current one
+    MovingAvg(String, Box<Expr>),
+
     // === String ===
     /// Concatenate strings
     Concat(Vec<Expr>),
     /// Format a string with values
     Format(String, Vec<Expr>),
+    /// Localized text: a key and named parameters, rendered by the UI in
+    /// the player's language (see [`Value::localized`])
+    Localized(String, Vec<(String, Expr)>),
 }
 
 /// Context for evaluating expressions
@@ -114,6 +151,12 @@ pub struct EvalContext<'a> {
     pub params: &'a ValueMap,
     /// Random number generator
     pub rng: &'a mut Rng,
+    /// Curves available to [`Expr::Curve`]
+    pub curves: Option<&'a HashMap<DefId, Curve>>,
+    /// Resources available to [`Expr::Income`] and [`Expr::Upkeep`]
+    pub resources: Option<&'a IndexMap<DefId, Resource>>,
+    /// State history queried by temporal expressions, and the current tick
+    pub history: Option<(&'a SharedHistory, Tick)>,
 }
 
 impl<'a> EvalContext<'a> {
@@ -130,6 +173,9 @@ impl<'a> EvalContext<'a> {
             globals,
             params,
             rng,
+            curves: None,
+            resources: None,
+            history: None,
         }
     }
 
@@ -138,6 +184,45 @@ impl<'a> EvalContext<'a> {
         self.target = Some(target);
         self
     }
+
+    /// Set the curves available to [`Expr::Curve`]
+    pub fn with_curves(mut self, curves: &'a HashMap<DefId, Curve>) -> Self {
+        self.curves = Some(curves);
+        self
+    }
+
+    /// Set the resources available to [`Expr::Income`] and [`Expr::Upkeep`]
+    pub fn with_resources(mut self, resources: &'a IndexMap<DefId, Resource>) -> Self {
+        self.resources = Some(resources);
+        self
+    }
+
+    /// Set the state history queried by temporal expressions, with the
+    /// current tick
+    pub fn with_history(mut self, history: &'a SharedHistory, tick: Tick) -> Self {
+        self.history = Some((history, tick));
+        self
+    }
+
+    /// Lock the state history for a temporal expression
+    fn read_history(
+        &self,
+        function: &str,
+    ) -> Result<(RwLockReadGuard<'a, dyn StateHistory + Send + Sync>, Tick)> {
+        let (history, tick) = self
+            .history
+            .ok_or_else(|| Error::EvaluationError(format!("No state history for {}", function)))?;
+        let history = history
+            .read()
+            .map_err(|_| Error::EvaluationError("State history lock poisoned".to_string()))?;
+        Ok((history, tick))
+    }
+
+    /// Get the target entity for a temporal expression
+    fn history_target(&self, function: &str) -> Result<&'a Entity> {
+        self.target
+            .ok_or_else(|| Error::EvaluationError(format!("No target entity for {}", function)))
+    }
 }
 
 impl Expr {
@@ -354,6 +439,12 @@ impl Expr {
                 })?;
                 Ok(Value::Bool(entity.has_flag(flag)))
             }
+            Expr::HasTag(tag) => {
+                let entity = ctx.target.ok_or_else(|| {
+                    Error::EvaluationError("No target entity for HasTag".to_string())
+                })?;
+                Ok(Value::Bool(entity.has_tag(tag)))
+            }
             Expr::EntityExists(entity_ref) => {
                 Ok(Value::Bool(ctx.entities.resolve(entity_ref).is_some()))
             }
@@ -406,6 +497,96 @@ impl Expr {
                 }
             }
 
+            // Curves
+            Expr::Curve { id, input } => {
+                let curve = ctx
+                    .curves
+                    .and_then(|curves| curves.get(id))
+                    .ok_or_else(|| Error::EvaluationError(format!("Unknown curve: {}", id)))?;
+                let v = input.eval(ctx)?;
+                let x = v.as_float().ok_or_else(|| Error::TypeError {
+                    expected: "number".to_string(),
+                    got: v.type_name().to_string(),
+                })?;
+                Ok(Value::Float(curve.sample(x)))
+            }
+
+            // Resources
+            Expr::Income(id) | Expr::Upkeep(id) => {
+                let resource = ctx
+                    .resources
+                    .and_then(|resources| resources.get(id))
+                    .ok_or_else(|| Error::EvaluationError(format!("Unknown resource: {}", id)))?;
+                let rate = match self {
+                    Expr::Income(_) => resource.eval_income(ctx)?,
+                    _ => resource.eval_upkeep(ctx)?,
+                };
+                Ok(Value::Float(rate))
+            }
+
+            // History
+            Expr::TicksSince(flag) => {
+                let entity = ctx.history_target("TicksSince")?;
+                if !entity.has_flag(flag) {
+                    return Ok(Value::Null);
+                }
+                let (history, tick) = ctx.read_history("TicksSince")?;
+                let mut since = 0;
+                while since < tick {
+                    let past = history
+                        .get_state(tick - since - 1)
+                        .and_then(|model| model.entities().get(entity.id));
+                    match past {
+                        Some(past) if past.has_flag(flag) => since += 1,
+                        _ => break,
+                    }
+                }
+                Ok(Value::Int(since as i64))
+            }
+            Expr::ValueAt(property, offset) => {
+                let entity = ctx.history_target("ValueAt")?;
+                let offset = tick_count(&offset.eval(ctx)?)?;
+                if offset == 0 {
+                    return Ok(entity.get(property).cloned().unwrap_or(Value::Null));
+                }
+                let (history, tick) = ctx.read_history("ValueAt")?;
+                let value = tick
+                    .checked_sub(offset)
+                    .and_then(|at| history.get_nearest_before(at))
+                    .and_then(|(_, model)| model.entities().get(entity.id))
+                    .and_then(|past| past.get(property).cloned());
+                Ok(value.unwrap_or(Value::Null))
+            }
+            Expr::MovingAvg(property, window) => {
+                let entity = ctx.history_target("MovingAvg")?;
+                let window = tick_count(&window.eval(ctx)?)?;
+                if window == 0 {
+                    return Err(Error::EvaluationError(
+                        "MovingAvg window must be positive".to_string(),
+                    ));
+                }
+                let (history, tick) = ctx.read_history("MovingAvg")?;
+                let mut values: Vec<f64> = entity
+                    .get(property)
+                    .and_then(|v| v.as_float())
+                    .into_iter()
+                    .collect();
+                for back in 1..window.min(tick + 1) {
+                    let past = history
+                        .get_state(tick - back)
+                        .and_then(|model| model.entities().get(entity.id))
+                        .and_then(|past| past.get(property))
+                        .and_then(|v| v.as_float());
+                    values.extend(past);
+                }
+                if values.is_empty() {
+                    return Ok(Value::Null);
+                }
+                Ok(Value::Float(
+                    values.iter().sum::<f64>() / values.len() as f64,
+                ))
+            }
+
             // String
             Expr::Concat(exprs) => {
                 let mut result = String::new();
@@ -424,6 +605,13 @@ impl Expr {
                 }
                 Ok(Value::String(result))
             }
+            Expr::Localized(key, params) => {
+                let mut values = ValueMap::new();
+                for (name, expr) in params {
+                    values.insert(name.clone(), expr.eval(ctx)?);
+                }
+                Ok(Value::localized(key.clone(), values))
+            }
         }
     }
 
@@ -442,10 +630,33 @@ impl Expr {
         Expr::Global(name.into())
     }
 
+    /// Create a localized text expression without parameters
+    pub fn localized(key: impl Into<String>) -> Self {
+        Expr::Localized(key.into(), Vec::new())
+    }
+
     /// Create a parameter access expression
     pub fn param(name: impl Into<String>) -> Self {
         Expr::Param(name.into())
     }
+
+    /// Create a curve sampling expression
+    pub fn curve(id: impl Into<DefId>, input: Expr) -> Self {
+        Expr::Curve {
+            id: id.into(),
+            input: Box::new(input),
+        }
+    }
+}
+
+/// Helper to read a non-negative tick count
+fn tick_count(value: &Value) -> Result<Tick> {
+    let n = value.as_int().ok_or_else(|| Error::TypeError {
+        expected: "int".to_string(),
+        got: value.type_name().to_string(),
+    })?;
+    u64::try_from(n)
+        .map_err(|_| Error::EvaluationError(format!("Tick count must not be negative: {}", n)))
 }
 
 /// Helper to perform numeric operations
@@ -475,7 +686,7 @@ fn compare_values(a: &Value, b: &Value, cmp: fn(f64, f64) -> bool) -> Result<Val
 }
 
 /// Check if two values are equal
-fn values_equal(a: &Value, b: &Value) -> bool {
+pub(crate) fn values_equal(a: &Value, b: &Value) -> bool {
     match (a, b) {
         (Value::Null, Value::Null) => true,
         (Value::Bool(x), Value::Bool(y)) => x == y,
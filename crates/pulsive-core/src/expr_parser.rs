@@ -0,0 +1,645 @@
+//! Parser for expressions written as text
+//!
+//! Lets conditions and values be written as `gold * 2 >= global.upkeep`
+//! instead of nested [`Expr`] trees:
+//!
+//! | Syntax                         | Expression                        |
+//! |--------------------------------|-----------------------------------|
+//! | `42`, `0.5`, `"text"`          | integer, float and string literals|
+//! | `true`, `false`, `null`        | boolean and null literals         |
+//! | `gold`, `target.gold`          | [`Expr::Property`]                |
+//! | `global.gold`                  | [`Expr::Global`]                  |
+//! | `param.amount`                 | [`Expr::Param`]                   |
+//! | `+ - * / %`, unary `-`         | arithmetic                        |
+//! | `== != < <= > >=`              | comparison                        |
+//! | `&&`/`and`, `\|\|`/`or`, `!`/`not` | logic                         |
+//! | `f(args)`                      | built-in functions (see below)    |
+//!
+//! Functions: `abs`, `floor`, `ceil`, `round`, `min`, `max`, `clamp`,
+//! `if(cond, then, else)`, `random()`, `random(min, max)`,
+//! `random_int(min, max)`, `weighted_random(...)`, `has_flag(flag)`, `has_tag(tag)`,
+//! `count(kind)`, `exists(def)`, `curve(id, input)`, `income(resource)`,
+//! `upkeep(resource)`, `ticks_since(flag)`, `value_at(property, offset)`,
+//! `moving_avg(property, window)`, `concat(...)` and `format("{0}", ...)`.
+
+use crate::{DefId, EntityRef, Expr, Value};
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Built-in functions of the expression syntax: name, signature and
+/// description (for editors and documentation)
+pub const EXPR_FUNCTIONS: &[(&str, &str, &str)] = &[
+    ("abs", "abs(x)", "Absolute value"),
+    ("floor", "floor(x)", "Round down"),
+    ("ceil", "ceil(x)", "Round up"),
+    ("round", "round(x)", "Round to the nearest integer"),
+    ("min", "min(a, b)", "Smaller of two values"),
+    ("max", "max(a, b)", "Larger of two values"),
+    (
+        "clamp",
+        "clamp(x, min, max)",
+        "Clamp a value between min and max",
+    ),
+    ("if", "if(cond, then, else)", "Pick a value by condition"),
+    ("random", "random()", "Random float in [0, 1)"),
+    ("random", "random(min, max)", "Random float in [min, max)"),
+    (
+        "random_int",
+        "random_int(min, max)",
+        "Random integer in [min, max]",
+    ),
+    (
+        "weighted_random",
+        "weighted_random(w0, w1)",
+        "Random index, weighted",
+    ),
+    (
+        "has_flag",
+        "has_flag(flag)",
+        "Whether the target has a flag",
+    ),
+    ("has_tag", "has_tag(tag)", "Whether the target has a tag"),
+    ("count", "count(kind)", "Number of entities of a kind"),
+    ("exists", "exists(def)", "Whether an entity exists"),
+    ("curve", "curve(id, input)", "Sample a registered curve"),
+    (
+        "income",
+        "income(resource)",
+        "Income per tick of a resource for the target",
+    ),
+    (
+        "upkeep",
+        "upkeep(resource)",
+        "Upkeep per tick of a resource for the target",
+    ),
+    (
+        "ticks_since",
+        "ticks_since(flag)",
+        "Ticks since the target gained a flag",
+    ),
+    (
+        "value_at",
+        "value_at(property, offset)",
+        "A target property `offset` ticks ago",
+    ),
+    (
+        "moving_avg",
+        "moving_avg(property, window)",
+        "Average of a target property over `window` ticks",
+    ),
+    ("concat", "concat(a, b)", "Concatenate values as text"),
+    (
+        "format",
+        "format(\"{0}\", x)",
+        "Substitute values into a template",
+    ),
+];
+
+/// Error produced when an expression string cannot be parsed
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{message} at {line}:{column}")]
+pub struct ParseError {
+    /// What went wrong
+    pub message: String,
+    /// 1-based line within the expression source
+    pub line: usize,
+    /// 1-based column within the expression source
+    pub column: usize,
+}
+
+impl Expr {
+    /// Parse an expression from text
+    pub fn parse(source: &str) -> Result<Expr, ParseError> {
+        let mut parser = Parser {
+            source,
+            tokens: tokenize(source)?,
+            pos: 0,
+        };
+        let expr = parser.expr()?;
+        match parser.peek() {
+            Token::End => Ok(expr),
+            token => Err(parser.error(format!("unexpected {}", token.describe()))),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Expr::parse(source)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Ident(String),
+    Op(&'static str),
+    End,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Int(n) => format!("number `{}`", n),
+            Token::Float(n) => format!("number `{}`", n),
+            Token::Str(s) => format!("string {:?}", s),
+            Token::Ident(name) => format!("`{}`", name),
+            Token::Op(op) => format!("`{}`", op),
+            Token::End => "end of expression".to_string(),
+        }
+    }
+}
+
+/// Operators, longest first so `<=` wins over `<`
+const OPERATORS: &[&str] = &[
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "%", "!", "(", ")", ",", ".",
+];
+
+/// Convert a byte offset into a 1-based line and column
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+fn error_at(source: &str, offset: usize, message: impl Into<String>) -> ParseError {
+    let (line, column) = line_column(source, offset);
+    ParseError {
+        message: message.into(),
+        line,
+        column,
+    }
+}
+
+/// Split the source into tokens paired with their byte offsets
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() {
+            let mut end = start;
+            let mut is_float = false;
+            while let Some(&(i, c)) = chars.peek() {
+                let fraction = c == '.'
+                    && !is_float
+                    && source[i + 1..].starts_with(|d: char| d.is_ascii_digit());
+                if !c.is_ascii_digit() && c != '_' && !fraction {
+                    break;
+                }
+                is_float |= fraction;
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let text = source[start..end].replace('_', "");
+            let token = if is_float {
+                text.parse().map(Token::Float).ok()
+            } else {
+                text.parse().map(Token::Int).ok()
+            };
+            let token = token.ok_or_else(|| error_at(source, start, "invalid number"))?;
+            tokens.push((token, start));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !c.is_alphanumeric() && c != '_' {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            tokens.push((Token::Ident(source[start..end].to_string()), start));
+        } else if c == '"' || c == '\'' {
+            let quote = c;
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, c)) if c == quote => break,
+                    Some((i, '\\')) => match chars.next() {
+                        Some((_, 'n')) => text.push('\n'),
+                        Some((_, 't')) => text.push('\t'),
+                        Some((_, c)) => text.push(c),
+                        None => return Err(error_at(source, i, "unterminated string")),
+                    },
+                    Some((_, c)) => text.push(c),
+                    None => return Err(error_at(source, start, "unterminated string")),
+                }
+            }
+            tokens.push((Token::Str(text), start));
+        } else {
+            let rest = &source[start..];
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| error_at(source, start, format!("unexpected character `{}`", c)))?;
+            for _ in 0..op.len() {
+                chars.next();
+            }
+            tokens.push((Token::Op(op), start));
+        }
+    }
+
+    tokens.push((Token::End, source.len()));
+    Ok(tokens)
+}
+
+/// Constructor of a binary expression (e.g. `Expr::Add`)
+type Binary = fn(Box<Expr>, Box<Expr>) -> Expr;
+
+/// Recursive descent parser, lowest precedence first:
+/// `or` < `and` < `not` < comparison < `+ -` < `* / %` < unary minus
+struct Parser<'s> {
+    source: &'s str,
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    /// Current token and its offset (the end token once exhausted)
+    fn current(&self) -> &(Token, usize) {
+        &self.tokens[self.pos.min(self.tokens.len() - 1)]
+    }
+
+    fn peek(&self) -> &Token {
+        &self.current().0
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.peek().clone();
+        self.pos += 1;
+        token
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        error_at(self.source, self.current().1, message)
+    }
+
+    /// Consume an operator or keyword if it is next
+    fn eat(&mut self, op: &str) -> bool {
+        let matches = match self.peek() {
+            Token::Op(o) => *o == op,
+            Token::Ident(name) => name == op,
+            _ => false,
+        };
+        if matches {
+            self.next();
+        }
+        matches
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), ParseError> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            let found = self.peek().describe();
+            Err(self.error(format!("expected `{}`, found {}", op, found)))
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        self.or()
+    }
+
+    fn or(&mut self) -> Result<Expr, ParseError> {
+        let mut terms = vec![self.and()?];
+        while self.eat("||") || self.eat("or") {
+            terms.push(self.and()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expr::Or(terms)
+        })
+    }
+
+    fn and(&mut self) -> Result<Expr, ParseError> {
+        let mut terms = vec![self.not()?];
+        while self.eat("&&") || self.eat("and") {
+            terms.push(self.not()?);
+        }
+        Ok(if terms.len() == 1 {
+            terms.remove(0)
+        } else {
+            Expr::And(terms)
+        })
+    }
+
+    fn not(&mut self) -> Result<Expr, ParseError> {
+        if self.eat("!") || self.eat("not") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, ParseError> {
+        let left = self.sum()?;
+        let build: Binary = match self.peek() {
+            Token::Op("==") => Expr::Eq,
+            Token::Op("!=") => Expr::Ne,
+            Token::Op("<") => Expr::Lt,
+            Token::Op("<=") => Expr::Le,
+            Token::Op(">") => Expr::Gt,
+            Token::Op(">=") => Expr::Ge,
+            _ => return Ok(left),
+        };
+        self.next();
+        let right = self.sum()?;
+        Ok(build(Box::new(left), Box::new(right)))
+    }
+
+    fn sum(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.product()?;
+        loop {
+            let build: Binary = match self.peek() {
+                Token::Op("+") => Expr::Add,
+                Token::Op("-") => Expr::Sub,
+                _ => return Ok(left),
+            };
+            self.next();
+            left = build(Box::new(left), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.unary()?;
+        loop {
+            let build: Binary = match self.peek() {
+                Token::Op("*") => Expr::Mul,
+                Token::Op("/") => Expr::Div,
+                Token::Op("%") => Expr::Mod,
+                _ => return Ok(left),
+            };
+            self.next();
+            left = build(Box::new(left), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.eat("-") {
+            return Ok(match self.unary()? {
+                Expr::Literal(Value::Int(n)) => Expr::Literal(Value::Int(-n)),
+                Expr::Literal(Value::Float(n)) => Expr::Literal(Value::Float(-n)),
+                expr => Expr::Neg(Box::new(expr)),
+            });
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        match self.next() {
+            Token::Int(n) => Ok(Expr::lit(n)),
+            Token::Float(n) => Ok(Expr::lit(n)),
+            Token::Str(s) => Ok(Expr::lit(s)),
+            Token::Op("(") => {
+                let expr = self.expr()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Token::Ident(name) => self.identifier(name),
+            token => {
+                self.pos -= 1;
+                Err(self.error(format!("expected a value, found {}", token.describe())))
+            }
+        }
+    }
+
+    fn identifier(&mut self, name: String) -> Result<Expr, ParseError> {
+        match name.as_str() {
+            "true" => return Ok(Expr::lit(true)),
+            "false" => return Ok(Expr::lit(false)),
+            "null" => return Ok(Expr::Literal(Value::Null)),
+            _ => {}
+        }
+
+        if self.eat("(") {
+            return self.call(&name);
+        }
+        if !self.eat(".") {
+            return Ok(Expr::Property(name));
+        }
+
+        let field = match self.next() {
+            Token::Ident(field) => field,
+            token => {
+                self.pos -= 1;
+                return Err(self.error(format!(
+                    "expected a property name, found {}",
+                    token.describe()
+                )));
+            }
+        };
+        match name.as_str() {
+            "global" => Ok(Expr::Global(field)),
+            "param" => Ok(Expr::Param(field)),
+            "target" | "self" => Ok(Expr::Property(field)),
+            _ => Err(self.error(format!(
+                "unknown scope `{}` (expected `global`, `param` or `target`)",
+                name
+            ))),
+        }
+    }
+
+    fn call(&mut self, name: &str) -> Result<Expr, ParseError> {
+        // Errors point at the function name, before the `(`
+        let name_offset = self.tokens[self.pos - 2].1;
+        let mut args = Vec::new();
+        if !self.eat(")") {
+            loop {
+                args.push(self.expr()?);
+                if self.eat(")") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+
+        let arity = |expected: usize| -> Result<(), ParseError> {
+            if args.len() == expected {
+                Ok(())
+            } else {
+                Err(error_at(
+                    self.source,
+                    name_offset,
+                    format!(
+                        "`{}` takes {} argument{}, got {}",
+                        name,
+                        expected,
+                        if expected == 1 { "" } else { "s" },
+                        args.len()
+                    ),
+                ))
+            }
+        };
+
+        let unary: Option<fn(Box<Expr>) -> Expr> = match name {
+            "abs" => Some(Expr::Abs),
+            "floor" => Some(Expr::Floor),
+            "ceil" => Some(Expr::Ceil),
+            "round" => Some(Expr::Round),
+            _ => None,
+        };
+        if let Some(build) = unary {
+            arity(1)?;
+            return Ok(build(Box::new(args.remove(0))));
+        }
+
+        let binary: Option<Binary> = match name {
+            "min" => Some(Expr::Min),
+            "max" => Some(Expr::Max),
+            "random_int" => Some(Expr::RandomInt),
+            "random" if !args.is_empty() => Some(Expr::RandomRange),
+            _ => None,
+        };
+        if let Some(build) = binary {
+            arity(2)?;
+            let b = args.pop().map(Box::new);
+            let a = args.pop().map(Box::new);
+            return Ok(build(a.unwrap(), b.unwrap()));
+        }
+
+        match name {
+            "random" => Ok(Expr::Random),
+            "clamp" | "if" => {
+                arity(3)?;
+                let mut args = args.into_iter().map(Box::new);
+                let (a, b, c) = (args.next(), args.next(), args.next());
+                let build = if name == "if" { Expr::If } else { Expr::Clamp };
+                Ok(build(a.unwrap(), b.unwrap(), c.unwrap()))
+            }
+            "has_flag" | "has_tag" | "count" | "exists" | "income" | "upkeep" | "ticks_since" => {
+                arity(1)?;
+                let id = match args.remove(0) {
+                    Expr::Literal(Value::String(id)) | Expr::Property(id) => DefId::new(id),
+                    _ => {
+                        return Err(error_at(
+                            self.source,
+                            name_offset,
+                            format!("`{}` takes a definition ID", name),
+                        ))
+                    }
+                };
+                Ok(match name {
+                    "has_flag" => Expr::HasFlag(id),
+                    "has_tag" => Expr::HasTag(id.0),
+                    "count" => Expr::CountEntities(id),
+                    "income" => Expr::Income(id),
+                    "upkeep" => Expr::Upkeep(id),
+                    "ticks_since" => Expr::TicksSince(id),
+                    _ => Expr::EntityExists(EntityRef::ByDef(id)),
+                })
+            }
+            "curve" => {
+                arity(2)?;
+                let input = Box::new(args.remove(1));
+                match args.remove(0) {
+                    Expr::Literal(Value::String(id)) | Expr::Property(id) => Ok(Expr::Curve {
+                        id: DefId::new(id),
+                        input,
+                    }),
+                    _ => Err(error_at(
+                        self.source,
+                        name_offset,
+                        "`curve` takes a curve ID first",
+                    )),
+                }
+            }
+            "value_at" | "moving_avg" => {
+                arity(2)?;
+                let ticks = Box::new(args.remove(1));
+                match args.remove(0) {
+                    Expr::Literal(Value::String(property)) | Expr::Property(property) => {
+                        Ok(if name == "value_at" {
+                            Expr::ValueAt(property, ticks)
+                        } else {
+                            Expr::MovingAvg(property, ticks)
+                        })
+                    }
+                    _ => Err(error_at(
+                        self.source,
+                        name_offset,
+                        format!("`{}` takes a property name first", name),
+                    )),
+                }
+            }
+            "weighted_random" => Ok(Expr::WeightedRandom(args)),
+            "concat" => Ok(Expr::Concat(args)),
+            "format" => match args.first() {
+                Some(Expr::Literal(Value::String(template))) => {
+                    let template = template.clone();
+                    Ok(Expr::Format(template, args.split_off(1)))
+                }
+                _ => Err(error_at(
+                    self.source,
+                    name_offset,
+                    "`format` takes a template string first",
+                )),
+            },
+            _ => Err(error_at(
+                self.source,
+                name_offset,
+                format!("unknown function `{}`", name),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EntityStore, EvalContext, Rng, ValueMap};
+
+    fn eval(source: &str) -> Value {
+        let entities = EntityStore::new();
+        let mut globals = ValueMap::new();
+        globals.insert("gold".to_string(), Value::Float(40.0));
+        let mut params = ValueMap::new();
+        params.insert("amount".to_string(), Value::Int(3));
+        let mut rng = Rng::new(42);
+        let mut ctx = EvalContext::new(&entities, &globals, &params, &mut rng);
+        Expr::parse(source).unwrap().eval(&mut ctx).unwrap()
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        assert_eq!(eval("1 + 2 * 3").as_float(), Some(7.0));
+        assert_eq!(eval("(1 + 2) * 3").as_float(), Some(9.0));
+        assert_eq!(eval("-2 * -param.amount").as_float(), Some(6.0));
+        assert_eq!(eval("global.gold / 4 >= 10"), Value::Bool(true));
+        assert_eq!(
+            eval("global.gold > 50 || param.amount == 3 and not false"),
+            Value::Bool(true)
+        );
+        assert_eq!(eval("max(min(global.gold, 5), 2)").as_float(), Some(5.0));
+        assert_eq!(
+            eval("if(param.amount < 2, 'few', \"many\")"),
+            Value::String("many".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = Expr::parse("gold +").unwrap_err();
+        assert_eq!(err.message, "expected a value, found end of expression");
+        assert_eq!((err.line, err.column), (1, 7));
+
+        let err = Expr::parse("global.gold >=\n  maxx(1, 2)").unwrap_err();
+        assert_eq!(err.message, "unknown function `maxx`");
+        assert_eq!((err.line, err.column), (2, 3));
+
+        for (_, signature, _) in EXPR_FUNCTIONS {
+            assert!(Expr::parse(signature).is_ok(), "{}", signature);
+        }
+
+        let err = Expr::parse("clamp(1, 2)").unwrap_err();
+        assert_eq!(err.message, "`clamp` takes 3 arguments, got 2");
+        assert!("1 + 2".parse::<Expr>().is_ok());
+    }
+}
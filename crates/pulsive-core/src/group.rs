@@ -0,0 +1,203 @@
+//! Ordering of handlers by group
+//!
+//! Priorities order the handlers of one event, or the tick handlers, among
+//! themselves. Groups order whole sets of handlers instead: every handler in
+//! a group runs before or after every handler in another, whatever their
+//! priorities and event types:
+//!
+//! ```rust,ignore
+//! runtime.register_group(HandlerGroup::new("income"))?;
+//! runtime.register_group(HandlerGroup::new("expense").after("income"))?;
+//! runtime.on_tick_in("expense", upkeep);
+//! runtime.on_tick_in("income", taxes); // runs before upkeep
+//! ```
+//!
+//! The constraints between groups form a graph, resolved into a run order
+//! when a group is registered; a constraint that closes a cycle is rejected.
+//! Groups not ordered relative to each other run in the order they were
+//! first declared or referenced, so the run order is the same on every
+//! machine.
+
+use crate::{DefId, Error, Result};
+use std::collections::{BTreeSet, HashMap};
+
+/// A named group of handlers with ordering constraints
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandlerGroup {
+    /// Group ID
+    pub id: DefId,
+    /// Groups whose handlers run after this group's
+    pub before: Vec<DefId>,
+    /// Groups whose handlers run before this group's
+    pub after: Vec<DefId>,
+}
+
+impl HandlerGroup {
+    /// Create a group with no constraints
+    pub fn new(id: impl Into<DefId>) -> Self {
+        Self {
+            id: id.into(),
+            before: Vec::new(),
+            after: Vec::new(),
+        }
+    }
+
+    /// Run this group's handlers before another group's
+    pub fn before(mut self, group: impl Into<DefId>) -> Self {
+        self.before.push(group.into());
+        self
+    }
+
+    /// Run this group's handlers after another group's
+    pub fn after(mut self, group: impl Into<DefId>) -> Self {
+        self.after.push(group.into());
+        self
+    }
+}
+
+/// Declared handler groups, resolved into a run order
+#[derive(Debug, Clone, Default)]
+pub struct HandlerGroups {
+    /// Groups, in the order first declared or referenced
+    groups: Vec<HandlerGroup>,
+    /// Position of each group in the run order
+    ranks: HashMap<DefId, usize>,
+}
+
+impl HandlerGroups {
+    /// Create an empty set of groups
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a group, replacing the constraints of any with the same ID
+    ///
+    /// Groups it references are declared without constraints if they were
+    /// not yet. Fails, leaving the groups unchanged, if its constraints
+    /// would close a cycle.
+    pub fn register(&mut self, group: HandlerGroup) -> Result<()> {
+        let mut groups = self.groups.clone();
+        for id in group.before.iter().chain(&group.after) {
+            if !groups.iter().any(|g| &g.id == id) {
+                groups.push(HandlerGroup::new(id.clone()));
+            }
+        }
+        match groups.iter_mut().find(|g| g.id == group.id) {
+            Some(existing) => *existing = group,
+            None => groups.push(group),
+        }
+        self.ranks = resolve(&groups)?;
+        self.groups = groups;
+        Ok(())
+    }
+
+    /// Get a group by ID
+    pub fn get(&self, id: &DefId) -> Option<&HandlerGroup> {
+        self.groups.iter().find(|g| &g.id == id)
+    }
+
+    /// Get the position of a group in the run order
+    pub fn rank(&self, id: &DefId) -> Option<usize> {
+        self.ranks.get(id).copied()
+    }
+
+    /// Get the group IDs in run order
+    pub fn order(&self) -> Vec<&DefId> {
+        let mut order: Vec<&DefId> = self.groups.iter().map(|g| &g.id).collect();
+        order.sort_by_key(|id| self.ranks[*id]);
+        order
+    }
+
+    /// Check whether no group is declared
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+}
+
+/// Order groups so every constraint holds, earliest declared first among
+/// groups free to run
+fn resolve(groups: &[HandlerGroup]) -> Result<HashMap<DefId, usize>> {
+    let index: HashMap<&DefId, usize> =
+        groups.iter().enumerate().map(|(i, g)| (&g.id, i)).collect();
+    // Edges from each group to the groups that must run after it
+    let mut successors = vec![Vec::new(); groups.len()];
+    for (i, group) in groups.iter().enumerate() {
+        successors[i].extend(group.before.iter().map(|id| index[id]));
+        for id in &group.after {
+            successors[index[id]].push(i);
+        }
+    }
+    let mut predecessors = vec![0usize; groups.len()];
+    for &next in successors.iter().flatten() {
+        predecessors[next] += 1;
+    }
+
+    let mut ready: BTreeSet<usize> = (0..groups.len())
+        .filter(|&i| predecessors[i] == 0)
+        .collect();
+    let mut ranks = HashMap::with_capacity(groups.len());
+    while let Some(i) = ready.pop_first() {
+        ranks.insert(groups[i].id.clone(), ranks.len());
+        for &next in &successors[i] {
+            predecessors[next] -= 1;
+            if predecessors[next] == 0 {
+                ready.insert(next);
+            }
+        }
+    }
+    if ranks.len() == groups.len() {
+        return Ok(ranks);
+    }
+
+    // Every unranked group lies on or after a cycle; walk back to one
+    let mut cycle = Vec::new();
+    let mut current = (0..groups.len()).find(|&i| predecessors[i] > 0).unwrap();
+    loop {
+        if let Some(start) = cycle.iter().position(|&i| i == current) {
+            cycle.drain(..start);
+            break;
+        }
+        cycle.push(current);
+        current = (0..groups.len())
+            .find(|&i| predecessors[i] > 0 && successors[i].contains(&current))
+            .unwrap();
+    }
+    cycle.reverse();
+    Err(Error::HandlerGroupCycle(
+        cycle.into_iter().map(|i| groups[i].id.clone()).collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_order() {
+        let mut groups = HandlerGroups::new();
+        groups
+            .register(HandlerGroup::new("expense").after("income"))
+            .unwrap();
+        groups
+            .register(HandlerGroup::new("cleanup").after("expense"))
+            .unwrap();
+        groups
+            .register(HandlerGroup::new("events").before("income"))
+            .unwrap();
+        let order: Vec<&str> = groups.order().iter().map(|id| id.as_str()).collect();
+        assert_eq!(order, ["events", "income", "expense", "cleanup"]);
+
+        // A cycle is rejected and leaves the order unchanged
+        let err = groups
+            .register(HandlerGroup::new("income").after("cleanup"))
+            .unwrap_err();
+        match err {
+            Error::HandlerGroupCycle(cycle) => {
+                assert_eq!(cycle.len(), 3);
+                assert!(cycle.contains(&DefId::new("income")));
+            }
+            other => panic!("unexpected error {:?}", other),
+        }
+        assert_eq!(groups.rank(&DefId::new("income")), Some(1));
+    }
+}
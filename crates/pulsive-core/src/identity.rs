@@ -4,31 +4,70 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 
 /// Unique identifier for an entity instance at runtime
+///
+/// An ID is the index of the entity's slot in the
+/// [`EntityStore`](crate::EntityStore) plus the generation of the slot, so an
+/// ID kept after its entity is destroyed never finds the entity that reuses
+/// the slot. The raw value packs the generation in the high 32 bits and the
+/// index in the low 32 bits; it is what IDs serialize as. Generation 0 IDs
+/// have the index as raw value.
+///
+/// IDs order by index, then generation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct EntityId(pub u64);
 
 impl EntityId {
-    /// Create a new entity ID
+    /// Create an entity ID from its raw value
     pub fn new(id: u64) -> Self {
         Self(id)
     }
 
+    /// Create an entity ID from a slot index and generation
+    pub fn from_parts(index: u32, generation: u32) -> Self {
+        Self((generation as u64) << 32 | index as u64)
+    }
+
     /// Get the raw ID value
     pub fn raw(&self) -> u64 {
         self.0
     }
+
+    /// Get the slot index
+    pub fn index(&self) -> u32 {
+        self.0 as u32
+    }
+
+    /// Get the generation of the slot
+    pub fn generation(&self) -> u32 {
+        (self.0 >> 32) as u32
+    }
+}
+
+impl Ord for EntityId {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.index(), self.generation()).cmp(&(other.index(), other.generation()))
+    }
+}
+
+impl PartialOrd for EntityId {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl fmt::Display for EntityId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "entity:{}", self.0)
+        match self.generation() {
+            0 => write!(f, "entity:{}", self.index()),
+            generation => write!(f, "entity:{}v{}", self.index(), generation),
+        }
     }
 }
 
 /// Identifier for a definition (type, event, resource, etc.) loaded from scripts
 ///
 /// Uses a string-based ID for easy reference from RON scripts
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct DefId(pub String);
 
@@ -71,6 +110,12 @@ mod tests {
         let id = EntityId::new(42);
         assert_eq!(id.raw(), 42);
         assert_eq!(format!("{}", id), "entity:42");
+
+        let reused = EntityId::from_parts(42, 3);
+        assert_eq!((reused.index(), reused.generation()), (42, 3));
+        assert_ne!(reused, id);
+        assert!(id < reused && reused < EntityId::new(43));
+        assert_eq!(format!("{}", reused), "entity:42v3");
     }
 
     #[test]
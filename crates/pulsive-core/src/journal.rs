@@ -28,7 +28,7 @@
 //! let entries = journal.entries_since(0);
 //! ```
 
-use crate::{Model, Msg, Tick};
+use crate::{HandlerTrace, Model, Msg, MsgId, Tick, WriteSet};
 use serde::{Deserialize, Serialize};
 
 /// A journal entry representing a recorded event
@@ -55,6 +55,24 @@ pub enum JournalEntry {
         /// Unique ID for this snapshot
         snapshot_id: SnapshotId,
     },
+    /// Writes applied during a tick (write-level recording)
+    Writes {
+        /// The tick the writes were applied in
+        tick: Tick,
+        /// All writes applied during the tick, in order
+        writes: WriteSet,
+        /// RNG state at the end of the tick
+        rng_state: u64,
+    },
+    /// Handlers run for the preceding message (causality tracing)
+    Provenance {
+        /// The tick the message was processed in
+        tick: Tick,
+        /// Sequence number of the message within the tick
+        seq: u64,
+        /// Handlers that processed the message and what their effects did
+        handlers: Vec<HandlerTrace>,
+    },
     /// Custom metadata entry (for auditing)
     Metadata {
         /// The tick when this was recorded
@@ -64,6 +82,32 @@ pub enum JournalEntry {
         /// Value (serialized)
         value: String,
     },
+    /// RNG use during a tick (RNG recording)
+    Rng {
+        /// The tick the RNG was used in
+        tick: Tick,
+        /// RNG state at the start of the tick
+        seed: u64,
+        /// Values drawn during the tick
+        draws: u64,
+        /// The values drawn, in order (empty unless draws are logged)
+        values: Vec<u64>,
+    },
+}
+
+impl JournalEntry {
+    /// Get the tick the entry was recorded at
+    pub fn tick(&self) -> Tick {
+        match self {
+            JournalEntry::Message { tick, .. }
+            | JournalEntry::TickBoundary { tick }
+            | JournalEntry::Snapshot { tick, .. }
+            | JournalEntry::Writes { tick, .. }
+            | JournalEntry::Rng { tick, .. }
+            | JournalEntry::Provenance { tick, .. }
+            | JournalEntry::Metadata { tick, .. } => *tick,
+        }
+    }
 }
 
 /// Unique identifier for a snapshot
@@ -88,6 +132,21 @@ pub struct Snapshot {
     pub model: Model,
 }
 
+/// Destination that persists journal records as they are recorded
+///
+/// Implemented by on-disk writers and database stores so long sessions can
+/// be streamed out of memory. Feed a sink with [`Journal::sync_to`].
+pub trait JournalSink {
+    /// Error returned when a record cannot be persisted
+    type Error;
+
+    /// Persist a journal entry
+    fn append_entry(&mut self, entry: &JournalEntry) -> Result<(), Self::Error>;
+
+    /// Persist a snapshot, before its [`JournalEntry::Snapshot`] entry
+    fn append_snapshot(&mut self, snapshot: &Snapshot) -> Result<(), Self::Error>;
+}
+
 /// Configuration for the journal
 #[derive(Debug, Clone)]
 pub struct JournalConfig {
@@ -99,6 +158,34 @@ pub struct JournalConfig {
     pub max_entries: usize,
     /// Maximum number of snapshots to keep (0 = unlimited)
     pub max_snapshots: usize,
+    /// Keep only entries from the last N ticks (0 = unlimited)
+    ///
+    /// Entries back to the newest snapshot before the window are kept as
+    /// well, so every retained tick stays replayable.
+    pub max_ticks: u64,
+    /// Reduce entries older than N ticks to snapshots only (0 = disabled)
+    ///
+    /// Applied by [`Journal::compact`], which also runs whenever a snapshot
+    /// is taken.
+    pub compact_after_ticks: u64,
+    /// Record the writes applied in each tick, not just messages
+    ///
+    /// Write-level recording lets a session be reconstructed exactly without
+    /// re-executing handlers.
+    pub record_writes: bool,
+    /// Record which handlers processed each message and what they produced
+    ///
+    /// Causality traces link messages to the writes and events they caused.
+    pub record_causality: bool,
+    /// Record the RNG state at the start of each tick and how many values
+    /// were drawn during it
+    ///
+    /// Lets replays restore the RNG exactly and detect divergence.
+    pub record_rng: bool,
+    /// Record every value drawn from the RNG as well (verbose)
+    ///
+    /// Only used with [`JournalConfig::record_rng`].
+    pub record_rng_draws: bool,
 }
 
 impl Default for JournalConfig {
@@ -108,6 +195,12 @@ impl Default for JournalConfig {
             snapshot_interval: 100, // Snapshot every 100 ticks by default
             max_entries: 0,         // Unlimited
             max_snapshots: 10,      // Keep last 10 snapshots
+            max_ticks: 0,           // Unlimited
+            compact_after_ticks: 0, // Never compact
+            record_writes: false,
+            record_causality: false,
+            record_rng: false,
+            record_rng_draws: false,
         }
     }
 }
@@ -154,6 +247,40 @@ impl Journal {
         }
     }
 
+    /// Rebuild a journal from previously recorded entries and snapshots
+    ///
+    /// Used when loading a journal back from persistent storage.
+    pub fn from_parts(
+        config: JournalConfig,
+        entries: Vec<JournalEntry>,
+        snapshots: Vec<Snapshot>,
+    ) -> Self {
+        let last_recorded_tick = entries.iter().rev().find_map(|e| match e {
+            JournalEntry::TickBoundary { tick } | JournalEntry::Message { tick, .. } => Some(*tick),
+            _ => None,
+        });
+        let current_seq = entries
+            .iter()
+            .rev()
+            .take_while(|e| !matches!(e, JournalEntry::TickBoundary { .. }))
+            .filter(|e| matches!(e, JournalEntry::Message { .. }))
+            .count() as u64;
+        let next_snapshot_id = snapshots.iter().map(|s| s.id.0 + 1).max().unwrap_or(0);
+        Self {
+            config,
+            entries,
+            snapshots,
+            current_seq,
+            next_snapshot_id,
+            last_recorded_tick,
+        }
+    }
+
+    /// Get the journal configuration
+    pub fn config(&self) -> &JournalConfig {
+        &self.config
+    }
+
     /// Start recording
     pub fn start_recording(&mut self) {
         self.config.recording_enabled = true;
@@ -207,6 +334,78 @@ impl Journal {
         self.enforce_limits();
     }
 
+    /// Check if write-level recording is enabled
+    pub fn is_recording_writes(&self) -> bool {
+        self.config.recording_enabled && self.config.record_writes
+    }
+
+    /// Record the writes applied during a tick
+    ///
+    /// Does nothing unless write-level recording is enabled.
+    pub fn record_writes(&mut self, tick: Tick, writes: WriteSet, rng_state: u64) {
+        if !self.is_recording_writes() {
+            return;
+        }
+
+        self.entries.push(JournalEntry::Writes {
+            tick,
+            writes,
+            rng_state,
+        });
+
+        self.enforce_limits();
+    }
+
+    /// Check if RNG recording is enabled
+    pub fn is_recording_rng(&self) -> bool {
+        self.config.recording_enabled && self.config.record_rng
+    }
+
+    /// Check if the values drawn from the RNG are recorded
+    pub fn is_recording_rng_draws(&self) -> bool {
+        self.is_recording_rng() && self.config.record_rng_draws
+    }
+
+    /// Record the RNG use during a tick
+    ///
+    /// Does nothing unless RNG recording is enabled.
+    pub fn record_rng(&mut self, tick: Tick, seed: u64, draws: u64, values: Vec<u64>) {
+        if !self.is_recording_rng() {
+            return;
+        }
+
+        self.entries.push(JournalEntry::Rng {
+            tick,
+            seed,
+            draws,
+            values,
+        });
+
+        self.enforce_limits();
+    }
+
+    /// Check if causality tracing is enabled
+    pub fn is_recording_causality(&self) -> bool {
+        self.config.recording_enabled && self.config.record_causality
+    }
+
+    /// Record the handlers run for the most recently recorded message
+    ///
+    /// Does nothing unless causality tracing is enabled.
+    pub fn record_provenance(&mut self, tick: Tick, handlers: Vec<HandlerTrace>) {
+        if !self.is_recording_causality() {
+            return;
+        }
+
+        self.entries.push(JournalEntry::Provenance {
+            tick,
+            seq: self.current_seq.saturating_sub(1),
+            handlers,
+        });
+
+        self.enforce_limits();
+    }
+
     /// Take a snapshot of the current model state
     pub fn take_snapshot(&mut self, model: &Model) -> SnapshotId {
         let id = SnapshotId::new(self.next_snapshot_id);
@@ -228,10 +427,71 @@ impl Journal {
             });
         }
 
+        self.enforce_snapshot_limits();
+        self.compact();
+        id
+    }
+
+    /// Insert a snapshot of past state, e.g. one rebuilt by replay
+    ///
+    /// Unlike [`Journal::take_snapshot`], the snapshot does not have to be
+    /// for the latest tick; it is placed in tick order among the existing
+    /// snapshots and entries.
+    pub fn insert_snapshot(&mut self, model: Model) -> SnapshotId {
+        let id = SnapshotId::new(self.next_snapshot_id);
+        self.next_snapshot_id += 1;
+
+        let tick = model.current_tick();
+        let index = self.snapshots.partition_point(|s| s.tick <= tick);
+        self.snapshots.insert(index, Snapshot { id, tick, model });
+
+        if self.config.recording_enabled {
+            let index = self.entries.partition_point(|e| e.tick() <= tick);
+            self.entries.insert(
+                index,
+                JournalEntry::Snapshot {
+                    tick,
+                    snapshot_id: id,
+                },
+            );
+        }
+
         self.enforce_snapshot_limits();
         id
     }
 
+    /// Get the tick before which entries may be compacted
+    ///
+    /// `None` unless [`JournalConfig::compact_after_ticks`] is set and enough
+    /// ticks have been recorded.
+    pub fn compaction_cutoff(&self) -> Option<Tick> {
+        if self.config.compact_after_ticks == 0 {
+            return None;
+        }
+        self.last_recorded_tick?
+            .checked_sub(self.config.compact_after_ticks)
+    }
+
+    /// Drop entries older than the compaction cutoff, keeping snapshots
+    ///
+    /// Only entries covered by a snapshot at or before the cutoff are
+    /// removed, so the session stays replayable from that snapshot on.
+    /// Insert a snapshot at the cutoff first (see [`Journal::insert_snapshot`])
+    /// to compact all the way up to it. Returns the number of entries removed.
+    pub fn compact(&mut self) -> usize {
+        let Some(cutoff) = self.compaction_cutoff() else {
+            return 0;
+        };
+        let Some(base) = self.snapshot_at_or_before(cutoff).map(|s| s.tick) else {
+            return 0;
+        };
+
+        let before = self.entries.len();
+        self.entries
+            .retain(|e| matches!(e, JournalEntry::Snapshot { .. }) || e.tick() > base);
+        before - self.entries.len()
+    }
+
     /// Check if a snapshot should be taken at this tick
     pub fn should_snapshot(&self, tick: Tick) -> bool {
         if self.config.snapshot_interval == 0 {
@@ -273,6 +533,9 @@ impl Journal {
                 JournalEntry::Message { tick: t, .. } => *t >= tick,
                 JournalEntry::TickBoundary { tick: t } => *t >= tick,
                 JournalEntry::Snapshot { tick: t, .. } => *t >= tick,
+                JournalEntry::Writes { tick: t, .. } => *t >= tick,
+                JournalEntry::Rng { tick: t, .. } => *t >= tick,
+                JournalEntry::Provenance { tick: t, .. } => *t >= tick,
                 JournalEntry::Metadata { tick: t, .. } => *t >= tick,
             })
             .collect()
@@ -287,6 +550,9 @@ impl Journal {
                     JournalEntry::Message { tick, .. } => *tick,
                     JournalEntry::TickBoundary { tick } => *tick,
                     JournalEntry::Snapshot { tick, .. } => *tick,
+                    JournalEntry::Writes { tick, .. } => *tick,
+                    JournalEntry::Rng { tick, .. } => *tick,
+                    JournalEntry::Provenance { tick, .. } => *tick,
                     JournalEntry::Metadata { tick, .. } => *tick,
                 };
                 t >= start_tick && t <= end_tick
@@ -302,6 +568,43 @@ impl Journal {
         })
     }
 
+    /// Get the messages of a cascade, by correlation ID, in recorded order
+    ///
+    /// The message that started the cascade is included.
+    pub fn cascade(&self, correlation: MsgId) -> impl Iterator<Item = (Tick, &Msg)> {
+        self.messages()
+            .filter(move |(_, msg)| msg.correlation_id() == correlation)
+    }
+
+    /// Get recorded write sets with their ticks
+    pub fn write_sets(&self) -> impl Iterator<Item = (Tick, &WriteSet)> {
+        self.entries.iter().filter_map(|e| match e {
+            JournalEntry::Writes { tick, writes, .. } => Some((*tick, writes)),
+            _ => None,
+        })
+    }
+
+    /// Append the entries from index `start` onwards to a sink
+    ///
+    /// Snapshot entries are preceded by their snapshot. Returns the number
+    /// of entries, to pass as `start` on the next call.
+    pub fn sync_to<S: JournalSink + ?Sized>(
+        &self,
+        sink: &mut S,
+        start: usize,
+    ) -> Result<usize, S::Error> {
+        let start = start.min(self.entries.len());
+        for entry in &self.entries[start..] {
+            if let JournalEntry::Snapshot { snapshot_id, .. } = entry {
+                if let Some(snapshot) = self.get_snapshot(*snapshot_id) {
+                    sink.append_snapshot(snapshot)?;
+                }
+            }
+            sink.append_entry(entry)?;
+        }
+        Ok(self.entries.len())
+    }
+
     /// Get all snapshots
     pub fn snapshots(&self) -> &[Snapshot] {
         &self.snapshots
@@ -350,6 +653,9 @@ impl Journal {
                 JournalEntry::Message { tick, .. } => *tick,
                 JournalEntry::TickBoundary { tick } => *tick,
                 JournalEntry::Snapshot { tick, .. } => *tick,
+                JournalEntry::Writes { tick, .. } => *tick,
+                JournalEntry::Rng { tick, .. } => *tick,
+                JournalEntry::Provenance { tick, .. } => *tick,
                 JournalEntry::Metadata { tick, .. } => *tick,
             }),
             last_tick: self.last_recorded_tick,
@@ -361,6 +667,24 @@ impl Journal {
             let excess = self.entries.len() - self.config.max_entries;
             self.entries.drain(0..excess);
         }
+
+        if self.config.max_ticks > 0 {
+            let Some(oldest) = self
+                .last_recorded_tick
+                .and_then(|last| last.checked_sub(self.config.max_ticks))
+            else {
+                return;
+            };
+            // Keep the snapshot the retained window replays from
+            let window_start = oldest + 1;
+            let keep_from = self
+                .snapshot_at_or_before(window_start)
+                .map_or(window_start, |s| s.tick);
+            if self.entries.first().is_some_and(|e| e.tick() < keep_from) {
+                self.entries.retain(|e| e.tick() >= keep_from);
+                self.snapshots.retain(|s| s.tick >= keep_from);
+            }
+        }
     }
 
     fn enforce_snapshot_limits(&mut self) {
@@ -434,6 +758,9 @@ impl StateHistory for Journal {
                 JournalEntry::Message { tick: t, .. } => *t,
                 JournalEntry::TickBoundary { tick: t } => *t,
                 JournalEntry::Snapshot { tick: t, .. } => *t,
+                JournalEntry::Writes { tick: t, .. } => *t,
+                JournalEntry::Rng { tick: t, .. } => *t,
+                JournalEntry::Provenance { tick: t, .. } => *t,
                 JournalEntry::Metadata { tick: t, .. } => *t,
             };
             entry_tick >= tick
@@ -471,6 +798,23 @@ impl StateHistory for Journal {
     }
 }
 
+/// A tick whose RNG use on replay differed from the recording
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RngDivergence {
+    /// The tick that diverged
+    pub tick: Tick,
+    /// Recorded RNG state at the start of the tick
+    pub recorded_seed: u64,
+    /// RNG state at the start of the tick on replay
+    pub replayed_seed: u64,
+    /// Values drawn during the tick when recorded
+    pub recorded_draws: u64,
+    /// Values drawn during the tick on replay
+    pub replayed_draws: u64,
+    /// Index of the first drawn value that differed, if draws were recorded
+    pub first_mismatch: Option<usize>,
+}
+
 /// Statistics about the journal
 #[derive(Debug, Clone)]
 pub struct JournalStats {
@@ -518,6 +862,63 @@ mod tests {
         assert!(journal.entries().is_empty());
     }
 
+    #[test]
+    fn test_sync_to() {
+        #[derive(Default)]
+        struct Records(Vec<String>);
+
+        impl JournalSink for Records {
+            type Error = ();
+
+            fn append_entry(&mut self, entry: &JournalEntry) -> Result<(), ()> {
+                self.0.push(format!("entry {}", entry.tick()));
+                Ok(())
+            }
+
+            fn append_snapshot(&mut self, snapshot: &Snapshot) -> Result<(), ()> {
+                self.0.push(format!("snapshot {}", snapshot.tick));
+                Ok(())
+            }
+        }
+
+        let mut journal = Journal::new();
+        journal.start_recording();
+        journal.record_message(0, Msg::tick(0));
+        let mut sink = Records::default();
+        let synced = journal.sync_to(&mut sink, 0).unwrap();
+
+        journal.take_snapshot(&Model::new());
+        let synced = journal.sync_to(&mut sink, synced).unwrap();
+        assert_eq!(synced, journal.entries().len());
+        assert_eq!(sink.0, vec!["entry 0", "entry 0", "snapshot 0", "entry 0"]);
+    }
+
+    #[test]
+    fn test_journal_from_parts() {
+        let mut journal = Journal::new();
+        journal.start_recording();
+        journal.record_message(1, Msg::tick(1));
+        journal.record_message(1, Msg::tick(1));
+        journal.take_snapshot(&Model::new());
+
+        let mut restored = Journal::from_parts(
+            JournalConfig {
+                recording_enabled: true,
+                ..Default::default()
+            },
+            journal.entries().to_vec(),
+            journal.snapshots().to_vec(),
+        );
+        restored.record_message(1, Msg::tick(1));
+
+        // Recording continues in the same tick with the next sequence number
+        assert!(matches!(
+            restored.entries().last(),
+            Some(JournalEntry::Message { seq: 2, .. })
+        ));
+        assert_eq!(restored.take_snapshot(&Model::new()), SnapshotId(1));
+    }
+
     #[test]
     fn test_journal_snapshot() {
         let mut journal = Journal::new();
@@ -574,6 +975,73 @@ mod tests {
         assert!(journal.entries().len() <= 5);
     }
 
+    /// Record `ticks` ticks of one message each, snapshotting every 5th tick
+    fn record_with_snapshots(config: JournalConfig, ticks: Tick) -> Journal {
+        let mut journal = Journal::with_config(JournalConfig {
+            recording_enabled: true,
+            max_snapshots: 0,
+            ..config
+        });
+        let mut model = Model::new();
+        for tick in 1..=ticks {
+            model.advance_tick();
+            journal.record_message(tick, Msg::tick(tick));
+            if tick % 5 == 0 {
+                journal.take_snapshot(&model);
+            }
+        }
+        journal
+    }
+
+    #[test]
+    fn test_max_ticks_retention() {
+        let journal = record_with_snapshots(
+            JournalConfig {
+                max_ticks: 7,
+                ..Default::default()
+            },
+            20,
+        );
+
+        // Window is ticks 14..=20; the snapshot at 10 is kept to replay it
+        assert_eq!(journal.entries().first().unwrap().tick(), 10);
+        assert_eq!(journal.snapshot_at_or_before(14).unwrap().tick, 10);
+        assert_eq!(journal.messages().next().unwrap().0, 10);
+    }
+
+    #[test]
+    fn test_compact_keeps_snapshots() {
+        let mut journal = record_with_snapshots(
+            JournalConfig {
+                compact_after_ticks: 8,
+                ..Default::default()
+            },
+            20,
+        );
+
+        // Compacted when the snapshot at 20 was taken: cutoff 12, base 10
+        assert_eq!(journal.compaction_cutoff(), Some(12));
+        assert!(journal.messages().all(|(tick, _)| tick > 10));
+        let snapshot_ticks: Vec<_> = journal
+            .entries()
+            .iter()
+            .filter(|e| matches!(e, JournalEntry::Snapshot { .. }))
+            .map(JournalEntry::tick)
+            .collect();
+        assert_eq!(snapshot_ticks, vec![5, 10, 15, 20]);
+
+        // A snapshot at the cutoff lets compaction go all the way up to it
+        let mut model = Model::new();
+        while model.current_tick() < 12 {
+            model.advance_tick();
+        }
+        journal.insert_snapshot(model);
+        // Tick boundaries and messages of ticks 11 and 12
+        assert_eq!(journal.compact(), 4);
+        assert!(journal.messages().all(|(tick, _)| tick > 12));
+        assert_eq!(journal.snapshot_at_or_before(14).unwrap().tick, 12);
+    }
+
     #[test]
     fn test_metadata_recording() {
         let mut journal = Journal::new();
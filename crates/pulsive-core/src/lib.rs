@@ -1,11 +1,27 @@
 //! Pulsive Core - Reactive engine with Elm-style architecture
 //!
 //! This crate provides the core types and runtime for the pulsive engine:
-//! - Dynamic value types (`Value`, `ValueMap`)
-//! - Entity and definition identifiers
-//! - Expression engine for conditions and effects
-//! - Tick-based time and deterministic RNG
-//! - Elm-style runtime with Model, Msg, and Cmd
+//! - Dynamic value types (`Value`, `ValueMap`), keyed by interned property
+//!   names (`Symbol`)
+//! - Entity and definition identifiers, with generational entity IDs
+//! - Expression engine for conditions and effects, with a text syntax, and
+//!   handler conditions compiled at registration
+//! - Value curves for tuning non-linear relationships in data
+//! - Tick-based time and deterministic RNG, with deterministic execution
+//!   order (see [`determinism`])
+//! - Property schemas checked at write time
+//! - Computed properties derived from other state
+//! - Referential integrity of entity references on destroy
+//! - Resources and currencies with capacities, per-tick income and upkeep,
+//!   and overflow policies
+//! - Elm-style runtime with Model, Msg, and Cmd, with opt-in per-handler
+//!   profiling, a depth limit on event cascades and a dead-letter queue of
+//!   unhandled messages
+//! - Speculative evaluation of effects against model snapshots
+//! - Reusable effect templates with parameters
+//! - Per-entity and global time scales for tick handlers (slow motion, stasis)
+//! - Structured log records sent to a pluggable log sink (in-memory buffer,
+//!   `tracing` with the `tracing` feature)
 //!
 //! ## Generic Reactive Concepts
 //!
@@ -25,18 +41,37 @@
 
 mod actor;
 mod cmd;
+mod compile;
+mod computed;
+mod curve;
+pub mod determinism;
+mod diff;
 pub mod effect;
 mod entity;
 mod error;
 mod expr;
+mod expr_parser;
+mod group;
 mod identity;
+mod log;
 mod model;
 mod msg;
+mod pacing;
+mod profile;
+mod provenance;
+mod reference;
+mod resource;
 mod rng;
 pub mod runtime;
+mod schema;
+mod speculate;
 pub mod state_history;
+mod symbol;
+mod template;
 pub mod time;
+mod time_scale;
 mod value;
+mod value_map;
 pub mod write_set;
 
 #[cfg(feature = "journal")]
@@ -44,22 +79,51 @@ pub mod journal;
 
 pub use actor::{ActorId, Command, Context};
 pub use cmd::Cmd;
+pub use compile::CompiledExpr;
+pub use computed::ComputedProperty;
+pub use curve::{Curve, Interpolation};
+pub use diff::ModelDiff;
 pub use effect::{Effect, EffectResult, ModifyOp};
 pub use entity::{Entity, EntityRef, EntityStore};
 pub use error::{Error, Result};
 pub use expr::{EvalContext, Expr};
+pub use expr_parser::{ParseError, EXPR_FUNCTIONS};
+pub use group::{HandlerGroup, HandlerGroups};
 pub use identity::{DefId, EntityId};
+#[cfg(feature = "tracing")]
+pub use log::TracingSink;
+pub use log::{LogBuffer, LogRecord, LogSink};
 pub use model::Model;
-pub use msg::{Msg, MsgKind};
+pub use msg::{Msg, MsgId, MsgKind, Priority};
+pub use pacing::CatchUp;
+pub use profile::HandlerStats;
+pub use provenance::{EffectTrace, HandlerId, HandlerTrace};
+pub use reference::{ReferencePolicy, ReferenceRule, ReferenceRules};
+pub use resource::{OverflowPolicy, Resource, RESOURCE_OVERFLOW, RESOURCE_UNDERFLOW};
 pub use rng::Rng;
-pub use runtime::{EventHandler, Runtime, TickHandler, UpdateResult};
-pub use state_history::{StateHistory, StateInterpolation};
+pub use runtime::{
+    BackgroundBudget, CascadeLimit, CascadeLimitExceeded, EventHandler, PhaseHook, Runtime,
+    ScheduleHandle, ScheduledMsg, TickHandler, TickPhase, UnhandledHook, UpdateResult,
+    CASCADE_LIMIT_EXCEEDED,
+};
+pub use schema::{PropertySchema, PropertySchemas, SchemaPolicy, SchemaViolation, ValueType};
+pub use speculate::{Speculation, SpeculativeView};
+pub use state_history::{SharedHistory, StateHistory, StateInterpolation};
+pub use symbol::{AsSymbol, Symbol, MAX_NAME_LEN, MAX_SYMBOLS};
+pub use template::{EffectTemplate, EffectTemplates};
 pub use time::{Clock, Speed, Tick, Timestamp};
-pub use value::{Value, ValueMap};
-pub use write_set::{PendingWrite, WriteSet, WriteSetResult};
+pub use time_scale::{TIME_ACCUMULATOR, TIME_SCALE};
+pub use value::Value;
+pub use value_map::ValueMap;
+pub use write_set::{
+    PendingWrite, SkippedWrite, WriteApplyReport, WriteFailure, WriteSet, WriteSetResult,
+};
 
 // Re-export indexmap for consumers that need it with actor maps
 pub use indexmap::IndexMap;
 
 #[cfg(feature = "journal")]
-pub use journal::{Journal, JournalConfig, JournalEntry, JournalStats, Snapshot, SnapshotId};
+pub use journal::{
+    Journal, JournalConfig, JournalEntry, JournalSink, JournalStats, RngDivergence, Snapshot,
+    SnapshotId,
+};
@@ -0,0 +1,186 @@
+//! Structured log records and log sinks
+//!
+//! `Log` effects and evaluation errors are recorded as [`LogRecord`]s in
+//! [`EffectResult::logs`](crate::EffectResult::logs), tagged with the tick,
+//! the handler and target they ran for and the handler's parameters, so
+//! tools can filter them rather than parse strings. A [`LogSink`] set on
+//! the [`Runtime`](crate::Runtime) receives every record as its message is
+//! processed:
+//!
+//! ```rust,ignore
+//! let buffer = Arc::new(LogBuffer::new(256));
+//! runtime.set_log_sink(Some(buffer.clone()));
+//! runtime.tick(&mut model);
+//!
+//! for record in buffer.records().iter().filter(|r| r.level >= LogLevel::Warn) {
+//!     eprintln!("{}", record);
+//! }
+//! ```
+//!
+//! [`LogBuffer`] keeps the latest records in memory; with the `tracing`
+//! feature, [`TracingSink`] forwards them as `tracing` events.
+
+use crate::effect::LogLevel;
+use crate::{EntityRef, HandlerId, Tick, ValueMap};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Mutex;
+
+/// A structured log record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogRecord {
+    /// Severity
+    pub level: LogLevel,
+    /// Tick the record was logged at
+    pub tick: Tick,
+    /// Handler whose effects logged it (None outside handlers)
+    pub handler: Option<HandlerId>,
+    /// Target the effects ran on
+    pub target: EntityRef,
+    /// Message text
+    pub message: String,
+    /// Parameters of the message being handled
+    pub params: ValueMap,
+}
+
+impl LogRecord {
+    /// Create a record at tick 0 with no handler, target or parameters
+    ///
+    /// The runtime fills in what it knows as the record leaves the handler
+    /// and the message being processed.
+    pub fn new(level: LogLevel, message: impl Into<String>) -> Self {
+        Self {
+            level,
+            tick: 0,
+            handler: None,
+            target: EntityRef::None,
+            message: message.into(),
+            params: ValueMap::new(),
+        }
+    }
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {:?}", self.tick, self.level)?;
+        if let Some(handler) = &self.handler {
+            write!(f, " {}", handler)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Receives the log records of processed messages
+pub trait LogSink: Send + Sync {
+    /// Handle a record
+    fn log(&self, record: &LogRecord);
+}
+
+/// A sink keeping the latest records in memory
+#[derive(Debug)]
+pub struct LogBuffer {
+    capacity: usize,
+    records: Mutex<VecDeque<LogRecord>>,
+}
+
+impl LogBuffer {
+    /// Create a buffer keeping at most `capacity` records, dropping the
+    /// oldest
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            records: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Get a copy of the records, oldest first
+    pub fn records(&self) -> Vec<LogRecord> {
+        self.lock().iter().cloned().collect()
+    }
+
+    /// Take the records, oldest first, leaving none
+    pub fn take(&self) -> Vec<LogRecord> {
+        self.lock().drain(..).collect()
+    }
+
+    /// Get the number of records kept
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Check if no records are kept
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Get the most records kept
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<LogRecord>> {
+        // A panic while holding the lock leaves the records usable
+        self.records.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl LogSink for LogBuffer {
+    fn log(&self, record: &LogRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut records = self.lock();
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record.clone());
+    }
+}
+
+/// A sink forwarding records as `tracing` events, with target `pulsive`
+#[cfg(feature = "tracing")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingSink;
+
+#[cfg(feature = "tracing")]
+impl LogSink for TracingSink {
+    fn log(&self, record: &LogRecord) {
+        let handler = record.handler.as_ref().map(|h| h.to_string());
+        macro_rules! emit {
+            ($macro:ident) => {
+                tracing::$macro!(
+                    target: "pulsive",
+                    tick = record.tick,
+                    handler = handler.as_deref(),
+                    entity = ?record.target,
+                    "{}",
+                    record.message
+                )
+            };
+        }
+        match record.level {
+            LogLevel::Debug => emit!(debug),
+            LogLevel::Info => emit!(info),
+            LogLevel::Warn => emit!(warn),
+            LogLevel::Error => emit!(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_buffer() {
+        let buffer = LogBuffer::new(2);
+        for message in ["one", "two", "three"] {
+            buffer.log(&LogRecord::new(LogLevel::Info, message));
+        }
+        let messages: Vec<String> = buffer.records().into_iter().map(|r| r.message).collect();
+        assert_eq!(messages, ["two", "three"]);
+        assert_eq!(buffer.take().len(), 2);
+        assert!(buffer.is_empty());
+    }
+}
@@ -3,7 +3,10 @@
 //! The Model uses `Arc` for structural sharing, enabling O(1) snapshot creation.
 //! Mutations use copy-on-write semantics via `Arc::make_mut()`.
 
-use crate::{ActorId, Clock, Context, EntityStore, Rng, Value, ValueMap};
+use crate::{
+    ActorId, AsSymbol, Clock, Context, EntityId, EntityStore, PropertySchemas, Rng,
+    SchemaViolation, Symbol, Value, ValueMap,
+};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -28,6 +31,10 @@ pub struct Model {
     pub rng: Rng,
     /// Actor contexts
     pub actors: IndexMap<ActorId, Context>,
+    /// Property schemas checked at write time (not saved with the model;
+    /// see [`Model::restore`])
+    #[serde(skip)]
+    schemas: Arc<PropertySchemas>,
 }
 
 // Custom serde for Arc<EntityStore>
@@ -79,6 +86,7 @@ impl Model {
             time: Clock::new(),
             rng: Rng::new(12345),
             actors: IndexMap::new(),
+            schemas: Arc::default(),
         }
     }
 
@@ -90,6 +98,7 @@ impl Model {
             time: Clock::new(),
             rng: Rng::new(seed),
             actors: IndexMap::new(),
+            schemas: Arc::default(),
         }
     }
 
@@ -110,9 +119,21 @@ impl Model {
             time,
             rng,
             actors,
+            schemas: Arc::default(),
         }
     }
 
+    /// Replace the state with another model's, keeping this model's
+    /// property schemas
+    ///
+    /// Schemas are not saved, so a loaded save, snapshot or server state
+    /// has none. Restore it into the running model rather than assigning it,
+    /// or writes after the load go unchecked.
+    pub fn restore(&mut self, state: Model) {
+        let schemas = std::mem::take(&mut self.schemas);
+        *self = Self { schemas, ..state };
+    }
+
     // ========================================================================
     // Entity Access
     // ========================================================================
@@ -156,15 +177,65 @@ impl Model {
     }
 
     /// Get a global property
-    pub fn get_global(&self, key: &str) -> Option<&Value> {
+    pub fn get_global<K: AsSymbol + ?Sized>(&self, key: &K) -> Option<&Value> {
         self.globals.get(key)
     }
 
     /// Set a global property (triggers copy-on-write if shared)
-    pub fn set_global(&mut self, key: impl Into<String>, value: impl Into<Value>) {
+    pub fn set_global(&mut self, key: impl Into<Symbol>, value: impl Into<Value>) {
         Arc::make_mut(&mut self.globals).insert(key.into(), value.into());
     }
 
+    // ========================================================================
+    // Property Schemas
+    // ========================================================================
+
+    /// Get the property schemas checked at write time
+    pub fn schemas(&self) -> &PropertySchemas {
+        &self.schemas
+    }
+
+    /// Get a mutable reference to the property schemas (copy-on-write)
+    pub fn schemas_mut(&mut self) -> &mut PropertySchemas {
+        Arc::make_mut(&mut self.schemas)
+    }
+
+    /// Replace the property schemas checked at write time
+    pub fn set_schemas(&mut self, schemas: PropertySchemas) {
+        self.schemas = Arc::new(schemas);
+    }
+
+    /// Check a write to an entity property against its schema
+    ///
+    /// Returns the value to write (`None` to skip the write) and the
+    /// violation, if any.
+    pub fn check_property(
+        &self,
+        entity_id: EntityId,
+        property: &str,
+        value: Value,
+    ) -> (Option<Value>, Option<SchemaViolation>) {
+        let schema = self
+            .entities
+            .get(entity_id)
+            .and_then(|entity| self.schemas.get(&entity.kind, property));
+        self.schemas
+            .resolve(schema, Some(entity_id), property, value)
+    }
+
+    /// Check a write to a global against its schema
+    ///
+    /// Returns the value to write (`None` to skip the write) and the
+    /// violation, if any.
+    pub fn check_global(
+        &self,
+        property: &str,
+        value: Value,
+    ) -> (Option<Value>, Option<SchemaViolation>) {
+        let schema = self.schemas.get_global(property);
+        self.schemas.resolve(schema, None, property, value)
+    }
+
     // ========================================================================
     // Actor Management
     // ========================================================================
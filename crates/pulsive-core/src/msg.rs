@@ -2,6 +2,51 @@
 
 use crate::{ActorId, DefId, EntityRef, ValueMap};
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// Unique identifier of a message
+///
+/// Messages created by the application get a fresh ID, unique across
+/// processes with overwhelming probability, so client and server logs of the
+/// same command match. Follow-up messages derive their ID from the message
+/// that caused them and their position among its follow-ups, so the same
+/// cascade gets the same IDs on every machine that runs it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct MsgId(pub u64);
+
+impl MsgId {
+    /// Generate a fresh ID
+    pub fn generate() -> Self {
+        static BASE: OnceLock<u64> = OnceLock::new();
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        // Randomly seeded per process, so processes draw different IDs
+        let base = *BASE.get_or_init(|| RandomState::new().build_hasher().finish());
+        Self(mix(base.wrapping_add(NEXT.fetch_add(1, Ordering::Relaxed))))
+    }
+
+    /// Derive the ID of the `n`th follow-up of this message
+    pub fn child(self, n: u64) -> Self {
+        Self(mix(self.0 ^ mix(n.wrapping_add(1))))
+    }
+}
+
+impl fmt::Display for MsgId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "msg:{:016x}", self.0)
+    }
+}
+
+/// SplitMix64 finalizer
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
 
 /// The kind of message
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -28,9 +73,42 @@ pub enum MsgKind {
     Custom(DefId),
 }
 
+/// Processing lane of a message
+///
+/// Within a lane messages run in the order they were sent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Priority {
+    /// Runs before any queued normal message
+    Immediate,
+    /// Runs in the order sent
+    #[default]
+    Normal,
+    /// Deferred to ticks with spare budget (cosmetic events, statistics);
+    /// see [`BackgroundBudget`](crate::BackgroundBudget)
+    Background,
+}
+
 /// A message in the reactive system
+///
+/// Every message has a unique [`MsgId`]. Messages emitted or scheduled while
+/// handling another one record it as their parent, share its correlation
+/// ID (the ID of the message that started the cascade) and are one level
+/// deeper in the cascade.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Msg {
+    /// Unique ID of this message
+    #[serde(default = "MsgId::generate")]
+    pub id: MsgId,
+    /// The message whose handling emitted or scheduled this one
+    #[serde(default)]
+    pub parent: Option<MsgId>,
+    /// The message that started the cascade this one belongs to
+    #[serde(default)]
+    pub correlation: Option<MsgId>,
+    /// How many messages lead from the start of the cascade to this one (0
+    /// for the message starting it)
+    #[serde(default)]
+    pub depth: u32,
     /// The kind of message
     pub kind: MsgKind,
     /// Event or action ID (if applicable)
@@ -43,42 +121,60 @@ pub struct Msg {
     pub actor: Option<ActorId>,
     /// The tick when this message was created
     pub tick: u64,
+    /// Processing lane
+    #[serde(default)]
+    pub priority: Priority,
 }
 
 impl Msg {
     /// Create a new message
     pub fn new(kind: MsgKind) -> Self {
         Self {
+            id: MsgId::generate(),
+            parent: None,
+            correlation: None,
+            depth: 0,
             kind,
             event_id: None,
             target: EntityRef::None,
             params: ValueMap::new(),
             actor: None,
             tick: 0,
+            priority: Priority::Normal,
         }
     }
 
     /// Create a tick message
     pub fn tick(tick: u64) -> Self {
         Self {
+            id: MsgId::generate(),
+            parent: None,
+            correlation: None,
+            depth: 0,
             kind: MsgKind::Tick,
             event_id: None,
             target: EntityRef::None,
             params: ValueMap::new(),
             actor: None,
             tick,
+            priority: Priority::Normal,
         }
     }
 
     /// Create an event message
     pub fn event(event_id: impl Into<DefId>, target: EntityRef, tick: u64) -> Self {
         Self {
+            id: MsgId::generate(),
+            parent: None,
+            correlation: None,
+            depth: 0,
             kind: MsgKind::Event,
             event_id: Some(event_id.into()),
             target,
             params: ValueMap::new(),
             actor: None,
             tick,
+            priority: Priority::Normal,
         }
     }
 
@@ -90,12 +186,17 @@ impl Msg {
         tick: u64,
     ) -> Self {
         Self {
+            id: MsgId::generate(),
+            parent: None,
+            correlation: None,
+            depth: 0,
             kind: MsgKind::Command,
             event_id: Some(action_id.into()),
             target,
             params: ValueMap::new(),
             actor: Some(actor),
             tick,
+            priority: Priority::Normal,
         }
     }
 
@@ -128,6 +229,36 @@ impl Msg {
         self.tick = tick;
         self
     }
+
+    /// Set the processing lane
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Set the correlation ID
+    pub fn with_correlation(mut self, correlation: MsgId) -> Self {
+        self.correlation = Some(correlation);
+        self
+    }
+
+    /// Make this the `n`th follow-up of another message
+    ///
+    /// Derives the ID from the parent's, records the parent and inherits its
+    /// correlation ID, one level deeper.
+    pub fn follow_up_of(mut self, parent: &Msg, n: u64) -> Self {
+        self.id = parent.id.child(n);
+        self.parent = Some(parent.id);
+        self.correlation = Some(parent.correlation_id());
+        self.depth = parent.depth + 1;
+        self
+    }
+
+    /// Get the ID of the message that started this message's cascade (its
+    /// own ID if it started one)
+    pub fn correlation_id(&self) -> MsgId {
+        self.correlation.unwrap_or(self.id)
+    }
 }
 
 #[cfg(test)]
@@ -150,4 +281,37 @@ mod tests {
         assert_eq!(msg.event_id, Some(DefId::new("peasant_uprising")));
         assert!(msg.params.contains_key("severity"));
     }
+
+    #[test]
+    fn test_msg_ids() {
+        let attack = Msg::event("attack", EntityRef::Global, 1);
+        let other = Msg::event("attack", EntityRef::Global, 1);
+        assert_ne!(attack.id, other.id);
+        assert_eq!(attack.correlation_id(), attack.id);
+
+        // Follow-ups derive their IDs, and keep the cascade's correlation ID
+        let damaged = Msg::event("damaged", EntityRef::Global, 1).follow_up_of(&attack, 0);
+        let died = Msg::event("died", EntityRef::Global, 1).follow_up_of(&damaged, 0);
+        assert_eq!(damaged.id, attack.id.child(0));
+        assert_ne!(damaged.id, attack.id.child(1));
+        assert_eq!(died.parent, Some(damaged.id));
+        assert_eq!(died.correlation_id(), attack.id);
+        assert_eq!((attack.depth, died.depth), (0, 2));
+
+        // IDs survive serialization
+        let restored: Msg = ron::from_str(&ron::to_string(&died).unwrap()).unwrap();
+        assert_eq!(
+            (restored.id, restored.parent, restored.correlation),
+            (died.id, died.parent, died.correlation)
+        );
+    }
+
+    #[test]
+    fn test_msg_priority() {
+        let msg = Msg::tick(1);
+        assert_eq!(msg.priority, Priority::Normal);
+
+        let msg = Msg::event("fireworks", EntityRef::Global, 1).with_priority(Priority::Background);
+        assert_eq!(msg.priority, Priority::Background);
+    }
 }
@@ -0,0 +1,128 @@
+//! Real-time pacing of ticks
+//!
+//! A real-time host calls [`Runtime::advance`](crate::Runtime::advance)
+//! every frame with the time elapsed since the last one; the runtime runs
+//! the ticks due at the clock's [`Speed`](crate::Speed). When the host falls
+//! behind (a slow frame, a hiccup, a window that lost focus) the
+//! [`CatchUp`] policy decides what happens to the missed ticks, so the
+//! simulation never spirals into running ever more ticks per frame.
+//!
+//! Pausing the runtime stops ticks and discards the time elapsed while
+//! paused: resuming continues from where it stopped rather than catching up.
+
+use std::time::Duration;
+
+/// What to do with ticks missed while the host fell behind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatchUp {
+    /// Run at most one tick per advance and drop the other missed ticks;
+    /// the simulation loses time but stays on schedule
+    SkipMissed,
+    /// Run missed ticks back to back, at most this many per advance, and
+    /// drop any beyond
+    FastForwardCapped(u32),
+    /// Run at most one tick per advance and drop nothing; the simulation
+    /// slows down while the host is behind and catches up once it keeps up
+    SlowMotion,
+}
+
+impl Default for CatchUp {
+    fn default() -> Self {
+        CatchUp::FastForwardCapped(8)
+    }
+}
+
+/// Wall-clock time owed to the simulation, converted into ticks
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Pacing {
+    pub(crate) catch_up: CatchUp,
+    pub(crate) paused: bool,
+    /// Elapsed time not yet consumed by ticks
+    backlog: Duration,
+}
+
+impl Pacing {
+    /// Pause, discarding the backlog
+    pub(crate) fn pause(&mut self) {
+        self.paused = true;
+        self.backlog = Duration::ZERO;
+    }
+
+    /// Resume from where the pacing was paused
+    pub(crate) fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Add elapsed time and take the number of ticks to run now, one per
+    /// interval (none while paused or without an interval)
+    pub(crate) fn due(&mut self, elapsed: Duration, interval: Option<Duration>) -> u32 {
+        let interval = match interval {
+            Some(interval) if !self.paused && !interval.is_zero() => interval,
+            _ => {
+                self.backlog = Duration::ZERO;
+                return 0;
+            }
+        };
+        self.backlog += elapsed;
+        let missed =
+            u32::try_from(self.backlog.as_nanos() / interval.as_nanos()).unwrap_or(u32::MAX);
+        let (ticks, kept) = match self.catch_up {
+            CatchUp::SkipMissed => (missed.min(1), missed.min(1)),
+            CatchUp::FastForwardCapped(max) => (missed.min(max), missed.min(max)),
+            CatchUp::SlowMotion => (missed.min(1), missed),
+        };
+        // Consume the ticks run and drop the missed ones not kept
+        self.backlog -= interval * (ticks + missed - kept);
+        ticks
+    }
+
+    /// Get how far the backlog is into the next interval (0 to 1)
+    pub(crate) fn alpha(&self, interval: Option<Duration>) -> f64 {
+        match interval {
+            Some(interval) if !interval.is_zero() => {
+                (self.backlog.as_secs_f64() / interval.as_secs_f64()).min(1.0)
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catch_up() {
+        let interval = Some(Duration::from_millis(100));
+        let ms = Duration::from_millis;
+
+        let mut pacing = Pacing {
+            catch_up: CatchUp::FastForwardCapped(3),
+            ..Default::default()
+        };
+        assert_eq!(pacing.due(ms(50), interval), 0);
+        assert_eq!(pacing.due(ms(160), interval), 2);
+        // A hiccup runs at most 3 ticks and drops the rest
+        assert_eq!(pacing.due(ms(1000), interval), 3);
+        assert_eq!(pacing.due(ms(0), interval), 0);
+        assert_eq!(pacing.due(ms(90), interval), 1);
+
+        pacing.catch_up = CatchUp::SkipMissed;
+        assert_eq!(pacing.due(ms(550), interval), 1);
+        assert_eq!(pacing.due(ms(50), interval), 1);
+
+        pacing.catch_up = CatchUp::SlowMotion;
+        assert_eq!(pacing.due(ms(300), interval), 1);
+        assert_eq!(pacing.due(ms(0), interval), 1);
+        assert_eq!(pacing.due(ms(0), interval), 1);
+        assert_eq!(pacing.due(ms(0), interval), 0);
+
+        // Time elapsed while paused is never caught up
+        pacing.pause();
+        assert_eq!(pacing.due(ms(5000), interval), 0);
+        pacing.resume();
+        assert_eq!(pacing.due(ms(100), interval), 1);
+        assert_eq!(pacing.due(ms(1000), None), 0);
+        assert_eq!(pacing.due(ms(50), interval), 0);
+    }
+}
@@ -0,0 +1,71 @@
+//! Provenance of model changes
+//!
+//! With causality tracing enabled, the runtime records which handlers
+//! processed each message, which of their effects produced which writes,
+//! and which events each effect emitted or scheduled. Journals store these
+//! traces next to the messages so cascading events can be followed back to
+//! the message that started them.
+
+use crate::{DefId, EntityRef, ValueMap, WriteSet};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Identifies the handler that processed a message
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum HandlerId {
+    /// A tick handler, by its ID
+    Tick(DefId),
+    /// An event handler, by event and position among that event's handlers
+    /// (in priority order)
+    Event {
+        /// The event the handler responds to
+        event_id: DefId,
+        /// Position among the handlers for `event_id`
+        index: usize,
+    },
+}
+
+impl fmt::Display for HandlerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandlerId::Tick(id) => write!(f, "tick:{}", id),
+            HandlerId::Event { event_id, index } => write!(f, "event:{}#{}", event_id, index),
+        }
+    }
+}
+
+/// A handler run triggered by a message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandlerTrace {
+    /// The handler that ran
+    pub handler: HandlerId,
+    /// The target the handler's effects ran against
+    pub target: EntityRef,
+    /// Trace of each top-level effect of the handler
+    pub effects: Vec<EffectTrace>,
+}
+
+impl HandlerTrace {
+    /// Iterate over all events emitted by the handler's effects
+    pub fn emitted_events(&self) -> impl Iterator<Item = &(DefId, EntityRef, ValueMap)> {
+        self.effects.iter().flat_map(|e| e.emitted.iter())
+    }
+
+    /// Count the writes made by the handler's effects
+    pub fn write_count(&self) -> usize {
+        self.effects.iter().map(|e| e.writes.len()).sum()
+    }
+}
+
+/// What a single top-level effect of a handler did
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EffectTrace {
+    /// Index of the effect in the handler's effect list
+    pub index: usize,
+    /// Writes applied by the effect (including nested effects)
+    pub writes: WriteSet,
+    /// Events emitted by the effect (event, target, params)
+    pub emitted: Vec<(DefId, EntityRef, ValueMap)>,
+    /// Events scheduled by the effect (event, target, delay, params)
+    pub scheduled: Vec<(DefId, EntityRef, u64, ValueMap)>,
+}
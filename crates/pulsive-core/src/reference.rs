@@ -0,0 +1,219 @@
+//! Referential integrity of entity references
+//!
+//! Destroying an entity leaves [`Value::EntityRef`] properties on other
+//! entities pointing at it. Declaring those properties as references makes
+//! the runtime clean them up when the referenced entity is destroyed, with a
+//! [`ReferencePolicy`] per property:
+//!
+//! ```rust,ignore
+//! runtime.register_reference("unit", "owner", ReferencePolicy::CascadeDestroy);
+//! runtime.register_reference("unit", "target", ReferencePolicy::Nullify);
+//! runtime.register_reference("city", "governor", ReferencePolicy::Restrict);
+//! ```
+//!
+//! Undeclared properties are left alone, and with no references declared
+//! destroying an entity costs nothing extra.
+
+use crate::{DefId, Entity, EntityId, EntityStore, SchemaViolation, Value};
+use std::collections::BTreeSet;
+
+/// What happens to a reference when the entity it points at is destroyed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReferencePolicy {
+    /// Set the property to null
+    Nullify,
+    /// Destroy the referencing entity as well
+    CascadeDestroy,
+    /// Refuse to destroy the referenced entity
+    Restrict,
+}
+
+/// A property of an entity kind declared as a reference
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferenceRule {
+    /// Entity kind the property belongs to
+    pub kind: DefId,
+    /// Property holding the reference
+    pub property: String,
+    /// What happens when the referenced entity is destroyed
+    pub policy: ReferencePolicy,
+}
+
+/// Declared reference properties, in registration order
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceRules {
+    rules: Vec<ReferenceRule>,
+}
+
+/// The entities a destroy removes and the references it clears
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct DestroyPlan {
+    /// Entities to destroy, the requested one first
+    pub destroyed: Vec<EntityId>,
+    /// References to set to null, as (entity, property)
+    pub nullified: Vec<(EntityId, String)>,
+}
+
+impl ReferenceRules {
+    /// Create an empty set of rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a property of an entity kind as a reference, replacing any
+    /// rule for the same property
+    pub fn register(
+        &mut self,
+        kind: impl Into<DefId>,
+        property: impl Into<String>,
+        policy: ReferencePolicy,
+    ) {
+        let rule = ReferenceRule {
+            kind: kind.into(),
+            property: property.into(),
+            policy,
+        };
+        self.remove(&rule.kind, &rule.property);
+        self.rules.push(rule);
+    }
+
+    /// Remove a rule, returning whether one was registered
+    pub fn remove(&mut self, kind: &DefId, property: &str) -> bool {
+        let len = self.rules.len();
+        self.rules
+            .retain(|r| !(&r.kind == kind && r.property == property));
+        self.rules.len() != len
+    }
+
+    /// Get the policy of a reference property
+    pub fn get(&self, kind: &DefId, property: &str) -> Option<ReferencePolicy> {
+        self.rules
+            .iter()
+            .find(|r| &r.kind == kind && r.property == property)
+            .map(|r| r.policy)
+    }
+
+    /// Iterate over the rules in registration order
+    pub fn iter(&self) -> impl Iterator<Item = &ReferenceRule> {
+        self.rules.iter()
+    }
+
+    /// Check whether no reference is declared
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Work out what destroying an entity does to the references to it
+    ///
+    /// Cascades are followed until no more entities are destroyed. Returns
+    /// the violation of the first restricted reference to a destroyed entity
+    /// from a surviving one, in which case nothing should be destroyed.
+    pub(crate) fn plan_destroy(
+        &self,
+        entities: &EntityStore,
+        id: EntityId,
+    ) -> Result<DestroyPlan, Box<SchemaViolation>> {
+        let mut plan = DestroyPlan {
+            destroyed: vec![id],
+            nullified: Vec::new(),
+        };
+        if self.rules.is_empty() {
+            return Ok(plan);
+        }
+
+        let mut destroyed = BTreeSet::from([id]);
+        loop {
+            let cascaded: Vec<EntityId> = entities
+                .iter()
+                .filter(|entity| !destroyed.contains(&entity.id))
+                .filter(|entity| {
+                    self.references(entity, &destroyed)
+                        .any(|(rule, _)| rule.policy == ReferencePolicy::CascadeDestroy)
+                })
+                .map(|entity| entity.id)
+                .collect();
+            if cascaded.is_empty() {
+                break;
+            }
+            destroyed.extend(cascaded.iter().copied());
+            plan.destroyed.extend(cascaded);
+        }
+
+        for entity in entities.iter() {
+            if destroyed.contains(&entity.id) {
+                continue;
+            }
+            for (rule, referenced) in self.references(entity, &destroyed) {
+                match rule.policy {
+                    ReferencePolicy::Nullify => {
+                        plan.nullified.push((entity.id, rule.property.clone()));
+                    }
+                    ReferencePolicy::Restrict => {
+                        return Err(Box::new(SchemaViolation {
+                            entity_id: Some(entity.id),
+                            property: rule.property.clone(),
+                            value: Value::EntityRef(referenced),
+                            reason: "restricted reference".to_string(),
+                            applied: None,
+                        }));
+                    }
+                    ReferencePolicy::CascadeDestroy => {}
+                }
+            }
+        }
+        Ok(plan)
+    }
+
+    /// The rules of an entity whose property points at a destroyed entity,
+    /// with the referenced entity
+    fn references<'a>(
+        &'a self,
+        entity: &'a Entity,
+        destroyed: &'a BTreeSet<EntityId>,
+    ) -> impl Iterator<Item = (&'a ReferenceRule, EntityId)> + 'a {
+        self.rules
+            .iter()
+            .filter(move |rule| rule.kind == entity.kind)
+            .filter_map(move |rule| match entity.get(&rule.property) {
+                Some(Value::EntityRef(referenced)) if destroyed.contains(referenced) => {
+                    Some((rule, *referenced))
+                }
+                _ => None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_destroy() {
+        let mut entities = EntityStore::new();
+        let nation = entities.create("nation").id;
+        let army = entities.create("army").id;
+        entities.get_mut(army).unwrap().set("owner", nation);
+        let general = entities.create("general").id;
+        entities.get_mut(general).unwrap().set("army", army);
+        let spy = entities.create("spy").id;
+        entities.get_mut(spy).unwrap().set("watching", army);
+
+        let mut rules = ReferenceRules::new();
+        rules.register("army", "owner", ReferencePolicy::CascadeDestroy);
+        rules.register("general", "army", ReferencePolicy::CascadeDestroy);
+        rules.register("spy", "watching", ReferencePolicy::Nullify);
+
+        let plan = rules.plan_destroy(&entities, nation).unwrap();
+        assert_eq!(plan.destroyed, vec![nation, army, general]);
+        assert_eq!(plan.nullified, vec![(spy, "watching".to_string())]);
+
+        rules.register("spy", "watching", ReferencePolicy::Restrict);
+        let violation = rules.plan_destroy(&entities, nation).unwrap_err();
+        assert_eq!(violation.entity_id, Some(spy));
+        assert_eq!(violation.value, Value::EntityRef(army));
+        assert_eq!(
+            rules.plan_destroy(&entities, spy).unwrap().destroyed,
+            vec![spy]
+        );
+    }
+}
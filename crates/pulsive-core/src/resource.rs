@@ -0,0 +1,227 @@
+//! Resources and currencies with capacities and per-tick flows
+//!
+//! A [`Resource`] registered on the [`Runtime`](crate::Runtime) is held as
+//! a numeric property named after it, by every entity of its holder kind,
+//! or as a global when it has none. Each tick, before tick handlers run,
+//! every holder gains its income and pays its upkeep, and the holding is
+//! brought back within its floor and capacity:
+//!
+//! ```rust,ignore
+//! runtime.register_resource(
+//!     Resource::new("grain")
+//!         .with_holder("city")
+//!         .with_capacity("granaries * 100".parse()?)
+//!         .with_income("farms * 2".parse()?)
+//!         .with_upkeep("population * 0.5".parse()?)
+//!         .with_overflow(OverflowPolicy::Spill),
+//! );
+//! ```
+//!
+//! Income and upkeep are lists of sources, summed per holder; `income(id)`
+//! and `upkeep(id)` ([`Expr::Income`], [`Expr::Upkeep`]) read the totals in
+//! expressions. Writes by effects are not checked against the bounds; the
+//! next tick's flow brings the holding back within them.
+
+use crate::{DefId, EvalContext, Expr, Result};
+use serde::{Deserialize, Serialize};
+
+/// Event emitted by [`OverflowPolicy::Spill`] when a holding passes its
+/// capacity
+pub const RESOURCE_OVERFLOW: &str = "resource_overflow";
+
+/// Event emitted by [`OverflowPolicy::Spill`] when a holding falls below
+/// its floor
+pub const RESOURCE_UNDERFLOW: &str = "resource_underflow";
+
+/// What happens to a holding that passes one of its bounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum OverflowPolicy {
+    /// Keep the holding at the bound, losing the difference
+    #[default]
+    Clamp,
+    /// Keep the holding at the bound and emit [`RESOURCE_OVERFLOW`] or
+    /// [`RESOURCE_UNDERFLOW`] at the holder, with the `resource` and the
+    /// `amount` past the bound as parameters
+    Spill,
+    /// Let the holding pass the bound (debt, below the floor)
+    Allow,
+}
+
+/// A resource or currency held by entities of a kind, or globally
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Resource {
+    /// Resource ID, also the property or global holding it
+    pub id: DefId,
+    /// Kind of the entities holding it (None for a global)
+    pub holder: Option<DefId>,
+    /// Most a holder can keep, evaluated per holder (None for no cap)
+    pub capacity: Option<Expr>,
+    /// Least a holder can keep
+    pub floor: f64,
+    /// Sources of income per tick, evaluated per holder and summed
+    pub income: Vec<Expr>,
+    /// Sources of upkeep per tick, evaluated per holder and summed
+    pub upkeep: Vec<Expr>,
+    /// What happens past the capacity
+    pub on_overflow: OverflowPolicy,
+    /// What happens below the floor
+    pub on_underflow: OverflowPolicy,
+}
+
+impl Resource {
+    /// Create a global resource with a floor of 0, no cap and no flows
+    pub fn new(id: impl Into<DefId>) -> Self {
+        Self {
+            id: id.into(),
+            holder: None,
+            capacity: None,
+            floor: 0.0,
+            income: Vec::new(),
+            upkeep: Vec::new(),
+            on_overflow: OverflowPolicy::Clamp,
+            on_underflow: OverflowPolicy::Clamp,
+        }
+    }
+
+    /// Hold the resource on every entity of a kind
+    pub fn with_holder(mut self, kind: impl Into<DefId>) -> Self {
+        self.holder = Some(kind.into());
+        self
+    }
+
+    /// Set the capacity
+    pub fn with_capacity(mut self, capacity: Expr) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Set the floor
+    pub fn with_floor(mut self, floor: f64) -> Self {
+        self.floor = floor;
+        self
+    }
+
+    /// Add a source of income
+    pub fn with_income(mut self, income: Expr) -> Self {
+        self.income.push(income);
+        self
+    }
+
+    /// Add a source of upkeep
+    pub fn with_upkeep(mut self, upkeep: Expr) -> Self {
+        self.upkeep.push(upkeep);
+        self
+    }
+
+    /// Set what happens past the capacity
+    pub fn with_overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.on_overflow = policy;
+        self
+    }
+
+    /// Set what happens below the floor
+    pub fn with_underflow(mut self, policy: OverflowPolicy) -> Self {
+        self.on_underflow = policy;
+        self
+    }
+
+    /// Evaluate the total income of the context's holder
+    pub fn eval_income(&self, ctx: &mut EvalContext) -> Result<f64> {
+        sum(&self.income, ctx)
+    }
+
+    /// Evaluate the total upkeep of the context's holder
+    pub fn eval_upkeep(&self, ctx: &mut EvalContext) -> Result<f64> {
+        sum(&self.upkeep, ctx)
+    }
+
+    /// Evaluate the capacity of the context's holder
+    pub fn eval_capacity(&self, ctx: &mut EvalContext) -> Result<Option<f64>> {
+        self.capacity
+            .as_ref()
+            .map(|capacity| number(capacity, ctx))
+            .transpose()
+    }
+
+    /// Apply a tick of flows to a holding
+    ///
+    /// Returns the new holding and, for a spilling policy, the event to emit
+    /// with the amount past the bound.
+    pub(crate) fn flow(
+        &self,
+        holding: f64,
+        ctx: &mut EvalContext,
+    ) -> Result<(f64, Option<(&'static str, f64)>)> {
+        let value = holding + self.eval_income(ctx)? - self.eval_upkeep(ctx)?;
+        let capacity = self.eval_capacity(ctx)?;
+        let (bound, policy, event) = match capacity {
+            Some(capacity) if value > capacity => (capacity, self.on_overflow, RESOURCE_OVERFLOW),
+            _ if value < self.floor => (self.floor, self.on_underflow, RESOURCE_UNDERFLOW),
+            _ => return Ok((value, None)),
+        };
+        Ok(match policy {
+            OverflowPolicy::Clamp => (bound, None),
+            OverflowPolicy::Spill => (bound, Some((event, (value - bound).abs()))),
+            OverflowPolicy::Allow => (value, None),
+        })
+    }
+}
+
+/// Evaluate an expression to a number
+fn number(expr: &Expr, ctx: &mut EvalContext) -> Result<f64> {
+    let value = expr.eval(ctx)?;
+    value.as_float().ok_or_else(|| crate::Error::TypeError {
+        expected: "number".to_string(),
+        got: value.type_name().to_string(),
+    })
+}
+
+/// Evaluate and sum a list of rates
+fn sum(exprs: &[Expr], ctx: &mut EvalContext) -> Result<f64> {
+    exprs.iter().map(|expr| number(expr, ctx)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Entity, EntityId, EntityStore, Rng, ValueMap};
+
+    fn flow(resource: &Resource, entity: &Entity) -> (f64, Option<(&'static str, f64)>) {
+        let entities = EntityStore::new();
+        let (globals, params) = (ValueMap::new(), ValueMap::new());
+        let mut rng = Rng::new(0);
+        let mut ctx = EvalContext::new(&entities, &globals, &params, &mut rng).with_target(entity);
+        let holding = entity.get_number(resource.id.as_str()).unwrap_or(0.0);
+        resource.flow(holding, &mut ctx).unwrap()
+    }
+
+    #[test]
+    fn test_flow_policies() {
+        let mut city = Entity::new(EntityId::new(1), "city");
+        city.set("grain", 90.0);
+        city.set("farms", 3.0);
+        let grain = Resource::new("grain")
+            .with_holder("city")
+            .with_capacity(Expr::lit(100.0))
+            .with_income("farms * 4".parse().unwrap())
+            .with_upkeep(Expr::lit(2.0));
+
+        // 90 + 12 - 2 = 100 fits exactly
+        assert_eq!(flow(&grain, &city), (100.0, None));
+
+        city.set("farms", 5.0);
+        assert_eq!(flow(&grain, &city), (100.0, None));
+        let spilling = grain.clone().with_overflow(OverflowPolicy::Spill);
+        assert_eq!(
+            flow(&spilling, &city),
+            (100.0, Some((RESOURCE_OVERFLOW, 8.0)))
+        );
+        let uncapped = grain.clone().with_overflow(OverflowPolicy::Allow);
+        assert_eq!(flow(&uncapped, &city).0, 108.0);
+
+        let upkeep = Resource::new("grain").with_upkeep(Expr::lit(100.0));
+        assert_eq!(flow(&upkeep, &city), (0.0, None));
+        let debt = upkeep.with_underflow(OverflowPolicy::Allow);
+        assert_eq!(flow(&debt, &city), (-10.0, None));
+    }
+}
@@ -12,20 +12,29 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Rng {
     state: u64,
+    /// Values drawn since creation or loading
+    #[serde(skip)]
+    draws: u64,
+    /// Values drawn while logging, in order
+    #[serde(skip)]
+    log: Option<Vec<u64>>,
 }
 
 impl Rng {
     /// Create a new RNG with the given seed
     pub fn new(seed: u64) -> Self {
         // Ensure non-zero state (xorshift requires this)
-        let state = if seed == 0 { 1 } else { seed };
-        Self { state }
+        Self::from_state(seed)
     }
 
     /// Create an RNG from a saved state
     pub fn from_state(state: u64) -> Self {
         let state = if state == 0 { 1 } else { state };
-        Self { state }
+        Self {
+            state,
+            draws: 0,
+            log: None,
+        }
     }
 
     /// Get the current state (useful for saving/loading)
@@ -33,6 +42,24 @@ impl Rng {
         self.state
     }
 
+    /// Get the number of values drawn since creation or loading
+    ///
+    /// Most methods draw one value per call; [`Rng::shuffle`] draws one per
+    /// element after the first.
+    pub fn draws(&self) -> u64 {
+        self.draws
+    }
+
+    /// Start or stop logging the values drawn
+    pub fn set_draw_log(&mut self, enabled: bool) {
+        self.log = enabled.then(Vec::new);
+    }
+
+    /// Take the values drawn since logging started or was last taken
+    pub fn take_draw_log(&mut self) -> Vec<u64> {
+        self.log.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
     /// Generate the next raw u64 value
     pub fn next_u64(&mut self) -> u64 {
         // xorshift64 algorithm
@@ -41,6 +68,10 @@ impl Rng {
         x ^= x >> 7;
         x ^= x << 17;
         self.state = x;
+        self.draws += 1;
+        if let Some(log) = &mut self.log {
+            log.push(x);
+        }
         x
     }
 
@@ -166,6 +197,25 @@ mod tests {
         assert!(counts[2] > counts[0] * 2);
     }
 
+    #[test]
+    fn test_draw_log() {
+        let mut rng = Rng::new(42);
+        rng.next_f64();
+        rng.set_draw_log(true);
+        let first = rng.next_u64();
+        rng.shuffle(&mut [1, 2, 3]);
+        assert_eq!(rng.draws(), 4);
+
+        let log = rng.take_draw_log();
+        assert_eq!(log.len(), 3);
+        assert_eq!(log[0], first);
+        assert!(rng.take_draw_log().is_empty());
+
+        // A restored RNG counts draws from zero
+        let restored = Rng::from_state(rng.state());
+        assert_eq!(restored.draws(), 0);
+    }
+
     #[test]
     fn test_shuffle() {
         let mut rng = Rng::new(42);
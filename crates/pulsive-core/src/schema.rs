@@ -0,0 +1,318 @@
+//! Property schemas validated at write time
+//!
+//! [`PropertySchemas`] registered on a [`Model`](crate::Model) constrain the
+//! type, numeric range and allowed values of entity properties (by entity
+//! kind) and globals. Every property write, by the runtime or by applying a
+//! [`WriteSet`](crate::WriteSet), is checked against them:
+//!
+//! ```rust,ignore
+//! let mut schemas = PropertySchemas::new().with_policy(SchemaPolicy::Clamp);
+//! schemas.register("nation", "stability", PropertySchema::new(ValueType::Float).with_range(-3.0, 3.0));
+//! schemas.register("nation", "government", PropertySchema::new(ValueType::String)
+//!     .with_allowed(["monarchy", "republic"]));
+//! model.set_schemas(schemas);
+//! ```
+//!
+//! What happens to an invalid write depends on the [`SchemaPolicy`]; either
+//! way a [`SchemaViolation`] is reported.
+
+use crate::{DefId, EntityId, Value};
+use std::collections::HashMap;
+
+/// Type a property value must have
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    /// A boolean
+    Bool,
+    /// An integer, or a float without a fractional part
+    Int,
+    /// A float or an integer
+    Float,
+    /// A string
+    String,
+    /// A reference to an entity
+    EntityRef,
+    /// A list
+    List,
+    /// A map
+    Map,
+}
+
+impl ValueType {
+    /// Check whether a value has this type
+    pub fn matches(&self, value: &Value) -> bool {
+        match (self, value) {
+            (ValueType::Bool, Value::Bool(_))
+            | (ValueType::Int, Value::Int(_))
+            | (ValueType::Float, Value::Int(_) | Value::Float(_))
+            | (ValueType::String, Value::String(_))
+            | (ValueType::EntityRef, Value::EntityRef(_))
+            | (ValueType::List, Value::List(_))
+            | (ValueType::Map, Value::Map(_)) => true,
+            (ValueType::Int, Value::Float(f)) => f.fract() == 0.0,
+            _ => false,
+        }
+    }
+}
+
+/// Constraints on one property
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropertySchema {
+    /// Required type (`None` accepts any)
+    pub value_type: Option<ValueType>,
+    /// Minimum of numeric values
+    pub min: Option<f64>,
+    /// Maximum of numeric values
+    pub max: Option<f64>,
+    /// Values the property may take (empty accepts any)
+    pub allowed: Vec<Value>,
+}
+
+impl PropertySchema {
+    /// Create a schema requiring a type
+    pub fn new(value_type: ValueType) -> Self {
+        Self {
+            value_type: Some(value_type),
+            ..Default::default()
+        }
+    }
+
+    /// Create a schema accepting any type
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    /// Set the minimum of numeric values
+    pub fn with_min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    /// Set the maximum of numeric values
+    pub fn with_max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Set the range of numeric values
+    pub fn with_range(self, min: f64, max: f64) -> Self {
+        self.with_min(min).with_max(max)
+    }
+
+    /// Set the values the property may take
+    pub fn with_allowed<V: Into<Value>>(mut self, allowed: impl IntoIterator<Item = V>) -> Self {
+        self.allowed = allowed.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Check a value, returning why it is invalid
+    ///
+    /// Null is always accepted, as it clears the property.
+    pub fn check(&self, value: &Value) -> Option<String> {
+        if value.is_null() {
+            return None;
+        }
+        if let Some(value_type) = self.value_type {
+            if !value_type.matches(value) {
+                return Some(format!(
+                    "expected {:?}, got {}",
+                    value_type,
+                    value.type_name()
+                ));
+            }
+        }
+        if let Some(n) = value.as_float() {
+            if self.min.is_some_and(|min| n < min) || self.max.is_some_and(|max| n > max) {
+                return Some(format!("{} is out of range", n));
+            }
+        }
+        if !self.allowed.is_empty() && !self.allowed.contains(value) {
+            return Some(format!("{} is not an allowed value", value));
+        }
+        None
+    }
+
+    /// Clamp a numeric value into range
+    ///
+    /// Returns `None` for values that are not numbers.
+    pub fn clamp(&self, value: &Value) -> Option<Value> {
+        let clamp = |n: f64| {
+            let n = self.min.map_or(n, |min| n.max(min));
+            self.max.map_or(n, |max| n.min(max))
+        };
+        match value {
+            Value::Int(i) => Some(Value::Int(clamp(*i as f64).round() as i64)),
+            Value::Float(f) => Some(Value::Float(clamp(*f))),
+            _ => None,
+        }
+    }
+}
+
+/// What happens to a write that violates its schema
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SchemaPolicy {
+    /// Skip the write
+    #[default]
+    Reject,
+    /// Clamp out-of-range numbers; skip other invalid writes
+    Clamp,
+    /// Apply the write anyway
+    Log,
+}
+
+/// A write that violated its property schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaViolation {
+    /// Entity written to (`None` for a global)
+    pub entity_id: Option<EntityId>,
+    /// Property written
+    pub property: String,
+    /// Value the write tried to set
+    pub value: Value,
+    /// Why the value is invalid
+    pub reason: String,
+    /// Value actually written (`None` if the write was skipped)
+    pub applied: Option<Value>,
+}
+
+/// Property schemas of entity kinds and globals, with a violation policy
+#[derive(Debug, Clone, Default)]
+pub struct PropertySchemas {
+    entities: HashMap<DefId, HashMap<String, PropertySchema>>,
+    globals: HashMap<String, PropertySchema>,
+    policy: SchemaPolicy,
+}
+
+impl PropertySchemas {
+    /// Create an empty set of schemas
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the violation policy
+    pub fn with_policy(mut self, policy: SchemaPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Get the violation policy
+    pub fn policy(&self) -> SchemaPolicy {
+        self.policy
+    }
+
+    /// Set the violation policy
+    pub fn set_policy(&mut self, policy: SchemaPolicy) {
+        self.policy = policy;
+    }
+
+    /// Register the schema of a property of an entity kind
+    pub fn register(
+        &mut self,
+        kind: impl Into<DefId>,
+        property: impl Into<String>,
+        schema: PropertySchema,
+    ) {
+        self.entities
+            .entry(kind.into())
+            .or_default()
+            .insert(property.into(), schema);
+    }
+
+    /// Register the schema of a global
+    pub fn register_global(&mut self, property: impl Into<String>, schema: PropertySchema) {
+        self.globals.insert(property.into(), schema);
+    }
+
+    /// Get the schema of a property of an entity kind
+    pub fn get(&self, kind: &DefId, property: &str) -> Option<&PropertySchema> {
+        self.entities.get(kind)?.get(property)
+    }
+
+    /// Get the schema of a global
+    pub fn get_global(&self, property: &str) -> Option<&PropertySchema> {
+        self.globals.get(property)
+    }
+
+    /// Check whether no schema is registered
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty() && self.globals.is_empty()
+    }
+
+    /// Check a write against a schema and the policy
+    ///
+    /// Returns the value to write (`None` to skip the write) and the
+    /// violation, if any.
+    pub(crate) fn resolve(
+        &self,
+        schema: Option<&PropertySchema>,
+        entity_id: Option<EntityId>,
+        property: &str,
+        value: Value,
+    ) -> (Option<Value>, Option<SchemaViolation>) {
+        let Some(reason) = schema.and_then(|s| s.check(&value)) else {
+            return (Some(value), None);
+        };
+        let applied = match self.policy {
+            SchemaPolicy::Reject => None,
+            SchemaPolicy::Clamp => schema
+                .and_then(|s| s.clamp(&value))
+                .filter(|clamped| schema.is_some_and(|s| s.check(clamped).is_none())),
+            SchemaPolicy::Log => Some(value.clone()),
+        };
+        let violation = SchemaViolation {
+            entity_id,
+            property: property.to_string(),
+            value,
+            reason,
+            applied: applied.clone(),
+        };
+        (applied, Some(violation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_check() {
+        let schema = PropertySchema::new(ValueType::Int).with_range(0.0, 10.0);
+        assert_eq!(schema.check(&Value::Int(5)), None);
+        assert_eq!(schema.check(&Value::Float(5.0)), None);
+        assert!(schema.check(&Value::Float(5.5)).is_some());
+        assert!(schema.check(&Value::Int(11)).is_some());
+        assert!(schema.check(&Value::String("5".to_string())).is_some());
+        assert_eq!(schema.clamp(&Value::Int(11)), Some(Value::Int(10)));
+
+        let schema = PropertySchema::new(ValueType::String).with_allowed(["monarchy", "republic"]);
+        assert_eq!(schema.check(&Value::from("republic")), None);
+        assert!(schema.check(&Value::from("theocracy")).is_some());
+    }
+
+    #[test]
+    fn test_schema_policies() {
+        let schema = PropertySchema::new(ValueType::Float).with_max(100.0);
+        let resolve = |policy, value| {
+            PropertySchemas::new()
+                .with_policy(policy)
+                .resolve(Some(&schema), None, "gold", value)
+        };
+
+        let (applied, violation) = resolve(SchemaPolicy::Reject, Value::Float(150.0));
+        assert_eq!(applied, None);
+        assert_eq!(violation.unwrap().applied, None);
+
+        let (applied, _) = resolve(SchemaPolicy::Clamp, Value::Float(150.0));
+        assert_eq!(applied, Some(Value::Float(100.0)));
+        let (applied, _) = resolve(SchemaPolicy::Clamp, Value::Bool(true));
+        assert_eq!(applied, None);
+
+        let (applied, violation) = resolve(SchemaPolicy::Log, Value::Float(150.0));
+        assert_eq!(applied, Some(Value::Float(150.0)));
+        assert!(violation.is_some());
+
+        let (applied, violation) = resolve(SchemaPolicy::Reject, Value::Float(50.0));
+        assert_eq!(applied, Some(Value::Float(50.0)));
+        assert!(violation.is_none());
+    }
+}
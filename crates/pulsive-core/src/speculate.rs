@@ -0,0 +1,161 @@
+//! Speculative evaluation against a model snapshot
+//!
+//! [`Model::speculate`] answers "what would happen if" questions (AI
+//! planning, UI previews of player choices) without touching the model:
+//!
+//! ```rust,ignore
+//! let preview = model.speculate(|view| {
+//!     view.apply_effects(&runtime, &choice.effects, &EntityRef::Entity(player), &params);
+//! });
+//! for write in preview.writes.iter() { /* show the predicted change */ }
+//! ```
+//!
+//! The view reads an O(1) snapshot of the model, with its own copy of the
+//! RNG, and collects writes into a scratch [`WriteSet`] instead of applying
+//! them. Reads see the snapshot only, not earlier speculative writes.
+
+use crate::{
+    Effect, EffectResult, EntityRef, Expr, Model, Result, Runtime, Value, ValueMap, WriteSet,
+};
+
+/// The outcome of [`Model::speculate`]
+#[derive(Debug, Clone)]
+pub struct Speculation<T> {
+    /// Value returned by the speculation closure
+    pub value: T,
+    /// Writes the speculated effects would apply
+    pub writes: WriteSet,
+    /// Predicted side effects (events, logs, notifications)
+    pub result: EffectResult,
+}
+
+/// A read view of a model snapshot with a scratch write set
+pub struct SpeculativeView {
+    snapshot: Model,
+    writes: WriteSet,
+    result: EffectResult,
+}
+
+impl SpeculativeView {
+    /// Get the snapshot being read
+    pub fn model(&self) -> &Model {
+        &self.snapshot
+    }
+
+    /// Get the writes collected so far
+    pub fn writes(&self) -> &WriteSet {
+        &self.writes
+    }
+
+    /// Get the side effects collected so far
+    pub fn result(&self) -> &EffectResult {
+        &self.result
+    }
+
+    /// Evaluate an expression against the snapshot
+    pub fn eval(
+        &mut self,
+        runtime: &Runtime,
+        expr: &Expr,
+        target: &EntityRef,
+        params: &ValueMap,
+    ) -> Result<Value> {
+        let mut ctx = runtime.make_eval_context(&mut self.snapshot, target, params);
+        expr.eval(&mut ctx)
+    }
+
+    /// Collect the writes of an effect without applying them
+    pub fn apply(
+        &mut self,
+        runtime: &Runtime,
+        effect: &Effect,
+        target: &EntityRef,
+        params: &ValueMap,
+    ) {
+        let writes =
+            runtime.collect_effect(&mut self.snapshot, effect, target, params, &mut self.result);
+        self.writes.extend(writes);
+    }
+
+    /// Collect the writes of several effects without applying them
+    pub fn apply_effects(
+        &mut self,
+        runtime: &Runtime,
+        effects: &[Effect],
+        target: &EntityRef,
+        params: &ValueMap,
+    ) {
+        for effect in effects {
+            self.apply(runtime, effect, target, params);
+        }
+    }
+}
+
+impl Model {
+    /// Evaluate "what would happen if" without committing any writes
+    ///
+    /// The closure gets a [`SpeculativeView`] of a snapshot of this model.
+    /// Returns its value with the writes and side effects it collected; the
+    /// model, including its RNG, is left untouched.
+    pub fn speculate<T>(&self, f: impl FnOnce(&mut SpeculativeView) -> T) -> Speculation<T> {
+        let mut view = SpeculativeView {
+            snapshot: self.clone(),
+            writes: WriteSet::new(),
+            result: EffectResult::new(),
+        };
+        let value = f(&mut view);
+        Speculation {
+            value,
+            writes: view.writes,
+            result: view.result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ModifyOp, PendingWrite};
+
+    #[test]
+    fn test_speculate_leaves_model_untouched() {
+        let mut model = Model::new();
+        let entity = model.entities_mut().create("nation");
+        entity.set("gold", 100.0f64);
+        let id = entity.id;
+        let runtime = Runtime::new();
+        let rng_before = model.rng.state();
+
+        let preview = model.speculate(|view| {
+            view.apply(
+                &runtime,
+                &Effect::ModifyProperty {
+                    property: "gold".to_string(),
+                    op: ModifyOp::Add,
+                    value: Expr::lit(50.0),
+                },
+                &EntityRef::Entity(id),
+                &ValueMap::new(),
+            );
+            view.eval(
+                &runtime,
+                &Expr::Random,
+                &EntityRef::Global,
+                &ValueMap::new(),
+            )
+            .is_ok()
+        });
+
+        assert!(preview.value);
+        assert_eq!(preview.writes.len(), 1);
+        assert!(matches!(
+            preview.writes.writes()[0],
+            PendingWrite::ModifyProperty { entity_id, .. } if entity_id == id
+        ));
+        assert_eq!(
+            model.entities().get(id).and_then(|e| e.get_number("gold")),
+            Some(100.0)
+        );
+        assert_eq!(model.rng.state(), rng_before);
+    }
+}
@@ -5,6 +5,9 @@
 //! - `pulsive-rollback-buffer` for real-time gaming (bounded ring buffer)
 //! - `pulsive-journal` for debugging and auditing (unbounded history)
 //!
+//! The trait is generic over the stored state type and defaults to [`Model`],
+//! so histories can also hold custom per-player state or render snapshots.
+//!
 //! # Example
 //!
 //! ```rust,ignore
@@ -28,33 +31,36 @@
 //! ```
 
 use crate::Model;
+use std::sync::{Arc, RwLock};
 
-/// Trait for storing and retrieving historical model states.
+/// Trait for storing and retrieving historical states.
+///
+/// `S` is the stored state type, [`Model`] unless specified otherwise.
 ///
 /// Implementations can choose different storage strategies:
 /// - Ring buffer (bounded, fast, for real-time)
 /// - Growing vector (unbounded, for auditing)
 /// - Hybrid (snapshots + deltas)
-pub trait StateHistory {
+pub trait StateHistory<S = Model> {
     /// Save a state snapshot at the given tick.
     ///
     /// The implementation decides whether to clone the model or store a reference.
-    fn save_state(&mut self, tick: u64, model: &Model);
+    fn save_state(&mut self, tick: u64, model: &S);
 
     /// Get the state at exactly the given tick, if it exists.
-    fn get_state(&self, tick: u64) -> Option<&Model>;
+    fn get_state(&self, tick: u64) -> Option<&S>;
 
     /// Get the state at or before the given tick.
     ///
     /// Returns `(actual_tick, model)` where `actual_tick <= tick`.
     /// This is useful for rollback when exact tick isn't available.
-    fn get_nearest_before(&self, tick: u64) -> Option<(u64, &Model)>;
+    fn get_nearest_before(&self, tick: u64) -> Option<(u64, &S)>;
 
     /// Get the state at or after the given tick.
     ///
     /// Returns `(actual_tick, model)` where `actual_tick >= tick`.
     /// This is useful for forward interpolation.
-    fn get_nearest_after(&self, tick: u64) -> Option<(u64, &Model)>;
+    fn get_nearest_after(&self, tick: u64) -> Option<(u64, &S)>;
 
     /// Clear all states before the given tick.
     ///
@@ -85,6 +91,13 @@ pub trait StateHistory {
     fn tick_range(&self) -> Option<(u64, u64)>;
 }
 
+/// A state history shared between its owner and a [`Runtime`](crate::Runtime)
+///
+/// Attached with [`Runtime::set_history`](crate::Runtime::set_history), it
+/// backs the temporal expressions ([`Expr::TicksSince`](crate::Expr::TicksSince),
+/// [`Expr::ValueAt`](crate::Expr::ValueAt), [`Expr::MovingAvg`](crate::Expr::MovingAvg)).
+pub type SharedHistory = Arc<RwLock<dyn StateHistory + Send + Sync>>;
+
 /// Extension trait for interpolation between states
 pub trait StateInterpolation: StateHistory {
     /// Get two states for interpolation: the state before and after the target tick.
@@ -277,4 +290,54 @@ mod tests {
         history.save_state(20, &model);
         assert_eq!(history.tick_range(), Some((10, 30)));
     }
+
+    #[test]
+    fn test_temporal_expressions() {
+        use crate::{
+            DefId, Effect, EvalContext, Expr, ModifyOp, Runtime, TickHandler, Value, ValueMap,
+        };
+
+        let history: SharedHistory = Arc::new(RwLock::new(SimpleHistory::new()));
+        let mut runtime = Runtime::new();
+        runtime.set_history(Some(history.clone()));
+        runtime.on_tick(TickHandler {
+            id: DefId::new("spend"),
+            condition: None,
+            target_kind: Some(DefId::new("nation")),
+            effects: vec![Effect::ModifyProperty {
+                property: "gold".to_string(),
+                op: ModifyOp::Sub,
+                value: Expr::lit(10.0),
+            }],
+            priority: 0,
+        });
+
+        let mut model = Model::new();
+        let entity = model.entities_mut().create("nation");
+        entity.set("gold", 100.0f64);
+        let id = entity.id;
+        for _ in 0..3 {
+            runtime.tick(&mut model);
+        }
+        model.entities_mut().get_mut(id).unwrap().add_flag("broke");
+        for _ in 0..2 {
+            runtime.tick(&mut model);
+        }
+        assert_eq!(history.read().unwrap().len(), 5);
+
+        let params = ValueMap::new();
+        let tick = model.current_tick();
+        let (entities, globals, rng) = model.eval_refs();
+        let mut ctx = EvalContext::new(entities, globals, &params, rng)
+            .with_target(entities.get(id).unwrap())
+            .with_history(&history, tick);
+        let mut eval = |source: &str| Expr::parse(source).unwrap().eval(&mut ctx).unwrap();
+
+        assert_eq!(eval("ticks_since(broke)"), Value::Int(1));
+        assert_eq!(eval("ticks_since(rich)"), Value::Null);
+        assert_eq!(eval("value_at(gold, 0)").as_float(), Some(50.0));
+        assert_eq!(eval("value_at(gold, 2)").as_float(), Some(70.0));
+        assert_eq!(eval("value_at(gold, 10)"), Value::Null);
+        assert_eq!(eval("moving_avg(gold, 3)").as_float(), Some(60.0));
+    }
 }
@@ -0,0 +1,289 @@
+//! Interned property names
+//!
+//! A [`Symbol`] is a `u32` standing for a string interned in a process-wide
+//! table, so [`ValueMap`](crate::ValueMap) keys compare and hash as integers
+//! and each name is stored once however many entities use it. Interned names
+//! live for the rest of the process; intern names, not arbitrary data.
+//!
+//! Each thread caches the table, so looking names up takes no lock once a
+//! thread has seen them.
+//!
+//! Deserialized data may come from the network, so deserializing a name that
+//! was never interned is refused once the table holds [`MAX_SYMBOLS`] names,
+//! or if the name is longer than [`MAX_NAME_LEN`] bytes
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::{OnceLock, RwLock};
+
+/// Most symbols the table may hold before deserialization stops interning
+/// new names
+pub const MAX_SYMBOLS: usize = 1 << 16;
+
+/// Longest name deserialization interns, in bytes
+pub const MAX_NAME_LEN: usize = 256;
+
+/// An interned string
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+/// The process-wide table of interned strings
+#[derive(Default)]
+struct Interner {
+    ids: HashMap<&'static str, Symbol>,
+    names: Vec<&'static str>,
+}
+
+fn interner() -> &'static RwLock<Interner> {
+    static INTERNER: OnceLock<RwLock<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(Default::default)
+}
+
+thread_local! {
+    /// This thread's copy of the interned strings seen so far
+    static CACHE: RefCell<Interner> = RefCell::default();
+}
+
+impl Symbol {
+    /// Intern a string
+    pub fn new(name: &str) -> Self {
+        Self::intern(name, usize::MAX).expect("symbol table is unbounded")
+    }
+
+    /// Intern a string unless that would grow the table past `limit` symbols
+    fn intern(name: &str, limit: usize) -> Option<Self> {
+        if let Some(symbol) = Self::get(name) {
+            return Some(symbol);
+        }
+        let mut interner = interner().write().unwrap_or_else(|e| e.into_inner());
+        if let Some(symbol) = interner.ids.get(name) {
+            return Some(*symbol);
+        }
+        if interner.names.len() >= limit {
+            return None;
+        }
+        let symbol = Symbol(interner.names.len() as u32);
+        let name: &'static str = Box::leak(name.into());
+        interner.names.push(name);
+        interner.ids.insert(name, symbol);
+        Some(symbol)
+    }
+
+    /// Get the symbol of a string, if it was interned
+    pub fn get(name: &str) -> Option<Self> {
+        CACHE.with(|cache| {
+            if let Some(symbol) = cache.borrow().ids.get(name) {
+                return Some(*symbol);
+            }
+            let symbol = *interner()
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .ids
+                .get(name)?;
+            let name = symbol.as_str();
+            cache.borrow_mut().ids.insert(name, symbol);
+            Some(symbol)
+        })
+    }
+
+    /// Get the interned string
+    pub fn as_str(self) -> &'static str {
+        CACHE.with(|cache| {
+            if let Some(name) = cache.borrow().names.get(self.0 as usize) {
+                return *name;
+            }
+            // Copy the strings interned since this thread last looked
+            let interner = interner().read().unwrap_or_else(|e| e.into_inner());
+            let mut cache = cache.borrow_mut();
+            let seen = cache.names.len();
+            cache.names.extend_from_slice(&interner.names[seen..]);
+            cache.names[self.0 as usize]
+        })
+    }
+
+    /// Get the index of the symbol in the interner
+    pub fn index(self) -> u32 {
+        self.0
+    }
+}
+
+/// A key that can be looked up as a symbol without interning it
+pub trait AsSymbol {
+    /// Get the symbol of the key, if it was interned
+    fn as_symbol(&self) -> Option<Symbol>;
+}
+
+impl AsSymbol for Symbol {
+    fn as_symbol(&self) -> Option<Symbol> {
+        Some(*self)
+    }
+}
+
+impl AsSymbol for str {
+    fn as_symbol(&self) -> Option<Symbol> {
+        Symbol::get(self)
+    }
+}
+
+impl AsSymbol for String {
+    fn as_symbol(&self) -> Option<Symbol> {
+        Symbol::get(self)
+    }
+}
+
+impl<T: AsSymbol + ?Sized> AsSymbol for &T {
+    fn as_symbol(&self) -> Option<Symbol> {
+        (**self).as_symbol()
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::new(name)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(name: String) -> Self {
+        Symbol::new(&name)
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(name: &String) -> Self {
+        Symbol::new(name)
+    }
+}
+
+impl From<&Symbol> for Symbol {
+    fn from(symbol: &Symbol) -> Self {
+        *symbol
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        symbol.as_str().to_string()
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let name = String::deserialize(deserializer)?;
+        if let Some(symbol) = Symbol::get(&name) {
+            return Ok(symbol);
+        }
+        if name.len() > MAX_NAME_LEN {
+            return Err(D::Error::custom(format!(
+                "name longer than {} bytes",
+                MAX_NAME_LEN
+            )));
+        }
+        Symbol::intern(&name, MAX_SYMBOLS)
+            .ok_or_else(|| D::Error::custom("too many distinct names"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern() {
+        let hp = Symbol::new("symbol_test_hp");
+        assert_eq!(Symbol::new("symbol_test_hp"), hp);
+        assert_ne!(Symbol::new("symbol_test_mp"), hp);
+        assert_eq!(hp.as_str(), "symbol_test_hp");
+        assert_eq!(Symbol::get("symbol_test_never_interned"), None);
+
+        // Other threads see the same symbols
+        let other = std::thread::spawn(|| {
+            (
+                Symbol::get("symbol_test_hp"),
+                Symbol::new("symbol_test_thread"),
+            )
+        })
+        .join()
+        .unwrap();
+        assert_eq!(other.0, Some(hp));
+        assert_eq!(other.1.as_str(), "symbol_test_thread");
+    }
+
+    #[test]
+    fn test_deserialize_limits() {
+        use serde::de::value::{Error, StrDeserializer};
+
+        let deserialize = |name: &str| Symbol::deserialize(StrDeserializer::<Error>::new(name));
+        let hp = Symbol::new("symbol_test_known");
+        assert_eq!(deserialize("symbol_test_known").unwrap(), hp);
+        assert_eq!(
+            deserialize("symbol_test_new").unwrap().as_str(),
+            "symbol_test_new"
+        );
+
+        let long = "x".repeat(MAX_NAME_LEN + 1);
+        assert!(deserialize(&long).is_err());
+        assert_eq!(Symbol::get(&long), None);
+
+        // A full table still resolves known names but interns no new ones
+        let full = interner().read().unwrap().names.len();
+        assert_eq!(Symbol::intern("symbol_test_known", full), Some(hp));
+        assert_eq!(Symbol::intern("symbol_test_overflow", full), None);
+        assert_eq!(Symbol::get("symbol_test_overflow"), None);
+    }
+}
@@ -0,0 +1,239 @@
+//! Reusable effect templates with parameters
+//!
+//! Content often repeats the same block of effects. An [`EffectTemplate`]
+//! names such a block once, with parameters its effects read as
+//! `Expr::Param`; [`Effect::Invoke`] runs it with arguments:
+//!
+//! ```rust,ignore
+//! runtime.register_effect_template(
+//!     "gain_gold",
+//!     EffectTemplate::new(vec![
+//!         Effect::add("gold", Expr::param("amount")),
+//!         Effect::emit("gold_gained", EntityRef::Global),
+//!     ])
+//!     .with_param("amount"),
+//! )?;
+//!
+//! // In any handler
+//! Effect::Invoke {
+//!     template: DefId::new("gain_gold"),
+//!     args: vec![("amount".to_string(), Expr::lit(50.0))],
+//! }
+//! ```
+//!
+//! Arguments are evaluated where the template is invoked, and its effects
+//! run on the same target. They also see the invoking handler's parameters,
+//! which arguments shadow. Templates are expanded when they run, so
+//! re-registering one changes every handler invoking it; a template that
+//! would invoke itself, directly or through others, is rejected.
+
+use crate::{DefId, Effect, Error, Result, Value, ValueMap};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// A named block of effects with parameters, run by [`Effect::Invoke`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EffectTemplate {
+    /// Parameters, with the default of optional ones
+    pub params: Vec<(String, Option<Value>)>,
+    /// Effects to run
+    pub effects: Vec<Effect>,
+}
+
+impl EffectTemplate {
+    /// Create a template without parameters
+    pub fn new(effects: Vec<Effect>) -> Self {
+        Self {
+            params: Vec::new(),
+            effects,
+        }
+    }
+
+    /// Add a required parameter
+    pub fn with_param(mut self, name: impl Into<String>) -> Self {
+        self.params.push((name.into(), None));
+        self
+    }
+
+    /// Add an optional parameter with a default value
+    pub fn with_default(mut self, name: impl Into<String>, default: impl Into<Value>) -> Self {
+        self.params.push((name.into(), Some(default.into())));
+        self
+    }
+
+    /// Bind evaluated arguments to the parameters, over the invoking
+    /// handler's parameters
+    ///
+    /// Fails on a missing required argument or an argument the template
+    /// doesn't declare.
+    pub fn bind(&self, mut args: ValueMap, params: &ValueMap) -> Result<ValueMap> {
+        let mut bound = params.clone();
+        for (name, default) in &self.params {
+            let value = args
+                .remove(name.as_str())
+                .or_else(|| default.clone())
+                .ok_or_else(|| Error::EvaluationError(format!("Missing argument: {}", name)))?;
+            bound.insert(name.clone(), value);
+        }
+        if let Some((name, _)) = args.iter().next() {
+            return Err(Error::EvaluationError(format!(
+                "Unknown argument: {}",
+                name
+            )));
+        }
+        Ok(bound)
+    }
+
+    /// Get the templates this template's effects invoke
+    fn invoked(&self) -> Vec<&DefId> {
+        let mut invoked = Vec::new();
+        for effect in &self.effects {
+            effect.invoked_templates(&mut invoked);
+        }
+        invoked
+    }
+}
+
+/// Registered effect templates, by ID
+#[derive(Debug, Clone, Default)]
+pub struct EffectTemplates {
+    templates: HashMap<DefId, Arc<EffectTemplate>>,
+}
+
+impl EffectTemplates {
+    /// Create an empty set of templates
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a template, replacing any with the same ID
+    ///
+    /// Fails with [`Error::EffectTemplateCycle`] if the template would
+    /// invoke itself, leaving the templates unchanged. Templates it invokes
+    /// need not be registered yet.
+    pub fn register(&mut self, id: impl Into<DefId>, template: EffectTemplate) -> Result<()> {
+        let id = id.into();
+        if let Some(cycle) = self.find_cycle(&id, &template) {
+            return Err(Error::EffectTemplateCycle(cycle));
+        }
+        self.templates.insert(id, Arc::new(template));
+        Ok(())
+    }
+
+    /// Remove a template, returning whether one was registered
+    pub fn remove(&mut self, id: &DefId) -> bool {
+        self.templates.remove(id).is_some()
+    }
+
+    /// Get a template by ID
+    pub fn get(&self, id: &DefId) -> Option<&Arc<EffectTemplate>> {
+        self.templates.get(id)
+    }
+
+    /// Get the number of templates
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    /// Check if there are no templates
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
+    /// Find a chain of invocations leading from a template back to itself
+    fn find_cycle(&self, id: &DefId, template: &EffectTemplate) -> Option<Vec<DefId>> {
+        let mut path = vec![id.clone()];
+        let mut visited = HashSet::new();
+        self.visit(id, template, &mut path, &mut visited)
+            .then_some(path)
+    }
+
+    /// Walk the invocations of a template, keeping the current chain in
+    /// `path`; returns true once the chain reaches `root`
+    fn visit<'a>(
+        &'a self,
+        root: &DefId,
+        template: &'a EffectTemplate,
+        path: &mut Vec<DefId>,
+        visited: &mut HashSet<&'a DefId>,
+    ) -> bool {
+        for next in template.invoked() {
+            if next == root {
+                return true;
+            }
+            if !visited.insert(next) {
+                continue;
+            }
+            if let Some(invoked) = self.templates.get(next) {
+                path.push(next.clone());
+                if self.visit(root, invoked, path, visited) {
+                    return true;
+                }
+                path.pop();
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Expr;
+
+    #[test]
+    fn test_bind() {
+        let template = EffectTemplate::default()
+            .with_param("amount")
+            .with_default("reason", "tax");
+        let mut params = ValueMap::new();
+        params.insert("amount", Value::Float(1.0));
+        params.insert("source", Value::from("event"));
+
+        let mut args = ValueMap::new();
+        args.insert("amount", Value::Float(5.0));
+        let bound = template.bind(args, &params).unwrap();
+        assert_eq!(bound.get("amount"), Some(&Value::Float(5.0)));
+        assert_eq!(bound.get("reason"), Some(&Value::from("tax")));
+        assert_eq!(bound.get("source"), Some(&Value::from("event")));
+
+        assert!(template.bind(ValueMap::new(), &params).is_err());
+        let mut args = ValueMap::new();
+        args.insert("amount", Value::Float(5.0));
+        args.insert("amont", Value::Float(5.0));
+        assert!(template.bind(args, &params).is_err());
+    }
+
+    #[test]
+    fn test_cycles_rejected() {
+        let mut templates = EffectTemplates::new();
+        templates
+            .register("a", EffectTemplate::new(vec![Effect::invoke("b")]))
+            .unwrap();
+        let nested = Effect::If {
+            condition: Expr::lit(true),
+            then_effects: vec![Effect::invoke("a")],
+            else_effects: Vec::new(),
+        };
+        templates
+            .register("c", EffectTemplate::new(vec![nested]))
+            .unwrap();
+
+        let err = templates
+            .register("b", EffectTemplate::new(vec![Effect::invoke("c")]))
+            .unwrap_err();
+        match err {
+            Error::EffectTemplateCycle(cycle) => {
+                let names: Vec<&str> = cycle.iter().map(|id| id.as_str()).collect();
+                assert_eq!(names, ["b", "c", "a"]);
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+        assert!(templates.get(&DefId::new("b")).is_none());
+        assert!(templates
+            .register("a", EffectTemplate::new(vec![Effect::invoke("a")]))
+            .is_err());
+        assert_eq!(templates.len(), 2);
+    }
+}
@@ -0,0 +1,68 @@
+//! Time dilation of tick handlers
+//!
+//! An entity's [`TIME_SCALE`] property speeds up or slows down the tick
+//! handlers running on it: at 0.5 they run every other tick, at 2 twice a
+//! tick, and at 0 not at all (stasis). The [`TIME_SCALE`] global scales
+//! every tick handler, multiplying the entities' own scales, e.g. for slow
+//! motion or for background regions simulated at a lower rate:
+//!
+//! ```rust,ignore
+//! // A stasis field: effects set the scale like any other property
+//! Effect::SetProperty {
+//!     property: TIME_SCALE.to_string(),
+//!     value: Expr::lit(0.0),
+//! }
+//! ```
+//!
+//! Fractional ticks carry over in the [`TIME_ACCUMULATOR`] property (or
+//! global, for handlers without a target kind), so the rate holds on
+//! average. Both are part of the model, so dilated handlers run the same
+//! after a snapshot, a rollback or a replay. Timed flags, scheduled events
+//! and resource flows keep to the unscaled clock.
+
+/// Property (and global) holding a time scale; 1 when unset
+pub const TIME_SCALE: &str = "time_scale";
+
+/// Property (and global) carrying fractional ticks over to the next tick
+pub const TIME_ACCUMULATOR: &str = "time_accumulator";
+
+/// Tolerance so scales like 0.1 add up to whole ticks despite rounding
+const EPSILON: f64 = 1e-9;
+
+/// Advance an accumulator by one tick at a time scale
+///
+/// Returns how many times handlers run this tick and the new accumulator.
+/// Negative scales count as 0.
+pub(crate) fn advance(scale: f64, accumulator: f64) -> (u32, f64) {
+    let total = accumulator + scale.max(0.0);
+    let runs = (total + EPSILON).floor();
+    (runs as u32, (total - runs).max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runs(scale: f64, ticks: usize) -> Vec<u32> {
+        let mut accumulator = 0.0;
+        (0..ticks)
+            .map(|_| {
+                let (runs, next) = advance(scale, accumulator);
+                accumulator = next;
+                runs
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_advance() {
+        assert_eq!(runs(0.5, 4), [0, 1, 0, 1]);
+        assert_eq!(runs(2.0, 2), [2, 2]);
+        assert_eq!(runs(0.0, 3), [0, 0, 0]);
+        assert_eq!(runs(-1.0, 2), [0, 0]);
+        assert_eq!(runs(1.5, 2), [1, 2]);
+        // Rounding doesn't lose a tick
+        assert_eq!(runs(0.1, 10).iter().sum::<u32>(), 1);
+        assert_eq!(runs(0.1, 10)[9], 1);
+    }
+}
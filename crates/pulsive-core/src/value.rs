@@ -1,7 +1,7 @@
 //! Dynamic value types for data-driven content
 
 use crate::identity::EntityId;
-use indexmap::IndexMap;
+use crate::ValueMap;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -27,10 +27,10 @@ pub enum Value {
     Map(ValueMap),
 }
 
-/// A map of string keys to dynamic values
-///
-/// Uses IndexMap to preserve insertion order (useful for deterministic serialization)
-pub type ValueMap = IndexMap<String, Value>;
+/// Map entry holding the key of localized text
+const LOCALIZED_KEY: &str = "$loc";
+/// Map entry holding the parameters of localized text
+const LOCALIZED_PARAMS: &str = "$params";
 
 impl Value {
     /// Check if this value is null
@@ -95,6 +95,25 @@ impl Value {
         }
     }
 
+    /// Create localized text: a key and parameters for the UI to render in
+    /// the player's language
+    ///
+    /// Represented as a map with `$loc` and `$params` entries.
+    pub fn localized(key: impl Into<String>, params: ValueMap) -> Self {
+        let mut map = ValueMap::new();
+        map.insert(LOCALIZED_KEY.to_string(), Value::String(key.into()));
+        map.insert(LOCALIZED_PARAMS.to_string(), Value::Map(params));
+        Value::Map(map)
+    }
+
+    /// Get the key and parameters of localized text
+    pub fn as_localized(&self) -> Option<(&str, &ValueMap)> {
+        let map = self.as_map()?;
+        let key = map.get(LOCALIZED_KEY)?.as_str()?;
+        let params = map.get(LOCALIZED_PARAMS)?.as_map()?;
+        Some((key, params))
+    }
+
     /// Get the type name of this value
     pub fn type_name(&self) -> &'static str {
         match self {
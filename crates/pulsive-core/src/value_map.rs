@@ -0,0 +1,351 @@
+//! Insertion-ordered map of interned keys to values
+//!
+//! Most entities have a handful of properties, so [`ValueMap`] keeps its
+//! entries in a vector and finds keys by comparing [`Symbol`]s; only maps of
+//! [`SMALL_LEN`] entries or more build a hash index. Keys may be looked up by
+//! string or by symbol; looking up a string that was never interned finds
+//! nothing without interning it.
+
+use crate::{AsSymbol, Symbol, Value};
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Index;
+
+/// Number of entries from which a map indexes its keys
+pub const SMALL_LEN: usize = 8;
+
+/// A map of interned string keys to dynamic values, in insertion order
+///
+/// Two maps are equal if they hold the same entries, in any order.
+#[derive(Clone, Default)]
+pub struct ValueMap {
+    entries: Vec<(Symbol, Value)>,
+    /// Position of each key, kept once the map reaches [`SMALL_LEN`] entries
+    index: Option<HashMap<Symbol, usize>>,
+}
+
+impl ValueMap {
+    /// Create an empty map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty map with room for some entries
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            index: None,
+        }
+    }
+
+    /// Get the number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the map is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Position of a key
+    fn position<K: AsSymbol + ?Sized>(&self, key: &K) -> Option<usize> {
+        let symbol = key.as_symbol()?;
+        match &self.index {
+            Some(index) => index.get(&symbol).copied(),
+            None => self.entries.iter().position(|(k, _)| *k == symbol),
+        }
+    }
+
+    /// Rebuild the index after entries moved
+    fn reindex(&mut self) {
+        self.index = (self.entries.len() >= SMALL_LEN).then(|| {
+            self.entries
+                .iter()
+                .enumerate()
+                .map(|(i, (k, _))| (*k, i))
+                .collect()
+        });
+    }
+
+    /// Get the value of a key
+    pub fn get<K: AsSymbol + ?Sized>(&self, key: &K) -> Option<&Value> {
+        self.position(key).map(|i| &self.entries[i].1)
+    }
+
+    /// Get a mutable reference to the value of a key
+    pub fn get_mut<K: AsSymbol + ?Sized>(&mut self, key: &K) -> Option<&mut Value> {
+        self.position(key).map(|i| &mut self.entries[i].1)
+    }
+
+    /// Check if the map has a key
+    pub fn contains_key<K: AsSymbol + ?Sized>(&self, key: &K) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Set the value of a key, returning the previous value
+    ///
+    /// A new key goes last; an existing key keeps its position.
+    pub fn insert(&mut self, key: impl Into<Symbol>, value: Value) -> Option<Value> {
+        let key = key.into();
+        if let Some(i) = self.position(&key) {
+            return Some(std::mem::replace(&mut self.entries[i].1, value));
+        }
+        self.entries.push((key, value));
+        match &mut self.index {
+            Some(index) => {
+                index.insert(key, self.entries.len() - 1);
+            }
+            None if self.entries.len() >= SMALL_LEN => self.reindex(),
+            None => {}
+        }
+        None
+    }
+
+    /// Remove a key, keeping the order of the other entries, and return its
+    /// value
+    pub fn shift_remove<K: AsSymbol + ?Sized>(&mut self, key: &K) -> Option<Value> {
+        let i = self.position(key)?;
+        let (_, value) = self.entries.remove(i);
+        if self.index.is_some() {
+            self.reindex();
+        }
+        Some(value)
+    }
+
+    /// Remove a key and return its value (same as [`shift_remove`](Self::shift_remove))
+    pub fn remove<K: AsSymbol + ?Sized>(&mut self, key: &K) -> Option<Value> {
+        self.shift_remove(key)
+    }
+
+    /// Keep only the entries for which the predicate holds
+    pub fn retain(&mut self, mut keep: impl FnMut(&Symbol, &mut Value) -> bool) {
+        let len = self.entries.len();
+        self.entries.retain_mut(|(k, v)| keep(k, v));
+        if self.entries.len() != len {
+            self.reindex();
+        }
+    }
+
+    /// Remove every entry
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index = None;
+    }
+
+    /// Get the entry at a position
+    pub fn get_index(&self, i: usize) -> Option<(&Symbol, &Value)> {
+        self.entries.get(i).map(|(k, v)| (k, v))
+    }
+
+    /// Iterate over the entries in order
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.entries.iter())
+    }
+
+    /// Iterate over the entries in order, with mutable values
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut(self.entries.iter_mut())
+    }
+
+    /// Iterate over the keys in order
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &Symbol> + ExactSizeIterator {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    /// Iterate over the values in order
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &Value> + ExactSizeIterator {
+        self.entries.iter().map(|(_, v)| v)
+    }
+
+    /// Iterate over mutable values in order
+    pub fn values_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = &mut Value> + ExactSizeIterator {
+        self.entries.iter_mut().map(|(_, v)| v)
+    }
+}
+
+/// Iterator over the entries of a [`ValueMap`]
+pub struct Iter<'a>(std::slice::Iter<'a, (Symbol, Value)>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a Symbol, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+/// Iterator over the entries of a [`ValueMap`], with mutable values
+pub struct IterMut<'a>(std::slice::IterMut<'a, (Symbol, Value)>);
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = (&'a Symbol, &'a mut Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (&*k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl ExactSizeIterator for IterMut<'_> {}
+
+impl<'a> IntoIterator for &'a ValueMap {
+    type Item = (&'a Symbol, &'a Value);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut ValueMap {
+    type Item = (&'a Symbol, &'a mut Value);
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> IterMut<'a> {
+        self.iter_mut()
+    }
+}
+
+impl IntoIterator for ValueMap {
+    type Item = (Symbol, Value);
+    type IntoIter = std::vec::IntoIter<(Symbol, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<K: Into<Symbol>> FromIterator<(K, Value)> for ValueMap {
+    fn from_iter<I: IntoIterator<Item = (K, Value)>>(iter: I) -> Self {
+        let mut map = ValueMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Into<Symbol>> Extend<(K, Value)> for ValueMap {
+    fn extend<I: IntoIterator<Item = (K, Value)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Into<Symbol>, const N: usize> From<[(K, Value); N]> for ValueMap {
+    fn from(entries: [(K, Value); N]) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl<K: AsSymbol + ?Sized> Index<&K> for ValueMap {
+    type Output = Value;
+
+    /// Get the value of a key
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key is not in the map.
+    fn index(&self, key: &K) -> &Value {
+        self.get(key).expect("key not in ValueMap")
+    }
+}
+
+impl PartialEq for ValueMap {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(k, v)| other.get(k) == Some(v))
+    }
+}
+
+impl fmt::Debug for ValueMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl Serialize for ValueMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self {
+            map.serialize_entry(key.as_str(), value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ValueMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MapVisitor;
+
+        impl<'de> Visitor<'de> for MapVisitor {
+            type Value = ValueMap;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a map of strings to values")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<ValueMap, A::Error> {
+                let mut map = ValueMap::with_capacity(access.size_hint().unwrap_or(0));
+                while let Some((key, value)) = access.next_entry::<Symbol, Value>()? {
+                    map.insert(key, value);
+                }
+                Ok(map)
+            }
+        }
+
+        deserializer.deserialize_map(MapVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_map() {
+        let mut map = ValueMap::new();
+        for i in 0..12 {
+            map.insert(format!("key_{}", i), Value::Int(i));
+            assert_eq!(map.len(), i as usize + 1);
+            assert_eq!(map.get(&format!("key_{}", i)), Some(&Value::Int(i)));
+        }
+        assert_eq!(map.insert("key_3", Value::Null), Some(Value::Int(3)));
+        assert_eq!(map.shift_remove("key_0"), Some(Value::Int(0)));
+        assert_eq!(map.get("key_0"), None);
+        assert_eq!(map.get("key_1"), Some(&Value::Int(1)));
+        assert_eq!(map.get_index(2).map(|(k, _)| k.as_str()), Some("key_3"));
+        assert_eq!(map.get("never_a_key"), None);
+
+        // Equality ignores order; serialization keeps it
+        let reversed: ValueMap = map.clone().into_iter().rev().collect();
+        assert_eq!(reversed, map);
+        let ron = ron::to_string(&map).unwrap();
+        let parsed: ValueMap = ron::from_str(&ron).unwrap();
+        assert_eq!(parsed, map);
+        assert!(parsed.keys().eq(map.keys()));
+
+        map.retain(|k, _| k.as_str() < "key_5");
+        assert_eq!(map.len(), 6);
+        assert_eq!(map.get("key_10"), Some(&Value::Int(10)));
+    }
+}
@@ -21,8 +21,9 @@
 //! - `WriteSet::apply()` is implemented in `pulsive-hub` (where the Hub owns the Model)
 
 use crate::effect::ModifyOp;
-use crate::{DefId, EntityId, Value, ValueMap};
+use crate::{DefId, EntityId, Tick, Value, ValueMap};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 /// A pending write operation to be applied to the model
 ///
@@ -99,6 +100,32 @@ pub enum PendingWrite {
         /// The entity to destroy
         id: EntityId,
     },
+
+    /// Add a flag to an entity that expires at a tick
+    AddFlagFor {
+        /// The entity to modify
+        entity_id: EntityId,
+        /// The flag to add
+        flag: DefId,
+        /// The tick at which the flag is removed
+        expires_at: Tick,
+    },
+
+    /// Add a tag to an entity
+    AddTag {
+        /// The entity to modify
+        entity_id: EntityId,
+        /// The tag to add
+        tag: String,
+    },
+
+    /// Remove a tag from an entity
+    RemoveTag {
+        /// The entity to modify
+        entity_id: EntityId,
+        /// The tag to remove
+        tag: String,
+    },
 }
 
 /// Result of applying a WriteSet to a model
@@ -108,6 +135,10 @@ pub struct WriteSetResult {
     pub spawned: Vec<EntityId>,
     /// Entity IDs that were destroyed
     pub destroyed: Vec<EntityId>,
+    /// Writes that violated their property schema
+    pub violations: Vec<crate::SchemaViolation>,
+    /// Writes that could not be applied
+    pub report: WriteApplyReport,
 }
 
 impl WriteSetResult {
@@ -120,6 +151,94 @@ impl WriteSetResult {
     pub fn merge(&mut self, other: WriteSetResult) {
         self.spawned.extend(other.spawned);
         self.destroyed.extend(other.destroyed);
+        self.violations.extend(other.violations);
+        self.report.merge(other.report);
+    }
+}
+
+/// Why a write could not be applied
+#[derive(Debug, Clone, PartialEq)]
+pub enum WriteFailure {
+    /// The entity written to does not exist
+    MissingEntity(EntityId),
+    /// A modify targeted a property or global holding a non-numeric value
+    NotNumeric {
+        /// The property key
+        key: String,
+        /// The value found
+        value: Value,
+    },
+}
+
+impl fmt::Display for WriteFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteFailure::MissingEntity(id) => write!(f, "entity {} does not exist", id),
+            WriteFailure::NotNumeric { key, value } => {
+                write!(f, "'{}' is a {}, not a number", key, value.type_name())
+            }
+        }
+    }
+}
+
+/// A write skipped when applying a WriteSet
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedWrite {
+    /// Position of the write in the applied WriteSet
+    pub index: usize,
+    /// The write
+    pub write: PendingWrite,
+    /// Why it was skipped
+    pub failure: WriteFailure,
+}
+
+/// The writes skipped when applying a WriteSet
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WriteApplyReport {
+    /// Skipped writes, in order
+    pub skipped: Vec<SkippedWrite>,
+}
+
+impl WriteApplyReport {
+    /// Create an empty report
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a skipped write
+    pub fn skip(&mut self, index: usize, write: &PendingWrite, failure: WriteFailure) {
+        self.skipped.push(SkippedWrite {
+            index,
+            write: write.clone(),
+            failure,
+        });
+    }
+
+    /// Number of skipped writes
+    pub fn len(&self) -> usize {
+        self.skipped.len()
+    }
+
+    /// Check whether no write was skipped
+    pub fn is_empty(&self) -> bool {
+        self.skipped.is_empty()
+    }
+
+    /// Merge another report into this one
+    pub fn merge(&mut self, other: WriteApplyReport) {
+        self.skipped.extend(other.skipped);
+    }
+}
+
+impl fmt::Display for WriteApplyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, skipped) in self.skipped.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "write {}: {}", skipped.index, skipped.failure)?;
+        }
+        Ok(())
     }
 }
 
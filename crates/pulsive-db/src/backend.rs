@@ -0,0 +1,167 @@
+//! Storage backend abstraction.
+//!
+//! [`StorageBackend`] covers the state persistence shared by every backend:
+//! entities, globals, clock, RNG, saved models and rollback frames. [`Store`]
+//! (native_db) implements it, as does `SqliteStore` with the `sqlite`
+//! feature, for deployments that need SQL tooling. Code that only persists
+//! state, like [`WriteBehind`](crate::WriteBehind), accepts any backend.
+
+use crate::error::Result;
+use crate::store::Store;
+use pulsive_core::{Clock, Entity, EntityId, Model, Rng, ValueMap};
+
+/// Changes to persisted state, written together.
+#[derive(Debug, Clone)]
+pub struct Changes {
+    /// Entities to insert or replace.
+    pub entities: Vec<Entity>,
+    /// Entities to delete.
+    pub destroyed: Vec<EntityId>,
+    /// Globals to replace, if they changed.
+    pub globals: Option<ValueMap>,
+    /// Clock to save.
+    pub clock: Clock,
+    /// RNG state to save.
+    pub rng: Rng,
+}
+
+/// Persistence of simulation state.
+pub trait StorageBackend: Send + Sync {
+    /// Save an entity.
+    fn save_entity(&self, entity: &Entity) -> Result<()>;
+
+    /// Load an entity by ID.
+    fn load_entity(&self, id: EntityId) -> Result<Option<Entity>>;
+
+    /// Delete an entity.
+    fn delete_entity(&self, id: EntityId) -> Result<()>;
+
+    /// Load all entities.
+    fn load_all_entities(&self) -> Result<Vec<Entity>>;
+
+    /// Save global variables.
+    fn save_globals(&self, globals: &ValueMap) -> Result<()>;
+
+    /// Load global variables.
+    fn load_globals(&self) -> Result<ValueMap>;
+
+    /// Save game time.
+    fn save_clock(&self, clock: &Clock) -> Result<()>;
+
+    /// Load game time.
+    fn load_clock(&self) -> Result<Option<Clock>>;
+
+    /// Save RNG state.
+    fn save_rng(&self, rng: &Rng) -> Result<()>;
+
+    /// Load RNG state.
+    fn load_rng(&self) -> Result<Option<Rng>>;
+
+    /// Write a set of changes in one transaction.
+    fn write_changes(&self, changes: &Changes) -> Result<()>;
+
+    /// Save a complete model into a slot, replacing any previous save.
+    fn save_model(&self, model: &Model, slot: &str) -> Result<()>;
+
+    /// Load the complete model saved in a slot.
+    ///
+    /// Property schemas are not saved; use [`Model::restore`] to load into a
+    /// running model without losing them.
+    fn load_model(&self, slot: &str) -> Result<Option<Model>>;
+
+    /// Delete a slot and its saved model.
+    fn delete_slot(&self, slot: &str) -> Result<()>;
+
+    /// Save a rollback history frame.
+    fn save_frame(&self, tick: u64, model: &Model) -> Result<()>;
+
+    /// Load a rollback history frame by tick.
+    fn load_frame(&self, tick: u64) -> Result<Option<Model>>;
+
+    /// Delete all rollback history frames before a tick.
+    fn delete_frames_before(&self, tick: u64) -> Result<()>;
+
+    /// Delete all rollback history frames.
+    fn clear_frames(&self) -> Result<()>;
+
+    /// Clear entities, globals, clock and RNG state.
+    fn clear(&self) -> Result<()>;
+}
+
+impl StorageBackend for Store {
+    fn save_entity(&self, entity: &Entity) -> Result<()> {
+        Store::save_entity(self, entity)
+    }
+
+    fn load_entity(&self, id: EntityId) -> Result<Option<Entity>> {
+        Store::load_entity(self, id)
+    }
+
+    fn delete_entity(&self, id: EntityId) -> Result<()> {
+        Store::delete_entity(self, id)
+    }
+
+    fn load_all_entities(&self) -> Result<Vec<Entity>> {
+        Store::load_all_entities(self)
+    }
+
+    fn save_globals(&self, globals: &ValueMap) -> Result<()> {
+        Store::save_globals(self, globals)
+    }
+
+    fn load_globals(&self) -> Result<ValueMap> {
+        Store::load_globals(self)
+    }
+
+    fn save_clock(&self, clock: &Clock) -> Result<()> {
+        Store::save_clock(self, clock)
+    }
+
+    fn load_clock(&self) -> Result<Option<Clock>> {
+        Store::load_clock(self)
+    }
+
+    fn save_rng(&self, rng: &Rng) -> Result<()> {
+        Store::save_rng(self, rng)
+    }
+
+    fn load_rng(&self) -> Result<Option<Rng>> {
+        Store::load_rng(self)
+    }
+
+    fn write_changes(&self, changes: &Changes) -> Result<()> {
+        Store::write_changes(self, changes)
+    }
+
+    fn save_model(&self, model: &Model, slot: &str) -> Result<()> {
+        Store::save_model(self, model, slot)
+    }
+
+    fn load_model(&self, slot: &str) -> Result<Option<Model>> {
+        Store::load_model(self, slot)
+    }
+
+    fn delete_slot(&self, slot: &str) -> Result<()> {
+        Store::delete_slot(self, slot)
+    }
+
+    fn save_frame(&self, tick: u64, model: &Model) -> Result<()> {
+        Store::save_frame(self, tick, model)
+    }
+
+    fn load_frame(&self, tick: u64) -> Result<Option<Model>> {
+        Store::load_frame(self, tick)
+    }
+
+    fn delete_frames_before(&self, tick: u64) -> Result<()> {
+        Store::delete_frames_before(self, tick)
+    }
+
+    fn clear_frames(&self) -> Result<()> {
+        Store::clear_frames(self)
+    }
+
+    fn clear(&self) -> Result<()> {
+        Store::clear(self)
+    }
+}
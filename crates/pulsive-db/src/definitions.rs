@@ -0,0 +1,129 @@
+//! Stored definitions.
+//!
+//! Servers can keep game definitions in the database as their source of
+//! truth. The store does not know the definition types, which live in
+//! pulsive-script: each definition is a [`DefinitionRecord`] holding its
+//! kind, ID and serialized text, and pulsive-script converts between records
+//! and its `GameDefs`.
+
+use crate::error::{Error, Result};
+use crate::models::*;
+use crate::store::Store;
+
+/// A stored definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefinitionRecord {
+    /// Definition kind (e.g. "resource", "event").
+    pub kind: String,
+    /// Definition ID.
+    pub id: String,
+    /// Serialized definition (RON).
+    pub data: String,
+}
+
+impl DefinitionRecord {
+    /// Create a record.
+    pub fn new(kind: impl Into<String>, id: impl Into<String>, data: impl Into<String>) -> Self {
+        Self {
+            kind: kind.into(),
+            id: id.into(),
+            data: data.into(),
+        }
+    }
+
+    fn from_stored(stored: StoredDefinition) -> Self {
+        Self {
+            kind: stored.kind,
+            id: stored.id,
+            data: stored.data,
+        }
+    }
+
+    fn to_stored(&self) -> StoredDefinition {
+        StoredDefinition {
+            key: StoredDefinition::key(&self.kind, &self.id),
+            kind: self.kind.clone(),
+            id: self.id.clone(),
+            data: self.data.clone(),
+        }
+    }
+}
+
+impl Store {
+    /// Save a definition, replacing any with the same kind and ID.
+    pub fn save_definition(&self, record: &DefinitionRecord) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        rw.upsert(record.to_stored())?;
+        rw.commit()?;
+        Ok(())
+    }
+
+    /// Load a definition by kind and ID.
+    pub fn load_definition(&self, kind: &str, id: &str) -> Result<Option<DefinitionRecord>> {
+        let r = self.db.r_transaction()?;
+        let stored: Option<StoredDefinition> = r.get().primary(StoredDefinition::key(kind, id))?;
+        Ok(stored.map(DefinitionRecord::from_stored))
+    }
+
+    /// Delete a definition.
+    pub fn delete_definition(&self, kind: &str, id: &str) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        let stored: Option<StoredDefinition> = rw.get().primary(StoredDefinition::key(kind, id))?;
+        if let Some(s) = stored {
+            rw.remove(s)?;
+        }
+        rw.commit()?;
+        Ok(())
+    }
+
+    /// Get all definitions, ordered by kind and ID.
+    pub fn definitions(&self) -> Result<Vec<DefinitionRecord>> {
+        let r = self.db.r_transaction()?;
+        let scan = r.scan().primary::<StoredDefinition>()?;
+        let iter = scan.all()?;
+        let stored: std::result::Result<Vec<StoredDefinition>, _> = iter.collect();
+        let stored = stored.map_err(|e| Error::Database(e.to_string()))?;
+        let mut records: Vec<DefinitionRecord> = stored
+            .into_iter()
+            .map(DefinitionRecord::from_stored)
+            .collect();
+        records.sort_by(|a, b| (&a.kind, &a.id).cmp(&(&b.kind, &b.id)));
+        Ok(records)
+    }
+
+    /// Get all definitions of a kind, ordered by ID.
+    pub fn definitions_of_kind(&self, kind: &str) -> Result<Vec<DefinitionRecord>> {
+        let r = self.db.r_transaction()?;
+        let scan = r
+            .scan()
+            .secondary::<StoredDefinition>(StoredDefinitionKey::kind)?;
+        let iter = scan.start_with(kind)?;
+        let stored: std::result::Result<Vec<StoredDefinition>, _> = iter.collect();
+        let stored = stored.map_err(|e| Error::Database(e.to_string()))?;
+        let mut records: Vec<DefinitionRecord> = stored
+            .into_iter()
+            .filter(|s| s.kind == kind)
+            .map(DefinitionRecord::from_stored)
+            .collect();
+        records.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(records)
+    }
+
+    /// Replace all stored definitions, in one transaction.
+    pub fn replace_definitions(&self, records: &[DefinitionRecord]) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        let stored: Vec<StoredDefinition> = rw
+            .scan()
+            .primary::<StoredDefinition>()?
+            .all()?
+            .collect::<std::result::Result<_, _>>()?;
+        for old in stored {
+            rw.remove(old)?;
+        }
+        for record in records {
+            rw.upsert(record.to_stored())?;
+        }
+        rw.commit()?;
+        Ok(())
+    }
+}
@@ -17,6 +17,23 @@ pub enum Error {
     #[error("Entity not found: {0}")]
     NotFound(String),
 
+    /// Stored data was written with an incompatible schema version.
+    #[error("Schema version mismatch: found {found}, expected {expected}")]
+    SchemaVersion {
+        /// Version of the stored data.
+        found: u32,
+        /// Version this build reads.
+        expected: u32,
+    },
+
+    /// Migration error.
+    #[error("Migration error: {0}")]
+    Migration(String),
+
+    /// Stored data does not match its checksum.
+    #[error("Corrupted data: {0}")]
+    Corrupted(String),
+
     /// Duplicate key.
     #[error("Duplicate key: {0}")]
     DuplicateKey(String),
@@ -0,0 +1,217 @@
+//! Journal persistence.
+//!
+//! A [`JournalStore`] streams journal entries and snapshots into the database
+//! while a session is recorded, then loads tick ranges back as a [`Journal`]
+//! so the replayer and auditor can work on sessions too big for memory.
+
+use crate::error::{Error, Result};
+use crate::models::*;
+use crate::store::Store;
+use pulsive_core::{Journal, JournalConfig, JournalEntry, JournalSink, Snapshot, Tick};
+
+/// Database-backed journal.
+pub struct JournalStore {
+    store: Store,
+    /// Number of entries of the recording journal already persisted.
+    synced: usize,
+    /// Sequence number of the next stored entry.
+    next_seq: u64,
+}
+
+impl JournalStore {
+    /// Create a journal store on a database, appending after any stored entries.
+    pub fn new(store: Store) -> Result<Self> {
+        let next_seq = {
+            let r = store.db.r_transaction()?;
+            let scan = r.scan().primary::<StoredJournalEntry>()?;
+            let mut iter = scan.all()?;
+            match iter.next_back() {
+                Some(last) => last.map_err(|e| Error::Database(e.to_string()))?.seq + 1,
+                None => 0,
+            }
+        };
+        Ok(Self {
+            store,
+            synced: 0,
+            next_seq,
+        })
+    }
+
+    /// Get the underlying store.
+    pub fn store(&self) -> &Store {
+        &self.store
+    }
+
+    /// Number of stored entries.
+    pub fn len(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Check if no entries are stored.
+    pub fn is_empty(&self) -> bool {
+        self.next_seq == 0
+    }
+
+    /// Persist all entries recorded in `journal` since the last call.
+    ///
+    /// Call this periodically while recording (e.g. once per tick); each call
+    /// is written in one transaction. Entries trimmed from the journal before
+    /// being synced (by `max_entries` or compaction) cannot be recovered.
+    pub fn sync(&mut self, journal: &Journal) -> Result<()> {
+        let mut batch = Batch {
+            next_seq: self.next_seq,
+            entries: Vec::new(),
+            snapshots: Vec::new(),
+        };
+        let synced = journal.sync_to(&mut batch, self.synced)?;
+
+        let rw = self.store.db.rw_transaction()?;
+        for snapshot in batch.snapshots {
+            rw.upsert(snapshot)?;
+        }
+        for entry in batch.entries {
+            rw.upsert(entry)?;
+        }
+        rw.commit()?;
+
+        self.synced = synced;
+        self.next_seq = batch.next_seq;
+        Ok(())
+    }
+
+    /// Get stored entries in a tick range (inclusive), in recording order.
+    pub fn entries_in_range(&self, start_tick: Tick, end_tick: Tick) -> Result<Vec<JournalEntry>> {
+        let r = self.store.db.r_transaction()?;
+        let scan = r
+            .scan()
+            .secondary::<StoredJournalEntry>(StoredJournalEntryKey::tick)?;
+        let iter = scan.range(start_tick..=end_tick)?;
+        let stored: std::result::Result<Vec<StoredJournalEntry>, _> = iter.collect();
+        let mut stored = stored.map_err(|e| Error::Database(e.to_string()))?;
+        stored.sort_by_key(|s| s.seq);
+        stored
+            .iter()
+            .map(|s| {
+                s.to_entry()
+                    .map_err(|e| Error::Serialization(format!("journal entry {}: {}", s.seq, e)))
+            })
+            .collect()
+    }
+
+    /// Get the nearest stored snapshot before or at a tick.
+    pub fn snapshot_at_or_before(&self, tick: Tick) -> Result<Option<Snapshot>> {
+        let r = self.store.db.r_transaction()?;
+        let scan = r
+            .scan()
+            .secondary::<StoredSnapshot>(StoredSnapshotKey::tick)?;
+        let iter = scan.range(..=tick)?;
+        let stored: std::result::Result<Vec<StoredSnapshot>, _> = iter.collect();
+        let stored = stored.map_err(|e| Error::Database(e.to_string()))?;
+        stored
+            .iter()
+            .max_by_key(|s| (s.tick, s.id))
+            .map(|s| {
+                s.to_snapshot()
+                    .map_err(|e| Error::Serialization(format!("snapshot {}: {}", s.id, e)))
+            })
+            .transpose()
+    }
+
+    /// Load a tick range (inclusive) as an in-memory journal.
+    ///
+    /// The range is extended back to the nearest snapshot at or before
+    /// `start_tick`, so the returned journal can be replayed from its start.
+    pub fn load_range(&self, start_tick: Tick, end_tick: Tick) -> Result<Journal> {
+        let snapshot = self.snapshot_at_or_before(start_tick)?;
+        let from = snapshot.as_ref().map_or(0, |s| s.tick);
+        let entries = self.entries_in_range(from, end_tick)?;
+        let config = JournalConfig {
+            snapshot_interval: 0,
+            ..Default::default()
+        };
+        Ok(Journal::from_parts(
+            config,
+            entries,
+            snapshot.into_iter().collect(),
+        ))
+    }
+
+    /// Delete all stored entries and snapshots.
+    pub fn clear(&mut self) -> Result<()> {
+        let (entries, snapshots) = {
+            let r = self.store.db.r_transaction()?;
+            let entries: std::result::Result<Vec<StoredJournalEntry>, _> =
+                r.scan().primary::<StoredJournalEntry>()?.all()?.collect();
+            let snapshots: std::result::Result<Vec<StoredSnapshot>, _> =
+                r.scan().primary::<StoredSnapshot>()?.all()?.collect();
+            (
+                entries.map_err(|e| Error::Database(e.to_string()))?,
+                snapshots.map_err(|e| Error::Database(e.to_string()))?,
+            )
+        };
+
+        let rw = self.store.db.rw_transaction()?;
+        for entry in entries {
+            rw.remove(entry)?;
+        }
+        for snapshot in snapshots {
+            rw.remove(snapshot)?;
+        }
+        rw.commit()?;
+
+        self.next_seq = 0;
+        Ok(())
+    }
+}
+
+/// Persist records one at a time, each in its own transaction.
+///
+/// Prefer [`JournalStore::sync`] while recording, which batches them.
+impl JournalSink for JournalStore {
+    type Error = Error;
+
+    fn append_entry(&mut self, entry: &JournalEntry) -> Result<()> {
+        let stored = StoredJournalEntry::from_entry(self.next_seq, entry)
+            .map_err(|e| Error::Serialization(e.to_string()))?;
+        let rw = self.store.db.rw_transaction()?;
+        rw.upsert(stored)?;
+        rw.commit()?;
+        self.next_seq += 1;
+        Ok(())
+    }
+
+    fn append_snapshot(&mut self, snapshot: &Snapshot) -> Result<()> {
+        let stored = StoredSnapshot::from_snapshot(snapshot)
+            .map_err(|e| Error::Serialization(e.to_string()))?;
+        let rw = self.store.db.rw_transaction()?;
+        rw.upsert(stored)?;
+        rw.commit()?;
+        Ok(())
+    }
+}
+
+/// Records collected for one [`JournalStore::sync`] transaction.
+struct Batch {
+    next_seq: u64,
+    entries: Vec<StoredJournalEntry>,
+    snapshots: Vec<StoredSnapshot>,
+}
+
+impl JournalSink for Batch {
+    type Error = Error;
+
+    fn append_entry(&mut self, entry: &JournalEntry) -> Result<()> {
+        let stored = StoredJournalEntry::from_entry(self.next_seq, entry)
+            .map_err(|e| Error::Serialization(e.to_string()))?;
+        self.entries.push(stored);
+        self.next_seq += 1;
+        Ok(())
+    }
+
+    fn append_snapshot(&mut self, snapshot: &Snapshot) -> Result<()> {
+        let stored = StoredSnapshot::from_snapshot(snapshot)
+            .map_err(|e| Error::Serialization(e.to_string()))?;
+        self.snapshots.push(stored);
+        Ok(())
+    }
+}
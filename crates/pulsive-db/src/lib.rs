@@ -4,11 +4,46 @@
 //! - Entity definitions (schemas loaded from scripts)
 //! - Runtime entity instances
 //! - Event definitions and triggers
+//! - Game definitions of any kind as [`DefinitionRecord`]s, imported from
+//!   and exported to pulsive-script
+//! - Rollback history frames spilled from a `RollbackBuffer`
+//! - Journal entries and snapshots, streamed in by a [`JournalStore`]
+//! - Changed entities and globals, flushed in the background by a
+//!   [`WriteBehind`] according to a [`PersistencePolicy`]
+//! - Complete models in named save slots with metadata and checksums,
+//!   versioned by [`MODEL_SCHEMA_VERSION`]
+//!
+//! The native_db [`Store`] and, with the `sqlite` feature, `SqliteStore`
+//! implement [`StorageBackend`] for the state persistence they share.
+//!
+//! Stored data is versioned; [`Store::migrate`] applies [`Migrations`] when
+//! entity schemas change between releases.
+//!
+//! Persisted entities can be queried without loading a model, with filters,
+//! ordering and paging ([`Store::query`]).
 
+mod backend;
+mod definitions;
 mod error;
+mod journal;
+mod migration;
 mod models;
+mod persist;
 mod queries;
+mod slots;
+#[cfg(feature = "sqlite")]
+mod sqlite;
 mod store;
 
+pub use backend::{Changes, StorageBackend};
+pub use definitions::DefinitionRecord;
 pub use error::{Error, Result};
+pub use journal::JournalStore;
+pub use migration::{Migration, Migrations};
+pub use models::MODEL_SCHEMA_VERSION;
+pub use persist::{PersistencePolicy, WriteBehind};
+pub use queries::{CmpOp, Query};
+pub use slots::{SaveMetadata, SaveSlot};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
 pub use store::Store;
@@ -0,0 +1,378 @@
+//! Migrations for stored data.
+//!
+//! When entity schemas change between versions, a [`Migration`] transforms
+//! the records written by the old version. The store tracks the schema
+//! version it holds, and [`Store::migrate`] applies every newer migration in
+//! order, in one transaction:
+//!
+//! ```rust,ignore
+//! let migrations = Migrations::new()
+//!     .with_migration(Migration::rename_property(1, "nation", "manpower", "soldiers"))
+//!     .with_migration(
+//!         Migration::new(2, "Gold is stored in thousands")
+//!             .entities(
+//!                 |e| if let Some(g) = e.get_number("gold") { e.set("gold", g / 1000.0) },
+//!                 |e| if let Some(g) = e.get_number("gold") { e.set("gold", g * 1000.0) },
+//!             ),
+//!     );
+//! store.migrate(&migrations)?;
+//! ```
+//!
+//! Stored entities, globals and the models in save slots are migrated.
+
+use crate::error::{Error, Result};
+use crate::models::*;
+use crate::slots::crc32;
+use crate::store::Store;
+use pulsive_core::{DefId, Entity, ValueMap};
+
+type EntityTransform = Box<dyn Fn(&mut Entity) + Send + Sync>;
+type GlobalsTransform = Box<dyn Fn(&mut ValueMap) + Send + Sync>;
+
+/// A step between two schema versions.
+pub struct Migration {
+    version: u32,
+    description: String,
+    entities_up: Option<EntityTransform>,
+    entities_down: Option<EntityTransform>,
+    globals_up: Option<GlobalsTransform>,
+    globals_down: Option<GlobalsTransform>,
+    reversible: bool,
+}
+
+impl Migration {
+    /// Create a migration to a schema version.
+    pub fn new(version: u32, description: impl Into<String>) -> Self {
+        Self {
+            version,
+            description: description.into(),
+            entities_up: None,
+            entities_down: None,
+            globals_up: None,
+            globals_down: None,
+            reversible: true,
+        }
+    }
+
+    /// Create a migration that renames a property of one entity kind.
+    pub fn rename_property(
+        version: u32,
+        kind: impl Into<DefId>,
+        from: impl Into<String>,
+        to: impl Into<String>,
+    ) -> Self {
+        let (kind, from, to) = (kind.into(), from.into(), to.into());
+        let description = format!("Rename {}.{} to {}", kind, from, to);
+        let rename = |kind: DefId, from: String, to: String| {
+            move |entity: &mut Entity| {
+                if entity.kind == kind {
+                    if let Some(value) = entity.remove(&from) {
+                        entity.set(to.clone(), value);
+                    }
+                }
+            }
+        };
+        Self::new(version, description).entities(
+            rename(kind.clone(), from.clone(), to.clone()),
+            rename(kind, to, from),
+        )
+    }
+
+    /// Transform every stored entity, with the inverse transform.
+    pub fn entities(
+        mut self,
+        up: impl Fn(&mut Entity) + Send + Sync + 'static,
+        down: impl Fn(&mut Entity) + Send + Sync + 'static,
+    ) -> Self {
+        self.entities_up = Some(Box::new(up));
+        self.entities_down = Some(Box::new(down));
+        self
+    }
+
+    /// Transform the stored globals, with the inverse transform.
+    pub fn globals(
+        mut self,
+        up: impl Fn(&mut ValueMap) + Send + Sync + 'static,
+        down: impl Fn(&mut ValueMap) + Send + Sync + 'static,
+    ) -> Self {
+        self.globals_up = Some(Box::new(up));
+        self.globals_down = Some(Box::new(down));
+        self
+    }
+
+    /// Mark the migration as impossible to undo (e.g. it drops data).
+    pub fn irreversible(mut self) -> Self {
+        self.reversible = false;
+        self
+    }
+
+    /// Get the schema version this migration leads to.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Get the description.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn apply(&self, up: bool, entities: &mut [Entity], globals: &mut ValueMap) {
+        let (entity_transform, globals_transform) = if up {
+            (&self.entities_up, &self.globals_up)
+        } else {
+            (&self.entities_down, &self.globals_down)
+        };
+        if let Some(transform) = entity_transform {
+            entities.iter_mut().for_each(transform);
+        }
+        if let Some(transform) = globals_transform {
+            transform(globals);
+        }
+    }
+}
+
+/// An ordered set of migrations.
+#[derive(Default)]
+pub struct Migrations {
+    steps: Vec<Migration>,
+}
+
+impl Migrations {
+    /// Create an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a migration, keeping the steps ordered by version.
+    pub fn with_migration(mut self, migration: Migration) -> Self {
+        let index = self
+            .steps
+            .partition_point(|m| m.version < migration.version);
+        self.steps.insert(index, migration);
+        self
+    }
+
+    /// Get the newest schema version (0 without migrations).
+    pub fn latest(&self) -> u32 {
+        self.steps.last().map_or(0, |m| m.version)
+    }
+
+    /// Get the steps, ordered by version.
+    pub fn steps(&self) -> &[Migration] {
+        &self.steps
+    }
+
+    /// Check the steps and pick those between two versions, in order.
+    fn path(&self, from: u32, to: u32) -> Result<Vec<&Migration>> {
+        if let Some(pair) = self.steps.windows(2).find(|w| w[0].version == w[1].version) {
+            return Err(Error::Migration(format!(
+                "duplicate migration to version {}",
+                pair[0].version
+            )));
+        }
+        if to > self.latest() {
+            return Err(Error::Migration(format!("no migration to version {}", to)));
+        }
+        if from <= to {
+            Ok(self
+                .steps
+                .iter()
+                .filter(|m| m.version > from && m.version <= to)
+                .collect())
+        } else {
+            let steps: Vec<&Migration> = self
+                .steps
+                .iter()
+                .rev()
+                .filter(|m| m.version > to && m.version <= from)
+                .collect();
+            match steps.iter().find(|m| !m.reversible) {
+                Some(m) => Err(Error::Migration(format!(
+                    "migration to version {} cannot be undone",
+                    m.version
+                ))),
+                None => Ok(steps),
+            }
+        }
+    }
+}
+
+const SCHEMA_KEY: &str = "schema";
+
+impl Store {
+    /// Get the schema version of the stored data (0 if never migrated).
+    pub fn schema_version(&self) -> Result<u32> {
+        let r = self.db.r_transaction()?;
+        let stored: Option<StoredSchemaVersion> = r.get().primary(SCHEMA_KEY.to_string())?;
+        Ok(stored.map_or(0, |s| s.version))
+    }
+
+    /// Migrate the stored data to the latest version.
+    ///
+    /// Returns the new schema version.
+    pub fn migrate(&self, migrations: &Migrations) -> Result<u32> {
+        self.migrate_to(migrations, migrations.latest())
+    }
+
+    /// Migrate the stored data up or down to a version.
+    ///
+    /// All steps are applied in one transaction, so a failure leaves the
+    /// data at its previous version.
+    pub fn migrate_to(&self, migrations: &Migrations, version: u32) -> Result<u32> {
+        let current = self.schema_version()?;
+        let steps = migrations.path(current, version)?;
+        if steps.is_empty() {
+            return Ok(current);
+        }
+        let up = version > current;
+
+        let rw = self.db.rw_transaction()?;
+
+        // Entities and globals
+        let stored: Vec<StoredEntity> = rw
+            .scan()
+            .primary::<StoredEntity>()?
+            .all()?
+            .collect::<std::result::Result<_, _>>()?;
+        let mut entities: Vec<Entity> = stored
+            .iter()
+            .map(StoredEntity::to_entity)
+            .collect::<std::result::Result<_, _>>()?;
+        let mut globals = rw
+            .get()
+            .primary::<StoredGlobals>("globals".to_string())?
+            .map(|s| s.to_globals())
+            .transpose()?
+            .unwrap_or_default();
+        for step in &steps {
+            step.apply(up, &mut entities, &mut globals);
+        }
+        for old in stored {
+            rw.remove(old)?;
+        }
+        for entity in &entities {
+            rw.upsert(StoredEntity::from_entity(entity)?)?;
+        }
+        rw.upsert(StoredGlobals::from_globals(&globals)?)?;
+
+        // Models in save slots
+        let saves: Vec<StoredModel> = rw
+            .scan()
+            .primary::<StoredModel>()?
+            .all()?
+            .collect::<std::result::Result<_, _>>()?;
+        for save in saves {
+            let mut model = save
+                .to_model()
+                .map_err(|e| Error::Serialization(format!("slot {}: {}", save.slot, e)))?;
+            let mut entities: Vec<Entity> = model.entities().iter().cloned().collect();
+            let mut globals = model.globals().clone();
+            for step in &steps {
+                step.apply(up, &mut entities, &mut globals);
+            }
+            for entity in entities {
+                model.entities_mut().insert(entity);
+            }
+            *model.globals_mut() = globals;
+            let migrated = StoredModel::from_model(&save.slot, &model)
+                .map_err(|e| Error::Serialization(e.to_string()))?;
+            let meta: Option<StoredSaveSlot> = rw.get().primary(save.slot.clone())?;
+            if let Some(mut meta) = meta {
+                meta.checksum = crc32(&migrated.data);
+                rw.upsert(meta)?;
+            }
+            rw.upsert(migrated)?;
+        }
+
+        rw.upsert(StoredSchemaVersion {
+            id: SCHEMA_KEY.to_string(),
+            version,
+        })?;
+        rw.commit()?;
+        Ok(version)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::{Model, Value};
+
+    fn migrations() -> Migrations {
+        Migrations::new()
+            .with_migration(Migration::rename_property(
+                2, "nation", "manpower", "soldiers",
+            ))
+            .with_migration(Migration::new(1, "Start tracking the year").globals(
+                |g| {
+                    g.insert("year", Value::Int(1444));
+                },
+                |g| {
+                    g.remove("year");
+                },
+            ))
+    }
+
+    #[test]
+    fn test_migrate_from_v1() {
+        let store = Store::in_memory().unwrap();
+        let mut model = Model::new();
+        let id = {
+            let nation = model.entities_mut().create("nation");
+            nation.set("manpower", 40.0);
+            nation.id
+        };
+        store
+            .save_entity(model.entities().get(id).unwrap())
+            .unwrap();
+        store.save_model(&model, "slot").unwrap();
+
+        let migrations = migrations();
+        assert_eq!(store.migrate_to(&migrations, 1).unwrap(), 1);
+        assert_eq!(store.schema_version().unwrap(), 1);
+        assert_eq!(
+            store.load_globals().unwrap().get("year"),
+            Some(&Value::Int(1444))
+        );
+
+        assert_eq!(store.migrate(&migrations).unwrap(), 2);
+        let nation = store.load_entity(id).unwrap().unwrap();
+        assert_eq!(nation.get_number("soldiers"), Some(40.0));
+        assert_eq!(nation.get("manpower"), None);
+        let saved = store.load_model("slot").unwrap().unwrap();
+        let nation = saved.entities().get(id).unwrap();
+        assert_eq!(nation.get_number("soldiers"), Some(40.0));
+        assert_eq!(store.verify_slot("slot").unwrap(), Some(true));
+
+        // And back down
+        assert_eq!(store.migrate_to(&migrations, 0).unwrap(), 0);
+        let nation = store.load_entity(id).unwrap().unwrap();
+        assert_eq!(nation.get_number("manpower"), Some(40.0));
+        assert_eq!(store.load_globals().unwrap().get("year"), None);
+    }
+
+    #[test]
+    fn test_invalid_paths() {
+        let store = Store::in_memory().unwrap();
+        let irreversible =
+            Migrations::new().with_migration(Migration::new(1, "Drop history").irreversible());
+        store.migrate(&irreversible).unwrap();
+        assert!(matches!(
+            store.migrate_to(&irreversible, 0),
+            Err(Error::Migration(_))
+        ));
+        assert!(matches!(
+            store.migrate_to(&irreversible, 5),
+            Err(Error::Migration(_))
+        ));
+
+        let duplicate = Migrations::new()
+            .with_migration(Migration::new(2, "a"))
+            .with_migration(Migration::new(2, "b"));
+        assert!(matches!(
+            store.migrate(&duplicate),
+            Err(Error::Migration(_))
+        ));
+        assert_eq!(store.schema_version().unwrap(), 1);
+    }
+}
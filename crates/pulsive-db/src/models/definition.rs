@@ -97,6 +97,30 @@ pub struct StoredModifierDef {
     pub stackable: bool,
 }
 
+/// Stored definition of any kind, as serialized text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 15, version = 1)]
+#[native_db]
+pub struct StoredDefinition {
+    /// Primary key - "kind/id".
+    #[primary_key]
+    pub key: String,
+    /// Definition kind (e.g. "resource", "event").
+    #[secondary_key]
+    pub kind: String,
+    /// Definition ID.
+    pub id: String,
+    /// Serialized definition (RON).
+    pub data: String,
+}
+
+impl StoredDefinition {
+    /// Primary key of a definition.
+    pub fn key(kind: &str, id: &str) -> String {
+        format!("{}/{}", kind, id)
+    }
+}
+
 /// Stored scheduled event.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[native_model(id = 20, version = 1)]
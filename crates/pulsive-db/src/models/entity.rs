@@ -5,9 +5,21 @@ use native_model::{native_model, Model};
 use pulsive_core::{DefId, EntityId, ValueMap};
 use serde::{Deserialize, Serialize};
 
+/// Version of the saved model format.
+///
+/// Bumped whenever the serialized layout of `Model` changes, so saves
+/// written by an incompatible version are rejected with
+/// [`Error::SchemaVersion`](crate::Error::SchemaVersion) instead of misread.
+///
+/// 1. initial layout
+/// 2. timed flag expiry on entities
+/// 3. generational entity IDs; the entity store is saved as its slots
+/// 4. entity tags
+pub const MODEL_SCHEMA_VERSION: u32 = 4;
+
 /// Stored entity in the database.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[native_model(id = 1, version = 1)]
+#[native_model(id = 1, version = 3, from = StoredEntityV2)]
 #[native_db]
 pub struct StoredEntity {
     /// Primary key - entity ID.
@@ -20,32 +32,131 @@ pub struct StoredEntity {
     pub properties: Vec<u8>,
     /// Active flags.
     pub flags: Vec<String>,
+    /// Expiry ticks of timed flags, in the order they were set.
+    pub flag_expiry: Vec<(String, u64)>,
+    /// Tags.
+    pub tags: Vec<String>,
+}
+
+/// Stored entity as written before tags were saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 1, version = 2, from = StoredEntityV1)]
+#[native_db]
+pub struct StoredEntityV2 {
+    /// Primary key - entity ID.
+    #[primary_key]
+    pub id: u64,
+    /// Entity type (kind).
+    #[secondary_key]
+    pub kind: String,
+    /// Serialized properties.
+    pub properties: Vec<u8>,
+    /// Active flags.
+    pub flags: Vec<String>,
+    /// Expiry ticks of timed flags, in the order they were set.
+    pub flag_expiry: Vec<(String, u64)>,
+}
+
+/// Stored entity as written before timed flags were saved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 1, version = 1)]
+#[native_db]
+pub struct StoredEntityV1 {
+    /// Primary key - entity ID.
+    #[primary_key]
+    pub id: u64,
+    /// Entity type (kind).
+    #[secondary_key]
+    pub kind: String,
+    /// Serialized properties.
+    pub properties: Vec<u8>,
+    /// Active flags.
+    pub flags: Vec<String>,
+}
+
+impl From<StoredEntityV2> for StoredEntity {
+    fn from(entity: StoredEntityV2) -> Self {
+        Self {
+            id: entity.id,
+            kind: entity.kind,
+            properties: entity.properties,
+            flags: entity.flags,
+            flag_expiry: entity.flag_expiry,
+            tags: Vec::new(),
+        }
+    }
+}
+
+impl From<StoredEntity> for StoredEntityV2 {
+    fn from(entity: StoredEntity) -> Self {
+        Self {
+            id: entity.id,
+            kind: entity.kind,
+            properties: entity.properties,
+            flags: entity.flags,
+            flag_expiry: entity.flag_expiry,
+        }
+    }
+}
+
+impl From<StoredEntityV1> for StoredEntityV2 {
+    fn from(entity: StoredEntityV1) -> Self {
+        Self {
+            id: entity.id,
+            kind: entity.kind,
+            properties: entity.properties,
+            flags: entity.flags,
+            flag_expiry: Vec::new(),
+        }
+    }
+}
+
+impl From<StoredEntityV2> for StoredEntityV1 {
+    fn from(entity: StoredEntityV2) -> Self {
+        Self {
+            id: entity.id,
+            kind: entity.kind,
+            properties: entity.properties,
+            flags: entity.flags,
+        }
+    }
 }
 
 impl StoredEntity {
     /// Create from a pulsive Entity.
-    pub fn from_entity(entity: &pulsive_core::Entity) -> Self {
-        let properties = bincode::serialize(&entity.properties).unwrap_or_default();
-        Self {
+    pub fn from_entity(entity: &pulsive_core::Entity) -> Result<Self, bincode::Error> {
+        Ok(Self {
             id: entity.id.raw(),
             kind: entity.kind.as_str().to_string(),
-            properties,
+            properties: bincode::serialize(&entity.properties)?,
             flags: entity
                 .flags
                 .iter()
                 .map(|f| f.as_str().to_string())
                 .collect(),
-        }
+            flag_expiry: entity
+                .flag_expiry
+                .iter()
+                .map(|(f, tick)| (f.as_str().to_string(), *tick))
+                .collect(),
+            tags: entity.tags.iter().cloned().collect(),
+        })
     }
 
     /// Convert to a pulsive Entity.
-    pub fn to_entity(&self) -> pulsive_core::Entity {
-        let properties: ValueMap = bincode::deserialize(&self.properties).unwrap_or_default();
+    pub fn to_entity(&self) -> Result<pulsive_core::Entity, bincode::Error> {
+        let properties: ValueMap = bincode::deserialize(&self.properties)?;
         let mut entity =
             pulsive_core::Entity::new(EntityId::new(self.id), DefId::new(self.kind.clone()));
         entity.properties = properties;
         entity.flags = self.flags.iter().map(|f| DefId::new(f.clone())).collect();
-        entity
+        entity.flag_expiry = self
+            .flag_expiry
+            .iter()
+            .map(|(f, tick)| (DefId::new(f.clone()), *tick))
+            .collect();
+        entity.tags = self.tags.iter().cloned().collect();
+        Ok(entity)
     }
 }
 
@@ -63,17 +174,16 @@ pub struct StoredGlobals {
 
 impl StoredGlobals {
     /// Create from a ValueMap.
-    pub fn from_globals(globals: &ValueMap) -> Self {
-        let data = bincode::serialize(globals).unwrap_or_default();
-        Self {
+    pub fn from_globals(globals: &ValueMap) -> Result<Self, bincode::Error> {
+        Ok(Self {
             id: "globals".to_string(),
-            data,
-        }
+            data: bincode::serialize(globals)?,
+        })
     }
 
     /// Convert to a ValueMap.
-    pub fn to_globals(&self) -> ValueMap {
-        bincode::deserialize(&self.data).unwrap_or_default()
+    pub fn to_globals(&self) -> Result<ValueMap, bincode::Error> {
+        bincode::deserialize(&self.data)
     }
 }
 
@@ -169,3 +279,101 @@ impl StoredRng {
         pulsive_core::Rng::from_state(self.state)
     }
 }
+
+/// Stored rollback history frame.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 5, version = 1)]
+#[native_db]
+pub struct StoredFrame {
+    /// Tick of the frame.
+    #[primary_key]
+    pub tick: u64,
+    /// Serialized model (bincode).
+    pub data: Vec<u8>,
+}
+
+impl StoredFrame {
+    /// Create from a model snapshot.
+    pub fn from_model(tick: u64, model: &pulsive_core::Model) -> Result<Self, bincode::Error> {
+        Ok(Self {
+            tick,
+            data: bincode::serialize(model)?,
+        })
+    }
+
+    /// Convert to a model snapshot.
+    pub fn to_model(&self) -> Result<pulsive_core::Model, bincode::Error> {
+        bincode::deserialize(&self.data)
+    }
+}
+
+/// Stored complete model, in a named save slot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 6, version = 1)]
+#[native_db]
+pub struct StoredModel {
+    /// Primary key - slot name.
+    #[primary_key]
+    pub slot: String,
+    /// Model format version the data was written with.
+    pub schema_version: u32,
+    /// Tick of the model when saved.
+    pub tick: u64,
+    /// Serialized model (bincode).
+    pub data: Vec<u8>,
+}
+
+impl StoredModel {
+    /// Create from a model.
+    pub fn from_model(slot: &str, model: &pulsive_core::Model) -> Result<Self, bincode::Error> {
+        Ok(Self {
+            slot: slot.to_string(),
+            schema_version: MODEL_SCHEMA_VERSION,
+            tick: model.time.tick,
+            data: bincode::serialize(model)?,
+        })
+    }
+
+    /// Convert to a model.
+    pub fn to_model(&self) -> Result<pulsive_core::Model, bincode::Error> {
+        bincode::deserialize(&self.data)
+    }
+}
+
+/// Stored schema version, advanced by migrations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 9, version = 1)]
+#[native_db]
+pub struct StoredSchemaVersion {
+    /// Always "schema" - single row.
+    #[primary_key]
+    pub id: String,
+    /// Version of the last applied migration.
+    pub version: u32,
+}
+
+/// Stored save slot metadata.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[native_model(id = 14, version = 1)]
+#[native_db]
+pub struct StoredSaveSlot {
+    /// Primary key - slot name.
+    #[primary_key]
+    pub slot: String,
+    /// User-facing label.
+    pub label: String,
+    /// Creation time (seconds since the Unix epoch).
+    pub created_at: u64,
+    /// Last save time (seconds since the Unix epoch).
+    pub saved_at: u64,
+    /// Tick of the saved model.
+    pub tick: u64,
+    /// Play time in milliseconds.
+    pub play_time_ms: u64,
+    /// Thumbnail image bytes.
+    pub thumbnail: Vec<u8>,
+    /// CRC-32 of the saved model data.
+    pub checksum: u32,
+    /// Model format version of the saved data.
+    pub schema_version: u32,
+}
@@ -0,0 +1,70 @@
+//! Journal models for database storage.
+
+use native_db::*;
+use native_model::{native_model, Model};
+use serde::{Deserialize, Serialize};
+
+/// Stored journal entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 7, version = 1)]
+#[native_db]
+pub struct StoredJournalEntry {
+    /// Primary key - position in the journal.
+    #[primary_key]
+    pub seq: u64,
+    /// Tick the entry was recorded at.
+    #[secondary_key]
+    pub tick: u64,
+    /// Serialized entry (bincode).
+    pub data: Vec<u8>,
+}
+
+impl StoredJournalEntry {
+    /// Create from a journal entry.
+    pub fn from_entry(
+        seq: u64,
+        entry: &pulsive_core::JournalEntry,
+    ) -> Result<Self, bincode::Error> {
+        Ok(Self {
+            seq,
+            tick: entry.tick(),
+            data: bincode::serialize(entry)?,
+        })
+    }
+
+    /// Convert to a journal entry.
+    pub fn to_entry(&self) -> Result<pulsive_core::JournalEntry, bincode::Error> {
+        bincode::deserialize(&self.data)
+    }
+}
+
+/// Stored journal snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[native_model(id = 8, version = 1)]
+#[native_db]
+pub struct StoredSnapshot {
+    /// Primary key - snapshot ID.
+    #[primary_key]
+    pub id: u64,
+    /// Tick of the snapshot.
+    #[secondary_key]
+    pub tick: u64,
+    /// Serialized snapshot (bincode).
+    pub data: Vec<u8>,
+}
+
+impl StoredSnapshot {
+    /// Create from a snapshot.
+    pub fn from_snapshot(snapshot: &pulsive_core::Snapshot) -> Result<Self, bincode::Error> {
+        Ok(Self {
+            id: snapshot.id.0,
+            tick: snapshot.tick,
+            data: bincode::serialize(snapshot)?,
+        })
+    }
+
+    /// Convert to a snapshot.
+    pub fn to_snapshot(&self) -> Result<pulsive_core::Snapshot, bincode::Error> {
+        bincode::deserialize(&self.data)
+    }
+}
@@ -2,6 +2,8 @@
 
 mod definition;
 mod entity;
+mod journal;
 
 pub use definition::*;
 pub use entity::*;
+pub use journal::*;
@@ -0,0 +1,374 @@
+//! Write-behind auto-persistence.
+//!
+//! A [`WriteBehind`] receives the writes applied each tick, tracks which
+//! entities and globals they touched, and flushes only those to a
+//! [`StorageBackend`] on a background thread whenever its
+//! [`PersistencePolicy`] says so. Long-running servers get durability without
+//! saving the full model every tick.
+//!
+//! ```rust,ignore
+//! let mut persist = WriteBehind::new(Arc::new(store), PersistencePolicy::default());
+//! loop {
+//!     persist.tick(&mut runtime, &mut model)?;
+//! }
+//! persist.finish(&model)?;
+//! ```
+
+use crate::backend::{Changes, StorageBackend};
+use crate::error::{Error, Result};
+use pulsive_core::{EntityId, Model, PendingWrite, Runtime, Tick, UpdateResult, WriteSet};
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// When a [`WriteBehind`] flushes changes to the store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PersistencePolicy {
+    /// Flush every N ticks (0 = disabled).
+    pub every_ticks: u64,
+    /// Flush once this many entities have unsaved changes (0 = disabled).
+    pub dirty_threshold: usize,
+    /// Flush remaining changes in [`WriteBehind::finish`].
+    pub on_shutdown: bool,
+}
+
+impl Default for PersistencePolicy {
+    fn default() -> Self {
+        Self {
+            every_ticks: 100,
+            dirty_threshold: 0,
+            on_shutdown: true,
+        }
+    }
+}
+
+/// Write-behind persistence of a running model.
+///
+/// The first flush writes every entity, so the backend matches the model;
+/// later flushes only write what changed since.
+pub struct WriteBehind {
+    policy: PersistencePolicy,
+    /// Entities changed since the last flush.
+    dirty: HashSet<EntityId>,
+    /// Entities destroyed since the last flush.
+    destroyed: HashSet<EntityId>,
+    /// Whether any global changed since the last flush.
+    globals_dirty: bool,
+    /// Whether entities were spawned since the last flush.
+    spawned: bool,
+    /// Entities in the backend as of the last flush (`None` before the
+    /// first flush).
+    persisted: Option<HashSet<EntityId>>,
+    last_flush: Tick,
+    sender: Option<Sender<Changes>>,
+    errors: Receiver<Error>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl WriteBehind {
+    /// Start write-behind persistence to a storage backend.
+    pub fn new<B: StorageBackend + ?Sized + 'static>(
+        backend: Arc<B>,
+        policy: PersistencePolicy,
+    ) -> Self {
+        let (sender, jobs) = mpsc::channel::<Changes>();
+        let (error_sender, errors) = mpsc::channel();
+        let worker = thread::spawn(move || {
+            for changes in jobs {
+                if let Err(e) = backend.write_changes(&changes) {
+                    let _ = error_sender.send(e);
+                }
+            }
+        });
+        Self {
+            policy,
+            dirty: HashSet::new(),
+            destroyed: HashSet::new(),
+            globals_dirty: false,
+            spawned: false,
+            persisted: None,
+            last_flush: 0,
+            sender: Some(sender),
+            errors,
+            worker: Some(worker),
+        }
+    }
+
+    /// Get the persistence policy.
+    pub fn policy(&self) -> &PersistencePolicy {
+        &self.policy
+    }
+
+    /// Number of entities with unsaved changes.
+    pub fn dirty_count(&self) -> usize {
+        self.dirty.len() + self.destroyed.len()
+    }
+
+    /// Advance the runtime by one tick and record its writes.
+    ///
+    /// Enables write logging on the runtime if needed.
+    pub fn tick(&mut self, runtime: &mut Runtime, model: &mut Model) -> Result<UpdateResult> {
+        if !runtime.is_write_logging() {
+            runtime.set_write_logging(true);
+        }
+        let result = runtime.tick(model);
+        let writes = runtime.take_write_log();
+        self.record(model, &writes)?;
+        Ok(result)
+    }
+
+    /// Record writes applied to the model, flushing if the policy says so.
+    ///
+    /// Also reports any error from a previous background flush.
+    pub fn record(&mut self, model: &Model, writes: &WriteSet) -> Result<()> {
+        self.take_error()?;
+        for write in writes.iter() {
+            match write {
+                PendingWrite::SetProperty { entity_id, .. }
+                | PendingWrite::ModifyProperty { entity_id, .. }
+                | PendingWrite::AddFlag { entity_id, .. }
+                | PendingWrite::AddFlagFor { entity_id, .. }
+                | PendingWrite::RemoveFlag { entity_id, .. }
+                | PendingWrite::AddTag { entity_id, .. }
+                | PendingWrite::RemoveTag { entity_id, .. } => {
+                    self.dirty.insert(*entity_id);
+                }
+                PendingWrite::SetGlobal { .. } | PendingWrite::ModifyGlobal { .. } => {
+                    self.globals_dirty = true;
+                }
+                PendingWrite::SpawnEntity { .. } => self.spawned = true,
+                PendingWrite::DestroyEntity { id } => {
+                    self.dirty.remove(id);
+                    self.destroyed.insert(*id);
+                }
+            }
+        }
+
+        let tick = model.current_tick();
+        let policy = &self.policy;
+        let due = self.persisted.is_none()
+            || (policy.every_ticks > 0 && tick >= self.last_flush + policy.every_ticks)
+            || (policy.dirty_threshold > 0 && self.dirty_count() >= policy.dirty_threshold);
+        if due {
+            self.flush(model)?;
+        }
+        Ok(())
+    }
+
+    /// Queue the unsaved changes for writing.
+    pub fn flush(&mut self, model: &Model) -> Result<()> {
+        let entities = model.entities();
+        let first = self.persisted.is_none();
+        let persisted = self.persisted.get_or_insert_with(HashSet::new);
+        let mut ids: HashSet<EntityId> = self.dirty.drain().collect();
+        // Spawns carry no ID, and may reuse a destroyed entity's slot, so
+        // look for entities the backend has not seen
+        if self.spawned || first {
+            ids.extend(entities.ids().filter(|id| !persisted.contains(id)));
+        }
+        let changes = Changes {
+            entities: ids
+                .iter()
+                .filter_map(|id| entities.get(*id))
+                .cloned()
+                .collect(),
+            destroyed: self.destroyed.drain().collect(),
+            globals: (self.globals_dirty || first).then(|| model.globals().clone()),
+            clock: model.time.clone(),
+            rng: model.rng.clone(),
+        };
+
+        for id in &changes.destroyed {
+            persisted.remove(id);
+        }
+        persisted.extend(changes.entities.iter().map(|entity| entity.id));
+        self.globals_dirty = false;
+        self.spawned = false;
+        self.last_flush = model.current_tick();
+
+        self.sender
+            .as_ref()
+            .and_then(|s| s.send(changes).ok())
+            .ok_or_else(|| Error::Database("write-behind worker stopped".to_string()))
+    }
+
+    /// Stop persistence, flushing remaining changes if the policy says so.
+    ///
+    /// Waits for queued writes and returns the first error among them.
+    pub fn finish(mut self, model: &Model) -> Result<()> {
+        if self.policy.on_shutdown {
+            self.flush(model)?;
+        }
+        self.join();
+        self.take_error()
+    }
+
+    fn take_error(&self) -> Result<()> {
+        match self.errors.try_recv() {
+            Ok(e) => Err(e),
+            Err(_) => Ok(()),
+        }
+    }
+
+    fn join(&mut self) {
+        self.sender = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for WriteBehind {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::Store;
+    use pulsive_core::{DefId, Value, ValueMap};
+
+    fn manual() -> PersistencePolicy {
+        PersistencePolicy {
+            every_ticks: 0,
+            dirty_threshold: 0,
+            on_shutdown: true,
+        }
+    }
+
+    fn set(entity_id: EntityId, key: &str, value: f64) -> PendingWrite {
+        PendingWrite::SetProperty {
+            entity_id,
+            key: key.to_string(),
+            value: Value::Float(value),
+        }
+    }
+
+    #[test]
+    fn test_flushes_only_dirty_entities() {
+        let store = Arc::new(Store::in_memory().unwrap());
+        let mut model = Model::new();
+        let a = model.entities_mut().create("nation").id;
+        let b = model.entities_mut().create("nation").id;
+        model.entities_mut().get_mut(a).unwrap().set("gold", 1.0);
+
+        // The first record flushes every entity
+        let mut persist = WriteBehind::new(Arc::clone(&store), manual());
+        persist.record(&model, &WriteSet::new()).unwrap();
+        assert_eq!(persist.dirty_count(), 0);
+
+        // Only b is recorded as written
+        model.entities_mut().get_mut(a).unwrap().set("gold", 2.0);
+        model.entities_mut().get_mut(b).unwrap().set("gold", 3.0);
+        let mut writes = WriteSet::new();
+        writes.push(set(b, "gold", 3.0));
+        persist.record(&model, &writes).unwrap();
+        assert_eq!(persist.dirty_count(), 1);
+        persist.finish(&model).unwrap();
+
+        let gold = |id| store.load_entity(id).unwrap().unwrap().get_number("gold");
+        assert_eq!(gold(a), Some(1.0));
+        assert_eq!(gold(b), Some(3.0));
+    }
+
+    #[test]
+    fn test_dirty_threshold_flushes() {
+        let store = Arc::new(Store::in_memory().unwrap());
+        let mut model = Model::new();
+        let ids: Vec<EntityId> = (0..3)
+            .map(|_| model.entities_mut().create("unit").id)
+            .collect();
+        let policy = PersistencePolicy {
+            dirty_threshold: 2,
+            ..manual()
+        };
+        let mut persist = WriteBehind::new(Arc::clone(&store), policy);
+        persist.record(&model, &WriteSet::new()).unwrap();
+
+        let mut writes = WriteSet::new();
+        writes.push(set(ids[0], "hp", 5.0));
+        persist.record(&model, &writes).unwrap();
+        assert_eq!(persist.dirty_count(), 1);
+        writes.push(set(ids[1], "hp", 5.0));
+        persist.record(&model, &writes).unwrap();
+        assert_eq!(persist.dirty_count(), 0);
+    }
+
+    #[test]
+    fn test_destroy_and_reload() {
+        let store = Arc::new(Store::in_memory().unwrap());
+        let mut model = Model::new();
+        let kept = model.entities_mut().create("city").id;
+        let razed = model.entities_mut().create("city").id;
+        let mut persist = WriteBehind::new(Arc::clone(&store), manual());
+        persist.record(&model, &WriteSet::new()).unwrap();
+
+        model.entities_mut().remove(razed);
+        model.set_global("wars", 1i64);
+        model.advance_tick();
+        let mut writes = WriteSet::new();
+        writes.push(PendingWrite::DestroyEntity { id: razed });
+        writes.push(PendingWrite::SetGlobal {
+            key: "wars".to_string(),
+            value: Value::Int(1),
+        });
+        persist.record(&model, &writes).unwrap();
+        persist.finish(&model).unwrap();
+
+        let entities = store.load_all_entities().unwrap();
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].id, kept);
+        assert_eq!(
+            store.load_globals().unwrap().get("wars"),
+            Some(&Value::Int(1))
+        );
+        assert_eq!(store.load_clock().unwrap().unwrap().tick, 1);
+    }
+
+    #[test]
+    fn test_spawn_into_reused_slot() {
+        let store = Arc::new(Store::in_memory().unwrap());
+        let mut model = Model::new();
+        let mut persist = WriteBehind::new(Arc::clone(&store), manual());
+        persist.record(&model, &WriteSet::new()).unwrap();
+
+        let spawn = || {
+            let mut writes = WriteSet::new();
+            writes.push(PendingWrite::SpawnEntity {
+                kind: DefId::new("army"),
+                properties: ValueMap::new(),
+            });
+            writes
+        };
+        let first = model.entities_mut().create("army").id;
+        persist.record(&model, &spawn()).unwrap();
+        persist.flush(&model).unwrap();
+
+        // The new army reuses the destroyed one's slot, with a higher ID
+        // than the next fresh slot
+        model.entities_mut().remove(first);
+        let reused = model.entities_mut().create("army").id;
+        assert_eq!(reused.index(), first.index());
+        let mut writes = spawn();
+        writes.push(PendingWrite::DestroyEntity { id: first });
+        persist.record(&model, &writes).unwrap();
+        persist.flush(&model).unwrap();
+
+        let fresh = model.entities_mut().create("army").id;
+        assert!(fresh.raw() < reused.raw());
+        persist.record(&model, &spawn()).unwrap();
+        persist.finish(&model).unwrap();
+
+        let mut ids: Vec<EntityId> = store
+            .load_all_entities()
+            .unwrap()
+            .iter()
+            .map(|entity| entity.id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec![reused, fresh]);
+    }
+}
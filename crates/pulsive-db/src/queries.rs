@@ -1,11 +1,38 @@
 //! Common query patterns for the database.
+//!
+//! [`Store::query`] builds typed queries over persisted entities:
+//!
+//! ```rust,ignore
+//! use pulsive_db::CmpOp;
+//!
+//! let richest = store
+//!     .query("nation")
+//!     .filter("gold", CmpOp::Gt, 100.0)
+//!     .order_by_desc("gold")
+//!     .limit(10)
+//!     .fetch()?;
+//! ```
 
 use crate::error::{Error, Result};
 use crate::models::*;
 use crate::store::Store;
-use pulsive_core::Entity;
+use pulsive_core::{DefId, Entity, Value};
+use std::cmp::Ordering;
 
 impl Store {
+    /// Start a query over persisted entities of a kind.
+    pub fn query(&self, kind: impl Into<String>) -> Query<'_> {
+        Query {
+            store: self,
+            kind: kind.into(),
+            filters: Vec::new(),
+            flags: Vec::new(),
+            order: None,
+            offset: 0,
+            limit: None,
+        }
+    }
+
     /// Get all entities of a specific kind.
     pub fn entities_by_kind(&self, kind: &str) -> Result<Vec<Entity>> {
         let r = self.db.r_transaction()?;
@@ -13,7 +40,10 @@ impl Store {
         let iter = scan.start_with(kind)?;
         let entities: std::result::Result<Vec<StoredEntity>, _> = iter.collect();
         let entities = entities.map_err(|e| Error::Database(e.to_string()))?;
-        Ok(entities.into_iter().map(|e| e.to_entity()).collect())
+        Ok(entities
+            .iter()
+            .map(StoredEntity::to_entity)
+            .collect::<std::result::Result<_, _>>()?)
     }
 
     /// Count entities of a specific kind.
@@ -32,10 +62,10 @@ impl Store {
         let all: std::result::Result<Vec<StoredEntity>, _> = iter.collect();
         let all = all.map_err(|e| Error::Database(e.to_string()))?;
         Ok(all
-            .into_iter()
+            .iter()
             .filter(|e| e.flags.contains(&flag.to_string()))
-            .map(|e| e.to_entity())
-            .collect())
+            .map(StoredEntity::to_entity)
+            .collect::<std::result::Result<_, _>>()?)
     }
 
     /// Get scheduled events for a specific tick.
@@ -76,3 +106,276 @@ impl Store {
         defs.map_err(|e| Error::Database(e.to_string()))
     }
 }
+
+/// Comparison applied by [`Query::filter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    /// Equal.
+    Eq,
+    /// Not equal (also matches a missing property).
+    Ne,
+    /// Less than.
+    Lt,
+    /// Less than or equal.
+    Le,
+    /// Greater than.
+    Gt,
+    /// Greater than or equal.
+    Ge,
+}
+
+impl CmpOp {
+    /// Check a property value against an expected value.
+    fn matches(self, actual: Option<&Value>, expected: &Value) -> bool {
+        let Some(actual) = actual else {
+            return self == CmpOp::Ne;
+        };
+        match (self, compare(actual, expected)) {
+            (CmpOp::Eq, o) => o == Some(Ordering::Equal),
+            (CmpOp::Ne, o) => o != Some(Ordering::Equal),
+            (_, None) => false,
+            (CmpOp::Lt, Some(o)) => o.is_lt(),
+            (CmpOp::Le, Some(o)) => o.is_le(),
+            (CmpOp::Gt, Some(o)) => o.is_gt(),
+            (CmpOp::Ge, Some(o)) => o.is_ge(),
+        }
+    }
+}
+
+/// Order two values: numbers numerically, strings lexically, others by
+/// equality only.
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a.as_float(), b.as_float()) {
+        (Some(a), Some(b)) => a.partial_cmp(&b),
+        _ => match (a.as_str(), b.as_str()) {
+            (Some(a), Some(b)) => Some(a.cmp(b)),
+            _ => (a == b).then_some(Ordering::Equal),
+        },
+    }
+}
+
+/// Order two values for sorting, totally: numbers first (NaN after every
+/// other number), then strings, then other values, which tie.
+fn sort_order(a: &Value, b: &Value) -> Ordering {
+    let rank = |v: &Value| match (v.as_float(), v.as_str()) {
+        (Some(_), _) => 0,
+        (None, Some(_)) => 1,
+        (None, None) => 2,
+    };
+    rank(a)
+        .cmp(&rank(b))
+        .then_with(|| match (a.as_float(), b.as_float()) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            _ => a.as_str().cmp(&b.as_str()),
+        })
+}
+
+/// A query over persisted entities of one kind.
+///
+/// Entities are looked up through the kind index, then filtered, ordered
+/// and paged in memory.
+pub struct Query<'a> {
+    store: &'a Store,
+    kind: String,
+    filters: Vec<(String, CmpOp, Value)>,
+    flags: Vec<DefId>,
+    /// Property to order by, and whether descending.
+    order: Option<(String, bool)>,
+    offset: usize,
+    limit: Option<usize>,
+}
+
+impl Query<'_> {
+    /// Keep entities whose property compares to a value.
+    pub fn filter(
+        mut self,
+        property: impl Into<String>,
+        op: CmpOp,
+        value: impl Into<Value>,
+    ) -> Self {
+        self.filters.push((property.into(), op, value.into()));
+        self
+    }
+
+    /// Keep entities with a flag.
+    pub fn with_flag(mut self, flag: impl Into<DefId>) -> Self {
+        self.flags.push(flag.into());
+        self
+    }
+
+    /// Order by a property, ascending. Entities without it come last.
+    ///
+    /// Numbers sort before strings, and those before other values; ties
+    /// keep entity ID order.
+    pub fn order_by(mut self, property: impl Into<String>) -> Self {
+        self.order = Some((property.into(), false));
+        self
+    }
+
+    /// Order by a property, descending. Entities without it come last.
+    pub fn order_by_desc(mut self, property: impl Into<String>) -> Self {
+        self.order = Some((property.into(), true));
+        self
+    }
+
+    /// Skip the first `offset` results.
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Return at most `limit` results.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Run the query.
+    pub fn fetch(&self) -> Result<Vec<Entity>> {
+        let mut entities = self.matching()?;
+        if let Some((property, descending)) = &self.order {
+            entities.sort_by(|a, b| {
+                let ordering = match (a.get(property), b.get(property)) {
+                    (Some(x), Some(y)) if *descending => sort_order(y, x),
+                    (Some(x), Some(y)) => sort_order(x, y),
+                    (Some(_), None) => Ordering::Less,
+                    (None, Some(_)) => Ordering::Greater,
+                    (None, None) => Ordering::Equal,
+                };
+                ordering.then(a.id.cmp(&b.id))
+            });
+        }
+        Ok(entities
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Get the first result.
+    pub fn first(&self) -> Result<Option<Entity>> {
+        Ok(self.fetch()?.into_iter().next())
+    }
+
+    /// Count the results, ignoring offset and limit.
+    pub fn count(&self) -> Result<usize> {
+        Ok(self.matching()?.len())
+    }
+
+    /// Entities of the kind that pass the filters.
+    fn matching(&self) -> Result<Vec<Entity>> {
+        let r = self.store.db.r_transaction()?;
+        let scan = r.scan().secondary::<StoredEntity>(StoredEntityKey::kind)?;
+        let iter = scan.start_with(self.kind.as_str())?;
+        let stored: std::result::Result<Vec<StoredEntity>, _> = iter.collect();
+        let stored = stored.map_err(|e| Error::Database(e.to_string()))?;
+        let entities: Vec<Entity> = stored
+            .iter()
+            // The index matches by prefix
+            .filter(|s| s.kind == self.kind)
+            .map(StoredEntity::to_entity)
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(entities
+            .into_iter()
+            .filter(|e| {
+                self.flags.iter().all(|flag| e.has_flag(flag))
+                    && self
+                        .filters
+                        .iter()
+                        .all(|(property, op, value)| op.matches(e.get(property), value))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::Model;
+
+    /// Store nations with the given gold values, in order
+    fn store_with(gold: &[Value]) -> (Store, Vec<pulsive_core::EntityId>) {
+        let store = Store::in_memory().unwrap();
+        let mut model = Model::new();
+        let mut ids = Vec::new();
+        for value in gold {
+            let entity = model.entities_mut().create("nation");
+            entity.set("gold", value.clone());
+            ids.push(entity.id);
+        }
+        let province = model.entities_mut().create("province");
+        province.set("gold", 1000.0);
+        for entity in model.entities().iter() {
+            store.save_entity(entity).unwrap();
+        }
+        (store, ids)
+    }
+
+    #[test]
+    fn test_filter_order_and_page() {
+        let gold: Vec<Value> = [50.0, 300.0, 120.0, 80.0].map(Value::Float).to_vec();
+        let (store, ids) = store_with(&gold);
+
+        let rich = store
+            .query("nation")
+            .filter("gold", CmpOp::Gt, 75.0)
+            .order_by_desc("gold");
+        assert_eq!(rich.count().unwrap(), 3);
+        let order: Vec<_> = rich.fetch().unwrap().iter().map(|e| e.id).collect();
+        assert_eq!(order, [ids[1], ids[2], ids[3]]);
+
+        let page = store
+            .query("nation")
+            .order_by("gold")
+            .offset(1)
+            .limit(2)
+            .fetch()
+            .unwrap();
+        let page: Vec<_> = page.iter().map(|e| e.id).collect();
+        assert_eq!(page, [ids[3], ids[2]]);
+        let first = store.query("nation").order_by("gold").first().unwrap();
+        assert_eq!(first.map(|e| e.id), Some(ids[0]));
+        assert_eq!(
+            store
+                .query("nation")
+                .filter("gold", CmpOp::Eq, 1000.0)
+                .count()
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_order_is_total() {
+        let values = [
+            Value::Float(f64::NAN),
+            Value::String("b".into()),
+            Value::Int(7),
+            Value::Bool(true),
+            Value::Float(-1.0),
+            Value::String("a".into()),
+            Value::Float(f64::NAN),
+        ];
+        let order = |values: &[Value]| -> Vec<String> {
+            let (store, _) = store_with(values);
+            let sorted = store.query("nation").order_by("gold").fetch().unwrap();
+            sorted
+                .iter()
+                .map(|e| format!("{:?}", e.get("gold")))
+                .collect()
+        };
+        let mut reversed = values.clone();
+        reversed.reverse();
+        let expected = [
+            "Some(Float(-1.0))",
+            "Some(Int(7))",
+            "Some(Float(NaN))",
+            "Some(Float(NaN))",
+            "Some(String(\"a\"))",
+            "Some(String(\"b\"))",
+            "Some(Bool(true))",
+        ];
+        assert_eq!(order(&values), expected);
+        assert_eq!(order(&reversed), expected);
+    }
+}
@@ -0,0 +1,413 @@
+//! Save slots.
+//!
+//! Complete models are saved into named slots. Each slot keeps metadata for
+//! save/load menus (label, play time, thumbnail, timestamps) and a checksum
+//! of the saved data, verified on load:
+//!
+//! ```rust,ignore
+//! let metadata = SaveMetadata::new()
+//!     .with_label("Before the war")
+//!     .with_play_time(Duration::from_secs(5400))
+//!     .with_thumbnail(png_bytes);
+//! store.save_slot("slot1", &model, &metadata)?;
+//!
+//! for slot in store.list_slots()? {
+//!     println!("{}: {} (tick {})", slot.name, slot.label, slot.tick);
+//! }
+//! let model = store.load_model("slot1")?;
+//! ```
+
+use crate::error::{Error, Result};
+use crate::models::*;
+use crate::store::Store;
+use pulsive_core::Model;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Metadata supplied when saving into a slot.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SaveMetadata {
+    /// User-facing label.
+    pub label: String,
+    /// Total play time.
+    pub play_time: Duration,
+    /// Thumbnail image bytes (format chosen by the game).
+    pub thumbnail: Vec<u8>,
+}
+
+impl SaveMetadata {
+    /// Create empty metadata.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the label.
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = label.into();
+        self
+    }
+
+    /// Set the play time.
+    pub fn with_play_time(mut self, play_time: Duration) -> Self {
+        self.play_time = play_time;
+        self
+    }
+
+    /// Set the thumbnail.
+    pub fn with_thumbnail(mut self, thumbnail: Vec<u8>) -> Self {
+        self.thumbnail = thumbnail;
+        self
+    }
+}
+
+/// A save slot, without its saved model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveSlot {
+    /// Slot name.
+    pub name: String,
+    /// User-facing label.
+    pub label: String,
+    /// When the slot was first saved (seconds since the Unix epoch).
+    pub created_at: u64,
+    /// When the slot was last saved (seconds since the Unix epoch).
+    pub saved_at: u64,
+    /// Tick of the saved model.
+    pub tick: u64,
+    /// Total play time.
+    pub play_time: Duration,
+    /// Thumbnail image bytes.
+    pub thumbnail: Vec<u8>,
+    /// CRC-32 of the saved model data.
+    pub checksum: u32,
+    /// Model format version the slot was saved with.
+    pub schema_version: u32,
+}
+
+impl SaveSlot {
+    fn from_stored(stored: StoredSaveSlot) -> Self {
+        Self {
+            name: stored.slot,
+            label: stored.label,
+            created_at: stored.created_at,
+            saved_at: stored.saved_at,
+            tick: stored.tick,
+            play_time: Duration::from_millis(stored.play_time_ms),
+            thumbnail: stored.thumbnail,
+            checksum: stored.checksum,
+            schema_version: stored.schema_version,
+        }
+    }
+}
+
+impl Store {
+    /// Save a complete model into a slot, replacing any previous save.
+    ///
+    /// Entities (with their IDs), globals, clock, RNG state and actors are
+    /// written in a single transaction, tagged with [`MODEL_SCHEMA_VERSION`].
+    /// The slot keeps its previous metadata, if any.
+    pub fn save_model(&self, model: &Model, slot: &str) -> Result<()> {
+        self.write_slot(slot, model, None).map(|_| ())
+    }
+
+    /// Save a complete model into a slot with metadata.
+    pub fn save_slot(
+        &self,
+        slot: &str,
+        model: &Model,
+        metadata: &SaveMetadata,
+    ) -> Result<SaveSlot> {
+        self.write_slot(slot, model, Some(metadata))
+    }
+
+    fn write_slot(
+        &self,
+        slot: &str,
+        model: &Model,
+        metadata: Option<&SaveMetadata>,
+    ) -> Result<SaveSlot> {
+        let stored = StoredModel::from_model(slot, model)
+            .map_err(|e| Error::Serialization(e.to_string()))?;
+        let now = now();
+        let rw = self.db.rw_transaction()?;
+        let previous: Option<StoredSaveSlot> = rw.get().primary(slot.to_string())?;
+        let mut meta = previous.unwrap_or_else(|| StoredSaveSlot {
+            slot: slot.to_string(),
+            created_at: now,
+            ..Default::default()
+        });
+        if let Some(metadata) = metadata {
+            meta.label = metadata.label.clone();
+            meta.play_time_ms = metadata.play_time.as_millis() as u64;
+            meta.thumbnail = metadata.thumbnail.clone();
+        }
+        meta.saved_at = now;
+        meta.tick = stored.tick;
+        meta.checksum = crc32(&stored.data);
+        meta.schema_version = stored.schema_version;
+
+        rw.upsert(stored)?;
+        rw.upsert(meta.clone())?;
+        rw.commit()?;
+        Ok(SaveSlot::from_stored(meta))
+    }
+
+    /// Load the complete model saved in a slot.
+    ///
+    /// Returns `None` if the slot is empty, [`Error::SchemaVersion`] if it
+    /// was saved with an incompatible format, and [`Error::Corrupted`] if the
+    /// data does not match its checksum.
+    pub fn load_model(&self, slot: &str) -> Result<Option<Model>> {
+        let r = self.db.r_transaction()?;
+        let stored: Option<StoredModel> = r.get().primary(slot.to_string())?;
+        let Some(stored) = stored else {
+            return Ok(None);
+        };
+        if stored.schema_version != MODEL_SCHEMA_VERSION {
+            return Err(Error::SchemaVersion {
+                found: stored.schema_version,
+                expected: MODEL_SCHEMA_VERSION,
+            });
+        }
+        let meta: Option<StoredSaveSlot> = r.get().primary(slot.to_string())?;
+        if meta.is_some_and(|m| m.checksum != crc32(&stored.data)) {
+            return Err(Error::Corrupted(format!("slot {}", slot)));
+        }
+        stored
+            .to_model()
+            .map(Some)
+            .map_err(|e| Error::Serialization(format!("slot {}: {}", slot, e)))
+    }
+
+    /// Get a slot's metadata.
+    pub fn slot(&self, slot: &str) -> Result<Option<SaveSlot>> {
+        let r = self.db.r_transaction()?;
+        let meta: Option<StoredSaveSlot> = r.get().primary(slot.to_string())?;
+        Ok(meta.map(SaveSlot::from_stored))
+    }
+
+    /// Get every slot's metadata, most recently saved first.
+    pub fn list_slots(&self) -> Result<Vec<SaveSlot>> {
+        let r = self.db.r_transaction()?;
+        let scan = r.scan().primary::<StoredSaveSlot>()?;
+        let iter = scan.all()?;
+        let metas: std::result::Result<Vec<StoredSaveSlot>, _> = iter.collect();
+        let metas = metas.map_err(|e| Error::Database(e.to_string()))?;
+        let mut slots: Vec<SaveSlot> = metas.into_iter().map(SaveSlot::from_stored).collect();
+        slots.sort_by(|a, b| b.saved_at.cmp(&a.saved_at).then(a.name.cmp(&b.name)));
+        Ok(slots)
+    }
+
+    /// Check that a slot's data matches its checksum.
+    ///
+    /// Returns `None` if the slot is empty.
+    pub fn verify_slot(&self, slot: &str) -> Result<Option<bool>> {
+        let r = self.db.r_transaction()?;
+        let stored: Option<StoredModel> = r.get().primary(slot.to_string())?;
+        let meta: Option<StoredSaveSlot> = r.get().primary(slot.to_string())?;
+        Ok(match (stored, meta) {
+            (Some(stored), Some(meta)) => Some(meta.checksum == crc32(&stored.data)),
+            (Some(_), None) => Some(true),
+            (None, _) => None,
+        })
+    }
+
+    /// Delete a slot and its saved model.
+    pub fn delete_slot(&self, slot: &str) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        let stored: Option<StoredModel> = rw.get().primary(slot.to_string())?;
+        if let Some(s) = stored {
+            rw.remove(s)?;
+        }
+        let meta: Option<StoredSaveSlot> = rw.get().primary(slot.to_string())?;
+        if let Some(m) = meta {
+            rw.remove(m)?;
+        }
+        rw.commit()?;
+        Ok(())
+    }
+
+    /// Copy a slot to a new name, replacing any slot with that name.
+    pub fn duplicate_slot(&self, from: &str, to: &str) -> Result<SaveSlot> {
+        let rw = self.db.rw_transaction()?;
+        let stored: Option<StoredModel> = rw.get().primary(from.to_string())?;
+        let mut stored = stored.ok_or_else(|| Error::NotFound(format!("save slot {}", from)))?;
+        let meta: Option<StoredSaveSlot> = rw.get().primary(from.to_string())?;
+        let now = now();
+        let mut meta = meta.unwrap_or_else(|| StoredSaveSlot {
+            tick: stored.tick,
+            checksum: crc32(&stored.data),
+            schema_version: stored.schema_version,
+            ..Default::default()
+        });
+        stored.slot = to.to_string();
+        meta.slot = to.to_string();
+        meta.created_at = now;
+        meta.saved_at = now;
+
+        rw.upsert(stored)?;
+        rw.upsert(meta.clone())?;
+        rw.commit()?;
+        Ok(SaveSlot::from_stored(meta))
+    }
+}
+
+/// Current time in seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Compute the CRC-32 (IEEE) checksum of `data`.
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        CRC_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::Value;
+
+    fn sample_model() -> Model {
+        let mut model = Model::new();
+        let entity = model.entities_mut().create("nation");
+        entity.set("gold", 250.0);
+        entity.add_flag("at_war");
+        entity.add_flag_until("truce", 10);
+        entity.add_tag("great_power");
+        model
+            .entities_mut()
+            .create("province")
+            .set("name", "Uppland");
+        model.set_global("year", 1444i64);
+        model.advance_tick();
+        model.advance_tick();
+        model.rng.next_u64();
+        model
+    }
+
+    #[test]
+    fn test_save_and_load_model() {
+        let store = Store::in_memory().unwrap();
+        let model = sample_model();
+        store.save_model(&model, "quick").unwrap();
+
+        let loaded = store.load_model("quick").unwrap().unwrap();
+        assert_eq!(loaded.current_tick(), 2);
+        assert_eq!(loaded.rng.state(), model.rng.state());
+        assert_eq!(loaded.get_global("year"), Some(&Value::Int(1444)));
+        assert_eq!(loaded.entities().len(), 2);
+        for entity in model.entities().iter() {
+            let restored = loaded.entities().get(entity.id).unwrap();
+            assert_eq!(restored.kind, entity.kind);
+            assert_eq!(restored.properties, entity.properties);
+            assert_eq!(restored.flags, entity.flags);
+            assert_eq!(restored.flag_expiry, entity.flag_expiry);
+            assert_eq!(restored.tags, entity.tags);
+        }
+
+        assert!(store.load_model("missing").unwrap().is_none());
+        store.delete_slot("quick").unwrap();
+        assert!(store.load_model("quick").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_slot_metadata() {
+        let store = Store::in_memory().unwrap();
+        let metadata = SaveMetadata::new()
+            .with_label("Before the war")
+            .with_play_time(Duration::from_secs(90))
+            .with_thumbnail(vec![1, 2, 3]);
+        let slot = store.save_slot("a", &sample_model(), &metadata).unwrap();
+        assert_eq!(slot.label, "Before the war");
+        assert_eq!(slot.tick, 2);
+        assert_eq!(slot.schema_version, MODEL_SCHEMA_VERSION);
+
+        // Saving without metadata keeps the previous metadata
+        store.save_model(&Model::new(), "a").unwrap();
+        let slot = store.slot("a").unwrap().unwrap();
+        assert_eq!(slot.label, "Before the war");
+        assert_eq!(slot.play_time, Duration::from_secs(90));
+        assert_eq!(slot.tick, 0);
+
+        let copy = store.duplicate_slot("a", "b").unwrap();
+        assert_eq!(copy.name, "b");
+        assert_eq!(copy.checksum, slot.checksum);
+        let names: Vec<_> = store
+            .list_slots()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"a".to_string()) && names.contains(&"b".to_string()));
+        assert!(matches!(
+            store.duplicate_slot("missing", "c"),
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_corrupted_slot() {
+        let store = Store::in_memory().unwrap();
+        store
+            .save_slot("a", &sample_model(), &SaveMetadata::new())
+            .unwrap();
+        assert_eq!(store.verify_slot("a").unwrap(), Some(true));
+
+        let rw = store.db.rw_transaction().unwrap();
+        let mut stored: StoredModel = rw.get().primary("a".to_string()).unwrap().unwrap();
+        let last = stored.data.len() - 1;
+        stored.data[last] ^= 0xFF;
+        rw.upsert(stored).unwrap();
+        rw.commit().unwrap();
+
+        assert_eq!(store.verify_slot("a").unwrap(), Some(false));
+        assert!(matches!(store.load_model("a"), Err(Error::Corrupted(_))));
+        assert_eq!(store.verify_slot("missing").unwrap(), None);
+    }
+
+    #[test]
+    fn test_rejects_other_schema_versions() {
+        let store = Store::in_memory().unwrap();
+        let mut stored = StoredModel::from_model("old", &sample_model()).unwrap();
+        stored.schema_version = MODEL_SCHEMA_VERSION - 1;
+        let rw = store.db.rw_transaction().unwrap();
+        rw.upsert(stored).unwrap();
+        rw.commit().unwrap();
+
+        let err = store.load_model("old").unwrap_err();
+        assert!(matches!(
+            err,
+            Error::SchemaVersion { found, expected }
+                if found == MODEL_SCHEMA_VERSION - 1 && expected == MODEL_SCHEMA_VERSION
+        ));
+    }
+}
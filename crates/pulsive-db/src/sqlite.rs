@@ -0,0 +1,575 @@
+//! SQLite storage backend.
+//!
+//! [`SqliteStore`] keeps state in plain SQL tables, so it can be inspected
+//! and queried with standard SQLite tooling:
+//!
+//! ```text
+//! entities(id INTEGER PRIMARY KEY, kind TEXT, properties BLOB)
+//! entity_flags(entity_id INTEGER, flag TEXT, expires_at INTEGER)
+//! entity_tags(entity_id INTEGER, tag TEXT)
+//! state(key TEXT PRIMARY KEY, data BLOB)          -- globals, clock, rng
+//! saves(slot TEXT PRIMARY KEY, schema_version INTEGER, tick INTEGER, data BLOB)
+//! frames(tick INTEGER PRIMARY KEY, data BLOB)
+//! ```
+//!
+//! Blobs are bincode-encoded, as in the native_db [`Store`](crate::Store).
+//! The table layout is versioned with `PRAGMA user_version`; databases
+//! written by older versions are upgraded when opened.
+
+use crate::backend::{Changes, StorageBackend};
+use crate::error::{Error, Result};
+use crate::models::MODEL_SCHEMA_VERSION;
+use pulsive_core::{Clock, DefId, Entity, EntityId, Model, Rng, ValueMap};
+use pulsive_rollback_buffer::SpillBackend;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+/// Version of the table layout, kept in `PRAGMA user_version`.
+///
+/// 0. flags in a comma-separated `entities.flags` column
+/// 1. flags in the `entity_flags` table
+/// 2. `entity_flags.expires_at` for timed flags
+/// 3. tags in the `entity_tags` table
+const LAYOUT_VERSION: u32 = 3;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS entities (
+    id INTEGER PRIMARY KEY,
+    kind TEXT NOT NULL,
+    properties BLOB NOT NULL
+);
+CREATE INDEX IF NOT EXISTS entities_kind ON entities (kind);
+CREATE TABLE IF NOT EXISTS entity_flags (
+    entity_id INTEGER NOT NULL,
+    flag TEXT NOT NULL,
+    expires_at INTEGER,
+    PRIMARY KEY (entity_id, flag)
+);
+CREATE TABLE IF NOT EXISTS entity_tags (
+    entity_id INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (entity_id, tag)
+);
+CREATE TABLE IF NOT EXISTS state (
+    key TEXT PRIMARY KEY,
+    data BLOB NOT NULL
+);
+CREATE TABLE IF NOT EXISTS saves (
+    slot TEXT PRIMARY KEY,
+    schema_version INTEGER NOT NULL,
+    tick INTEGER NOT NULL,
+    data BLOB NOT NULL
+);
+CREATE TABLE IF NOT EXISTS frames (
+    tick INTEGER PRIMARY KEY,
+    data BLOB NOT NULL
+);
+";
+
+/// SQLite-backed store for persistent game state.
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Open or create a database at the given path.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    /// Create an in-memory database.
+    pub fn in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(mut conn: Connection) -> Result<Self> {
+        let version: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        let tx = conn.transaction()?;
+        tx.execute_batch(SCHEMA)?;
+        if version < 1 && has_column(&tx, "entities", "flags")? {
+            split_flags(&tx)?;
+        }
+        if version < 2 && !has_column(&tx, "entity_flags", "expires_at")? {
+            tx.execute_batch("ALTER TABLE entity_flags ADD COLUMN expires_at INTEGER")?;
+        }
+        tx.pragma_update(None, "user_version", LAYOUT_VERSION)?;
+        tx.commit()?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|_| Error::Database("connection lock poisoned".to_string()))
+    }
+
+    /// Get all entities of a specific kind.
+    pub fn entities_by_kind(&self, kind: &str) -> Result<Vec<Entity>> {
+        query_entities(
+            &*self.conn()?,
+            "SELECT id, kind, properties FROM entities WHERE kind = ?1",
+            [kind],
+        )
+    }
+
+    fn save_state<T: Serialize>(&self, key: &str, value: &T) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT OR REPLACE INTO state (key, data) VALUES (?1, ?2)",
+            params![key, encode(value)?],
+        )?;
+        Ok(())
+    }
+
+    fn load_state<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let data: Option<Vec<u8>> = self
+            .conn()?
+            .query_row("SELECT data FROM state WHERE key = ?1", [key], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        data.map(|data| decode(&data)).transpose()
+    }
+}
+
+impl StorageBackend for SqliteStore {
+    fn save_entity(&self, entity: &Entity) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        insert_entity(&tx, entity)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn load_entity(&self, id: EntityId) -> Result<Option<Entity>> {
+        let entities = query_entities(
+            &*self.conn()?,
+            "SELECT id, kind, properties FROM entities WHERE id = ?1",
+            [id.raw() as i64],
+        )?;
+        Ok(entities.into_iter().next())
+    }
+
+    fn delete_entity(&self, id: EntityId) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        remove_entity(&tx, id)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn load_all_entities(&self) -> Result<Vec<Entity>> {
+        query_entities(
+            &*self.conn()?,
+            "SELECT id, kind, properties FROM entities",
+            [],
+        )
+    }
+
+    fn save_globals(&self, globals: &ValueMap) -> Result<()> {
+        self.save_state("globals", globals)
+    }
+
+    fn load_globals(&self) -> Result<ValueMap> {
+        Ok(self.load_state("globals")?.unwrap_or_default())
+    }
+
+    fn save_clock(&self, clock: &Clock) -> Result<()> {
+        self.save_state("time", clock)
+    }
+
+    fn load_clock(&self) -> Result<Option<Clock>> {
+        self.load_state("time")
+    }
+
+    fn save_rng(&self, rng: &Rng) -> Result<()> {
+        self.save_state("rng", rng)
+    }
+
+    fn load_rng(&self) -> Result<Option<Rng>> {
+        self.load_state("rng")
+    }
+
+    fn write_changes(&self, changes: &Changes) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        for entity in &changes.entities {
+            insert_entity(&tx, entity)?;
+        }
+        for id in &changes.destroyed {
+            remove_entity(&tx, *id)?;
+        }
+        let mut state = vec![
+            ("time", encode(&changes.clock)?),
+            ("rng", encode(&changes.rng)?),
+        ];
+        if let Some(globals) = &changes.globals {
+            state.push(("globals", encode(globals)?));
+        }
+        for (key, data) in state {
+            tx.execute(
+                "INSERT OR REPLACE INTO state (key, data) VALUES (?1, ?2)",
+                params![key, data],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn save_model(&self, model: &Model, slot: &str) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT OR REPLACE INTO saves (slot, schema_version, tick, data) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                slot,
+                MODEL_SCHEMA_VERSION,
+                model.time.tick as i64,
+                encode(model)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn load_model(&self, slot: &str) -> Result<Option<Model>> {
+        let save: Option<(u32, Vec<u8>)> = self
+            .conn()?
+            .query_row(
+                "SELECT schema_version, data FROM saves WHERE slot = ?1",
+                [slot],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((schema_version, data)) = save else {
+            return Ok(None);
+        };
+        if schema_version != MODEL_SCHEMA_VERSION {
+            return Err(Error::SchemaVersion {
+                found: schema_version,
+                expected: MODEL_SCHEMA_VERSION,
+            });
+        }
+        decode(&data).map(Some)
+    }
+
+    fn delete_slot(&self, slot: &str) -> Result<()> {
+        self.conn()?
+            .execute("DELETE FROM saves WHERE slot = ?1", [slot])?;
+        Ok(())
+    }
+
+    fn save_frame(&self, tick: u64, model: &Model) -> Result<()> {
+        self.conn()?.execute(
+            "INSERT OR REPLACE INTO frames (tick, data) VALUES (?1, ?2)",
+            params![tick as i64, encode(model)?],
+        )?;
+        Ok(())
+    }
+
+    fn load_frame(&self, tick: u64) -> Result<Option<Model>> {
+        let data: Option<Vec<u8>> = self
+            .conn()?
+            .query_row(
+                "SELECT data FROM frames WHERE tick = ?1",
+                [tick as i64],
+                |row| row.get(0),
+            )
+            .optional()?;
+        data.map(|data| decode(&data)).transpose()
+    }
+
+    fn delete_frames_before(&self, tick: u64) -> Result<()> {
+        self.conn()?
+            .execute("DELETE FROM frames WHERE tick < ?1", [tick as i64])?;
+        Ok(())
+    }
+
+    fn clear_frames(&self) -> Result<()> {
+        self.conn()?.execute("DELETE FROM frames", [])?;
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.conn()?.execute_batch(
+            "DELETE FROM entities; DELETE FROM entity_flags; DELETE FROM entity_tags;
+             DELETE FROM state WHERE key IN ('globals', 'time', 'rng');",
+        )?;
+        Ok(())
+    }
+}
+
+/// Spill evicted rollback frames into the database.
+impl SpillBackend<Model> for SqliteStore {
+    fn store(&mut self, tick: u64, state: &Model) -> pulsive_rollback_buffer::Result<()> {
+        self.save_frame(tick, state).map_err(backend_error)
+    }
+
+    fn load(&self, tick: u64) -> pulsive_rollback_buffer::Result<Option<Model>> {
+        self.load_frame(tick).map_err(backend_error)
+    }
+
+    fn remove_before(&mut self, tick: u64) -> pulsive_rollback_buffer::Result<()> {
+        self.delete_frames_before(tick).map_err(backend_error)
+    }
+
+    fn clear(&mut self) -> pulsive_rollback_buffer::Result<()> {
+        self.clear_frames().map_err(backend_error)
+    }
+}
+
+fn backend_error(err: Error) -> pulsive_rollback_buffer::Error {
+    pulsive_rollback_buffer::Error::Backend(err.to_string())
+}
+
+/// Write an entity with its flags and tags; call within a transaction.
+fn insert_entity(conn: &Connection, entity: &Entity) -> Result<()> {
+    let id = entity.id.raw() as i64;
+    conn.execute(
+        "INSERT OR REPLACE INTO entities (id, kind, properties) VALUES (?1, ?2, ?3)",
+        params![id, entity.kind.as_str(), encode(&entity.properties)?],
+    )?;
+    conn.execute("DELETE FROM entity_flags WHERE entity_id = ?1", [id])?;
+    let mut insert = conn.prepare_cached(
+        "INSERT INTO entity_flags (entity_id, flag, expires_at) VALUES (?1, ?2, ?3)",
+    )?;
+    // Timed flags first, so their expiry order survives a reload
+    for (flag, expires_at) in &entity.flag_expiry {
+        insert.execute(params![id, flag.as_str(), *expires_at as i64])?;
+    }
+    for flag in entity
+        .flags
+        .iter()
+        .filter(|f| !entity.flag_expiry.contains_key(*f))
+    {
+        insert.execute(params![id, flag.as_str(), None::<i64>])?;
+    }
+    conn.execute("DELETE FROM entity_tags WHERE entity_id = ?1", [id])?;
+    let mut insert =
+        conn.prepare_cached("INSERT INTO entity_tags (entity_id, tag) VALUES (?1, ?2)")?;
+    for tag in &entity.tags {
+        insert.execute(params![id, tag])?;
+    }
+    Ok(())
+}
+
+/// Delete an entity with its flags and tags; call within a transaction.
+fn remove_entity(conn: &Connection, id: EntityId) -> Result<()> {
+    let id = id.raw() as i64;
+    conn.execute("DELETE FROM entities WHERE id = ?1", [id])?;
+    conn.execute("DELETE FROM entity_flags WHERE entity_id = ?1", [id])?;
+    conn.execute("DELETE FROM entity_tags WHERE entity_id = ?1", [id])?;
+    Ok(())
+}
+
+/// Run a query over the entities table and attach each entity's flags and
+/// tags.
+fn query_entities(
+    conn: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<Entity>> {
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt.query_map(params, entity_row)?;
+    let mut entities = rows.map(|row| row?).collect::<Result<Vec<Entity>>>()?;
+    let mut flags = conn.prepare_cached(
+        "SELECT flag, expires_at FROM entity_flags WHERE entity_id = ?1 ORDER BY rowid",
+    )?;
+    let mut tags = conn.prepare_cached("SELECT tag FROM entity_tags WHERE entity_id = ?1")?;
+    for entity in &mut entities {
+        let rows = flags.query_map([entity.id.raw() as i64], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<i64>>(1)?))
+        })?;
+        for row in rows {
+            let (flag, expires_at) = row?;
+            let flag = DefId::new(flag);
+            if let Some(tick) = expires_at {
+                entity.flag_expiry.insert(flag.clone(), tick as u64);
+            }
+            entity.flags.insert(flag);
+        }
+        let rows = tags.query_map([entity.id.raw() as i64], |row| row.get::<_, String>(0))?;
+        for tag in rows {
+            entity.tags.insert(tag?);
+        }
+    }
+    Ok(entities)
+}
+
+/// Decode an entity row; the outer result is SQLite's, the inner ours.
+fn entity_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Result<Entity>> {
+    let id: i64 = row.get(0)?;
+    let kind: String = row.get(1)?;
+    let properties: Vec<u8> = row.get(2)?;
+    Ok(decode(&properties).map(|properties| {
+        let mut entity = Entity::new(EntityId::new(id as u64), DefId::new(kind));
+        entity.properties = properties;
+        entity
+    }))
+}
+
+/// Check if a table has a column.
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let names = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Upgrade from layout 0, where flags were a comma-separated column.
+fn split_flags(conn: &Connection) -> Result<()> {
+    let rows: Vec<(i64, String)> = conn
+        .prepare("SELECT id, flags FROM entities")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<_>>()?;
+    let mut insert = conn
+        .prepare_cached("INSERT OR IGNORE INTO entity_flags (entity_id, flag) VALUES (?1, ?2)")?;
+    for (id, flags) in rows {
+        for flag in flags.split(',').filter(|f| !f.is_empty()) {
+            insert.execute(params![id, flag])?;
+        }
+    }
+    conn.execute_batch("ALTER TABLE entities DROP COLUMN flags")?;
+    Ok(())
+}
+
+fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    bincode::serialize(value).map_err(|e| Error::Serialization(e.to_string()))
+}
+
+fn decode<T: DeserializeOwned>(data: &[u8]) -> Result<T> {
+    bincode::deserialize(data).map_err(|e| Error::Serialization(e.to_string()))
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Error::Database(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nation(model: &mut Model) -> EntityId {
+        let entity = model.entities_mut().create("nation");
+        entity.set("gold", 100.0);
+        entity.add_flag("at_war");
+        entity.add_flag("a,b");
+        entity.add_flag_until("truce", 30);
+        entity.add_flag_until("blockade", 12);
+        entity.add_tag("coastal");
+        entity.id
+    }
+
+    #[test]
+    fn test_entity_round_trip() {
+        let store = SqliteStore::in_memory().unwrap();
+        let mut model = Model::new();
+        let id = nation(&mut model);
+        let entity = model.entities().get(id).unwrap();
+
+        store.save_entity(entity).unwrap();
+        let loaded = store.load_entity(id).unwrap().unwrap();
+        assert_eq!(loaded.kind.as_str(), "nation");
+        assert_eq!(loaded.get_number("gold"), Some(100.0));
+        assert_eq!(loaded.flags, entity.flags);
+        assert_eq!(loaded.flag_expiry, entity.flag_expiry);
+        assert_eq!(loaded.tags, entity.tags);
+        assert_eq!(store.entities_by_kind("nation").unwrap().len(), 1);
+
+        // Saving again replaces the flags rather than adding to them
+        let mut entity = entity.clone();
+        entity.remove_flag(&"at_war".into());
+        store.save_entity(&entity).unwrap();
+        let loaded = store.load_entity(id).unwrap().unwrap();
+        assert_eq!(loaded.flags, entity.flags);
+
+        store.delete_entity(id).unwrap();
+        assert!(store.load_entity(id).unwrap().is_none());
+        assert!(store.load_all_entities().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_write_changes_and_models() {
+        let store = SqliteStore::in_memory().unwrap();
+        let mut model = Model::new();
+        let kept = nation(&mut model);
+        let destroyed = model.entities_mut().create("province").id;
+        model.set_global("year", 1444.0);
+        model.advance_tick();
+        store
+            .write_changes(&Changes {
+                entities: model.entities().iter().cloned().collect(),
+                destroyed: vec![],
+                globals: Some(model.globals().clone()),
+                clock: model.time.clone(),
+                rng: model.rng.clone(),
+            })
+            .unwrap();
+        store
+            .write_changes(&Changes {
+                entities: vec![],
+                destroyed: vec![destroyed],
+                globals: None,
+                clock: model.time.clone(),
+                rng: model.rng.clone(),
+            })
+            .unwrap();
+
+        let entities = store.load_all_entities().unwrap();
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].id, kept);
+        assert_eq!(store.load_globals().unwrap(), *model.globals());
+        assert_eq!(store.load_clock().unwrap().unwrap().tick, model.time.tick);
+
+        store.save_model(&model, "slot").unwrap();
+        let loaded = store.load_model("slot").unwrap().unwrap();
+        assert_eq!(loaded.current_tick(), model.current_tick());
+        assert_eq!(
+            loaded.entities().get(kept).unwrap().flags,
+            model.entities().get(kept).unwrap().flags
+        );
+
+        store.save_frame(3, &model).unwrap();
+        assert!(store.load_frame(3).unwrap().is_some());
+        store.delete_frames_before(4).unwrap();
+        assert!(store.load_frame(3).unwrap().is_none());
+
+        store.clear().unwrap();
+        assert!(store.load_all_entities().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_upgrades_comma_separated_flags() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE entities (
+                id INTEGER PRIMARY KEY,
+                kind TEXT NOT NULL,
+                properties BLOB NOT NULL,
+                flags TEXT NOT NULL
+            );",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO entities (id, kind, properties, flags) VALUES (1, 'nation', ?1, 'at_war,rich')",
+            [encode(&ValueMap::new()).unwrap()],
+        )
+        .unwrap();
+
+        let store = SqliteStore::from_connection(conn).unwrap();
+        let entity = store.load_entity(EntityId::new(1)).unwrap().unwrap();
+        assert!(entity.has_flag(&"at_war".into()));
+        assert!(entity.has_flag(&"rich".into()));
+        assert_eq!(entity.flags.len(), 2);
+
+        // New rows no longer need the old column
+        store.save_entity(&entity).unwrap();
+    }
+}
@@ -1,22 +1,33 @@
 //! Database store wrapper.
 
+use crate::backend::Changes;
 use crate::error::{Error, Result};
 use crate::models::*;
 use native_db::*;
 use pulsive_core::{Clock, Entity, EntityId, Model, Rng, ValueMap};
+use pulsive_rollback_buffer::SpillBackend;
 use std::path::Path;
 use std::sync::LazyLock;
 
 // Static models for the database
 static MODELS: LazyLock<Models> = LazyLock::new(|| {
     let mut models = Models::new();
+    models.define::<StoredEntityV1>().unwrap();
+    models.define::<StoredEntityV2>().unwrap();
     models.define::<StoredEntity>().unwrap();
     models.define::<StoredGlobals>().unwrap();
     models.define::<StoredClock>().unwrap();
     models.define::<StoredRng>().unwrap();
+    models.define::<StoredFrame>().unwrap();
+    models.define::<StoredModel>().unwrap();
+    models.define::<StoredSaveSlot>().unwrap();
+    models.define::<StoredJournalEntry>().unwrap();
+    models.define::<StoredSchemaVersion>().unwrap();
+    models.define::<StoredSnapshot>().unwrap();
     models.define::<StoredResourceDef>().unwrap();
     models.define::<StoredEntityTypeDef>().unwrap();
     models.define::<StoredEventDef>().unwrap();
+    models.define::<StoredDefinition>().unwrap();
     models.define::<StoredScheduledEvent>().unwrap();
     models
 });
@@ -32,7 +43,9 @@ impl Store {
         let db = Builder::new()
             .create(&MODELS, path.as_ref())
             .map_err(|e| Error::Database(e.to_string()))?;
-        Ok(Self { db })
+        let store = Self { db };
+        store.upgrade_models()?;
+        Ok(store)
     }
 
     /// Create an in-memory database.
@@ -43,9 +56,17 @@ impl Store {
         Ok(Self { db })
     }
 
+    /// Convert rows written with older model versions to the current ones.
+    fn upgrade_models(&self) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        rw.migrate::<StoredEntity>()?;
+        rw.commit()?;
+        Ok(())
+    }
+
     /// Save an entity.
     pub fn save_entity(&self, entity: &Entity) -> Result<()> {
-        let stored = StoredEntity::from_entity(entity);
+        let stored = StoredEntity::from_entity(entity)?;
         let rw = self.db.rw_transaction()?;
         rw.upsert(stored)?;
         rw.commit()?;
@@ -56,7 +77,7 @@ impl Store {
     pub fn load_entity(&self, id: EntityId) -> Result<Option<Entity>> {
         let r = self.db.r_transaction()?;
         let stored: Option<StoredEntity> = r.get().primary(id.raw())?;
-        Ok(stored.map(|s| s.to_entity()))
+        Ok(stored.map(|s| s.to_entity()).transpose()?)
     }
 
     /// Delete an entity.
@@ -77,12 +98,15 @@ impl Store {
         let iter = scan.all()?;
         let entities: std::result::Result<Vec<StoredEntity>, _> = iter.collect();
         let entities = entities.map_err(|e| Error::Database(e.to_string()))?;
-        Ok(entities.into_iter().map(|e| e.to_entity()).collect())
+        Ok(entities
+            .iter()
+            .map(StoredEntity::to_entity)
+            .collect::<std::result::Result<_, _>>()?)
     }
 
     /// Save global variables.
     pub fn save_globals(&self, globals: &ValueMap) -> Result<()> {
-        let stored = StoredGlobals::from_globals(globals);
+        let stored = StoredGlobals::from_globals(globals)?;
         let rw = self.db.rw_transaction()?;
         rw.upsert(stored)?;
         rw.commit()?;
@@ -93,7 +117,10 @@ impl Store {
     pub fn load_globals(&self) -> Result<ValueMap> {
         let r = self.db.r_transaction()?;
         let stored: Option<StoredGlobals> = r.get().primary("globals".to_string())?;
-        Ok(stored.map(|s| s.to_globals()).unwrap_or_default())
+        Ok(stored
+            .map(|s| s.to_globals())
+            .transpose()?
+            .unwrap_or_default())
     }
 
     /// Save game time.
@@ -128,60 +155,79 @@ impl Store {
         Ok(stored.map(|s| s.to_rng()))
     }
 
-    /// Save a complete model.
-    pub fn save_model(&self, model: &Model) -> Result<()> {
+    /// Write a set of changes in one transaction.
+    pub fn write_changes(&self, changes: &Changes) -> Result<()> {
         let rw = self.db.rw_transaction()?;
-
-        // Save all entities
-        for entity in model.entities().iter() {
-            let stored = StoredEntity::from_entity(entity);
-            rw.upsert(stored)?;
+        for entity in &changes.entities {
+            rw.upsert(StoredEntity::from_entity(entity)?)?;
         }
-
-        // Save globals
-        let globals = StoredGlobals::from_globals(model.globals());
-        rw.upsert(globals)?;
-
-        // Save time
-        let time = StoredClock::from_clock(&model.time);
-        rw.upsert(time)?;
-
-        // Save RNG
-        let rng = StoredRng::from_rng(&model.rng);
-        rw.upsert(rng)?;
-
+        for id in &changes.destroyed {
+            let stored: Option<StoredEntity> = rw.get().primary(id.raw())?;
+            if let Some(s) = stored {
+                rw.remove(s)?;
+            }
+        }
+        if let Some(globals) = &changes.globals {
+            rw.upsert(StoredGlobals::from_globals(globals)?)?;
+        }
+        rw.upsert(StoredClock::from_clock(&changes.clock))?;
+        rw.upsert(StoredRng::from_rng(&changes.rng))?;
         rw.commit()?;
         Ok(())
     }
 
-    /// Load a complete model.
-    pub fn load_model(&self) -> Result<Model> {
-        let mut model = Model::new();
+    /// Save a rollback history frame.
+    pub fn save_frame(&self, tick: u64, model: &Model) -> Result<()> {
+        let stored = StoredFrame::from_model(tick, model)?;
+        let rw = self.db.rw_transaction()?;
+        rw.upsert(stored)?;
+        rw.commit()?;
+        Ok(())
+    }
 
-        // Load entities
-        for entity in self.load_all_entities()? {
-            // Insert entity into the store
-            let kind = entity.kind.clone();
-            let new_entity = model.entities_mut().create(kind);
-            new_entity.properties = entity.properties;
-            new_entity.flags = entity.flags;
-        }
+    /// Load a rollback history frame by tick.
+    pub fn load_frame(&self, tick: u64) -> Result<Option<Model>> {
+        let r = self.db.r_transaction()?;
+        let stored: Option<StoredFrame> = r.get().primary(tick)?;
+        stored
+            .map(|s| {
+                s.to_model()
+                    .map_err(|e| Error::Serialization(format!("frame {}: {}", tick, e)))
+            })
+            .transpose()
+    }
 
-        // Load globals
-        let loaded_globals = self.load_globals()?;
-        *model.globals_mut() = loaded_globals;
+    /// Delete all rollback history frames before a tick.
+    pub fn delete_frames_before(&self, tick: u64) -> Result<()> {
+        let frames: Vec<StoredFrame> = {
+            let r = self.db.r_transaction()?;
+            let scan = r.scan().primary::<StoredFrame>()?;
+            let iter = scan.range(..tick)?;
+            let frames: std::result::Result<Vec<StoredFrame>, _> = iter.collect();
+            frames.map_err(|e| Error::Database(e.to_string()))?
+        };
+        self.delete_frames(frames)
+    }
 
-        // Load time
-        if let Some(time) = self.load_clock()? {
-            model.time = time;
-        }
+    /// Delete all rollback history frames.
+    pub fn clear_frames(&self) -> Result<()> {
+        let frames: Vec<StoredFrame> = {
+            let r = self.db.r_transaction()?;
+            let scan = r.scan().primary::<StoredFrame>()?;
+            let iter = scan.all()?;
+            let frames: std::result::Result<Vec<StoredFrame>, _> = iter.collect();
+            frames.map_err(|e| Error::Database(e.to_string()))?
+        };
+        self.delete_frames(frames)
+    }
 
-        // Load RNG
-        if let Some(rng) = self.load_rng()? {
-            model.rng = rng;
+    fn delete_frames(&self, frames: Vec<StoredFrame>) -> Result<()> {
+        let rw = self.db.rw_transaction()?;
+        for frame in frames {
+            rw.remove(frame)?;
         }
-
-        Ok(model)
+        rw.commit()?;
+        Ok(())
     }
 
     /// Clear all data.
@@ -226,8 +272,117 @@ impl Store {
     }
 }
 
+/// Spill evicted rollback frames into the database.
+impl SpillBackend<Model> for Store {
+    fn store(&mut self, tick: u64, state: &Model) -> pulsive_rollback_buffer::Result<()> {
+        self.save_frame(tick, state).map_err(backend_error)
+    }
+
+    fn load(&self, tick: u64) -> pulsive_rollback_buffer::Result<Option<Model>> {
+        self.load_frame(tick).map_err(backend_error)
+    }
+
+    fn remove_before(&mut self, tick: u64) -> pulsive_rollback_buffer::Result<()> {
+        self.delete_frames_before(tick).map_err(backend_error)
+    }
+
+    fn clear(&mut self) -> pulsive_rollback_buffer::Result<()> {
+        self.clear_frames().map_err(backend_error)
+    }
+}
+
+fn backend_error(err: Error) -> pulsive_rollback_buffer::Error {
+    pulsive_rollback_buffer::Error::Backend(err.to_string())
+}
+
 impl From<native_db::db_type::Error> for Error {
     fn from(err: native_db::db_type::Error) -> Self {
         Error::Database(err.to_string())
     }
 }
+
+impl From<bincode::Error> for Error {
+    fn from(err: bincode::Error) -> Self {
+        Error::Serialization(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entity_and_frame_round_trip() {
+        let store = Store::in_memory().unwrap();
+        let mut model = Model::new();
+        let entity = model.entities_mut().create("nation");
+        entity.set("gold", 100.0);
+        entity.add_flag("at_war");
+        let id = entity.id;
+
+        store
+            .save_entity(model.entities().get(id).unwrap())
+            .unwrap();
+        let loaded = store.load_entity(id).unwrap().unwrap();
+        assert_eq!(loaded.get_number("gold"), Some(100.0));
+        assert!(loaded.has_flag(&"at_war".into()));
+
+        store.save_frame(5, &model).unwrap();
+        let frame = store.load_frame(5).unwrap().unwrap();
+        assert_eq!(
+            frame.entities().get(id).unwrap().get_number("gold"),
+            Some(100.0)
+        );
+        assert!(store.load_frame(6).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_flags_and_tags_round_trip() {
+        let store = Store::in_memory().unwrap();
+        let mut entity = Entity::new(EntityId::new(1), "nation");
+        entity.add_flag("at_war");
+        entity.add_flag_until("truce", 30);
+        entity.add_flag_until("blockade", 12);
+        entity.add_tag("coastal");
+
+        store.save_entity(&entity).unwrap();
+        let loaded = store.load_entity(entity.id).unwrap().unwrap();
+        assert_eq!(loaded.flags, entity.flags);
+        assert_eq!(loaded.flag_expiry, entity.flag_expiry);
+        assert_eq!(loaded.tags, entity.tags);
+        assert_eq!(loaded.flag_expires_at(&"truce".into()), Some(30));
+    }
+
+    #[test]
+    fn test_upgrades_v1_entities() {
+        let store = Store::in_memory().unwrap();
+        let rw = store.db.rw_transaction().unwrap();
+        rw.insert(StoredEntityV1 {
+            id: 1,
+            kind: "nation".to_string(),
+            properties: bincode::serialize(&ValueMap::new()).unwrap(),
+            flags: vec!["at_war".to_string()],
+        })
+        .unwrap();
+        rw.commit().unwrap();
+
+        store.upgrade_models().unwrap();
+        let loaded = store.load_entity(EntityId::new(1)).unwrap().unwrap();
+        assert!(loaded.has_flag(&"at_war".into()));
+        assert!(loaded.flag_expiry.is_empty());
+        assert!(loaded.tags.is_empty());
+    }
+
+    #[test]
+    fn test_corrupted_frame_is_an_error() {
+        let store = Store::in_memory().unwrap();
+        let rw = store.db.rw_transaction().unwrap();
+        rw.upsert(StoredFrame {
+            tick: 1,
+            data: vec![0xFF; 3],
+        })
+        .unwrap();
+        rw.commit().unwrap();
+        assert!(matches!(store.load_frame(1), Err(Error::Serialization(_))));
+    }
+}
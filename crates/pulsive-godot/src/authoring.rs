@@ -0,0 +1,349 @@
+//! Expression and effect trees from GDScript dictionaries
+//!
+//! Values are converted from variants first, so this works on pulsive
+//! values. Expressions:
+//!
+//! - Numbers, booleans, strings and null are literals (as is `{"value": v}`)
+//! - `{"property": "gold"}`, optionally with `"entity": id`
+//! - `{"global": "year"}` and `{"param": "amount"}`
+//! - `{"op": "add", "args": [a, b]}` for operators and functions (`add`,
+//!   `sub`, `mul`, `div`, `mod`, `min`, `max`, `eq`, `ne`, `lt`, `le`, `gt`,
+//!   `ge`, `and`, `or`, `not`, `neg`, `abs`, `floor`, `ceil`, `round`,
+//!   `clamp`, `if`, `random`, `random_range`, `random_int`, `concat`)
+//! - `{"has_flag": "at_war"}`, `{"has_tag": "undead"}` and `{"count": "city"}`
+//!
+//! Effects are dictionaries with an `op`:
+//!
+//! - `set`, `add`, `sub`, `mul`, `div`, `min`, `max` with `property` (on the
+//!   target, or `target`) or `global`, and a `value` expression
+//! - `add_flag` / `remove_flag` with `flag` (and optional `target`)
+//! - `add_tag` / `remove_tag` with `tag` (on the target)
+//! - `spawn` with `kind` and optional `properties` (name to expression)
+//! - `destroy` (the target, or `target`)
+//! - `emit` with `event` and optional `target`, `params` and `delay` (ticks)
+//! - `if` with `condition`, `then` and optional `else`
+//! - `for_each` with `kind`, optional `filter` and `effects`
+//! - `log` with `message` and optional `level`
+//! - `notify` with `kind`, `title`, `message` and optional `target`
+//!
+//! Targets are entity IDs, `"global"`, or definition IDs.
+
+use pulsive_core::effect::LogLevel;
+use pulsive_core::{DefId, Effect, EntityId, EntityRef, Expr, ModifyOp, Value, ValueMap};
+
+type BinaryOp = fn(Box<Expr>, Box<Expr>) -> Expr;
+
+/// Result of converting a value, with a description of what is wrong
+pub(crate) type Result<T> = std::result::Result<T, String>;
+
+/// Convert a value to an expression
+pub(crate) fn value_to_expr(value: &Value) -> Result<Expr> {
+    let Value::Map(map) = value else {
+        return match value {
+            Value::List(_) => Err("lists are not expressions".to_string()),
+            _ => Ok(Expr::Literal(value.clone())),
+        };
+    };
+
+    if let Some(value) = map.get("value") {
+        return Ok(Expr::Literal(value.clone()));
+    }
+    if let Some(property) = map.get("property") {
+        let property = string(property, "property")?;
+        return Ok(match map.get("entity") {
+            Some(entity) => Expr::EntityProperty(target(entity)?, property),
+            None => Expr::Property(property),
+        });
+    }
+    if let Some(global) = map.get("global") {
+        return Ok(Expr::Global(string(global, "global")?));
+    }
+    if let Some(param) = map.get("param") {
+        return Ok(Expr::Param(string(param, "param")?));
+    }
+    if let Some(flag) = map.get("has_flag") {
+        return Ok(Expr::HasFlag(DefId::new(string(flag, "has_flag")?)));
+    }
+    if let Some(tag) = map.get("has_tag") {
+        return Ok(Expr::HasTag(string(tag, "has_tag")?));
+    }
+    if let Some(kind) = map.get("count") {
+        return Ok(Expr::CountEntities(DefId::new(string(kind, "count")?)));
+    }
+
+    let op = string(required(map, "op")?, "op")?;
+    let args = match map.get("args") {
+        Some(Value::List(args)) => args.iter().map(value_to_expr).collect::<Result<_>>()?,
+        Some(_) => return Err(format!("{}: args must be a list", op)),
+        None => Vec::new(),
+    };
+    operator(&op, args)
+}
+
+/// Build an operator expression from its arguments
+fn operator(op: &str, args: Vec<Expr>) -> Result<Expr> {
+    let binary: Option<BinaryOp> = match op {
+        "add" => Some(Expr::Add),
+        "sub" => Some(Expr::Sub),
+        "mul" => Some(Expr::Mul),
+        "div" => Some(Expr::Div),
+        "mod" => Some(Expr::Mod),
+        "min" => Some(Expr::Min),
+        "max" => Some(Expr::Max),
+        "eq" => Some(Expr::Eq),
+        "ne" => Some(Expr::Ne),
+        "lt" => Some(Expr::Lt),
+        "le" => Some(Expr::Le),
+        "gt" => Some(Expr::Gt),
+        "ge" => Some(Expr::Ge),
+        "random_range" => Some(Expr::RandomRange),
+        "random_int" => Some(Expr::RandomInt),
+        _ => None,
+    };
+    if let Some(binary) = binary {
+        let [a, b] = take(op, args)?;
+        return Ok(binary(a, b));
+    }
+
+    let unary: Option<fn(Box<Expr>) -> Expr> = match op {
+        "not" => Some(Expr::Not),
+        "neg" => Some(Expr::Neg),
+        "abs" => Some(Expr::Abs),
+        "floor" => Some(Expr::Floor),
+        "ceil" => Some(Expr::Ceil),
+        "round" => Some(Expr::Round),
+        _ => None,
+    };
+    if let Some(unary) = unary {
+        let [a] = take(op, args)?;
+        return Ok(unary(a));
+    }
+
+    match op {
+        "clamp" => {
+            let [value, min, max] = take(op, args)?;
+            Ok(Expr::Clamp(value, min, max))
+        }
+        "if" => {
+            let [condition, then, otherwise] = take(op, args)?;
+            Ok(Expr::If(condition, then, otherwise))
+        }
+        "random" => {
+            let [] = take(op, args)?;
+            Ok(Expr::Random)
+        }
+        "and" => Ok(Expr::And(args)),
+        "or" => Ok(Expr::Or(args)),
+        "concat" => Ok(Expr::Concat(args)),
+        _ => Err(format!("unknown operator {}", op)),
+    }
+}
+
+/// Check an operator's argument count and box the arguments
+fn take<const N: usize>(op: &str, args: Vec<Expr>) -> Result<[Box<Expr>; N]> {
+    let count = args.len();
+    args.into_iter()
+        .map(Box::new)
+        .collect::<Vec<_>>()
+        .try_into()
+        .map_err(|_| format!("{} takes {} args, got {}", op, N, count))
+}
+
+/// Convert a value to a list of effects (a list, or a single effect)
+pub(crate) fn value_to_effects(value: &Value) -> Result<Vec<Effect>> {
+    match value {
+        Value::List(effects) => effects.iter().map(value_to_effect).collect(),
+        Value::Null => Ok(Vec::new()),
+        _ => Ok(vec![value_to_effect(value)?]),
+    }
+}
+
+/// Convert a value to an effect
+pub(crate) fn value_to_effect(value: &Value) -> Result<Effect> {
+    let Value::Map(map) = value else {
+        return Err("effects must be dictionaries".to_string());
+    };
+    let op = string(required(map, "op")?, "op")?;
+    let expr = |key: &str| value_to_expr(required(map, key)?);
+    let def = |key: &str| string(required(map, key)?, key).map(DefId::new);
+    let target_or = |default: EntityRef| map.get("target").map_or(Ok(default), target);
+    let effects = |key: &str| map.get(key).map_or(Ok(Vec::new()), value_to_effects);
+
+    if let Some(modify) = modify_op(&op) {
+        let value = expr("value")?;
+        if let Some(global) = map.get("global") {
+            let property = string(global, "global")?;
+            return Ok(match modify {
+                ModifyOp::Set => Effect::SetGlobal { property, value },
+                op => Effect::ModifyGlobal {
+                    property,
+                    op,
+                    value,
+                },
+            });
+        }
+        let property = string(required(map, "property")?, "property")?;
+        return Ok(match (modify, map.get("target")) {
+            (ModifyOp::Set, None) => Effect::SetProperty { property, value },
+            (op, None) => Effect::ModifyProperty {
+                property,
+                op,
+                value,
+            },
+            (ModifyOp::Set, Some(t)) => Effect::SetEntityProperty {
+                target: target(t)?,
+                property,
+                value,
+            },
+            (op, Some(t)) => Effect::ModifyEntityProperty {
+                target: target(t)?,
+                property,
+                op,
+                value,
+            },
+        });
+    }
+
+    match op.as_str() {
+        "add_flag" | "remove_flag" => {
+            let flag = def("flag")?;
+            let add = op == "add_flag";
+            if let (true, Some(duration)) = (add, map.get("duration")) {
+                if map.contains_key("target") {
+                    return Err("timed flags can't have a target".to_string());
+                }
+                return Ok(Effect::AddFlagFor {
+                    flag,
+                    duration_ticks: value_to_expr(duration)?,
+                });
+            }
+            Ok(match (add, map.get("target")) {
+                (true, None) => Effect::AddFlag(flag),
+                (false, None) => Effect::RemoveFlag(flag),
+                (true, Some(t)) => Effect::AddEntityFlag {
+                    target: target(t)?,
+                    flag,
+                },
+                (false, Some(t)) => Effect::RemoveEntityFlag {
+                    target: target(t)?,
+                    flag,
+                },
+            })
+        }
+        "add_tag" => Ok(Effect::AddTag(string(required(map, "tag")?, "tag")?)),
+        "remove_tag" => Ok(Effect::RemoveTag(string(required(map, "tag")?, "tag")?)),
+        "spawn" => Ok(Effect::SpawnEntity {
+            kind: def("kind")?,
+            properties: named_exprs(map, "properties")?,
+        }),
+        "destroy" => Ok(match map.get("target") {
+            Some(t) => Effect::DestroyEntity(target(t)?),
+            None => Effect::DestroyTarget,
+        }),
+        "emit" => {
+            let event = def("event")?;
+            let target = target_or(EntityRef::None)?;
+            let params = named_exprs(map, "params")?;
+            Ok(match map.get("delay") {
+                Some(delay) => Effect::ScheduleEvent {
+                    event,
+                    target,
+                    delay_ticks: value_to_expr(delay)?,
+                    params,
+                },
+                None => Effect::EmitEvent {
+                    event,
+                    target,
+                    params,
+                },
+            })
+        }
+        "if" => Ok(Effect::If {
+            condition: expr("condition")?,
+            then_effects: effects("then")?,
+            else_effects: effects("else")?,
+        }),
+        "for_each" => Ok(Effect::ForEachEntity {
+            kind: def("kind")?,
+            filter: map.get("filter").map(value_to_expr).transpose()?,
+            effects: effects("effects")?,
+        }),
+        "log" => Ok(Effect::Log {
+            level: match map.get("level").and_then(|l| l.as_str()) {
+                None | Some("info") => LogLevel::Info,
+                Some("debug") => LogLevel::Debug,
+                Some("warn") => LogLevel::Warn,
+                Some("error") => LogLevel::Error,
+                Some(level) => return Err(format!("unknown log level {}", level)),
+            },
+            message: expr("message")?,
+        }),
+        "notify" => Ok(Effect::Notify {
+            kind: def("kind")?,
+            title: expr("title")?,
+            message: expr("message")?,
+            target: target_or(EntityRef::None)?,
+        }),
+        _ => Err(format!("unknown effect {}", op)),
+    }
+}
+
+fn modify_op(op: &str) -> Option<ModifyOp> {
+    match op {
+        "set" => Some(ModifyOp::Set),
+        "add" => Some(ModifyOp::Add),
+        "sub" => Some(ModifyOp::Sub),
+        "mul" => Some(ModifyOp::Mul),
+        "div" => Some(ModifyOp::Div),
+        "min" => Some(ModifyOp::Min),
+        "max" => Some(ModifyOp::Max),
+        _ => None,
+    }
+}
+
+/// Convert an entity ID, `"global"`, a definition ID, an array of entity IDs,
+/// or `{"kind": kind}` to a target
+fn target(value: &Value) -> Result<EntityRef> {
+    match value {
+        Value::Int(id) if *id >= 0 => Ok(EntityRef::Entity(EntityId::new(*id as u64))),
+        Value::EntityRef(id) => Ok(EntityRef::Entity(*id)),
+        Value::String(s) if s == "global" => Ok(EntityRef::Global),
+        Value::String(s) => Ok(EntityRef::ByDef(DefId::new(s.clone()))),
+        Value::List(ids) => ids
+            .iter()
+            .map(|id| match target(id)? {
+                EntityRef::Entity(id) => Ok(id),
+                _ => Err(format!("invalid entity ID {:?}", id)),
+            })
+            .collect::<Result<_>>()
+            .map(EntityRef::Set),
+        Value::Map(map) => match map.get("kind") {
+            Some(kind) => Ok(EntityRef::Kind(DefId::new(string(kind, "kind")?))),
+            None => Err("target dictionaries need a kind".to_string()),
+        },
+        _ => Err(format!("invalid target {:?}", value)),
+    }
+}
+
+/// Convert a dictionary of names to expressions
+fn named_exprs(map: &ValueMap, key: &str) -> Result<Vec<(String, Expr)>> {
+    match map.get(key) {
+        Some(Value::Map(entries)) => entries
+            .iter()
+            .map(|(name, value)| Ok((name.to_string(), value_to_expr(value)?)))
+            .collect(),
+        Some(_) => Err(format!("{} must be a dictionary", key)),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn required<'a>(map: &'a ValueMap, key: &str) -> Result<&'a Value> {
+    map.get(key).ok_or_else(|| format!("missing {}", key))
+}
+
+fn string(value: &Value, key: &str) -> Result<String> {
+    value
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| format!("{} must be a string", key))
+}
@@ -1,7 +1,7 @@
 //! Type conversion between Pulsive and Godot types
 
 use godot::prelude::*;
-use pulsive_core::{Value, ValueMap};
+use pulsive_core::{Entity, EntityRef, Value, ValueMap};
 
 /// Convert a Pulsive Value to a Godot Variant
 pub fn value_to_variant(value: &Value) -> Variant {
@@ -31,6 +31,40 @@ pub fn variant_to_value(variant: &Variant) -> Value {
         VariantType::INT => Value::Int(variant.to::<i64>()),
         VariantType::FLOAT => Value::Float(variant.to::<f64>()),
         VariantType::STRING => Value::String(variant.to::<GString>().to_string()),
+        VariantType::STRING_NAME => Value::String(variant.to::<StringName>().to_string()),
+        VariantType::NODE_PATH => Value::String(variant.to::<NodePath>().to_string()),
+        VariantType::VECTOR2 => {
+            let v = variant.to::<Vector2>();
+            vector_to_value(&[("x", v.x as f64), ("y", v.y as f64)])
+        }
+        VariantType::VECTOR3 => {
+            let v = variant.to::<Vector3>();
+            vector_to_value(&[("x", v.x as f64), ("y", v.y as f64), ("z", v.z as f64)])
+        }
+        VariantType::PACKED_INT64_ARRAY => Value::List(
+            variant
+                .to::<PackedInt64Array>()
+                .as_slice()
+                .iter()
+                .map(|i| Value::Int(*i))
+                .collect(),
+        ),
+        VariantType::PACKED_FLOAT64_ARRAY => Value::List(
+            variant
+                .to::<PackedFloat64Array>()
+                .as_slice()
+                .iter()
+                .map(|f| Value::Float(*f))
+                .collect(),
+        ),
+        VariantType::PACKED_STRING_ARRAY => Value::List(
+            variant
+                .to::<PackedStringArray>()
+                .as_slice()
+                .iter()
+                .map(|s| Value::String(s.to_string()))
+                .collect(),
+        ),
         VariantType::ARRAY => {
             let arr = variant.to::<Array<Variant>>();
             let list: Vec<Value> = arr.iter_shared().map(|v| variant_to_value(&v)).collect();
@@ -51,7 +85,7 @@ pub fn variant_to_value(variant: &Variant) -> Value {
 pub fn value_map_to_dict(map: &ValueMap) -> VarDictionary {
     let mut dict = VarDictionary::new();
     for (key, value) in map {
-        dict.set(key.clone(), value_to_variant(value));
+        dict.set(key.to_string(), value_to_variant(value));
     }
     dict
 }
@@ -65,3 +99,34 @@ pub fn dict_to_value_map(dict: &VarDictionary) -> ValueMap {
     }
     map
 }
+
+/// Convert vector components to a map Value (e.g. `{x, y}`)
+fn vector_to_value(components: &[(&str, f64)]) -> Value {
+    let mut map = ValueMap::new();
+    for (name, component) in components {
+        map.insert(name.to_string(), Value::Float(*component));
+    }
+    Value::Map(map)
+}
+
+/// Convert an entity to a VarDictionary with `id`, `kind`, `properties` and
+/// `flags`
+pub fn entity_to_dict(entity: &Entity) -> VarDictionary {
+    let mut dict = VarDictionary::new();
+    dict.set("id", entity.id.raw() as i64);
+    dict.set("kind", entity.kind.as_str());
+    dict.set("properties", value_map_to_dict(&entity.properties));
+    let mut flags: Vec<&str> = entity.flags.iter().map(|f| f.as_str()).collect();
+    flags.sort();
+    let flags: PackedStringArray = flags.into_iter().map(GString::from).collect();
+    dict.set("flags", flags);
+    dict
+}
+
+/// Convert a target to an entity ID, or -1 for none/global targets
+pub fn target_to_id(target: &EntityRef) -> i64 {
+    match target {
+        EntityRef::Entity(id) => id.raw() as i64,
+        _ => -1,
+    }
+}
@@ -0,0 +1,343 @@
+//! Node class connecting to a `PulsiveServer` with client-side prediction
+
+use godot::prelude::*;
+use pulsive_core::{ActorId, Model};
+use pulsive_netcode::{Address, ConnectionStats, Interpolator, PredictionEngine, Transport};
+use pulsive_rollback_buffer::RollbackBuffer;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use crate::bridge::{entity_to_dict, value_to_variant};
+use crate::debug::connection_stats_to_dict;
+use crate::engine::PulsiveEngine;
+use crate::net::{any_addr, Packet, UdpTransport};
+
+/// Predicted states kept for reconciliation
+const HISTORY_CAPACITY: usize = 256;
+
+/// Seconds between hellos until the server answers
+const HELLO_INTERVAL: f64 = 0.5;
+
+/// A node connecting a local PulsiveEngine to a `PulsiveServer`
+///
+/// Inputs are applied to the local engine right away (prediction) and sent
+/// to the server. Each server state replaces the local model, replaying
+/// inputs the server has not applied yet (reconciliation). Server states are
+/// also interpolated for smooth rendering with `get_interpolated_*`.
+///
+/// The local engine needs the same definitions as the server and should not
+/// tick on its own; server states drive the simulation.
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct PulsiveClient {
+    base: Base<Node>,
+    /// Path to the local PulsiveEngine node
+    #[export]
+    engine_path: NodePath,
+    /// The engine, found on ready
+    engine: Option<Gd<PulsiveEngine>>,
+    /// Socket, while connected
+    transport: Option<UdpTransport>,
+    /// Server address, while connected
+    server: Option<Address>,
+    /// Predicted states and unacknowledged inputs
+    prediction: PredictionEngine<RollbackBuffer>,
+    /// Last two server states
+    interpolator: Interpolator,
+    /// Interpolated server state for this frame
+    view: Option<Model>,
+    /// Seconds since the last server state
+    since_state: f64,
+    /// Seconds between the last two server states
+    state_interval: f64,
+    /// Tick of the last server state (-1 before the first)
+    server_tick: i64,
+    /// Seconds until the next hello
+    hello_timer: f64,
+    /// Ticks and send times of unacknowledged inputs, for RTT samples
+    sent_inputs: VecDeque<(u64, Instant)>,
+}
+
+#[godot_api]
+impl INode for PulsiveClient {
+    fn init(base: Base<Node>) -> Self {
+        Self {
+            base,
+            engine_path: NodePath::default(),
+            engine: None,
+            transport: None,
+            server: None,
+            prediction: PredictionEngine::new(RollbackBuffer::new(HISTORY_CAPACITY)),
+            interpolator: Interpolator::new(),
+            view: None,
+            since_state: 0.0,
+            state_interval: 0.0,
+            server_tick: -1,
+            hello_timer: 0.0,
+            sent_inputs: VecDeque::new(),
+        }
+    }
+
+    fn ready(&mut self) {
+        self.engine = self
+            .base()
+            .try_get_node_as::<PulsiveEngine>(&self.engine_path);
+        if self.engine.is_none() {
+            godot_error!("PulsiveClient: no PulsiveEngine at {}", self.engine_path);
+        }
+    }
+
+    fn process(&mut self, delta: f64) {
+        if self.transport.is_none() {
+            return;
+        }
+        if self.server_tick < 0 {
+            self.hello_timer -= delta;
+            if self.hello_timer <= 0.0 {
+                self.hello_timer = HELLO_INTERVAL;
+                self.send(&Packet::Hello);
+            }
+        }
+        self.receive();
+
+        self.since_state += delta;
+        let alpha = self.get_interpolation_alpha();
+        self.view = self.interpolator.interpolate(alpha as f32);
+    }
+}
+
+#[godot_api]
+impl PulsiveClient {
+    /// The first state arrived from the server
+    #[signal]
+    fn connected();
+
+    /// A state arrived from the server and was reconciled
+    #[signal]
+    fn state_received(tick: i64);
+
+    /// Connect to a server
+    #[func]
+    fn connect_to_server(&mut self, host: GString, port: i64) -> bool {
+        let server = match UdpTransport::resolve(&host.to_string(), port as u16) {
+            Ok(server) => server,
+            Err(e) => {
+                godot_error!("PulsiveClient: can't resolve {}: {}", host, e);
+                return false;
+            }
+        };
+        match UdpTransport::bind(any_addr()) {
+            Ok(transport) => {
+                self.disconnect_from_server();
+                self.transport = Some(transport);
+                self.server = Some(server);
+                true
+            }
+            Err(e) => {
+                godot_error!("PulsiveClient: failed to open socket: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Disconnect and reset prediction and interpolation
+    #[func]
+    fn disconnect_from_server(&mut self) {
+        self.transport = None;
+        self.server = None;
+        self.prediction.reset();
+        self.interpolator.reset();
+        self.view = None;
+        self.since_state = 0.0;
+        self.state_interval = 0.0;
+        self.server_tick = -1;
+        self.hello_timer = 0.0;
+        self.sent_inputs.clear();
+    }
+
+    /// Check if the server has answered
+    #[func]
+    fn is_connected_to_server(&self) -> bool {
+        self.transport.is_some() && self.server_tick >= 0
+    }
+
+    /// Apply an action locally and send it to the server
+    #[func]
+    fn send_input(&mut self, action_type: GString, target_id: i64, params: VarDictionary) -> bool {
+        let Some(engine) = &mut self.engine else {
+            return false;
+        };
+        if self.transport.is_none() {
+            godot_error!("PulsiveClient: not connected");
+            return false;
+        }
+        let tick = self.prediction.predicted_tick();
+        let mut engine = engine.bind_mut();
+        // The server replaces the actor with this client's ID
+        let msg = engine.action_msg(&action_type, target_id, &params, ActorId::new(1));
+        let (model, runtime) = engine.model_and_runtime_mut();
+        if let Err(e) = self.prediction.predict(model, runtime, msg.clone()) {
+            godot_error!("PulsiveClient: {}", e);
+            return false;
+        }
+        drop(engine);
+        self.sent_inputs.push_back((tick, Instant::now()));
+        self.send(&Packet::Input { tick, msg });
+        true
+    }
+
+    /// Get the tick of the last server state (-1 before the first)
+    #[func]
+    fn get_server_tick(&self) -> i64 {
+        self.server_tick
+    }
+
+    /// Get the number of inputs the server has not acknowledged
+    #[func]
+    fn get_pending_inputs(&self) -> i64 {
+        self.prediction.pending_inputs() as i64
+    }
+
+    /// Get network and prediction metrics as a dictionary (see
+    /// `ConnectionStats`); packet loss is not measured over UDP states
+    #[func]
+    fn get_connection_stats(&self) -> VarDictionary {
+        connection_stats_to_dict(self.prediction.stats())
+    }
+
+    /// Get network and prediction metrics
+    pub(crate) fn connection_stats(&self) -> &ConnectionStats {
+        self.prediction.stats()
+    }
+
+    /// Get how far rendering is between the last two server states (0 to 1)
+    #[func]
+    fn get_interpolation_alpha(&self) -> f64 {
+        if self.state_interval <= 0.0 {
+            return 1.0;
+        }
+        (self.since_state / self.state_interval).clamp(0.0, 1.0)
+    }
+
+    /// Get an entity's property, interpolated between the last two server
+    /// states
+    #[func]
+    fn get_interpolated_property(&self, entity_id: i64, property: GString) -> Variant {
+        let id = pulsive_core::EntityId::new(entity_id as u64);
+        if let Some(entity) = self.view.as_ref().and_then(|m| m.entities().get(id)) {
+            if let Some(value) = entity.get(&property.to_string()) {
+                return value_to_variant(value);
+            }
+        }
+        Variant::nil()
+    }
+
+    /// Get an entity, interpolated between the last two server states, as a
+    /// dictionary with `id`, `kind`, `properties` and `flags`
+    #[func]
+    fn get_interpolated_entity(&self, entity_id: i64) -> VarDictionary {
+        let id = pulsive_core::EntityId::new(entity_id as u64);
+        if let Some(entity) = self.view.as_ref().and_then(|m| m.entities().get(id)) {
+            return entity_to_dict(entity);
+        }
+        VarDictionary::new()
+    }
+
+    fn receive(&mut self) {
+        let Some(transport) = &self.transport else {
+            return;
+        };
+        let mut states = Vec::new();
+        let mut received = Vec::new();
+        loop {
+            match transport.recv() {
+                Ok(Some((data, source))) if Some(&source) == self.server.as_ref() => {
+                    received.push(data.len());
+                    if let Some(Packet::State { tick, ack, model }) = Packet::decode(&data) {
+                        states.push((tick, ack, model));
+                    }
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(e) => {
+                    godot_error!("PulsiveClient: receive failed: {}", e);
+                    break;
+                }
+            }
+        }
+
+        let stats = self.prediction.stats_mut();
+        for bytes in received {
+            stats.record_received(bytes);
+        }
+
+        for (tick, ack, model) in states {
+            if (tick as i64) <= self.server_tick {
+                continue;
+            }
+            self.reconcile(ack, &model);
+            self.interpolator.push_state(tick, model);
+            self.state_interval = self.since_state;
+            self.since_state = 0.0;
+
+            let first = self.server_tick < 0;
+            self.server_tick = tick as i64;
+            if first {
+                self.base_mut().emit_signal("connected", &[]);
+            }
+            self.base_mut()
+                .emit_signal("state_received", &[self.server_tick.to_variant()]);
+        }
+    }
+
+    /// Replace the local model with a server state, replaying inputs the
+    /// server has not applied yet
+    fn reconcile(&mut self, ack: Option<u64>, state: &Model) {
+        if let Some(ack) = ack {
+            // The newest input this state acknowledges measures the round trip
+            let mut acked = None;
+            while let Some(&(tick, sent)) = self.sent_inputs.front() {
+                if tick > ack {
+                    break;
+                }
+                acked = Some(sent);
+                self.sent_inputs.pop_front();
+            }
+            if let Some(sent) = acked {
+                self.prediction.stats_mut().record_rtt(sent.elapsed());
+            }
+        }
+
+        let Some(engine) = &mut self.engine else {
+            return;
+        };
+        let mut engine = engine.bind_mut();
+        let (model, runtime) = engine.model_and_runtime_mut();
+        match ack {
+            Some(ack) => {
+                if let Err(e) = self.prediction.reconcile(model, runtime, state, ack) {
+                    godot_error!("PulsiveClient: {}", e);
+                }
+            }
+            // The server has none of our inputs yet; keep predicting until it does
+            None if self.prediction.pending_inputs() > 0 => {}
+            None => model.restore(state.clone()),
+        }
+    }
+
+    fn send(&mut self, packet: &Packet) {
+        let (Some(transport), Some(server)) = (&self.transport, &self.server) else {
+            return;
+        };
+        let result = packet.encode().map_err(|e| e.to_string()).and_then(|data| {
+            transport
+                .send(&data, server)
+                .map(|()| data.len())
+                .map_err(|e| e.to_string())
+        });
+        match result {
+            Ok(bytes) => self.prediction.stats_mut().record_sent(bytes),
+            Err(e) => godot_error!("PulsiveClient: send failed: {}", e),
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Debug statistics and the overlay node showing them
+
+use godot::classes::{CanvasLayer, ICanvasLayer, Label};
+use godot::prelude::*;
+use pulsive_netcode::ConnectionStats;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::client::PulsiveClient;
+use crate::engine::PulsiveEngine;
+
+/// Performance and size statistics of a PulsiveEngine
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DebugStats {
+    /// Current tick
+    pub tick: u64,
+    /// Duration of the last tick
+    pub tick_time: Duration,
+    /// Registered event handlers
+    pub event_handlers: usize,
+    /// Registered tick handlers
+    pub tick_handlers: usize,
+    /// Messages waiting to be processed
+    pub queued_messages: usize,
+    /// Messages scheduled for future ticks
+    pub scheduled_messages: usize,
+    /// Total entities
+    pub entity_count: usize,
+    /// Entities per kind, sorted by kind
+    pub entities_by_kind: BTreeMap<String, usize>,
+    /// Threads used by parallel ticks
+    pub threads: usize,
+    /// Conflicting writes reported by parallel ticks
+    pub hub_conflicts: usize,
+    /// Journal entries (0 if not recording)
+    pub journal_entries: usize,
+    /// Journal snapshots (0 if not recording)
+    pub journal_snapshots: usize,
+}
+
+impl DebugStats {
+    /// Convert to a dictionary
+    pub fn to_dict(&self) -> VarDictionary {
+        let mut by_kind = VarDictionary::new();
+        for (kind, count) in &self.entities_by_kind {
+            by_kind.set(GString::from(kind.as_str()), *count as i64);
+        }
+        let mut dict = VarDictionary::new();
+        dict.set("tick", self.tick as i64);
+        dict.set("tick_usec", self.tick_time.as_micros() as i64);
+        dict.set("event_handlers", self.event_handlers as i64);
+        dict.set("tick_handlers", self.tick_handlers as i64);
+        dict.set("queued_messages", self.queued_messages as i64);
+        dict.set("scheduled_messages", self.scheduled_messages as i64);
+        dict.set("entity_count", self.entity_count as i64);
+        dict.set("entities_by_kind", by_kind);
+        dict.set("threads", self.threads as i64);
+        dict.set("hub_conflicts", self.hub_conflicts as i64);
+        dict.set("journal_entries", self.journal_entries as i64);
+        dict.set("journal_snapshots", self.journal_snapshots as i64);
+        dict
+    }
+}
+
+impl fmt::Display for DebugStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Tick {} ({:.2} ms, {} threads)",
+            self.tick,
+            self.tick_time.as_secs_f64() * 1000.0,
+            self.threads
+        )?;
+        writeln!(
+            f,
+            "Handlers: {} event, {} tick",
+            self.event_handlers, self.tick_handlers
+        )?;
+        writeln!(
+            f,
+            "Messages: {} queued, {} scheduled",
+            self.queued_messages, self.scheduled_messages
+        )?;
+        writeln!(f, "Entities: {}", self.entity_count)?;
+        for (kind, count) in &self.entities_by_kind {
+            writeln!(f, "  {}: {}", kind, count)?;
+        }
+        writeln!(f, "Hub conflicts: {}", self.hub_conflicts)?;
+        write!(
+            f,
+            "Journal: {} entries, {} snapshots",
+            self.journal_entries, self.journal_snapshots
+        )
+    }
+}
+
+/// Convert a connection's metrics to a dictionary
+pub(crate) fn connection_stats_to_dict(stats: &ConnectionStats) -> VarDictionary {
+    let mut dict = VarDictionary::new();
+    let rtt = stats.rtt().map_or(-1, |rtt| rtt.as_micros() as i64);
+    dict.set("rtt_usec", rtt);
+    dict.set("jitter_usec", stats.jitter().as_micros() as i64);
+    dict.set("packet_loss", stats.packet_loss());
+    dict.set("packets_sent", stats.packets_sent() as i64);
+    dict.set("packets_received", stats.packets_received() as i64);
+    dict.set("bytes_sent", stats.bytes_sent() as i64);
+    dict.set("bytes_received", stats.bytes_received() as i64);
+    dict.set("corrections", stats.corrections() as i64);
+    dict.set(
+        "corrections_per_second",
+        stats.corrections_per_second(Instant::now()),
+    );
+    dict.set("misprediction", stats.misprediction());
+    dict
+}
+
+/// Format a connection's metrics for the overlay
+fn format_connection_stats(stats: &ConnectionStats) -> String {
+    let rtt = match stats.rtt() {
+        Some(rtt) => format!("{:.1} ms", rtt.as_secs_f64() * 1000.0),
+        None => "-".to_string(),
+    };
+    format!(
+        "RTT: {} (jitter {:.1} ms)\nTraffic: {} B in, {} B out\nCorrections: {}/s, misprediction {:.2}",
+        rtt,
+        stats.jitter().as_secs_f64() * 1000.0,
+        stats.bytes_received(),
+        stats.bytes_sent(),
+        stats.corrections_per_second(Instant::now()),
+        stats.misprediction()
+    )
+}
+
+/// A CanvasLayer showing a PulsiveEngine's debug statistics in a corner of
+/// the screen, and a PulsiveClient's connection metrics if one is set
+#[derive(GodotClass)]
+#[class(base=CanvasLayer)]
+pub struct PulsiveDebugOverlay {
+    base: Base<CanvasLayer>,
+    /// Path to the PulsiveEngine node
+    #[export]
+    engine_path: NodePath,
+    /// Path to a PulsiveClient node (optional)
+    #[export]
+    client_path: NodePath,
+    /// Seconds between refreshes
+    #[export]
+    refresh_interval: f64,
+    /// The engine, found on ready
+    engine: Option<Gd<PulsiveEngine>>,
+    /// The client, found on ready
+    client: Option<Gd<PulsiveClient>>,
+    /// Label showing the statistics
+    label: Option<Gd<Label>>,
+    /// Seconds until the next refresh
+    timer: f64,
+}
+
+#[godot_api]
+impl ICanvasLayer for PulsiveDebugOverlay {
+    fn init(base: Base<CanvasLayer>) -> Self {
+        Self {
+            base,
+            engine_path: NodePath::default(),
+            client_path: NodePath::default(),
+            refresh_interval: 0.25,
+            engine: None,
+            client: None,
+            label: None,
+            timer: 0.0,
+        }
+    }
+
+    fn ready(&mut self) {
+        self.engine = self
+            .base()
+            .try_get_node_as::<PulsiveEngine>(&self.engine_path);
+        if self.engine.is_none() {
+            godot_error!(
+                "PulsiveDebugOverlay: no PulsiveEngine at {}",
+                self.engine_path
+            );
+        }
+        if !self.client_path.is_empty() {
+            self.client = self
+                .base()
+                .try_get_node_as::<PulsiveClient>(&self.client_path);
+            if self.client.is_none() {
+                godot_error!(
+                    "PulsiveDebugOverlay: no PulsiveClient at {}",
+                    self.client_path
+                );
+            }
+        }
+        let mut label = Label::new_alloc();
+        label.set_position(Vector2::new(8.0, 8.0));
+        self.base_mut().add_child(&label);
+        self.label = Some(label);
+    }
+
+    fn process(&mut self, delta: f64) {
+        self.timer -= delta;
+        if self.timer > 0.0 {
+            return;
+        }
+        self.timer = self.refresh_interval;
+        let (Some(engine), Some(label)) = (&self.engine, &mut self.label) else {
+            return;
+        };
+        let mut text = engine.bind().debug_stats().to_string();
+        if let Some(client) = &self.client {
+            text.push('\n');
+            text.push_str(&format_connection_stats(client.bind().connection_stats()));
+        }
+        label.set_text(&GString::from(text.as_str()));
+    }
+}
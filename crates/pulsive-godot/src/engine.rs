@@ -1,12 +1,37 @@
 //! Main engine class for Godot integration
 
+use godot::classes::{DirAccess, FileAccess, ProjectSettings};
 use godot::prelude::*;
-use pulsive_core::{ActorId, DefId, EntityRef, Model, Msg, Runtime, Speed, UpdateResult};
+use pulsive_core::{
+    ActorId, DefId, Effect, Entity, EntityRef, EventHandler, Expr, LogSink, Model, Msg, Runtime,
+    Speed, TickHandler, UpdateResult, Value,
+};
 use pulsive_db::Store;
-use pulsive_script::{GameDefs, Loader};
+use pulsive_hub::{
+    Core, CoreId, GroupId, Hub, HubConfig, PartitionStrategy, ResolutionStrategy, TickSyncGroup,
+};
+use pulsive_script::{Format, GameDefs, Loader};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::bridge::{dict_to_value_map, value_map_to_dict, value_to_variant, variant_to_value};
+/// Most fixed ticks run per frame; time beyond that is dropped so a slow
+/// frame can't snowball into ever more ticks
+const MAX_TICKS_PER_FRAME: u32 = 8;
+
+/// Database for `save_game()` when no database is configured
+const DEFAULT_SAVE_PATH: &str = "user://pulsive_saves.db";
+
+use crate::authoring::{value_to_effects, value_to_expr};
+use crate::debug::DebugStats;
+use crate::history::History;
+use crate::log::{parse_log_level, GodotLogSink};
+
+use crate::bridge::{
+    dict_to_value_map, entity_to_dict, target_to_id, value_map_to_dict, value_to_variant,
+    variant_to_value,
+};
 
 /// The main Pulsive engine exposed to Godot
 #[derive(GodotClass)]
@@ -25,6 +50,28 @@ pub struct PulsiveEngine {
     db_path: GString,
     /// Path to the scripts directory
     scripts_path: GString,
+    /// Hub for parallel ticks (built on demand)
+    hub: Option<Hub>,
+    /// Number of hub cores
+    thread_count: usize,
+    /// Partition strategy for hub cores
+    partition: PartitionStrategy,
+    /// Conflict resolution for hub cores, by name
+    resolution: GString,
+    /// Recorded history for time-travel debugging (when enabled)
+    history: Option<History>,
+    /// Fixed ticks per second run from `process` (0 = tick manually)
+    tick_rate: f64,
+    /// Frame time not yet consumed by fixed ticks, in seconds
+    accumulator: f64,
+    /// Whether to interpolate float properties between the last two ticks
+    interpolate: bool,
+    /// Model before the last fixed tick (when interpolating)
+    previous: Option<Model>,
+    /// Duration of the last tick
+    last_tick_time: Duration,
+    /// Conflicting writes reported by parallel ticks
+    conflict_count: usize,
 }
 
 #[godot_api]
@@ -38,16 +85,76 @@ impl INode for PulsiveEngine {
             defs: GameDefs::new(),
             db_path: GString::new(),
             scripts_path: GString::new(),
+            hub: None,
+            thread_count: 1,
+            partition: PartitionStrategy::by_id(),
+            resolution: GString::from("abort"),
+            history: None,
+            tick_rate: 0.0,
+            accumulator: 0.0,
+            interpolate: false,
+            previous: None,
+            last_tick_time: Duration::ZERO,
+            conflict_count: 0,
         }
     }
 
     fn ready(&mut self) {
         godot_print!("Pulsive Engine initialized");
     }
+
+    fn process(&mut self, delta: f64) {
+        if self.tick_rate <= 0.0 || self.model.time.speed.is_paused() {
+            return;
+        }
+        let step = 1.0 / self.tick_rate;
+        self.accumulator += delta;
+        let ticks = ((self.accumulator / step) as u32).min(MAX_TICKS_PER_FRAME);
+        for i in 0..ticks {
+            if self.interpolate && i + 1 == ticks {
+                self.previous = Some(self.model.clone());
+            }
+            self.tick();
+        }
+        self.accumulator = (self.accumulator - ticks as f64 * step).min(step);
+    }
 }
 
 #[godot_api]
 impl PulsiveEngine {
+    // === Signals ===
+    //
+    // Emitted after each tick, action or event, in the order the handlers
+    // produced them. Targets are entity IDs, or -1 for none/global.
+
+    /// An event emitted by a handler
+    #[signal]
+    fn pulsive_event(event_id: GString, target_id: i64, params: VarDictionary);
+
+    /// A notification for the UI
+    #[signal]
+    fn pulsive_notification(kind: GString, title: GString, message: GString, target_id: i64);
+
+    /// A log record, at the tick it was logged
+    #[signal]
+    fn pulsive_log(level: GString, message: GString, tick: i64, target_id: i64);
+
+    /// A `save_game()` finished
+    #[signal]
+    fn game_saved(slot: GString);
+
+    /// A `load_game()` finished
+    #[signal]
+    fn game_loaded(slot: GString);
+
+    /// A `save_game()` failed
+    #[signal]
+    fn save_failed(slot: GString, error: GString);
+
+    /// A `load_game()` failed
+    #[signal]
+    fn load_failed(slot: GString, error: GString);
+
     // === Configuration ===
 
     /// Set the path to the database file
@@ -69,20 +176,9 @@ impl PulsiveEngine {
     fn initialize(&mut self) -> bool {
         // Load scripts if path is set
         if !self.scripts_path.is_empty() {
-            let path = PathBuf::from(self.scripts_path.to_string());
-            if path.exists() {
-                let mut loader = Loader::new();
-                if let Err(e) = loader.load_directory(&path) {
-                    godot_error!("Failed to load scripts: {}", e);
-                    return false;
-                }
-                self.defs = loader.finish();
-                godot_print!(
-                    "Loaded {} resources, {} events, {} entity types",
-                    self.defs.resources.len(),
-                    self.defs.events.len(),
-                    self.defs.entity_types.len()
-                );
+            let path = self.scripts_path.clone();
+            if !self.load_defs(path) {
+                return false;
             }
         }
 
@@ -120,6 +216,33 @@ impl PulsiveEngine {
         }
     }
 
+    /// Load RON definitions from a file or directory and install their
+    /// handlers, templates, curves and resource globals
+    ///
+    /// Paths are read through Godot's file system, so `res://` and `user://`
+    /// paths work in exported games. Each file should be loaded once.
+    #[func]
+    fn load_defs(&mut self, path: GString) -> bool {
+        let mut loader = Loader::new();
+        if let Err(e) = load_defs_path(&mut loader, &path.to_string()) {
+            godot_error!("Failed to load definitions from {}: {}", path, e);
+            return false;
+        }
+        let defs = loader.finish();
+        defs.install(&mut self.runtime, &mut self.model);
+        // Rebuild hub cores with the new handlers on the next parallel tick
+        self.hub = None;
+        godot_print!(
+            "Loaded {} resources, {} events, {} entity types from {}",
+            defs.resources.len(),
+            defs.events.len(),
+            defs.entity_types.len(),
+            path
+        );
+        self.merge_defs(defs);
+        true
+    }
+
     // === Model/State Access ===
 
     /// Create a new entity of the given type
@@ -141,12 +264,17 @@ impl PulsiveEngine {
         Variant::nil()
     }
 
-    /// Set an entity's property
+    /// Set an entity's property (computed properties are read-only)
     #[func]
     fn set_property(&mut self, entity_id: i64, property: GString, value: Variant) {
         let id = pulsive_core::EntityId::new(entity_id as u64);
+        let property = property.to_string();
         if let Some(entity) = self.model.entities_mut().get_mut(id) {
-            entity.set(property.to_string(), variant_to_value(&value));
+            if self.runtime.is_computed(&entity.kind, &property) {
+                godot_warn!("Cannot set computed property {}", property);
+                return;
+            }
+            entity.set(property, variant_to_value(&value));
         }
     }
 
@@ -160,9 +288,9 @@ impl PulsiveEngine {
         VarDictionary::new()
     }
 
-    /// Delete an entity
+    /// Destroy an entity
     #[func]
-    fn delete_entity(&mut self, entity_id: i64) -> bool {
+    fn destroy_entity(&mut self, entity_id: i64) -> bool {
         let id = pulsive_core::EntityId::new(entity_id as u64);
         self.model.entities_mut().remove(id).is_some()
     }
@@ -180,6 +308,53 @@ impl PulsiveEngine {
         PackedInt64Array::from(ids.as_slice())
     }
 
+    /// Get all entities of a given type as dictionaries with `id`, `kind`,
+    /// `properties` and `flags`
+    #[func]
+    fn query_entities(&self, kind: GString) -> Array<VarDictionary> {
+        self.sorted_by_kind(&kind)
+            .into_iter()
+            .map(entity_to_dict)
+            .collect()
+    }
+
+    /// Get a numeric property of all entities of a given type, ordered by
+    /// entity ID (0 where missing), e.g. for MultiMesh instance data
+    #[func]
+    fn get_property_batch(&self, kind: GString, property: GString) -> PackedFloat64Array {
+        let property = property.to_string();
+        let values: Vec<f64> = self
+            .sorted_by_kind(&kind)
+            .into_iter()
+            .map(|e| self.interpolated_number(e, &property))
+            .collect();
+        PackedFloat64Array::from(values.as_slice())
+    }
+
+    /// Get 2D positions of all entities of a given type from two numeric
+    /// properties, ordered by entity ID (0 where missing), e.g. for
+    /// MultiMesh instance transforms
+    #[func]
+    fn get_positions_2d(
+        &self,
+        kind: GString,
+        x_prop: GString,
+        y_prop: GString,
+    ) -> PackedVector2Array {
+        let (x_prop, y_prop) = (x_prop.to_string(), y_prop.to_string());
+        let positions: Vec<Vector2> = self
+            .sorted_by_kind(&kind)
+            .into_iter()
+            .map(|e| {
+                Vector2::new(
+                    self.interpolated_number(e, &x_prop) as f32,
+                    self.interpolated_number(e, &y_prop) as f32,
+                )
+            })
+            .collect();
+        PackedVector2Array::from(positions.as_slice())
+    }
+
     // === Global State ===
 
     /// Get a global property
@@ -258,10 +433,241 @@ impl PulsiveEngine {
     /// Advance the simulation by one tick
     #[func]
     fn tick(&mut self) -> VarDictionary {
-        let result = self.runtime.tick(&mut self.model);
+        let start = Instant::now();
+        let result = match &mut self.history {
+            Some(history) => self
+                .runtime
+                .tick_with_journal(&mut self.model, history.journal_mut()),
+            None => self.runtime.tick(&mut self.model),
+        };
+        self.last_tick_time = start.elapsed();
+        self.emit_result_signals(&result);
         self.update_result_to_dict(&result)
     }
 
+    // === Logging ===
+
+    /// Print log records at or above a level (`"debug"`, `"info"`,
+    /// `"warn"` or `"error"`) to the Godot output, or stop printing them
+    /// with `""`
+    ///
+    /// Returns false for an unknown level.
+    #[func]
+    fn set_log_print_level(&mut self, level: GString) -> bool {
+        let level = level.to_string();
+        let sink: Option<Arc<dyn LogSink>> = if level.is_empty() {
+            None
+        } else {
+            match parse_log_level(&level) {
+                Some(min_level) => Some(Arc::new(GodotLogSink { min_level })),
+                None => return false,
+            }
+        };
+        self.runtime.set_log_sink(sink);
+        if self.hub.is_some() {
+            self.build_hub();
+        }
+        true
+    }
+
+    // === Fixed Timestep ===
+
+    /// Tick automatically at a fixed rate (ticks per second) independent of
+    /// the frame rate, or 0 to tick manually
+    ///
+    /// Ticks stop while the speed is paused.
+    #[func]
+    fn set_tick_rate(&mut self, ticks_per_second: f64) {
+        self.tick_rate = ticks_per_second.max(0.0);
+        self.accumulator = 0.0;
+    }
+
+    /// Get the fixed tick rate (0 = manual)
+    #[func]
+    fn get_tick_rate(&self) -> f64 {
+        self.tick_rate
+    }
+
+    /// Get how far the current frame is between the last tick and the next
+    /// one (0 to 1), for interpolating rendered state
+    #[func]
+    fn get_interpolation_alpha(&self) -> f64 {
+        if self.tick_rate <= 0.0 {
+            return 1.0;
+        }
+        (self.accumulator * self.tick_rate).clamp(0.0, 1.0)
+    }
+
+    /// Interpolate float properties between the last two fixed ticks in
+    /// `get_interpolated_property()`, batch readbacks and `PulsiveEntity`
+    /// bindings
+    #[func]
+    fn set_interpolation_enabled(&mut self, enabled: bool) {
+        self.interpolate = enabled;
+        if !enabled {
+            self.previous = None;
+        }
+    }
+
+    /// Check if property interpolation is enabled
+    #[func]
+    fn is_interpolation_enabled(&self) -> bool {
+        self.interpolate
+    }
+
+    /// Get an entity's property, interpolated between the last two fixed
+    /// ticks if interpolation is enabled and it is a float
+    #[func]
+    fn get_interpolated_property(&self, entity_id: i64, property: GString) -> Variant {
+        let id = pulsive_core::EntityId::new(entity_id as u64);
+        if let Some(entity) = self.model.entities().get(id) {
+            if let Some(value) = self.interpolated(entity, &property.to_string()) {
+                return value_to_variant(&value);
+            }
+        }
+        Variant::nil()
+    }
+
+    // === Parallel Execution ===
+
+    /// Set the number of threads used by `parallel_tick()` and rebuild the
+    /// hub cores with the current handlers
+    ///
+    /// Clamped to the number of CPUs.
+    #[func]
+    fn set_thread_count(&mut self, count: i64) {
+        self.thread_count = count.max(1) as usize;
+        self.build_hub();
+    }
+
+    /// Get the number of threads used by `parallel_tick()`
+    #[func]
+    fn get_thread_count(&self) -> i64 {
+        match &self.hub {
+            Some(hub) => hub.core_count() as i64,
+            None => self.thread_count.clamp(1, pulsive_hub::max_cores()) as i64,
+        }
+    }
+
+    /// Get the maximum number of threads available
+    #[func]
+    fn get_max_threads(&self) -> i64 {
+        pulsive_hub::max_cores() as i64
+    }
+
+    /// Set how entities are partitioned across threads: `"by_id"`,
+    /// `"by_owner"` (`params.property`) or `"spatial_grid"`
+    /// (`params.cell_size`, `params.x_prop`, `params.y_prop`)
+    #[func]
+    fn set_partition_strategy(&mut self, strategy: GString, params: VarDictionary) -> bool {
+        let params = dict_to_value_map(&params);
+        let text = |key: &str, default: &str| {
+            params
+                .get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or(default)
+                .to_string()
+        };
+        self.partition = match strategy.to_string().as_str() {
+            "by_id" => PartitionStrategy::by_id(),
+            "by_owner" => PartitionStrategy::by_owner(text("property", "owner")),
+            "spatial_grid" => {
+                let cell_size = params
+                    .get("cell_size")
+                    .and_then(|v| v.as_float())
+                    .unwrap_or(100.0);
+                PartitionStrategy::spatial_grid(cell_size, text("x_prop", "x"), text("y_prop", "y"))
+            }
+            other => {
+                godot_error!("Unknown partition strategy: {}", other);
+                return false;
+            }
+        };
+        if let Some(hub) = &mut self.hub {
+            hub.set_partition_strategy(self.partition.clone());
+        }
+        true
+    }
+
+    /// Get the partition strategy name
+    #[func]
+    fn get_partition_strategy(&self) -> GString {
+        let name = match self.partition.kind() {
+            pulsive_hub::PartitionKind::ById => "by_id",
+            pulsive_hub::PartitionKind::ByOwner { .. } => "by_owner",
+            pulsive_hub::PartitionKind::SpatialGrid { .. } => "spatial_grid",
+            pulsive_hub::PartitionKind::Custom(_) => "custom",
+        };
+        GString::from(name)
+    }
+
+    /// Set how conflicting writes between threads are resolved: `"abort"`,
+    /// `"last_write_wins"`, `"first_write_wins"` or `"merge"`
+    #[func]
+    fn set_conflict_resolution(&mut self, strategy: GString) -> bool {
+        let Some(resolution) = resolution_strategy(&strategy.to_string()) else {
+            godot_error!("Unknown conflict resolution: {}", strategy);
+            return false;
+        };
+        self.resolution = strategy;
+        if let Some(hub) = &mut self.hub {
+            hub.set_resolution_strategy(resolution);
+        }
+        true
+    }
+
+    /// Get the conflict resolution name
+    #[func]
+    fn get_conflict_resolution(&self) -> GString {
+        self.resolution.clone()
+    }
+
+    /// Advance the simulation by one tick on the hub's threads
+    ///
+    /// Returns the combined results of all cores, like `tick()`.
+    #[func]
+    fn parallel_tick(&mut self) -> VarDictionary {
+        if self.hub.is_none() {
+            self.build_hub();
+        }
+        let Some(hub) = &mut self.hub else {
+            return VarDictionary::new();
+        };
+
+        let start = Instant::now();
+        std::mem::swap(hub.model_mut(), &mut self.model);
+        let result = hub.tick();
+        std::mem::swap(hub.model_mut(), &mut self.model);
+        self.last_tick_time = start.elapsed();
+
+        match result {
+            Ok(tick_result) => {
+                let mut result = UpdateResult::new();
+                for update in tick_result.updates {
+                    let effects = update.effect_result;
+                    let merged = &mut result.effect_result;
+                    merged.spawned.extend(effects.spawned);
+                    merged.destroyed.extend(effects.destroyed);
+                    merged.emitted_events.extend(effects.emitted_events);
+                    merged.scheduled_events.extend(effects.scheduled_events);
+                    merged.logs.extend(effects.logs);
+                    merged.notifications.extend(effects.notifications);
+                    result.emitted_messages.extend(update.emitted_messages);
+                    result.scheduled_messages.extend(update.scheduled_messages);
+                }
+                self.emit_result_signals(&result);
+                self.update_result_to_dict(&result)
+            }
+            Err(e) => {
+                if let Some(report) = e.conflict_report() {
+                    self.conflict_count += report.len();
+                }
+                godot_error!("Parallel tick failed: {}", e);
+                VarDictionary::new()
+            }
+        }
+    }
+
     /// Send an actor command
     #[func]
     fn send_action(
@@ -270,25 +676,11 @@ impl PulsiveEngine {
         target_id: i64,
         params: VarDictionary,
     ) -> VarDictionary {
-        let target = if target_id >= 0 {
-            EntityRef::Entity(pulsive_core::EntityId::new(target_id as u64))
-        } else {
-            EntityRef::Global
-        };
-
-        let msg = Msg::command(
-            action_type.to_string(),
-            target,
-            ActorId::new(1), // Default actor
-            self.model.current_tick(),
-        );
-
-        // Add params
-        let mut msg = msg;
-        msg.params = dict_to_value_map(&params);
-
+        // Default actor
+        let msg = self.action_msg(&action_type, target_id, &params, ActorId::new(1));
         self.runtime.send(msg);
-        let result = self.runtime.process_queue(&mut self.model);
+        let result = self.process_queue();
+        self.emit_result_signals(&result);
         self.update_result_to_dict(&result)
     }
 
@@ -312,17 +704,252 @@ impl PulsiveEngine {
         msg.params = dict_to_value_map(&params);
 
         self.runtime.send(msg);
-        let result = self.runtime.process_queue(&mut self.model);
+        let result = self.process_queue();
+        self.emit_result_signals(&result);
         self.update_result_to_dict(&result)
     }
 
+    // === Handler Authoring ===
+    //
+    // Conditions and effects are dictionaries, e.g.
+    // `{"op": "add", "property": "gold", "value": {"param": "amount"}}`; see
+    // the `authoring` module for the format.
+
+    /// Register a handler for an event (or action), running `effects` when
+    /// `condition` holds (null = always); higher priorities run first
+    ///
+    /// Errors in the dictionaries are reported in the Godot output.
+    #[func]
+    fn register_event_handler(
+        &mut self,
+        event_id: GString,
+        condition: Variant,
+        effects: Variant,
+        priority: i64,
+    ) -> bool {
+        let Some((condition, effects)) = handler_parts(&event_id, &condition, &effects) else {
+            return false;
+        };
+        self.runtime.on_event(EventHandler {
+            event_id: DefId::new(event_id.to_string()),
+            condition,
+            effects,
+            priority: priority as i32,
+        });
+        // Rebuild hub cores with the new handler on the next parallel tick
+        self.hub = None;
+        true
+    }
+
+    /// Register a handler running every tick, once per entity of
+    /// `target_kind` (or once globally if empty)
+    #[func]
+    fn register_tick_handler(
+        &mut self,
+        id: GString,
+        target_kind: GString,
+        condition: Variant,
+        effects: Variant,
+        priority: i64,
+    ) -> bool {
+        let Some((condition, effects)) = handler_parts(&id, &condition, &effects) else {
+            return false;
+        };
+        self.runtime.on_tick(TickHandler {
+            id: DefId::new(id.to_string()),
+            condition,
+            target_kind: (!target_kind.is_empty()).then(|| DefId::new(target_kind.to_string())),
+            effects,
+            priority: priority as i32,
+        });
+        self.hub = None;
+        true
+    }
+
+    /// Register a property of entities of `kind` computed by an expression,
+    /// recomputed at the end of every tick where its inputs changed
+    #[func]
+    fn register_computed_property(
+        &mut self,
+        kind: GString,
+        property: GString,
+        expr: Variant,
+    ) -> bool {
+        match value_to_expr(&variant_to_value(&expr)) {
+            Ok(expr) => {
+                self.runtime
+                    .register_computed(kind.to_string(), property.to_string(), expr);
+                self.runtime.recompute(&mut self.model);
+                self.hub = None;
+                true
+            }
+            Err(e) => {
+                godot_error!("Invalid computed property {}: {}", property, e);
+                false
+            }
+        }
+    }
+
+    // === Time-Travel Debugging ===
+
+    /// Start recording history for time-travel debugging, snapshotting the
+    /// model every `snapshot_interval` ticks (0 = only at the start)
+    ///
+    /// Changes made directly from GDScript between snapshots are not
+    /// recorded; only messages processed by the runtime are replayed.
+    #[func]
+    fn enable_journal(&mut self, snapshot_interval: i64) {
+        self.history = Some(History::start(&self.model, snapshot_interval.max(0) as u64));
+    }
+
+    /// Stop recording history and discard it
+    #[func]
+    fn disable_journal(&mut self) {
+        self.history = None;
+    }
+
+    /// Check if history is being recorded
+    #[func]
+    fn is_journal_enabled(&self) -> bool {
+        self.history.is_some()
+    }
+
+    /// Get journal statistics: `total_entries`, `message_count`,
+    /// `tick_count`, `snapshot_count`, `first_tick` and `last_tick` (-1 if
+    /// none)
+    #[func]
+    fn get_journal_stats(&self) -> VarDictionary {
+        let mut dict = VarDictionary::new();
+        let Some(history) = &self.history else {
+            return dict;
+        };
+        let stats = history.journal().stats();
+        dict.set("total_entries", stats.total_entries as i64);
+        dict.set("message_count", stats.message_count as i64);
+        dict.set("tick_count", stats.tick_count as i64);
+        dict.set("snapshot_count", stats.snapshot_count as i64);
+        dict.set("first_tick", stats.first_tick.map_or(-1, |t| t as i64));
+        dict.set("last_tick", stats.last_tick.map_or(-1, |t| t as i64));
+        dict
+    }
+
+    /// Get the ticks of the recorded snapshots
+    #[func]
+    fn get_snapshot_ticks(&self) -> PackedInt64Array {
+        let ticks: Vec<i64> = self
+            .history
+            .iter()
+            .flat_map(|h| h.journal().snapshots())
+            .map(|s| s.tick as i64)
+            .collect();
+        PackedInt64Array::from(ticks.as_slice())
+    }
+
+    /// View the simulation as it was at a recorded tick
+    ///
+    /// The live simulation keeps running; `inspect_*` methods read the viewed
+    /// tick until `return_to_live()`.
+    #[func]
+    fn goto_tick(&mut self, tick: i64) -> bool {
+        let Some(history) = &mut self.history else {
+            godot_error!("Journal not enabled");
+            return false;
+        };
+        if let Err(e) = history.goto(&self.runtime, tick.max(0) as u64) {
+            godot_error!("Failed to go to tick {}: {}", tick, e);
+            return false;
+        }
+        true
+    }
+
+    /// View the tick before the viewed one (or before the live tick)
+    #[func]
+    fn step_back(&mut self) -> bool {
+        let Some((first, _)) = self.history.as_ref().and_then(|h| h.range()) else {
+            return false;
+        };
+        let tick = match self.get_history_tick() {
+            -1 => self.get_tick(),
+            tick => tick,
+        };
+        tick > first as i64 && self.goto_tick(tick - 1)
+    }
+
+    /// View the tick after the viewed one
+    #[func]
+    fn step_forward(&mut self) -> bool {
+        let Some((_, last)) = self.history.as_ref().and_then(|h| h.range()) else {
+            return false;
+        };
+        let tick = self.get_history_tick();
+        tick >= 0 && tick < last as i64 && self.goto_tick(tick + 1)
+    }
+
+    /// Get the viewed tick, or -1 when viewing the live simulation
+    #[func]
+    fn get_history_tick(&self) -> i64 {
+        self.history
+            .as_ref()
+            .and_then(|h| h.view())
+            .map_or(-1, |(tick, _)| tick as i64)
+    }
+
+    /// Go back to viewing the live simulation
+    #[func]
+    fn return_to_live(&mut self) {
+        if let Some(history) = &mut self.history {
+            history.leave();
+        }
+    }
+
+    /// Get an entity at the viewed tick as a dictionary with `id`, `kind`,
+    /// `properties` and `flags` (empty if it did not exist)
+    #[func]
+    fn inspect_entity(&self, entity_id: i64) -> VarDictionary {
+        let id = pulsive_core::EntityId::new(entity_id as u64);
+        if let Some(entity) = self.viewed_model().entities().get(id) {
+            return entity_to_dict(entity);
+        }
+        VarDictionary::new()
+    }
+
+    /// Get the IDs of all entities at the viewed tick
+    #[func]
+    fn inspect_entity_ids(&self) -> PackedInt64Array {
+        let mut ids: Vec<i64> = self
+            .viewed_model()
+            .entities()
+            .ids()
+            .map(|id| id.raw() as i64)
+            .collect();
+        ids.sort();
+        PackedInt64Array::from(ids.as_slice())
+    }
+
+    /// Get the globals at the viewed tick
+    #[func]
+    fn inspect_globals(&self) -> VarDictionary {
+        value_map_to_dict(self.viewed_model().globals())
+    }
+
+    // === Debugging ===
+
+    /// Get performance and size statistics for debug overlays:
+    /// `tick_usec`, `event_handlers`, `tick_handlers`, `queued_messages`,
+    /// `scheduled_messages`, `entity_count`, `entities_by_kind`, `threads`,
+    /// `hub_conflicts`, `journal_entries` and `journal_snapshots`
+    #[func]
+    fn get_debug_stats(&self) -> VarDictionary {
+        self.debug_stats().to_dict()
+    }
+
     // === Persistence ===
 
-    /// Save the current state to the database
+    /// Save the current state to a slot in the database
     #[func]
-    fn save(&mut self) -> bool {
+    fn save(&mut self, slot: GString) -> bool {
         if let Some(ref store) = self.store {
-            if let Err(e) = store.save_model(&self.model) {
+            if let Err(e) = store.save_model(&self.model, &slot.to_string()) {
                 godot_error!("Failed to save: {}", e);
                 return false;
             }
@@ -332,15 +959,19 @@ impl PulsiveEngine {
         false
     }
 
-    /// Load state from the database
+    /// Load state from a slot in the database
     #[func]
-    fn load(&mut self) -> bool {
+    fn load(&mut self, slot: GString) -> bool {
         if let Some(ref store) = self.store {
-            match store.load_model() {
-                Ok(model) => {
-                    self.model = model;
+            match store.load_model(&slot.to_string()) {
+                Ok(Some(model)) => {
+                    self.model.restore(model);
                     return true;
                 }
+                Ok(None) => {
+                    godot_error!("No save in slot {}", slot);
+                    return false;
+                }
                 Err(e) => {
                     godot_error!("Failed to load: {}", e);
                     return false;
@@ -351,8 +982,285 @@ impl PulsiveEngine {
         false
     }
 
+    /// Save the complete game (entities, globals, clock and RNG state) to a
+    /// slot, emitting `game_saved` or `save_failed`
+    ///
+    /// Uses the configured database, or `user://pulsive_saves.db` if none.
+    #[func]
+    fn save_game(&mut self, slot: GString) -> bool {
+        let result = save_store(&mut self.store)
+            .and_then(|store| store.save_model(&self.model, &slot.to_string()));
+        match result {
+            Ok(()) => {
+                self.base_mut()
+                    .emit_signal("game_saved", &[slot.to_variant()]);
+                true
+            }
+            Err(e) => {
+                godot_error!("Failed to save {}: {}", slot, e);
+                let error = GString::from(e.to_string().as_str());
+                self.base_mut()
+                    .emit_signal("save_failed", &[slot.to_variant(), error.to_variant()]);
+                false
+            }
+        }
+    }
+
+    /// Load a game saved with `save_game()`, emitting `game_loaded` or
+    /// `load_failed`
+    #[func]
+    fn load_game(&mut self, slot: GString) -> bool {
+        let result = save_store(&mut self.store)
+            .and_then(|store| store.load_model(&slot.to_string()))
+            .and_then(|model| {
+                model.ok_or_else(|| pulsive_db::Error::NotFound(format!("save slot {}", slot)))
+            });
+        match result {
+            Ok(model) => {
+                self.model.restore(model);
+                self.previous = None;
+                self.accumulator = 0.0;
+                if let Some(history) = &self.history {
+                    // Recorded ticks no longer lead to the loaded state
+                    let interval = history.journal().config().snapshot_interval;
+                    self.history = Some(History::start(&self.model, interval));
+                }
+                self.base_mut()
+                    .emit_signal("game_loaded", &[slot.to_variant()]);
+                true
+            }
+            Err(e) => {
+                godot_error!("Failed to load {}: {}", slot, e);
+                let error = GString::from(e.to_string().as_str());
+                self.base_mut()
+                    .emit_signal("load_failed", &[slot.to_variant(), error.to_variant()]);
+                false
+            }
+        }
+    }
+
+    /// List saved games, most recent first, as dictionaries with `slot`,
+    /// `label`, `tick`, `saved_at` and `play_time` (seconds)
+    #[func]
+    fn list_saves(&mut self) -> Array<VarDictionary> {
+        let slots = match save_store(&mut self.store).and_then(|store| store.list_slots()) {
+            Ok(slots) => slots,
+            Err(e) => {
+                godot_error!("Failed to list saves: {}", e);
+                return Array::new();
+            }
+        };
+        slots
+            .iter()
+            .map(|slot| {
+                let mut dict = VarDictionary::new();
+                dict.set("slot", GString::from(slot.name.as_str()));
+                dict.set("label", GString::from(slot.label.as_str()));
+                dict.set("tick", slot.tick as i64);
+                dict.set("saved_at", slot.saved_at as i64);
+                dict.set("play_time", slot.play_time.as_secs_f64());
+                dict
+            })
+            .collect()
+    }
+
+    /// Delete a saved game
+    #[func]
+    fn delete_save(&mut self, slot: GString) -> bool {
+        match save_store(&mut self.store).and_then(|store| store.delete_slot(&slot.to_string())) {
+            Ok(()) => true,
+            Err(e) => {
+                godot_error!("Failed to delete save {}: {}", slot, e);
+                false
+            }
+        }
+    }
+
     // === Helpers ===
 
+    /// Collect debug statistics
+    pub(crate) fn debug_stats(&self) -> DebugStats {
+        let entities = self.model.entities();
+        let mut by_kind: BTreeMap<String, usize> = BTreeMap::new();
+        for entity in entities.iter() {
+            *by_kind.entry(entity.kind.to_string()).or_default() += 1;
+        }
+        let journal = self.history.as_ref().map(|h| h.journal().stats());
+        DebugStats {
+            tick: self.model.current_tick(),
+            tick_time: self.last_tick_time,
+            event_handlers: self.runtime.event_handler_count(),
+            tick_handlers: self.runtime.tick_handler_count(),
+            queued_messages: self.runtime.queue_len(),
+            scheduled_messages: self.runtime.scheduled_len(),
+            entity_count: entities.len(),
+            entities_by_kind: by_kind,
+            threads: self.thread_count,
+            hub_conflicts: self.conflict_count,
+            journal_entries: journal.as_ref().map_or(0, |s| s.total_entries),
+            journal_snapshots: journal.as_ref().map_or(0, |s| s.snapshot_count),
+        }
+    }
+
+    /// Build an action command message
+    pub(crate) fn action_msg(
+        &self,
+        action_type: &GString,
+        target_id: i64,
+        params: &VarDictionary,
+        actor: ActorId,
+    ) -> Msg {
+        let target = if target_id >= 0 {
+            EntityRef::Entity(pulsive_core::EntityId::new(target_id as u64))
+        } else {
+            EntityRef::Global
+        };
+        let mut msg = Msg::command(
+            action_type.to_string(),
+            target,
+            actor,
+            self.model.current_tick(),
+        );
+        msg.params = dict_to_value_map(params);
+        msg
+    }
+
+    /// Queue a message for the next tick
+    pub(crate) fn queue_msg(&mut self, msg: Msg) {
+        self.runtime.send(msg);
+    }
+
+    /// Get the model and runtime together, e.g. for prediction
+    pub(crate) fn model_and_runtime_mut(&mut self) -> (&mut Model, &mut Runtime) {
+        (&mut self.model, &mut self.runtime)
+    }
+
+    /// Get an entity's property, interpolated between the last two fixed
+    /// ticks if enabled
+    ///
+    /// Only floats are interpolated; integers (counts, IDs) and other values
+    /// are returned as they are now.
+    pub(crate) fn interpolated(&self, entity: &Entity, property: &str) -> Option<Value> {
+        let value = entity.get(property)?;
+        let (Some(previous), Value::Float(current)) = (&self.previous, value) else {
+            return Some(value.clone());
+        };
+        let before = previous
+            .entities()
+            .get(entity.id)
+            .and_then(|e| e.get(property));
+        match before {
+            Some(Value::Float(before)) => {
+                let alpha = self.get_interpolation_alpha();
+                Some(Value::Float(before + (current - before) * alpha))
+            }
+            _ => Some(value.clone()),
+        }
+    }
+
+    /// Get a numeric property for batch readback (0 where missing)
+    fn interpolated_number(&self, entity: &Entity, property: &str) -> f64 {
+        self.interpolated(entity, property)
+            .and_then(|v| v.as_float())
+            .unwrap_or(0.0)
+    }
+
+    /// Get all entities of a given type, ordered by ID
+    fn sorted_by_kind(&self, kind: &GString) -> Vec<&Entity> {
+        let def_id = DefId::new(kind.to_string());
+        let mut entities: Vec<&Entity> = self.model.entities().by_kind(&def_id).collect();
+        entities.sort_by_key(|e| e.id.raw());
+        entities
+    }
+
+    /// Process queued messages, recording them if history is enabled
+    fn process_queue(&mut self) -> UpdateResult {
+        match &mut self.history {
+            Some(history) => self
+                .runtime
+                .process_queue_with_journal(&mut self.model, history.journal_mut()),
+            None => self.runtime.process_queue(&mut self.model),
+        }
+    }
+
+    /// Get the model at the viewed tick, or the live model
+    fn viewed_model(&self) -> &Model {
+        self.history
+            .as_ref()
+            .and_then(|h| h.view())
+            .map_or(&self.model, |(_, model)| model)
+    }
+
+    fn build_hub(&mut self) {
+        let config = HubConfig::new(self.thread_count, pulsive_hub::DEFAULT_GLOBAL_SEED);
+        let seed = config.global_seed();
+        let cores = (0..config.core_count())
+            .map(|i| Core::new(CoreId(i), self.runtime.clone(), seed))
+            .collect();
+        let mut hub = Hub::with_config(Model::new(), config);
+        hub.add_group(TickSyncGroup::new(GroupId(0), cores, seed));
+        hub.set_partition_strategy(self.partition.clone());
+        if let Some(resolution) = resolution_strategy(&self.resolution.to_string()) {
+            hub.set_resolution_strategy(resolution);
+        }
+        self.hub = Some(hub);
+    }
+
+    /// Get the model
+    pub(crate) fn model(&self) -> &Model {
+        &self.model
+    }
+
+    /// Get the model mutably
+    pub(crate) fn model_mut(&mut self) -> &mut Model {
+        &mut self.model
+    }
+
+    fn merge_defs(&mut self, defs: GameDefs) {
+        self.defs.resources.extend(defs.resources);
+        self.defs.events.extend(defs.events);
+        self.defs.entity_types.extend(defs.entity_types);
+        self.defs.curves.extend(defs.curves);
+        self.defs.event_pools.extend(defs.event_pools);
+        self.defs.localization.merge(defs.localization);
+        let sources = &mut self.defs.sources;
+        sources.resources.extend(defs.sources.resources);
+        sources.events.extend(defs.sources.events);
+        sources.entity_types.extend(defs.sources.entity_types);
+        sources.curves.extend(defs.sources.curves);
+        sources.event_pools.extend(defs.sources.event_pools);
+    }
+
+    fn emit_result_signals(&mut self, result: &UpdateResult) {
+        let effects = &result.effect_result;
+        for (event_id, target, params) in &effects.emitted_events {
+            let args = [
+                event_id.as_str().to_variant(),
+                target_to_id(target).to_variant(),
+                value_map_to_dict(params).to_variant(),
+            ];
+            self.base_mut().emit_signal("pulsive_event", &args);
+        }
+        for notification in &effects.notifications {
+            let args = [
+                notification.kind.as_str().to_variant(),
+                notification.title.to_variant(),
+                notification.message.to_variant(),
+                target_to_id(&notification.target).to_variant(),
+            ];
+            self.base_mut().emit_signal("pulsive_notification", &args);
+        }
+        for record in &effects.logs {
+            let args = [
+                format!("{:?}", record.level).to_variant(),
+                record.message.to_variant(),
+                (record.tick as i64).to_variant(),
+                target_to_id(&record.target).to_variant(),
+            ];
+            self.base_mut().emit_signal("pulsive_log", &args);
+        }
+    }
+
     fn update_result_to_dict(&self, result: &UpdateResult) -> VarDictionary {
         let mut dict = VarDictionary::new();
 
@@ -376,10 +1284,15 @@ impl PulsiveEngine {
 
         // Logs
         let mut logs = Array::new();
-        for (level, message) in &result.effect_result.logs {
+        for record in &result.effect_result.logs {
             let mut log_dict = VarDictionary::new();
-            log_dict.set("level", format!("{:?}", level).to_variant());
-            log_dict.set("message", message.to_variant());
+            log_dict.set("level", format!("{:?}", record.level).to_variant());
+            log_dict.set("message", record.message.to_variant());
+            log_dict.set("tick", (record.tick as i64).to_variant());
+            let handler = record.handler.as_ref().map(|h| h.to_string());
+            log_dict.set("handler", handler.unwrap_or_default().to_variant());
+            log_dict.set("target_id", target_to_id(&record.target).to_variant());
+            log_dict.set("params", value_map_to_dict(&record.params).to_variant());
             logs.push(&log_dict.to_variant());
         }
         dict.set("logs", logs);
@@ -398,3 +1311,89 @@ impl PulsiveEngine {
         dict
     }
 }
+
+/// Load a definition file, or every content file under a directory, through
+/// Godot's file system
+fn load_defs_path(loader: &mut Loader, path: &str) -> pulsive_script::Result<()> {
+    if !DirAccess::dir_exists_absolute(path) {
+        if !FileAccess::file_exists(path) {
+            return Err(not_found(path));
+        }
+        let content = FileAccess::get_file_as_string(path);
+        return loader.load_file_str(path, &content.to_string());
+    }
+
+    let mut dir = DirAccess::open(path).ok_or_else(|| not_found(path))?;
+    let join = |name: &GString| {
+        if path.ends_with('/') {
+            format!("{}{}", path, name)
+        } else {
+            format!("{}/{}", path, name)
+        }
+    };
+    let mut files: Vec<String> = dir.get_files().as_slice().iter().map(join).collect();
+    files.sort();
+    for file in files {
+        if Format::from_path(&file).is_some() {
+            load_defs_path(loader, &file)?;
+        }
+    }
+    let mut subdirs: Vec<String> = dir.get_directories().as_slice().iter().map(join).collect();
+    subdirs.sort();
+    for subdir in subdirs {
+        load_defs_path(loader, &subdir)?;
+    }
+    Ok(())
+}
+
+fn not_found(path: &str) -> pulsive_script::Error {
+    pulsive_script::Error::Io(std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("Not found: {}", path),
+    ))
+}
+
+/// Convert a handler's condition (null = none) and effects, reporting errors
+fn handler_parts(
+    id: &GString,
+    condition: &Variant,
+    effects: &Variant,
+) -> Option<(Option<Expr>, Vec<Effect>)> {
+    let condition = match variant_to_value(condition) {
+        Value::Null => Ok(None),
+        value => value_to_expr(&value).map(Some),
+    };
+    let parts =
+        condition.and_then(|c| value_to_effects(&variant_to_value(effects)).map(|e| (c, e)));
+    match parts {
+        Ok(parts) => Some(parts),
+        Err(e) => {
+            godot_error!("Invalid handler {}: {}", id, e);
+            None
+        }
+    }
+}
+
+/// Look up a conflict resolution strategy by name
+fn resolution_strategy(name: &str) -> Option<ResolutionStrategy> {
+    match name {
+        "abort" => Some(ResolutionStrategy::Abort),
+        "last_write_wins" => Some(ResolutionStrategy::LastWriteWins),
+        "first_write_wins" => Some(ResolutionStrategy::FirstWriteWins),
+        "merge" => Some(ResolutionStrategy::Merge),
+        _ => None,
+    }
+}
+
+/// Get the store for saved games, opening the default one if no database is
+/// configured
+fn save_store(store: &mut Option<Store>) -> pulsive_db::Result<&Store> {
+    let opened = match store.take() {
+        Some(store) => store,
+        None => {
+            let path = ProjectSettings::singleton().globalize_path(DEFAULT_SAVE_PATH);
+            Store::open(PathBuf::from(path.to_string()))?
+        }
+    };
+    Ok(store.insert(opened))
+}
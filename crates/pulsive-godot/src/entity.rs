@@ -0,0 +1,142 @@
+//! Node class binding a scene node to an entity
+
+use godot::prelude::*;
+use pulsive_core::EntityId;
+
+use crate::bridge::value_to_variant;
+use crate::engine::PulsiveEngine;
+
+/// A node that creates (or links) an entity on ready, copies mapped entity
+/// properties to a node every frame, and destroys the entity on exit
+///
+/// `bindings` maps entity properties to node property paths, e.g.
+/// `{"x": "position:x", "health": "value"}`. The target node defaults to the
+/// parent.
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct PulsiveEntity {
+    base: Base<Node>,
+    /// Path to the PulsiveEngine node
+    #[export]
+    engine_path: NodePath,
+    /// Kind of entity to create
+    #[export]
+    kind: GString,
+    /// Entity property to node property path mappings
+    #[export]
+    bindings: VarDictionary,
+    /// Node receiving the mapped properties (parent if empty)
+    #[export]
+    target_path: NodePath,
+    /// Entity to link instead of creating one (-1 to create)
+    #[export]
+    entity_id: i64,
+    /// Destroy the entity when the node leaves the tree
+    #[export]
+    destroy_on_exit: bool,
+    /// The engine, found on ready
+    engine: Option<Gd<PulsiveEngine>>,
+}
+
+#[godot_api]
+impl INode for PulsiveEntity {
+    fn init(base: Base<Node>) -> Self {
+        Self {
+            base,
+            engine_path: NodePath::default(),
+            kind: GString::new(),
+            bindings: VarDictionary::new(),
+            target_path: NodePath::default(),
+            entity_id: -1,
+            destroy_on_exit: true,
+            engine: None,
+        }
+    }
+
+    fn ready(&mut self) {
+        let Some(mut engine) = self
+            .base()
+            .try_get_node_as::<PulsiveEngine>(&self.engine_path)
+        else {
+            godot_error!("PulsiveEntity: no PulsiveEngine at {}", self.engine_path);
+            return;
+        };
+
+        if self.entity_id < 0 {
+            if self.kind.is_empty() {
+                godot_error!("PulsiveEntity: no kind set");
+                return;
+            }
+            let mut engine = engine.bind_mut();
+            let entity = engine
+                .model_mut()
+                .entities_mut()
+                .create(self.kind.to_string());
+            self.entity_id = entity.id.raw() as i64;
+        } else if engine.bind().model().entities().get(self.id()).is_none() {
+            godot_error!("PulsiveEntity: no entity with ID {}", self.entity_id);
+            return;
+        }
+
+        self.engine = Some(engine);
+        self.sync();
+    }
+
+    fn process(&mut self, _delta: f64) {
+        self.sync();
+    }
+
+    fn exit_tree(&mut self) {
+        let Some(mut engine) = self.engine.take() else {
+            return;
+        };
+        if self.destroy_on_exit {
+            engine
+                .bind_mut()
+                .model_mut()
+                .entities_mut()
+                .remove(self.id());
+            self.entity_id = -1;
+        }
+    }
+}
+
+#[godot_api]
+impl PulsiveEntity {
+    /// Copy the mapped entity properties to the target node
+    #[func]
+    fn sync(&mut self) {
+        let Some(engine) = self.engine.clone() else {
+            return;
+        };
+        let target = if self.target_path.is_empty() {
+            self.base().get_parent()
+        } else {
+            self.base().try_get_node_as::<Node>(&self.target_path)
+        };
+        let Some(mut target) = target else {
+            return;
+        };
+
+        let values: Vec<(GString, Variant)> = {
+            let engine = engine.bind();
+            let Some(entity) = engine.model().entities().get(self.id()) else {
+                return;
+            };
+            self.bindings
+                .iter_shared()
+                .filter_map(|(property, path)| {
+                    let value = engine.interpolated(entity, &property.stringify().to_string())?;
+                    Some((path.stringify(), value_to_variant(&value)))
+                })
+                .collect()
+        };
+        for (path, value) in values {
+            target.set_indexed(&NodePath::from(&path), &value);
+        }
+    }
+
+    fn id(&self) -> EntityId {
+        EntityId::new(self.entity_id as u64)
+    }
+}
@@ -0,0 +1,83 @@
+//! Journal-backed simulation history for time-travel debugging
+
+use pulsive_core::{Journal, JournalConfig, Model, Runtime};
+use pulsive_journal::Replayer;
+
+/// Recorded history of the simulation, with a view of one past tick
+pub(crate) struct History {
+    /// Recorded messages and snapshots
+    journal: Journal,
+    /// Tick and model being inspected, if not viewing the live model
+    view: Option<(u64, Model)>,
+}
+
+impl History {
+    /// Start recording from the current model
+    ///
+    /// The model is snapshotted right away, so state set up outside of
+    /// handlers (e.g. from GDScript) is part of the history.
+    pub fn start(model: &Model, snapshot_interval: u64) -> Self {
+        let mut journal = Journal::with_config(JournalConfig {
+            snapshot_interval,
+            ..Default::default()
+        });
+        journal.start_recording();
+        journal.take_snapshot(model);
+        Self {
+            journal,
+            view: None,
+        }
+    }
+
+    /// Get the journal
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    /// Get the journal mutably
+    pub fn journal_mut(&mut self) -> &mut Journal {
+        &mut self.journal
+    }
+
+    /// Get the first and last recorded ticks
+    pub fn range(&self) -> Option<(u64, u64)> {
+        let stats = self.journal.stats();
+        let snapshot = self.journal.snapshots().first().map(|s| s.tick);
+        let first = match (snapshot, stats.first_tick) {
+            (Some(a), Some(b)) => a.min(b),
+            (a, b) => a.or(b)?,
+        };
+        Some((first, stats.last_tick.unwrap_or(first).max(first)))
+    }
+
+    /// Rebuild the model at a recorded tick and view it
+    ///
+    /// Replays with a copy of the runtime's handlers; the live model and
+    /// runtime are left alone.
+    pub fn goto(&mut self, runtime: &Runtime, tick: u64) -> pulsive_journal::Result<()> {
+        let Some((first, last)) = self.range() else {
+            return Err(pulsive_journal::Error::ReplayError(
+                "nothing recorded".to_string(),
+            ));
+        };
+        if tick < first || tick > last {
+            return Err(pulsive_journal::Error::InvalidTickRange(first, last));
+        }
+
+        let mut model = Model::new();
+        let mut runtime = runtime.clone();
+        Replayer::new(&self.journal).goto(&mut model, &mut runtime, tick)?;
+        self.view = Some((tick, model));
+        Ok(())
+    }
+
+    /// Get the viewed tick and model
+    pub fn view(&self) -> Option<(u64, &Model)> {
+        self.view.as_ref().map(|(tick, model)| (*tick, model))
+    }
+
+    /// Stop viewing a past tick
+    pub fn leave(&mut self) {
+        self.view = None;
+    }
+}
@@ -2,8 +2,16 @@
 //!
 //! Exposes the pulsive engine to Godot as native classes.
 
+mod authoring;
 mod bridge;
+mod client;
+mod debug;
 mod engine;
+mod entity;
+mod history;
+mod log;
+mod net;
+mod server;
 
 use godot::prelude::*;
 
@@ -13,4 +21,8 @@ struct PulsiveExtension;
 unsafe impl ExtensionLibrary for PulsiveExtension {}
 
 // Re-export the main engine class
+pub use client::PulsiveClient;
+pub use debug::PulsiveDebugOverlay;
 pub use engine::PulsiveEngine;
+pub use entity::PulsiveEntity;
+pub use server::PulsiveServer;
@@ -0,0 +1,37 @@
+//! Log sink printing log records to the Godot output
+
+use godot::prelude::*;
+use pulsive_core::effect::LogLevel;
+use pulsive_core::{LogRecord, LogSink};
+
+/// Prints log records at or above a level with `godot_print!`,
+/// `godot_warn!` or `godot_error!`
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GodotLogSink {
+    /// Least severe level printed
+    pub min_level: LogLevel,
+}
+
+impl LogSink for GodotLogSink {
+    fn log(&self, record: &LogRecord) {
+        if record.level < self.min_level {
+            return;
+        }
+        match record.level {
+            LogLevel::Debug | LogLevel::Info => godot_print!("{}", record),
+            LogLevel::Warn => godot_warn!("{}", record),
+            LogLevel::Error => godot_error!("{}", record),
+        }
+    }
+}
+
+/// Parse a log level name as used by the `pulsive_log` signal
+pub(crate) fn parse_log_level(name: &str) -> Option<LogLevel> {
+    match name.to_ascii_lowercase().as_str() {
+        "debug" => Some(LogLevel::Debug),
+        "info" => Some(LogLevel::Info),
+        "warn" => Some(LogLevel::Warn),
+        "error" => Some(LogLevel::Error),
+        _ => None,
+    }
+}
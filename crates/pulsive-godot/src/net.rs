@@ -0,0 +1,98 @@
+//! Wire format and UDP transport shared by `PulsiveClient` and `PulsiveServer`
+
+use pulsive_core::{Model, Msg};
+use pulsive_netcode::{Address, Transport};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+/// Largest UDP payload; bigger states can't be sent in one packet
+pub(crate) const MAX_PACKET_SIZE: usize = 65_507;
+
+/// A packet between client and server
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) enum Packet {
+    /// Client announcing itself to the server
+    Hello,
+    /// Client input, tagged with the client's predicted tick
+    Input { tick: u64, msg: Msg },
+    /// Authoritative state after a server tick
+    State {
+        /// Server tick
+        tick: u64,
+        /// Last input from the receiving client applied to this state
+        ack: Option<u64>,
+        /// Complete model
+        model: Model,
+    },
+}
+
+impl Packet {
+    /// Encode to bytes
+    pub fn encode(&self) -> Result<Vec<u8>, bincode::Error> {
+        bincode::serialize(self)
+    }
+
+    /// Decode from bytes, or `None` if malformed
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        bincode::deserialize(data).ok()
+    }
+}
+
+/// Non-blocking UDP transport
+pub(crate) struct UdpTransport {
+    socket: UdpSocket,
+}
+
+impl UdpTransport {
+    /// Bind to a local address
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket })
+    }
+
+    /// Resolve a host and port to an address
+    pub fn resolve(host: &str, port: u16) -> io::Result<Address> {
+        (host, port)
+            .to_socket_addrs()?
+            .next()
+            .map(Address::Socket)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, host.to_string()))
+    }
+}
+
+impl Transport for UdpTransport {
+    type Error = io::Error;
+
+    fn send(&self, data: &[u8], target: &Address) -> io::Result<()> {
+        let Address::Socket(addr) = target else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "UDP needs a socket address",
+            ));
+        };
+        self.socket.send_to(data, addr).map(|_| ())
+    }
+
+    fn recv(&self) -> io::Result<Option<(Vec<u8>, Address)>> {
+        let mut buf = vec![0; MAX_PACKET_SIZE];
+        match self.socket.recv_from(&mut buf) {
+            Ok((len, source)) => {
+                buf.truncate(len);
+                Ok(Some((buf, Address::Socket(source))))
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn local_addr(&self) -> Option<Address> {
+        self.socket.local_addr().ok().map(Address::Socket)
+    }
+}
+
+/// Unspecified local address for a client socket
+pub(crate) fn any_addr() -> SocketAddr {
+    SocketAddr::from(([0, 0, 0, 0], 0))
+}
@@ -0,0 +1,213 @@
+//! Node class serving the simulation to `PulsiveClient`s
+
+use godot::prelude::*;
+use pulsive_core::ActorId;
+use pulsive_netcode::{Address, Transport};
+
+use crate::engine::PulsiveEngine;
+use crate::net::{Packet, UdpTransport, MAX_PACKET_SIZE};
+
+/// First client ID; actor 1 is the local player of `send_action()`
+const FIRST_CLIENT_ID: i64 = 2;
+
+/// A connected client
+struct Client {
+    id: i64,
+    address: Address,
+    /// Last input tick received from the client
+    ack: Option<u64>,
+}
+
+/// A node running the authoritative simulation for networked clients
+///
+/// Client inputs are queued on the engine as actions from the client's actor
+/// (its client ID) and run on the engine's next tick. After every tick the
+/// complete state is sent to each client, acknowledging its inputs.
+#[derive(GodotClass)]
+#[class(base=Node)]
+pub struct PulsiveServer {
+    base: Base<Node>,
+    /// Path to the PulsiveEngine node running the simulation
+    #[export]
+    engine_path: NodePath,
+    /// UDP port to listen on
+    #[export]
+    port: i64,
+    /// The engine, found on ready
+    engine: Option<Gd<PulsiveEngine>>,
+    /// Socket, while running
+    transport: Option<UdpTransport>,
+    /// Connected clients
+    clients: Vec<Client>,
+    /// ID for the next client
+    next_client_id: i64,
+    /// Tick of the last state sent
+    last_sent_tick: Option<u64>,
+}
+
+#[godot_api]
+impl INode for PulsiveServer {
+    fn init(base: Base<Node>) -> Self {
+        Self {
+            base,
+            engine_path: NodePath::default(),
+            port: 7777,
+            engine: None,
+            transport: None,
+            clients: Vec::new(),
+            next_client_id: FIRST_CLIENT_ID,
+            last_sent_tick: None,
+        }
+    }
+
+    fn ready(&mut self) {
+        self.engine = self
+            .base()
+            .try_get_node_as::<PulsiveEngine>(&self.engine_path);
+        if self.engine.is_none() {
+            godot_error!("PulsiveServer: no PulsiveEngine at {}", self.engine_path);
+        }
+    }
+
+    fn process(&mut self, _delta: f64) {
+        if self.transport.is_none() {
+            return;
+        }
+        // States first, so inputs received below are acknowledged only once
+        // a tick has applied them
+        self.send_state();
+        self.receive();
+    }
+}
+
+#[godot_api]
+impl PulsiveServer {
+    /// A client sent its first packet
+    #[signal]
+    fn client_connected(client_id: i64);
+
+    /// Start listening on a UDP port
+    #[func]
+    fn start(&mut self, port: i64) -> bool {
+        match UdpTransport::bind(("0.0.0.0", port as u16)) {
+            Ok(transport) => {
+                self.port = port;
+                self.transport = Some(transport);
+                self.last_sent_tick = None;
+                true
+            }
+            Err(e) => {
+                godot_error!("PulsiveServer: failed to listen on {}: {}", port, e);
+                false
+            }
+        }
+    }
+
+    /// Stop listening and forget all clients
+    #[func]
+    fn stop(&mut self) {
+        self.transport = None;
+        self.clients.clear();
+    }
+
+    /// Check if the server is listening
+    #[func]
+    fn is_running(&self) -> bool {
+        self.transport.is_some()
+    }
+
+    /// Get the IDs (actor IDs) of the connected clients
+    #[func]
+    fn get_client_ids(&self) -> PackedInt64Array {
+        let ids: Vec<i64> = self.clients.iter().map(|c| c.id).collect();
+        PackedInt64Array::from(ids.as_slice())
+    }
+
+    fn receive(&mut self) {
+        let Some(transport) = &self.transport else {
+            return;
+        };
+        let mut packets = Vec::new();
+        loop {
+            match transport.recv() {
+                Ok(Some(packet)) => packets.push(packet),
+                Ok(None) => break,
+                Err(e) => {
+                    godot_error!("PulsiveServer: receive failed: {}", e);
+                    break;
+                }
+            }
+        }
+
+        for (data, source) in packets {
+            let Some(packet) = Packet::decode(&data) else {
+                continue;
+            };
+            let index = self.client_index(source);
+            match packet {
+                Packet::Hello => {}
+                Packet::Input { tick, mut msg } => {
+                    let client = &mut self.clients[index];
+                    client.ack = Some(client.ack.map_or(tick, |ack| ack.max(tick)));
+                    msg.actor = Some(ActorId::new(client.id as u64));
+                    if let Some(engine) = &mut self.engine {
+                        engine.bind_mut().queue_msg(msg);
+                    }
+                }
+                Packet::State { .. } => {}
+            }
+        }
+    }
+
+    fn send_state(&mut self) {
+        let (Some(transport), Some(engine)) = (&self.transport, &self.engine) else {
+            return;
+        };
+        let engine = engine.bind();
+        let model = engine.model();
+        let tick = model.current_tick();
+        if self.last_sent_tick == Some(tick) {
+            return;
+        }
+        self.last_sent_tick = Some(tick);
+
+        for client in &self.clients {
+            let packet = Packet::State {
+                tick,
+                ack: client.ack,
+                model: model.clone(),
+            };
+            let data = match packet.encode() {
+                Ok(data) if data.len() <= MAX_PACKET_SIZE => data,
+                Ok(data) => {
+                    godot_error!("PulsiveServer: state too large ({} bytes)", data.len());
+                    return;
+                }
+                Err(e) => {
+                    godot_error!("PulsiveServer: failed to encode state: {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = transport.send(&data, &client.address) {
+                godot_error!("PulsiveServer: send to client {} failed: {}", client.id, e);
+            }
+        }
+    }
+
+    /// Find a client by address, adding it if new
+    fn client_index(&mut self, address: Address) -> usize {
+        if let Some(index) = self.clients.iter().position(|c| c.address == address) {
+            return index;
+        }
+        let id = self.next_client_id;
+        self.next_client_id += 1;
+        self.clients.push(Client {
+            id,
+            address,
+            ack: None,
+        });
+        self.base_mut()
+            .emit_signal("client_connected", &[id.to_variant()]);
+        self.clients.len() - 1
+    }
+}
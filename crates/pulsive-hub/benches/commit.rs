@@ -0,0 +1,86 @@
+//! Committing 100k writes per tick, one write at a time and in batches
+//! grouped by entity
+//!
+//! Run with `cargo bench -p pulsive-hub --bench commit`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use pulsive_core::{DefId, EntityId, Model, ModifyOp, PendingWrite, Value, WriteSet};
+use pulsive_hub::{apply, CommitArena};
+use std::hint::black_box;
+
+const WRITES: usize = 100_000;
+const ENTITY_COUNTS: [usize; 2] = [1_000, 10_000];
+
+/// A model with units of varied health
+fn model(units: usize) -> (Model, Vec<EntityId>) {
+    let mut model = Model::with_seed(1);
+    let ids = (0..units)
+        .map(|i| {
+            let unit = model.entities_mut().create("unit");
+            unit.set("hp", (i % 100) as f64);
+            unit.id
+        })
+        .collect();
+    (model, ids)
+}
+
+/// A tick's writes, spread round-robin over the units like the output of
+/// several cores merged
+fn writes(ids: &[EntityId]) -> WriteSet {
+    let mut writes = WriteSet::new();
+    for i in 0..WRITES {
+        let entity_id = ids[i % ids.len()];
+        writes.push(match i % 4 {
+            0 => PendingWrite::SetProperty {
+                entity_id,
+                key: "target".to_string(),
+                value: Value::String(format!("unit_{}", i % 64)),
+            },
+            1 => PendingWrite::AddFlag {
+                entity_id,
+                flag: DefId::new("engaged"),
+            },
+            _ => PendingWrite::ModifyProperty {
+                entity_id,
+                key: "hp".to_string(),
+                op: ModifyOp::Sub,
+                value: 1.0,
+            },
+        });
+    }
+    writes
+}
+
+fn bench_commit(c: &mut Criterion) {
+    let mut group = c.benchmark_group("commit_100k_writes");
+    group.sample_size(20);
+    for units in ENTITY_COUNTS {
+        let (mut model, ids) = model(units);
+        let write_set = writes(&ids);
+
+        // Both consume a fresh WriteSet, as a tick does
+        group.bench_with_input(BenchmarkId::new("apply", units), &units, |b, _| {
+            b.iter_batched(
+                || write_set.clone(),
+                |write_set| {
+                    let result = apply(&write_set, &mut model);
+                    drop(write_set);
+                    black_box(result)
+                },
+                BatchSize::LargeInput,
+            )
+        });
+        let mut arena = CommitArena::new();
+        group.bench_with_input(BenchmarkId::new("arena", units), &units, |b, _| {
+            b.iter_batched(
+                || write_set.clone(),
+                |write_set| black_box(arena.apply(write_set, &mut model)),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_commit);
+criterion_main!(benches);
@@ -0,0 +1,346 @@
+//! Batched commit path for large WriteSets
+//!
+//! [`apply`](crate::apply) handles writes one at a time: each clones its
+//! value, allocates its key and looks its entity up again. At tens of
+//! thousands of writes per tick that dominates the commit. A
+//! [`CommitArena`] takes the WriteSet by value and applies it in batches
+//! grouped by entity instead:
+//!
+//! - values are moved into the model, not cloned, and keys are interned
+//!   without allocating
+//! - each entity is looked up once per batch, and its writes land together
+//! - the arena only orders write indices, in buffers kept between commits,
+//!   so a steady workload allocates nothing once warmed up
+//!
+//! Keep one arena per hub (or per thread) and reuse it every tick.
+//!
+//! # Ordering
+//!
+//! Writes to the same entity keep their order, and spawns and destroys
+//! split the WriteSet into segments applied in order, so the model ends up
+//! exactly as with [`apply`](crate::apply). Skipped writes are reported in
+//! WriteSet order; schema violations are reported grouped by entity.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! let mut arena = CommitArena::new();
+//! loop {
+//!     let writes = collect_writes();
+//!     let result = arena.commit(writes, &mut model, &mut version);
+//! }
+//! ```
+
+use crate::commit::{apply_write, not_numeric, CommitResult};
+use pulsive_core::{
+    EntityId, Model, PendingWrite, Symbol, Value, WriteFailure, WriteSet, WriteSetResult,
+};
+
+/// No batch for an entity slot
+const NONE: u32 = u32::MAX;
+
+/// Reusable buffers for applying WriteSets grouped by entity
+///
+/// Writes stay in the WriteSet; the arena only orders their indices.
+#[derive(Debug, Default)]
+pub struct CommitArena {
+    /// Batch of each entity slot index in the current segment
+    slots: Vec<u32>,
+    /// Entities written in the current segment, in first-written order,
+    /// with their write count (turned into an offset into `order`)
+    batches: Vec<(EntityId, u32)>,
+    /// Batch and WriteSet index of each entity write of the segment
+    pending: Vec<(u32, u32)>,
+    /// WriteSet indices of the segment's entity writes, grouped by batch
+    order: Vec<u32>,
+}
+
+impl CommitArena {
+    /// Create an arena with no buffers allocated yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a WriteSet, consuming it
+    ///
+    /// Same result as [`apply`](crate::apply) on the same model, see the
+    /// [module docs](self) for ordering.
+    pub fn apply(&mut self, write_set: WriteSet, model: &mut Model) -> WriteSetResult {
+        let mut writes = write_set.into_writes();
+        let mut result = WriteSetResult::new();
+        for index in 0..writes.len() {
+            match &writes[index] {
+                PendingWrite::SetProperty { entity_id, .. }
+                | PendingWrite::ModifyProperty { entity_id, .. }
+                | PendingWrite::AddFlag { entity_id, .. }
+                | PendingWrite::RemoveFlag { entity_id, .. }
+                | PendingWrite::AddFlagFor { entity_id, .. }
+                | PendingWrite::AddTag { entity_id, .. }
+                | PendingWrite::RemoveTag { entity_id, .. } => {
+                    if !self.defer(*entity_id, index) {
+                        apply_write(model, index, &writes[index], &mut result);
+                    }
+                }
+                // Globals don't depend on entity writes
+                PendingWrite::SetGlobal { .. } | PendingWrite::ModifyGlobal { .. } => {
+                    apply_write(model, index, &writes[index], &mut result);
+                }
+                // Spawns and destroys end the segment
+                PendingWrite::SpawnEntity { .. } | PendingWrite::DestroyEntity { .. } => {
+                    self.flush(&mut writes, model, &mut result);
+                    apply_write(model, index, &writes[index], &mut result);
+                }
+            }
+        }
+        self.flush(&mut writes, model, &mut result);
+        result.report.skipped.sort_by_key(|skipped| skipped.index);
+        result
+    }
+
+    /// Apply several WriteSets merged in order, consuming them
+    ///
+    /// Like [`apply_batch`](crate::apply_batch), this does not detect
+    /// conflicts.
+    pub fn apply_batch(&mut self, write_sets: Vec<WriteSet>, model: &mut Model) -> WriteSetResult {
+        self.apply(WriteSet::merge(write_sets), model)
+    }
+
+    /// Commit a WriteSet with version tracking, like [`commit`](crate::commit)
+    pub fn commit(
+        &mut self,
+        write_set: WriteSet,
+        model: &mut Model,
+        version: &mut u64,
+    ) -> CommitResult {
+        if write_set.is_empty() {
+            return CommitResult::new(*version);
+        }
+        let write_result = self.apply(write_set, model);
+        *version += 1;
+        let mut result = CommitResult::new(*version);
+        result.merge_write_result(write_result);
+        result
+    }
+
+    /// Add an entity write to its batch in the current segment
+    ///
+    /// Returns false if the entity's slot already has a batch for another
+    /// generation of it: writes to different entities commute, so the
+    /// caller applies the write right away.
+    fn defer(&mut self, entity_id: EntityId, index: usize) -> bool {
+        let slot = entity_id.index() as usize;
+        if slot >= self.slots.len() {
+            self.slots.resize(slot + 1, NONE);
+        }
+        let batch = match self.slots[slot] {
+            NONE => {
+                self.slots[slot] = self.batches.len() as u32;
+                self.batches.push((entity_id, 0));
+                self.batches.len() - 1
+            }
+            batch if self.batches[batch as usize].0 == entity_id => batch as usize,
+            _ => return false,
+        };
+        self.batches[batch].1 += 1;
+        self.pending.push((batch as u32, index as u32));
+        true
+    }
+
+    /// Apply the batches of the current segment and release them
+    fn flush(
+        &mut self,
+        writes: &mut [PendingWrite],
+        model: &mut Model,
+        result: &mut WriteSetResult,
+    ) {
+        if self.batches.is_empty() {
+            return;
+        }
+        // Counting sort of the pending writes by batch, keeping their order
+        let mut offset = 0;
+        for (_, count) in &mut self.batches {
+            let start = offset;
+            offset += *count;
+            *count = start;
+        }
+        self.order.clear();
+        self.order.resize(self.pending.len(), 0);
+        for &(batch, index) in &self.pending {
+            let next = &mut self.batches[batch as usize].1;
+            self.order[*next as usize] = index;
+            *next += 1;
+        }
+
+        // Schemas need the model to check each write: take the per-write path
+        let checked = !model.schemas().is_empty();
+        let mut start = 0;
+        for &(entity_id, end) in &self.batches {
+            let indices = &self.order[start as usize..end as usize];
+            start = end;
+            if checked {
+                for &index in indices {
+                    apply_write(model, index as usize, &writes[index as usize], result);
+                }
+                continue;
+            }
+            let Some(entity) = model.entities_mut().get_mut(entity_id) else {
+                for &index in indices {
+                    let failure = WriteFailure::MissingEntity(entity_id);
+                    result
+                        .report
+                        .skip(index as usize, &writes[index as usize], failure);
+                }
+                continue;
+            };
+            for &index in indices {
+                let index = index as usize;
+                let failure = match &mut writes[index] {
+                    PendingWrite::SetProperty { key, value, .. } => {
+                        entity.set(Symbol::new(key), std::mem::take(value));
+                        None
+                    }
+                    PendingWrite::ModifyProperty { key, op, value, .. } => {
+                        let key = Symbol::new(key);
+                        let current = entity.get(&key);
+                        let failure = not_numeric(&key, current);
+                        if failure.is_none() {
+                            let current = current.and_then(|v| v.as_float()).unwrap_or(0.0);
+                            entity.set(key, Value::Float(op.apply(current, *value)));
+                        }
+                        failure
+                    }
+                    PendingWrite::AddFlag { flag, .. } => {
+                        entity.add_flag(flag.clone());
+                        None
+                    }
+                    PendingWrite::RemoveFlag { flag, .. } => {
+                        entity.remove_flag(flag);
+                        None
+                    }
+                    PendingWrite::AddFlagFor {
+                        flag, expires_at, ..
+                    } => {
+                        entity.add_flag_until(flag.clone(), *expires_at);
+                        None
+                    }
+                    PendingWrite::AddTag { tag, .. } => {
+                        entity.add_tag(tag.clone());
+                        None
+                    }
+                    PendingWrite::RemoveTag { tag, .. } => {
+                        entity.remove_tag(tag);
+                        None
+                    }
+                    _ => unreachable!("only entity writes are batched"),
+                };
+                if let Some(failure) = failure {
+                    result.report.skip(index, &writes[index], failure);
+                }
+            }
+        }
+
+        for &(entity_id, _) in &self.batches {
+            self.slots[entity_id.index() as usize] = NONE;
+        }
+        self.batches.clear();
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply;
+    use pulsive_core::{DefId, ModifyOp, PropertySchema, PropertySchemas, ValueMap, ValueType};
+
+    /// Writes touching two entities, globals, a spawn, a destroy and
+    /// failing writes
+    fn writes(a: EntityId, b: EntityId) -> WriteSet {
+        let mut writes = WriteSet::new();
+        let set = |entity_id, key: &str, value: f64| PendingWrite::SetProperty {
+            entity_id,
+            key: key.to_string(),
+            value: Value::Float(value),
+        };
+        let add = |entity_id, key: &str, value: f64| PendingWrite::ModifyProperty {
+            entity_id,
+            key: key.to_string(),
+            op: ModifyOp::Add,
+            value,
+        };
+        writes.push(set(a, "hp", 10.0));
+        writes.push(add(b, "hp", 5.0));
+        writes.push(PendingWrite::SetGlobal {
+            key: "gold".to_string(),
+            value: Value::Float(3.0),
+        });
+        writes.push(add(a, "hp", 1.0));
+        writes.push(add(a, "name", 1.0));
+        writes.push(PendingWrite::AddFlag {
+            entity_id: b,
+            flag: DefId::new("wounded"),
+        });
+        writes.push(PendingWrite::DestroyEntity { id: b });
+        writes.push(set(b, "hp", 1.0));
+        writes.push(PendingWrite::SpawnEntity {
+            kind: DefId::new("unit"),
+            properties: ValueMap::new(),
+        });
+        writes.push(set(a, "hp", 200.0));
+        writes
+    }
+
+    fn model() -> (Model, EntityId, EntityId) {
+        let mut model = Model::new();
+        let a = model.entities_mut().create("unit");
+        a.set("name", "Ada");
+        let a = a.id;
+        let b = model.entities_mut().create("unit").id;
+        (model, a, b)
+    }
+
+    #[test]
+    fn test_matches_apply() {
+        let (mut expected, a, b) = model();
+        let mut model = expected.clone();
+        let expected_result = apply(&writes(a, b), &mut expected);
+
+        let mut arena = CommitArena::new();
+        let result = arena.apply(writes(a, b), &mut model);
+        assert_eq!(result.report, expected_result.report);
+        assert_eq!(result.report.len(), 2);
+        assert_eq!(result.spawned, expected_result.spawned);
+        assert_eq!(result.destroyed, [b]);
+        assert_eq!(model.get_global("gold"), expected.get_global("gold"));
+        assert_eq!(model.entities().get(a), expected.entities().get(a));
+        assert_eq!(model.entities().len(), expected.entities().len());
+
+        // Buffers are reused
+        let expected_result = apply(&writes(a, b), &mut expected);
+        let mut version = 0;
+        let result = arena.commit(writes(a, b), &mut model, &mut version);
+        assert_eq!(version, 1);
+        assert_eq!(result.report, expected_result.report);
+        assert_eq!(model.entities().get(a), expected.entities().get(a));
+        assert!(arena.batches.is_empty() && arena.pending.capacity() > 0);
+    }
+
+    #[test]
+    fn test_checks_schemas() {
+        let (mut model, a, b) = model();
+        let mut schemas = PropertySchemas::new();
+        schemas.register(
+            "unit",
+            "hp",
+            PropertySchema::new(ValueType::Float).with_max(100.0),
+        );
+        model.set_schemas(schemas);
+        let mut expected = model.clone();
+        let expected_result = apply(&writes(a, b), &mut expected);
+
+        let result = CommitArena::new().apply(writes(a, b), &mut model);
+        assert_eq!(result.violations, expected_result.violations);
+        assert_eq!(result.violations.len(), 1);
+        assert_eq!(model.entities().get(a), expected.entities().get(a));
+    }
+}
@@ -0,0 +1,298 @@
+//! Event bus - one integration point for host applications
+//!
+//! After each tick the hub publishes what its cores produced (emitted
+//! events, notifications and logs) to the subscribers whose pattern matches,
+//! so a host (a Godot node, a server) reacts to one stream instead of
+//! polling every core.
+//!
+//! # Topics
+//!
+//! Every published item has a topic:
+//! - `event:<event_id>` for emitted events
+//! - `notification:<kind>` for notifications
+//! - `log:<level>` for logs (`debug`, `info`, `warn` or `error`)
+//!
+//! When a core panics, the hub quarantines it and publishes a
+//! [`CORE_FAILED`] event (`event:core_failed`) after the tick's items.
+//!
+//! Patterns match topics with `*` standing for any run of characters:
+//! `event:*` receives every event, `*` everything.
+//!
+//! # Order
+//!
+//! Items are published in the order of the tick's update results (groups in
+//! registration order, cores in ID order, messages in processing order);
+//! within an update, events come first, then notifications, then logs. Each
+//! item goes to its subscribers in subscription order. The order is the
+//! same on every run.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! hub.subscribe("event:battle_*", |event| {
+//!     if let BusEvent::Event { event_id, target, .. } = event {
+//!         println!("{} on {:?}", event_id, target);
+//!     }
+//! });
+//! hub.tick()?;
+//! ```
+
+use crate::group::CoreFailure;
+use pulsive_core::effect::{LogLevel, Notification};
+use pulsive_core::{DefId, EntityRef, LogRecord, UpdateResult, Value, ValueMap};
+
+/// Event published when a core panics and is quarantined
+///
+/// Targets [`EntityRef::Global`], with params `group`, `core`, `tick` and
+/// `error`.
+pub const CORE_FAILED: &str = "core_failed";
+
+/// Something a tick produced, published to subscribers
+#[derive(Debug, Clone)]
+pub enum BusEvent {
+    /// An event emitted by a handler
+    Event {
+        /// The event emitted
+        event_id: DefId,
+        /// Its target
+        target: EntityRef,
+        /// Its parameters
+        params: ValueMap,
+    },
+    /// A notification for the UI
+    Notification(Notification),
+    /// A log record
+    Log(LogRecord),
+}
+
+impl BusEvent {
+    /// Get the topic subscribers' patterns are matched against
+    pub fn topic(&self) -> String {
+        match self {
+            BusEvent::Event { event_id, .. } => format!("event:{}", event_id),
+            BusEvent::Notification(notification) => format!("notification:{}", notification.kind),
+            BusEvent::Log(record) => {
+                let level = match record.level {
+                    LogLevel::Debug => "debug",
+                    LogLevel::Info => "info",
+                    LogLevel::Warn => "warn",
+                    LogLevel::Error => "error",
+                };
+                format!("log:{}", level)
+            }
+        }
+    }
+
+    /// Collect the items of an update result, in publishing order
+    pub fn from_update(update: &UpdateResult) -> Vec<BusEvent> {
+        let effects = &update.effect_result;
+        let events = effects
+            .emitted_events
+            .iter()
+            .map(|(event_id, target, params)| BusEvent::Event {
+                event_id: event_id.clone(),
+                target: target.clone(),
+                params: params.clone(),
+            });
+        let notifications = effects
+            .notifications
+            .iter()
+            .cloned()
+            .map(BusEvent::Notification);
+        let logs = effects.logs.iter().cloned().map(BusEvent::Log);
+        events.chain(notifications).chain(logs).collect()
+    }
+
+    /// Create the [`CORE_FAILED`] event of a quarantined core
+    pub fn core_failed(failure: &CoreFailure) -> BusEvent {
+        let mut params = ValueMap::new();
+        params.insert("group", Value::from(failure.group.0 as i64));
+        params.insert("core", Value::from(failure.core.0 as i64));
+        params.insert("tick", Value::from(failure.tick as i64));
+        params.insert("error", Value::from(failure.error.as_str()));
+        BusEvent::Event {
+            event_id: DefId::new(CORE_FAILED),
+            target: EntityRef::Global,
+            params,
+        }
+    }
+}
+
+/// Identifies a subscription, for [`EventBus::unsubscribe`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+/// Callback run for each published item a subscription matches
+pub type BusCallback = Box<dyn FnMut(&BusEvent) + Send>;
+
+struct Subscription {
+    id: SubscriptionId,
+    pattern: String,
+    callback: BusCallback,
+}
+
+/// Subscriptions to what ticks produce
+#[derive(Default)]
+pub struct EventBus {
+    /// Subscriptions, in subscription order
+    subscriptions: Vec<Subscription>,
+    /// ID of the next subscription
+    next_id: u64,
+}
+
+impl EventBus {
+    /// Create a bus with no subscriptions
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe to the items whose topic matches a pattern
+    pub fn subscribe(
+        &mut self,
+        pattern: impl Into<String>,
+        callback: impl FnMut(&BusEvent) + Send + 'static,
+    ) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.subscriptions.push(Subscription {
+            id,
+            pattern: pattern.into(),
+            callback: Box::new(callback),
+        });
+        id
+    }
+
+    /// Remove a subscription, returning whether it existed
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let before = self.subscriptions.len();
+        self.subscriptions.retain(|s| s.id != id);
+        self.subscriptions.len() != before
+    }
+
+    /// Get the number of subscriptions
+    pub fn len(&self) -> usize {
+        self.subscriptions.len()
+    }
+
+    /// Check if there are no subscriptions
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions.is_empty()
+    }
+
+    /// Publish the items of a tick's update results
+    pub fn publish(&mut self, updates: &[UpdateResult]) {
+        self.publish_events(updates.iter().flat_map(BusEvent::from_update));
+    }
+
+    /// Publish items to the subscribers whose pattern matches, in order
+    pub fn publish_events(&mut self, events: impl IntoIterator<Item = BusEvent>) {
+        if self.subscriptions.is_empty() {
+            return;
+        }
+        for event in events {
+            let topic = event.topic();
+            for subscription in &mut self.subscriptions {
+                if glob_match(&subscription.pattern, &topic) {
+                    (subscription.callback)(&event);
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.subscriptions.iter().map(|s| &s.pattern))
+            .finish()
+    }
+}
+
+/// Match `topic` against a pattern where `*` matches any run of characters
+fn glob_match(pattern: &str, topic: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = topic.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<_> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No wildcard: the whole topic must match
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::EffectResult;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_publish_order_and_patterns() {
+        let mut effect_result = EffectResult::new();
+        effect_result.emitted_events.push((
+            DefId::new("battle_won"),
+            EntityRef::Global,
+            ValueMap::new(),
+        ));
+        effect_result.emitted_events.push((
+            DefId::new("trade"),
+            EntityRef::Global,
+            ValueMap::new(),
+        ));
+        effect_result
+            .logs
+            .push(LogRecord::new(LogLevel::Warn, "low gold"));
+        let mut update = UpdateResult::new();
+        update.effect_result = effect_result;
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut bus = EventBus::new();
+        let battles = {
+            let seen = Arc::clone(&seen);
+            bus.subscribe("event:battle_*", move |e| {
+                seen.lock().unwrap().push(format!("battles {}", e.topic()))
+            })
+        };
+        {
+            let seen = Arc::clone(&seen);
+            bus.subscribe("*", move |e| {
+                seen.lock().unwrap().push(format!("all {}", e.topic()))
+            });
+        }
+
+        bus.publish(&[update.clone()]);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                "battles event:battle_won",
+                "all event:battle_won",
+                "all event:trade",
+                "all log:warn",
+            ]
+        );
+
+        assert!(bus.unsubscribe(battles));
+        assert!(!bus.unsubscribe(battles));
+        seen.lock().unwrap().clear();
+        bus.publish(&[update]);
+        assert_eq!(seen.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", "log:info"));
+        assert!(glob_match("event:*", "event:trade"));
+        assert!(glob_match("event:*_won", "event:battle_won"));
+        assert!(!glob_match("event:*", "notification:alert"));
+        assert!(!glob_match("event:trade", "event:trade_route"));
+    }
+}
@@ -3,9 +3,12 @@
 //! This module provides functions for applying WriteSets to the Model:
 //!
 //! - [`apply`]: Apply a single WriteSet directly (no conflict checking)
+//! - [`apply_strict`]: Apply a WriteSet, failing if any write can't be applied
 //! - [`apply_batch`]: Apply multiple WriteSets merged together (no conflict checking)
+//! - [`apply_undoable`](crate::apply_undoable): Apply a WriteSet, recording an undo step
 //! - [`commit`]: Commit a WriteSet with version tracking
 //! - [`commit_batch`]: Commit multiple WriteSets with conflict detection/resolution
+//! - [`CommitArena`](crate::CommitArena): Apply and commit large WriteSets in batches grouped by entity
 //!
 //! # Design
 //!
@@ -36,9 +39,14 @@
 //!     result.version, result.conflicts_resolved);
 //! ```
 
-use crate::conflict::{detect_conflicts, resolve_conflicts, ResolutionStrategy};
-use crate::{CoreId, Result};
-use pulsive_core::{EntityId, Model, PendingWrite, Value, WriteSet, WriteSetResult};
+use crate::conflict::{
+    detect_conflicts_with_detail, resolve_conflicts_with_detail, ConflictDetail, ResolutionStrategy,
+};
+use crate::{CoreId, Error, Result};
+use pulsive_core::{
+    EntityId, Model, PendingWrite, SchemaViolation, Value, WriteApplyReport, WriteFailure,
+    WriteSet, WriteSetResult,
+};
 
 /// Result of a successful commit operation
 #[derive(Debug, Clone, Default)]
@@ -51,6 +59,10 @@ pub struct CommitResult {
     pub destroyed: Vec<EntityId>,
     /// Number of conflicts that were resolved (0 for single WriteSet)
     pub conflicts_resolved: usize,
+    /// Writes that violated their property schema
+    pub violations: Vec<SchemaViolation>,
+    /// Writes that could not be applied
+    pub report: WriteApplyReport,
 }
 
 impl CommitResult {
@@ -66,6 +78,8 @@ impl CommitResult {
     pub fn merge_write_result(&mut self, result: WriteSetResult) {
         self.spawned.extend(result.spawned);
         self.destroyed.extend(result.destroyed);
+        self.violations.extend(result.violations);
+        self.report.merge(result.report);
     }
 }
 
@@ -87,82 +101,190 @@ impl CommitResult {
 /// A `WriteSetResult` containing:
 /// - `spawned`: Entity IDs that were created
 /// - `destroyed`: Entity IDs that were removed
+/// - `violations`: Writes that violated the model's property schemas, which
+///   are skipped, clamped or applied per the schema policy
+/// - `report`: Writes that were skipped because they target a missing entity
+///   or modify a non-numeric value
 pub fn apply(write_set: &WriteSet, model: &mut Model) -> WriteSetResult {
     let mut result = WriteSetResult::new();
+    for (index, write) in write_set.iter().enumerate() {
+        apply_write(model, index, write, &mut result);
+    }
+    result
+}
 
-    for write in write_set.iter() {
-        match write {
-            PendingWrite::SetProperty {
-                entity_id,
-                key,
-                value,
-            } => {
-                if let Some(entity) = model.entities_mut().get_mut(*entity_id) {
-                    entity.set(key.clone(), value.clone());
-                }
-            }
+/// Apply one write of a WriteSet, recording it in the result
+pub(crate) fn apply_write(
+    model: &mut Model,
+    index: usize,
+    write: &PendingWrite,
+    result: &mut WriteSetResult,
+) {
+    if let Some(failure) = check_write(model, write) {
+        result.report.skip(index, write, failure);
+        return;
+    }
+    match write {
+        PendingWrite::SetProperty {
+            entity_id,
+            key,
+            value,
+        } => {
+            set_property(model, *entity_id, key, value.clone(), result);
+        }
 
-            PendingWrite::ModifyProperty {
-                entity_id,
-                key,
-                op,
-                value,
-            } => {
-                if let Some(entity) = model.entities_mut().get_mut(*entity_id) {
-                    let current = entity.get_number(key).unwrap_or(0.0);
-                    let new_value = op.apply(current, *value);
-                    entity.set(key.clone(), new_value);
-                }
+        PendingWrite::ModifyProperty {
+            entity_id,
+            key,
+            op,
+            value,
+        } => {
+            if let Some(entity) = model.entities().get(*entity_id) {
+                let current = entity.get_number(key).unwrap_or(0.0);
+                let new_value = op.apply(current, *value);
+                set_property(model, *entity_id, key, Value::Float(new_value), result);
             }
+        }
+
+        PendingWrite::SetGlobal { key, value } => {
+            set_global(model, key, value.clone(), result);
+        }
+
+        PendingWrite::ModifyGlobal { key, op, value } => {
+            let current = model
+                .globals()
+                .get(key)
+                .and_then(|v| v.as_float())
+                .unwrap_or(0.0);
+            let new_value = op.apply(current, *value);
+            set_global(model, key, Value::Float(new_value), result);
+        }
 
-            PendingWrite::SetGlobal { key, value } => {
-                model.globals_mut().insert(key.clone(), value.clone());
+        PendingWrite::AddFlag { entity_id, flag } => {
+            if let Some(entity) = model.entities_mut().get_mut(*entity_id) {
+                entity.add_flag(flag.clone());
             }
+        }
 
-            PendingWrite::ModifyGlobal { key, op, value } => {
-                let current = model
-                    .globals()
-                    .get(key)
-                    .and_then(|v| v.as_float())
-                    .unwrap_or(0.0);
-                let new_value = op.apply(current, *value);
-                model
-                    .globals_mut()
-                    .insert(key.clone(), Value::Float(new_value));
+        PendingWrite::RemoveFlag { entity_id, flag } => {
+            if let Some(entity) = model.entities_mut().get_mut(*entity_id) {
+                entity.remove_flag(flag);
             }
+        }
 
-            PendingWrite::AddFlag { entity_id, flag } => {
-                if let Some(entity) = model.entities_mut().get_mut(*entity_id) {
-                    entity.add_flag(flag.clone());
-                }
+        PendingWrite::AddFlagFor {
+            entity_id,
+            flag,
+            expires_at,
+        } => {
+            if let Some(entity) = model.entities_mut().get_mut(*entity_id) {
+                entity.add_flag_until(flag.clone(), *expires_at);
             }
+        }
 
-            PendingWrite::RemoveFlag { entity_id, flag } => {
-                if let Some(entity) = model.entities_mut().get_mut(*entity_id) {
-                    entity.remove_flag(flag);
-                }
+        PendingWrite::AddTag { entity_id, tag } => {
+            if let Some(entity) = model.entities_mut().get_mut(*entity_id) {
+                entity.add_tag(tag.clone());
             }
+        }
 
-            PendingWrite::SpawnEntity { kind, properties } => {
-                let entity = model.entities_mut().create(kind.clone());
-                let entity_id = entity.id;
+        PendingWrite::RemoveTag { entity_id, tag } => {
+            if let Some(entity) = model.entities_mut().get_mut(*entity_id) {
+                entity.remove_tag(tag);
+            }
+        }
 
-                // Set initial properties
-                for (key, value) in properties {
-                    entity.set(key.clone(), value.clone());
-                }
+        PendingWrite::SpawnEntity { kind, properties } => {
+            let entity_id = model.entities_mut().create(kind.clone()).id;
 
-                result.spawned.push(entity_id);
+            // Set initial properties
+            for (key, value) in properties {
+                set_property(model, entity_id, key, value.clone(), result);
             }
 
-            PendingWrite::DestroyEntity { id } => {
-                model.entities_mut().remove(*id);
-                result.destroyed.push(*id);
-            }
+            result.spawned.push(entity_id);
+        }
+
+        PendingWrite::DestroyEntity { id } => {
+            model.entities_mut().remove(*id);
+            result.destroyed.push(*id);
         }
     }
+}
 
-    result
+/// Apply a WriteSet, failing if any write can't be applied
+///
+/// Unlike [`apply`], which skips such writes and reports them, this is
+/// all-or-nothing: on failure the model is left unchanged and
+/// [`Error::WriteFailed`] carries the report. Use it in tests to catch writes
+/// that would otherwise be dropped silently.
+pub fn apply_strict(write_set: &WriteSet, model: &mut Model) -> Result<WriteSetResult> {
+    let mut staged = model.clone();
+    let result = apply(write_set, &mut staged);
+    if !result.report.is_empty() {
+        return Err(Error::WriteFailed(Box::new(result.report)));
+    }
+    *model = staged;
+    Ok(result)
+}
+
+/// Find why a write can't be applied to the model, if it can't
+fn check_write(model: &Model, write: &PendingWrite) -> Option<WriteFailure> {
+    let entity_id = match write {
+        PendingWrite::SetProperty { entity_id, .. }
+        | PendingWrite::ModifyProperty { entity_id, .. }
+        | PendingWrite::AddFlag { entity_id, .. }
+        | PendingWrite::RemoveFlag { entity_id, .. }
+        | PendingWrite::AddFlagFor { entity_id, .. }
+        | PendingWrite::AddTag { entity_id, .. }
+        | PendingWrite::RemoveTag { entity_id, .. } => *entity_id,
+        PendingWrite::DestroyEntity { id } => *id,
+        PendingWrite::ModifyGlobal { key, .. } => {
+            return not_numeric(key, model.globals().get(key))
+        }
+        PendingWrite::SetGlobal { .. } | PendingWrite::SpawnEntity { .. } => return None,
+    };
+    let Some(entity) = model.entities().get(entity_id) else {
+        return Some(WriteFailure::MissingEntity(entity_id));
+    };
+    match write {
+        PendingWrite::ModifyProperty { key, .. } => not_numeric(key, entity.get(key)),
+        _ => None,
+    }
+}
+
+/// The failure of modifying a value that is set but not a number
+pub(crate) fn not_numeric(key: &str, value: Option<&Value>) -> Option<WriteFailure> {
+    value
+        .filter(|v| !v.is_null() && v.as_float().is_none())
+        .map(|v| WriteFailure::NotNumeric {
+            key: key.to_string(),
+            value: v.clone(),
+        })
+}
+
+/// Set an entity property, checked against its schema
+fn set_property(
+    model: &mut Model,
+    entity_id: EntityId,
+    key: &str,
+    value: Value,
+    result: &mut WriteSetResult,
+) {
+    let (applied, violation) = model.check_property(entity_id, key, value);
+    result.violations.extend(violation);
+    if let (Some(value), Some(entity)) = (applied, model.entities_mut().get_mut(entity_id)) {
+        entity.set(key.to_string(), value);
+    }
+}
+
+/// Set a global, checked against its schema
+fn set_global(model: &mut Model, key: &str, value: Value, result: &mut WriteSetResult) {
+    let (applied, violation) = model.check_global(key, value);
+    result.violations.extend(violation);
+    if let Some(value) = applied {
+        model.globals_mut().insert(key.to_string(), value);
+    }
 }
 
 /// Apply multiple WriteSets by merging them first
@@ -242,6 +364,20 @@ pub fn commit_batch(
     model: &mut Model,
     version: &mut u64,
     strategy: &ResolutionStrategy,
+) -> Result<CommitResult> {
+    commit_batch_with_detail(write_sets, model, version, strategy, ConflictDetail::Full)
+}
+
+/// Commit multiple WriteSets, keeping only the writes a detail level asks
+/// for in the report of an `Abort` error
+///
+/// See [`commit_batch`] and [`resolve_conflicts_with_detail`].
+pub fn commit_batch_with_detail(
+    write_sets: Vec<(CoreId, WriteSet)>,
+    model: &mut Model,
+    version: &mut u64,
+    strategy: &ResolutionStrategy,
+    detail: ConflictDetail,
 ) -> Result<CommitResult> {
     // Fast path: single WriteSet has no conflicts
     if write_sets.len() <= 1 {
@@ -250,7 +386,7 @@ pub fn commit_batch(
     }
 
     // Detect and resolve conflicts
-    let resolution_result = resolve_conflicts(&write_sets, strategy)?;
+    let resolution_result = resolve_conflicts_with_detail(&write_sets, strategy, detail)?;
 
     // Skip version increment if no writes (no state change)
     if resolution_result.write_set.is_empty() {
@@ -277,7 +413,7 @@ pub fn commit_batch(
 ///
 /// `true` if there are conflicts, `false` if safe to merge.
 pub fn has_conflicts(write_sets: &[(CoreId, WriteSet)]) -> bool {
-    detect_conflicts(write_sets).has_conflicts()
+    detect_conflicts_with_detail(write_sets, ConflictDetail::CountsOnly).has_conflicts()
 }
 
 #[cfg(test)]
@@ -398,6 +534,112 @@ mod tests {
         assert_eq!(entity.get_number("gold"), Some(100.0));
     }
 
+    #[test]
+    fn test_apply_checks_schemas() {
+        use pulsive_core::{PropertySchema, PropertySchemas, SchemaPolicy, ValueType};
+
+        let mut model = Model::new();
+        let mut schemas = PropertySchemas::new().with_policy(SchemaPolicy::Clamp);
+        schemas.register(
+            "nation",
+            "stability",
+            PropertySchema::new(ValueType::Float).with_range(-3.0, 3.0),
+        );
+        schemas.register(
+            "nation",
+            "government",
+            PropertySchema::new(ValueType::String).with_allowed(["monarchy", "republic"]),
+        );
+        model.set_schemas(schemas);
+        let entity_id = model.entities_mut().create("nation").id;
+
+        let mut write_set = WriteSet::new();
+        write_set.push(PendingWrite::ModifyProperty {
+            entity_id,
+            key: "stability".to_string(),
+            op: ModifyOp::Add,
+            value: 5.0,
+        });
+        write_set.push(PendingWrite::SetProperty {
+            entity_id,
+            key: "government".to_string(),
+            value: Value::String("theocracy".to_string()),
+        });
+
+        let result = apply(&write_set, &mut model);
+
+        let entity = model.entities().get(entity_id).unwrap();
+        assert_eq!(entity.get_number("stability"), Some(3.0));
+        assert_eq!(entity.get("government"), None);
+        assert_eq!(result.violations.len(), 2);
+        assert_eq!(result.violations[0].applied, Some(Value::Float(3.0)));
+        assert_eq!(result.violations[1].applied, None);
+    }
+
+    #[test]
+    fn test_apply_reports_skipped_writes() {
+        let mut model = Model::new();
+        let entity = model.entities_mut().create("nation");
+        entity.set("name", "France");
+        let id = entity.id;
+        let missing = EntityId::new(99);
+
+        let mut write_set = WriteSet::new();
+        write_set.push(PendingWrite::SetProperty {
+            entity_id: missing,
+            key: "gold".to_string(),
+            value: Value::Float(1.0),
+        });
+        write_set.push(PendingWrite::ModifyProperty {
+            entity_id: id,
+            key: "name".to_string(),
+            op: ModifyOp::Add,
+            value: 1.0,
+        });
+        write_set.push(PendingWrite::SetGlobal {
+            key: "year".to_string(),
+            value: Value::Int(1444),
+        });
+
+        let result = apply(&write_set, &mut model);
+        let failures: Vec<_> = result.report.skipped.iter().map(|s| s.index).collect();
+        assert_eq!(failures, [0, 1]);
+        assert_eq!(
+            result.report.skipped[0].failure,
+            WriteFailure::MissingEntity(missing)
+        );
+        assert!(matches!(
+            result.report.skipped[1].failure,
+            WriteFailure::NotNumeric { .. }
+        ));
+        assert_eq!(model.get_global("year"), Some(&Value::Int(1444)));
+    }
+
+    #[test]
+    fn test_apply_strict() {
+        let mut model = Model::new();
+        let mut write_set = WriteSet::new();
+        write_set.push(PendingWrite::SetGlobal {
+            key: "year".to_string(),
+            value: Value::Int(1444),
+        });
+        write_set.push(PendingWrite::DestroyEntity {
+            id: EntityId::new(7),
+        });
+
+        let err = apply_strict(&write_set, &mut model).unwrap_err();
+        assert!(matches!(err, Error::WriteFailed(ref report) if report.len() == 1));
+        assert_eq!(model.get_global("year"), None);
+
+        let mut write_set = WriteSet::new();
+        write_set.push(PendingWrite::SetGlobal {
+            key: "year".to_string(),
+            value: Value::Int(1444),
+        });
+        assert!(apply_strict(&write_set, &mut model).is_ok());
+        assert_eq!(model.get_global("year"), Some(&Value::Int(1444)));
+    }
+
     #[test]
     fn test_apply_destroy_entity() {
         let mut model = Model::new();
@@ -593,6 +835,49 @@ mod tests {
         assert_eq!(version, 0);
     }
 
+    #[test]
+    fn test_commit_batch_reduces_abort_report() {
+        let write_sets = || {
+            (0..2)
+                .map(|core| {
+                    let mut ws = WriteSet::new();
+                    for value in 0..3 {
+                        ws.push(PendingWrite::SetGlobal {
+                            key: "gold".to_string(),
+                            value: Value::Float(value as f64),
+                        });
+                    }
+                    (CoreId(core), ws)
+                })
+                .collect::<Vec<_>>()
+        };
+        let kept = |detail| {
+            let mut model = Model::new();
+            let mut version = 0u64;
+            let error = commit_batch_with_detail(
+                write_sets(),
+                &mut model,
+                &mut version,
+                &ResolutionStrategy::Abort,
+                detail,
+            )
+            .unwrap_err();
+            let conflict = error
+                .conflict_report()
+                .unwrap()
+                .iter()
+                .next()
+                .unwrap()
+                .clone();
+            assert_eq!(conflict.write_count(), 6);
+            conflict.writes.len()
+        };
+
+        assert_eq!(kept(ConflictDetail::Full), 6);
+        assert_eq!(kept(ConflictDetail::SampledWrites(2)), 2);
+        assert_eq!(kept(ConflictDetail::CountsOnly), 0);
+    }
+
     #[test]
     fn test_commit_batch_with_conflicts_last_write_wins() {
         let mut model = Model::new();
@@ -740,7 +1025,7 @@ mod tests {
         let mut model = Model::new();
         model.set_global("gold", 100.0f64);
 
-        let mut runtime = Runtime::new();
+        let runtime = Runtime::new();
         let mut effect_result = EffectResult::new();
         let params = ValueMap::new();
 
@@ -788,7 +1073,7 @@ mod tests {
         entity.set("population", 1000.0f64);
         let entity_id = entity.id;
 
-        let mut runtime = Runtime::new();
+        let runtime = Runtime::new();
         let mut effect_result = EffectResult::new();
         let params = ValueMap::new();
 
@@ -832,7 +1117,7 @@ mod tests {
         let mut model = Model::new();
         model.set_global("counter", 0.0f64);
 
-        let mut runtime = Runtime::new();
+        let runtime = Runtime::new();
         let mut effect_result = EffectResult::new();
         let params = ValueMap::new();
 
@@ -882,7 +1167,7 @@ mod tests {
     fn test_collect_spawn_then_apply() {
         let mut model = Model::new();
 
-        let mut runtime = Runtime::new();
+        let runtime = Runtime::new();
         let mut effect_result = EffectResult::new();
         let params = ValueMap::new();
 
@@ -921,7 +1206,7 @@ mod tests {
     fn test_collect_emit_event() {
         let mut model = Model::new();
 
-        let mut runtime = Runtime::new();
+        let runtime = Runtime::new();
         let mut effect_result = EffectResult::new();
         let params = ValueMap::new();
 
@@ -1,7 +1,8 @@
 //! Hub Configuration - Thread count and runtime settings
 //!
 //! This module provides configuration for the Hub's execution model,
-//! including core count and global seed for deterministic execution.
+//! including core count and global seed for deterministic execution, and
+//! how much detail conflict reports keep.
 //!
 //! # Deterministic RNG
 //!
@@ -17,6 +18,7 @@
 //! - Replay produces identical results
 //! - Works with any number of cores
 
+use crate::conflict::ConflictDetail;
 use pulsive_core::Rng;
 use serde::{Deserialize, Serialize};
 
@@ -57,6 +59,12 @@ pub struct HubConfig {
     ///
     /// This ensures each core has an independent, deterministic RNG stream.
     global_seed: u64,
+
+    /// How many conflicting writes conflict reports keep
+    ///
+    /// Defaults to [`ConflictDetail::Full`]; lower it for giant simulations.
+    #[serde(default)]
+    conflict_detail: ConflictDetail,
 }
 
 /// Default global seed for deterministic RNG
@@ -85,6 +93,7 @@ impl HubConfig {
         Self {
             core_count: core_count.clamp(1, max_cores()),
             global_seed,
+            conflict_detail: ConflictDetail::default(),
         }
     }
 
@@ -110,6 +119,7 @@ impl HubConfig {
         Self {
             core_count: core_count.clamp(1, max_cores()),
             global_seed: DEFAULT_GLOBAL_SEED,
+            conflict_detail: ConflictDetail::default(),
         }
     }
 
@@ -134,6 +144,7 @@ impl HubConfig {
         Self {
             core_count: 1,
             global_seed,
+            conflict_detail: ConflictDetail::default(),
         }
     }
 
@@ -191,6 +202,29 @@ impl HubConfig {
         self.global_seed = seed;
     }
 
+    /// Get the conflict detail level
+    ///
+    /// Applies to the conflict reports of [`Hub::commit_batch`](crate::Hub::commit_batch).
+    pub fn conflict_detail(&self) -> ConflictDetail {
+        self.conflict_detail
+    }
+
+    /// Set how many conflicting writes conflict reports keep
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use pulsive_hub::{ConflictDetail, HubConfig};
+    ///
+    /// let mut config = HubConfig::default();
+    /// assert_eq!(config.conflict_detail(), ConflictDetail::Full);
+    /// config.set_conflict_detail(ConflictDetail::SampledWrites(4));
+    /// assert_eq!(config.conflict_detail(), ConflictDetail::SampledWrites(4));
+    /// ```
+    pub fn set_conflict_detail(&mut self, detail: ConflictDetail) {
+        self.conflict_detail = detail;
+    }
+
     /// Check if configured for single-core mode
     ///
     /// Returns true when `core_count == 1`.
@@ -258,6 +292,7 @@ impl Default for HubConfig {
         Self {
             core_count: 1,
             global_seed: DEFAULT_GLOBAL_SEED,
+            conflict_detail: ConflictDetail::default(),
         }
     }
 }
@@ -32,9 +32,18 @@
 //! acceptable because spawns are independent operations - each core creates its own
 //! new entity. Use `detect_conflicts_filtered` with `default_conflict_filter` to
 //! exclude spawn conflicts if they are not relevant to your use case.
+//!
+//! # Detail Levels
+//!
+//! A report keeps every conflicting write by default. For giant simulations
+//! that can be most of a tick's writes; [`detect_conflicts_with_detail`]
+//! keeps only per-core write counts ([`ConflictDetail::CountsOnly`]) or a
+//! few writes per conflict ([`ConflictDetail::SampledWrites`]) instead.
+//! [`ConflictReport::dump_pretty`] renders a report as a table for debugging.
 
 use crate::CoreId;
-use pulsive_core::{DefId, EntityId, PendingWrite, WriteSet};
+use pulsive_core::{DefId, EntityId, PendingWrite, Symbol, WriteSet};
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
 // Re-export WriteSet for convenience in resolution result
@@ -45,21 +54,23 @@ pub use pulsive_core::WriteSet as WriteSetCore;
 /// This enum identifies the specific resource (entity property, global, etc.)
 /// that multiple cores attempted to write to. It serves as both the key for
 /// conflict detection and part of the conflict report.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ConflictTarget {
     /// Property on a specific entity
     EntityProperty {
         entity_id: EntityId,
-        property: String,
+        property: Symbol,
     },
     /// Flag on a specific entity
     EntityFlag { entity_id: EntityId, flag: DefId },
     /// Global property
-    GlobalProperty { property: String },
+    GlobalProperty { property: Symbol },
     /// Entity spawn (conflicts if same kind spawned by multiple cores - usually OK)
     SpawnEntity { kind: DefId },
     /// Entity destruction (conflicts if same entity destroyed by multiple cores)
     DestroyEntity { entity_id: EntityId },
+    /// Tag on a specific entity
+    EntityTag { entity_id: EntityId, tag: String },
 }
 
 impl ConflictTarget {
@@ -68,23 +79,26 @@ impl ConflictTarget {
         match write {
             PendingWrite::SetProperty { entity_id, key, .. } => ConflictTarget::EntityProperty {
                 entity_id: *entity_id,
-                property: key.clone(),
+                property: Symbol::new(key),
             },
             PendingWrite::ModifyProperty { entity_id, key, .. } => ConflictTarget::EntityProperty {
                 entity_id: *entity_id,
-                property: key.clone(),
+                property: Symbol::new(key),
             },
             PendingWrite::SetGlobal { key, .. } => ConflictTarget::GlobalProperty {
-                property: key.clone(),
+                property: Symbol::new(key),
             },
             PendingWrite::ModifyGlobal { key, .. } => ConflictTarget::GlobalProperty {
-                property: key.clone(),
+                property: Symbol::new(key),
             },
             PendingWrite::AddFlag { entity_id, flag } => ConflictTarget::EntityFlag {
                 entity_id: *entity_id,
                 flag: flag.clone(),
             },
-            PendingWrite::RemoveFlag { entity_id, flag } => ConflictTarget::EntityFlag {
+            PendingWrite::RemoveFlag { entity_id, flag }
+            | PendingWrite::AddFlagFor {
+                entity_id, flag, ..
+            } => ConflictTarget::EntityFlag {
                 entity_id: *entity_id,
                 flag: flag.clone(),
             },
@@ -92,6 +106,11 @@ impl ConflictTarget {
                 ConflictTarget::SpawnEntity { kind: kind.clone() }
             }
             PendingWrite::DestroyEntity { id } => ConflictTarget::DestroyEntity { entity_id: *id },
+            PendingWrite::AddTag { entity_id, tag }
+            | PendingWrite::RemoveTag { entity_id, tag } => ConflictTarget::EntityTag {
+                entity_id: *entity_id,
+                tag: tag.clone(),
+            },
         }
     }
 }
@@ -111,6 +130,9 @@ impl std::fmt::Display for ConflictTarget {
             ConflictTarget::DestroyEntity { entity_id } => {
                 write!(f, "destroy entity {}", entity_id)
             }
+            ConflictTarget::EntityTag { entity_id, tag } => {
+                write!(f, "entity {} tag '{}'", entity_id, tag)
+            }
         }
     }
 }
@@ -143,6 +165,50 @@ impl std::fmt::Display for ConflictType {
     }
 }
 
+/// How many of the conflicting writes a [`ConflictReport`] keeps
+///
+/// Write counts per core are kept at every level; resolution always works
+/// on every write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ConflictDetail {
+    /// No writes, only how many each core made
+    CountsOnly,
+    /// At most this many writes per conflict, taken from each core in turn
+    SampledWrites(usize),
+    /// Every conflicting write
+    #[default]
+    Full,
+}
+
+impl ConflictDetail {
+    /// Pick the indices of the writes to keep, in write order
+    fn sample(&self, writes: &[CoreId], cores: &[CoreId]) -> Vec<usize> {
+        let limit = match *self {
+            ConflictDetail::CountsOnly => return Vec::new(),
+            ConflictDetail::SampledWrites(limit) if limit < writes.len() => limit,
+            _ => return (0..writes.len()).collect(),
+        };
+        let by_core: Vec<Vec<usize>> = cores
+            .iter()
+            .map(|core| (0..writes.len()).filter(|&i| writes[i] == *core).collect())
+            .collect();
+        let mut picked = Vec::with_capacity(limit);
+        let mut round = 0;
+        while picked.len() < limit {
+            for indices in &by_core {
+                if let Some(&index) = indices.get(round) {
+                    if picked.len() < limit {
+                        picked.push(index);
+                    }
+                }
+            }
+            round += 1;
+        }
+        picked.sort_unstable();
+        picked
+    }
+}
+
 /// A detected conflict with diagnostic information
 ///
 /// Contains all information needed for conflict resolution:
@@ -150,6 +216,8 @@ impl std::fmt::Display for ConflictType {
 /// - `conflict_type`: The type of conflict (write-write, read-write)
 /// - `cores`: All distinct cores involved (sorted by ID for deterministic output)
 /// - `writes`: All conflicting writes for debugging/resolution
+/// - `write_counts`: How many writes each core made, even when `writes`
+///   holds fewer (see [`ConflictDetail`])
 ///
 /// # Example
 ///
@@ -176,6 +244,9 @@ pub struct Conflict {
     /// All conflicting writes from all cores (for debugging/resolution)
     pub writes: Vec<(CoreId, PendingWrite)>,
 
+    /// Number of conflicting writes from each core, in `cores` order
+    pub write_counts: Vec<(CoreId, usize)>,
+
     /// Reserved for future read-write conflict detection
     ///
     /// When read-write conflict detection is implemented, this field will contain
@@ -211,15 +282,41 @@ impl Conflict {
             cores.len()
         );
 
+        let write_counts = cores
+            .iter()
+            .map(|core| (*core, writes.iter().filter(|(c, _)| c == core).count()))
+            .collect();
         Self {
             target,
             conflict_type,
             cores,
             writes,
+            write_counts,
             reads: Vec::new(),
         }
     }
 
+    /// Get the number of conflicting writes, including those not kept
+    pub fn write_count(&self) -> usize {
+        self.write_counts.iter().map(|(_, count)| count).sum()
+    }
+
+    /// Drop the writes a detail level doesn't keep
+    pub fn reduce(&mut self, detail: ConflictDetail) {
+        let cores: Vec<CoreId> = self.writes.iter().map(|(core, _)| *core).collect();
+        let keep = detail.sample(&cores, &self.cores);
+        if keep.len() == self.writes.len() {
+            return;
+        }
+        let mut keep = keep.into_iter().peekable();
+        let mut index = 0;
+        self.writes.retain(|_| {
+            let kept = keep.next_if_eq(&index).is_some();
+            index += 1;
+            kept
+        });
+    }
+
     /// Get the number of cores involved in this conflict
     pub fn core_count(&self) -> usize {
         self.cores.len()
@@ -293,6 +390,107 @@ impl ConflictReport {
     pub fn iter(&self) -> impl Iterator<Item = &Conflict> {
         self.conflicts.iter()
     }
+
+    /// Drop the writes a detail level doesn't keep from every conflict
+    pub fn reduce(&mut self, detail: ConflictDetail) {
+        for conflict in &mut self.conflicts {
+            conflict.reduce(detail);
+        }
+    }
+
+    /// Render the report as a table grouped by target and core
+    ///
+    /// One row per core of each conflict, with its write count and the
+    /// writes kept, one per line; targets are sorted so dumps diff cleanly.
+    ///
+    /// ```text
+    /// 1 conflict
+    /// TARGET         CORE     WRITES  KEPT
+    /// global 'gold'  Core(0)       1  set 100
+    ///                Core(1)       2  add 5
+    ///                                 add 7
+    /// ```
+    pub fn dump_pretty(&self) -> String {
+        let mut conflicts: Vec<(String, &Conflict)> = self
+            .conflicts
+            .iter()
+            .map(|c| (c.target.to_string(), c))
+            .collect();
+        conflicts.sort_by(|a, b| a.0.cmp(&b.0));
+
+        // (target, core, writes, kept) cells, blank when continuing a row above
+        let mut rows: Vec<[String; 4]> = Vec::new();
+        for (target, conflict) in &conflicts {
+            let mut target = target.clone();
+            for (core, count) in &conflict.write_counts {
+                let mut cells = [
+                    std::mem::take(&mut target),
+                    core.to_string(),
+                    count.to_string(),
+                ];
+                let kept = conflict.writes.iter().filter(|(c, _)| c == core);
+                let mut any = false;
+                for (_, write) in kept {
+                    let [target, core, count] = std::mem::take(&mut cells);
+                    rows.push([target, core, count, describe_write(write)]);
+                    any = true;
+                }
+                if !any {
+                    let [target, core, count] = cells;
+                    rows.push([target, core, count, String::new()]);
+                }
+            }
+        }
+
+        let header = ["TARGET", "CORE", "WRITES", "KEPT"].map(String::from);
+        let mut widths = [0; 3];
+        for row in std::iter::once(&header).chain(&rows) {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let mut out = format!("{}\n", self);
+        if rows.is_empty() {
+            return out;
+        }
+        for [target, core, count, kept] in std::iter::once(header).chain(rows) {
+            let line = format!(
+                "{:<tw$}  {:<cw$}  {:>nw$}  {}",
+                target,
+                core,
+                count,
+                kept,
+                tw = widths[0],
+                cw = widths[1],
+                nw = widths[2],
+            );
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Describe what a write does, for [`ConflictReport::dump_pretty`]
+///
+/// The target column already says what the write is to.
+fn describe_write(write: &PendingWrite) -> String {
+    match write {
+        PendingWrite::SetProperty { value, .. } | PendingWrite::SetGlobal { value, .. } => {
+            format!("set {}", value)
+        }
+        PendingWrite::ModifyProperty { op, value, .. }
+        | PendingWrite::ModifyGlobal { op, value, .. } => {
+            format!("{} {}", format!("{:?}", op).to_lowercase(), value)
+        }
+        PendingWrite::AddFlag { .. } | PendingWrite::AddTag { .. } => "add".to_string(),
+        PendingWrite::RemoveFlag { .. } | PendingWrite::RemoveTag { .. } => "remove".to_string(),
+        PendingWrite::AddFlagFor { expires_at, .. } => format!("add until tick {}", expires_at),
+        PendingWrite::SpawnEntity { properties, .. } => {
+            format!("spawn with {} properties", properties.len())
+        }
+        PendingWrite::DestroyEntity { .. } => "destroy".to_string(),
+    }
 }
 
 /// Detect write-write conflicts across multiple WriteSets from different cores
@@ -317,16 +515,34 @@ impl ConflictReport {
 ///
 /// A `ConflictReport` containing all detected conflicts
 pub fn detect_conflicts(write_sets: &[(CoreId, WriteSet)]) -> ConflictReport {
-    let mut write_map: HashMap<ConflictTarget, Vec<(CoreId, PendingWrite)>> = HashMap::new();
+    detect(write_sets, |_| true, ConflictDetail::Full)
+}
+
+/// Detect conflicts, keeping only the writes a detail level asks for
+///
+/// Writes not kept are never cloned, so [`ConflictDetail::CountsOnly`] costs
+/// little more than [`has_conflicts`](crate::has_conflicts).
+pub fn detect_conflicts_with_detail(
+    write_sets: &[(CoreId, WriteSet)],
+    detail: ConflictDetail,
+) -> ConflictReport {
+    detect(write_sets, |_| true, detail)
+}
+
+/// Collect writes by target and report the targets written by several cores
+fn detect<F>(write_sets: &[(CoreId, WriteSet)], filter: F, detail: ConflictDetail) -> ConflictReport
+where
+    F: Fn(&ConflictTarget) -> bool,
+{
+    let mut write_map: HashMap<ConflictTarget, Vec<(CoreId, &PendingWrite)>> = HashMap::new();
 
     // Phase 1: Collect all writes by target
     for (core_id, ws) in write_sets {
         for write in ws.iter() {
             let target = ConflictTarget::from_pending_write(write);
-            write_map
-                .entry(target)
-                .or_default()
-                .push((*core_id, write.clone()));
+            if filter(&target) {
+                write_map.entry(target).or_default().push((*core_id, write));
+            }
         }
     }
 
@@ -339,7 +555,7 @@ pub fn detect_conflicts(write_sets: &[(CoreId, WriteSet)]) -> ConflictReport {
 
         // Only a conflict if at least two distinct cores wrote to the same target
         if distinct_cores.len() > 1 {
-            let conflict = create_conflict(target, writes, distinct_cores);
+            let conflict = create_conflict(target, &writes, distinct_cores, detail);
             report.conflicts.push(conflict);
         }
     }
@@ -349,13 +565,16 @@ pub fn detect_conflicts(write_sets: &[(CoreId, WriteSet)]) -> ConflictReport {
 
 /// Create a Conflict from a target, its conflicting writes, and the set of distinct cores
 ///
+/// Only the writes `detail` keeps are cloned into the conflict.
+///
 /// # Precondition
 ///
 /// `distinct_cores` must contain at least 2 elements. This is enforced by a debug_assert.
 fn create_conflict(
     target: ConflictTarget,
-    writes: Vec<(CoreId, PendingWrite)>,
+    writes: &[(CoreId, &PendingWrite)],
     distinct_cores: HashSet<CoreId>,
+    detail: ConflictDetail,
 ) -> Conflict {
     debug_assert!(
         distinct_cores.len() >= 2,
@@ -367,8 +586,20 @@ fn create_conflict(
     let mut sorted_cores: Vec<CoreId> = distinct_cores.into_iter().collect();
     sorted_cores.sort_by_key(|c| c.0);
 
-    // Create conflict using the new API
-    Conflict::new(target, ConflictType::WriteWrite, sorted_cores, writes)
+    let write_cores: Vec<CoreId> = writes.iter().map(|(c, _)| *c).collect();
+    let kept = detail
+        .sample(&write_cores, &sorted_cores)
+        .into_iter()
+        .map(|i| (writes[i].0, writes[i].1.clone()))
+        .collect();
+    let write_counts = sorted_cores
+        .iter()
+        .map(|core| (*core, write_cores.iter().filter(|c| *c == core).count()))
+        .collect();
+
+    let mut conflict = Conflict::new(target, ConflictType::WriteWrite, sorted_cores, kept);
+    conflict.write_counts = write_counts;
+    conflict
 }
 
 /// Detect conflicts with an option to exclude certain target types
@@ -380,34 +611,7 @@ pub fn detect_conflicts_filtered<F>(write_sets: &[(CoreId, WriteSet)], filter: F
 where
     F: Fn(&ConflictTarget) -> bool,
 {
-    let mut write_map: HashMap<ConflictTarget, Vec<(CoreId, PendingWrite)>> = HashMap::new();
-
-    for (core_id, ws) in write_sets {
-        for write in ws.iter() {
-            let target = ConflictTarget::from_pending_write(write);
-            if filter(&target) {
-                write_map
-                    .entry(target)
-                    .or_default()
-                    .push((*core_id, write.clone()));
-            }
-        }
-    }
-
-    let mut report = ConflictReport::new();
-
-    for (target, writes) in write_map {
-        // Collect distinct core IDs
-        let distinct_cores: HashSet<CoreId> = writes.iter().map(|(c, _)| *c).collect();
-
-        // Only a conflict if at least two distinct cores wrote to the same target
-        if distinct_cores.len() > 1 {
-            let conflict = create_conflict(target, writes, distinct_cores);
-            report.conflicts.push(conflict);
-        }
-    }
-
-    report
+    detect(write_sets, filter, ConflictDetail::Full)
 }
 
 /// Default filter that excludes spawn conflicts (which are usually acceptable)
@@ -609,8 +813,24 @@ pub fn resolve_conflicts(
     write_sets: &[(CoreId, WriteSet)],
     strategy: &ResolutionStrategy,
 ) -> crate::Result<ResolutionResult> {
-    // Detect all conflicts first
-    let report = detect_conflicts(write_sets);
+    resolve_conflicts_with_detail(write_sets, strategy, ConflictDetail::Full)
+}
+
+/// Resolve conflicts, keeping only the writes a detail level asks for in
+/// the report of an `Abort` error
+///
+/// Other strategies resolve using every write whatever the detail level.
+pub fn resolve_conflicts_with_detail(
+    write_sets: &[(CoreId, WriteSet)],
+    strategy: &ResolutionStrategy,
+    detail: ConflictDetail,
+) -> crate::Result<ResolutionResult> {
+    // Detect all conflicts first; only an abort reports them
+    let detail = match strategy {
+        ResolutionStrategy::Abort => detail,
+        _ => ConflictDetail::Full,
+    };
+    let report = detect_conflicts_with_detail(write_sets, detail);
 
     // If no conflicts, just merge the WriteSets
     if !report.has_conflicts() {
@@ -939,6 +1159,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_entity_tag_write_write_conflict() {
+        let entity_id = EntityId::new(42);
+
+        let mut ws1 = WriteSet::new();
+        ws1.push(PendingWrite::AddTag {
+            entity_id,
+            tag: "boss".to_string(),
+        });
+        // A flag of the same name is a different target
+        ws1.push(PendingWrite::AddFlag {
+            entity_id,
+            flag: DefId::new("boss"),
+        });
+
+        let mut ws2 = WriteSet::new();
+        ws2.push(PendingWrite::RemoveTag {
+            entity_id,
+            tag: "boss".to_string(),
+        });
+
+        let report = detect_conflicts(&[(CoreId(0), ws1), (CoreId(1), ws2)]);
+        assert_eq!(report.len(), 1);
+        assert_eq!(
+            report.conflicts[0].target,
+            ConflictTarget::EntityTag {
+                entity_id,
+                tag: "boss".to_string(),
+            }
+        );
+    }
+
     #[test]
     fn test_destroy_entity_write_write_conflict() {
         let entity_id = EntityId::new(42);
@@ -1065,7 +1317,7 @@ mod tests {
     fn test_conflict_display() {
         let conflict = Conflict::new(
             ConflictTarget::GlobalProperty {
-                property: "gold".to_string(),
+                property: "gold".into(),
             },
             ConflictType::WriteWrite,
             vec![CoreId(0), CoreId(1)],
@@ -2012,13 +2264,13 @@ mod tests {
     #[test]
     fn test_new_api_conflict_target_display() {
         let target = ConflictTarget::GlobalProperty {
-            property: "gold".to_string(),
+            property: "gold".into(),
         };
         assert_eq!(format!("{}", target), "global 'gold'");
 
         let target = ConflictTarget::EntityProperty {
             entity_id: EntityId::new(42),
-            property: "health".to_string(),
+            property: "health".into(),
         };
         assert!(format!("{}", target).contains("entity"));
         assert!(format!("{}", target).contains("health"));
@@ -2040,7 +2292,7 @@ mod tests {
     fn test_new_api_conflict_helper_methods() {
         let conflict = Conflict::new(
             ConflictTarget::GlobalProperty {
-                property: "gold".to_string(),
+                property: "gold".into(),
             },
             ConflictType::WriteWrite,
             vec![CoreId(0), CoreId(1)],
@@ -2080,6 +2332,79 @@ mod tests {
         ));
     }
 
+    /// Core 0 sets gold once, core 1 adds to it three times
+    fn gold_write_sets() -> Vec<(CoreId, WriteSet)> {
+        let mut ws0 = WriteSet::new();
+        ws0.push(PendingWrite::SetGlobal {
+            key: "gold".to_string(),
+            value: Value::Float(100.0),
+        });
+        let mut ws1 = WriteSet::new();
+        for value in [5.0, 7.0, 9.0] {
+            ws1.push(PendingWrite::ModifyGlobal {
+                key: "gold".to_string(),
+                op: ModifyOp::Add,
+                value,
+            });
+        }
+        vec![(CoreId(0), ws0), (CoreId(1), ws1)]
+    }
+
+    #[test]
+    fn test_conflict_detail_levels() {
+        let write_sets = gold_write_sets();
+        let full = detect_conflicts(&write_sets);
+        assert_eq!(full.conflicts[0].writes.len(), 4);
+        assert_eq!(
+            full.conflicts[0].write_counts,
+            [(CoreId(0), 1), (CoreId(1), 3)]
+        );
+
+        let counts = detect_conflicts_with_detail(&write_sets, ConflictDetail::CountsOnly);
+        assert!(counts.conflicts[0].writes.is_empty());
+        assert_eq!(
+            counts.conflicts[0].write_counts,
+            full.conflicts[0].write_counts
+        );
+        assert_eq!(counts.conflicts[0].write_count(), 4);
+
+        // Samples take from each core in turn, keeping write order
+        let sampled = detect_conflicts_with_detail(&write_sets, ConflictDetail::SampledWrites(3));
+        let kept: Vec<_> = sampled.conflicts[0]
+            .writes
+            .iter()
+            .map(|(c, _)| *c)
+            .collect();
+        assert_eq!(kept, [CoreId(0), CoreId(1), CoreId(1)]);
+        assert_eq!(sampled.conflicts[0].write_count(), 4);
+
+        let mut reduced = full.clone();
+        reduced.reduce(ConflictDetail::SampledWrites(3));
+        assert_eq!(reduced, sampled);
+        reduced.reduce(ConflictDetail::SampledWrites(10));
+        assert_eq!(reduced, sampled);
+        reduced.reduce(ConflictDetail::CountsOnly);
+        assert_eq!(reduced, counts);
+    }
+
+    #[test]
+    fn test_dump_pretty() {
+        let report =
+            detect_conflicts_with_detail(&gold_write_sets(), ConflictDetail::SampledWrites(3));
+        assert_eq!(
+            report.dump_pretty(),
+            "1 conflict\n\
+             TARGET         CORE     WRITES  KEPT\n\
+             global 'gold'  Core(0)       1  set 100\n\
+             \x20              Core(1)       3  add 5\n\
+             \x20                               add 7\n"
+        );
+
+        let report = detect_conflicts_with_detail(&gold_write_sets(), ConflictDetail::CountsOnly);
+        assert!(report.dump_pretty().contains("Core(1)       3\n"));
+        assert_eq!(ConflictReport::new().dump_pretty(), "0 conflicts\n");
+    }
+
     #[test]
     fn test_send_sync_for_types() {
         _assert_send_sync::<ConflictTarget>();
@@ -18,10 +18,17 @@
 //! - Current tick (simulation time)
 //!
 //! This ensures reproducible results regardless of execution order.
+//!
+//! # Failure Isolation
+//!
+//! [`Core::try_tick`] catches a panic in the runtime instead of letting it
+//! unwind through the group. The core is then quarantined: the model it was
+//! ticking is discarded and the group skips it until it is released.
 
 use crate::config::hash_seed;
 use pulsive_core::{Model, Rng, Runtime, UpdateResult};
 use serde::{Deserialize, Serialize};
+use std::panic::{self, AssertUnwindSafe};
 
 /// Unique identifier for a core within a group
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -51,6 +58,8 @@ pub struct Core {
     pub model: Model,
     /// Seed for deterministic per-core RNG
     rng_seed: u64,
+    /// Why the core is quarantined (None = healthy)
+    quarantine: Option<String>,
 }
 
 impl Core {
@@ -61,6 +70,7 @@ impl Core {
             runtime,
             model: Model::new(),
             rng_seed: seed,
+            quarantine: None,
         }
     }
 
@@ -92,11 +102,54 @@ impl Core {
         self.model.rng = Rng::new(hash_seed(self.rng_seed, self.id.0 as u64, tick));
     }
 
+    /// Reset this core to a model restored from history
+    ///
+    /// Loads the model and cancels the runtime's pending and scheduled
+    /// messages, which belong to the abandoned ticks.
+    pub fn reset(&mut self, model: Model) {
+        self.runtime.cancel_where(|_| true);
+        self.load_model(model);
+    }
+
     /// Execute one tick - delegates directly to `runtime.tick(&mut model)`
     pub fn tick(&mut self) -> UpdateResult {
         self.runtime.tick(&mut self.model)
     }
 
+    /// Execute one tick, quarantining the core if it panics
+    ///
+    /// Returns the panic message on failure; the local model is then left
+    /// as the panic found it and must not be merged.
+    pub fn try_tick(&mut self) -> Result<UpdateResult, String> {
+        let (runtime, model) = (&mut self.runtime, &mut self.model);
+        panic::catch_unwind(AssertUnwindSafe(|| runtime.tick(model))).map_err(|payload| {
+            let error = match payload.downcast::<String>() {
+                Ok(message) => *message,
+                Err(payload) => match payload.downcast::<&str>() {
+                    Ok(message) => message.to_string(),
+                    Err(_) => "unknown panic".to_string(),
+                },
+            };
+            self.quarantine = Some(error.clone());
+            error
+        })
+    }
+
+    /// Check whether the core is quarantined after panicking
+    pub fn is_quarantined(&self) -> bool {
+        self.quarantine.is_some()
+    }
+
+    /// Get the panic message the core was quarantined with
+    pub fn quarantine_error(&self) -> Option<&str> {
+        self.quarantine.as_deref()
+    }
+
+    /// Return the core to service, returning whether it was quarantined
+    pub fn release(&mut self) -> bool {
+        self.quarantine.take().is_some()
+    }
+
     /// Get the current tick of the local model
     pub fn current_tick(&self) -> u64 {
         self.model.current_tick()
@@ -115,6 +168,7 @@ impl std::fmt::Debug for Core {
         f.debug_struct("Core")
             .field("id", &self.id)
             .field("tick", &self.model.current_tick())
+            .field("quarantined", &self.is_quarantined())
             .finish()
     }
 }
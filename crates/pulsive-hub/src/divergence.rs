@@ -0,0 +1,319 @@
+//! Per-core hashes for pinpointing divergence
+//!
+//! When a run stops matching its replay, or a parallel run stops matching
+//! the sequential one, comparing models only says *that* they diverged.
+//! With hashing enabled ([`Hub::enable_hashing`](crate::Hub::enable_hashing)),
+//! every core's tick is summarized by a [`CoreHash`]:
+//!
+//! - a hash of the WriteSet the core produced, in write order
+//! - a hash of the writes to each target, in first-written order
+//! - a hash of the entities the core wrote to, as it left them
+//!
+//! The hub logs them per tick in a [`HashLog`]. Save the logs of two runs
+//! and [`HashLog::first_divergence`] names the first tick, core and target
+//! where they differ.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! hub.enable_hashing();
+//! run(&mut hub);
+//! let expected: HashLog = load("replay.hashes");
+//! if let Some(divergence) = hub.hash_log().unwrap().first_divergence(&expected) {
+//!     println!("{}", divergence); // "tick 41 Group(0) Core(2): writes to global 'gold' differ"
+//! }
+//! ```
+
+use crate::config::{hash_seed, DEFAULT_GLOBAL_SEED};
+use crate::conflict::ConflictTarget;
+use crate::core::CoreId;
+use crate::group::GroupId;
+use crate::hash::{hash_entity_with_seed, hash_write_with_seed};
+use pulsive_core::{EntityId, IndexMap, Model, WriteSet};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Hashes of what one core did during one tick
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoreHash {
+    /// Group of the core
+    pub group: GroupId,
+    /// The core
+    pub core: CoreId,
+    /// Hash of the core's WriteSet, in write order
+    pub writes: u64,
+    /// Hash of the writes to each target, in first-written order
+    pub targets: Vec<(ConflictTarget, u64)>,
+    /// Hash of the entities written to, as the core left them
+    pub entities: u64,
+}
+
+impl CoreHash {
+    /// Hash a core's writes, and the entities they touched in its model
+    pub fn new(group: GroupId, core: CoreId, writes: &WriteSet, model: &Model) -> Self {
+        let seed = DEFAULT_GLOBAL_SEED;
+        let mut all = hash_seed(seed, writes.len() as u64, 0);
+        let mut targets: IndexMap<ConflictTarget, u64> = IndexMap::new();
+        for (i, write) in writes.iter().enumerate() {
+            let h = hash_write_with_seed(write, seed);
+            all = hash_seed(all, h, i as u64 + 1);
+            let target = targets
+                .entry(ConflictTarget::from_pending_write(write))
+                .or_insert(seed);
+            *target = hash_seed(*target, h, 1);
+        }
+
+        // Entities in ID order, so the hash doesn't depend on write order
+        let mut touched: Vec<EntityId> = targets
+            .keys()
+            .filter_map(|target| match target {
+                ConflictTarget::EntityProperty { entity_id, .. }
+                | ConflictTarget::EntityFlag { entity_id, .. }
+                | ConflictTarget::DestroyEntity { entity_id } => Some(*entity_id),
+                _ => None,
+            })
+            .collect();
+        touched.sort();
+        touched.dedup();
+        let mut entities = hash_seed(seed, touched.len() as u64, 0);
+        for (i, id) in touched.into_iter().enumerate() {
+            // A destroyed entity hashes as its ID alone
+            let h = match model.entities().get(id) {
+                Some(entity) => hash_entity_with_seed(entity, seed),
+                None => id.raw(),
+            };
+            entities = hash_seed(entities, h, i as u64 + 1);
+        }
+
+        Self {
+            group,
+            core,
+            writes: all,
+            targets: targets.into_iter().collect(),
+            entities,
+        }
+    }
+}
+
+/// Where two runs first diverged, found by [`HashLog::first_divergence`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// The tick
+    pub tick: u64,
+    /// Group of the core
+    pub group: GroupId,
+    /// The core
+    pub core: CoreId,
+    /// What differs
+    pub kind: DivergenceKind,
+}
+
+/// What differs between two runs at a [`Divergence`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DivergenceKind {
+    /// Only one run has hashes for the core (it panicked in the other, say)
+    MissingCore,
+    /// The writes to this target differ, or only one run wrote to it; it is
+    /// the first such target in the core's write order
+    Target(ConflictTarget),
+    /// The writes match but the entities written to differ, so the core
+    /// started from different states
+    Entities,
+}
+
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "tick {} {} {}: ", self.tick, self.group, self.core)?;
+        match &self.kind {
+            DivergenceKind::MissingCore => write!(f, "core ran in only one run"),
+            DivergenceKind::Target(target) => write!(f, "writes to {} differ", target),
+            DivergenceKind::Entities => write!(f, "entities written to differ"),
+        }
+    }
+}
+
+/// Per-tick [`CoreHash`]es of a run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HashLog {
+    ticks: BTreeMap<u64, Vec<CoreHash>>,
+}
+
+impl HashLog {
+    /// Create an empty log
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the hashes of the cores that ran a tick, replacing any
+    /// recorded for it
+    pub fn record(&mut self, tick: u64, hashes: Vec<CoreHash>) {
+        self.ticks.insert(tick, hashes);
+    }
+
+    /// Get the hashes recorded for a tick
+    pub fn get(&self, tick: u64) -> Option<&[CoreHash]> {
+        self.ticks.get(&tick).map(Vec::as_slice)
+    }
+
+    /// Get the number of ticks recorded
+    pub fn len(&self) -> usize {
+        self.ticks.len()
+    }
+
+    /// Check if no tick is recorded
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+
+    /// Iterate over the recorded ticks in order
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &[CoreHash])> {
+        self.ticks
+            .iter()
+            .map(|(tick, hashes)| (*tick, hashes.as_slice()))
+    }
+
+    /// Drop the ticks after `tick`
+    pub fn truncate(&mut self, tick: u64) {
+        self.ticks.split_off(&tick.saturating_add(1));
+    }
+
+    /// Find where this run first diverged from another
+    ///
+    /// Compares the ticks both logs recorded, in order, and the cores of
+    /// each tick by group and core ID. Returns None if they all match.
+    pub fn first_divergence(&self, other: &HashLog) -> Option<Divergence> {
+        for (tick, hashes) in &self.ticks {
+            let Some(others) = other.ticks.get(tick) else {
+                continue;
+            };
+            let mut keys: Vec<(GroupId, CoreId)> = hashes
+                .iter()
+                .chain(others)
+                .map(|h| (h.group, h.core))
+                .collect();
+            keys.sort_by_key(|(group, core)| (group.0, core.0));
+            keys.dedup();
+            for (group, core) in keys {
+                let find = |hashes: &[CoreHash]| {
+                    hashes
+                        .iter()
+                        .find(|h| h.group == group && h.core == core)
+                        .cloned()
+                };
+                let kind = match (find(hashes), find(others)) {
+                    (Some(a), Some(b)) => match diverged_target(&a, &b) {
+                        Some(target) => DivergenceKind::Target(target),
+                        None if a.entities != b.entities => DivergenceKind::Entities,
+                        None => continue,
+                    },
+                    _ => DivergenceKind::MissingCore,
+                };
+                return Some(Divergence {
+                    tick: *tick,
+                    group,
+                    core,
+                    kind,
+                });
+            }
+        }
+        None
+    }
+}
+
+/// Find the first target, in write order, whose writes differ
+fn diverged_target(a: &CoreHash, b: &CoreHash) -> Option<ConflictTarget> {
+    if a.writes == b.writes {
+        return None;
+    }
+    let b_targets: IndexMap<&ConflictTarget, u64> =
+        b.targets.iter().map(|(target, h)| (target, *h)).collect();
+    for (target, h) in &a.targets {
+        if b_targets.get(target) != Some(h) {
+            return Some(target.clone());
+        }
+    }
+    // Every target of `a` matches: `b` wrote to more, or in another order
+    let a_targets: IndexMap<&ConflictTarget, u64> =
+        a.targets.iter().map(|(target, h)| (target, *h)).collect();
+    b.targets
+        .iter()
+        .find(|(target, _)| !a_targets.contains_key(target))
+        .or(b.targets.first())
+        .map(|(target, _)| target.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::{ModifyOp, PendingWrite, Value};
+
+    fn hash(core: usize, writes: &[PendingWrite], model: &Model) -> CoreHash {
+        let mut write_set = WriteSet::new();
+        for write in writes {
+            write_set.push(write.clone());
+        }
+        CoreHash::new(GroupId(0), CoreId(core), &write_set, model)
+    }
+
+    #[test]
+    fn test_first_divergence() {
+        let mut model = Model::new();
+        let unit = model.entities_mut().create("unit").id;
+        let gold = |value: f64| PendingWrite::SetGlobal {
+            key: "gold".to_string(),
+            value: Value::Float(value),
+        };
+        let heal = PendingWrite::ModifyProperty {
+            entity_id: unit,
+            key: "hp".to_string(),
+            op: ModifyOp::Add,
+            value: 1.0,
+        };
+
+        let mut run = HashLog::new();
+        run.record(1, vec![hash(0, &[gold(1.0)], &model)]);
+        run.record(
+            2,
+            vec![
+                hash(0, &[heal.clone(), gold(2.0)], &model),
+                hash(1, &[], &model),
+            ],
+        );
+        let mut replay = run.clone();
+        assert_eq!(run.first_divergence(&replay), None);
+
+        replay.record(
+            2,
+            vec![
+                hash(0, &[heal.clone(), gold(3.0)], &model),
+                hash(1, &[], &model),
+            ],
+        );
+        let divergence = run.first_divergence(&replay).unwrap();
+        assert_eq!((divergence.tick, divergence.core), (2, CoreId(0)));
+        assert_eq!(
+            divergence.to_string(),
+            "tick 2 Group(0) Core(0): writes to global 'gold' differ"
+        );
+
+        // Same writes, different starting state
+        model.entities_mut().get_mut(unit).unwrap().set("hp", 5.0);
+        replay.record(
+            2,
+            vec![hash(0, &[heal, gold(2.0)], &model), hash(1, &[], &model)],
+        );
+        let divergence = run.first_divergence(&replay).unwrap();
+        assert_eq!(divergence.kind, DivergenceKind::Entities);
+
+        replay.record(1, Vec::new());
+        let divergence = run.first_divergence(&replay).unwrap();
+        assert_eq!(
+            (divergence.tick, divergence.kind),
+            (1, DivergenceKind::MissingCore)
+        );
+
+        replay.truncate(0);
+        assert!(replay.is_empty());
+        assert_eq!(run.first_divergence(&replay), None);
+    }
+}
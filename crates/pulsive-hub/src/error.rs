@@ -38,6 +38,41 @@ pub enum Error {
         report: Box<ConflictReport>,
     },
 
+    /// Writes that could not be applied by [`apply_strict`](crate::apply_strict)
+    #[error("failed to apply writes: {0}")]
+    WriteFailed(Box<pulsive_core::WriteApplyReport>),
+
+    /// Writes collected from a partition to state it does not own
+    ///
+    /// Returned by [`PartitionSnapshot::check_writes`](crate::PartitionSnapshot::check_writes).
+    #[error("{core} wrote outside its partition: {}", Self::format_violations(.violations))]
+    CrossPartitionWrites {
+        /// Core the partition is assigned to
+        core: crate::CoreId,
+        /// The writes made outside the partition
+        violations: Vec<crate::PartitionViolation>,
+    },
+
+    /// Rollback requested on a hub without history
+    ///
+    /// Enable it with [`Hub::enable_history`](crate::Hub::enable_history).
+    #[error("history is not enabled")]
+    HistoryDisabled,
+
+    /// Rollback requested to a tick with no checkpoint in the history
+    #[error("no checkpoint for tick {tick} (current tick {current})")]
+    NoCheckpoint {
+        /// Tick requested
+        tick: u64,
+        /// Current tick of the hub
+        current: u64,
+    },
+
+    /// A remote core could not be reached or sent an invalid message
+    #[cfg(feature = "remote")]
+    #[error("remote core error: {0}")]
+    Remote(String),
+
     /// Core error
     #[error("core error: {0}")]
     Core(#[from] pulsive_core::Error),
@@ -60,6 +95,12 @@ impl Error {
         }
     }
 
+    /// Format partition violations as a list
+    fn format_violations(violations: &[crate::PartitionViolation]) -> String {
+        let violations: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+        violations.join(", ")
+    }
+
     /// Format conflict count with proper pluralization
     fn format_conflict_count(count: usize) -> String {
         if count == 1 {
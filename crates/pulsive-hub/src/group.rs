@@ -3,7 +3,9 @@
 //! Hub only interacts with CoreGroup, never with individual Cores.
 //! This allows different execution strategies to be implemented.
 
-use pulsive_core::{Model, UpdateResult};
+use crate::core::CoreId;
+use crate::divergence::CoreHash;
+use pulsive_core::{Model, UpdateResult, WriteSet};
 use serde::{Deserialize, Serialize};
 
 /// Unique identifier for a core group
@@ -16,6 +18,29 @@ impl std::fmt::Display for GroupId {
     }
 }
 
+/// A core quarantined after panicking during a tick
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreFailure {
+    /// Group of the core
+    pub group: GroupId,
+    /// The core that panicked
+    pub core: CoreId,
+    /// Tick the core was executing
+    pub tick: u64,
+    /// The panic message
+    pub error: String,
+}
+
+impl std::fmt::Display for CoreFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} panicked at tick {}: {}",
+            self.group, self.core, self.tick, self.error
+        )
+    }
+}
+
 /// Trait for groups of cores with different execution strategies
 ///
 /// Hub interacts only with this trait, never with individual Cores.
@@ -45,15 +70,58 @@ pub trait CoreGroup: Send {
     /// - Parallel with barrier sync
     /// - etc.
     ///
-    /// Returns combined results from all cores.
+    /// Returns combined results from all healthy cores. A core that panics
+    /// is quarantined rather than taking the group down; its failure is
+    /// reported by [`CoreGroup::take_failures`].
     fn execute_tick(&mut self) -> Vec<UpdateResult>;
 
-    /// Extract the modified models from all cores
+    /// Extract the modified models from all healthy cores
     ///
     /// After execute_tick, call this to get the mutated models
     /// which can be diffed against the original to produce WriteSets.
+    /// Quarantined cores are left out, discarding their changes.
     fn extract_models(&self) -> Vec<&Model>;
 
+    /// Take the failures of the cores quarantined since the last call
+    fn take_failures(&mut self) -> Vec<CoreFailure>;
+
+    /// Get the quarantined cores
+    fn quarantined(&self) -> Vec<CoreId>;
+
+    /// Return a quarantined core to service, returning whether it was
+    /// quarantined
+    fn release(&mut self, core: CoreId) -> bool;
+
     /// Advance the tick counter for this group
     fn advance_tick(&mut self);
+
+    /// Reset all cores to a model restored from history, `ticks` ticks
+    /// before the group's current tick
+    ///
+    /// Called by [`Hub::rollback_to`](crate::Hub::rollback_to). Any state a
+    /// core kept from the abandoned ticks must be dropped so the ticks run
+    /// again exactly as if they were run for the first time.
+    fn rewind(&mut self, model: &Model, ticks: u64);
+
+    /// Record the writes cores make, for [`CoreGroup::take_writes`]
+    ///
+    /// Enabled by the hub once it has several groups, which may run on the
+    /// same tick.
+    fn set_write_logging(&mut self, enabled: bool);
+
+    /// Take the writes behind the first model of
+    /// [`CoreGroup::extract_models`], made during the last tick
+    ///
+    /// Empty unless write logging is enabled.
+    fn take_writes(&mut self) -> WriteSet;
+
+    /// Hash what each core does every tick, for [`CoreGroup::take_hashes`]
+    ///
+    /// Enabled by [`Hub::enable_hashing`](crate::Hub::enable_hashing).
+    fn set_hashing(&mut self, enabled: bool);
+
+    /// Take the hashes of the healthy cores that ran the last tick
+    ///
+    /// Empty unless hashing is enabled.
+    fn take_hashes(&mut self) -> Vec<CoreHash>;
 }
@@ -37,7 +37,7 @@
 //! ```
 
 use crate::config::hash_seed;
-use pulsive_core::Value;
+use pulsive_core::{Entity, PendingWrite, Value, ValueMap};
 
 // Type discriminators for Value hashing.
 // Using hash_seed with different "slot" values ensures type-specific mixing.
@@ -50,6 +50,10 @@ const TYPE_ENTITY_REF: u64 = 5;
 const TYPE_LIST: u64 = 6;
 const TYPE_MAP: u64 = 7;
 
+// Discriminators for entities and writes, past the Value ones
+const TYPE_ENTITY: u64 = 16;
+const TYPE_WRITE: u64 = 17;
+
 /// Hash a u64 value with a seed
 ///
 /// Uses the hub's [`hash_seed`] mixing function to combine the seed
@@ -192,20 +196,99 @@ pub fn hash_value_with_seed(value: &Value, seed: u64) -> u64 {
             }
             h
         }
-        Value::Map(map) => {
-            // Sort keys to ensure hash is order-independent
-            let mut h = hash_seed(seed, TYPE_MAP, 0);
-            let mut keys: Vec<_> = map.keys().collect();
-            keys.sort();
-            for (i, k) in keys.into_iter().enumerate() {
-                let v = map.get(k).unwrap();
-                let key_hash = hash_bytes_with_seed(k.as_bytes(), h);
-                let val_hash = hash_value_with_seed(v, h);
-                h = hash_seed(h, key_hash, i as u64 * 2 + 1);
-                h = hash_seed(h, val_hash, i as u64 * 2 + 2);
-            }
-            h
+        Value::Map(map) => hash_map_with_seed(map, seed),
+    }
+}
+
+/// Hash a [`ValueMap`] with a seed
+///
+/// Same as hashing it as a [`Value::Map`]: keys are sorted, so the hash is
+/// independent of insertion order.
+pub fn hash_map_with_seed(map: &ValueMap, seed: u64) -> u64 {
+    // Sort keys by name to ensure hash is order-independent (symbol
+    // indices depend on interning order, so differ between processes)
+    let mut h = hash_seed(seed, TYPE_MAP, 0);
+    let mut keys: Vec<_> = map.keys().collect();
+    keys.sort_by_key(|k| k.as_str());
+    for (i, k) in keys.into_iter().enumerate() {
+        let v = map.get(k).unwrap();
+        let key_hash = hash_bytes_with_seed(k.as_bytes(), h);
+        let val_hash = hash_value_with_seed(v, h);
+        h = hash_seed(h, key_hash, i as u64 * 2 + 1);
+        h = hash_seed(h, val_hash, i as u64 * 2 + 2);
+    }
+    h
+}
+
+/// Hash an [`Entity`] with a seed
+///
+/// Covers the ID, kind, properties, flags, flag expiries and tags, so two entities
+/// hash the same exactly when they are in the same state.
+pub fn hash_entity_with_seed(entity: &Entity, seed: u64) -> u64 {
+    let mut h = hash_seed(seed, TYPE_ENTITY, entity.id.raw());
+    h = hash_seed(
+        h,
+        hash_bytes_with_seed(entity.kind.as_str().as_bytes(), h),
+        1,
+    );
+    h = hash_seed(h, hash_map_with_seed(&entity.properties, h), 2);
+    // Flags iterate sorted by ID; expiries in insertion order, so sort them
+    for flag in &entity.flags {
+        h = hash_seed(h, hash_bytes_with_seed(flag.as_str().as_bytes(), h), 3);
+    }
+    let mut expiries: Vec<_> = entity.flag_expiry.iter().collect();
+    expiries.sort();
+    for (flag, tick) in expiries {
+        h = hash_seed(h, hash_bytes_with_seed(flag.as_str().as_bytes(), h), *tick);
+    }
+    for tag in &entity.tags {
+        h = hash_seed(h, hash_bytes_with_seed(tag.as_bytes(), h), 4);
+    }
+    h
+}
+
+/// Hash a [`PendingWrite`] with a seed
+///
+/// Two writes hash the same exactly when they are equal.
+pub fn hash_write_with_seed(write: &PendingWrite, seed: u64) -> u64 {
+    let key = |h: u64, key: &str| hash_seed(h, hash_bytes_with_seed(key.as_bytes(), h), 1);
+    let value = |h: u64, value: &Value| hash_seed(h, hash_value_with_seed(value, h), 2);
+    let number = |h: u64, op: &pulsive_core::ModifyOp, n: f64| {
+        hash_seed(hash_seed(h, op.clone() as u64, 2), n.to_bits(), 3)
+    };
+    let h = |variant: u64, entity: u64| hash_seed(hash_seed(seed, TYPE_WRITE, variant), entity, 0);
+    match write {
+        PendingWrite::SetProperty {
+            entity_id,
+            key: k,
+            value: v,
+        } => value(key(h(0, entity_id.raw()), k), v),
+        PendingWrite::ModifyProperty {
+            entity_id,
+            key: k,
+            op,
+            value: n,
+        } => number(key(h(1, entity_id.raw()), k), op, *n),
+        PendingWrite::SetGlobal { key: k, value: v } => value(key(h(2, 0), k), v),
+        PendingWrite::ModifyGlobal {
+            key: k,
+            op,
+            value: n,
+        } => number(key(h(3, 0), k), op, *n),
+        PendingWrite::AddFlag { entity_id, flag } => key(h(4, entity_id.raw()), flag.as_str()),
+        PendingWrite::RemoveFlag { entity_id, flag } => key(h(5, entity_id.raw()), flag.as_str()),
+        PendingWrite::SpawnEntity { kind, properties } => {
+            let h = key(h(6, 0), kind.as_str());
+            hash_seed(h, hash_map_with_seed(properties, h), 2)
         }
+        PendingWrite::DestroyEntity { id } => h(7, id.raw()),
+        PendingWrite::AddFlagFor {
+            entity_id,
+            flag,
+            expires_at,
+        } => hash_seed(key(h(8, entity_id.raw()), flag.as_str()), *expires_at, 2),
+        PendingWrite::AddTag { entity_id, tag } => key(h(9, entity_id.raw()), tag),
+        PendingWrite::RemoveTag { entity_id, tag } => key(h(10, entity_id.raw()), tag),
     }
 }
 
@@ -310,10 +393,10 @@ mod tests {
 
     #[test]
     fn test_hash_value_all_types() {
-        use pulsive_core::{EntityId, IndexMap};
+        use pulsive_core::{EntityId, ValueMap};
 
         // Test all value types are hashable and deterministic
-        let mut map = IndexMap::new();
+        let mut map = ValueMap::new();
         map.insert("a".to_string(), Value::Int(1));
         map.insert("b".to_string(), Value::Int(2));
 
@@ -342,9 +425,9 @@ mod tests {
 
     #[test]
     fn test_hash_value_nested() {
-        use pulsive_core::IndexMap;
+        use pulsive_core::ValueMap;
 
-        let mut map = IndexMap::new();
+        let mut map = ValueMap::new();
         map.insert("x".to_string(), Value::Float(1.0));
         map.insert("y".to_string(), Value::Float(2.0));
 
@@ -357,15 +440,15 @@ mod tests {
 
     #[test]
     fn test_hash_value_map_order_independent() {
-        use pulsive_core::IndexMap;
+        use pulsive_core::ValueMap;
 
         // Build two maps with the same key-value pairs but different insertion order
-        let mut map1 = IndexMap::new();
+        let mut map1 = ValueMap::new();
         map1.insert("a".to_string(), Value::Int(1));
         map1.insert("b".to_string(), Value::Int(2));
         map1.insert("c".to_string(), Value::Int(3));
 
-        let mut map2 = IndexMap::new();
+        let mut map2 = ValueMap::new();
         map2.insert("c".to_string(), Value::Int(3));
         map2.insert("a".to_string(), Value::Int(1));
         map2.insert("b".to_string(), Value::Int(2));
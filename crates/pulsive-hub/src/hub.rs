@@ -9,12 +9,19 @@
 //! The `core_count` setting controls how many worker cores will be used
 //! when parallel execution is implemented. Currently stored for future use.
 
+use crate::bus::{BusEvent, EventBus, SubscriptionId};
+use crate::commit::{apply_batch, commit_batch_with_detail, CommitResult};
 use crate::config::{max_cores, HubConfig};
+use crate::conflict::ResolutionStrategy;
+use crate::core::CoreId;
+use crate::divergence::HashLog;
 use crate::error::{Error, Result};
-use crate::group::{CoreGroup, GroupId};
+use crate::group::{CoreFailure, CoreGroup, GroupId};
+use crate::partition::{PartitionResult, PartitionStrategy};
 use crate::snapshot::ModelSnapshot;
 use crate::tick_sync::TickSyncGroup;
-use pulsive_core::{Model, UpdateResult};
+use pulsive_core::{Model, StateHistory, UpdateResult, WriteSet};
+use pulsive_rollback_buffer::RollbackBuffer;
 
 /// Result of a hub tick
 #[derive(Debug, Clone)]
@@ -23,6 +30,8 @@ pub struct TickResult {
     pub tick: u64,
     /// Combined update results from all groups
     pub updates: Vec<UpdateResult>,
+    /// Cores that panicked during the tick and were quarantined
+    pub failures: Vec<CoreFailure>,
 }
 
 /// Central coordinator that owns the global model and manages CoreGroups
@@ -33,7 +42,8 @@ pub struct TickResult {
 /// - Merge changes from groups back to global model
 /// - Configure thread/core count for parallel execution
 /// - (Future) Handle journal integration
-/// - (Future) Handle rollback requests
+/// - Checkpoint ticks and roll back to them (see [`Hub::enable_history`])
+/// - Hash what each core does to pinpoint divergence (see [`Hub::enable_hashing`])
 ///
 /// ## Thread Configuration
 ///
@@ -61,10 +71,22 @@ pub struct Hub {
     model: Model,
     /// Core groups (Hub owns these, never individual cores)
     groups: Vec<Box<dyn CoreGroup>>,
+    /// Tick divisor of each group, in `groups` order
+    divisors: Vec<u64>,
     /// Version counter for MVCC
     version: u64,
     /// Runtime configuration including thread count
     config: HubConfig,
+    /// How entities are assigned to cores in parallel execution
+    partition: PartitionStrategy,
+    /// How conflicting writes from different cores are resolved
+    resolution: ResolutionStrategy,
+    /// Subscribers to what ticks produce
+    bus: EventBus,
+    /// Checkpoints of the global model, one per tick (None = disabled)
+    history: Option<RollbackBuffer>,
+    /// Per-core hashes of every tick (None = disabled)
+    hashes: Option<HashLog>,
 }
 
 impl Hub {
@@ -75,8 +97,14 @@ impl Hub {
         Self {
             model: Model::new(),
             groups: Vec::new(),
+            divisors: Vec::new(),
             version: 0,
             config: HubConfig::default(),
+            partition: PartitionStrategy::by_id(),
+            resolution: ResolutionStrategy::default(),
+            bus: EventBus::new(),
+            history: None,
+            hashes: None,
         }
     }
 
@@ -87,8 +115,14 @@ impl Hub {
         Self {
             model,
             groups: Vec::new(),
+            divisors: Vec::new(),
             version: 0,
             config: HubConfig::default(),
+            partition: PartitionStrategy::by_id(),
+            resolution: ResolutionStrategy::default(),
+            bus: EventBus::new(),
+            history: None,
+            hashes: None,
         }
     }
 
@@ -102,7 +136,13 @@ impl Hub {
         Self {
             model,
             groups: Vec::new(),
+            divisors: Vec::new(),
             version: 0,
+            partition: PartitionStrategy::by_id_from_config(&config),
+            resolution: ResolutionStrategy::default(),
+            bus: EventBus::new(),
+            history: None,
+            hashes: None,
             config,
         }
     }
@@ -133,9 +173,60 @@ impl Hub {
         hub
     }
 
-    /// Add a core group to the hub
+    /// Add a core group to the hub, running every tick
     pub fn add_group(&mut self, group: impl CoreGroup + 'static) {
-        self.groups.push(Box::new(group));
+        self.add_group_every(group, 1);
+    }
+
+    /// Add a core group running every `divisor` ticks
+    ///
+    /// The group runs on the master ticks that are multiples of `divisor`
+    /// (a divisor of 0 is taken as 1): an AI group every 5 ticks and an
+    /// economy group every 30, say. The schedule only depends on the tick
+    /// number, so it replays identically. Each run is one tick for the
+    /// group's cores, so handlers that accumulate should scale by the
+    /// divisor.
+    ///
+    /// Groups running on the same tick all start from the model as it was
+    /// before the tick. The first of them, in the order they were added,
+    /// provides the new model; the writes of the others are applied on top
+    /// of it in the same order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use pulsive_hub::{CoreGroup, GroupId, Hub, TickSyncGroup};
+    ///
+    /// let mut hub = Hub::new();
+    /// hub.add_group(TickSyncGroup::single(GroupId(0), 1));
+    /// hub.add_group_every(TickSyncGroup::single(GroupId(1), 2), 5);
+    /// for _ in 0..10 {
+    ///     hub.tick().unwrap();
+    /// }
+    /// assert_eq!(hub.current_tick(), 10);
+    /// assert_eq!(hub.group_divisor(GroupId(1)), Some(5));
+    /// ```
+    pub fn add_group_every(&mut self, group: impl CoreGroup + 'static, divisor: u64) {
+        let mut group = Box::new(group);
+        if self.hashes.is_some() {
+            group.set_hashing(true);
+        }
+        self.groups.push(group);
+        self.divisors.push(divisor.max(1));
+        // Groups may now run on the same tick, merged by their writes
+        if self.groups.len() > 1 {
+            for group in &mut self.groups {
+                group.set_write_logging(true);
+            }
+        }
+    }
+
+    /// Get the tick divisor of a group
+    pub fn group_divisor(&self, id: GroupId) -> Option<u64> {
+        self.groups
+            .iter()
+            .position(|g| g.id() == id)
+            .map(|index| self.divisors[index])
     }
 
     /// Get the number of groups
@@ -231,6 +322,64 @@ impl Hub {
         &mut self.config
     }
 
+    /// Get the partition strategy
+    ///
+    /// Defaults to [`PartitionStrategy::by_id`] with the config's seed.
+    pub fn partition_strategy(&self) -> &PartitionStrategy {
+        &self.partition
+    }
+
+    /// Set how entities are assigned to cores in parallel execution
+    ///
+    /// Stored for the parallel execution path, like `core_count`; it can be
+    /// changed between ticks.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use pulsive_hub::{Hub, PartitionKind, PartitionStrategy};
+    ///
+    /// let mut hub = Hub::new();
+    /// hub.set_partition_strategy(PartitionStrategy::by_owner("owner"));
+    /// assert!(matches!(
+    ///     hub.partition_strategy().kind(),
+    ///     PartitionKind::ByOwner { .. }
+    /// ));
+    /// ```
+    pub fn set_partition_strategy(&mut self, strategy: PartitionStrategy) {
+        self.partition = strategy;
+    }
+
+    /// Get the conflict resolution strategy
+    ///
+    /// Defaults to [`ResolutionStrategy::Abort`].
+    pub fn resolution_strategy(&self) -> &ResolutionStrategy {
+        &self.resolution
+    }
+
+    /// Set how conflicting writes from different cores are resolved
+    ///
+    /// Used by [`Hub::commit_batch`] and stored for the parallel execution
+    /// path, like `core_count`; it can be changed between ticks.
+    pub fn set_resolution_strategy(&mut self, strategy: ResolutionStrategy) {
+        self.resolution = strategy;
+    }
+
+    /// Commit WriteSets from several cores to the global model
+    ///
+    /// Conflicts are resolved with the hub's resolution strategy; the report
+    /// of an `Abort` error keeps the writes the config's
+    /// [`conflict_detail`](HubConfig::conflict_detail) asks for.
+    pub fn commit_batch(&mut self, write_sets: Vec<(CoreId, WriteSet)>) -> Result<CommitResult> {
+        commit_batch_with_detail(
+            write_sets,
+            &mut self.model,
+            &mut self.version,
+            &self.resolution,
+            self.config.conflict_detail(),
+        )
+    }
+
     /// Get the global seed
     ///
     /// Returns the master seed used for deriving per-core RNG seeds.
@@ -304,6 +453,208 @@ impl Hub {
         self.config.create_core_rng(core_id, tick)
     }
 
+    // ========================================================================
+    // Event Bus
+    // ========================================================================
+
+    /// Subscribe to what ticks produce
+    ///
+    /// After each tick, the callback runs for every emitted event,
+    /// notification and log from all cores whose topic matches `pattern`
+    /// (see [`crate::bus`] for topics, patterns and ordering).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use pulsive_hub::{BusEvent, Hub, HubConfig};
+    /// use pulsive_core::Model;
+    ///
+    /// let mut hub = Hub::with_default_group(Model::new(), HubConfig::default());
+    /// let id = hub.subscribe("event:*", |event: &BusEvent| println!("{}", event.topic()));
+    /// hub.tick().unwrap();
+    /// assert!(hub.unsubscribe(id));
+    /// ```
+    pub fn subscribe(
+        &mut self,
+        pattern: impl Into<String>,
+        callback: impl FnMut(&BusEvent) + Send + 'static,
+    ) -> SubscriptionId {
+        self.bus.subscribe(pattern, callback)
+    }
+
+    /// Remove a subscription, returning whether it existed
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        self.bus.unsubscribe(id)
+    }
+
+    // ========================================================================
+    // History and Rollback
+    // ========================================================================
+
+    /// Keep a checkpoint of the global model after every tick
+    ///
+    /// The history holds the last `capacity` ticks, starting with the
+    /// current one; it replaces any history kept so far.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is 0.
+    pub fn enable_history(&mut self, capacity: usize) {
+        let mut history = RollbackBuffer::new(capacity);
+        history.save_state(self.model.current_tick(), &self.model);
+        self.history = Some(history);
+    }
+
+    /// Stop keeping checkpoints, dropping the history
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    /// Get the checkpoint history, if enabled
+    pub fn history(&self) -> Option<&RollbackBuffer> {
+        self.history.as_ref()
+    }
+
+    /// Roll the global model back to the checkpoint of an earlier tick
+    ///
+    /// Every group is reset to the restored model (see
+    /// [`CoreGroup::rewind`]), dropping what its cores kept from the
+    /// abandoned ticks, including pending messages: send inputs for the
+    /// ticks to run again after rolling back. The version advances, so
+    /// snapshots taken before the rollback are stale.
+    ///
+    /// Returns the restored entities partitioned across the cores with the
+    /// hub's [`PartitionStrategy`], since the entities that existed at the
+    /// checkpoint may differ from the current ones.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use pulsive_hub::{Hub, HubConfig};
+    /// use pulsive_core::Model;
+    ///
+    /// let mut hub = Hub::with_default_group(Model::new(), HubConfig::default());
+    /// hub.enable_history(64);
+    /// hub.tick().unwrap();
+    /// hub.tick().unwrap();
+    ///
+    /// hub.rollback_to(1).unwrap();
+    /// assert_eq!(hub.current_tick(), 1);
+    /// ```
+    pub fn rollback_to(&mut self, tick: u64) -> Result<PartitionResult> {
+        let current = self.model.current_tick();
+        let history = self.history.as_mut().ok_or(Error::HistoryDisabled)?;
+        // Checkpoints past the current tick belong to an abandoned timeline
+        let model = match history.get_state(tick) {
+            Some(model) if tick <= current => model.clone(),
+            _ => return Err(Error::NoCheckpoint { tick, current }),
+        };
+
+        for (group, divisor) in self.groups.iter_mut().zip(&self.divisors) {
+            // Number of abandoned ticks the group ran on
+            group.rewind(&model, current / divisor - tick / divisor);
+        }
+        self.model = model;
+        self.version += 1;
+        if let Some(hashes) = &mut self.hashes {
+            hashes.truncate(tick);
+        }
+        Ok(self.partitions())
+    }
+
+    /// Partition the global model's entities across the configured cores
+    ///
+    /// The configured cores belong to no group, so quarantined cores are
+    /// not taken into account; see [`Hub::group_partitions`] for that.
+    pub fn partitions(&self) -> PartitionResult {
+        self.partition
+            .partition(self.model.entities(), self.config.core_count())
+    }
+
+    /// Partition the global model's entities across the cores of a group
+    ///
+    /// Entities of the group's quarantined cores are re-partitioned to its
+    /// healthy ones (see [`PartitionResult::evacuate`]). Cores quarantined
+    /// in other groups are not affected.
+    pub fn group_partitions(&self, group: GroupId) -> Result<PartitionResult> {
+        let group = self
+            .groups
+            .iter()
+            .find(|g| g.id() == group)
+            .ok_or(Error::GroupNotFound(group))?;
+        let mut partitions = self
+            .partition
+            .partition(self.model.entities(), group.core_count());
+        partitions.evacuate(&group.quarantined());
+        Ok(partitions)
+    }
+
+    // ========================================================================
+    // Divergence Hashing
+    // ========================================================================
+
+    /// Hash what each core does every tick, starting with the next tick
+    ///
+    /// Every core that runs a tick is summarized by a
+    /// [`CoreHash`](crate::CoreHash) of its writes and of the entities they
+    /// touched, logged per tick. Compare the logs of two runs with
+    /// [`HashLog::first_divergence`] to find the first tick, core and target
+    /// where they differ. Replaces any log kept so far; a rollback drops the
+    /// ticks it abandons.
+    pub fn enable_hashing(&mut self) {
+        for group in &mut self.groups {
+            group.set_hashing(true);
+        }
+        self.hashes = Some(HashLog::new());
+    }
+
+    /// Stop hashing, dropping the log
+    pub fn disable_hashing(&mut self) {
+        for group in &mut self.groups {
+            group.set_hashing(false);
+        }
+        self.hashes = None;
+    }
+
+    /// Get the per-core hashes logged so far, if enabled
+    pub fn hash_log(&self) -> Option<&HashLog> {
+        self.hashes.as_ref()
+    }
+
+    // ========================================================================
+    // Failure Isolation
+    // ========================================================================
+
+    /// Get the cores quarantined after panicking, with their group
+    ///
+    /// A core that panics during a tick is quarantined instead of crashing
+    /// the host: its changes are discarded, it is skipped by later ticks,
+    /// the failure is reported in [`TickResult::failures`] and a
+    /// [`CORE_FAILED`](crate::bus::CORE_FAILED) event is published.
+    pub fn quarantined_cores(&self) -> Vec<(GroupId, CoreId)> {
+        self.groups
+            .iter()
+            .flat_map(|group| {
+                group
+                    .quarantined()
+                    .into_iter()
+                    .map(|core| (group.id(), core))
+            })
+            .collect()
+    }
+
+    /// Return a quarantined core to service
+    ///
+    /// Returns whether the core was quarantined.
+    pub fn release_core(&mut self, group: GroupId, core: CoreId) -> Result<bool> {
+        let group = self
+            .groups
+            .iter_mut()
+            .find(|g| g.id() == group)
+            .ok_or(Error::GroupNotFound(group))?;
+        Ok(group.release(core))
+    }
+
     // ========================================================================
     // Snapshot and Tick
     // ========================================================================
@@ -316,10 +667,16 @@ impl Hub {
     /// Execute one tick across all groups
     ///
     /// Flow:
-    /// 1. Load current model into each group's cores
-    /// 2. Execute tick on all groups
-    /// 3. Merge results back to global model
-    /// 4. Advance version
+    /// 1. Load current model into the cores of each group due this tick
+    ///    (see [`Hub::add_group_every`])
+    /// 2. Execute tick on those groups
+    /// 3. Merge results back to global model (or just advance its tick if
+    ///    no group is due)
+    /// 4. Advance version and checkpoint the model (see [`Hub::enable_history`])
+    /// 5. Publish the results to subscribers (see [`Hub::subscribe`])
+    ///
+    /// A core that panics is quarantined rather than failing the tick (see
+    /// [`Hub::quarantined_cores`]).
     ///
     /// # Execution Mode
     ///
@@ -365,32 +722,62 @@ impl Hub {
     /// No thread pool, no parallel infrastructure.
     fn tick_sequential(&mut self) -> Result<TickResult> {
         let mut all_updates = Vec::new();
+        let mut failures = Vec::new();
+        let tick = self.model.current_tick() + 1;
+        let mut merged: Option<Model> = None;
+        let mut writes = Vec::new();
+        let mut tick_hashes = Vec::new();
+
+        for (group, divisor) in self.groups.iter_mut().zip(&self.divisors) {
+            if !tick.is_multiple_of(*divisor) {
+                continue;
+            }
 
-        for group in &mut self.groups {
             // Load current model into group's cores
             group.load_model(&self.model);
 
             // Execute tick (group handles its cores)
             let updates = group.execute_tick();
             all_updates.extend(updates);
+            failures.extend(group.take_failures());
+            tick_hashes.extend(group.take_hashes());
 
-            // Extract the modified model from the group
+            // Extract the modified model from the first group, and the
+            // writes of the others
             // TODO: Implement proper MVCC merge when multiple cores produce WriteSets
-            let models = group.extract_models();
-            if let Some(modified_model) = models.first() {
-                self.model = (*modified_model).clone();
+            let group_writes = group.take_writes();
+            match (&merged, group.extract_models().first()) {
+                (None, Some(modified_model)) => merged = Some((*modified_model).clone()),
+                (Some(_), Some(_)) => writes.push(group_writes),
+                (_, None) => {}
             }
 
             // Advance group tick
             group.advance_tick();
         }
 
+        match merged {
+            Some(model) => self.model = model,
+            None => self.model.advance_tick(),
+        }
+        apply_batch(writes, &mut self.model);
+
         // Advance version
         self.version += 1;
+        if let Some(history) = &mut self.history {
+            history.save_state(self.model.current_tick(), &self.model);
+        }
+        if let Some(hashes) = &mut self.hashes {
+            hashes.record(tick, tick_hashes);
+        }
+        self.bus.publish(&all_updates);
+        self.bus
+            .publish_events(failures.iter().map(BusEvent::core_failed));
 
         Ok(TickResult {
             tick: self.model.current_tick(),
             updates: all_updates,
+            failures,
         })
     }
 
@@ -436,6 +823,11 @@ impl std::fmt::Debug for Hub {
             .field("version", &self.version)
             .field("groups", &self.groups.len())
             .field("core_count", &self.config.core_count())
+            .field("partition", &self.partition)
+            .field("resolution", &self.resolution)
+            .field("subscriptions", &self.bus.len())
+            .field("history", &self.history.as_ref().map(|h| h.len()))
+            .field("hashes", &self.hashes.as_ref().map(|h| h.len()))
             .finish()
     }
 }
@@ -443,7 +835,7 @@ impl std::fmt::Debug for Hub {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::CoreId;
+    use crate::partition::PartitionKind;
     use pulsive_core::{DefId, Effect, Expr, TickHandler};
 
     #[test]
@@ -514,6 +906,246 @@ mod tests {
     // Thread Configuration API Tests
     // ========================================================================
 
+    #[test]
+    fn test_subscribe() {
+        let mut group = TickSyncGroup::single(GroupId(0), 12345);
+        group.on_tick(TickHandler {
+            id: DefId::new("report"),
+            condition: None,
+            target_kind: None,
+            effects: vec![
+                Effect::EmitEvent {
+                    event: DefId::new("harvest"),
+                    target: pulsive_core::EntityRef::Global,
+                    params: vec![],
+                },
+                Effect::Log {
+                    level: pulsive_core::effect::LogLevel::Info,
+                    message: Expr::lit("tick"),
+                },
+            ],
+            priority: 0,
+        });
+        let mut hub = Hub::new();
+        hub.add_group(group);
+
+        let topics = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = std::sync::Arc::clone(&topics);
+        let id = hub.subscribe("*", move |event| seen.lock().unwrap().push(event.topic()));
+        hub.tick().unwrap();
+        assert_eq!(*topics.lock().unwrap(), ["event:harvest", "log:info"]);
+
+        assert!(hub.unsubscribe(id));
+        hub.tick().unwrap();
+        assert_eq!(topics.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_rollback_to() {
+        let mut group = TickSyncGroup::single(GroupId(0), 12345);
+        group.on_tick(TickHandler {
+            id: DefId::new("counter"),
+            condition: None,
+            target_kind: None,
+            effects: vec![Effect::ModifyGlobal {
+                property: "count".to_string(),
+                op: pulsive_core::effect::ModifyOp::Add,
+                value: Expr::lit(1.0),
+            }],
+            priority: 0,
+        });
+        let mut hub = Hub::new();
+        hub.model_mut().set_global("count", 0.0f64);
+        hub.add_group(group);
+        assert!(matches!(hub.rollback_to(0), Err(Error::HistoryDisabled)));
+
+        hub.enable_history(8);
+        for _ in 0..5 {
+            hub.tick().unwrap();
+        }
+        let mut rng_at_3 = hub.history().unwrap().get_state(3).unwrap().rng.clone();
+        let version = hub.version();
+
+        let partitions = hub.rollback_to(2).unwrap();
+        assert_eq!(partitions.partition_count(), hub.core_count());
+        assert_eq!(hub.current_tick(), 2);
+        assert_eq!(hub.version(), version + 1);
+        let count = hub.model().get_global("count").and_then(|v| v.as_float());
+        assert_eq!(count, Some(2.0));
+        assert!(matches!(
+            hub.rollback_to(4),
+            Err(Error::NoCheckpoint {
+                tick: 4,
+                current: 2
+            })
+        ));
+
+        // Running the ticks again reproduces them
+        let result = hub.tick().unwrap();
+        assert_eq!(result.tick, 3);
+        assert_eq!(hub.model().rng.clone().next_u64(), rng_at_3.next_u64());
+        assert_eq!(hub.groups[0].tick(), 3);
+        hub.tick().unwrap();
+        hub.rollback_to(4).unwrap();
+    }
+
+    #[test]
+    fn test_group_tick_divisors() {
+        let counter = |id: usize, key: &str, divisor: u64| {
+            let mut group = TickSyncGroup::single(GroupId(id), 12345);
+            group.on_tick(TickHandler {
+                id: DefId::new(key),
+                condition: None,
+                target_kind: None,
+                effects: vec![Effect::ModifyGlobal {
+                    property: key.to_string(),
+                    op: pulsive_core::effect::ModifyOp::Add,
+                    value: Expr::lit(1.0),
+                }],
+                priority: 0,
+            });
+            (group, divisor)
+        };
+        let mut hub = Hub::new();
+        for (group, divisor) in [counter(0, "ai", 2), counter(1, "economy", 3)] {
+            hub.add_group_every(group, divisor);
+        }
+        hub.enable_history(8);
+        let global = |hub: &Hub, key: &str| hub.model().get_global(key).and_then(|v| v.as_float());
+
+        let updates: Vec<usize> = (0..7).map(|_| hub.tick().unwrap().updates.len()).collect();
+        assert_eq!(updates, [0, 1, 1, 1, 0, 2, 0]);
+        assert_eq!(hub.current_tick(), 7);
+        assert_eq!(global(&hub, "ai"), Some(3.0));
+        assert_eq!(global(&hub, "economy"), Some(2.0));
+        assert_eq!(hub.group_divisor(GroupId(1)), Some(3));
+        assert_eq!(hub.group_divisor(GroupId(2)), None);
+
+        // Rolled back groups forget the ticks they ran on
+        hub.rollback_to(4).unwrap();
+        assert_eq!(hub.groups[0].tick(), 2);
+        assert_eq!(hub.groups[1].tick(), 1);
+        hub.tick().unwrap();
+        hub.tick().unwrap();
+        assert_eq!(global(&hub, "ai"), Some(3.0));
+        assert_eq!(global(&hub, "economy"), Some(2.0));
+    }
+
+    #[test]
+    fn test_hashing_pinpoints_divergence() {
+        let run = |amount: f64| {
+            let mut group = TickSyncGroup::with_core_count(GroupId(0), 2, 12345);
+            group.on_tick(TickHandler {
+                id: DefId::new("counter"),
+                condition: None,
+                target_kind: None,
+                effects: vec![Effect::ModifyGlobal {
+                    property: "count".to_string(),
+                    op: pulsive_core::effect::ModifyOp::Add,
+                    value: Expr::lit(amount),
+                }],
+                priority: 0,
+            });
+            let mut hub = Hub::new();
+            hub.enable_hashing();
+            hub.add_group(group);
+            for _ in 0..3 {
+                hub.tick().unwrap();
+            }
+            hub
+        };
+        let hub = run(1.0);
+        let log = hub.hash_log().unwrap();
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.get(1).unwrap().len(), 2);
+        assert_eq!(log.first_divergence(run(1.0).hash_log().unwrap()), None);
+
+        let divergence = log.first_divergence(run(2.0).hash_log().unwrap()).unwrap();
+        assert_eq!(
+            divergence.to_string(),
+            "tick 1 Group(0) Core(0): writes to global 'count' differ"
+        );
+    }
+
+    #[test]
+    fn test_panicking_core_is_quarantined() {
+        let mut group = TickSyncGroup::with_core_count(GroupId(0), 2, 12345);
+        group.cores_mut()[1].runtime_mut().on_phase(
+            pulsive_core::TickPhase::PostTick,
+            "crash",
+            |_, model, _| {
+                if model.current_tick() == 2 {
+                    panic!("bad state at tick {}", model.current_tick());
+                }
+            },
+        );
+        let mut hub = Hub::new();
+        hub.add_group(group);
+        let failed = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = std::sync::Arc::clone(&failed);
+        hub.subscribe("event:core_failed", move |event| {
+            if let BusEvent::Event { params, .. } = event {
+                seen.lock().unwrap().push(params.get("error").cloned());
+            }
+        });
+
+        assert!(hub.tick().unwrap().failures.is_empty());
+        let result = hub.tick().unwrap();
+        assert_eq!(result.tick, 2);
+        assert_eq!(result.updates.len(), 1);
+        assert_eq!(result.failures.len(), 1);
+        assert_eq!(
+            result.failures[0].to_string(),
+            "Group(0) Core(1) panicked at tick 2: bad state at tick 2"
+        );
+        assert_eq!(
+            *failed.lock().unwrap(),
+            [Some(pulsive_core::Value::from("bad state at tick 2"))]
+        );
+        assert_eq!(hub.quarantined_cores(), [(GroupId(0), CoreId(1))]);
+
+        // The hub keeps ticking without the quarantined core
+        assert_eq!(hub.tick().unwrap().updates.len(), 1);
+        assert!(hub.release_core(GroupId(0), CoreId(1)).unwrap());
+        assert!(hub.quarantined_cores().is_empty());
+        assert_eq!(hub.tick().unwrap().updates.len(), 2);
+        assert!(matches!(
+            hub.release_core(GroupId(7), CoreId(0)),
+            Err(Error::GroupNotFound(GroupId(7)))
+        ));
+    }
+
+    #[test]
+    fn test_quarantine_evacuates_only_its_group() {
+        let mut model = Model::new();
+        for _ in 0..8 {
+            model.entities_mut().create("unit");
+        }
+        let mut hub = Hub::with_config(model, HubConfig::default());
+        let mut failing = TickSyncGroup::with_core_count(GroupId(0), 2, 12345);
+        failing.cores_mut()[1].runtime_mut().on_phase(
+            pulsive_core::TickPhase::PostTick,
+            "crash",
+            |_, _, _| panic!("crash"),
+        );
+        hub.add_group(failing);
+        hub.add_group(TickSyncGroup::with_core_count(GroupId(1), 2, 12345));
+        assert_eq!(hub.tick().unwrap().failures.len(), 1);
+        assert_eq!(hub.quarantined_cores(), [(GroupId(0), CoreId(1))]);
+
+        let failing = hub.group_partitions(GroupId(0)).unwrap();
+        assert!(failing.get(CoreId(1)).is_empty());
+        assert_eq!(failing.get(CoreId(0)).len(), 8);
+
+        // Core 1 of the other group keeps its entities
+        let healthy = hub.group_partitions(GroupId(1)).unwrap();
+        assert_eq!(healthy.partition_sizes(), [4, 4]);
+        assert!(matches!(
+            hub.group_partitions(GroupId(7)),
+            Err(Error::GroupNotFound(GroupId(7)))
+        ));
+    }
+
     #[test]
     fn test_default_is_single_core() {
         let hub = Hub::new();
@@ -586,6 +1218,74 @@ mod tests {
         assert_eq!(hub.current_tick(), 3);
     }
 
+    #[test]
+    fn test_strategy_accessors() {
+        let config = HubConfig::with_seed(42);
+        let mut hub = Hub::with_config(Model::new(), config);
+        assert!(matches!(
+            hub.partition_strategy().kind(),
+            PartitionKind::ById
+        ));
+        assert_eq!(hub.partition_strategy().seed(), 42);
+        assert!(matches!(
+            hub.resolution_strategy(),
+            ResolutionStrategy::Abort
+        ));
+
+        hub.set_partition_strategy(PartitionStrategy::spatial_grid(10.0, "x", "y"));
+        hub.set_resolution_strategy(ResolutionStrategy::Merge);
+        assert!(matches!(
+            hub.partition_strategy().kind(),
+            PartitionKind::SpatialGrid { .. }
+        ));
+        assert!(matches!(
+            hub.resolution_strategy(),
+            ResolutionStrategy::Merge
+        ));
+
+        // Strategies can change between ticks
+        hub.add_group(TickSyncGroup::single(GroupId(0), 42));
+        hub.tick().unwrap();
+        hub.set_resolution_strategy(ResolutionStrategy::LastWriteWins);
+        hub.tick().unwrap();
+        assert_eq!(hub.current_tick(), 2);
+    }
+
+    #[test]
+    fn test_commit_batch_uses_config() {
+        use crate::ConflictDetail;
+        use pulsive_core::{PendingWrite, Value};
+
+        let write_sets = || {
+            (0..2)
+                .map(|core| {
+                    let mut ws = WriteSet::new();
+                    ws.push(PendingWrite::SetGlobal {
+                        key: "gold".to_string(),
+                        value: Value::Float(core as f64),
+                    });
+                    (CoreId(core), ws)
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut hub = Hub::new();
+        let error = hub.commit_batch(write_sets()).unwrap_err();
+        let report = error.conflict_report().unwrap();
+        assert_eq!(report.iter().next().unwrap().writes.len(), 2);
+
+        hub.config_mut()
+            .set_conflict_detail(ConflictDetail::CountsOnly);
+        let error = hub.commit_batch(write_sets()).unwrap_err();
+        let conflict = error.conflict_report().unwrap().iter().next().unwrap();
+        assert!(conflict.writes.is_empty());
+        assert_eq!(conflict.write_count(), 2);
+
+        hub.set_resolution_strategy(ResolutionStrategy::LastWriteWins);
+        let result = hub.commit_batch(write_sets()).unwrap();
+        assert_eq!(result.conflicts_resolved, 1);
+        assert_eq!(hub.model().get_global("gold"), Some(&Value::Float(1.0)));
+    }
+
     #[test]
     fn test_deterministic_regardless_of_core_count() {
         // Helper to create a tick handler that increments a counter
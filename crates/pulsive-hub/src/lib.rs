@@ -22,6 +22,9 @@
 //! - [`CoreGroup`]: Trait for groups of cores with different execution strategies
 //! - [`TickSyncGroup`]: Implementation where all cores stay at the same tick
 //! - [`Core`]: Thin wrapper bundling pulsive-core's Runtime + Model
+//! - [`EventBus`]: Publishes what ticks produce to host applications
+//! - [`UndoStack`]: Undo and redo of applied WriteSets, for editors
+//! - `RemoteCoreGroup` (feature `remote`): Cores in other processes, over pulsive-netcode
 //!
 //! ## Design Principles
 //!
@@ -29,29 +32,45 @@
 //! 2. **pulsive-core is standalone** - it does NOT know about pulsive-hub
 //! 3. **Core is just a wrapper** - bundles Runtime+Model, delegates all logic to pulsive-core
 
+mod arena;
+pub mod bus;
 pub mod commit;
 mod config;
 pub mod conflict;
 mod core;
+pub mod divergence;
 mod error;
 mod group;
 pub mod hash;
 mod hub;
 pub mod partition;
+#[cfg(feature = "remote")]
+pub mod remote;
 mod snapshot;
 mod tick_sync;
+mod undo;
 
-pub use commit::{apply, apply_batch, commit, commit_batch, has_conflicts, CommitResult};
+pub use arena::CommitArena;
+pub use bus::{BusEvent, EventBus, SubscriptionId};
+pub use commit::{
+    apply, apply_batch, apply_strict, commit, commit_batch, commit_batch_with_detail,
+    has_conflicts, CommitResult,
+};
 pub use config::{hash_seed, max_cores, HubConfig, DEFAULT_GLOBAL_SEED};
 pub use conflict::{
-    default_conflict_filter, detect_conflicts, detect_conflicts_filtered, resolve_conflicts,
-    Conflict, ConflictReport, ConflictResolver, ConflictTarget, ConflictType, ResolutionResult,
-    ResolutionStrategy, ResolvedConflict,
+    default_conflict_filter, detect_conflicts, detect_conflicts_filtered,
+    detect_conflicts_with_detail, resolve_conflicts, resolve_conflicts_with_detail, Conflict,
+    ConflictDetail, ConflictReport, ConflictResolver, ConflictTarget, ConflictType,
+    ResolutionResult, ResolutionStrategy, ResolvedConflict,
 };
 pub use core::{Core, CoreId};
+pub use divergence::{CoreHash, Divergence, DivergenceKind, HashLog};
 pub use error::{Error, Result};
-pub use group::{CoreGroup, GroupId};
+pub use group::{CoreFailure, CoreGroup, GroupId};
 pub use hub::Hub;
 pub use partition::{PartitionFn, PartitionKind, PartitionResult, PartitionStrategy};
-pub use snapshot::ModelSnapshot;
+#[cfg(feature = "remote")]
+pub use remote::{RemoteConfig, RemoteCoreGroup, RemoteMessage, RemoteUpdate, RemoteWorker};
+pub use snapshot::{ModelSnapshot, PartitionSnapshot, PartitionViolation, SharedState};
 pub use tick_sync::TickSyncGroup;
+pub use undo::{apply_undoable, UndoStack};
@@ -619,6 +619,30 @@ impl PartitionResult {
         std_dev / mean
     }
 
+    /// Move the entities of some cores to the other cores
+    ///
+    /// Used to take failed cores out of service: each moved entity goes to
+    /// a remaining core chosen by its slot index, so the same entities always
+    /// land on the same cores. The partitions of the evacuated cores are left
+    /// empty. Does nothing if no core would remain.
+    pub fn evacuate(&mut self, cores: &[CoreId]) {
+        let remaining: Vec<usize> = (0..self.partitions.len())
+            .filter(|&i| !cores.contains(&CoreId(i)))
+            .collect();
+        if remaining.is_empty() {
+            return;
+        }
+        for core in cores {
+            let Some(partition) = self.partitions.get_mut(core.0) else {
+                continue;
+            };
+            for id in std::mem::take(partition) {
+                let target = remaining[id.index() as usize % remaining.len()];
+                self.partitions[target].push(id);
+            }
+        }
+    }
+
     /// Iterate over partitions with their core IDs
     pub fn iter(&self) -> impl Iterator<Item = (CoreId, &[EntityId])> {
         self.partitions
@@ -1209,6 +1233,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_partition_result_evacuate() {
+        let store = create_test_store(12);
+        let strategy = PartitionStrategy::by_id();
+        let mut result = strategy.partition(&store, 4);
+
+        result.evacuate(&[CoreId(1)]);
+        assert!(result.get(CoreId(1)).is_empty());
+        assert_eq!(result.total_entities(), 12);
+        assert_eq!(result.partition_sizes(), vec![4, 0, 4, 4]);
+
+        // Nothing moves when no core would remain
+        let mut single = strategy.partition(&store, 1);
+        single.evacuate(&[CoreId(0)]);
+        assert_eq!(single.get(CoreId(0)).len(), 12);
+    }
+
     #[test]
     fn test_empty_store_partitioning() {
         let store = EntityStore::new();
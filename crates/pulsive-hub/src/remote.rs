@@ -0,0 +1,865 @@
+//! Remote core groups - one simulation across processes and machines
+//!
+//! A [`RemoteCoreGroup`] is a [`CoreGroup`] whose cores run elsewhere, each
+//! in a [`RemoteWorker`] at the other end of a pulsive-netcode
+//! [`Connection`]. Every tick the group:
+//!
+//! 1. partitions the model's entities across the healthy workers and sends
+//!    each its [`PartitionSnapshot`](crate::PartitionSnapshot) as a model
+//! 2. asks them to tick and waits for their WriteSets, up to a timeout
+//! 3. applies the WriteSets to its copy of the model, in worker order
+//!
+//! A worker that disconnects, panics, misses the timeout or stops sending
+//! heartbeats is quarantined like a panicking local core (see
+//! [`CoreGroup::take_failures`]): its WriteSet is discarded and its entities
+//! go to the other workers from the next tick on.
+//!
+//! Messages are [`RemoteMessage`]s encoded with bincode; the connection must
+//! be reliable and ordered. Both ends send a heartbeat every
+//! [`RemoteConfig::heartbeat_interval`].
+//!
+//! # Spawned entities
+//!
+//! Entities spawned by a worker get their IDs when the group applies the
+//! WriteSets, so the worker's writes to an entity it spawned in the same
+//! tick are dropped (the spawn's initial properties are kept).
+//!
+//! # Hashing
+//!
+//! With hashing enabled, [`CoreHash`]es are computed by the group from the
+//! writes it keeps, and the entities they touched are hashed as the group
+//! left them after applying every worker's writes.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! // On each worker machine
+//! let mut worker = RemoteWorker::new(core, connection, RemoteConfig::default());
+//! while worker.is_connected() {
+//!     worker.poll()?;
+//! }
+//!
+//! // On the hub
+//! let mut group = RemoteCoreGroup::new(GroupId(0), RemoteConfig::default());
+//! for connection in worker_connections {
+//!     group.add_worker(connection);
+//! }
+//! hub.add_group(group);
+//! hub.tick()?;
+//! ```
+
+use crate::commit::apply_batch;
+use crate::core::{Core, CoreId};
+use crate::divergence::CoreHash;
+use crate::group::{CoreFailure, CoreGroup, GroupId};
+use crate::partition::PartitionStrategy;
+use crate::snapshot::{ModelSnapshot, SharedState};
+use crate::{Error, Result};
+use pulsive_core::effect::Notification;
+use pulsive_core::{
+    DefId, EntityId, EntityRef, LogRecord, Model, PendingWrite, UpdateResult, ValueMap, WriteSet,
+};
+use pulsive_netcode::Connection;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Timeouts and heartbeats of remote cores
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RemoteConfig {
+    /// How long to wait for a worker's tick, or for any message from it,
+    /// before quarantining it
+    pub timeout: Duration,
+    /// How often each end sends a heartbeat; keep it well below the
+    /// timeout
+    pub heartbeat_interval: Duration,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            heartbeat_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl RemoteConfig {
+    /// Set the timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the heartbeat interval
+    pub fn with_heartbeat_interval(mut self, interval: Duration) -> Self {
+        self.heartbeat_interval = interval;
+        self
+    }
+}
+
+/// What a remote tick produced, besides its writes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RemoteUpdate {
+    /// Events emitted
+    pub emitted_events: Vec<(DefId, EntityRef, ValueMap)>,
+    /// Notifications
+    pub notifications: Vec<Notification>,
+    /// Log records
+    pub logs: Vec<LogRecord>,
+}
+
+impl From<&UpdateResult> for RemoteUpdate {
+    fn from(update: &UpdateResult) -> Self {
+        let effects = &update.effect_result;
+        Self {
+            emitted_events: effects.emitted_events.clone(),
+            notifications: effects.notifications.clone(),
+            logs: effects.logs.clone(),
+        }
+    }
+}
+
+impl From<RemoteUpdate> for UpdateResult {
+    fn from(update: RemoteUpdate) -> Self {
+        let mut result = UpdateResult::new();
+        result.effect_result.emitted_events = update.emitted_events;
+        result.effect_result.notifications = update.notifications;
+        result.effect_result.logs = update.logs;
+        result
+    }
+}
+
+/// A message between a [`RemoteCoreGroup`] and a [`RemoteWorker`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RemoteMessage {
+    /// Load the worker's partition of the model (group to worker)
+    Load {
+        /// The partition, as a model
+        model: Model,
+    },
+    /// Execute one tick on the loaded partition (group to worker)
+    Tick {
+        /// Tick to execute
+        tick: u64,
+    },
+    /// Drop the state kept from ticks rolled back (group to worker)
+    Reset,
+    /// A tick executed (worker to group)
+    TickDone {
+        /// Tick executed
+        tick: u64,
+        /// Writes the tick made to the partition
+        writes: WriteSet,
+        /// Events, notifications and logs the tick produced
+        update: RemoteUpdate,
+    },
+    /// A tick panicked (worker to group)
+    TickFailed {
+        /// Tick executed
+        tick: u64,
+        /// The panic message
+        error: String,
+    },
+    /// Liveness signal (both ways)
+    Heartbeat,
+}
+
+impl RemoteMessage {
+    /// Encode the message for sending
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        bincode::serialize(self).map_err(|e| Error::Remote(e.to_string()))
+    }
+
+    /// Decode a received message
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        bincode::deserialize(data).map_err(|e| Error::Remote(e.to_string()))
+    }
+}
+
+/// Encode and send a message
+fn send<C: Connection>(connection: &C, message: &RemoteMessage) -> Result<()> {
+    connection
+        .send_reliable(&message.encode()?)
+        .map_err(|e| Error::Remote(e.to_string()))
+}
+
+/// Receive and decode a message, if one arrived
+fn recv<C: Connection>(connection: &C) -> Result<Option<RemoteMessage>> {
+    match connection.recv() {
+        Ok(Some(data)) => RemoteMessage::decode(&data).map(Some),
+        Ok(None) => Ok(None),
+        Err(e) => Err(Error::Remote(e.to_string())),
+    }
+}
+
+/// The hub's end of a connection to a worker
+struct Worker<C> {
+    id: CoreId,
+    connection: C,
+    /// Why the worker is quarantined (None = healthy)
+    quarantine: Option<String>,
+    /// When a message was last received
+    last_seen: Instant,
+    /// When a message was last sent
+    last_sent: Instant,
+    /// The tick result received, while waiting for results
+    done: Option<(WriteSet, RemoteUpdate)>,
+}
+
+impl<C: Connection> Worker<C> {
+    fn send(&mut self, message: &RemoteMessage) -> Result<()> {
+        send(&self.connection, message)?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+}
+
+/// A core group whose cores run in [`RemoteWorker`]s
+///
+/// See the [module docs](self) for the protocol.
+pub struct RemoteCoreGroup<C: Connection> {
+    id: GroupId,
+    tick: u64,
+    workers: Vec<Worker<C>>,
+    config: RemoteConfig,
+    /// How entities are assigned to workers
+    partition: PartitionStrategy,
+    /// State every worker receives besides its partition
+    shared: SharedState,
+    /// The model loaded, then with the workers' writes applied
+    model: Model,
+    /// Whether a worker's results were applied this tick
+    merged: bool,
+    /// The workers' writes applied this tick (None = not recorded)
+    writes: Option<WriteSet>,
+    /// Whether each worker's tick is hashed
+    hashing: bool,
+    /// Hashes not yet taken by the hub
+    hashes: Vec<CoreHash>,
+    /// Failures not yet taken by the hub
+    failures: Vec<CoreFailure>,
+}
+
+impl<C: Connection> RemoteCoreGroup<C> {
+    /// Create a group with no workers
+    pub fn new(id: GroupId, config: RemoteConfig) -> Self {
+        Self {
+            id,
+            tick: 0,
+            workers: Vec::new(),
+            config,
+            partition: PartitionStrategy::by_id(),
+            shared: SharedState::new(),
+            model: Model::new(),
+            merged: false,
+            writes: None,
+            hashing: false,
+            hashes: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+
+    /// Set how entities are assigned to workers
+    pub fn with_partition_strategy(mut self, strategy: PartitionStrategy) -> Self {
+        self.partition = strategy;
+        self
+    }
+
+    /// Set the state every worker receives besides its partition
+    pub fn with_shared(mut self, shared: SharedState) -> Self {
+        self.shared = shared;
+        self
+    }
+
+    /// Add a connected worker, returning its core ID
+    pub fn add_worker(&mut self, connection: C) -> CoreId {
+        let id = CoreId(self.workers.len());
+        let now = Instant::now();
+        self.workers.push(Worker {
+            id,
+            connection,
+            quarantine: None,
+            last_seen: now,
+            last_sent: now,
+            done: None,
+        });
+        id
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &RemoteConfig {
+        &self.config
+    }
+
+    /// Send heartbeats that are due and handle the messages received
+    ///
+    /// Quarantines the workers that disconnected, sent an invalid message or
+    /// have been silent longer than the timeout. Called by
+    /// [`CoreGroup::execute_tick`]; call it between ticks too if they are
+    /// far apart.
+    pub fn poll(&mut self) {
+        let now = Instant::now();
+        for index in 0..self.workers.len() {
+            let worker = &mut self.workers[index];
+            if worker.quarantine.is_some() {
+                continue;
+            }
+            let result = self.receive(index).and_then(|()| {
+                let worker = &mut self.workers[index];
+                if now.duration_since(worker.last_sent) >= self.config.heartbeat_interval {
+                    worker.send(&RemoteMessage::Heartbeat)?;
+                }
+                Ok(())
+            });
+            let worker = &self.workers[index];
+            let error = match result {
+                Err(e) => Some(e.to_string()),
+                Ok(()) if !worker.connection.is_connected() => Some("disconnected".to_string()),
+                Ok(()) if now.duration_since(worker.last_seen) > self.config.timeout => Some(
+                    format!("no message for {:?}", now.duration_since(worker.last_seen)),
+                ),
+                Ok(()) => None,
+            };
+            if let Some(error) = error {
+                self.fail(index, self.model.current_tick() + 1, error);
+            }
+        }
+    }
+
+    /// Handle the messages received from a worker
+    fn receive(&mut self, index: usize) -> Result<()> {
+        let tick = self.model.current_tick() + 1;
+        while let Some(message) = recv(&self.workers[index].connection)? {
+            self.workers[index].last_seen = Instant::now();
+            match message {
+                RemoteMessage::TickDone {
+                    tick: done,
+                    writes,
+                    update,
+                } if done == tick => {
+                    self.workers[index].done = Some((writes, update));
+                }
+                RemoteMessage::TickFailed {
+                    tick: failed,
+                    error,
+                } if failed == tick => {
+                    return Err(Error::Remote(format!("panicked: {}", error)));
+                }
+                // Late results of abandoned ticks and heartbeats
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Quarantine a worker
+    fn fail(&mut self, index: usize, tick: u64, error: String) {
+        let worker = &mut self.workers[index];
+        worker.quarantine = Some(error.clone());
+        worker.done = None;
+        self.failures.push(CoreFailure {
+            group: self.id,
+            core: worker.id,
+            tick,
+            error,
+        });
+    }
+
+    /// Get the indices of the healthy workers
+    fn healthy(&self) -> Vec<usize> {
+        (0..self.workers.len())
+            .filter(|&i| self.workers[i].quarantine.is_none())
+            .collect()
+    }
+
+    /// Check whether a write targets an entity absent from the loaded model
+    fn targets_new_entity(&self, write: &PendingWrite) -> bool {
+        let entity_id: EntityId = match write {
+            PendingWrite::SetProperty { entity_id, .. }
+            | PendingWrite::ModifyProperty { entity_id, .. }
+            | PendingWrite::AddFlag { entity_id, .. }
+            | PendingWrite::RemoveFlag { entity_id, .. }
+            | PendingWrite::AddFlagFor { entity_id, .. }
+            | PendingWrite::AddTag { entity_id, .. }
+            | PendingWrite::RemoveTag { entity_id, .. } => *entity_id,
+            PendingWrite::DestroyEntity { id } => *id,
+            _ => return false,
+        };
+        self.model.entities().get(entity_id).is_none()
+    }
+}
+
+impl<C: Connection> CoreGroup for RemoteCoreGroup<C> {
+    fn id(&self) -> GroupId {
+        self.id
+    }
+
+    fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    fn core_count(&self) -> usize {
+        self.workers.len()
+    }
+
+    fn load_model(&mut self, model: &Model) {
+        self.model = model.clone();
+        self.merged = false;
+        if let Some(writes) = &mut self.writes {
+            *writes = WriteSet::new();
+        }
+        let healthy = self.healthy();
+        if healthy.is_empty() {
+            return;
+        }
+        let mut partitions = self
+            .partition
+            .partition(model.entities(), self.workers.len());
+        let quarantined: Vec<CoreId> = self
+            .workers
+            .iter()
+            .filter(|w| w.quarantine.is_some())
+            .map(|w| w.id)
+            .collect();
+        partitions.evacuate(&quarantined);
+
+        let snapshot = ModelSnapshot::new(model, 0);
+        for index in healthy {
+            let core = self.workers[index].id;
+            let partition = snapshot.partition(core, partitions.get(core), &self.shared);
+            let message = RemoteMessage::Load {
+                model: partition.to_model(),
+            };
+            if let Err(e) = self.workers[index].send(&message) {
+                self.fail(index, model.current_tick() + 1, e.to_string());
+            }
+        }
+    }
+
+    fn execute_tick(&mut self) -> Vec<UpdateResult> {
+        let tick = self.model.current_tick() + 1;
+        for index in self.healthy() {
+            if let Err(e) = self.workers[index].send(&RemoteMessage::Tick { tick }) {
+                self.fail(index, tick, e.to_string());
+            }
+        }
+
+        // Wait for every healthy worker's result, up to the timeout
+        let deadline = Instant::now() + self.config.timeout;
+        loop {
+            self.poll();
+            let waiting = self
+                .workers
+                .iter()
+                .any(|w| w.quarantine.is_none() && w.done.is_none());
+            if !waiting {
+                break;
+            }
+            if Instant::now() >= deadline {
+                for index in self.healthy() {
+                    if self.workers[index].done.is_none() {
+                        let error = format!("no tick result within {:?}", self.config.timeout);
+                        self.fail(index, tick, error);
+                    }
+                }
+                break;
+            }
+            std::thread::sleep(Duration::from_micros(100));
+        }
+
+        let mut cores = Vec::new();
+        let mut write_sets = Vec::new();
+        let mut updates = Vec::new();
+        for index in 0..self.workers.len() {
+            let Some((writes, update)) = self.workers[index].done.take() else {
+                continue;
+            };
+            let mut kept = WriteSet::new();
+            for write in writes.into_writes() {
+                if !self.targets_new_entity(&write) {
+                    kept.push(write);
+                }
+            }
+            cores.push(self.workers[index].id);
+            write_sets.push(kept);
+            updates.push(UpdateResult::from(update));
+        }
+        if !updates.is_empty() {
+            if let Some(writes) = &mut self.writes {
+                *writes = WriteSet::merge(write_sets.clone());
+            }
+            let hashed = self.hashing.then(|| write_sets.clone());
+            apply_batch(write_sets, &mut self.model);
+            self.model.advance_tick();
+            self.merged = true;
+            for (core, writes) in cores.into_iter().zip(hashed.iter().flatten()) {
+                let hash = CoreHash::new(self.id, core, writes, &self.model);
+                self.hashes.push(hash);
+            }
+        }
+        updates
+    }
+
+    fn extract_models(&self) -> Vec<&Model> {
+        if self.merged {
+            vec![&self.model]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn advance_tick(&mut self) {
+        self.tick += 1;
+    }
+
+    fn rewind(&mut self, model: &Model, ticks: u64) {
+        self.model = model.clone();
+        self.merged = false;
+        self.tick = self.tick.saturating_sub(ticks);
+        for index in self.healthy() {
+            if let Err(e) = self.workers[index].send(&RemoteMessage::Reset) {
+                self.fail(index, model.current_tick() + 1, e.to_string());
+            }
+        }
+    }
+
+    fn take_failures(&mut self) -> Vec<CoreFailure> {
+        std::mem::take(&mut self.failures)
+    }
+
+    fn set_write_logging(&mut self, enabled: bool) {
+        self.writes = enabled.then(WriteSet::new);
+    }
+
+    fn take_writes(&mut self) -> WriteSet {
+        self.writes.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    fn set_hashing(&mut self, enabled: bool) {
+        self.hashing = enabled;
+    }
+
+    fn take_hashes(&mut self) -> Vec<CoreHash> {
+        std::mem::take(&mut self.hashes)
+    }
+
+    fn quarantined(&self) -> Vec<CoreId> {
+        self.workers
+            .iter()
+            .filter(|w| w.quarantine.is_some())
+            .map(|w| w.id)
+            .collect()
+    }
+
+    fn release(&mut self, core: CoreId) -> bool {
+        let Some(worker) = self.workers.iter_mut().find(|w| w.id == core) else {
+            return false;
+        };
+        // Silence while quarantined doesn't count against the worker
+        worker.last_seen = Instant::now();
+        worker.quarantine.take().is_some()
+    }
+}
+
+impl<C: Connection> std::fmt::Debug for RemoteCoreGroup<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteCoreGroup")
+            .field("id", &self.id)
+            .field("tick", &self.tick)
+            .field("workers", &self.workers.len())
+            .field("quarantined", &self.quarantined())
+            .finish()
+    }
+}
+
+/// A core run for a [`RemoteCoreGroup`] at the other end of a connection
+pub struct RemoteWorker<C: Connection> {
+    core: Core,
+    connection: C,
+    config: RemoteConfig,
+    /// When a message was last received
+    last_seen: Instant,
+    /// When a message was last sent
+    last_sent: Instant,
+}
+
+impl<C: Connection> RemoteWorker<C> {
+    /// Serve a core over a connection to the group
+    ///
+    /// Enables write logging on the core's runtime: the logged writes are
+    /// what the worker sends back.
+    pub fn new(mut core: Core, connection: C, config: RemoteConfig) -> Self {
+        core.runtime_mut().set_write_logging(true);
+        let now = Instant::now();
+        Self {
+            core,
+            connection,
+            config,
+            last_seen: now,
+            last_sent: now,
+        }
+    }
+
+    /// Get the core
+    pub fn core(&self) -> &Core {
+        &self.core
+    }
+
+    /// Get the core mutably (for registering handlers)
+    pub fn core_mut(&mut self) -> &mut Core {
+        &mut self.core
+    }
+
+    /// Check whether the connection is up and the group has been heard
+    /// from within the timeout
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_connected() && self.last_seen.elapsed() <= self.config.timeout
+    }
+
+    /// Handle the messages received and send a heartbeat if due
+    ///
+    /// Returns the number of messages handled. Call it in a loop.
+    pub fn poll(&mut self) -> Result<usize> {
+        let mut handled = 0;
+        while let Some(message) = recv(&self.connection)? {
+            self.last_seen = Instant::now();
+            handled += 1;
+            match message {
+                RemoteMessage::Load { model } => {
+                    self.core.release();
+                    self.core.load_model(model);
+                }
+                RemoteMessage::Tick { tick } => {
+                    // Drop writes made outside a tick
+                    self.core.runtime_mut().take_write_log();
+                    let reply = match self.core.try_tick() {
+                        Ok(update) => RemoteMessage::TickDone {
+                            tick,
+                            writes: self.core.runtime_mut().take_write_log(),
+                            update: RemoteUpdate::from(&update),
+                        },
+                        Err(error) => RemoteMessage::TickFailed { tick, error },
+                    };
+                    self.send(&reply)?;
+                }
+                RemoteMessage::Reset => {
+                    self.core.runtime_mut().cancel_where(|_| true);
+                }
+                _ => {}
+            }
+        }
+        if self.last_sent.elapsed() >= self.config.heartbeat_interval {
+            self.send(&RemoteMessage::Heartbeat)?;
+        }
+        Ok(handled)
+    }
+
+    fn send(&mut self, message: &RemoteMessage) -> Result<()> {
+        send(&self.connection, message)?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+}
+
+impl<C: Connection> std::fmt::Debug for RemoteWorker<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteWorker")
+            .field("core", &self.core)
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Hub, TickSyncGroup};
+    use pulsive_core::{Effect, Expr, TickHandler};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::thread;
+
+    #[derive(Debug)]
+    struct Closed;
+
+    impl std::fmt::Display for Closed {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "connection closed")
+        }
+    }
+
+    impl std::error::Error for Closed {}
+
+    /// One end of an in-memory connection
+    struct Pipe {
+        tx: mpsc::Sender<Vec<u8>>,
+        rx: Mutex<mpsc::Receiver<Vec<u8>>>,
+    }
+
+    fn pipe() -> (Pipe, Pipe) {
+        let (a_tx, a_rx) = mpsc::channel();
+        let (b_tx, b_rx) = mpsc::channel();
+        let a = Pipe {
+            tx: a_tx,
+            rx: Mutex::new(b_rx),
+        };
+        let b = Pipe {
+            tx: b_tx,
+            rx: Mutex::new(a_rx),
+        };
+        (a, b)
+    }
+
+    impl Connection for Pipe {
+        type Error = Closed;
+
+        fn send_reliable(&self, data: &[u8]) -> std::result::Result<(), Closed> {
+            self.tx.send(data.to_vec()).map_err(|_| Closed)
+        }
+
+        fn send_unreliable(&self, data: &[u8]) -> std::result::Result<(), Closed> {
+            self.send_reliable(data)
+        }
+
+        fn recv(&self) -> std::result::Result<Option<Vec<u8>>, Closed> {
+            match self.rx.lock().unwrap().try_recv() {
+                Ok(data) => Ok(Some(data)),
+                Err(mpsc::TryRecvError::Empty) => Ok(None),
+                Err(mpsc::TryRecvError::Disconnected) => Err(Closed),
+            }
+        }
+
+        fn is_connected(&self) -> bool {
+            true
+        }
+
+        fn remote_addr(&self) -> Option<pulsive_netcode::Address> {
+            None
+        }
+
+        fn close(&self) -> std::result::Result<(), Closed> {
+            Ok(())
+        }
+    }
+
+    fn heal() -> TickHandler {
+        TickHandler {
+            id: DefId::new("heal"),
+            condition: None,
+            target_kind: Some(DefId::new("unit")),
+            effects: vec![Effect::add("hp", Expr::lit(1.0))],
+            priority: 0,
+        }
+    }
+
+    fn model() -> Model {
+        let mut model = Model::new();
+        for _ in 0..6 {
+            model.entities_mut().create("unit").set("hp", 0.0f64);
+        }
+        model
+    }
+
+    /// Run a worker on a thread until stopped
+    fn spawn_worker(
+        id: usize,
+        connection: Pipe,
+        config: RemoteConfig,
+        stop: &Arc<AtomicBool>,
+    ) -> thread::JoinHandle<()> {
+        let stop = Arc::clone(stop);
+        thread::spawn(move || {
+            let mut core = Core::with_seed(CoreId(id), 7);
+            core.runtime_mut().on_tick(heal());
+            let mut worker = RemoteWorker::new(core, connection, config);
+            while !stop.load(Ordering::Relaxed) && worker.poll().is_ok() {
+                thread::sleep(Duration::from_micros(100));
+            }
+        })
+    }
+
+    #[test]
+    fn test_remote_group_matches_local() {
+        let mut local = TickSyncGroup::single(GroupId(0), 7);
+        local.on_tick(heal());
+        let mut expected = Hub::with_model(model());
+        expected.add_group(local);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let mut group = RemoteCoreGroup::new(GroupId(0), RemoteConfig::default());
+        let mut threads = Vec::new();
+        for id in 0..2 {
+            let (hub_end, worker_end) = pipe();
+            group.add_worker(hub_end);
+            threads.push(spawn_worker(id, worker_end, RemoteConfig::default(), &stop));
+        }
+        let mut hub = Hub::with_model(model());
+        hub.add_group(group);
+
+        for _ in 0..3 {
+            let result = hub.tick().unwrap();
+            assert!(result.failures.is_empty());
+            assert_eq!(result.updates.len(), 2);
+            expected.tick().unwrap();
+        }
+        assert_eq!(hub.current_tick(), 3);
+        for entity in expected.model().entities().iter() {
+            let remote = hub.model().entities().get(entity.id).unwrap();
+            assert_eq!(remote.get_number("hp"), Some(3.0));
+            assert_eq!(remote.get_number("hp"), entity.get_number("hp"));
+        }
+
+        stop.store(true, Ordering::Relaxed);
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_silent_worker_is_quarantined() {
+        let stop = Arc::new(AtomicBool::new(false));
+        let config = RemoteConfig::default()
+            .with_timeout(Duration::from_millis(50))
+            .with_heartbeat_interval(Duration::from_millis(5));
+        let mut group = RemoteCoreGroup::new(GroupId(0), config);
+        let (hub_end, worker_end) = pipe();
+        group.add_worker(hub_end);
+        let worker = spawn_worker(0, worker_end, config, &stop);
+        // Nobody answers at the other end of this one
+        let (hub_end, _silent) = pipe();
+        group.add_worker(hub_end);
+        let mut hub = Hub::with_model(model());
+        hub.add_group(group);
+
+        let result = hub.tick().unwrap();
+        assert_eq!(result.updates.len(), 1);
+        assert_eq!(result.failures.len(), 1);
+        assert_eq!(result.failures[0].core, CoreId(1));
+        assert_eq!(hub.quarantined_cores(), [(GroupId(0), CoreId(1))]);
+
+        // The healthy worker takes over the silent one's entities
+        let result = hub.tick().unwrap();
+        assert!(result.failures.is_empty());
+        let mut hp: Vec<_> = hub
+            .model()
+            .entities()
+            .iter()
+            .map(|e| e.get_number("hp"))
+            .collect();
+        hp.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            hp,
+            [
+                Some(1.0),
+                Some(1.0),
+                Some(1.0),
+                Some(2.0),
+                Some(2.0),
+                Some(2.0)
+            ]
+        );
+
+        stop.store(true, Ordering::Relaxed);
+        worker.join().unwrap();
+    }
+}
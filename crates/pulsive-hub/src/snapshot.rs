@@ -31,11 +31,32 @@
 //!     println!("Game speed: {:?}", value);
 //! }
 //! ```
+//!
+//! # Partition Views
+//!
+//! A [`PartitionSnapshot`] holds only the entities assigned to one core, plus
+//! the entities and globals declared shared in a [`SharedState`], which every
+//! core may read but none may write. Cores loading a partition view clone
+//! just their part of the model, and the writes they produce can be checked
+//! for writes outside their partition when collected:
+//!
+//! ```rust,ignore
+//! let shared = SharedState::new().with_global("tax_rate");
+//! let partitions = hub.partition_strategy().partition(snapshot.entity_store(), 4);
+//! for view in snapshot.partitions(&partitions, &shared) {
+//!     // ... run a core on view.to_model(), collect its WriteSet ...
+//!     view.check_writes(&writes)?;
+//! }
+//! ```
 
+use crate::conflict::ConflictTarget;
+use crate::partition::PartitionResult;
+use crate::{CoreId, Error, Result};
 use pulsive_core::{
-    ActorId, Clock, Context, DefId, Entity, EntityId, EntityStore, IndexMap, Model, Rng, Value,
-    ValueMap,
+    ActorId, Clock, Context, DefId, Entity, EntityId, EntityStore, IndexMap, Model, PendingWrite,
+    Rng, Symbol, Value, ValueMap, WriteSet,
 };
+use std::collections::{BTreeSet, HashSet};
 use std::sync::Arc;
 
 /// An immutable snapshot of the model at a point in time
@@ -209,7 +230,7 @@ impl ModelSnapshot {
     }
 
     /// Iterate over all global properties
-    pub fn globals_iter(&self) -> impl Iterator<Item = (&String, &Value)> {
+    pub fn globals_iter(&self) -> impl Iterator<Item = (&Symbol, &Value)> {
         self.globals.iter()
     }
 
@@ -253,6 +274,255 @@ impl ModelSnapshot {
     pub fn globals_arc(&self) -> Arc<ValueMap> {
         Arc::clone(&self.globals)
     }
+
+    // ========================================================================
+    // Partition Views
+    // ========================================================================
+
+    /// Create a view scoped to one partition
+    ///
+    /// The view holds the `owned` entities and the shared entities and
+    /// globals; clock, RNG and actors are copied whole.
+    pub fn partition(
+        &self,
+        core: CoreId,
+        owned: &[EntityId],
+        shared: &SharedState,
+    ) -> PartitionSnapshot {
+        let owned: BTreeSet<EntityId> = owned
+            .iter()
+            .copied()
+            .filter(|id| self.entities.get(*id).is_some())
+            .collect();
+        let entities = self
+            .entities
+            .subset(owned.iter().chain(&shared.entities).copied());
+        let globals = self
+            .globals
+            .iter()
+            .filter(|(key, _)| shared.globals.contains(*key))
+            .map(|(key, value)| (*key, value.clone()))
+            .collect();
+        PartitionSnapshot {
+            core,
+            owned,
+            shared: shared.clone(),
+            entities,
+            globals,
+            base: Arc::clone(&self.entities),
+            time: self.time.clone(),
+            rng: self.rng.clone(),
+            actors: self.actors.clone(),
+            version: self.version,
+        }
+    }
+
+    /// Create a view for every partition, in core order
+    pub fn partitions(
+        &self,
+        partitions: &PartitionResult,
+        shared: &SharedState,
+    ) -> Vec<PartitionSnapshot> {
+        partitions
+            .iter()
+            .map(|(core, owned)| self.partition(core, owned, shared))
+            .collect()
+    }
+}
+
+/// Entities and globals every partition may read but none may write
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SharedState {
+    /// Shared entities
+    pub entities: BTreeSet<EntityId>,
+    /// Shared globals
+    pub globals: HashSet<Symbol>,
+}
+
+impl SharedState {
+    /// Create an empty set of shared state
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Share an entity
+    pub fn with_entity(mut self, id: EntityId) -> Self {
+        self.entities.insert(id);
+        self
+    }
+
+    /// Share a global
+    pub fn with_global(mut self, key: impl Into<Symbol>) -> Self {
+        self.globals.insert(key.into());
+        self
+    }
+}
+
+/// A write made from a partition to state it does not own
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartitionViolation {
+    /// Position of the write in its WriteSet
+    pub index: usize,
+    /// What the write targeted
+    pub target: ConflictTarget,
+}
+
+impl std::fmt::Display for PartitionViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "write #{} to {}", self.index, self.target)
+    }
+}
+
+/// An immutable snapshot scoped to the entities of one partition
+///
+/// Created with [`ModelSnapshot::partition`]. Holds the entities assigned
+/// to a core plus the [`SharedState`]; reads of anything else see nothing.
+/// Globals are read-only in a view: only shared ones are visible, and no
+/// partition owns any.
+#[derive(Debug, Clone)]
+pub struct PartitionSnapshot {
+    /// Core the partition is assigned to
+    core: CoreId,
+    /// Entities the partition owns
+    owned: BTreeSet<EntityId>,
+    /// State readable but not writable
+    shared: SharedState,
+    /// Owned and shared entities
+    entities: EntityStore,
+    /// Shared globals
+    globals: ValueMap,
+    /// Entities of the whole snapshot, to tell other partitions' entities
+    /// from ones spawned in this one
+    base: Arc<EntityStore>,
+    /// Clock state
+    time: Clock,
+    /// RNG state
+    rng: Rng,
+    /// Actor contexts
+    actors: IndexMap<ActorId, Context>,
+    /// Version of the snapshot the view was created from
+    version: u64,
+}
+
+impl PartitionSnapshot {
+    /// Get the core the partition is assigned to
+    pub fn core(&self) -> CoreId {
+        self.core
+    }
+
+    /// Get the tick the snapshot was taken at
+    pub fn tick(&self) -> u64 {
+        self.time.tick
+    }
+
+    /// Get the version of the snapshot the view was created from
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Get the shared state
+    pub fn shared(&self) -> &SharedState {
+        &self.shared
+    }
+
+    /// Check if the partition owns an entity
+    pub fn owns(&self, id: EntityId) -> bool {
+        self.owned.contains(&id)
+    }
+
+    /// Iterate over the IDs of the owned entities, in ascending order
+    pub fn owned(&self) -> impl Iterator<Item = EntityId> + '_ {
+        self.owned.iter().copied()
+    }
+
+    /// Get an owned or shared entity by ID
+    pub fn get_entity(&self, id: EntityId) -> Option<&Entity> {
+        self.entities.get(id)
+    }
+
+    /// Iterate over the owned and shared entities
+    pub fn entities(&self) -> impl Iterator<Item = &Entity> {
+        self.entities.iter()
+    }
+
+    /// Get the number of owned and shared entities
+    pub fn entity_count(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Get a shared global property value
+    pub fn get_global(&self, key: &str) -> Option<&Value> {
+        self.globals.get(key)
+    }
+
+    /// Iterate over the shared global properties
+    pub fn globals_iter(&self) -> impl Iterator<Item = (&Symbol, &Value)> {
+        self.globals.iter()
+    }
+
+    /// Convert to an owned Model for a core to use
+    ///
+    /// Entities the core creates in it never take the ID of an entity
+    /// outside the partition.
+    pub fn to_model(&self) -> Model {
+        Model::from_snapshot_data(
+            self.entities.clone(),
+            self.globals.clone(),
+            self.time.clone(),
+            self.rng.clone(),
+            self.actors.clone(),
+        )
+    }
+
+    /// Find the writes made outside the partition
+    ///
+    /// Writes to entities of other partitions, to shared entities and to
+    /// globals are violations. Spawns are not, and neither are writes to
+    /// entities spawned in the partition.
+    pub fn violations(&self, writes: &WriteSet) -> Vec<PartitionViolation> {
+        writes
+            .writes()
+            .iter()
+            .enumerate()
+            .filter(|(_, write)| self.is_outside(write))
+            .map(|(index, write)| PartitionViolation {
+                index,
+                target: ConflictTarget::from_pending_write(write),
+            })
+            .collect()
+    }
+
+    /// Check that a core's writes stay inside the partition
+    ///
+    /// Fails with [`Error::CrossPartitionWrites`] listing the violations.
+    pub fn check_writes(&self, writes: &WriteSet) -> Result<()> {
+        let violations = self.violations(writes);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::CrossPartitionWrites {
+                core: self.core,
+                violations,
+            })
+        }
+    }
+
+    /// Check if a write targets state outside the partition
+    fn is_outside(&self, write: &PendingWrite) -> bool {
+        let entity_id = match write {
+            PendingWrite::SetProperty { entity_id, .. }
+            | PendingWrite::ModifyProperty { entity_id, .. }
+            | PendingWrite::AddFlag { entity_id, .. }
+            | PendingWrite::RemoveFlag { entity_id, .. }
+            | PendingWrite::AddFlagFor { entity_id, .. }
+            | PendingWrite::AddTag { entity_id, .. }
+            | PendingWrite::RemoveTag { entity_id, .. }
+            | PendingWrite::DestroyEntity { id: entity_id } => *entity_id,
+            PendingWrite::SetGlobal { .. } | PendingWrite::ModifyGlobal { .. } => return true,
+            PendingWrite::SpawnEntity { .. } => return false,
+        };
+        !self.owns(entity_id) && self.base.get(entity_id).is_some()
+    }
 }
 
 // ModelSnapshot is automatically Send + Sync because:
@@ -427,6 +697,60 @@ mod tests {
         assert_send_sync::<ModelSnapshot>();
     }
 
+    #[test]
+    fn test_partition_snapshot() {
+        let mut model = Model::new();
+        let ids: Vec<EntityId> = (0..4)
+            .map(|_| model.entities_mut().create("unit").id)
+            .collect();
+        model.set_global("tax_rate", 0.1f64);
+        model.set_global("secret", 1.0f64);
+        let snapshot = ModelSnapshot::new(&model, 1);
+        let shared = SharedState::new()
+            .with_entity(ids[1])
+            .with_global("tax_rate");
+
+        let view = snapshot.partition(CoreId(0), &[ids[0], ids[2]], &shared);
+        assert_eq!(view.entity_count(), 3);
+        assert!(view.get_entity(ids[3]).is_none());
+        assert!(view.owns(ids[0]) && !view.owns(ids[1]));
+        assert!(view.get_global("tax_rate").is_some());
+        assert!(view.get_global("secret").is_none());
+
+        let mut local = view.to_model();
+        let spawned = local.entities_mut().create("unit").id;
+        assert!(model.entities().get(spawned).is_none());
+
+        let mut writes = WriteSet::new();
+        writes.push(PendingWrite::SetProperty {
+            entity_id: ids[0],
+            key: "hp".to_string(),
+            value: Value::Int(5),
+        });
+        writes.push(PendingWrite::SetProperty {
+            entity_id: spawned,
+            key: "hp".to_string(),
+            value: Value::Int(5),
+        });
+        writes.push(PendingWrite::AddFlag {
+            entity_id: ids[1],
+            flag: DefId::new("seen"),
+        });
+        writes.push(PendingWrite::SetGlobal {
+            key: "tax_rate".to_string(),
+            value: Value::Float(0.2),
+        });
+        writes.push(PendingWrite::DestroyEntity { id: ids[3] });
+
+        let violations = view.violations(&writes);
+        let indices: Vec<usize> = violations.iter().map(|v| v.index).collect();
+        assert_eq!(indices, [2, 3, 4]);
+        assert!(matches!(
+            view.check_writes(&writes),
+            Err(Error::CrossPartitionWrites { core: CoreId(0), ref violations }) if violations.len() == 3
+        ));
+    }
+
     #[test]
     fn test_snapshot_globals_iteration() {
         let mut model = Model::new();
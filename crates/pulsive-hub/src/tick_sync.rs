@@ -15,8 +15,9 @@
 //! This ensures reproducible results when replaying simulations.
 
 use crate::core::{Core, CoreId};
-use crate::group::{CoreGroup, GroupId};
-use pulsive_core::{Model, Runtime, UpdateResult};
+use crate::divergence::CoreHash;
+use crate::group::{CoreFailure, CoreGroup, GroupId};
+use pulsive_core::{Model, Runtime, UpdateResult, WriteSet};
 
 /// A group where all cores stay synchronized at the same tick
 ///
@@ -34,6 +35,16 @@ pub struct TickSyncGroup {
     cores: Vec<Core>,
     /// Base seed for RNG
     base_seed: u64,
+    /// Failures not yet taken by the hub
+    failures: Vec<CoreFailure>,
+    /// Whether the first healthy core's writes are kept for the hub
+    write_logging: bool,
+    /// Whether each core's tick is hashed
+    hashing: bool,
+    /// Writes of the first healthy core during the last tick
+    writes: WriteSet,
+    /// Hashes not yet taken by the hub
+    hashes: Vec<CoreHash>,
 }
 
 impl TickSyncGroup {
@@ -44,6 +55,11 @@ impl TickSyncGroup {
             tick: 0,
             cores,
             base_seed,
+            failures: Vec::new(),
+            write_logging: false,
+            hashing: false,
+            writes: WriteSet::new(),
+            hashes: Vec::new(),
         }
     }
 
@@ -73,10 +89,21 @@ impl TickSyncGroup {
     }
 
     /// Add a core to this group
-    pub fn add_core(&mut self, core: Core) {
+    pub fn add_core(&mut self, mut core: Core) {
+        if self.write_logging || self.hashing {
+            core.runtime_mut().set_write_logging(true);
+        }
         self.cores.push(core);
     }
 
+    /// Record the writes of the cores' runtimes if the hub needs them
+    fn update_write_logging(&mut self) {
+        let enabled = self.write_logging || self.hashing;
+        for core in &mut self.cores {
+            core.runtime_mut().set_write_logging(enabled);
+        }
+    }
+
     /// Get a reference to the cores (for registering handlers)
     pub fn cores(&self) -> &[Core] {
         &self.cores
@@ -134,25 +161,71 @@ impl CoreGroup for TickSyncGroup {
     }
 
     fn load_model(&mut self, model: &Model) {
-        for core in &mut self.cores {
+        self.writes = WriteSet::new();
+        for core in self.cores.iter_mut().filter(|c| !c.is_quarantined()) {
             core.load_model(model.clone());
+            // Drop writes made outside a tick
+            core.runtime_mut().take_write_log();
         }
     }
 
     fn execute_tick(&mut self) -> Vec<UpdateResult> {
-        if self.cores.len() == 1 {
-            // Single core - direct execution, no overhead
-            let result = self.cores[0].tick();
-            vec![result]
-        } else {
-            // Multiple cores - for now, execute serially
-            // TODO: Add parallel execution with rayon when needed
-            self.cores.iter_mut().map(|core| core.tick()).collect()
+        // Execute serially, skipping quarantined cores
+        // TODO: Add parallel execution with rayon when needed
+        let mut results = Vec::with_capacity(self.cores.len());
+        for core in self.cores.iter_mut().filter(|c| !c.is_quarantined()) {
+            let tick = core.current_tick() + 1;
+            match core.try_tick() {
+                Ok(result) => {
+                    if self.write_logging || self.hashing {
+                        let writes = core.runtime_mut().take_write_log();
+                        if self.hashing {
+                            let hash = CoreHash::new(self.id, core.id, &writes, core.model());
+                            self.hashes.push(hash);
+                        }
+                        // Only the first healthy core's model is extracted
+                        if self.write_logging && results.is_empty() {
+                            self.writes = writes;
+                        }
+                    }
+                    results.push(result);
+                }
+                Err(error) => self.failures.push(CoreFailure {
+                    group: self.id,
+                    core: core.id,
+                    tick,
+                    error,
+                }),
+            }
         }
+        results
     }
 
     fn extract_models(&self) -> Vec<&Model> {
-        self.cores.iter().map(|core| core.model()).collect()
+        self.cores
+            .iter()
+            .filter(|core| !core.is_quarantined())
+            .map(|core| core.model())
+            .collect()
+    }
+
+    fn take_failures(&mut self) -> Vec<CoreFailure> {
+        std::mem::take(&mut self.failures)
+    }
+
+    fn quarantined(&self) -> Vec<CoreId> {
+        self.cores
+            .iter()
+            .filter(|core| core.is_quarantined())
+            .map(|core| core.id)
+            .collect()
+    }
+
+    fn release(&mut self, core: CoreId) -> bool {
+        self.cores
+            .iter_mut()
+            .find(|c| c.id == core)
+            .is_some_and(|c| c.release())
     }
 
     fn advance_tick(&mut self) {
@@ -161,6 +234,31 @@ impl CoreGroup for TickSyncGroup {
         // This ensures the RNG is seeded based on the model's actual tick, not the
         // group's tick counter. The formula is: hash(base_seed, core_id, model_tick)
     }
+
+    fn rewind(&mut self, model: &Model, ticks: u64) {
+        for core in &mut self.cores {
+            core.reset(model.clone());
+        }
+        self.tick = self.tick.saturating_sub(ticks);
+    }
+
+    fn set_write_logging(&mut self, enabled: bool) {
+        self.write_logging = enabled;
+        self.update_write_logging();
+    }
+
+    fn take_writes(&mut self) -> WriteSet {
+        std::mem::take(&mut self.writes)
+    }
+
+    fn set_hashing(&mut self, enabled: bool) {
+        self.hashing = enabled;
+        self.update_write_logging();
+    }
+
+    fn take_hashes(&mut self) -> Vec<CoreHash> {
+        std::mem::take(&mut self.hashes)
+    }
 }
 
 impl std::fmt::Debug for TickSyncGroup {
@@ -169,6 +267,7 @@ impl std::fmt::Debug for TickSyncGroup {
             .field("id", &self.id)
             .field("tick", &self.tick)
             .field("core_count", &self.cores.len())
+            .field("quarantined", &self.quarantined())
             .finish()
     }
 }
@@ -207,6 +306,38 @@ mod tests {
         assert_eq!(models[0].current_tick(), 1);
     }
 
+    #[test]
+    fn test_panicking_core_is_quarantined() {
+        let mut group = TickSyncGroup::with_core_count(GroupId(0), 2, 12345);
+        group.cores_mut()[1].runtime_mut().on_phase(
+            pulsive_core::TickPhase::Handlers,
+            "crash",
+            |_, _, _| panic!("handler crashed"),
+        );
+        group.load_model(&Model::new());
+
+        assert_eq!(group.execute_tick().len(), 1);
+        assert_eq!(group.extract_models().len(), 1);
+        assert_eq!(
+            group.take_failures(),
+            [CoreFailure {
+                group: GroupId(0),
+                core: CoreId(1),
+                tick: 1,
+                error: "handler crashed".to_string(),
+            }]
+        );
+        assert!(group.take_failures().is_empty());
+        assert_eq!(group.quarantined(), [CoreId(1)]);
+
+        // Quarantined cores are skipped until released
+        assert_eq!(group.execute_tick().len(), 1);
+        assert!(group.take_failures().is_empty());
+        assert!(group.release(CoreId(1)));
+        assert!(!group.release(CoreId(1)));
+        assert!(group.quarantined().is_empty());
+    }
+
     #[test]
     fn test_advance_tick() {
         let mut group = TickSyncGroup::single(GroupId(0), 12345);
@@ -0,0 +1,365 @@
+//! Undo and redo of applied WriteSets
+//!
+//! Editors and level designers built on pulsive apply changes as WriteSets.
+//! [`apply_undoable`] applies one like [`apply`](crate::apply) and records
+//! on an [`UndoStack`] what it overwrote: each entity and global it touched,
+//! as it was before. Undoing puts those back, recording the current state so
+//! the step can be redone.
+//!
+//! ```rust,ignore
+//! let mut undo = UndoStack::new().with_limit(100);
+//!
+//! // A drag in the editor moves several entities; undo it as one step
+//! undo.begin_group();
+//! for write_set in drag_updates {
+//!     apply_undoable(&write_set, &mut model, &mut undo);
+//! }
+//! undo.end_group();
+//!
+//! undo.undo(&mut model); // back to before the drag
+//! undo.redo(&mut model); // and forward again
+//! ```
+//!
+//! Entities are restored with their original IDs, so later steps that refer
+//! to a spawned entity still find it after an undo and redo.
+
+use crate::commit::apply;
+use pulsive_core::{
+    Entity, EntityId, IndexMap, Model, PendingWrite, Value, WriteSet, WriteSetResult,
+};
+use std::collections::VecDeque;
+
+/// The state an undo step restores
+#[derive(Debug, Clone, Default)]
+struct UndoStep {
+    /// Entities as they were (None: the entity didn't exist)
+    entities: IndexMap<EntityId, Option<Entity>>,
+    /// Globals as they were (None: the global wasn't set)
+    globals: IndexMap<String, Option<Value>>,
+}
+
+impl UndoStep {
+    /// Record the state of what a WriteSet is about to touch
+    fn capture(write_set: &WriteSet, model: &Model) -> Self {
+        let mut step = Self::default();
+        for write in write_set {
+            match write {
+                PendingWrite::SetProperty { entity_id, .. }
+                | PendingWrite::ModifyProperty { entity_id, .. }
+                | PendingWrite::AddFlag { entity_id, .. }
+                | PendingWrite::RemoveFlag { entity_id, .. }
+                | PendingWrite::AddFlagFor { entity_id, .. }
+                | PendingWrite::AddTag { entity_id, .. }
+                | PendingWrite::RemoveTag { entity_id, .. }
+                | PendingWrite::DestroyEntity { id: entity_id } => {
+                    step.entities
+                        .entry(*entity_id)
+                        .or_insert_with(|| model.entities().get(*entity_id).cloned());
+                }
+                PendingWrite::SetGlobal { key, .. } | PendingWrite::ModifyGlobal { key, .. } => {
+                    step.globals
+                        .entry(key.clone())
+                        .or_insert_with(|| model.globals().get(key).cloned());
+                }
+                // Known once applied
+                PendingWrite::SpawnEntity { .. } => {}
+            }
+        }
+        step
+    }
+
+    fn is_empty(&self) -> bool {
+        self.entities.is_empty() && self.globals.is_empty()
+    }
+
+    /// Add a later step, keeping the earliest state of everything
+    fn merge(&mut self, later: UndoStep) {
+        for (id, entity) in later.entities {
+            self.entities.entry(id).or_insert(entity);
+        }
+        for (key, value) in later.globals {
+            self.globals.entry(key).or_insert(value);
+        }
+    }
+
+    /// Put the recorded state back, returning the step that reverses it
+    fn restore(self, model: &mut Model) -> UndoStep {
+        let mut inverse = UndoStep::default();
+        for (id, entity) in self.entities {
+            inverse
+                .entities
+                .insert(id, model.entities().get(id).cloned());
+            match entity {
+                Some(entity) => model.entities_mut().insert(entity),
+                None => {
+                    model.entities_mut().remove(id);
+                }
+            }
+        }
+        for (key, value) in self.globals {
+            inverse
+                .globals
+                .insert(key.clone(), model.globals().get(&key).cloned());
+            match value {
+                Some(value) => {
+                    model.globals_mut().insert(key, value);
+                }
+                None => {
+                    model.globals_mut().remove(&key);
+                }
+            }
+        }
+        inverse
+    }
+}
+
+/// Undo and redo history of WriteSets applied with [`apply_undoable`]
+#[derive(Debug, Clone, Default)]
+pub struct UndoStack {
+    undo: VecDeque<UndoStep>,
+    redo: Vec<UndoStep>,
+    /// Most undo steps kept (None for no limit)
+    limit: Option<usize>,
+    /// Open group nesting depth
+    depth: usize,
+    /// Step collecting the writes of the open group
+    group: UndoStep,
+}
+
+impl UndoStack {
+    /// Create an empty stack with no step limit
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep at most `limit` undo steps, dropping the oldest
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self.trim();
+        self
+    }
+
+    /// Get the step limit
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Start grouping applied WriteSets into one undo step
+    ///
+    /// Groups nest; the step is recorded when the outermost group ends.
+    pub fn begin_group(&mut self) {
+        self.depth += 1;
+    }
+
+    /// End a group started with [`begin_group`](Self::begin_group)
+    pub fn end_group(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+        if self.depth == 0 {
+            let step = std::mem::take(&mut self.group);
+            self.push(step);
+        }
+    }
+
+    /// Check if a group is open
+    pub fn in_group(&self) -> bool {
+        self.depth > 0
+    }
+
+    fn record(&mut self, step: UndoStep) {
+        if self.in_group() {
+            self.group.merge(step);
+        } else {
+            self.push(step);
+        }
+    }
+
+    fn push(&mut self, step: UndoStep) {
+        if step.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push_back(step);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        if let Some(limit) = self.limit {
+            while self.undo.len() > limit {
+                self.undo.pop_front();
+            }
+        }
+    }
+
+    /// Undo the latest step
+    ///
+    /// Closes any open group first. Returns false if there was nothing to
+    /// undo.
+    pub fn undo(&mut self, model: &mut Model) -> bool {
+        self.close_groups();
+        let Some(step) = self.undo.pop_back() else {
+            return false;
+        };
+        self.redo.push(step.restore(model));
+        true
+    }
+
+    /// Redo the latest undone step
+    ///
+    /// Returns false if there was nothing to redo. Applying a WriteSet
+    /// after an undo discards the steps that could be redone.
+    pub fn redo(&mut self, model: &mut Model) -> bool {
+        self.close_groups();
+        let Some(step) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push_back(step.restore(model));
+        self.trim();
+        true
+    }
+
+    fn close_groups(&mut self) {
+        if self.in_group() {
+            self.depth = 1;
+            self.end_group();
+        }
+    }
+
+    /// Check if there is a step to undo
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || !self.group.is_empty()
+    }
+
+    /// Check if there is a step to redo
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Get the number of steps that can be undone
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Get the number of steps that can be redone
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    /// Forget all steps
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.group = UndoStep::default();
+        self.depth = 0;
+    }
+}
+
+/// Apply a WriteSet to a Model, recording an undo step
+///
+/// Applies like [`apply`](crate::apply). Inside a group, the step joins the
+/// group's.
+pub fn apply_undoable(
+    write_set: &WriteSet,
+    model: &mut Model,
+    undo: &mut UndoStack,
+) -> WriteSetResult {
+    let mut step = UndoStep::capture(write_set, model);
+    let result = apply(write_set, model);
+    for id in &result.spawned {
+        step.entities.entry(*id).or_insert(None);
+    }
+    undo.record(step);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::{DefId, ModifyOp, ValueMap};
+
+    fn set_global(key: &str, value: f64) -> WriteSet {
+        let mut ws = WriteSet::new();
+        ws.push(PendingWrite::SetGlobal {
+            key: key.to_string(),
+            value: Value::Float(value),
+        });
+        ws
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        let mut model = Model::new();
+        let mut undo = UndoStack::new();
+        let town = model.entities_mut().create("town").id;
+        model.entities_mut().get_mut(town).unwrap().set("pop", 10.0);
+
+        let mut ws = WriteSet::new();
+        ws.push(PendingWrite::ModifyProperty {
+            entity_id: town,
+            key: "pop".to_string(),
+            op: ModifyOp::Add,
+            value: 5.0,
+        });
+        ws.push(PendingWrite::SetProperty {
+            entity_id: town,
+            key: "name".to_string(),
+            value: Value::from("Ashford"),
+        });
+        ws.push(PendingWrite::SpawnEntity {
+            kind: DefId::new("farm"),
+            properties: ValueMap::new(),
+        });
+        let farm = apply_undoable(&ws, &mut model, &mut undo).spawned[0];
+
+        let mut ws = set_global("year", 1200.0);
+        ws.push(PendingWrite::DestroyEntity { id: town });
+        apply_undoable(&ws, &mut model, &mut undo);
+        assert_eq!(undo.undo_len(), 2);
+
+        assert!(undo.undo(&mut model));
+        assert_eq!(model.get_global("year"), None);
+        assert_eq!(
+            model.entities().get(town).unwrap().get_number("pop"),
+            Some(15.0)
+        );
+
+        assert!(undo.undo(&mut model));
+        let restored = model.entities().get(town).unwrap();
+        assert_eq!(restored.get_number("pop"), Some(10.0));
+        assert_eq!(restored.get("name"), None);
+        assert!(model.entities().get(farm).is_none());
+        assert!(!undo.undo(&mut model));
+
+        // Redo brings the farm back with its ID
+        assert!(undo.redo(&mut model));
+        assert!(model.entities().get(farm).is_some());
+        assert!(undo.redo(&mut model));
+        assert!(model.entities().get(town).is_none());
+        assert!(!undo.can_redo());
+    }
+
+    #[test]
+    fn test_groups_and_limit() {
+        let mut model = Model::new();
+        let mut undo = UndoStack::new().with_limit(2);
+
+        undo.begin_group();
+        for x in 1..=3 {
+            apply_undoable(&set_global("x", x as f64), &mut model, &mut undo);
+        }
+        undo.end_group();
+        assert_eq!(undo.undo_len(), 1);
+        undo.undo(&mut model);
+        assert_eq!(model.get_global("x"), None);
+
+        // A new change discards the redo
+        apply_undoable(&set_global("y", 1.0), &mut model, &mut undo);
+        assert!(!undo.can_redo());
+
+        apply_undoable(&set_global("y", 2.0), &mut model, &mut undo);
+        apply_undoable(&set_global("y", 3.0), &mut model, &mut undo);
+        assert_eq!(undo.undo_len(), 2);
+        undo.undo(&mut model);
+        undo.undo(&mut model);
+        assert_eq!(model.get_global("y").and_then(|v| v.as_float()), Some(1.0));
+    }
+}
@@ -0,0 +1,489 @@
+//! Statistical anomaly detection on recorded sessions
+//!
+//! Two kinds of series are computed from a journal:
+//!
+//! - **Event rates**: how often each event (or message kind) was processed
+//!   in every tick of the session. A tick whose count lies more than
+//!   [`AnomalyConfig::z_threshold`] standard deviations above the mean is a
+//!   spike; explicit per-event limits can be set as well.
+//! - **Property trajectories**: the value of every numeric entity property
+//!   and global after each tick, rebuilt from recorded writes (or read from
+//!   snapshots if the journal has none). Non-finite values are always
+//!   flagged, negative values for properties configured as non-negative,
+//!   and sudden jumps when a tick's change is a z-score outlier among the
+//!   property's changes.
+
+use crate::writes::advance_to;
+use pulsive_core::{
+    EntityId, Journal, JournalEntry, Model, Msg, PendingWrite, Tick, Value, ValueMap,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+/// Settings for anomaly detection
+#[derive(Debug, Clone)]
+pub struct AnomalyConfig {
+    /// Z-score above which a value is an outlier
+    pub z_threshold: f64,
+    /// Minimum number of samples in a series before z-scores are used
+    pub min_samples: usize,
+    /// Maximum count per tick for specific events
+    pub rate_limits: HashMap<String, u64>,
+    /// Properties and globals that must never go negative
+    pub non_negative: HashSet<String>,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            z_threshold: 3.0,
+            min_samples: 8,
+            rate_limits: HashMap::new(),
+            non_negative: HashSet::new(),
+        }
+    }
+}
+
+impl AnomalyConfig {
+    /// Create a config with default thresholds
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the z-score threshold
+    pub fn z_threshold(mut self, threshold: f64) -> Self {
+        self.z_threshold = threshold;
+        self
+    }
+
+    /// Set the minimum number of samples for z-score detection
+    pub fn min_samples(mut self, samples: usize) -> Self {
+        self.min_samples = samples;
+        self
+    }
+
+    /// Flag ticks where `event` is processed more than `limit` times
+    pub fn max_rate(mut self, event: impl Into<String>, limit: u64) -> Self {
+        self.rate_limits.insert(event.into(), limit);
+        self
+    }
+
+    /// Flag the property (or global) `key` whenever it goes negative
+    pub fn non_negative(mut self, key: impl Into<String>) -> Self {
+        self.non_negative.insert(key.into());
+        self
+    }
+}
+
+/// A numeric property tracked across the session
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PropertyRef {
+    /// A global property
+    Global(String),
+    /// A property on an entity (raw entity ID)
+    Entity(u64, String),
+}
+
+impl PropertyRef {
+    /// Get the property key
+    pub fn key(&self) -> &str {
+        match self {
+            PropertyRef::Global(key) | PropertyRef::Entity(_, key) => key,
+        }
+    }
+}
+
+impl fmt::Display for PropertyRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertyRef::Global(key) => write!(f, "global '{}'", key),
+            PropertyRef::Entity(id, key) => write!(f, "entity {} property '{}'", id, key),
+        }
+    }
+}
+
+/// What was unusual
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnomalyKind {
+    /// An event was processed unusually often
+    RateSpike {
+        /// Event name
+        event: String,
+        /// Count in the tick
+        count: u64,
+        /// Mean count per tick over the session
+        mean: f64,
+        /// Z-score of the count
+        z_score: f64,
+    },
+    /// An event exceeded its configured per-tick limit
+    RateLimitExceeded {
+        /// Event name
+        event: String,
+        /// Count in the tick
+        count: u64,
+        /// Configured limit
+        limit: u64,
+    },
+    /// A property became NaN or infinite
+    NonFinite {
+        /// The property
+        property: PropertyRef,
+        /// The value
+        value: f64,
+    },
+    /// A non-negative property went below zero
+    Negative {
+        /// The property
+        property: PropertyRef,
+        /// The value
+        value: f64,
+    },
+    /// A property changed by an unusually large amount
+    Jump {
+        /// The property
+        property: PropertyRef,
+        /// Change during the tick
+        delta: f64,
+        /// Z-score of the change
+        z_score: f64,
+    },
+}
+
+/// An anomaly found in a session
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anomaly {
+    /// Tick the anomaly occurred in
+    pub tick: Tick,
+    /// What was unusual
+    pub kind: AnomalyKind,
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tick {}: ", self.tick)?;
+        match &self.kind {
+            AnomalyKind::RateSpike {
+                event,
+                count,
+                mean,
+                z_score,
+            } => write!(
+                f,
+                "'{}' spiked to {} (mean {:.2}, z {:.1})",
+                event, count, mean, z_score
+            ),
+            AnomalyKind::RateLimitExceeded {
+                event,
+                count,
+                limit,
+            } => write!(f, "'{}' occurred {} times (limit {})", event, count, limit),
+            AnomalyKind::NonFinite { property, value } => {
+                write!(f, "{} is {}", property, value)
+            }
+            AnomalyKind::Negative { property, value } => {
+                write!(f, "{} went negative ({})", property, value)
+            }
+            AnomalyKind::Jump {
+                property,
+                delta,
+                z_score,
+            } => write!(f, "{} jumped by {} (z {:.1})", property, delta, z_score),
+        }
+    }
+}
+
+/// Name used for a message in rate series
+fn event_name(msg: &Msg) -> String {
+    match &msg.event_id {
+        Some(id) => id.to_string(),
+        None => format!("{:?}", msg.kind),
+    }
+}
+
+/// Mean and population standard deviation of a series
+fn mean_std(values: &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    (mean, variance.sqrt())
+}
+
+/// Detect anomalies in a journal
+pub(crate) fn detect(journal: &Journal, config: &AnomalyConfig) -> Vec<Anomaly> {
+    let mut anomalies = detect_rates(journal, config);
+    anomalies.extend(detect_trajectories(journal, config));
+    anomalies.sort_by_key(|a| a.tick);
+    anomalies
+}
+
+fn detect_rates(journal: &Journal, config: &AnomalyConfig) -> Vec<Anomaly> {
+    let stats = journal.stats();
+    let (Some(first), Some(last)) = (stats.first_tick, stats.last_tick) else {
+        return Vec::new();
+    };
+
+    let mut counts: BTreeMap<String, BTreeMap<Tick, u64>> = BTreeMap::new();
+    for entry in journal.entries() {
+        if let JournalEntry::Message { tick, msg, .. } = entry {
+            let name = event_name(msg);
+            *counts.entry(name).or_default().entry(*tick).or_insert(0) += 1;
+        }
+    }
+
+    let mut anomalies = Vec::new();
+    for (event, per_tick) in counts {
+        if let Some(&limit) = config.rate_limits.get(&event) {
+            for (&tick, &count) in per_tick.iter().filter(|(_, c)| **c > limit) {
+                anomalies.push(Anomaly {
+                    tick,
+                    kind: AnomalyKind::RateLimitExceeded {
+                        event: event.clone(),
+                        count,
+                        limit,
+                    },
+                });
+            }
+        }
+
+        let series: Vec<f64> = (first..=last)
+            .map(|t| per_tick.get(&t).copied().unwrap_or(0) as f64)
+            .collect();
+        if series.len() < config.min_samples {
+            continue;
+        }
+        let (mean, std) = mean_std(&series);
+        if std == 0.0 {
+            continue;
+        }
+        for (&tick, &count) in &per_tick {
+            let z_score = (count as f64 - mean) / std;
+            if z_score > config.z_threshold {
+                anomalies.push(Anomaly {
+                    tick,
+                    kind: AnomalyKind::RateSpike {
+                        event: event.clone(),
+                        count,
+                        mean,
+                        z_score,
+                    },
+                });
+            }
+        }
+    }
+    anomalies
+}
+
+/// Value of every numeric property after each tick it was observed
+type Trajectories = BTreeMap<PropertyRef, Vec<(Tick, f64)>>;
+
+fn detect_trajectories(journal: &Journal, config: &AnomalyConfig) -> Vec<Anomaly> {
+    let mut trajectories = Trajectories::new();
+
+    if journal.write_sets().next().is_some() {
+        let mut model = journal
+            .snapshots()
+            .first()
+            .map(|s| s.model.clone())
+            .unwrap_or_default();
+        let base_tick = model.current_tick();
+        observe_model(&mut trajectories, &model, base_tick);
+
+        for entry in journal.entries() {
+            if let JournalEntry::Writes { tick, writes, .. } = entry {
+                if *tick <= base_tick {
+                    continue;
+                }
+                advance_to(&mut model, *tick);
+                pulsive_hub::apply(writes, &mut model);
+                for write in writes.iter() {
+                    if let Some((property, value)) = written_value(&model, write) {
+                        trajectories
+                            .entry(property)
+                            .or_default()
+                            .push((*tick, value));
+                    }
+                }
+            }
+        }
+    } else {
+        for snapshot in journal.snapshots() {
+            observe_model(&mut trajectories, &snapshot.model, snapshot.tick);
+        }
+    }
+
+    let mut anomalies = Vec::new();
+    for (property, points) in trajectories {
+        let mut flagged_non_finite = false;
+        for &(tick, value) in &points {
+            let kind = if !value.is_finite() {
+                if flagged_non_finite {
+                    continue;
+                }
+                flagged_non_finite = true;
+                AnomalyKind::NonFinite {
+                    property: property.clone(),
+                    value,
+                }
+            } else if value < 0.0 && config.non_negative.contains(property.key()) {
+                AnomalyKind::Negative {
+                    property: property.clone(),
+                    value,
+                }
+            } else {
+                continue;
+            };
+            anomalies.push(Anomaly { tick, kind });
+        }
+
+        let deltas: Vec<(Tick, f64)> = points
+            .windows(2)
+            .map(|w| (w[1].0, w[1].1 - w[0].1))
+            .filter(|(_, d)| d.is_finite())
+            .collect();
+        if deltas.len() < config.min_samples {
+            continue;
+        }
+        let values: Vec<f64> = deltas.iter().map(|(_, d)| *d).collect();
+        let (mean, std) = mean_std(&values);
+        if std == 0.0 {
+            continue;
+        }
+        for (tick, delta) in deltas {
+            let z_score = (delta - mean).abs() / std;
+            if z_score > config.z_threshold {
+                anomalies.push(Anomaly {
+                    tick,
+                    kind: AnomalyKind::Jump {
+                        property: property.clone(),
+                        delta,
+                        z_score,
+                    },
+                });
+            }
+        }
+    }
+    anomalies
+}
+
+/// Record every numeric property of a model
+fn observe_model(trajectories: &mut Trajectories, model: &Model, tick: Tick) {
+    let mut observe = |property: PropertyRef, values: &ValueMap, key: &str| {
+        if let Some(value) = values.get(key).and_then(Value::as_float) {
+            trajectories
+                .entry(property)
+                .or_default()
+                .push((tick, value));
+        }
+    };
+    for key in model.globals().keys() {
+        observe(PropertyRef::Global(key.to_string()), model.globals(), key);
+    }
+    for entity in model.entities().iter() {
+        for key in entity.properties.keys() {
+            observe(
+                PropertyRef::Entity(entity.id.raw(), key.to_string()),
+                &entity.properties,
+                key,
+            );
+        }
+    }
+}
+
+/// Get the value a write left behind, if numeric
+fn written_value(model: &Model, write: &PendingWrite) -> Option<(PropertyRef, f64)> {
+    let (property, value) = match write {
+        PendingWrite::SetProperty { entity_id, key, .. }
+        | PendingWrite::ModifyProperty { entity_id, key, .. } => (
+            PropertyRef::Entity(entity_id.raw(), key.clone()),
+            entity_value(model, *entity_id, key)?,
+        ),
+        PendingWrite::SetGlobal { key, .. } | PendingWrite::ModifyGlobal { key, .. } => {
+            (PropertyRef::Global(key.clone()), model.get_global(key)?)
+        }
+        _ => return None,
+    };
+    Some((property, value.as_float()?))
+}
+
+fn entity_value<'m>(model: &'m Model, id: EntityId, key: &str) -> Option<&'m Value> {
+    model.entities().get(id)?.get(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Auditor;
+    use pulsive_core::{EntityRef, JournalConfig, Msg, WriteSet};
+
+    fn record_session() -> Journal {
+        let mut journal = Journal::with_config(JournalConfig {
+            recording_enabled: true,
+            record_writes: true,
+            ..Default::default()
+        });
+        let mut gold = 0.0;
+        for tick in 1..=20 {
+            journal.record_tick(tick);
+            let errors = if tick == 12 { 40 } else { tick % 2 };
+            for _ in 0..errors {
+                journal.record_message(tick, Msg::event("proxy_error", EntityRef::Global, tick));
+            }
+            gold += match tick {
+                15 => -500.0,
+                18 => f64::NAN,
+                _ => 10.0,
+            };
+            let mut writes = WriteSet::new();
+            writes.push(PendingWrite::SetGlobal {
+                key: "gold".to_string(),
+                value: Value::Float(gold),
+            });
+            journal.record_writes(tick, writes, 0);
+        }
+        journal
+    }
+
+    #[test]
+    fn test_detect_anomalies() {
+        let journal = record_session();
+        let config = AnomalyConfig::new()
+            .non_negative("gold")
+            .max_rate("proxy_error", 30);
+        let anomalies = detect(&journal, &config);
+
+        let at = |tick| anomalies.iter().filter(move |a| a.tick == tick);
+        assert!(at(12).any(|a| matches!(
+            &a.kind,
+            AnomalyKind::RateSpike { event, count: 40, .. } if event == "proxy_error"
+        )));
+        assert!(at(12).any(|a| matches!(a.kind, AnomalyKind::RateLimitExceeded { .. })));
+        assert!(at(15).any(|a| matches!(a.kind, AnomalyKind::Jump { .. })));
+        assert!(at(15).any(|a| matches!(a.kind, AnomalyKind::Negative { .. })));
+        assert_eq!(
+            anomalies
+                .iter()
+                .filter(|a| matches!(a.kind, AnomalyKind::NonFinite { .. }))
+                .map(|a| a.tick)
+                .collect::<Vec<_>>(),
+            vec![18]
+        );
+        // Regular alternating errors are not flagged
+        assert!(anomalies.iter().all(|a| a.tick >= 12));
+    }
+
+    #[test]
+    fn test_report_includes_anomalies() {
+        let journal = record_session();
+        let report = Auditor::new(&journal)
+            .with_anomaly_detection(AnomalyConfig::new())
+            .generate_report();
+        assert!(!report.anomalies.is_empty());
+        assert!(report.to_string().contains("Anomalies"));
+
+        assert!(Auditor::new(&journal)
+            .generate_report()
+            .anomalies
+            .is_empty());
+    }
+}
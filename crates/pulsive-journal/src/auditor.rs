@@ -1,17 +1,51 @@
 //! Auditing and analytics for journal data
 
+use crate::anomaly::{self, Anomaly, AnomalyConfig};
+use crate::RedactionPolicy;
 use pulsive_core::{ActorId, DefId, Journal, JournalEntry, MsgKind};
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 /// Auditor for querying and analyzing journal data
 pub struct Auditor<'a> {
-    journal: &'a Journal,
+    journal: Cow<'a, Journal>,
+    anomaly_config: Option<AnomalyConfig>,
 }
 
 impl<'a> Auditor<'a> {
     /// Create a new auditor for a journal
     pub fn new(journal: &'a Journal) -> Self {
-        Self { journal }
+        Self {
+            journal: Cow::Borrowed(journal),
+            anomaly_config: None,
+        }
+    }
+
+    /// Create an auditor over a redacted copy of a journal
+    ///
+    /// Reports and queries never see values removed by the policy, and
+    /// redacted actors do not appear in per-actor statistics.
+    pub fn with_redaction(journal: &Journal, policy: &RedactionPolicy) -> Self {
+        Self {
+            journal: Cow::Owned(policy.apply(journal)),
+            anomaly_config: None,
+        }
+    }
+
+    /// Include an anomaly pass in generated reports
+    pub fn with_anomaly_detection(mut self, config: AnomalyConfig) -> Self {
+        self.anomaly_config = Some(config);
+        self
+    }
+
+    /// Find outliers in event rates and property trajectories
+    pub fn detect_anomalies(&self, config: &AnomalyConfig) -> Vec<Anomaly> {
+        anomaly::detect(&self.journal, config)
+    }
+
+    /// Get the journal being audited
+    pub(crate) fn journal(&self) -> &Journal {
+        &self.journal
     }
 
     /// Generate a comprehensive audit report
@@ -62,6 +96,11 @@ impl<'a> Auditor<'a> {
             event_counts,
             actor_actions,
             commands_by_type,
+            anomalies: self
+                .anomaly_config
+                .as_ref()
+                .map(|config| self.detect_anomalies(config))
+                .unwrap_or_default(),
         }
     }
 
@@ -218,6 +257,54 @@ impl<'a> Auditor<'a> {
                 }
                 true
             }
+            JournalEntry::Writes { tick, .. } => {
+                if !query.include_writes {
+                    return false;
+                }
+                if let Some(start) = query.start_tick {
+                    if *tick < start {
+                        return false;
+                    }
+                }
+                if let Some(end) = query.end_tick {
+                    if *tick > end {
+                        return false;
+                    }
+                }
+                true
+            }
+            JournalEntry::Rng { tick, .. } => {
+                if !query.include_rng {
+                    return false;
+                }
+                if let Some(start) = query.start_tick {
+                    if *tick < start {
+                        return false;
+                    }
+                }
+                if let Some(end) = query.end_tick {
+                    if *tick > end {
+                        return false;
+                    }
+                }
+                true
+            }
+            JournalEntry::Provenance { tick, .. } => {
+                if !query.include_provenance {
+                    return false;
+                }
+                if let Some(start) = query.start_tick {
+                    if *tick < start {
+                        return false;
+                    }
+                }
+                if let Some(end) = query.end_tick {
+                    if *tick > end {
+                        return false;
+                    }
+                }
+                true
+            }
             JournalEntry::Metadata { tick, key, .. } => {
                 if !query.include_metadata {
                     return false;
@@ -264,6 +351,8 @@ pub struct AuditReport {
     pub actor_actions: HashMap<u64, u64>,
     /// Commands grouped by type
     pub commands_by_type: HashMap<String, u64>,
+    /// Anomalies found (empty unless detection is enabled)
+    pub anomalies: Vec<Anomaly>,
 }
 
 impl std::fmt::Display for AuditReport {
@@ -301,6 +390,13 @@ impl std::fmt::Display for AuditReport {
             }
         }
 
+        if !self.anomalies.is_empty() {
+            writeln!(f, "\nAnomalies:")?;
+            for anomaly in &self.anomalies {
+                writeln!(f, "  {}", anomaly)?;
+            }
+        }
+
         Ok(())
     }
 }
@@ -324,6 +420,12 @@ pub struct AuditQuery {
     pub include_snapshots: bool,
     /// Include metadata in results
     pub include_metadata: bool,
+    /// Include recorded write sets in results
+    pub include_writes: bool,
+    /// Include causality traces in results
+    pub include_provenance: bool,
+    /// Include RNG records in results
+    pub include_rng: bool,
     /// Filter metadata by key
     pub metadata_key: Option<String>,
 }
@@ -377,6 +479,24 @@ impl AuditQuery {
         self
     }
 
+    /// Include recorded write sets
+    pub fn with_writes(mut self) -> Self {
+        self.include_writes = true;
+        self
+    }
+
+    /// Include causality traces
+    pub fn with_provenance(mut self) -> Self {
+        self.include_provenance = true;
+        self
+    }
+
+    /// Include RNG records
+    pub fn with_rng(mut self) -> Self {
+        self.include_rng = true;
+        self
+    }
+
     /// Filter metadata by key
     pub fn metadata_with_key(mut self, key: impl Into<String>) -> Self {
         self.include_metadata = true;
@@ -451,6 +571,25 @@ mod tests {
         assert!(!events.is_empty());
     }
 
+    #[test]
+    fn test_with_redaction() {
+        let mut journal = create_test_journal();
+        let mut msg = Msg::event("login", EntityRef::Global, 10);
+        msg.actor = Some(ActorId::new(7));
+        journal.record_message(10, msg);
+        assert_eq!(
+            Auditor::new(&journal).actor_summary(ActorId::new(7)).total,
+            1
+        );
+
+        let policy = RedactionPolicy::new()
+            .redact_actor(ActorId::new(7))
+            .redact_metadata("user");
+        let auditor = Auditor::with_redaction(&journal, &policy);
+        assert_eq!(auditor.actor_summary(ActorId::new(7)).total, 0);
+        assert!(auditor.metadata().iter().all(|(_, v, _)| *v != "test_user"));
+    }
+
     #[test]
     fn test_metadata() {
         let journal = create_test_journal();
@@ -0,0 +1,572 @@
+//! Compact binary on-disk journal format
+//!
+//! Journals can grow far beyond what fits comfortably in memory during
+//! multi-hour sessions. This format lets a [`JournalWriter`] append entries
+//! while recording and a [`JournalReader`] stream them back without loading
+//! the whole file.
+//!
+//! # Layout
+//!
+//! ```text
+//! header:  magic "PJNL" | version: u16
+//! chunk:   payload_len: u32 | codec: u8 | raw_len: u32 | crc32: u32 | payload
+//! payload: (record_len: u32 | record)*     (compressed as a whole)
+//! ```
+//!
+//! All integers are little-endian. Records are bincode-encoded [`Record`]s.
+//! Each chunk names its own [`Codec`] (0 none, 1 LZ4, 2 zstd), so a reader
+//! handles files written with any of them; writers use zstd by default, and
+//! LZ4 trades some of its ratio for faster compression. The CRC covers the stored payload so corruption is detected before
+//! decompression. A chunk cut short at the end of the file (e.g. after a
+//! crash while recording) is reported as [`Error::Corrupted`]; every chunk
+//! before it is still readable.
+//!
+//! bincode is not self-describing, so the version is bumped whenever the
+//! encoding of a record changes (journal entries, messages, snapshots of
+//! the model) and files of any other version are rejected:
+//!
+//! 1. initial format
+//! 2. `Writes` entries
+//! 3. `Provenance` entries
+//! 4. message priorities
+//! 5. timed flag expiry on entities
+//! 6. generational entity IDs and slot-based entity storage
+//! 7. message IDs, parents and correlation IDs
+//! 8. `Rng` entries
+//! 9. entity tags
+//! 10. message cascade depth
+
+use crate::{Error, Result};
+use pulsive_core::{
+    Journal, JournalConfig, JournalEntry, JournalSink, Model, Runtime, Snapshot, Tick,
+};
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+
+const MAGIC: &[u8; 4] = b"PJNL";
+const VERSION: u16 = 10;
+const CHUNK_HEADER_LEN: usize = 13;
+
+/// Default uncompressed chunk size in bytes
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Compression applied to a chunk payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum Codec {
+    /// Stored as is
+    None = 0,
+    /// LZ4: fast, moderate ratio
+    Lz4 = 1,
+    /// Zstandard: better ratio, still fast to decompress
+    #[default]
+    Zstd = 2,
+}
+
+impl Codec {
+    fn from_byte(byte: u8) -> Result<Self> {
+        match byte {
+            0 => Ok(Codec::None),
+            1 => Ok(Codec::Lz4),
+            2 => Ok(Codec::Zstd),
+            other => Err(Error::Corrupted(format!("unknown chunk codec {}", other))),
+        }
+    }
+
+    fn compress(self, raw: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Codec::None => Ok(raw.to_vec()),
+            Codec::Lz4 => Ok(lz4_flex::compress(raw)),
+            Codec::Zstd => Ok(zstd::bulk::compress(raw, 0)?),
+        }
+    }
+
+    fn decompress(self, payload: Vec<u8>, raw_len: usize) -> Result<Vec<u8>> {
+        match self {
+            Codec::None => Ok(payload),
+            Codec::Lz4 => {
+                lz4_flex::decompress(&payload, raw_len).map_err(|e| Error::Corrupted(e.to_string()))
+            }
+            Codec::Zstd => zstd::bulk::decompress(&payload, raw_len)
+                .map_err(|e| Error::Corrupted(e.to_string())),
+        }
+    }
+}
+
+/// A single record in a binary journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Record {
+    /// A journal entry
+    Entry(JournalEntry),
+    /// A state snapshot, written just before its [`JournalEntry::Snapshot`]
+    Snapshot(Snapshot),
+}
+
+/// Streaming writer for the binary journal format
+///
+/// Records are buffered into chunks of roughly `chunk_size` bytes, each
+/// compressed and checksummed as it is written. Call [`JournalWriter::finish`]
+/// (or [`JournalWriter::flush`]) to write out the last partial chunk.
+pub struct JournalWriter<W: Write> {
+    writer: W,
+    chunk: Vec<u8>,
+    chunk_size: usize,
+    codec: Codec,
+    /// Number of journal entries written by [`JournalWriter::sync`]
+    synced: usize,
+    records: u64,
+}
+
+impl<W: Write> JournalWriter<W> {
+    /// Create a writer and write the file header
+    pub fn new(mut writer: W) -> Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        Ok(Self {
+            writer,
+            chunk: Vec::new(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            codec: Codec::default(),
+            synced: 0,
+            records: 0,
+        })
+    }
+
+    /// Set the uncompressed chunk size in bytes
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Enable or disable per-chunk compression (enabled by default)
+    ///
+    /// Enabling it selects the default codec, zstd.
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.codec = if compress {
+            Codec::default()
+        } else {
+            Codec::None
+        };
+        self
+    }
+
+    /// Set the codec chunks are compressed with ([`Codec::Zstd`] by default)
+    pub fn with_codec(mut self, codec: Codec) -> Self {
+        self.codec = codec;
+        self
+    }
+
+    /// Append a single record
+    pub fn write_record(&mut self, record: &Record) -> Result<()> {
+        let bytes = bincode::serialize(record).map_err(|e| Error::Serialization(e.to_string()))?;
+        self.chunk
+            .extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        self.chunk.extend_from_slice(&bytes);
+        self.records += 1;
+
+        if self.chunk.len() >= self.chunk_size {
+            self.write_chunk()?;
+        }
+        Ok(())
+    }
+
+    /// Append a journal entry
+    pub fn write_entry(&mut self, entry: &JournalEntry) -> Result<()> {
+        self.write_record(&Record::Entry(entry.clone()))
+    }
+
+    /// Append a snapshot
+    pub fn write_snapshot(&mut self, snapshot: &Snapshot) -> Result<()> {
+        self.write_record(&Record::Snapshot(snapshot.clone()))
+    }
+
+    /// Append all entries recorded in `journal` since the last call
+    ///
+    /// Call this periodically while recording (e.g. once per tick). Snapshot
+    /// entries are written together with their snapshot. Entries trimmed
+    /// from the journal before being synced (by `max_entries` or
+    /// `clear_before`) cannot be recovered, so use an unbounded journal or
+    /// sync more often than the limit is reached.
+    pub fn sync(&mut self, journal: &Journal) -> Result<()> {
+        let start = self.synced;
+        self.synced = journal.sync_to(self, start)?;
+        Ok(())
+    }
+
+    /// Number of records written so far
+    pub fn records_written(&self) -> u64 {
+        self.records
+    }
+
+    /// Write the buffered partial chunk and flush the underlying writer
+    pub fn flush(&mut self) -> Result<()> {
+        if !self.chunk.is_empty() {
+            self.write_chunk()?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Flush and return the underlying writer
+    pub fn finish(mut self) -> Result<W> {
+        self.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_chunk(&mut self) -> Result<()> {
+        let raw_len = self.chunk.len() as u32;
+        let codec = self.codec;
+        let payload = codec.compress(&self.chunk)?;
+
+        self.writer
+            .write_all(&(payload.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&[codec as u8])?;
+        self.writer.write_all(&raw_len.to_le_bytes())?;
+        self.writer.write_all(&crc32(&payload).to_le_bytes())?;
+        self.writer.write_all(&payload)?;
+        self.chunk.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> JournalSink for JournalWriter<W> {
+    type Error = Error;
+
+    fn append_entry(&mut self, entry: &JournalEntry) -> Result<()> {
+        self.write_entry(entry)
+    }
+
+    fn append_snapshot(&mut self, snapshot: &Snapshot) -> Result<()> {
+        self.write_snapshot(snapshot)
+    }
+}
+
+/// Streaming reader for the binary journal format
+///
+/// Iterating the reader yields one [`Record`] at a time, decoding a chunk
+/// only when the previous one is exhausted.
+pub struct JournalReader<R: Read> {
+    reader: R,
+    chunk: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<R: Read> JournalReader<R> {
+    /// Create a reader and validate the file header
+    pub fn new(mut reader: R) -> Result<Self> {
+        let mut header = [0u8; 6];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| Error::Corrupted("missing journal header".to_string()))?;
+        if &header[..4] != MAGIC {
+            return Err(Error::Corrupted("not a binary journal".to_string()));
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != VERSION {
+            return Err(Error::Corrupted(format!(
+                "unsupported journal version {}",
+                version
+            )));
+        }
+        Ok(Self {
+            reader,
+            chunk: Vec::new(),
+            pos: 0,
+            done: false,
+        })
+    }
+
+    /// Read the next record, or `None` at the end of the file
+    pub fn next_record(&mut self) -> Result<Option<Record>> {
+        if self.pos >= self.chunk.len() && !self.read_chunk()? {
+            return Ok(None);
+        }
+
+        let len_bytes = self
+            .chunk
+            .get(self.pos..self.pos + 4)
+            .ok_or_else(|| Error::Corrupted("truncated record length".to_string()))?;
+        let len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+        let start = self.pos + 4;
+        let bytes = self
+            .chunk
+            .get(start..start + len)
+            .ok_or_else(|| Error::Corrupted("truncated record".to_string()))?;
+        let record =
+            bincode::deserialize(bytes).map_err(|e| Error::Serialization(e.to_string()))?;
+        self.pos = start + len;
+        Ok(Some(record))
+    }
+
+    /// Read the whole file into an in-memory journal
+    ///
+    /// Useful for short recordings that should be inspected with the
+    /// [`crate::Auditor`] or [`crate::Replayer`].
+    pub fn read_journal(self) -> Result<Journal> {
+        let mut entries = Vec::new();
+        let mut snapshots = Vec::new();
+        for record in self {
+            match record? {
+                Record::Entry(entry) => entries.push(entry),
+                Record::Snapshot(snapshot) => snapshots.push(snapshot),
+            }
+        }
+        let config = JournalConfig {
+            max_snapshots: 0,
+            ..Default::default()
+        };
+        Ok(Journal::from_parts(config, entries, snapshots))
+    }
+
+    /// Replay the recording up to `tick` without loading the whole file
+    ///
+    /// Restores the newest snapshot at or before `tick` and re-sends the
+    /// messages recorded after it, like [`crate::Replayer::goto`]. Only the
+    /// messages since the latest snapshot are held in memory.
+    pub fn replay_to(self, model: &mut Model, runtime: &mut Runtime, tick: Tick) -> Result<()> {
+        model.restore(Model::new());
+        let mut base_tick = 0;
+        let mut pending = Vec::new();
+
+        for record in self {
+            match record? {
+                Record::Snapshot(snapshot) if snapshot.tick <= tick => {
+                    model.restore(snapshot.model);
+                    base_tick = snapshot.tick;
+                    pending.clear();
+                }
+                Record::Entry(JournalEntry::Message { tick: t, msg, .. }) => {
+                    if t > tick {
+                        break;
+                    }
+                    if t > base_tick {
+                        pending.push(msg);
+                    }
+                }
+                Record::Entry(JournalEntry::TickBoundary { tick: t }) if t > tick => break,
+                _ => {}
+            }
+        }
+
+        for msg in pending {
+            runtime.send(msg);
+        }
+        runtime.process_queue(model);
+        Ok(())
+    }
+
+    /// Load the next chunk, returning `false` at a clean end of file
+    fn read_chunk(&mut self) -> Result<bool> {
+        if self.done {
+            return Ok(false);
+        }
+
+        let mut header = [0u8; CHUNK_HEADER_LEN];
+        match read_full(&mut self.reader, &mut header)? {
+            0 => {
+                self.done = true;
+                return Ok(false);
+            }
+            CHUNK_HEADER_LEN => {}
+            _ => {
+                self.done = true;
+                return Err(Error::Corrupted("truncated chunk header".to_string()));
+            }
+        }
+
+        let payload_len = u32::from_le_bytes(header[0..4].try_into().unwrap()) as usize;
+        let codec = Codec::from_byte(header[4])?;
+        let raw_len = u32::from_le_bytes(header[5..9].try_into().unwrap()) as usize;
+        let crc = u32::from_le_bytes(header[9..13].try_into().unwrap());
+
+        let mut payload = vec![0u8; payload_len];
+        if read_full(&mut self.reader, &mut payload)? != payload_len {
+            self.done = true;
+            return Err(Error::Corrupted("truncated chunk".to_string()));
+        }
+        if crc32(&payload) != crc {
+            self.done = true;
+            return Err(Error::Corrupted("chunk checksum mismatch".to_string()));
+        }
+
+        self.chunk = codec.decompress(payload, raw_len)?;
+        self.pos = 0;
+        Ok(!self.chunk.is_empty())
+    }
+}
+
+impl<R: Read> Iterator for JournalReader<R> {
+    type Item = Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+/// Read until `buf` is full or the reader is exhausted, returning bytes read
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// CRC-32 (IEEE) lookup table
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// Compute the CRC-32 (IEEE) checksum of `data`
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        CRC_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record_session(ticks: usize) -> Journal {
+        let mut model = Model::new();
+        let mut runtime = Runtime::new();
+        let mut journal = Journal::with_config(JournalConfig {
+            recording_enabled: true,
+            snapshot_interval: 5,
+            max_snapshots: 0,
+            ..Default::default()
+        });
+        for _ in 0..ticks {
+            runtime.tick_with_journal(&mut model, &mut journal);
+        }
+        journal
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let journal = record_session(20);
+        let mut writer = JournalWriter::new(Vec::new()).unwrap().with_chunk_size(256);
+        writer.sync(&journal).unwrap();
+        let bytes = writer.finish().unwrap();
+
+        let restored = JournalReader::new(bytes.as_slice())
+            .unwrap()
+            .read_journal()
+            .unwrap();
+        assert_eq!(restored.entries().len(), journal.entries().len());
+        assert_eq!(restored.snapshots().len(), journal.snapshots().len());
+        assert_eq!(restored.stats().last_tick, journal.stats().last_tick);
+    }
+
+    #[test]
+    fn test_codecs() {
+        let journal = record_session(20);
+        let mut sizes = Vec::new();
+        for codec in [Codec::None, Codec::Lz4, Codec::Zstd] {
+            let mut writer = JournalWriter::new(Vec::new())
+                .unwrap()
+                .with_chunk_size(4096)
+                .with_codec(codec);
+            writer.sync(&journal).unwrap();
+            let bytes = writer.finish().unwrap();
+            // The first chunk header follows the file header
+            assert_eq!(bytes[6 + 4], codec as u8);
+            sizes.push(bytes.len());
+
+            let restored = JournalReader::new(bytes.as_slice())
+                .unwrap()
+                .read_journal()
+                .unwrap();
+            assert_eq!(restored.entries().len(), journal.entries().len());
+        }
+        assert!(sizes[2] < sizes[0] && sizes[1] < sizes[0]);
+    }
+
+    #[test]
+    fn test_sync_appends_incrementally() {
+        let mut model = Model::new();
+        let mut runtime = Runtime::new();
+        let mut journal = Journal::new();
+        journal.start_recording();
+        let mut writer = JournalWriter::new(Vec::new())
+            .unwrap()
+            .with_compression(false);
+
+        for _ in 0..10 {
+            runtime.tick_with_journal(&mut model, &mut journal);
+            writer.sync(&journal).unwrap();
+        }
+        assert_eq!(writer.records_written(), journal.entries().len() as u64);
+    }
+
+    #[test]
+    fn test_replay_to_streams() {
+        let journal = record_session(20);
+        let mut writer = JournalWriter::new(Vec::new()).unwrap();
+        writer.sync(&journal).unwrap();
+        let bytes = writer.finish().unwrap();
+
+        let mut model = Model::new();
+        let mut runtime = Runtime::new();
+        JournalReader::new(bytes.as_slice())
+            .unwrap()
+            .replay_to(&mut model, &mut runtime, 12)
+            .unwrap();
+        assert!(model.current_tick() >= 10);
+    }
+
+    #[test]
+    fn test_detects_corruption() {
+        let journal = record_session(10);
+        let mut writer = JournalWriter::new(Vec::new()).unwrap();
+        writer.sync(&journal).unwrap();
+        let mut bytes = writer.finish().unwrap();
+
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+        let result: Result<Vec<_>> = JournalReader::new(bytes.as_slice()).unwrap().collect();
+        assert!(matches!(result, Err(Error::Corrupted(_))));
+
+        bytes.truncate(last);
+        let result: Result<Vec<_>> = JournalReader::new(bytes.as_slice()).unwrap().collect();
+        assert!(matches!(result, Err(Error::Corrupted(_))));
+
+        assert!(JournalReader::new(&b"nope"[..]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_other_versions() {
+        let mut bytes = JournalWriter::new(Vec::new()).unwrap().finish().unwrap();
+        bytes[4..6].copy_from_slice(&1u16.to_le_bytes());
+        let result = JournalReader::new(bytes.as_slice());
+        assert!(matches!(result, Err(Error::Corrupted(e)) if e.contains("version 1")));
+    }
+}
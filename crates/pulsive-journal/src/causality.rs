@@ -0,0 +1,307 @@
+//! Causality tracing between recorded messages
+//!
+//! With [`JournalConfig::record_causality`](pulsive_core::JournalConfig)
+//! enabled, every message entry is followed by a provenance entry listing
+//! the handlers that processed it, the writes each effect made, and the
+//! events each effect emitted or scheduled. Emitted events only become
+//! messages once the caller sends them back into the runtime, so a later
+//! event message is linked to the first outstanding emitted event with the
+//! same event ID, target and parameters (scheduled events once their delay
+//! has passed).
+
+use crate::Auditor;
+use pulsive_core::{DefId, EntityRef, HandlerTrace, JournalEntry, Tick, ValueMap};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+/// Position of an entry in the journal
+///
+/// Entry IDs are indices into [`Journal::entries`](pulsive_core::Journal::entries)
+/// and shift when old entries are trimmed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EntryId(pub usize);
+
+impl fmt::Display for EntryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "entry#{}", self.0)
+    }
+}
+
+/// A cause-and-effect link between two message entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CausalLink {
+    /// The message whose handlers emitted the event
+    pub cause: EntryId,
+    /// The event message that resulted
+    pub effect: EntryId,
+}
+
+/// The causality chain around a message entry
+#[derive(Debug, Clone)]
+pub struct CausalTrace<'a> {
+    /// The traced message entry
+    pub entry: EntryId,
+    /// Handlers that processed the message (empty if not traced)
+    pub handlers: &'a [HandlerTrace],
+    /// Messages that led to this one, nearest cause first
+    pub causes: Vec<EntryId>,
+    /// Messages caused by this one, directly or transitively, breadth-first
+    pub consequences: Vec<CausalLink>,
+}
+
+impl CausalTrace<'_> {
+    /// Get the message that started the chain
+    pub fn root(&self) -> EntryId {
+        self.causes.last().copied().unwrap_or(self.entry)
+    }
+}
+
+/// An emitted or scheduled event not yet matched to a message
+struct Outstanding<'a> {
+    origin: usize,
+    event: &'a DefId,
+    target: &'a EntityRef,
+    params: &'a ValueMap,
+    due: Tick,
+}
+
+/// Links between messages and their provenance, built from a journal
+#[derive(Default)]
+struct CausalGraph {
+    parent: HashMap<usize, usize>,
+    children: HashMap<usize, Vec<usize>>,
+    /// Provenance entry for each message entry
+    provenance: HashMap<usize, usize>,
+    /// Message entry for each provenance entry
+    message_of: HashMap<usize, usize>,
+}
+
+impl CausalGraph {
+    fn build(entries: &[JournalEntry]) -> Self {
+        let mut graph = Self::default();
+        let mut messages: HashMap<(Tick, u64), usize> = HashMap::new();
+        let mut outstanding: Vec<Outstanding> = Vec::new();
+
+        for (index, entry) in entries.iter().enumerate() {
+            match entry {
+                JournalEntry::Message { tick, msg, seq } => {
+                    messages.insert((*tick, *seq), index);
+                    let Some(event_id) = &msg.event_id else {
+                        continue;
+                    };
+                    let matched = outstanding.iter().position(|o| {
+                        o.event == event_id
+                            && *o.target == msg.target
+                            && *o.params == msg.params
+                            && *tick >= o.due
+                    });
+                    if let Some(position) = matched {
+                        let origin = outstanding.remove(position).origin;
+                        graph.parent.insert(index, origin);
+                        graph.children.entry(origin).or_default().push(index);
+                    }
+                }
+                JournalEntry::Provenance {
+                    tick,
+                    seq,
+                    handlers,
+                } => {
+                    let Some(&origin) = messages.get(&(*tick, *seq)) else {
+                        continue;
+                    };
+                    graph.provenance.insert(origin, index);
+                    graph.message_of.insert(index, origin);
+
+                    for effect in handlers.iter().flat_map(|h| &h.effects) {
+                        for (event, target, params) in &effect.emitted {
+                            outstanding.push(Outstanding {
+                                origin,
+                                event,
+                                target,
+                                params,
+                                due: *tick,
+                            });
+                        }
+                        for (event, target, delay, params) in &effect.scheduled {
+                            outstanding.push(Outstanding {
+                                origin,
+                                event,
+                                target,
+                                params,
+                                due: tick + delay,
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        graph
+    }
+}
+
+impl Auditor<'_> {
+    /// Walk the causality chain of a message entry backward and forward
+    ///
+    /// Passing a provenance entry traces its message. Returns `None` if
+    /// the entry is out of range or not a message.
+    pub fn trace(&self, entry: EntryId) -> Option<CausalTrace<'_>> {
+        let entries = self.journal().entries();
+        let graph = CausalGraph::build(entries);
+
+        let index = match entries.get(entry.0)? {
+            JournalEntry::Message { .. } => entry.0,
+            JournalEntry::Provenance { .. } => *graph.message_of.get(&entry.0)?,
+            _ => return None,
+        };
+
+        let handlers = match graph.provenance.get(&index).map(|&i| &entries[i]) {
+            Some(JournalEntry::Provenance { handlers, .. }) => handlers.as_slice(),
+            _ => &[],
+        };
+
+        let mut causes = Vec::new();
+        let mut current = index;
+        while let Some(&parent) = graph.parent.get(&current) {
+            causes.push(EntryId(parent));
+            current = parent;
+        }
+
+        let mut consequences = Vec::new();
+        let mut queue = VecDeque::from([index]);
+        while let Some(cause) = queue.pop_front() {
+            for &effect in graph.children.get(&cause).into_iter().flatten() {
+                consequences.push(CausalLink {
+                    cause: EntryId(cause),
+                    effect: EntryId(effect),
+                });
+                queue.push_back(effect);
+            }
+        }
+
+        Some(CausalTrace {
+            entry: EntryId(index),
+            handlers,
+            causes,
+            consequences,
+        })
+    }
+
+    /// Find the entry ID of the message recorded at `tick` with sequence `seq`
+    pub fn message_entry(&self, tick: Tick, seq: u64) -> Option<EntryId> {
+        self.journal()
+            .entries()
+            .iter()
+            .position(|e| {
+                matches!(e, JournalEntry::Message { tick: t, seq: s, .. } if *t == tick && *s == seq)
+            })
+            .map(EntryId)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::{
+        Effect, EventHandler, Expr, HandlerId, Journal, JournalConfig, Model, ModifyOp, Msg,
+        Runtime,
+    };
+
+    fn cascade_runtime() -> Runtime {
+        let mut runtime = Runtime::new();
+        runtime.on_event(EventHandler {
+            event_id: DefId::new("attack"),
+            condition: None,
+            effects: vec![
+                Effect::ModifyGlobal {
+                    property: "attacks".to_string(),
+                    op: ModifyOp::Add,
+                    value: Expr::lit(1.0),
+                },
+                Effect::EmitEvent {
+                    event: DefId::new("damaged"),
+                    target: EntityRef::Global,
+                    params: vec![],
+                },
+            ],
+            priority: 0,
+        });
+        runtime.on_event(EventHandler {
+            event_id: DefId::new("damaged"),
+            condition: None,
+            effects: vec![Effect::SetGlobal {
+                property: "hurt".to_string(),
+                value: Expr::lit(true),
+            }],
+            priority: 0,
+        });
+        runtime
+    }
+
+    /// Record an attack, then feed its emitted events back in the next tick
+    fn record_cascade() -> Journal {
+        let mut model = Model::new();
+        let mut runtime = cascade_runtime();
+        let mut journal = Journal::with_config(JournalConfig {
+            recording_enabled: true,
+            record_causality: true,
+            ..Default::default()
+        });
+
+        runtime.send(Msg::event("attack", EntityRef::Global, 1));
+        let result = runtime.tick_with_journal(&mut model, &mut journal);
+        for (event, target, params) in result.effect_result.emitted_events {
+            let mut msg = Msg::event(event, target, 2);
+            msg.params = params;
+            runtime.send(msg);
+        }
+        runtime.tick_with_journal(&mut model, &mut journal);
+        journal
+    }
+
+    #[test]
+    fn test_trace_forward_and_backward() {
+        let journal = record_cascade();
+        let auditor = Auditor::new(&journal);
+
+        // Queued messages are processed before each tick's Tick message
+        let attack = auditor.message_entry(1, 0).unwrap();
+        let damaged = auditor.message_entry(2, 0).unwrap();
+
+        let trace = auditor.trace(attack).unwrap();
+        assert!(trace.causes.is_empty());
+        assert_eq!(
+            trace.consequences,
+            vec![CausalLink {
+                cause: attack,
+                effect: damaged,
+            }]
+        );
+        assert_eq!(trace.handlers.len(), 1);
+        assert_eq!(
+            trace.handlers[0].handler,
+            HandlerId::Event {
+                event_id: DefId::new("attack"),
+                index: 0,
+            }
+        );
+        assert_eq!(trace.handlers[0].effects[0].writes.len(), 1);
+        assert_eq!(trace.handlers[0].effects[1].emitted.len(), 1);
+
+        let trace = auditor.trace(damaged).unwrap();
+        assert_eq!(trace.causes, vec![attack]);
+        assert_eq!(trace.root(), attack);
+        assert!(trace.consequences.is_empty());
+    }
+
+    #[test]
+    fn test_trace_non_message() {
+        let journal = record_cascade();
+        let auditor = Auditor::new(&journal);
+
+        // First entry is a tick boundary
+        assert!(auditor.trace(EntryId(0)).is_none());
+        assert!(auditor.trace(EntryId(usize::MAX)).is_none());
+    }
+}
@@ -0,0 +1,162 @@
+//! Compaction of long-running journals
+//!
+//! [`Journal::compact`] can only drop entries up to the newest real
+//! snapshot before the compaction cutoff. The [`Compactor`] first rebuilds
+//! the state at the cutoff and stores it as a synthetic snapshot, so every
+//! message older than [`JournalConfig::compact_after_ticks`](pulsive_core::JournalConfig)
+//! can be collapsed while the retained window stays replayable.
+
+use crate::writes::advance_to;
+use crate::{Replayer, Result, WriteReplayer};
+use pulsive_core::{Journal, Model, Runtime, SnapshotId, Tick};
+
+/// Compacts a journal into synthetic snapshots
+pub struct Compactor<'a> {
+    journal: &'a mut Journal,
+}
+
+impl<'a> Compactor<'a> {
+    /// Create a compactor for a journal
+    pub fn new(journal: &'a mut Journal) -> Self {
+        Self { journal }
+    }
+
+    /// Compact using recorded writes to rebuild the state at the cutoff
+    ///
+    /// Journals without write-level recording are compacted up to their
+    /// newest snapshot before the cutoff instead.
+    pub fn compact(&mut self) -> Result<CompactionReport> {
+        self.compact_with(|journal, cutoff| {
+            let replayer = WriteReplayer::new(journal);
+            if !replayer.has_writes() {
+                return Ok(None);
+            }
+            replayer.reconstruct(cutoff).map(Some)
+        })
+    }
+
+    /// Compact by re-executing recorded messages up to the cutoff
+    pub fn compact_replaying(&mut self, runtime: &mut Runtime) -> Result<CompactionReport> {
+        self.compact_with(|journal, cutoff| {
+            let mut model = Model::new();
+            Replayer::new(journal).goto(&mut model, runtime, cutoff)?;
+            Ok(Some(model))
+        })
+    }
+
+    fn compact_with(
+        &mut self,
+        rebuild: impl FnOnce(&Journal, Tick) -> Result<Option<Model>>,
+    ) -> Result<CompactionReport> {
+        let Some(cutoff) = self.journal.compaction_cutoff() else {
+            return Ok(CompactionReport::default());
+        };
+
+        let mut synthetic_snapshot = None;
+        let covered = self
+            .journal
+            .snapshot_at_or_before(cutoff)
+            .is_some_and(|s| s.tick == cutoff);
+        if !covered {
+            if let Some(mut model) = rebuild(self.journal, cutoff)? {
+                advance_to(&mut model, cutoff);
+                synthetic_snapshot = Some(self.journal.insert_snapshot(model));
+            }
+        }
+
+        Ok(CompactionReport {
+            cutoff: Some(cutoff),
+            synthetic_snapshot,
+            entries_removed: self.journal.compact(),
+        })
+    }
+}
+
+/// Result of compacting a journal
+#[derive(Debug, Clone, Default)]
+pub struct CompactionReport {
+    /// Tick before which entries were compacted (`None` if compaction is
+    /// disabled or the journal is too short)
+    pub cutoff: Option<Tick>,
+    /// Snapshot inserted at the cutoff, if one was rebuilt
+    pub synthetic_snapshot: Option<SnapshotId>,
+    /// Number of entries removed
+    pub entries_removed: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::{DefId, Effect, Expr, JournalConfig, JournalEntry, ModifyOp, TickHandler};
+
+    fn income_runtime() -> Runtime {
+        let mut runtime = Runtime::new();
+        runtime.on_tick(TickHandler {
+            id: DefId::new("income"),
+            condition: None,
+            target_kind: None,
+            effects: vec![Effect::ModifyGlobal {
+                property: "gold".to_string(),
+                op: ModifyOp::Add,
+                value: Expr::lit(5.0),
+            }],
+            priority: 0,
+        });
+        runtime
+    }
+
+    fn record(record_writes: bool) -> (Journal, Model) {
+        let mut model = Model::new();
+        let mut runtime = income_runtime();
+        let mut journal = Journal::with_config(JournalConfig {
+            recording_enabled: true,
+            record_writes,
+            snapshot_interval: 8,
+            compact_after_ticks: 5,
+            ..Default::default()
+        });
+        for _ in 0..20 {
+            runtime.tick_with_journal(&mut model, &mut journal);
+        }
+        (journal, model)
+    }
+
+    fn gold(model: &Model) -> Option<f64> {
+        model.get_global("gold")?.as_float()
+    }
+
+    #[test]
+    fn test_compact_from_writes() {
+        let (mut journal, model) = record(true);
+
+        let report = Compactor::new(&mut journal).compact().unwrap();
+        assert_eq!(report.cutoff, Some(15));
+        assert!(report.synthetic_snapshot.is_some());
+        assert!(report.entries_removed > 0);
+        assert!(journal
+            .entries()
+            .iter()
+            .all(|e| matches!(e, JournalEntry::Snapshot { .. }) || e.tick() > 15));
+
+        let snapshot = journal.snapshot_at_or_before(15).unwrap();
+        assert_eq!(snapshot.tick, 15);
+        assert_eq!(gold(&snapshot.model), Some(75.0));
+
+        let rebuilt = WriteReplayer::new(&journal).reconstruct(20).unwrap();
+        assert_eq!(gold(&rebuilt), gold(&model));
+    }
+
+    #[test]
+    fn test_compact_replaying() {
+        let (mut journal, _) = record(false);
+
+        let report = Compactor::new(&mut journal)
+            .compact_replaying(&mut income_runtime())
+            .unwrap();
+        let id = report.synthetic_snapshot.unwrap();
+        let snapshot = journal.get_snapshot(id).unwrap();
+        assert_eq!(snapshot.tick, 15);
+        // Snapshot at 8 (gold 40) plus seven replayed ticks
+        assert_eq!(gold(&snapshot.model), Some(75.0));
+    }
+}
@@ -0,0 +1,468 @@
+//! Comparing two recorded sessions
+//!
+//! Steps two journals forward in lockstep and hashes the model after every
+//! tick. The first tick where the hashes differ is reported together with a
+//! structured diff of entities and globals, which is usually enough to spot
+//! a desync between a client and a server recording, or between two
+//! versions of the handlers.
+
+use crate::writes::advance_to;
+use crate::{Auditor, Error, Result};
+use pulsive_core::{
+    DefId, EntityId, Journal, JournalEntry, Model, Rng, Runtime, Tick, Value, ValueMap,
+};
+use pulsive_hub::hash::{hash_bytes_with_seed, hash_value_with_seed};
+use pulsive_hub::{hash_seed, DEFAULT_GLOBAL_SEED};
+use std::collections::BTreeSet;
+
+/// Hash the simulation state of a model
+///
+/// Covers entities (kind, properties and flags), globals and the RNG state.
+/// The clock and actor contexts are not included. Entity and key order do
+/// not affect the hash.
+pub fn state_hash(model: &Model) -> u64 {
+    let mut h = hash_seed(DEFAULT_GLOBAL_SEED, model.rng.state(), 0);
+
+    let mut entities: Vec<_> = model.entities().iter().collect();
+    entities.sort_by_key(|e| e.id.raw());
+    for entity in entities {
+        let mut eh = hash_seed(h, entity.id.raw(), 1);
+        eh = hash_bytes_with_seed(entity.kind.as_str().as_bytes(), eh);
+        let mut keys: Vec<_> = entity.properties.keys().collect();
+        keys.sort_by_key(|k| k.as_str());
+        for key in keys {
+            eh = hash_bytes_with_seed(key.as_bytes(), eh);
+            eh = hash_value_with_seed(&entity.properties[key], eh);
+        }
+        let mut flags: Vec<_> = entity.flags.iter().map(|f| f.as_str()).collect();
+        flags.sort();
+        for flag in flags {
+            eh = hash_bytes_with_seed(flag.as_bytes(), hash_seed(eh, 0, 2));
+        }
+        for tag in &entity.tags {
+            eh = hash_bytes_with_seed(tag.as_bytes(), hash_seed(eh, 0, 5));
+        }
+        h = hash_seed(h, eh, 3);
+    }
+
+    let mut keys: Vec<_> = model.globals().keys().collect();
+    keys.sort_by_key(|k| k.as_str());
+    for key in keys {
+        h = hash_bytes_with_seed(key.as_bytes(), hash_seed(h, 0, 4));
+        h = hash_value_with_seed(&model.globals()[key], h);
+    }
+    h
+}
+
+/// Result of comparing two sessions
+#[derive(Debug, Clone)]
+pub struct SessionComparison {
+    /// Number of ticks compared
+    pub ticks_compared: u64,
+    /// First tick where the sessions diverged, if any
+    pub divergence: Option<Divergence>,
+}
+
+impl SessionComparison {
+    /// Check if the sessions matched at every compared tick
+    pub fn is_identical(&self) -> bool {
+        self.divergence.is_none()
+    }
+}
+
+/// The first tick where two sessions diverged
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    /// The tick at whose end the states differ
+    pub tick: Tick,
+    /// State hash of the first session
+    pub hash_a: u64,
+    /// State hash of the second session
+    pub hash_b: u64,
+    /// What differs between the two states
+    pub diff: StateDiff,
+}
+
+/// Differences between two model states
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StateDiff {
+    /// Entities that only exist in the first state
+    pub only_in_a: Vec<EntityId>,
+    /// Entities that only exist in the second state
+    pub only_in_b: Vec<EntityId>,
+    /// Entities present in both states that differ
+    pub entities: Vec<EntityDiff>,
+    /// Globals that differ
+    pub globals: Vec<ValueDiff>,
+    /// RNG states, if they differ
+    pub rng: Option<(u64, u64)>,
+}
+
+impl StateDiff {
+    /// Compute the differences between two models
+    pub fn between(a: &Model, b: &Model) -> Self {
+        let mut diff = Self::default();
+
+        for entity in a.entities().iter() {
+            let Some(other) = b.entities().get(entity.id) else {
+                diff.only_in_a.push(entity.id);
+                continue;
+            };
+            if entity == other {
+                continue;
+            }
+            let flags_a: BTreeSet<_> = entity.flags.iter().map(|f| f.as_str()).collect();
+            let flags_b: BTreeSet<_> = other.flags.iter().map(|f| f.as_str()).collect();
+            diff.entities.push(EntityDiff {
+                id: entity.id,
+                kind: (entity.kind != other.kind)
+                    .then(|| (entity.kind.clone(), other.kind.clone())),
+                properties: value_diffs(&entity.properties, &other.properties),
+                flags_only_in_a: flags_a
+                    .difference(&flags_b)
+                    .map(|f| DefId::new(*f))
+                    .collect(),
+                flags_only_in_b: flags_b
+                    .difference(&flags_a)
+                    .map(|f| DefId::new(*f))
+                    .collect(),
+            });
+        }
+        diff.only_in_b = b
+            .entities()
+            .ids()
+            .filter(|id| a.entities().get(*id).is_none())
+            .collect();
+
+        diff.globals = value_diffs(a.globals(), b.globals());
+
+        if a.rng.state() != b.rng.state() {
+            diff.rng = Some((a.rng.state(), b.rng.state()));
+        }
+        diff
+    }
+
+    /// Check if the states are identical
+    pub fn is_empty(&self) -> bool {
+        self.only_in_a.is_empty()
+            && self.only_in_b.is_empty()
+            && self.entities.is_empty()
+            && self.globals.is_empty()
+            && self.rng.is_none()
+    }
+}
+
+/// Differences in a single entity present in both states
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityDiff {
+    /// The entity
+    pub id: EntityId,
+    /// Kinds in both states, if they differ
+    pub kind: Option<(DefId, DefId)>,
+    /// Properties that differ
+    pub properties: Vec<ValueDiff>,
+    /// Flags only set in the first state
+    pub flags_only_in_a: Vec<DefId>,
+    /// Flags only set in the second state
+    pub flags_only_in_b: Vec<DefId>,
+}
+
+/// A property or global that differs between two states
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueDiff {
+    /// Property or global key
+    pub key: String,
+    /// Value in the first state
+    pub a: Option<Value>,
+    /// Value in the second state
+    pub b: Option<Value>,
+}
+
+/// Collect differing keys, sorted by key
+fn value_diffs(a: &ValueMap, b: &ValueMap) -> Vec<ValueDiff> {
+    let keys: BTreeSet<&str> = a.keys().chain(b.keys()).map(|k| k.as_str()).collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let (a, b) = (a.get(key), b.get(key));
+            (a != b).then(|| ValueDiff {
+                key: key.to_string(),
+                a: a.cloned(),
+                b: b.cloned(),
+            })
+        })
+        .collect()
+}
+
+/// Steps a journal forward one tick at a time
+struct SessionCursor<'a> {
+    entries: &'a [JournalEntry],
+    next: usize,
+    model: Model,
+}
+
+impl<'a> SessionCursor<'a> {
+    fn new(journal: &'a Journal) -> Self {
+        let model = entry_tick(journal.entries().first())
+            .and_then(|tick| journal.snapshot_at_or_before(tick))
+            .map(|snapshot| snapshot.model.clone())
+            .unwrap_or_default();
+        Self {
+            entries: journal.entries(),
+            next: 0,
+            model,
+        }
+    }
+
+    /// Take the entries recorded up to and including `tick`
+    fn take_until(&mut self, tick: Tick) -> &'a [JournalEntry] {
+        let start = self.next;
+        while entry_tick(self.entries.get(self.next)).is_some_and(|t| t <= tick) {
+            self.next += 1;
+        }
+        &self.entries[start..self.next]
+    }
+
+    /// Advance by re-applying recorded writes
+    fn apply_writes(&mut self, tick: Tick) {
+        for entry in self.take_until(tick) {
+            if let JournalEntry::Writes {
+                tick,
+                writes,
+                rng_state,
+            } = entry
+            {
+                advance_to(&mut self.model, *tick);
+                pulsive_hub::apply(writes, &mut self.model);
+                self.model.rng = Rng::from_state(*rng_state);
+            }
+        }
+        advance_to(&mut self.model, tick);
+    }
+
+    /// Advance by re-executing recorded messages
+    fn execute(&mut self, tick: Tick, runtime: &mut Runtime) {
+        let entries = self.take_until(tick);
+        if self.model.current_tick() >= tick {
+            return;
+        }
+        advance_to(&mut self.model, tick);
+        for entry in entries {
+            if let JournalEntry::Message { msg, .. } = entry {
+                runtime.send(msg.clone());
+            }
+        }
+        runtime.process_queue(&mut self.model);
+    }
+}
+
+fn entry_tick(entry: Option<&JournalEntry>) -> Option<Tick> {
+    Some(match entry? {
+        JournalEntry::Message { tick, .. }
+        | JournalEntry::TickBoundary { tick }
+        | JournalEntry::Snapshot { tick, .. }
+        | JournalEntry::Writes { tick, .. }
+        | JournalEntry::Rng { tick, .. }
+        | JournalEntry::Provenance { tick, .. }
+        | JournalEntry::Metadata { tick, .. } => *tick,
+    })
+}
+
+/// All ticks recorded in either journal, in order
+fn recorded_ticks(a: &Journal, b: &Journal) -> BTreeSet<Tick> {
+    a.entries()
+        .iter()
+        .chain(b.entries())
+        .filter_map(|e| entry_tick(Some(e)))
+        .collect()
+}
+
+/// Compare the cursors' states after stepping both to each tick
+fn lockstep(
+    a: &mut SessionCursor,
+    b: &mut SessionCursor,
+    ticks: BTreeSet<Tick>,
+    mut step: impl FnMut(&mut SessionCursor, &mut SessionCursor, Tick),
+) -> SessionComparison {
+    let mut ticks_compared = 0;
+    for tick in ticks {
+        step(a, b, tick);
+        ticks_compared += 1;
+
+        let (hash_a, hash_b) = (state_hash(&a.model), state_hash(&b.model));
+        if hash_a != hash_b {
+            return SessionComparison {
+                ticks_compared,
+                divergence: Some(Divergence {
+                    tick,
+                    hash_a,
+                    hash_b,
+                    diff: StateDiff::between(&a.model, &b.model),
+                }),
+            };
+        }
+    }
+    SessionComparison {
+        ticks_compared,
+        divergence: None,
+    }
+}
+
+impl Auditor<'_> {
+    /// Compare two sessions recorded with write-level recording
+    ///
+    /// Both journals are rebuilt from their recorded writes, so no handlers
+    /// are needed. Each starts from the snapshot at or before its first
+    /// entry, or from an empty model.
+    pub fn compare(a: &Journal, b: &Journal) -> Result<SessionComparison> {
+        for journal in [a, b] {
+            if journal.write_sets().next().is_none() {
+                return Err(Error::ReplayError(
+                    "journal has no recorded writes".to_string(),
+                ));
+            }
+        }
+
+        let (mut cursor_a, mut cursor_b) = (SessionCursor::new(a), SessionCursor::new(b));
+        Ok(lockstep(
+            &mut cursor_a,
+            &mut cursor_b,
+            recorded_ticks(a, b),
+            |a, b, tick| {
+                a.apply_writes(tick);
+                b.apply_writes(tick);
+            },
+        ))
+    }
+
+    /// Compare two sessions by re-executing their recorded messages
+    ///
+    /// Each journal is replayed with its own runtime, which lets the same
+    /// recording be compared across two versions of the handlers.
+    pub fn compare_replayed(
+        a: &Journal,
+        runtime_a: &mut Runtime,
+        b: &Journal,
+        runtime_b: &mut Runtime,
+    ) -> SessionComparison {
+        let (mut cursor_a, mut cursor_b) = (SessionCursor::new(a), SessionCursor::new(b));
+        lockstep(
+            &mut cursor_a,
+            &mut cursor_b,
+            recorded_ticks(a, b),
+            |a, b, tick| {
+                a.execute(tick, runtime_a);
+                b.execute(tick, runtime_b);
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::{Effect, Expr, JournalConfig, ModifyOp, TickHandler};
+
+    fn income_runtime(amount: f64) -> Runtime {
+        let mut runtime = Runtime::new();
+        runtime.on_tick(TickHandler {
+            id: DefId::new("income"),
+            condition: None,
+            target_kind: None,
+            effects: vec![Effect::ModifyGlobal {
+                property: "gold".to_string(),
+                op: ModifyOp::Add,
+                value: Expr::lit(amount),
+            }],
+            priority: 0,
+        });
+        runtime
+    }
+
+    fn record(runtime: &mut Runtime, ticks: usize) -> Journal {
+        let mut model = Model::new();
+        model.entities_mut().create("city").set("size", 1.0);
+        let mut journal = Journal::with_config(JournalConfig {
+            recording_enabled: true,
+            record_writes: true,
+            ..Default::default()
+        });
+        journal.take_snapshot(&model);
+        for _ in 0..ticks {
+            runtime.tick_with_journal(&mut model, &mut journal);
+        }
+        journal
+    }
+
+    #[test]
+    fn test_state_hash() {
+        let mut a = Model::new();
+        a.set_global("x", 1.0);
+        a.set_global("y", 2.0);
+        let mut b = Model::new();
+        b.set_global("y", 2.0);
+        b.set_global("x", 1.0);
+        assert_eq!(state_hash(&a), state_hash(&b));
+
+        b.set_global("x", 3.0);
+        assert_ne!(state_hash(&a), state_hash(&b));
+    }
+
+    #[test]
+    fn test_compare_identical_sessions() {
+        let a = record(&mut income_runtime(5.0), 10);
+        let b = record(&mut income_runtime(5.0), 10);
+
+        let comparison = Auditor::compare(&a, &b).unwrap();
+        assert!(comparison.is_identical());
+        assert_eq!(comparison.ticks_compared, 11);
+    }
+
+    #[test]
+    fn test_compare_finds_first_divergence() {
+        let a = record(&mut income_runtime(5.0), 10);
+        let mut b_runtime = income_runtime(5.0);
+        // Runs after income, once gold reaches 20 at tick 4
+        b_runtime.on_tick(TickHandler {
+            id: DefId::new("bonus"),
+            condition: Some(Expr::Ge(
+                Box::new(Expr::global("gold")),
+                Box::new(Expr::lit(20.0)),
+            )),
+            target_kind: None,
+            effects: vec![Effect::SetGlobal {
+                property: "bonus".to_string(),
+                value: Expr::lit(true),
+            }],
+            priority: -1,
+        });
+        let b = record(&mut b_runtime, 10);
+
+        let divergence = Auditor::compare(&a, &b).unwrap().divergence.unwrap();
+        assert_eq!(divergence.tick, 4);
+        assert_ne!(divergence.hash_a, divergence.hash_b);
+        assert_eq!(
+            divergence.diff.globals,
+            vec![ValueDiff {
+                key: "bonus".to_string(),
+                a: None,
+                b: Some(Value::Bool(true)),
+            }]
+        );
+        assert!(divergence.diff.entities.is_empty());
+    }
+
+    #[test]
+    fn test_compare_replayed_across_handler_versions() {
+        let journal = record(&mut income_runtime(5.0), 10);
+
+        let comparison = Auditor::compare_replayed(
+            &journal,
+            &mut income_runtime(5.0),
+            &journal,
+            &mut income_runtime(7.0),
+        );
+        let divergence = comparison.divergence.unwrap();
+        assert_eq!(divergence.tick, 1);
+        assert_eq!(divergence.diff.globals[0].key, "gold");
+    }
+}
@@ -28,6 +28,14 @@ pub enum Error {
     /// Serialization error
     #[error("Serialization error: {0}")]
     Serialization(String),
+
+    /// Corrupted or truncated journal file
+    #[error("Corrupted journal: {0}")]
+    Corrupted(String),
+
+    /// Invalid query string, with the byte offset of the problem
+    #[error("Query parse error at {0}: {1}")]
+    QueryParse(usize, String),
 }
 
 /// Result type for journal operations
@@ -1,9 +1,13 @@
 //! Export journal data to various formats
 
-use crate::{Error, Result};
+use crate::table::ExportTable;
+use crate::{Error, RedactionPolicy, Result};
 use pulsive_core::{Journal, JournalEntry, Tick};
 use serde::Serialize;
+use std::borrow::Cow;
 use std::io::Write;
+#[cfg(any(feature = "parquet", feature = "sqlite"))]
+use std::path::Path;
 
 /// Export format
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -12,21 +16,37 @@ pub enum ExportFormat {
     Ron,
     /// JSON format (requires serde_json feature)
     Json,
+    /// JSON Lines format, one entry per line (requires serde_json feature)
+    JsonLines,
     /// CSV format (messages only)
     Csv,
+    /// SQL script creating and filling all tables (loadable into SQLite)
+    Sql,
     /// Human-readable text format
     Text,
 }
 
 /// Exporter for journal data
 pub struct Exporter<'a> {
-    journal: &'a Journal,
+    journal: Cow<'a, Journal>,
 }
 
 impl<'a> Exporter<'a> {
     /// Create a new exporter
     pub fn new(journal: &'a Journal) -> Self {
-        Self { journal }
+        Self {
+            journal: Cow::Borrowed(journal),
+        }
+    }
+
+    /// Create an exporter that writes a redacted copy of a journal
+    ///
+    /// Every format, including snapshots and SQL tables, is produced from
+    /// the redacted copy so shared exports cannot leak the removed values.
+    pub fn with_redaction(journal: &Journal, policy: &RedactionPolicy) -> Self {
+        Self {
+            journal: Cow::Owned(policy.apply(journal)),
+        }
     }
 
     /// Export to a string in the specified format
@@ -34,7 +54,9 @@ impl<'a> Exporter<'a> {
         match format {
             ExportFormat::Ron => self.to_ron(),
             ExportFormat::Json => self.to_json(),
+            ExportFormat::JsonLines => self.to_json_lines(),
             ExportFormat::Csv => self.to_csv(),
+            ExportFormat::Sql => Ok(self.to_sql()),
             ExportFormat::Text => Ok(self.to_text()),
         }
     }
@@ -50,7 +72,7 @@ impl<'a> Exporter<'a> {
 
     /// Export to RON format
     pub fn to_ron(&self) -> Result<String> {
-        let export = ExportData::from_journal(self.journal);
+        let export = ExportData::from_journal(&self.journal);
         ron::ser::to_string_pretty(&export, ron::ser::PrettyConfig::default())
             .map_err(|e| Error::Serialization(e.to_string()))
     }
@@ -58,7 +80,7 @@ impl<'a> Exporter<'a> {
     /// Export to JSON format
     #[cfg(feature = "serde_json")]
     pub fn to_json(&self) -> Result<String> {
-        let export = ExportData::from_journal(self.journal);
+        let export = ExportData::from_journal(&self.journal);
         serde_json::to_string_pretty(&export).map_err(|e| Error::Serialization(e.to_string()))
     }
 
@@ -69,37 +91,138 @@ impl<'a> Exporter<'a> {
         ))
     }
 
+    /// Export to JSON Lines format, one journal entry per line
+    #[cfg(feature = "serde_json")]
+    pub fn to_json_lines(&self) -> Result<String> {
+        let mut output = String::new();
+        for entry in self.journal.entries() {
+            let line =
+                serde_json::to_string(entry).map_err(|e| Error::Serialization(e.to_string()))?;
+            output.push_str(&line);
+            output.push('\n');
+        }
+        Ok(output)
+    }
+
+    #[cfg(not(feature = "serde_json"))]
+    pub fn to_json_lines(&self) -> Result<String> {
+        Err(Error::ExportError(
+            "JSON Lines export requires the 'serde_json' feature".to_string(),
+        ))
+    }
+
     /// Export to CSV format (messages only)
     pub fn to_csv(&self) -> Result<String> {
-        let mut output = String::new();
-        output.push_str("tick,seq,kind,event_id,actor,params\n");
+        Ok(self.to_csv_table(ExportTable::Messages))
+    }
 
-        for entry in self.journal.entries() {
-            if let JournalEntry::Message { tick, msg, seq } = entry {
-                let kind = format!("{:?}", msg.kind);
-                let event_id = msg
-                    .event_id
-                    .as_ref()
-                    .map(|id| id.to_string())
-                    .unwrap_or_default();
-                let actor = msg
-                    .actor
-                    .as_ref()
-                    .map(|a| a.raw().to_string())
-                    .unwrap_or_default();
-                let params = format!("{:?}", msg.params);
-
-                // Escape CSV fields
-                let params_escaped = params.replace('"', "\"\"");
+    /// Export a single table to CSV with a header row
+    pub fn to_csv_table(&self, table: ExportTable) -> String {
+        let mut output = String::new();
+        let header: Vec<_> = table.columns().iter().map(|c| c.name).collect();
+        output.push_str(&header.join(","));
+        output.push('\n');
+
+        for row in table.rows(&self.journal) {
+            let fields: Vec<_> = row.iter().map(|cell| cell.to_csv()).collect();
+            output.push_str(&fields.join(","));
+            output.push('\n');
+        }
+        output
+    }
 
+    /// Export all tables as an SQL script
+    ///
+    /// The script creates one table per [`ExportTable`] and inserts all rows
+    /// in a single transaction, e.g. `sqlite3 session.db < export.sql`.
+    pub fn to_sql(&self) -> String {
+        let mut output = String::from("BEGIN TRANSACTION;\n");
+
+        for table in ExportTable::ALL {
+            let columns: Vec<_> = table
+                .columns()
+                .iter()
+                .map(|c| format!("{} {}", c.name, c.kind.sql_type()))
+                .collect();
+            output.push_str(&format!(
+                "CREATE TABLE IF NOT EXISTS {} ({});\n",
+                table.name(),
+                columns.join(", ")
+            ));
+
+            for row in table.rows(&self.journal) {
+                let values: Vec<_> = row.iter().map(|cell| cell.to_sql()).collect();
                 output.push_str(&format!(
-                    "{},{},{},{},{},\"{}\"\n",
-                    tick, seq, kind, event_id, actor, params_escaped
+                    "INSERT INTO {} VALUES ({});\n",
+                    table.name(),
+                    values.join(", ")
                 ));
             }
         }
 
-        Ok(output)
+        output.push_str("COMMIT;\n");
+        output
+    }
+
+    /// Export a single table as a Parquet file
+    #[cfg(feature = "parquet")]
+    pub fn to_parquet_table(&self, table: ExportTable) -> Vec<u8> {
+        crate::parquet::write_table(table, &self.journal)
+    }
+
+    /// Export all tables as Parquet files in a directory, one per table
+    /// (`messages.parquet`, `writes.parquet`, ...)
+    #[cfg(feature = "parquet")]
+    pub fn write_parquet(&self, dir: impl AsRef<Path>) -> Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        for table in ExportTable::ALL {
+            let path = dir.join(format!("{}.parquet", table.name()));
+            std::fs::write(path, self.to_parquet_table(table))?;
+        }
+        Ok(())
+    }
+
+    /// Export all tables into an SQLite database, creating it if needed
+    ///
+    /// Creates the same tables as [`to_sql`](Self::to_sql) and inserts all
+    /// rows in a single transaction.
+    #[cfg(feature = "sqlite")]
+    pub fn write_sqlite(&self, path: impl AsRef<Path>) -> Result<()> {
+        let sqlite_error = |e: rusqlite::Error| Error::ExportError(e.to_string());
+        let mut conn = rusqlite::Connection::open(path).map_err(sqlite_error)?;
+        let tx = conn.transaction().map_err(sqlite_error)?;
+        for table in ExportTable::ALL {
+            let columns: Vec<_> = table
+                .columns()
+                .iter()
+                .map(|c| format!("{} {}", c.name, c.kind.sql_type()))
+                .collect();
+            tx.execute(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS {} ({})",
+                    table.name(),
+                    columns.join(", ")
+                ),
+                [],
+            )
+            .map_err(sqlite_error)?;
+
+            let placeholders: Vec<_> = (1..=columns.len()).map(|i| format!("?{}", i)).collect();
+            let mut insert = tx
+                .prepare(&format!(
+                    "INSERT INTO {} VALUES ({})",
+                    table.name(),
+                    placeholders.join(", ")
+                ))
+                .map_err(sqlite_error)?;
+            for row in table.rows(&self.journal) {
+                insert
+                    .execute(rusqlite::params_from_iter(row))
+                    .map_err(sqlite_error)?;
+            }
+        }
+        tx.commit().map_err(sqlite_error)
     }
 
     /// Export to human-readable text format
@@ -158,6 +281,31 @@ impl<'a> Exporter<'a> {
                         snapshot_id.0, tick
                     ));
                 }
+                JournalEntry::Writes { tick, writes, .. } => {
+                    output.push_str(&format!(
+                        "  [WRITES] {} writes at tick {}\n",
+                        writes.len(),
+                        tick
+                    ));
+                }
+                JournalEntry::Rng {
+                    tick, seed, draws, ..
+                } => {
+                    output.push_str(&format!(
+                        "  [RNG] {} draws from {:#018x} at tick {}\n",
+                        draws, seed, tick
+                    ));
+                }
+                JournalEntry::Provenance { seq, handlers, .. } => {
+                    for trace in handlers {
+                        output.push_str(&format!(
+                            "      #{} -> {} ({} writes)\n",
+                            seq,
+                            trace.handler,
+                            trace.write_count()
+                        ));
+                    }
+                }
                 JournalEntry::Metadata { tick, key, value } => {
                     output.push_str(&format!("  [META] {}={} at tick {}\n", key, value, tick));
                 }
@@ -279,6 +427,88 @@ mod tests {
         assert!(csv.lines().count() > 1);
     }
 
+    #[test]
+    fn test_export_csv_tables() {
+        let journal = create_test_journal();
+        let exporter = Exporter::new(&journal);
+
+        let snapshots = exporter.to_csv_table(ExportTable::Snapshots);
+        assert!(snapshots.starts_with("id,tick,entities,globals\n"));
+        assert_eq!(snapshots.lines().count(), 1 + journal.snapshots().len());
+
+        let summaries = exporter.to_csv_table(ExportTable::TickSummaries);
+        assert_eq!(summaries.lines().count(), 1 + journal.stats().tick_count);
+    }
+
+    #[test]
+    fn test_export_sql() {
+        let journal = create_test_journal();
+        let sql = Exporter::new(&journal).to_sql();
+
+        for table in ExportTable::ALL {
+            assert!(sql.contains(&format!("CREATE TABLE IF NOT EXISTS {} (", table)));
+        }
+        assert!(sql.contains("INSERT INTO messages VALUES ("));
+        assert!(sql.ends_with("COMMIT;\n"));
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_export_parquet() {
+        let journal = create_test_journal();
+        let exporter = Exporter::new(&journal);
+
+        for table in ExportTable::ALL {
+            let file = exporter.to_parquet_table(table);
+            assert!(file.starts_with(b"PAR1") && file.ends_with(b"PAR1"));
+            let footer = file.len() - 8;
+            let meta_len = u32::from_le_bytes(file[footer..footer + 4].try_into().unwrap());
+            assert!(meta_len as usize <= footer - 4);
+        }
+        let dir = std::env::temp_dir().join(format!("pulsive-parquet-{}", std::process::id()));
+        exporter.write_parquet(&dir).unwrap();
+        for table in ExportTable::ALL {
+            let path = dir.join(format!("{}.parquet", table.name()));
+            let written = std::fs::read(path).unwrap();
+            assert_eq!(written, exporter.to_parquet_table(table));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_export_sqlite() {
+        let journal = create_test_journal();
+        let path = std::env::temp_dir().join(format!("pulsive-export-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        Exporter::new(&journal).write_sqlite(&path).unwrap();
+
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        let count = |table: &str| -> usize {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+        assert_eq!(count("snapshots"), journal.snapshots().len());
+        assert_eq!(count("tick_summaries"), journal.stats().tick_count);
+        assert_eq!(count("messages"), journal.stats().message_count);
+        drop(conn);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn test_export_json_lines() {
+        let journal = create_test_journal();
+        let lines = Exporter::new(&journal).to_json_lines().unwrap();
+
+        assert_eq!(lines.lines().count(), journal.entries().len());
+        for line in lines.lines() {
+            serde_json::from_str::<serde_json::Value>(line).unwrap();
+        }
+    }
+
     #[test]
     fn test_export_text() {
         let journal = create_test_journal();
@@ -297,4 +527,16 @@ mod tests {
 
         assert!(ron.contains("entries"));
     }
+
+    #[test]
+    fn test_export_with_redaction() {
+        let journal = create_test_journal();
+        let policy = RedactionPolicy::new().redact_metadata("test_*");
+        let exporter = Exporter::with_redaction(&journal, &policy);
+
+        let text = exporter.to_text();
+        assert!(!text.contains("test_value"));
+        assert!(text.contains(crate::REDACTED));
+        assert!(Exporter::new(&journal).to_text().contains("test_value"));
+    }
 }
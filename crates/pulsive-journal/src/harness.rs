@@ -0,0 +1,394 @@
+//! Headless replay with assertions
+//!
+//! A [`ReplayHarness`] replays a recorded session without any UI and checks
+//! a list of expectations against the model along the way, turning recorded
+//! sessions into regression tests:
+//!
+//! ```rust,ignore
+//! let report = ReplayHarness::new(&journal)
+//!     .expect_at("rich by tick 10", 10, Expr::Ge(
+//!         Box::new(Expr::global("gold")),
+//!         Box::new(Expr::lit(50.0)),
+//!     ))
+//!     .expect_always("never in debt", Expr::Ge(
+//!         Box::new(Expr::global("gold")),
+//!         Box::new(Expr::lit(0.0)),
+//!     ))
+//!     .run(&mut runtime)?;
+//! assert!(report.passed(), "{}", report);
+//! ```
+
+use crate::{Replayer, Result};
+use pulsive_core::{EvalContext, Expr, Journal, Model, Runtime, Value, ValueMap};
+use std::fmt;
+
+/// When an assertion is checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckAt {
+    /// After the given tick has been replayed
+    Tick(u64),
+    /// After the last replayed tick
+    End,
+    /// After every replayed tick (an invariant)
+    Always,
+}
+
+/// A named expectation about the replayed model
+#[derive(Debug, Clone)]
+pub struct Assertion {
+    /// Name shown in reports
+    pub name: String,
+    /// When to check the assertion
+    pub at: CheckAt,
+    /// Condition that must evaluate to a truthy value
+    pub condition: Expr,
+}
+
+/// Outcome of a single assertion
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssertionOutcome {
+    /// The condition held every time it was checked
+    Passed,
+    /// The condition evaluated to a falsy value
+    Failed {
+        /// Tick the condition was checked at
+        tick: u64,
+        /// Value the condition evaluated to
+        value: Value,
+    },
+    /// The condition could not be evaluated
+    Error {
+        /// Tick the condition was checked at
+        tick: u64,
+        /// Evaluation error message
+        message: String,
+    },
+    /// The replay ended before the assertion's tick
+    NotReached,
+}
+
+impl AssertionOutcome {
+    /// Check if the assertion passed
+    pub fn is_pass(&self) -> bool {
+        matches!(self, AssertionOutcome::Passed)
+    }
+}
+
+/// Result of a single assertion
+#[derive(Debug, Clone)]
+pub struct AssertionResult {
+    /// Name of the assertion
+    pub name: String,
+    /// When the assertion was checked
+    pub at: CheckAt,
+    /// What happened
+    pub outcome: AssertionOutcome,
+}
+
+/// Report of a harness run
+#[derive(Debug, Clone)]
+pub struct HarnessReport {
+    /// Tick the replay started from
+    pub start_tick: u64,
+    /// Last tick replayed
+    pub end_tick: u64,
+    /// Results in the order the assertions were added
+    pub results: Vec<AssertionResult>,
+}
+
+impl HarnessReport {
+    /// Check if every assertion passed
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(|r| r.outcome.is_pass())
+    }
+
+    /// Get the assertions that did not pass
+    pub fn failures(&self) -> impl Iterator<Item = &AssertionResult> {
+        self.results.iter().filter(|r| !r.outcome.is_pass())
+    }
+}
+
+impl fmt::Display for HarnessReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failed = self.failures().count();
+        writeln!(
+            f,
+            "Replayed ticks {}-{}: {} passed, {} failed",
+            self.start_tick,
+            self.end_tick,
+            self.results.len() - failed,
+            failed
+        )?;
+        for result in &self.results {
+            match &result.outcome {
+                AssertionOutcome::Passed => writeln!(f, "  ok    {}", result.name)?,
+                AssertionOutcome::Failed { tick, value } => writeln!(
+                    f,
+                    "  FAIL  {} (tick {}: evaluated to {})",
+                    result.name, tick, value
+                )?,
+                AssertionOutcome::Error { tick, message } => {
+                    writeln!(f, "  ERROR {} (tick {}: {})", result.name, tick, message)?
+                }
+                AssertionOutcome::NotReached => {
+                    writeln!(f, "  FAIL  {} (tick not reached)", result.name)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replays a journal and checks assertions against the model
+pub struct ReplayHarness<'a> {
+    journal: &'a Journal,
+    assertions: Vec<Assertion>,
+    start_tick: u64,
+    end_tick: Option<u64>,
+    stop_on_failure: bool,
+}
+
+impl<'a> ReplayHarness<'a> {
+    /// Create a harness for a journal
+    pub fn new(journal: &'a Journal) -> Self {
+        Self {
+            journal,
+            assertions: Vec::new(),
+            start_tick: 0,
+            end_tick: None,
+            stop_on_failure: false,
+        }
+    }
+
+    /// Start replaying from a tick (restored from the nearest snapshot)
+    pub fn start_at(mut self, tick: u64) -> Self {
+        self.start_tick = tick;
+        self
+    }
+
+    /// Stop replaying after a tick instead of at the end of the journal
+    pub fn end_at(mut self, tick: u64) -> Self {
+        self.end_tick = Some(tick);
+        self
+    }
+
+    /// Stop the replay at the first failing assertion
+    ///
+    /// Assertions not checked yet are reported as not reached.
+    pub fn stop_on_failure(mut self) -> Self {
+        self.stop_on_failure = true;
+        self
+    }
+
+    /// Add an assertion
+    pub fn assert(mut self, assertion: Assertion) -> Self {
+        self.assertions.push(assertion);
+        self
+    }
+
+    /// Expect a condition to hold after a tick
+    pub fn expect_at(self, name: impl Into<String>, tick: u64, condition: Expr) -> Self {
+        self.assert(Assertion {
+            name: name.into(),
+            at: CheckAt::Tick(tick),
+            condition,
+        })
+    }
+
+    /// Expect a condition to hold at the end of the replay
+    pub fn expect_at_end(self, name: impl Into<String>, condition: Expr) -> Self {
+        self.assert(Assertion {
+            name: name.into(),
+            at: CheckAt::End,
+            condition,
+        })
+    }
+
+    /// Expect a condition to hold after every replayed tick
+    pub fn expect_always(self, name: impl Into<String>, condition: Expr) -> Self {
+        self.assert(Assertion {
+            name: name.into(),
+            at: CheckAt::Always,
+            condition,
+        })
+    }
+
+    /// Get the assertions
+    pub fn assertions(&self) -> &[Assertion] {
+        &self.assertions
+    }
+
+    /// Replay the journal and check every assertion
+    ///
+    /// The runtime must have the same handlers registered as when the
+    /// session was recorded.
+    pub fn run(&self, runtime: &mut Runtime) -> Result<HarnessReport> {
+        let mut model = Model::new();
+        self.run_with_model(&mut model, runtime)
+    }
+
+    /// Replay into a caller-provided model, leaving it at the final state
+    pub fn run_with_model(
+        &self,
+        model: &mut Model,
+        runtime: &mut Runtime,
+    ) -> Result<HarnessReport> {
+        let mut outcomes: Vec<Option<AssertionOutcome>> = vec![None; self.assertions.len()];
+        let mut replayer = Replayer::new(self.journal);
+        replayer.goto(model, runtime, self.start_tick)?;
+
+        let end = self
+            .end_tick
+            .unwrap_or_else(|| replayer.last_tick().unwrap_or(0));
+        let mut failed = false;
+        while replayer.current_tick() < end && !failed {
+            if !replayer.step_forward(model, runtime)? {
+                break;
+            }
+            let tick = replayer.current_tick();
+            for (assertion, outcome) in self.assertions.iter().zip(&mut outcomes) {
+                let due = match assertion.at {
+                    CheckAt::Tick(t) => t == tick,
+                    CheckAt::Always => outcome.is_none(),
+                    CheckAt::End => false,
+                };
+                if due {
+                    if let Some(failure) = check(&assertion.condition, model, tick) {
+                        *outcome = Some(failure);
+                        failed |= self.stop_on_failure;
+                    } else if matches!(assertion.at, CheckAt::Tick(_)) {
+                        *outcome = Some(AssertionOutcome::Passed);
+                    }
+                }
+            }
+        }
+
+        let end_tick = replayer.current_tick();
+        let completed = !failed;
+        let results = self
+            .assertions
+            .iter()
+            .zip(outcomes)
+            .map(|(assertion, outcome)| {
+                let outcome = match (outcome, assertion.at) {
+                    (Some(outcome), _) => outcome,
+                    (None, CheckAt::End) if completed => {
+                        check(&assertion.condition, model, end_tick)
+                            .unwrap_or(AssertionOutcome::Passed)
+                    }
+                    (None, CheckAt::Always) if completed => AssertionOutcome::Passed,
+                    (None, _) => AssertionOutcome::NotReached,
+                };
+                AssertionResult {
+                    name: assertion.name.clone(),
+                    at: assertion.at,
+                    outcome,
+                }
+            })
+            .collect();
+
+        Ok(HarnessReport {
+            start_tick: self.start_tick,
+            end_tick,
+            results,
+        })
+    }
+}
+
+/// Evaluate a condition without disturbing the model's RNG, returning the
+/// failure if it does not hold
+fn check(condition: &Expr, model: &Model, tick: u64) -> Option<AssertionOutcome> {
+    let params = ValueMap::new();
+    let mut rng = model.rng.clone();
+    let mut ctx = EvalContext::new(model.entities(), model.globals(), &params, &mut rng);
+    match condition.eval(&mut ctx) {
+        Ok(value) if value.is_truthy() => None,
+        Ok(value) => Some(AssertionOutcome::Failed { tick, value }),
+        Err(e) => Some(AssertionOutcome::Error {
+            tick,
+            message: e.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::{DefId, Effect, ModifyOp, TickHandler};
+
+    fn income_runtime() -> Runtime {
+        let mut runtime = Runtime::new();
+        runtime.on_tick(TickHandler {
+            id: DefId::new("income"),
+            condition: None,
+            target_kind: None,
+            effects: vec![Effect::ModifyGlobal {
+                property: "gold".to_string(),
+                op: ModifyOp::Add,
+                value: Expr::lit(5.0),
+            }],
+            priority: 0,
+        });
+        runtime
+    }
+
+    fn record_session() -> Journal {
+        let mut model = Model::new();
+        let mut runtime = income_runtime();
+        let mut journal = Journal::new();
+        journal.start_recording();
+        for _ in 0..20 {
+            runtime.tick_with_journal(&mut model, &mut journal);
+        }
+        journal
+    }
+
+    fn gold_at_least(amount: f64) -> Expr {
+        Expr::Ge(Box::new(Expr::global("gold")), Box::new(Expr::lit(amount)))
+    }
+
+    #[test]
+    fn test_harness_passes() {
+        let journal = record_session();
+        let report = ReplayHarness::new(&journal)
+            .expect_at("gold at tick 4", 4, gold_at_least(20.0))
+            .expect_always("gold never negative", gold_at_least(0.0))
+            .expect_at_end("final gold", gold_at_least(100.0))
+            .run(&mut income_runtime())
+            .unwrap();
+
+        assert!(report.passed(), "{}", report);
+        assert_eq!(report.end_tick, 20);
+    }
+
+    #[test]
+    fn test_harness_reports_failures() {
+        let journal = record_session();
+        let report = ReplayHarness::new(&journal)
+            .end_at(10)
+            .expect_at("too rich too early", 2, gold_at_least(50.0))
+            .expect_always(
+                "stays poor",
+                Expr::Lt(Box::new(Expr::global("gold")), Box::new(Expr::lit(25.0))),
+            )
+            .expect_at("after the end", 15, gold_at_least(0.0))
+            .run(&mut income_runtime())
+            .unwrap();
+
+        assert!(!report.passed());
+        let outcomes: Vec<_> = report.results.iter().map(|r| &r.outcome).collect();
+        assert_eq!(
+            outcomes[0],
+            &AssertionOutcome::Failed {
+                tick: 2,
+                value: Value::Bool(false),
+            }
+        );
+        assert!(matches!(
+            outcomes[1],
+            AssertionOutcome::Failed { tick: 5, .. }
+        ));
+        assert_eq!(outcomes[2], &AssertionOutcome::NotReached);
+        assert_eq!(report.failures().count(), 3);
+    }
+}
@@ -3,8 +3,20 @@
 //! This crate builds on `pulsive-core`'s journal infrastructure to provide:
 //!
 //! - **Auditor**: Query and analyze recorded events for compliance and analytics
-//! - **Replayer**: Replay sessions with fine-grained control
-//! - **Exporter**: Export journal data to various formats
+//! - **Anomaly detection**: Flag event rate spikes and suspicious property values
+//! - **Query language**: Filter and aggregate recordings with query strings
+//! - **Replayer**: Replay sessions with fine-grained control, breakpoints and watchpoints
+//! - **Replay harness**: Turn recorded sessions into regression tests with assertions
+//! - **Exporter**: Export journal data to various formats, including SQLite
+//!   databases (`sqlite` feature) and Parquet files (`parquet` feature)
+//! - **Write replay**: Rebuild state from recorded writes and verify re-execution
+//! - **Causality**: Trace cascading events back to the message that caused them
+//! - **Session comparison**: Find where two recordings diverge (desync debugging)
+//! - **Compaction**: Collapse old messages into snapshots for long sessions
+//! - **Projections**: Incrementally fold entries into read models, with checkpoints
+//! - **Redaction**: Strip PII and secrets before sharing journals externally
+//! - **Binary journal**: Stream recordings to disk and back (`binary` feature)
+//! - **Tracing bridge**: Emit sessions as `tracing` spans and events (`tracing` feature)
 //!
 //! # Example
 //!
@@ -36,15 +48,53 @@
 //! let json = exporter.to_json()?;
 //! ```
 
+mod anomaly;
 mod auditor;
+#[cfg(feature = "binary")]
+mod binary;
+mod causality;
+mod compaction;
+mod compare;
 mod error;
 mod exporter;
+mod harness;
+#[cfg(feature = "parquet")]
+mod parquet;
+mod projection;
+mod query;
+mod redaction;
 mod replayer;
+mod table;
+#[cfg(feature = "tracing")]
+mod tracing_bridge;
+mod writes;
 
+pub use anomaly::{Anomaly, AnomalyConfig, AnomalyKind, PropertyRef};
 pub use auditor::{AuditQuery, AuditReport, Auditor, EventSummary};
+#[cfg(feature = "binary")]
+pub use binary::{Codec, JournalReader, JournalWriter, Record, DEFAULT_CHUNK_SIZE};
+pub use causality::{CausalLink, CausalTrace, EntryId};
+pub use compaction::{CompactionReport, Compactor};
+pub use compare::{state_hash, Divergence, EntityDiff, SessionComparison, StateDiff, ValueDiff};
 pub use error::{Error, Result};
 pub use exporter::{ExportFormat, Exporter};
-pub use replayer::{ReplaySpeed, ReplayState, Replayer};
+pub use harness::{
+    Assertion, AssertionOutcome, AssertionResult, CheckAt, HarnessReport, ReplayHarness,
+};
+pub use projection::{
+    EventFrequency, Projection, ProjectionCheckpoint, ProjectionEngine, ProjectionPosition,
+    PropertySeries,
+};
+pub use query::{AggregateResult, Aggregation, Field, Query, QueryResult};
+pub use redaction::{RedactionPolicy, REDACTED};
+pub use replayer::{
+    BreakOn, BreakpointHit, BreakpointId, HitReason, ReplaySpeed, ReplayState, Replayer,
+    WatchTarget,
+};
+pub use table::{Column, ColumnType, ExportTable};
+#[cfg(feature = "tracing")]
+pub use tracing_bridge::{emit_conflicts, record_conflicts, TracingBridge, CONFLICT_METADATA_KEY};
+pub use writes::{VerifyReport, WriteMismatch, WriteReplayer};
 
 // Re-export core journal types for convenience
 pub use pulsive_core::{Journal, JournalConfig, JournalEntry, JournalStats, Snapshot, SnapshotId};
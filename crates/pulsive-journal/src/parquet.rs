@@ -0,0 +1,259 @@
+//! Minimal Parquet writer for exported tables
+//!
+//! Each [`ExportTable`] becomes a Parquet file with a single row group and
+//! one uncompressed, PLAIN-encoded data page per column. Integer columns are
+//! `INT64` annotated as `UINT_64`, text columns `BYTE_ARRAY` annotated as
+//! `UTF8`. Every column is optional, so NULL cells survive the round trip.
+//!
+//! The file metadata is Thrift compact-encoded, as the format requires; only
+//! the handful of structures needed for flat tables are written.
+
+use crate::table::{Cell, ColumnType, ExportTable};
+use pulsive_core::Journal;
+
+const MAGIC: &[u8; 4] = b"PAR1";
+
+// Thrift compact protocol field types
+const T_I32: u8 = 5;
+const T_I64: u8 = 6;
+const T_BINARY: u8 = 8;
+const T_LIST: u8 = 9;
+const T_STRUCT: u8 = 12;
+
+// Parquet enums
+const TYPE_INT64: i32 = 2;
+const TYPE_BYTE_ARRAY: i32 = 6;
+const REPETITION_OPTIONAL: i32 = 1;
+const CONVERTED_UTF8: i32 = 0;
+const CONVERTED_UINT_64: i32 = 14;
+const ENCODING_PLAIN: i32 = 0;
+const ENCODING_RLE: i32 = 3;
+const CODEC_UNCOMPRESSED: i32 = 0;
+const PAGE_DATA: i32 = 0;
+
+/// Encode a table of a journal as a Parquet file
+pub(crate) fn write_table(table: ExportTable, journal: &Journal) -> Vec<u8> {
+    let rows = table.rows(journal);
+    let mut file = MAGIC.to_vec();
+    let mut chunks = Vec::new();
+    if !rows.is_empty() {
+        for (index, column) in table.columns().iter().enumerate() {
+            let offset = file.len() as i64;
+            let page = column_page(column.kind, rows.iter().map(|row| &row[index]));
+            file.extend_from_slice(&page);
+            chunks.push((offset, page.len() as i64));
+        }
+    }
+
+    let mut meta = Thrift::new();
+    meta.i32(1, 1);
+    meta.list(2, T_STRUCT, table.columns().len() + 1);
+    meta.begin();
+    meta.binary(4, b"schema");
+    meta.i32(5, table.columns().len() as i32);
+    meta.end();
+    for column in table.columns() {
+        let (physical, converted) = types(column.kind);
+        meta.begin();
+        meta.i32(1, physical);
+        meta.i32(3, REPETITION_OPTIONAL);
+        meta.binary(4, column.name.as_bytes());
+        meta.i32(6, converted);
+        meta.end();
+    }
+    meta.i64(3, rows.len() as i64);
+    if chunks.is_empty() {
+        meta.list(4, T_STRUCT, 0);
+    } else {
+        meta.list(4, T_STRUCT, 1);
+        meta.begin();
+        meta.list(1, T_STRUCT, chunks.len());
+        for (column, (offset, size)) in table.columns().iter().zip(&chunks) {
+            meta.begin();
+            meta.i64(2, *offset);
+            meta.struct_field(3);
+            meta.i32(1, types(column.kind).0);
+            meta.list(2, T_I32, 2);
+            meta.element_i32(ENCODING_PLAIN);
+            meta.element_i32(ENCODING_RLE);
+            meta.list(3, T_BINARY, 1);
+            meta.element_binary(column.name.as_bytes());
+            meta.i32(4, CODEC_UNCOMPRESSED);
+            meta.i64(5, rows.len() as i64);
+            meta.i64(6, *size);
+            meta.i64(7, *size);
+            meta.i64(9, *offset);
+            meta.end();
+            meta.end();
+        }
+        meta.i64(2, chunks.iter().map(|(_, size)| size).sum());
+        meta.i64(3, rows.len() as i64);
+        meta.end();
+    }
+    meta.binary(6, b"pulsive-journal");
+    let meta = meta.finish();
+
+    file.extend_from_slice(&meta);
+    file.extend_from_slice(&(meta.len() as u32).to_le_bytes());
+    file.extend_from_slice(MAGIC);
+    file
+}
+
+/// Physical and converted type of a column
+fn types(kind: ColumnType) -> (i32, i32) {
+    match kind {
+        ColumnType::Integer => (TYPE_INT64, CONVERTED_UINT_64),
+        ColumnType::Text => (TYPE_BYTE_ARRAY, CONVERTED_UTF8),
+    }
+}
+
+/// Encode a column as a data page with its header
+fn column_page<'a>(kind: ColumnType, cells: impl Iterator<Item = &'a Cell>) -> Vec<u8> {
+    let mut levels = Vec::new();
+    let mut values = Vec::new();
+    for cell in cells {
+        levels.push(!matches!(cell, Cell::Null));
+        match (kind, cell) {
+            (ColumnType::Integer, Cell::Int(n)) => values.extend_from_slice(&n.to_le_bytes()),
+            (ColumnType::Text, Cell::Text(s)) => {
+                values.extend_from_slice(&(s.len() as u32).to_le_bytes());
+                values.extend_from_slice(s.as_bytes());
+            }
+            (_, Cell::Null) => {}
+            // Cells always match their column's type
+            _ => unreachable!("cell does not match column type"),
+        }
+    }
+
+    let count = levels.len() as i32;
+    let levels = definition_levels(&levels);
+    let mut data = (levels.len() as u32).to_le_bytes().to_vec();
+    data.extend_from_slice(&levels);
+    data.extend_from_slice(&values);
+
+    let mut header = Thrift::new();
+    header.i32(1, PAGE_DATA);
+    header.i32(2, data.len() as i32);
+    header.i32(3, data.len() as i32);
+    header.struct_field(5);
+    header.i32(1, count);
+    header.i32(2, ENCODING_PLAIN);
+    header.i32(3, ENCODING_RLE);
+    header.i32(4, ENCODING_RLE);
+    header.end();
+    let mut page = header.finish();
+    page.extend_from_slice(&data);
+    page
+}
+
+/// RLE-encode definition levels (1 = present, 0 = NULL) with bit width 1
+fn definition_levels(present: &[bool]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < present.len() {
+        let run = present[i..]
+            .iter()
+            .take_while(|&&p| p == present[i])
+            .count();
+        write_varint(&mut out, (run as u64) << 1);
+        out.push(present[i] as u8);
+        i += run;
+    }
+    out
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Writer for the Thrift compact protocol
+struct Thrift {
+    out: Vec<u8>,
+    /// Last field ID written in each open struct
+    last: Vec<i16>,
+}
+
+impl Thrift {
+    fn new() -> Self {
+        Self {
+            out: Vec::new(),
+            last: vec![0],
+        }
+    }
+
+    fn field(&mut self, id: i16, kind: u8) {
+        let last = self.last.last_mut().expect("open struct");
+        let delta = id - *last;
+        if (1..=15).contains(&delta) {
+            self.out.push((delta as u8) << 4 | kind);
+        } else {
+            self.out.push(kind);
+            write_varint(&mut self.out, zigzag(id.into()));
+        }
+        *last = id;
+    }
+
+    fn i32(&mut self, id: i16, value: i32) {
+        self.field(id, T_I32);
+        self.element_i32(value);
+    }
+
+    fn i64(&mut self, id: i16, value: i64) {
+        self.field(id, T_I64);
+        write_varint(&mut self.out, zigzag(value));
+    }
+
+    fn binary(&mut self, id: i16, value: &[u8]) {
+        self.field(id, T_BINARY);
+        self.element_binary(value);
+    }
+
+    fn list(&mut self, id: i16, element: u8, len: usize) {
+        self.field(id, T_LIST);
+        if len < 15 {
+            self.out.push((len as u8) << 4 | element);
+        } else {
+            self.out.push(0xF0 | element);
+            write_varint(&mut self.out, len as u64);
+        }
+    }
+
+    fn element_i32(&mut self, value: i32) {
+        write_varint(&mut self.out, zigzag(value.into()));
+    }
+
+    fn element_binary(&mut self, value: &[u8]) {
+        write_varint(&mut self.out, value.len() as u64);
+        self.out.extend_from_slice(value);
+    }
+
+    /// Open a struct-typed field
+    fn struct_field(&mut self, id: i16) {
+        self.field(id, T_STRUCT);
+        self.begin();
+    }
+
+    /// Open a struct (a list element or a field opened by `struct_field`)
+    fn begin(&mut self) {
+        self.last.push(0);
+    }
+
+    fn end(&mut self) {
+        self.out.push(0);
+        self.last.pop();
+    }
+
+    /// Close the top-level struct and get the encoded bytes
+    fn finish(mut self) -> Vec<u8> {
+        self.out.push(0);
+        self.out
+    }
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
@@ -0,0 +1,401 @@
+//! Event-sourcing projections over journal entries
+//!
+//! A [`Projection`] folds journal entries into a read model (gold over time,
+//! event frequency tables, ...). The [`ProjectionEngine`] feeds each
+//! registered projection the entries it has not seen yet, so projections
+//! can be kept up to date while a session is still recording. Engine
+//! positions and projection states can be saved as a
+//! [`ProjectionCheckpoint`] and restored after a restart.
+//!
+//! Positions are tracked by tick rather than by entry index, because the
+//! journal trims and compacts old entries. Entries recorded out of order at
+//! a tick older than the engine's position (e.g. late metadata) are not
+//! delivered.
+
+use crate::{Error, Result};
+use pulsive_core::{EntityId, Journal, JournalEntry, PendingWrite, Tick};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+
+/// A read model built by folding journal entries
+pub trait Projection: Any {
+    /// Unique name, used to match saved states on restore
+    fn name(&self) -> &str;
+
+    /// Fold one entry into the projection
+    fn apply(&mut self, entry: &JournalEntry);
+
+    /// Serialize the projection's state for a checkpoint
+    fn save_state(&self) -> Result<String>;
+
+    /// Restore state previously produced by [`save_state`](Self::save_state)
+    fn restore_state(&mut self, state: &str) -> Result<()>;
+}
+
+/// Position of the engine within a journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectionPosition {
+    /// Tick of the last delivered entry
+    pub tick: Tick,
+    /// Number of entries delivered at that tick
+    pub entries_at_tick: usize,
+}
+
+/// Saved engine position and projection states
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectionCheckpoint {
+    /// Position reached (`None` if nothing was delivered yet)
+    pub position: Option<ProjectionPosition>,
+    /// Serialized state of each projection, by name
+    pub states: BTreeMap<String, String>,
+}
+
+impl ProjectionCheckpoint {
+    /// Serialize the checkpoint to RON
+    pub fn to_ron(&self) -> Result<String> {
+        ron::to_string(self).map_err(|e| Error::Serialization(e.to_string()))
+    }
+
+    /// Deserialize a checkpoint from RON
+    pub fn from_ron(source: &str) -> Result<Self> {
+        ron::from_str(source).map_err(|e| Error::Serialization(e.to_string()))
+    }
+}
+
+/// Feeds journal entries to registered projections incrementally
+#[derive(Default)]
+pub struct ProjectionEngine {
+    projections: Vec<Box<dyn Projection>>,
+    position: Option<ProjectionPosition>,
+}
+
+impl ProjectionEngine {
+    /// Create an engine with no projections
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a projection
+    ///
+    /// A projection registered after entries were delivered only sees
+    /// entries from then on.
+    pub fn register(&mut self, projection: impl Projection) {
+        self.projections.push(Box::new(projection));
+    }
+
+    /// Register a projection (builder style)
+    pub fn with(mut self, projection: impl Projection) -> Self {
+        self.register(projection);
+        self
+    }
+
+    /// Get a registered projection by name
+    pub fn get(&self, name: &str) -> Option<&dyn Projection> {
+        self.projections
+            .iter()
+            .find(|p| p.name() == name)
+            .map(|p| p.as_ref())
+    }
+
+    /// Get the first registered projection of a concrete type
+    pub fn projection<P: Projection>(&self) -> Option<&P> {
+        self.projections
+            .iter()
+            .find_map(|p| (p.as_ref() as &dyn Any).downcast_ref::<P>())
+    }
+
+    /// Get the engine's position
+    pub fn position(&self) -> Option<ProjectionPosition> {
+        self.position
+    }
+
+    /// Deliver all entries recorded since the last call
+    ///
+    /// Returns the number of entries delivered.
+    pub fn catch_up(&mut self, journal: &Journal) -> usize {
+        let start = self.position;
+        let mut seen_at_tick = 0;
+        let mut delivered = 0;
+
+        for entry in journal.entries() {
+            let tick = entry.tick();
+            if let Some(position) = start {
+                if tick < position.tick {
+                    continue;
+                }
+                if tick == position.tick {
+                    seen_at_tick += 1;
+                    if seen_at_tick <= position.entries_at_tick {
+                        continue;
+                    }
+                }
+            }
+
+            for projection in &mut self.projections {
+                projection.apply(entry);
+            }
+            delivered += 1;
+            self.position = Some(match self.position {
+                Some(p) if p.tick == tick => ProjectionPosition {
+                    tick,
+                    entries_at_tick: p.entries_at_tick + 1,
+                },
+                _ => ProjectionPosition {
+                    tick,
+                    entries_at_tick: 1,
+                },
+            });
+        }
+
+        delivered
+    }
+
+    /// Save the engine position and every projection's state
+    pub fn checkpoint(&self) -> Result<ProjectionCheckpoint> {
+        let states = self
+            .projections
+            .iter()
+            .map(|p| Ok((p.name().to_string(), p.save_state()?)))
+            .collect::<Result<_>>()?;
+        Ok(ProjectionCheckpoint {
+            position: self.position,
+            states,
+        })
+    }
+
+    /// Restore the engine position and projection states from a checkpoint
+    ///
+    /// Register projections before restoring. Projections without a saved
+    /// state are restored as if they were registered fresh.
+    pub fn restore(&mut self, checkpoint: &ProjectionCheckpoint) -> Result<()> {
+        for projection in &mut self.projections {
+            if let Some(state) = checkpoint.states.get(projection.name()) {
+                projection.restore_state(state)?;
+            }
+        }
+        self.position = checkpoint.position;
+        Ok(())
+    }
+}
+
+/// Counts processed messages by event ID (or message kind for non-events)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventFrequency {
+    /// Message counts by event name
+    pub counts: BTreeMap<String, u64>,
+    /// Message counts by event name and tick
+    pub per_tick: BTreeMap<Tick, BTreeMap<String, u64>>,
+}
+
+impl EventFrequency {
+    /// Create an empty frequency table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the total count for an event
+    pub fn count(&self, event: &str) -> u64 {
+        self.counts.get(event).copied().unwrap_or(0)
+    }
+}
+
+impl Projection for EventFrequency {
+    fn name(&self) -> &str {
+        "event_frequency"
+    }
+
+    fn apply(&mut self, entry: &JournalEntry) {
+        let JournalEntry::Message { tick, msg, .. } = entry else {
+            return;
+        };
+        let name = match &msg.event_id {
+            Some(id) => id.to_string(),
+            None => format!("{:?}", msg.kind),
+        };
+        *self
+            .per_tick
+            .entry(*tick)
+            .or_default()
+            .entry(name.clone())
+            .or_insert(0) += 1;
+        *self.counts.entry(name).or_insert(0) += 1;
+    }
+
+    fn save_state(&self) -> Result<String> {
+        ron::to_string(self).map_err(|e| Error::Serialization(e.to_string()))
+    }
+
+    fn restore_state(&mut self, state: &str) -> Result<()> {
+        *self = ron::from_str(state).map_err(|e| Error::Serialization(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Tracks a numeric entity property over time (e.g. each nation's gold)
+///
+/// Built from write-level recording
+/// ([`JournalConfig::record_writes`](pulsive_core::JournalConfig)). Values
+/// start from zero until the property is first set, so modifications of
+/// properties that existed before recording began are relative.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PropertySeries {
+    name: String,
+    key: String,
+    /// Values after each tick that changed them, by entity
+    pub series: HashMap<EntityId, Vec<(Tick, f64)>>,
+}
+
+impl PropertySeries {
+    /// Track the property `key` on every entity
+    pub fn new(key: impl Into<String>) -> Self {
+        let key = key.into();
+        Self {
+            name: format!("property_series:{}", key),
+            key,
+            series: HashMap::new(),
+        }
+    }
+
+    /// Get the tracked property key
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Get the latest value for an entity
+    pub fn latest(&self, entity: EntityId) -> Option<f64> {
+        self.series.get(&entity)?.last().map(|(_, v)| *v)
+    }
+
+    fn record(&mut self, tick: Tick, entity: EntityId, update: impl FnOnce(f64) -> f64) {
+        let points = self.series.entry(entity).or_default();
+        let value = update(points.last().map_or(0.0, |(_, v)| *v));
+        match points.last_mut() {
+            Some((t, v)) if *t == tick => *v = value,
+            _ => points.push((tick, value)),
+        }
+    }
+}
+
+impl Projection for PropertySeries {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn apply(&mut self, entry: &JournalEntry) {
+        let JournalEntry::Writes { tick, writes, .. } = entry else {
+            return;
+        };
+        for write in writes.iter() {
+            match write {
+                PendingWrite::SetProperty {
+                    entity_id,
+                    key,
+                    value,
+                } if *key == self.key => {
+                    if let Some(value) = value.as_float() {
+                        self.record(*tick, *entity_id, |_| value);
+                    }
+                }
+                PendingWrite::ModifyProperty {
+                    entity_id,
+                    key,
+                    op,
+                    value,
+                } if *key == self.key => {
+                    self.record(*tick, *entity_id, |current| op.apply(current, *value));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn save_state(&self) -> Result<String> {
+        ron::to_string(&self.series).map_err(|e| Error::Serialization(e.to_string()))
+    }
+
+    fn restore_state(&mut self, state: &str) -> Result<()> {
+        self.series = ron::from_str(state).map_err(|e| Error::Serialization(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::{
+        DefId, Effect, EntityRef, Expr, JournalConfig, Model, ModifyOp, Msg, Runtime, TickHandler,
+    };
+
+    fn record(journal: &mut Journal, model: &mut Model, runtime: &mut Runtime, ticks: u64) {
+        for _ in 0..ticks {
+            runtime.send(Msg::event("trade", EntityRef::Global, 0));
+            runtime.tick_with_journal(model, journal);
+        }
+    }
+
+    fn setup() -> (Journal, Model, Runtime) {
+        let mut model = Model::new();
+        model.entities_mut().create("nation").set("gold", 0.0);
+        let mut runtime = Runtime::new();
+        runtime.on_tick(TickHandler {
+            id: DefId::new("income"),
+            condition: None,
+            target_kind: Some(DefId::new("nation")),
+            effects: vec![Effect::ModifyProperty {
+                property: "gold".to_string(),
+                op: ModifyOp::Add,
+                value: Expr::lit(3.0),
+            }],
+            priority: 0,
+        });
+        let journal = Journal::with_config(JournalConfig {
+            recording_enabled: true,
+            record_writes: true,
+            ..Default::default()
+        });
+        (journal, model, runtime)
+    }
+
+    #[test]
+    fn test_incremental_projection() {
+        let (mut journal, mut model, mut runtime) = setup();
+        let nation = model.entities().iter().next().unwrap().id;
+        let mut engine = ProjectionEngine::new()
+            .with(EventFrequency::new())
+            .with(PropertySeries::new("gold"));
+
+        record(&mut journal, &mut model, &mut runtime, 3);
+        assert!(engine.catch_up(&journal) > 0);
+        assert_eq!(engine.catch_up(&journal), 0);
+
+        record(&mut journal, &mut model, &mut runtime, 2);
+        engine.catch_up(&journal);
+
+        let frequency = engine.projection::<EventFrequency>().unwrap();
+        assert_eq!(frequency.count("trade"), 5);
+        let gold = engine.projection::<PropertySeries>().unwrap();
+        assert_eq!(gold.latest(nation), Some(15.0));
+        assert_eq!(gold.series[&nation].len(), 5);
+    }
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let (mut journal, mut model, mut runtime) = setup();
+        let mut engine = ProjectionEngine::new().with(EventFrequency::new());
+        record(&mut journal, &mut model, &mut runtime, 3);
+        engine.catch_up(&journal);
+        let saved = engine.checkpoint().unwrap().to_ron().unwrap();
+
+        record(&mut journal, &mut model, &mut runtime, 2);
+        let mut resumed = ProjectionEngine::new().with(EventFrequency::new());
+        resumed
+            .restore(&ProjectionCheckpoint::from_ron(&saved).unwrap())
+            .unwrap();
+        resumed.catch_up(&journal);
+
+        let frequency = resumed.projection::<EventFrequency>().unwrap();
+        assert_eq!(frequency.count("trade"), 5);
+        assert_eq!(frequency.per_tick.len(), 5);
+    }
+}
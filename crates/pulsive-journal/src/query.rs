@@ -0,0 +1,874 @@
+//! Text query language for auditing journal messages
+//!
+//! Lets ops users query recorded sessions without writing Rust:
+//!
+//! ```text
+//! event == "proxy_error" && tick in 100..200 && target.kind == "backend"
+//!     | count by event
+//!     | histogram by tick 50
+//! ```
+//!
+//! # Syntax
+//!
+//! - Comparisons: `field == value`, `!=`, `<`, `<=`, `>`, `>=`
+//! - Ranges: `field in 100..200` (end exclusive) or `field in 100..=200`
+//! - Combinators: `&&`, `||`, `!` and parentheses
+//! - Values: `"strings"`, numbers, or bare words treated as strings
+//! - Aggregations after `|`: `count by <field>`, `histogram by <field> <bucket>`
+//!
+//! # Fields
+//!
+//! `tick`, `seq`, `event`, `kind`, `actor`, `target` (entity ID),
+//! `target.id`, `target.kind` and `params.<name>`.
+//!
+//! `target.kind` is resolved from the nearest snapshot at or before the
+//! message's tick, so entities spawned after that snapshot have no kind.
+//!
+//! Top-level `&&` terms on `event`, `kind`, `actor` and `tick` are compiled
+//! onto the [`AuditQuery`] filters; everything else is evaluated per
+//! message.
+
+use crate::{AuditQuery, Auditor, Error, Result};
+use pulsive_core::{DefId, EntityRef, Journal, JournalEntry, Msg, MsgKind, Tick, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::num::NonZeroU64;
+
+/// A parsed query string
+#[derive(Debug, Clone)]
+pub struct Query {
+    /// Filters compiled onto the programmatic query
+    filter: AuditQuery,
+    /// Conditions that could not be compiled onto `filter`
+    predicate: Option<Cond>,
+    /// Aggregations to compute over the matching messages
+    aggregations: Vec<Aggregation>,
+}
+
+impl Query {
+    /// Parse a query string
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = lex(source)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            len: source.len(),
+        };
+        let cond = if parser.at_query_end() {
+            None
+        } else {
+            Some(parser.parse_or()?)
+        };
+        let mut aggregations = Vec::new();
+        while parser.eat(&Token::Pipe) {
+            aggregations.push(parser.parse_aggregation()?);
+        }
+        if let Some((token, pos)) = parser.peek_at() {
+            return Err(Error::QueryParse(
+                pos,
+                format!("unexpected {}", token.describe()),
+            ));
+        }
+
+        let mut filter = AuditQuery::new();
+        let predicate = cond.and_then(|cond| compile(cond, &mut filter));
+        Ok(Self {
+            filter,
+            predicate,
+            aggregations,
+        })
+    }
+
+    /// Get the filters compiled onto an [`AuditQuery`]
+    pub fn filter(&self) -> &AuditQuery {
+        &self.filter
+    }
+
+    /// Get the requested aggregations
+    pub fn aggregations(&self) -> &[Aggregation] {
+        &self.aggregations
+    }
+
+    /// Check if a message entry matches the query's conditions
+    fn matches(&self, journal: &Journal, entry: &JournalEntry) -> bool {
+        match (&self.predicate, entry) {
+            (None, _) => true,
+            (Some(cond), JournalEntry::Message { tick, msg, seq }) => {
+                cond.eval(&MessageView::new(journal, *tick, *seq, msg))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// An aggregation clause
+#[derive(Debug, Clone, PartialEq)]
+pub enum Aggregation {
+    /// Count matching messages grouped by a field's value
+    CountBy(Field),
+    /// Count matching messages in fixed-size buckets of a numeric field
+    Histogram {
+        /// Field to bucket
+        field: Field,
+        /// Bucket width
+        bucket: NonZeroU64,
+    },
+}
+
+impl fmt::Display for Aggregation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Aggregation::CountBy(field) => write!(f, "count by {}", field),
+            Aggregation::Histogram { field, bucket } => {
+                write!(f, "histogram by {} {}", field, bucket)
+            }
+        }
+    }
+}
+
+/// Result of an aggregation clause
+#[derive(Debug, Clone, PartialEq)]
+pub enum AggregateResult {
+    /// Message counts per field value (missing values are counted under "")
+    Counts(BTreeMap<String, u64>),
+    /// Message counts per bucket start
+    Histogram(BTreeMap<u64, u64>),
+}
+
+/// Result of running a [`Query`]
+#[derive(Debug, Clone)]
+pub struct QueryResult<'a> {
+    /// Matching journal entries
+    pub entries: Vec<&'a JournalEntry>,
+    /// One result per aggregation clause, in order
+    pub aggregates: Vec<AggregateResult>,
+}
+
+impl<'a> Auditor<'a> {
+    /// Run a query string against the journal
+    ///
+    /// See the [`Query`] docs for the syntax.
+    pub fn query_str(&self, source: &str) -> Result<QueryResult<'_>> {
+        Ok(self.run(&Query::parse(source)?))
+    }
+
+    /// Run a parsed query against the journal
+    pub fn run(&self, query: &Query) -> QueryResult<'_> {
+        let journal = self.journal();
+        let entries: Vec<_> = self
+            .query(&query.filter)
+            .into_iter()
+            .filter(|entry| query.matches(journal, entry))
+            .collect();
+
+        let aggregates = query
+            .aggregations
+            .iter()
+            .map(|aggregation| aggregate(journal, aggregation, &entries))
+            .collect();
+
+        QueryResult {
+            entries,
+            aggregates,
+        }
+    }
+}
+
+fn aggregate(
+    journal: &Journal,
+    aggregation: &Aggregation,
+    entries: &[&JournalEntry],
+) -> AggregateResult {
+    let views = entries.iter().filter_map(|entry| match entry {
+        JournalEntry::Message { tick, msg, seq } => {
+            Some(MessageView::new(journal, *tick, *seq, msg))
+        }
+        _ => None,
+    });
+
+    match aggregation {
+        Aggregation::CountBy(field) => {
+            let mut counts = BTreeMap::new();
+            for view in views {
+                let key = view.get(field).map(|v| v.to_string()).unwrap_or_default();
+                *counts.entry(key).or_insert(0) += 1;
+            }
+            AggregateResult::Counts(counts)
+        }
+        Aggregation::Histogram { field, bucket } => {
+            let mut buckets = BTreeMap::new();
+            for view in views {
+                if let Some(FieldValue::Num(n)) = view.get(field) {
+                    let bucket = bucket.get();
+                    let start = (n.max(0.0) as u64 / bucket) * bucket;
+                    *buckets.entry(start).or_insert(0) += 1;
+                }
+            }
+            AggregateResult::Histogram(buckets)
+        }
+    }
+}
+
+/// Move top-level conjuncts onto `filter`, returning what is left
+fn compile(cond: Cond, filter: &mut AuditQuery) -> Option<Cond> {
+    let mut rest = Vec::new();
+    for term in cond.into_conjuncts() {
+        if !compile_term(&term, filter) {
+            rest.push(term);
+        }
+    }
+    rest.into_iter()
+        .reduce(|a, b| Cond::And(Box::new(a), Box::new(b)))
+}
+
+fn compile_term(term: &Cond, filter: &mut AuditQuery) -> bool {
+    match term {
+        Cond::Compare(Field::Event, CmpOp::Eq, FieldValue::Str(s)) if filter.event_id.is_none() => {
+            filter.event_id = Some(DefId::new(s.as_str()));
+            true
+        }
+        Cond::Compare(Field::Kind, CmpOp::Eq, FieldValue::Str(s)) if filter.msg_kind.is_none() => {
+            match parse_kind(s) {
+                Some(kind) => {
+                    filter.msg_kind = Some(kind);
+                    true
+                }
+                None => false,
+            }
+        }
+        Cond::Compare(Field::Actor, CmpOp::Eq, FieldValue::Num(n)) if filter.actor_id.is_none() => {
+            filter.actor_id = Some(*n as u64);
+            true
+        }
+        Cond::Compare(Field::Tick, op, FieldValue::Num(n)) => {
+            let n = n.max(0.0) as u64;
+            match op {
+                CmpOp::Ge => narrow(filter, Some(n), None),
+                CmpOp::Gt => narrow(filter, Some(n + 1), None),
+                CmpOp::Le => narrow(filter, None, Some(n)),
+                CmpOp::Lt if n > 0 => narrow(filter, None, Some(n - 1)),
+                CmpOp::Eq => narrow(filter, Some(n), Some(n)),
+                _ => return false,
+            }
+            true
+        }
+        Cond::InRange(Field::Tick, start, end, inclusive) => {
+            let start = start.max(0.0) as u64;
+            let end = end.max(0.0) as u64;
+            match (inclusive, end) {
+                (true, end) => narrow(filter, Some(start), Some(end)),
+                (false, 0) => return false,
+                (false, end) => narrow(filter, Some(start), Some(end - 1)),
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Intersect the filter's tick range with `start..=end`
+fn narrow(filter: &mut AuditQuery, start: Option<u64>, end: Option<u64>) {
+    if let Some(start) = start {
+        filter.start_tick = Some(filter.start_tick.map_or(start, |s| s.max(start)));
+    }
+    if let Some(end) = end {
+        filter.end_tick = Some(filter.end_tick.map_or(end, |e| e.min(end)));
+    }
+}
+
+fn parse_kind(name: &str) -> Option<MsgKind> {
+    Some(match name {
+        "Tick" => MsgKind::Tick,
+        "Command" => MsgKind::Command,
+        "Event" => MsgKind::Event,
+        "ScheduledEvent" => MsgKind::ScheduledEvent,
+        "EntitySpawned" => MsgKind::EntitySpawned,
+        "EntityDestroyed" => MsgKind::EntityDestroyed,
+        "PropertyChanged" => MsgKind::PropertyChanged,
+        "FlagAdded" => MsgKind::FlagAdded,
+        "FlagRemoved" => MsgKind::FlagRemoved,
+        _ => return None,
+    })
+}
+
+// ============================================================================
+// Conditions
+// ============================================================================
+
+/// A field of a recorded message
+#[derive(Debug, Clone, PartialEq)]
+pub enum Field {
+    /// Tick the message was processed in
+    Tick,
+    /// Sequence number within the tick
+    Seq,
+    /// Event or action ID
+    Event,
+    /// Message kind name
+    Kind,
+    /// Actor ID
+    Actor,
+    /// Target entity ID
+    TargetId,
+    /// Kind of the target entity
+    TargetKind,
+    /// A message parameter
+    Param(String),
+}
+
+impl Field {
+    fn parse(path: &str) -> Option<Self> {
+        Some(match path {
+            "tick" => Field::Tick,
+            "seq" => Field::Seq,
+            "event" => Field::Event,
+            "kind" => Field::Kind,
+            "actor" => Field::Actor,
+            "target" | "target.id" => Field::TargetId,
+            "target.kind" => Field::TargetKind,
+            _ => Field::Param(path.strip_prefix("params.")?.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Field::Tick => f.write_str("tick"),
+            Field::Seq => f.write_str("seq"),
+            Field::Event => f.write_str("event"),
+            Field::Kind => f.write_str("kind"),
+            Field::Actor => f.write_str("actor"),
+            Field::TargetId => f.write_str("target.id"),
+            Field::TargetKind => f.write_str("target.kind"),
+            Field::Param(name) => write!(f, "params.{}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FieldValue {
+    Num(f64),
+    Str(String),
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::Num(n) => write!(f, "{}", n),
+            FieldValue::Str(s) => f.write_str(s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+enum Cond {
+    Compare(Field, CmpOp, FieldValue),
+    InRange(Field, f64, f64, bool),
+    And(Box<Cond>, Box<Cond>),
+    Or(Box<Cond>, Box<Cond>),
+    Not(Box<Cond>),
+}
+
+impl Cond {
+    fn into_conjuncts(self) -> Vec<Cond> {
+        match self {
+            Cond::And(a, b) => {
+                let mut terms = a.into_conjuncts();
+                terms.extend(b.into_conjuncts());
+                terms
+            }
+            other => vec![other],
+        }
+    }
+
+    fn eval(&self, view: &MessageView) -> bool {
+        match self {
+            Cond::Compare(field, op, expected) => {
+                let Some(actual) = view.get(field) else {
+                    return *op == CmpOp::Ne;
+                };
+                let ordering = match (&actual, expected) {
+                    (FieldValue::Num(a), FieldValue::Num(b)) => a.partial_cmp(b),
+                    (FieldValue::Str(a), FieldValue::Str(b)) => Some(a.cmp(b)),
+                    _ => None,
+                };
+                match (op, ordering) {
+                    (CmpOp::Eq, o) => o == Some(std::cmp::Ordering::Equal),
+                    (CmpOp::Ne, o) => o != Some(std::cmp::Ordering::Equal),
+                    (_, None) => false,
+                    (CmpOp::Lt, Some(o)) => o.is_lt(),
+                    (CmpOp::Le, Some(o)) => o.is_le(),
+                    (CmpOp::Gt, Some(o)) => o.is_gt(),
+                    (CmpOp::Ge, Some(o)) => o.is_ge(),
+                }
+            }
+            Cond::InRange(field, start, end, inclusive) => match view.get(field) {
+                Some(FieldValue::Num(n)) => n >= *start && (n < *end || (*inclusive && n == *end)),
+                _ => false,
+            },
+            Cond::And(a, b) => a.eval(view) && b.eval(view),
+            Cond::Or(a, b) => a.eval(view) || b.eval(view),
+            Cond::Not(inner) => !inner.eval(view),
+        }
+    }
+}
+
+/// A message with the context needed to resolve fields
+struct MessageView<'a> {
+    journal: &'a Journal,
+    tick: Tick,
+    seq: u64,
+    msg: &'a Msg,
+}
+
+impl<'a> MessageView<'a> {
+    fn new(journal: &'a Journal, tick: Tick, seq: u64, msg: &'a Msg) -> Self {
+        Self {
+            journal,
+            tick,
+            seq,
+            msg,
+        }
+    }
+
+    fn get(&self, field: &Field) -> Option<FieldValue> {
+        let msg = self.msg;
+        match field {
+            Field::Tick => Some(FieldValue::Num(self.tick as f64)),
+            Field::Seq => Some(FieldValue::Num(self.seq as f64)),
+            Field::Event => msg
+                .event_id
+                .as_ref()
+                .map(|id| FieldValue::Str(id.to_string())),
+            Field::Kind => Some(FieldValue::Str(match &msg.kind {
+                MsgKind::Custom(id) => id.to_string(),
+                kind => format!("{:?}", kind),
+            })),
+            Field::Actor => msg.actor.map(|a| FieldValue::Num(a.raw() as f64)),
+            Field::TargetId => match msg.target {
+                EntityRef::Entity(id) => Some(FieldValue::Num(id.raw() as f64)),
+                _ => None,
+            },
+            Field::TargetKind => {
+                let EntityRef::Entity(id) = msg.target else {
+                    return None;
+                };
+                let snapshot = self.journal.snapshot_at_or_before(self.tick)?;
+                let entity = snapshot.model.entities().get(id)?;
+                Some(FieldValue::Str(entity.kind.to_string()))
+            }
+            Field::Param(name) => match msg.params.get(name)? {
+                Value::String(s) => Some(FieldValue::Str(s.clone())),
+                value => value
+                    .as_float()
+                    .map(FieldValue::Num)
+                    .or_else(|| Some(FieldValue::Str(value.to_string()))),
+            },
+        }
+    }
+}
+
+// ============================================================================
+// Lexer and parser
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Cmp(CmpOp),
+    AndAnd,
+    OrOr,
+    Not,
+    LParen,
+    RParen,
+    Pipe,
+    DotDot,
+    DotDotEq,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Ident(s) => format!("'{}'", s),
+            Token::Str(s) => format!("\"{}\"", s),
+            Token::Num(n) => format!("number {}", n),
+            Token::Cmp(_) => "comparison".to_string(),
+            Token::AndAnd => "'&&'".to_string(),
+            Token::OrOr => "'||'".to_string(),
+            Token::Not => "'!'".to_string(),
+            Token::LParen => "'('".to_string(),
+            Token::RParen => "')'".to_string(),
+            Token::Pipe => "'|'".to_string(),
+            Token::DotDot => "'..'".to_string(),
+            Token::DotDotEq => "'..='".to_string(),
+        }
+    }
+}
+
+fn lex(source: &str) -> Result<Vec<(Token, usize)>> {
+    let chars: Vec<(usize, char)> = source.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let peek = |i: usize| chars.get(i).map(|(_, c)| *c);
+
+    while let Some(&(pos, c)) = chars.get(i) {
+        let two = (c, peek(i + 1));
+        let token = match two {
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('=', Some('=')) => Token::Cmp(CmpOp::Eq),
+            ('!', Some('=')) => Token::Cmp(CmpOp::Ne),
+            ('<', Some('=')) => Token::Cmp(CmpOp::Le),
+            ('>', Some('=')) => Token::Cmp(CmpOp::Ge),
+            ('&', Some('&')) => Token::AndAnd,
+            ('|', Some('|')) => Token::OrOr,
+            ('.', Some('.')) if peek(i + 2) == Some('=') => {
+                i += 1;
+                Token::DotDotEq
+            }
+            ('.', Some('.')) => Token::DotDot,
+            ('<', _) => Token::Cmp(CmpOp::Lt),
+            ('>', _) => Token::Cmp(CmpOp::Gt),
+            ('!', _) => Token::Not,
+            ('(', _) => Token::LParen,
+            (')', _) => Token::RParen,
+            ('|', _) => Token::Pipe,
+            ('"', _) => {
+                let start = i + 1;
+                let mut end = start;
+                while peek(end).is_some_and(|c| c != '"') {
+                    end += 1;
+                }
+                if peek(end).is_none() {
+                    return Err(Error::QueryParse(pos, "unterminated string".to_string()));
+                }
+                let text = chars[start..end].iter().map(|(_, c)| c).collect();
+                tokens.push((Token::Str(text), pos));
+                i = end + 1;
+                continue;
+            }
+            (c, _)
+                if c.is_ascii_digit()
+                    || (c == '-' && two.1.is_some_and(|d| d.is_ascii_digit())) =>
+            {
+                let start = i;
+                i += 1;
+                while let Some(c) = peek(i) {
+                    // Stop before a `..` range operator
+                    let is_fraction = c == '.' && peek(i + 1).is_some_and(|d| d.is_ascii_digit());
+                    if c.is_ascii_digit() || is_fraction {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                let text: String = chars[start..i].iter().map(|(_, c)| c).collect();
+                let n = text
+                    .parse()
+                    .map_err(|_| Error::QueryParse(pos, format!("invalid number '{}'", text)))?;
+                tokens.push((Token::Num(n), pos));
+                continue;
+            }
+            (c, _) if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while peek(i)
+                    .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == ':')
+                {
+                    i += 1;
+                }
+                let text = chars[start..i].iter().map(|(_, c)| c).collect();
+                tokens.push((Token::Ident(text), pos));
+                continue;
+            }
+            (c, _) => {
+                return Err(Error::QueryParse(
+                    pos,
+                    format!("unexpected character '{}'", c),
+                ));
+            }
+        };
+        let width = match token {
+            Token::Cmp(CmpOp::Lt | CmpOp::Gt) | Token::Not | Token::LParen | Token::RParen => 1,
+            Token::Pipe => 1,
+            _ => 2,
+        };
+        tokens.push((token, pos));
+        i += width;
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// Source length, used as the position of end-of-input errors
+    len: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn peek_at(&self) -> Option<(&Token, usize)> {
+        self.tokens.get(self.pos).map(|(t, p)| (t, *p))
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.len, |(_, p)| *p)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(t, _)| t.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T> {
+        Err(Error::QueryParse(self.position(), message.into()))
+    }
+
+    fn at_query_end(&self) -> bool {
+        matches!(self.peek(), None | Some(Token::Pipe))
+    }
+
+    fn parse_or(&mut self) -> Result<Cond> {
+        let mut cond = self.parse_and()?;
+        while self.eat(&Token::OrOr) {
+            cond = Cond::Or(Box::new(cond), Box::new(self.parse_and()?));
+        }
+        Ok(cond)
+    }
+
+    fn parse_and(&mut self) -> Result<Cond> {
+        let mut cond = self.parse_unary()?;
+        while self.eat(&Token::AndAnd) {
+            cond = Cond::And(Box::new(cond), Box::new(self.parse_unary()?));
+        }
+        Ok(cond)
+    }
+
+    fn parse_unary(&mut self) -> Result<Cond> {
+        if self.eat(&Token::Not) {
+            return Ok(Cond::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.eat(&Token::LParen) {
+            let cond = self.parse_or()?;
+            if !self.eat(&Token::RParen) {
+                return self.error("expected ')'");
+            }
+            return Ok(cond);
+        }
+
+        let field = self.parse_field()?;
+        match self.next() {
+            Some(Token::Cmp(op)) => Ok(Cond::Compare(field, op, self.parse_value()?)),
+            Some(Token::Ident(word)) if word == "in" => {
+                let start = self.parse_number()?;
+                let inclusive = match self.next() {
+                    Some(Token::DotDot) => false,
+                    Some(Token::DotDotEq) => true,
+                    _ => {
+                        self.pos -= 1;
+                        return self.error("expected '..' or '..='");
+                    }
+                };
+                let end = self.parse_number()?;
+                Ok(Cond::InRange(field, start, end, inclusive))
+            }
+            _ => {
+                self.pos -= 1;
+                self.error("expected a comparison or 'in'")
+            }
+        }
+    }
+
+    fn parse_field(&mut self) -> Result<Field> {
+        match self.next() {
+            Some(Token::Ident(path)) => match Field::parse(&path) {
+                Some(field) => Ok(field),
+                None => {
+                    self.pos -= 1;
+                    self.error(format!("unknown field '{}'", path))
+                }
+            },
+            _ => {
+                self.pos = self.pos.saturating_sub(1);
+                self.error("expected a field")
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<FieldValue> {
+        match self.next() {
+            Some(Token::Str(s) | Token::Ident(s)) => Ok(FieldValue::Str(s)),
+            Some(Token::Num(n)) => Ok(FieldValue::Num(n)),
+            _ => {
+                self.pos = self.pos.saturating_sub(1);
+                self.error("expected a value")
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<f64> {
+        match self.next() {
+            Some(Token::Num(n)) => Ok(n),
+            _ => {
+                self.pos = self.pos.saturating_sub(1);
+                self.error("expected a number")
+            }
+        }
+    }
+
+    fn parse_aggregation(&mut self) -> Result<Aggregation> {
+        let word = match self.next() {
+            Some(Token::Ident(word)) => word,
+            _ => {
+                self.pos = self.pos.saturating_sub(1);
+                return self.error("expected 'count' or 'histogram'");
+            }
+        };
+        if !matches!(self.next(), Some(Token::Ident(by)) if by == "by") {
+            self.pos = self.pos.saturating_sub(1);
+            return self.error("expected 'by'");
+        }
+        let field = self.parse_field()?;
+
+        match word.as_str() {
+            "count" => Ok(Aggregation::CountBy(field)),
+            "histogram" => {
+                let bucket = self.parse_number()?;
+                match NonZeroU64::new(bucket as u64) {
+                    Some(bucket) => Ok(Aggregation::Histogram { field, bucket }),
+                    None => {
+                        self.pos -= 1;
+                        self.error("bucket size must be at least 1")
+                    }
+                }
+            }
+            other => Err(Error::QueryParse(
+                self.position(),
+                format!("unknown aggregation '{}'", other),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::{ActorId, Model};
+
+    fn create_test_journal() -> Journal {
+        let mut journal = Journal::new();
+        journal.start_recording();
+
+        let mut model = Model::new();
+        let backend = model.entities_mut().create("backend").id;
+        let frontend = model.entities_mut().create("frontend").id;
+        journal.take_snapshot(&model);
+
+        for tick in 0..300 {
+            journal.record_message(tick, Msg::tick(tick));
+            if tick % 10 == 0 {
+                let target = if tick % 20 == 0 { backend } else { frontend };
+                let mut msg = Msg::event("proxy_error", EntityRef::Entity(target), tick);
+                msg.params.insert("status".to_string(), Value::Int(502));
+                journal.record_message(tick, msg);
+            }
+        }
+
+        let mut command = Msg::event("build", EntityRef::Global, 5);
+        command.kind = MsgKind::Command;
+        command.actor = Some(ActorId::new(7));
+        journal.record_message(5, command);
+        journal
+    }
+
+    #[test]
+    fn test_compiles_onto_filters() {
+        let query =
+            Query::parse(r#"event == "proxy_error" && tick in 100..200 && actor == 3"#).unwrap();
+
+        assert_eq!(query.filter().event_id, Some(DefId::new("proxy_error")));
+        assert_eq!(query.filter().start_tick, Some(100));
+        assert_eq!(query.filter().end_tick, Some(199));
+        assert_eq!(query.filter().actor_id, Some(3));
+        assert!(query.predicate.is_none());
+    }
+
+    #[test]
+    fn test_query_with_target_kind() {
+        let journal = create_test_journal();
+        let auditor = Auditor::new(&journal);
+
+        let result = auditor
+            .query_str(r#"event == "proxy_error" && tick in 100..200 && target.kind == "backend""#)
+            .unwrap();
+        // Ticks 100, 120, 140, 160, 180
+        assert_eq!(result.entries.len(), 5);
+
+        let result = auditor
+            .query_str("params.status >= 500 && !(target.kind == backend)")
+            .unwrap();
+        assert_eq!(result.entries.len(), 15);
+    }
+
+    #[test]
+    fn test_aggregations() {
+        let journal = create_test_journal();
+        let auditor = Auditor::new(&journal);
+
+        let result = auditor
+            .query_str("kind == Event || kind == Command | count by event | histogram by tick 100")
+            .unwrap();
+        assert_eq!(result.entries.len(), 31);
+
+        let AggregateResult::Counts(counts) = &result.aggregates[0] else {
+            panic!("expected counts");
+        };
+        assert_eq!(counts.get("proxy_error"), Some(&30));
+        assert_eq!(counts.get("build"), Some(&1));
+
+        let AggregateResult::Histogram(buckets) = &result.aggregates[1] else {
+            panic!("expected histogram");
+        };
+        assert_eq!(buckets.get(&0), Some(&11));
+        assert_eq!(buckets.get(&200), Some(&10));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = Query::parse("tick in 5").unwrap_err();
+        assert!(matches!(err, Error::QueryParse(9, _)));
+        assert!(Query::parse("bogus == 1").is_err());
+        assert!(Query::parse(r#"event == "unterminated"#).is_err());
+        assert!(Query::parse("tick > 5 | histogram by tick 0").is_err());
+        assert!(Query::parse("| count by event").is_ok());
+    }
+}
@@ -0,0 +1,322 @@
+//! Redaction of sensitive data before sharing journals
+//!
+//! A [`RedactionPolicy`] lists what must not leave the building: entity
+//! properties and globals by name pattern, message parameters by key
+//! pattern, metadata by key pattern, and actors by ID. Applying it yields a
+//! copy of the journal with matching values replaced, which the
+//! [`Exporter`](crate::Exporter) and [`Auditor`](crate::Auditor) can use
+//! via their `with_redaction` constructors.
+//!
+//! Patterns match whole names and support `*` as a wildcard, e.g.
+//! `"email"`, `"*_token"` or `"secret*"`.
+
+use pulsive_core::{
+    ActorId, HandlerTrace, Journal, JournalEntry, Model, Msg, PendingWrite, Snapshot, Value,
+    ValueMap, WriteSet,
+};
+use std::collections::HashSet;
+
+/// Default value substituted for redacted values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Rules for removing sensitive data from a journal
+#[derive(Debug, Clone)]
+pub struct RedactionPolicy {
+    properties: Vec<String>,
+    params: Vec<String>,
+    metadata: Vec<String>,
+    actors: HashSet<ActorId>,
+    replacement: Value,
+}
+
+impl Default for RedactionPolicy {
+    fn default() -> Self {
+        Self {
+            properties: Vec::new(),
+            params: Vec::new(),
+            metadata: Vec::new(),
+            actors: HashSet::new(),
+            replacement: Value::String(REDACTED.to_string()),
+        }
+    }
+}
+
+impl RedactionPolicy {
+    /// Create an empty policy that redacts nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Redact entity properties and globals whose name matches `pattern`
+    pub fn redact_property(mut self, pattern: impl Into<String>) -> Self {
+        self.properties.push(pattern.into());
+        self
+    }
+
+    /// Redact message and event parameters whose key matches `pattern`
+    pub fn redact_param(mut self, pattern: impl Into<String>) -> Self {
+        self.params.push(pattern.into());
+        self
+    }
+
+    /// Redact metadata values whose key matches `pattern`
+    pub fn redact_metadata(mut self, pattern: impl Into<String>) -> Self {
+        self.metadata.push(pattern.into());
+        self
+    }
+
+    /// Remove an actor from messages and snapshots
+    ///
+    /// The actor's messages are kept but no longer attributed, and its
+    /// context is dropped from snapshots.
+    pub fn redact_actor(mut self, actor: ActorId) -> Self {
+        self.actors.insert(actor);
+        self
+    }
+
+    /// Set the value substituted for redacted values
+    pub fn with_replacement(mut self, value: impl Into<Value>) -> Self {
+        self.replacement = value.into();
+        self
+    }
+
+    /// Check if the policy redacts anything
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+            && self.params.is_empty()
+            && self.metadata.is_empty()
+            && self.actors.is_empty()
+    }
+
+    /// Produce a redacted copy of a journal
+    pub fn apply(&self, journal: &Journal) -> Journal {
+        let entries = journal
+            .entries()
+            .iter()
+            .map(|entry| self.redact_entry(entry.clone()))
+            .collect();
+        let snapshots = journal
+            .snapshots()
+            .iter()
+            .map(|snapshot| {
+                let mut snapshot = snapshot.clone();
+                self.redact_model(&mut snapshot.model);
+                snapshot
+            })
+            .collect::<Vec<Snapshot>>();
+        Journal::from_parts(journal.config().clone(), entries, snapshots)
+    }
+
+    /// Redact a single journal entry
+    pub fn redact_entry(&self, mut entry: JournalEntry) -> JournalEntry {
+        match &mut entry {
+            JournalEntry::Message { msg, .. } => self.redact_msg(msg),
+            JournalEntry::Writes { writes, .. } => self.redact_writes(writes),
+            JournalEntry::Provenance { handlers, .. } => {
+                handlers.iter_mut().for_each(|h| self.redact_trace(h))
+            }
+            JournalEntry::Metadata { key, value, .. } => {
+                if matches_any(&self.metadata, key) {
+                    *value = match self.replacement.as_str() {
+                        Some(text) => text.to_string(),
+                        None => self.replacement.to_string(),
+                    };
+                }
+            }
+            JournalEntry::TickBoundary { .. }
+            | JournalEntry::Snapshot { .. }
+            | JournalEntry::Rng { .. } => {}
+        }
+        entry
+    }
+
+    /// Redact a message's parameters and actor
+    pub fn redact_msg(&self, msg: &mut Msg) {
+        self.redact_map(&mut msg.params, &self.params);
+        if msg.actor.is_some_and(|a| self.actors.contains(&a)) {
+            msg.actor = None;
+        }
+    }
+
+    /// Redact entity properties, globals and actor contexts of a model
+    pub fn redact_model(&self, model: &mut Model) {
+        if !self.properties.is_empty() {
+            // Only touch the copy-on-write stores when something matches
+            let needs_redaction = model.entities().iter().any(|e| {
+                e.properties
+                    .keys()
+                    .any(|k| matches_any(&self.properties, k))
+            });
+            if needs_redaction {
+                for entity in model.entities_mut().iter_mut() {
+                    self.redact_map(&mut entity.properties, &self.properties);
+                }
+            }
+            if model
+                .globals()
+                .keys()
+                .any(|k| matches_any(&self.properties, k))
+            {
+                self.redact_map(model.globals_mut(), &self.properties);
+            }
+        }
+        model.actors.retain(|id, _| !self.actors.contains(id));
+    }
+
+    fn redact_writes(&self, writes: &mut WriteSet) {
+        if self.properties.is_empty() {
+            return;
+        }
+        let redacted = writes.iter().map(|write| self.redact_write(write.clone()));
+        *writes = redacted.collect();
+    }
+
+    fn redact_write(&self, mut write: PendingWrite) -> PendingWrite {
+        match &mut write {
+            PendingWrite::SetProperty { key, value, .. }
+            | PendingWrite::SetGlobal { key, value } => {
+                if matches_any(&self.properties, key) {
+                    *value = self.replacement.clone();
+                }
+            }
+            PendingWrite::ModifyProperty { key, value, .. }
+            | PendingWrite::ModifyGlobal { key, value, .. } => {
+                if matches_any(&self.properties, key) {
+                    *value = 0.0;
+                }
+            }
+            PendingWrite::SpawnEntity { properties, .. } => {
+                self.redact_map(properties, &self.properties)
+            }
+            PendingWrite::AddFlag { .. }
+            | PendingWrite::AddFlagFor { .. }
+            | PendingWrite::RemoveFlag { .. }
+            | PendingWrite::AddTag { .. }
+            | PendingWrite::RemoveTag { .. }
+            | PendingWrite::DestroyEntity { .. } => {}
+        }
+        write
+    }
+
+    fn redact_trace(&self, trace: &mut HandlerTrace) {
+        for effect in &mut trace.effects {
+            self.redact_writes(&mut effect.writes);
+            for (_, _, params) in &mut effect.emitted {
+                self.redact_map(params, &self.params);
+            }
+            for (_, _, _, params) in &mut effect.scheduled {
+                self.redact_map(params, &self.params);
+            }
+        }
+    }
+
+    fn redact_map(&self, map: &mut ValueMap, patterns: &[String]) {
+        for (key, value) in map.iter_mut() {
+            if matches_any(patterns, key) {
+                *value = self.replacement.clone();
+            }
+        }
+    }
+}
+
+fn matches_any(patterns: &[String], name: &str) -> bool {
+    patterns.iter().any(|pattern| glob_match(pattern, name))
+}
+
+/// Match `name` against a pattern where `*` matches any run of characters
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<_> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No wildcard: the whole name must match
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::{Context, EntityRef, JournalConfig};
+
+    fn create_test_journal() -> Journal {
+        let mut journal = Journal::with_config(JournalConfig {
+            recording_enabled: true,
+            ..Default::default()
+        });
+
+        let mut model = Model::new();
+        let player = model.entities_mut().create("player");
+        player.set("name", "alice");
+        player.set("email", "alice@example.com");
+        player.set("gold", 10.0);
+        model.set_global("api_token", "hunter2");
+        model.add_actor(Context::new(ActorId::new(1)));
+        journal.take_snapshot(&model);
+
+        let mut msg = Msg::event("login", EntityRef::Global, 1);
+        msg.params
+            .insert("password".to_string(), Value::from("hunter2"));
+        msg.params.insert("region".to_string(), Value::from("eu"));
+        msg.actor = Some(ActorId::new(1));
+        journal.record_message(1, msg);
+        journal.record_metadata(1, "user_ip", "10.0.0.1");
+        journal
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("email", "email"));
+        assert!(!glob_match("email", "email2"));
+        assert!(glob_match("*_token", "api_token"));
+        assert!(glob_match("secret*", "secret"));
+        assert!(glob_match("a*b*c", "aXbYc"));
+        assert!(!glob_match("a*b*c", "aXc"));
+        assert!(glob_match("*", "anything"));
+    }
+
+    #[test]
+    fn test_apply_redacts_journal() {
+        let journal = create_test_journal();
+        let policy = RedactionPolicy::new()
+            .redact_property("email")
+            .redact_property("*_token")
+            .redact_param("password")
+            .redact_metadata("user_*")
+            .redact_actor(ActorId::new(1));
+
+        let redacted = policy.apply(&journal);
+        let redacted_value = Value::String(REDACTED.to_string());
+
+        let model = &redacted.snapshots()[0].model;
+        let player = model.entities().iter().next().unwrap();
+        assert_eq!(player.get("email"), Some(&redacted_value));
+        assert_eq!(player.get("name"), Some(&Value::from("alice")));
+        assert_eq!(model.get_global("api_token"), Some(&redacted_value));
+        assert!(model.actors().is_empty());
+
+        let (_, msg) = redacted.messages().next().unwrap();
+        assert_eq!(msg.params.get("password"), Some(&redacted_value));
+        assert_eq!(msg.params.get("region"), Some(&Value::from("eu")));
+        assert_eq!(msg.actor, None);
+
+        assert!(redacted.entries().iter().any(|e| matches!(
+            e,
+            JournalEntry::Metadata { value, .. } if value == REDACTED
+        )));
+
+        // The original is untouched
+        let (_, msg) = journal.messages().next().unwrap();
+        assert_eq!(msg.params.get("password"), Some(&Value::from("hunter2")));
+    }
+}
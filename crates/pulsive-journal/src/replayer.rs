@@ -3,7 +3,9 @@
 #![allow(dead_code)] // Public API that will be used by consumers
 
 use crate::Result;
-use pulsive_core::{Journal, JournalEntry, Model, Msg, Runtime};
+use pulsive_core::{
+    EntityId, EvalContext, Expr, Journal, JournalEntry, Model, Msg, Runtime, Value, ValueMap,
+};
 
 /// Speed for replay playback
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
@@ -30,6 +32,125 @@ pub enum ReplayState {
     Finished,
 }
 
+/// How often breakpoints are checked during replay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BreakOn {
+    /// After every replayed tick
+    #[default]
+    Tick,
+    /// After every replayed message
+    Message,
+}
+
+/// Identifier of a breakpoint or watchpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BreakpointId(pub u32);
+
+/// A value watched for changes during replay
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchTarget {
+    /// A global property
+    Global(String),
+    /// A property of an entity
+    Property(EntityId, String),
+}
+
+impl WatchTarget {
+    /// Read the watched value from a model
+    fn read(&self, model: &Model) -> Option<Value> {
+        match self {
+            WatchTarget::Global(key) => model.get_global(key).cloned(),
+            WatchTarget::Property(id, key) => model.entities().get(*id)?.get(key).cloned(),
+        }
+    }
+}
+
+/// Why a breakpoint paused the replay
+#[derive(Debug, Clone, PartialEq)]
+pub enum HitReason {
+    /// A breakpoint condition became true
+    Condition,
+    /// A watched value changed
+    Changed {
+        /// Value before the triggering step
+        old: Option<Value>,
+        /// Value after the triggering step
+        new: Option<Value>,
+    },
+}
+
+/// Report of a breakpoint that paused the replay
+#[derive(Debug, Clone)]
+pub struct BreakpointHit {
+    /// The breakpoint that fired
+    pub id: BreakpointId,
+    /// Tick being replayed when it fired
+    pub tick: u64,
+    /// Why it fired
+    pub reason: HitReason,
+    /// The message entry replayed last before it fired
+    pub entry: Option<JournalEntry>,
+}
+
+#[derive(Debug, Clone)]
+enum BreakKind {
+    /// Fires when the condition goes from false to true
+    Condition { expr: Expr, last: bool },
+    /// Fires when the watched value changes
+    Watch {
+        target: WatchTarget,
+        last: Option<Value>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Breakpoint {
+    id: BreakpointId,
+    kind: BreakKind,
+}
+
+impl Breakpoint {
+    /// Record the current state without firing
+    fn prime(&mut self, model: &Model) {
+        match &mut self.kind {
+            BreakKind::Condition { expr, last } => *last = eval_condition(expr, model, None),
+            BreakKind::Watch { target, last } => *last = target.read(model),
+        }
+    }
+
+    /// Update the recorded state, returning why it fired (if it did)
+    fn check(&mut self, model: &Model, msg: Option<&Msg>) -> Option<HitReason> {
+        match &mut self.kind {
+            BreakKind::Condition { expr, last } => {
+                let now = eval_condition(expr, model, msg);
+                let fired = now && !*last;
+                *last = now;
+                fired.then_some(HitReason::Condition)
+            }
+            BreakKind::Watch { target, last } => {
+                let now = target.read(model);
+                if now == *last {
+                    return None;
+                }
+                let old = std::mem::replace(last, now.clone());
+                Some(HitReason::Changed { old, new: now })
+            }
+        }
+    }
+}
+
+/// Evaluate a breakpoint condition without disturbing the model's RNG
+///
+/// The triggering message's parameters are available as `Expr::param`.
+/// Evaluation errors count as false.
+fn eval_condition(expr: &Expr, model: &Model, msg: Option<&Msg>) -> bool {
+    let empty = ValueMap::new();
+    let params = msg.map_or(&empty, |m| &m.params);
+    let mut rng = model.rng.clone();
+    let mut ctx = EvalContext::new(model.entities(), model.globals(), params, &mut rng);
+    expr.eval(&mut ctx).is_ok_and(|v| v.is_truthy())
+}
+
 /// Replayer for journal data
 ///
 /// Provides fine-grained control over replaying recorded sessions:
@@ -37,12 +158,18 @@ pub enum ReplayState {
 /// - Step forward/backward
 /// - Play at various speeds
 /// - Seek to snapshots
+/// - Run until a breakpoint or watchpoint fires
 pub struct Replayer<'a> {
     journal: &'a Journal,
     state: ReplayState,
     speed: ReplaySpeed,
     current_tick: u64,
     target_tick: Option<u64>,
+    breakpoints: Vec<Breakpoint>,
+    next_breakpoint: u32,
+    break_on: BreakOn,
+    /// Index of the next message to replay within a partially replayed tick
+    resume_at: Option<usize>,
 }
 
 impl<'a> Replayer<'a> {
@@ -54,6 +181,10 @@ impl<'a> Replayer<'a> {
             speed: ReplaySpeed::default(),
             current_tick: 0,
             target_tick: None,
+            breakpoints: Vec::new(),
+            next_breakpoint: 0,
+            break_on: BreakOn::default(),
+            resume_at: None,
         }
     }
 
@@ -96,11 +227,11 @@ impl<'a> Replayer<'a> {
 
         if let Some(snapshot) = snapshot {
             // Restore from snapshot
-            *model = snapshot.model.clone();
+            model.restore(snapshot.model.clone());
             self.current_tick = snapshot.tick;
         } else {
             // Start from beginning
-            *model = Model::new();
+            model.restore(Model::new());
             self.current_tick = 0;
         }
 
@@ -110,12 +241,22 @@ impl<'a> Replayer<'a> {
         }
 
         self.current_tick = tick;
+        self.resume_at = None;
         self.state = ReplayState::Paused;
         Ok(())
     }
 
     /// Step forward one tick
     pub fn step_forward(&mut self, model: &mut Model, runtime: &mut Runtime) -> Result<bool> {
+        // Finish a tick that a breakpoint stopped part-way through
+        if let Some(index) = self.resume_at.take() {
+            for msg in self.messages_at(self.current_tick).into_iter().skip(index) {
+                runtime.send(msg.clone());
+            }
+            runtime.process_queue(model);
+            return Ok(true);
+        }
+
         let last_tick = self.last_tick().unwrap_or(0);
         if self.current_tick >= last_tick {
             self.state = ReplayState::Finished;
@@ -160,16 +301,18 @@ impl<'a> Replayer<'a> {
 
     /// Reset to the beginning
     pub fn reset(&mut self, model: &mut Model) {
-        *model = Model::new();
+        model.restore(Model::new());
         self.current_tick = 0;
+        self.resume_at = None;
         self.state = ReplayState::Idle;
     }
 
     /// Seek to the nearest snapshot at or before a tick
     pub fn seek_to_snapshot(&mut self, model: &mut Model, tick: u64) -> Result<Option<u64>> {
         if let Some(snapshot) = self.journal.snapshot_at_or_before(tick) {
-            *model = snapshot.model.clone();
+            model.restore(snapshot.model.clone());
             self.current_tick = snapshot.tick;
+            self.resume_at = None;
             self.state = ReplayState::Paused;
             Ok(Some(snapshot.tick))
         } else {
@@ -177,6 +320,116 @@ impl<'a> Replayer<'a> {
         }
     }
 
+    /// Add a breakpoint that pauses when `condition` becomes true
+    ///
+    /// The condition is evaluated against globals and entities (with the
+    /// triggering message's parameters when breaking on messages). It only
+    /// fires on a false-to-true transition, so a condition that stays true
+    /// does not pause every step.
+    pub fn add_breakpoint(&mut self, condition: Expr) -> BreakpointId {
+        self.push_breakpoint(BreakKind::Condition {
+            expr: condition,
+            last: false,
+        })
+    }
+
+    /// Add a watchpoint that pauses when a global or entity property changes
+    pub fn add_watchpoint(&mut self, target: WatchTarget) -> BreakpointId {
+        self.push_breakpoint(BreakKind::Watch { target, last: None })
+    }
+
+    /// Remove a breakpoint or watchpoint
+    pub fn remove_breakpoint(&mut self, id: BreakpointId) -> bool {
+        let before = self.breakpoints.len();
+        self.breakpoints.retain(|bp| bp.id != id);
+        self.breakpoints.len() != before
+    }
+
+    /// Remove all breakpoints and watchpoints
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Get the number of breakpoints and watchpoints
+    pub fn breakpoint_count(&self) -> usize {
+        self.breakpoints.len()
+    }
+
+    /// Get how often breakpoints are checked
+    pub fn break_on(&self) -> BreakOn {
+        self.break_on
+    }
+
+    /// Set how often breakpoints are checked
+    pub fn set_break_on(&mut self, break_on: BreakOn) {
+        self.break_on = break_on;
+    }
+
+    /// Replay forward until a breakpoint fires or the journal ends
+    ///
+    /// Breakpoints are primed from the current model state first, so only
+    /// changes made by the replayed steps fire. Returns the hit and leaves
+    /// the replayer paused, or returns `None` with the replayer finished.
+    /// Calling again resumes from where the replay stopped.
+    pub fn run_to_breakpoint(
+        &mut self,
+        model: &mut Model,
+        runtime: &mut Runtime,
+    ) -> Result<Option<BreakpointHit>> {
+        for bp in &mut self.breakpoints {
+            bp.prime(model);
+        }
+        self.state = ReplayState::Playing;
+        let last_tick = self.last_tick().unwrap_or(0);
+
+        loop {
+            let (tick, skip) = match self.resume_at.take() {
+                Some(index) => (self.current_tick, index),
+                None if self.current_tick < last_tick => (self.current_tick + 1, 0),
+                None => break,
+            };
+            self.current_tick = tick;
+            let entries = self.message_entries_at(tick);
+
+            let hit = match self.break_on {
+                BreakOn::Tick => {
+                    for entry in entries.iter().skip(skip) {
+                        if let JournalEntry::Message { msg, .. } = entry {
+                            runtime.send(msg.clone());
+                        }
+                    }
+                    runtime.process_queue(model);
+                    self.check_breakpoints(model, tick, entries.last().copied())
+                }
+                BreakOn::Message => {
+                    let mut hit = None;
+                    for (index, entry) in entries.iter().enumerate().skip(skip) {
+                        if let JournalEntry::Message { msg, .. } = entry {
+                            runtime.send(msg.clone());
+                            runtime.process_queue(model);
+                        }
+                        hit = self.check_breakpoints(model, tick, Some(entry));
+                        if hit.is_some() {
+                            if index + 1 < entries.len() {
+                                self.resume_at = Some(index + 1);
+                            }
+                            break;
+                        }
+                    }
+                    hit
+                }
+            };
+
+            if hit.is_some() {
+                self.state = ReplayState::Paused;
+                return Ok(hit);
+            }
+        }
+
+        self.state = ReplayState::Finished;
+        Ok(None)
+    }
+
     /// Get messages for a specific tick
     pub fn messages_at(&self, tick: u64) -> Vec<&Msg> {
         self.journal
@@ -194,6 +447,50 @@ impl<'a> Replayer<'a> {
         self.journal.snapshots().iter().map(|s| s.tick).collect()
     }
 
+    fn push_breakpoint(&mut self, kind: BreakKind) -> BreakpointId {
+        let id = BreakpointId(self.next_breakpoint);
+        self.next_breakpoint += 1;
+        self.breakpoints.push(Breakpoint { id, kind });
+        id
+    }
+
+    /// Get message entries for a specific tick
+    fn message_entries_at(&self, tick: u64) -> Vec<&'a JournalEntry> {
+        self.journal
+            .entries_in_range(tick, tick)
+            .into_iter()
+            .filter(|e| matches!(e, JournalEntry::Message { .. }))
+            .collect()
+    }
+
+    /// Check all breakpoints, returning the first that fired
+    ///
+    /// Every breakpoint is updated even if an earlier one fired, so none of
+    /// them fire again for the same change on resume.
+    fn check_breakpoints(
+        &mut self,
+        model: &Model,
+        tick: u64,
+        entry: Option<&JournalEntry>,
+    ) -> Option<BreakpointHit> {
+        let msg = match entry {
+            Some(JournalEntry::Message { msg, .. }) => Some(msg),
+            _ => None,
+        };
+        let mut hit = None;
+        for bp in &mut self.breakpoints {
+            if let Some(reason) = bp.check(model, msg) {
+                hit.get_or_insert_with(|| BreakpointHit {
+                    id: bp.id,
+                    tick,
+                    reason,
+                    entry: entry.cloned(),
+                });
+            }
+        }
+        hit
+    }
+
     /// Replay a range of ticks
     fn replay_range(
         &self,
@@ -271,7 +568,9 @@ impl<'a> ReplaySessionBuilder<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use pulsive_core::{Journal, JournalConfig, Model, Runtime};
+    use pulsive_core::{
+        DefId, Effect, Journal, JournalConfig, Model, ModifyOp, MsgKind, Runtime, TickHandler,
+    };
 
     fn create_recorded_session() -> (Journal, Model) {
         let mut model = Model::new();
@@ -331,6 +630,130 @@ mod tests {
         assert_eq!(replayer.state(), ReplayState::Idle);
     }
 
+    fn income_runtime() -> Runtime {
+        let mut runtime = Runtime::new();
+        runtime.on_tick(TickHandler {
+            id: DefId::new("income"),
+            condition: None,
+            target_kind: None,
+            effects: vec![Effect::ModifyGlobal {
+                property: "gold".to_string(),
+                op: ModifyOp::Add,
+                value: Expr::lit(5.0),
+            }],
+            priority: 0,
+        });
+        runtime
+    }
+
+    fn create_income_session() -> Journal {
+        let mut model = Model::new();
+        let mut runtime = income_runtime();
+        let mut journal = Journal::new();
+        journal.start_recording();
+        for _ in 0..20 {
+            runtime.tick_with_journal(&mut model, &mut journal);
+        }
+        journal
+    }
+
+    #[test]
+    fn test_breakpoint_condition() {
+        let journal = create_income_session();
+        let mut model = Model::new();
+        let mut runtime = income_runtime();
+        let mut replayer = Replayer::new(&journal);
+
+        let id = replayer.add_breakpoint(Expr::Ge(
+            Box::new(Expr::global("gold")),
+            Box::new(Expr::lit(30.0)),
+        ));
+        let hit = replayer
+            .run_to_breakpoint(&mut model, &mut runtime)
+            .unwrap()
+            .unwrap();
+        assert_eq!(hit.id, id);
+        assert_eq!(hit.tick, 6);
+        assert_eq!(hit.reason, HitReason::Condition);
+        assert_eq!(replayer.state(), ReplayState::Paused);
+
+        // The condition stays true, so resuming runs to the end
+        assert!(replayer
+            .run_to_breakpoint(&mut model, &mut runtime)
+            .unwrap()
+            .is_none());
+        assert_eq!(replayer.state(), ReplayState::Finished);
+        assert_eq!(replayer.current_tick(), 20);
+    }
+
+    #[test]
+    fn test_watchpoint_on_message() {
+        let journal = create_income_session();
+        let mut model = Model::new();
+        let mut runtime = income_runtime();
+        let mut replayer = Replayer::new(&journal);
+        replayer.set_break_on(BreakOn::Message);
+
+        let id = replayer.add_watchpoint(WatchTarget::Global("gold".to_string()));
+        let hit = replayer
+            .run_to_breakpoint(&mut model, &mut runtime)
+            .unwrap()
+            .unwrap();
+        assert_eq!(hit.id, id);
+        assert_eq!(hit.tick, 1);
+        assert_eq!(
+            hit.reason,
+            HitReason::Changed {
+                old: None,
+                new: Some(Value::Float(5.0)),
+            }
+        );
+        assert!(matches!(
+            hit.entry,
+            Some(JournalEntry::Message { ref msg, .. }) if msg.kind == MsgKind::Tick
+        ));
+
+        let hit = replayer
+            .run_to_breakpoint(&mut model, &mut runtime)
+            .unwrap()
+            .unwrap();
+        assert_eq!(hit.tick, 2);
+
+        assert!(replayer.remove_breakpoint(id));
+        assert!(replayer
+            .run_to_breakpoint(&mut model, &mut runtime)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_replay_keeps_schemas() {
+        use pulsive_core::{PropertySchema, PropertySchemas, SchemaPolicy, ValueType};
+
+        let mut schemas = PropertySchemas::new().with_policy(SchemaPolicy::Clamp);
+        schemas.register_global("gold", PropertySchema::new(ValueType::Float).with_max(12.0));
+        let mut recorded = Model::new();
+        recorded.set_schemas(schemas.clone());
+        let mut runtime = income_runtime();
+        let mut journal = Journal::new();
+        journal.start_recording();
+        for _ in 0..5 {
+            runtime.tick_with_journal(&mut recorded, &mut journal);
+        }
+        assert_eq!(recorded.get_global("gold"), Some(&Value::Float(12.0)));
+
+        let mut model = Model::new();
+        model.set_schemas(schemas);
+        let mut runtime = income_runtime();
+        let mut replayer = Replayer::new(&journal);
+        replayer.goto(&mut model, &mut runtime, 5).unwrap();
+        assert_eq!(model.get_global("gold"), recorded.get_global("gold"));
+        assert!(!model.schemas().is_empty());
+
+        replayer.reset(&mut model);
+        assert!(!model.schemas().is_empty());
+    }
+
     #[test]
     fn test_snapshot_ticks() {
         let (journal, _) = create_recorded_session();
@@ -0,0 +1,347 @@
+//! Tabular views of journal data
+//!
+//! Row-oriented exports (CSV, SQL, SQLite, Parquet) flatten the journal into fixed tables.
+//! Each [`ExportTable`] has a column schema so downstream tools can create
+//! matching tables up front.
+
+use pulsive_core::{Journal, JournalEntry, PendingWrite, Tick};
+use std::collections::BTreeMap;
+use std::fmt;
+
+const MESSAGE_COLUMNS: &[Column] = &[
+    Column::new("tick", ColumnType::Integer),
+    Column::new("seq", ColumnType::Integer),
+    Column::new("kind", ColumnType::Text),
+    Column::new("event_id", ColumnType::Text),
+    Column::new("actor", ColumnType::Integer),
+    Column::new("params", ColumnType::Text),
+];
+
+const SNAPSHOT_COLUMNS: &[Column] = &[
+    Column::new("id", ColumnType::Integer),
+    Column::new("tick", ColumnType::Integer),
+    Column::new("entities", ColumnType::Integer),
+    Column::new("globals", ColumnType::Integer),
+];
+
+const WRITE_COLUMNS: &[Column] = &[
+    Column::new("tick", ColumnType::Integer),
+    Column::new("seq", ColumnType::Integer),
+    Column::new("kind", ColumnType::Text),
+    Column::new("entity_id", ColumnType::Integer),
+    Column::new("key", ColumnType::Text),
+    Column::new("op", ColumnType::Text),
+    Column::new("value", ColumnType::Text),
+];
+
+const TICK_SUMMARY_COLUMNS: &[Column] = &[
+    Column::new("tick", ColumnType::Integer),
+    Column::new("messages", ColumnType::Integer),
+    Column::new("writes", ColumnType::Integer),
+    Column::new("snapshots", ColumnType::Integer),
+    Column::new("metadata", ColumnType::Integer),
+];
+
+/// A table that journal data can be exported as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExportTable {
+    /// One row per recorded message
+    Messages,
+    /// One row per recorded write (write-level recording)
+    Writes,
+    /// One row per state snapshot
+    Snapshots,
+    /// One row per recorded tick with entry counts
+    TickSummaries,
+}
+
+impl ExportTable {
+    /// All tables, in export order
+    pub const ALL: [ExportTable; 4] = [
+        ExportTable::Messages,
+        ExportTable::Writes,
+        ExportTable::Snapshots,
+        ExportTable::TickSummaries,
+    ];
+
+    /// Table name used in SQL exports and file names
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportTable::Messages => "messages",
+            ExportTable::Writes => "writes",
+            ExportTable::Snapshots => "snapshots",
+            ExportTable::TickSummaries => "tick_summaries",
+        }
+    }
+
+    /// Column schema of the table
+    pub fn columns(&self) -> &'static [Column] {
+        match self {
+            ExportTable::Messages => MESSAGE_COLUMNS,
+            ExportTable::Writes => WRITE_COLUMNS,
+            ExportTable::Snapshots => SNAPSHOT_COLUMNS,
+            ExportTable::TickSummaries => TICK_SUMMARY_COLUMNS,
+        }
+    }
+
+    /// Extract the table's rows from a journal
+    pub(crate) fn rows(&self, journal: &Journal) -> Vec<Vec<Cell>> {
+        match self {
+            ExportTable::Messages => journal
+                .entries()
+                .iter()
+                .filter_map(|entry| match entry {
+                    JournalEntry::Message { tick, msg, seq } => Some(vec![
+                        Cell::Int(*tick),
+                        Cell::Int(*seq),
+                        Cell::Text(format!("{:?}", msg.kind)),
+                        msg.event_id
+                            .as_ref()
+                            .map_or(Cell::Null, |id| Cell::Text(id.to_string())),
+                        msg.actor.map_or(Cell::Null, |a| Cell::Int(a.raw())),
+                        Cell::Text(format!("{:?}", msg.params)),
+                    ]),
+                    _ => None,
+                })
+                .collect(),
+            ExportTable::Writes => journal
+                .write_sets()
+                .flat_map(|(tick, writes)| {
+                    writes
+                        .iter()
+                        .enumerate()
+                        .map(move |(seq, write)| write_row(tick, seq as u64, write))
+                })
+                .collect(),
+            ExportTable::Snapshots => journal
+                .snapshots()
+                .iter()
+                .map(|snapshot| {
+                    vec![
+                        Cell::Int(snapshot.id.0),
+                        Cell::Int(snapshot.tick),
+                        Cell::Int(snapshot.model.entities().len() as u64),
+                        Cell::Int(snapshot.model.globals().len() as u64),
+                    ]
+                })
+                .collect(),
+            ExportTable::TickSummaries => {
+                let mut ticks: BTreeMap<Tick, [u64; 4]> = BTreeMap::new();
+                for entry in journal.entries() {
+                    match entry {
+                        JournalEntry::TickBoundary { tick } => {
+                            ticks.entry(*tick).or_default();
+                        }
+                        JournalEntry::Message { tick, .. } => {
+                            ticks.entry(*tick).or_default()[0] += 1;
+                        }
+                        JournalEntry::Writes { tick, writes, .. } => {
+                            ticks.entry(*tick).or_default()[1] += writes.len() as u64;
+                        }
+                        JournalEntry::Snapshot { tick, .. } => {
+                            ticks.entry(*tick).or_default()[2] += 1;
+                        }
+                        JournalEntry::Rng { .. } | JournalEntry::Provenance { .. } => {}
+                        JournalEntry::Metadata { tick, .. } => {
+                            ticks.entry(*tick).or_default()[3] += 1;
+                        }
+                    }
+                }
+                ticks
+                    .into_iter()
+                    .map(|(tick, [messages, writes, snapshots, metadata])| {
+                        vec![
+                            Cell::Int(tick),
+                            Cell::Int(messages),
+                            Cell::Int(writes),
+                            Cell::Int(snapshots),
+                            Cell::Int(metadata),
+                        ]
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Flatten a single write into a row of the writes table
+fn write_row(tick: Tick, seq: u64, write: &PendingWrite) -> Vec<Cell> {
+    let text = |s: &str| Cell::Text(s.to_string());
+    let debug = |v: &dyn fmt::Debug| Cell::Text(format!("{:?}", v));
+    let (kind, entity_id, key, op, value) = match write {
+        PendingWrite::SetProperty {
+            entity_id,
+            key,
+            value,
+        } => (
+            "SetProperty",
+            Some(*entity_id),
+            text(key),
+            Cell::Null,
+            debug(value),
+        ),
+        PendingWrite::ModifyProperty {
+            entity_id,
+            key,
+            op,
+            value,
+        } => (
+            "ModifyProperty",
+            Some(*entity_id),
+            text(key),
+            debug(op),
+            debug(value),
+        ),
+        PendingWrite::SetGlobal { key, value } => {
+            ("SetGlobal", None, text(key), Cell::Null, debug(value))
+        }
+        PendingWrite::ModifyGlobal { key, op, value } => {
+            ("ModifyGlobal", None, text(key), debug(op), debug(value))
+        }
+        PendingWrite::AddFlag { entity_id, flag } => (
+            "AddFlag",
+            Some(*entity_id),
+            text(flag.as_str()),
+            Cell::Null,
+            Cell::Null,
+        ),
+        PendingWrite::AddFlagFor {
+            entity_id,
+            flag,
+            expires_at,
+        } => (
+            "AddFlagFor",
+            Some(*entity_id),
+            text(flag.as_str()),
+            Cell::Null,
+            debug(expires_at),
+        ),
+        PendingWrite::RemoveFlag { entity_id, flag } => (
+            "RemoveFlag",
+            Some(*entity_id),
+            text(flag.as_str()),
+            Cell::Null,
+            Cell::Null,
+        ),
+        PendingWrite::AddTag { entity_id, tag } => (
+            "AddTag",
+            Some(*entity_id),
+            text(tag),
+            Cell::Null,
+            Cell::Null,
+        ),
+        PendingWrite::RemoveTag { entity_id, tag } => (
+            "RemoveTag",
+            Some(*entity_id),
+            text(tag),
+            Cell::Null,
+            Cell::Null,
+        ),
+        PendingWrite::SpawnEntity { kind, properties } => (
+            "SpawnEntity",
+            None,
+            text(kind.as_str()),
+            Cell::Null,
+            debug(properties),
+        ),
+        PendingWrite::DestroyEntity { id } => (
+            "DestroyEntity",
+            Some(*id),
+            Cell::Null,
+            Cell::Null,
+            Cell::Null,
+        ),
+    };
+    vec![
+        Cell::Int(tick),
+        Cell::Int(seq),
+        text(kind),
+        entity_id.map_or(Cell::Null, |id| Cell::Int(id.raw())),
+        key,
+        op,
+        value,
+    ]
+}
+
+impl fmt::Display for ExportTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A column in an [`ExportTable`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    /// Column name
+    pub name: &'static str,
+    /// Column type
+    pub kind: ColumnType,
+}
+
+impl Column {
+    const fn new(name: &'static str, kind: ColumnType) -> Self {
+        Self { name, kind }
+    }
+}
+
+/// Type of a column value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    /// Unsigned integer
+    Integer,
+    /// UTF-8 text
+    Text,
+}
+
+impl ColumnType {
+    /// SQL type name
+    pub fn sql_type(&self) -> &'static str {
+        match self {
+            ColumnType::Integer => "INTEGER",
+            ColumnType::Text => "TEXT",
+        }
+    }
+}
+
+/// A single value in an exported row
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Cell {
+    Int(u64),
+    Text(String),
+    Null,
+}
+
+impl Cell {
+    /// Format as a CSV field
+    pub fn to_csv(&self) -> String {
+        match self {
+            Cell::Int(n) => n.to_string(),
+            Cell::Text(s) if s.contains([',', '"', '\n']) => {
+                format!("\"{}\"", s.replace('"', "\"\""))
+            }
+            Cell::Text(s) => s.clone(),
+            Cell::Null => String::new(),
+        }
+    }
+
+    /// Format as an SQL literal
+    pub fn to_sql(&self) -> String {
+        match self {
+            Cell::Int(n) => n.to_string(),
+            Cell::Text(s) => format!("'{}'", s.replace('\'', "''")),
+            Cell::Null => "NULL".to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl rusqlite::ToSql for Cell {
+    fn to_sql(&self) -> rusqlite::Result<rusqlite::types::ToSqlOutput<'_>> {
+        use rusqlite::types::{ToSqlOutput, ValueRef};
+        Ok(ToSqlOutput::Borrowed(match self {
+            Cell::Int(n) => ValueRef::Integer(*n as i64),
+            Cell::Text(s) => ValueRef::Text(s.as_bytes()),
+            Cell::Null => ValueRef::Null,
+        }))
+    }
+}
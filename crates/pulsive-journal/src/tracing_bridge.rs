@@ -0,0 +1,236 @@
+//! Bridge from recorded journals to the `tracing` ecosystem
+//!
+//! The [`TracingBridge`] walks a journal and emits its contents as
+//! `tracing` spans and events, so sessions show up next to server traces
+//! in any subscriber (OpenTelemetry exporters for Jaeger or Grafana Tempo,
+//! `tracing-subscriber` fmt layers, ...):
+//!
+//! | Journal entry | Emitted as                                   | Level |
+//! |---------------|----------------------------------------------|-------|
+//! | Tick          | `tick` span around the tick's entries        | INFO  |
+//! | Message       | event with kind, event ID, target and actor  | DEBUG |
+//! | Provenance    | `handler` span per handler run               | DEBUG |
+//! | Writes        | event with the write count                   | TRACE |
+//! | Snapshot      | event with the snapshot ID                   | INFO  |
+//! | Conflict      | event with the conflict description          | WARN  |
+//! | Metadata      | event with key and value                     | INFO  |
+//!
+//! Events use the `pulsive::journal` target. Journals do not store wall
+//! clock time, so spans are emitted back to back rather than with their
+//! original durations.
+//!
+//! Conflicts detected by the hub are recorded as metadata entries with the
+//! [`CONFLICT_METADATA_KEY`] key via [`record_conflicts`].
+
+use pulsive_core::{Journal, JournalEntry, Tick};
+use pulsive_hub::ConflictReport;
+use tracing::{debug, debug_span, info, info_span, trace, warn, Span};
+
+/// Metadata key under which conflicts are recorded
+pub const CONFLICT_METADATA_KEY: &str = "conflict";
+
+/// Record the conflicts of a hub tick as journal metadata
+pub fn record_conflicts(journal: &mut Journal, tick: Tick, report: &ConflictReport) {
+    for conflict in report.iter() {
+        journal.record_metadata(tick, CONFLICT_METADATA_KEY, conflict.to_string());
+    }
+}
+
+/// Emit conflicts detected live by the hub as `tracing` events
+pub fn emit_conflicts(tick: Tick, report: &ConflictReport) {
+    for conflict in report.iter() {
+        warn!(target: "pulsive::journal", tick, conflict = %conflict, "conflict");
+    }
+}
+
+/// Emits recorded journal entries as `tracing` spans and events
+pub struct TracingBridge<'a> {
+    journal: &'a Journal,
+    session: Option<String>,
+}
+
+impl<'a> TracingBridge<'a> {
+    /// Create a bridge for a journal
+    pub fn new(journal: &'a Journal) -> Self {
+        Self {
+            journal,
+            session: None,
+        }
+    }
+
+    /// Wrap all emitted spans in a `session` span with this name
+    pub fn with_session(mut self, name: impl Into<String>) -> Self {
+        self.session = Some(name.into());
+        self
+    }
+
+    /// Emit every entry in the journal
+    pub fn emit(&self) -> usize {
+        self.emit_entries(self.journal.entries().iter())
+    }
+
+    /// Emit entries for ticks in `start..=end`
+    pub fn emit_range(&self, start: Tick, end: Tick) -> usize {
+        self.emit_entries(self.journal.entries_in_range(start, end).into_iter())
+    }
+
+    /// Emit entries, returning the number of entries emitted
+    fn emit_entries<'e>(&self, entries: impl Iterator<Item = &'e JournalEntry>) -> usize {
+        let session = match &self.session {
+            Some(name) => info_span!(target: "pulsive::journal", "session", name = %name),
+            None => Span::none(),
+        };
+        let _session = session.enter();
+
+        let mut tick_span: Option<(Tick, Span)> = None;
+        let mut count = 0;
+        for entry in entries {
+            let tick = entry.tick();
+            if tick_span.as_ref().is_none_or(|(t, _)| *t != tick)
+                && !matches!(entry, JournalEntry::Metadata { .. })
+            {
+                tick_span = Some((tick, info_span!(target: "pulsive::journal", "tick", tick)));
+            }
+            let _tick = tick_span.as_ref().map(|(_, span)| span.enter());
+            emit_entry(entry);
+            count += 1;
+        }
+        count
+    }
+}
+
+fn emit_entry(entry: &JournalEntry) {
+    match entry {
+        JournalEntry::TickBoundary { .. } => {}
+        JournalEntry::Message { msg, seq, .. } => {
+            debug!(
+                target: "pulsive::journal",
+                seq,
+                kind = ?msg.kind,
+                event = msg.event_id.as_ref().map(|id| id.to_string()),
+                target_ref = ?msg.target,
+                actor = msg.actor.map(|a| a.0),
+                "message"
+            );
+        }
+        JournalEntry::Provenance { handlers, .. } => {
+            for handler in handlers {
+                let span = debug_span!(
+                    target: "pulsive::journal",
+                    "handler",
+                    handler = %handler.handler,
+                    target_ref = ?handler.target,
+                    writes = handler.write_count(),
+                );
+                let _handler = span.enter();
+                for (event, target, _) in handler.emitted_events() {
+                    debug!(
+                        target: "pulsive::journal",
+                        event = %event,
+                        target_ref = ?target,
+                        "emitted"
+                    );
+                }
+            }
+        }
+        JournalEntry::Writes { writes, .. } => {
+            trace!(target: "pulsive::journal", count = writes.len(), "writes");
+        }
+        JournalEntry::Rng { draws, .. } => {
+            trace!(target: "pulsive::journal", draws, "rng");
+        }
+        JournalEntry::Snapshot { snapshot_id, .. } => {
+            info!(target: "pulsive::journal", snapshot = snapshot_id.0, "snapshot");
+        }
+        JournalEntry::Metadata {
+            tick, key, value, ..
+        } if key == CONFLICT_METADATA_KEY => {
+            warn!(target: "pulsive::journal", tick, conflict = %value, "conflict");
+        }
+        JournalEntry::Metadata {
+            tick, key, value, ..
+        } => {
+            info!(target: "pulsive::journal", tick, key = %key, value = %value, "metadata");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::{JournalConfig, Model, Runtime};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// Subscriber that records span names and event levels
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Recorded>);
+
+    #[derive(Default)]
+    struct Recorded {
+        next_id: AtomicU64,
+        spans: Mutex<Vec<&'static str>>,
+        events: Mutex<Vec<tracing::Level>>,
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            self.0.spans.lock().unwrap().push(span.metadata().name());
+            Id::from_u64(self.0.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            self.0
+                .events
+                .lock()
+                .unwrap()
+                .push(*event.metadata().level());
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn test_emit_journal() {
+        let mut model = Model::new();
+        let mut runtime = Runtime::new();
+        let mut journal = Journal::with_config(JournalConfig {
+            recording_enabled: true,
+            ..Default::default()
+        });
+        for _ in 0..3 {
+            runtime.tick_with_journal(&mut model, &mut journal);
+        }
+        journal.record_metadata(3, CONFLICT_METADATA_KEY, "write-write conflict");
+
+        let recorder = Recorder::default();
+        let emitted = tracing::subscriber::with_default(recorder.clone(), || {
+            TracingBridge::new(&journal).with_session("test").emit()
+        });
+
+        assert_eq!(emitted, journal.entries().len());
+        let spans = recorder.0.spans.lock().unwrap();
+        assert_eq!(spans.first(), Some(&"session"));
+        assert_eq!(spans.iter().filter(|s| **s == "tick").count(), 3);
+        let events = recorder.0.events.lock().unwrap();
+        assert_eq!(
+            events
+                .iter()
+                .filter(|l| **l == tracing::Level::WARN)
+                .count(),
+            1
+        );
+    }
+}
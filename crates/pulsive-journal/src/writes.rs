@@ -0,0 +1,235 @@
+//! Write-level replay and verification
+//!
+//! With [`JournalConfig::record_writes`](pulsive_core::JournalConfig) enabled,
+//! the journal stores the writes applied in each tick alongside the
+//! messages. Re-applying those writes rebuilds the exact recorded state
+//! without running any handlers, so replays survive handler changes and
+//! non-determinism. Re-executing the recorded messages and comparing the
+//! resulting writes against the recorded ones shows where behaviour
+//! diverged.
+
+use crate::{Error, Result};
+use pulsive_core::{Journal, JournalEntry, Model, Rng, Runtime, Tick, WriteSet};
+
+/// Replays a journal by re-applying its recorded writes
+pub struct WriteReplayer<'a> {
+    journal: &'a Journal,
+}
+
+impl<'a> WriteReplayer<'a> {
+    /// Create a write replayer for a journal
+    pub fn new(journal: &'a Journal) -> Self {
+        Self { journal }
+    }
+
+    /// Check if the journal contains recorded writes
+    pub fn has_writes(&self) -> bool {
+        self.journal.write_sets().next().is_some()
+    }
+
+    /// Rebuild the model state at the end of `tick`
+    ///
+    /// Starts from the nearest snapshot at or before `tick` (or an empty
+    /// model) and re-applies the recorded writes and RNG state of every
+    /// later tick up to `tick`.
+    pub fn reconstruct(&self, tick: Tick) -> Result<Model> {
+        if !self.has_writes() {
+            return Err(Error::ReplayError(
+                "journal has no recorded writes".to_string(),
+            ));
+        }
+
+        let mut model = self
+            .journal
+            .snapshot_at_or_before(tick)
+            .map(|snapshot| snapshot.model.clone())
+            .unwrap_or_default();
+        let base_tick = model.current_tick();
+
+        for entry in self.journal.entries_in_range(base_tick + 1, tick) {
+            match entry {
+                JournalEntry::TickBoundary { tick } => advance_to(&mut model, *tick),
+                JournalEntry::Writes {
+                    tick,
+                    writes,
+                    rng_state,
+                } => {
+                    advance_to(&mut model, *tick);
+                    pulsive_hub::apply(writes, &mut model);
+                    model.rng = Rng::from_state(*rng_state);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(model)
+    }
+
+    /// Re-execute the recorded messages and compare against recorded writes
+    ///
+    /// `model` must be the state the recording started from; it is advanced
+    /// tick by tick using the re-executed handlers and ends in the replayed
+    /// final state. Every tick whose writes differ from the recording is
+    /// reported.
+    pub fn verify(&self, model: &mut Model, runtime: &mut Runtime) -> Result<VerifyReport> {
+        if !self.has_writes() {
+            return Err(Error::ReplayError(
+                "journal has no recorded writes".to_string(),
+            ));
+        }
+
+        let mut report = VerifyReport::default();
+        let mut pending = Vec::new();
+        let was_logging = runtime.is_write_logging();
+        runtime.set_write_logging(true);
+
+        for entry in self.journal.entries() {
+            match entry {
+                JournalEntry::Message { msg, .. } => pending.push(msg.clone()),
+                JournalEntry::Writes { tick, writes, .. } => {
+                    advance_to(model, *tick);
+                    runtime.take_write_log();
+                    for msg in pending.drain(..) {
+                        runtime.send(msg);
+                    }
+                    runtime.process_queue(model);
+
+                    let replayed = runtime.take_write_log();
+                    report.ticks_checked += 1;
+                    if replayed.writes() != writes.writes() {
+                        report.mismatches.push(WriteMismatch {
+                            tick: *tick,
+                            recorded: writes.clone(),
+                            replayed,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        runtime.set_write_logging(was_logging);
+        Ok(report)
+    }
+}
+
+/// Advance the model clock to `tick`
+pub(crate) fn advance_to(model: &mut Model, tick: Tick) {
+    while model.current_tick() < tick {
+        model.advance_tick();
+    }
+}
+
+/// Result of verifying re-execution against recorded writes
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    /// Number of ticks compared
+    pub ticks_checked: u64,
+    /// Ticks whose re-executed writes differ from the recording
+    pub mismatches: Vec<WriteMismatch>,
+}
+
+impl VerifyReport {
+    /// Check if re-execution matched the recording exactly
+    pub fn is_ok(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// Get the first tick where re-execution diverged
+    pub fn first_divergence(&self) -> Option<Tick> {
+        self.mismatches.first().map(|m| m.tick)
+    }
+}
+
+/// A tick where re-execution produced different writes
+#[derive(Debug, Clone)]
+pub struct WriteMismatch {
+    /// The tick that diverged
+    pub tick: Tick,
+    /// Writes stored in the journal
+    pub recorded: WriteSet,
+    /// Writes produced by re-executing the tick
+    pub replayed: WriteSet,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::{DefId, Effect, Expr, JournalConfig, ModifyOp, TickHandler};
+
+    fn income_runtime(amount: f64) -> Runtime {
+        let mut runtime = Runtime::new();
+        runtime.on_tick(TickHandler {
+            id: DefId::new("income"),
+            condition: None,
+            target_kind: None,
+            effects: vec![Effect::ModifyGlobal {
+                property: "gold".to_string(),
+                op: ModifyOp::Add,
+                value: Expr::lit(amount),
+            }],
+            priority: 0,
+        });
+        runtime
+    }
+
+    fn record_session(ticks: usize) -> (Journal, Model) {
+        let mut model = Model::new();
+        let mut runtime = income_runtime(5.0);
+        let mut journal = Journal::with_config(JournalConfig {
+            recording_enabled: true,
+            record_writes: true,
+            snapshot_interval: 4,
+            ..Default::default()
+        });
+        for _ in 0..ticks {
+            runtime.tick_with_journal(&mut model, &mut journal);
+        }
+        (journal, model)
+    }
+
+    fn gold(model: &Model) -> Option<f64> {
+        model.get_global("gold")?.as_float()
+    }
+
+    #[test]
+    fn test_reconstruct_without_handlers() {
+        let (journal, model) = record_session(10);
+        let replayer = WriteReplayer::new(&journal);
+
+        let rebuilt = replayer.reconstruct(10).unwrap();
+        assert_eq!(rebuilt.current_tick(), 10);
+        assert_eq!(gold(&rebuilt), gold(&model));
+
+        let midway = replayer.reconstruct(6).unwrap();
+        assert_eq!(midway.current_tick(), 6);
+        assert_eq!(gold(&midway), Some(30.0));
+    }
+
+    #[test]
+    fn test_verify_detects_handler_change() {
+        let (journal, _) = record_session(5);
+        let replayer = WriteReplayer::new(&journal);
+
+        let report = replayer
+            .verify(&mut Model::new(), &mut income_runtime(5.0))
+            .unwrap();
+        assert!(report.is_ok());
+        assert_eq!(report.ticks_checked, 5);
+
+        let report = replayer
+            .verify(&mut Model::new(), &mut income_runtime(7.0))
+            .unwrap();
+        assert_eq!(report.first_divergence(), Some(1));
+        assert_eq!(report.mismatches.len(), 5);
+    }
+
+    #[test]
+    fn test_requires_recorded_writes() {
+        let mut journal = Journal::new();
+        journal.start_recording();
+        Runtime::new().tick_with_journal(&mut Model::new(), &mut journal);
+
+        assert!(WriteReplayer::new(&journal).reconstruct(1).is_err());
+    }
+}
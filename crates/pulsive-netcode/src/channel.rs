@@ -0,0 +1,391 @@
+//! Message channels multiplexed over one connection
+//!
+//! Games send several kinds of traffic to the same peer: state streams
+//! where only the newest packet matters, and control messages (chat, lobby
+//! commands) that must all arrive, in order. A [`ChannelMux`] carries them
+//! all over one [`Connection`], each on a [`ChannelId`] with its own
+//! [`Reliability`]:
+//!
+//! - [`Reliability::Unreliable`]: sent unreliably, delivered as received
+//! - [`Reliability::UnreliableSequenced`]: sent unreliably, packets older
+//!   than one already delivered are dropped
+//! - [`Reliability::ReliableOrdered`]: sent reliably, delivered in the order
+//!   sent even if the connection reorders them. At most [`RECV_WINDOW`]
+//!   packets are held waiting for a late one; a packet further ahead makes
+//!   [`ChannelMux::recv`] fail with [`Error::WindowExceeded`], since the
+//!   channel can no longer deliver everything in order
+//!
+//! Each packet is prefixed with its channel ID (1 byte) and its sequence
+//! number on the channel (4 bytes, little-endian). Both peers must register
+//! the same channels.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! use pulsive_netcode::{ChannelId, ChannelMux, Reliability};
+//!
+//! const STATE: ChannelId = ChannelId(0);
+//! const CHAT: ChannelId = ChannelId(1);
+//!
+//! let mut mux = ChannelMux::new(connection)
+//!     .with_channel(STATE, Reliability::UnreliableSequenced)
+//!     .with_channel(CHAT, Reliability::ReliableOrdered);
+//!
+//! mux.send(CHAT, b"gg")?;
+//! while let Some((channel, data)) = mux.recv()? {
+//!     // ...
+//! }
+//! ```
+
+use crate::{Connection, Error, Result};
+use std::collections::{HashMap, VecDeque};
+
+/// Length of the header prefixed to each packet
+const HEADER_LEN: usize = 5;
+
+/// How far ahead of the next packet to deliver a reliable packet may be
+/// held before the channel gives up on it
+pub const RECV_WINDOW: u32 = 1024;
+
+/// Identifier of a channel, the same on both peers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChannelId(pub u8);
+
+impl std::fmt::Display for ChannelId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Channel({})", self.0)
+    }
+}
+
+/// Delivery guarantees of a channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Reliability {
+    /// Best effort: packets may be lost, duplicated or reordered
+    #[default]
+    Unreliable,
+    /// Best effort, but never older than a packet already delivered
+    UnreliableSequenced,
+    /// Every packet is delivered, in the order sent
+    ReliableOrdered,
+}
+
+/// State of one channel
+#[derive(Debug)]
+struct Channel {
+    reliability: Reliability,
+    /// Sequence number of the next packet sent
+    next_send: u32,
+    /// Sequence number of the next packet to deliver (sequenced: one past
+    /// the newest delivered)
+    next_recv: u32,
+    /// Reliable packets received ahead of `next_recv`, within `RECV_WINDOW`
+    pending: HashMap<u32, Vec<u8>>,
+}
+
+impl Channel {
+    fn new(reliability: Reliability) -> Self {
+        Self {
+            reliability,
+            next_send: 0,
+            next_recv: 0,
+            pending: HashMap::new(),
+        }
+    }
+}
+
+/// Check whether sequence number `a` is at or after `b`, with wrapping
+fn at_or_after(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) >= 0
+}
+
+/// Several message channels sharing one connection
+///
+/// See the [module docs](self) for the channel kinds and wire format.
+pub struct ChannelMux<C: Connection> {
+    connection: C,
+    channels: HashMap<ChannelId, Channel>,
+    /// Reliable packets released in order, not yet returned by `recv`
+    ready: VecDeque<(ChannelId, Vec<u8>)>,
+}
+
+impl<C: Connection> ChannelMux<C> {
+    /// Wrap a connection, with no channels registered yet
+    pub fn new(connection: C) -> Self {
+        Self {
+            connection,
+            channels: HashMap::new(),
+            ready: VecDeque::new(),
+        }
+    }
+
+    /// Register a channel
+    pub fn with_channel(mut self, id: ChannelId, reliability: Reliability) -> Self {
+        self.add_channel(id, reliability);
+        self
+    }
+
+    /// Register a channel, resetting its sequence numbers if it exists
+    pub fn add_channel(&mut self, id: ChannelId, reliability: Reliability) {
+        self.channels.insert(id, Channel::new(reliability));
+    }
+
+    /// Get the reliability of a channel
+    pub fn reliability(&self, id: ChannelId) -> Option<Reliability> {
+        self.channels.get(&id).map(|c| c.reliability)
+    }
+
+    /// Get the underlying connection
+    pub fn connection(&self) -> &C {
+        &self.connection
+    }
+
+    /// Unwrap the underlying connection
+    pub fn into_inner(self) -> C {
+        self.connection
+    }
+
+    /// Send a message on a channel
+    pub fn send(&mut self, id: ChannelId, data: &[u8]) -> Result<()> {
+        let channel = self
+            .channels
+            .get_mut(&id)
+            .ok_or(Error::UnknownChannel(id.0))?;
+        let mut packet = Vec::with_capacity(HEADER_LEN + data.len());
+        packet.push(id.0);
+        packet.extend_from_slice(&channel.next_send.to_le_bytes());
+        packet.extend_from_slice(data);
+        channel.next_send = channel.next_send.wrapping_add(1);
+
+        let sent = match channel.reliability {
+            Reliability::ReliableOrdered => self.connection.send_reliable(&packet),
+            _ => self.connection.send_unreliable(&packet),
+        };
+        sent.map_err(|e| Error::Transport(e.to_string()))
+    }
+
+    /// Receive the next message to deliver, with its channel (non-blocking)
+    ///
+    /// Returns `Ok(None)` once the connection has nothing more to deliver;
+    /// reliable messages received early are held until those before them
+    /// arrive.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::WindowExceeded`] if a reliable message arrives
+    /// [`RECV_WINDOW`] or more ahead of the next one to deliver. The message
+    /// is not held, so the channel has lost data and should be closed.
+    pub fn recv(&mut self) -> Result<Option<(ChannelId, Vec<u8>)>> {
+        loop {
+            if let Some(message) = self.ready.pop_front() {
+                return Ok(Some(message));
+            }
+            let Some(packet) = self
+                .connection
+                .recv()
+                .map_err(|e| Error::Transport(e.to_string()))?
+            else {
+                return Ok(None);
+            };
+            if packet.len() < HEADER_LEN {
+                return Err(Error::Serialization(format!(
+                    "channel packet of {} bytes is shorter than its header",
+                    packet.len()
+                )));
+            }
+            let id = ChannelId(packet[0]);
+            let seq = u32::from_le_bytes([packet[1], packet[2], packet[3], packet[4]]);
+            let data = packet[HEADER_LEN..].to_vec();
+            let channel = self
+                .channels
+                .get_mut(&id)
+                .ok_or(Error::UnknownChannel(id.0))?;
+
+            match channel.reliability {
+                Reliability::Unreliable => return Ok(Some((id, data))),
+                Reliability::UnreliableSequenced => {
+                    if at_or_after(seq, channel.next_recv) {
+                        channel.next_recv = seq.wrapping_add(1);
+                        return Ok(Some((id, data)));
+                    }
+                }
+                Reliability::ReliableOrdered => {
+                    // Duplicates of delivered packets are dropped
+                    if !at_or_after(seq, channel.next_recv) {
+                        continue;
+                    }
+                    if seq.wrapping_sub(channel.next_recv) >= RECV_WINDOW {
+                        return Err(Error::WindowExceeded {
+                            channel: id.0,
+                            seq,
+                            next: channel.next_recv,
+                        });
+                    }
+                    channel.pending.insert(seq, data);
+                    while let Some(data) = channel.pending.remove(&channel.next_recv) {
+                        self.ready.push_back((id, data));
+                        channel.next_recv = channel.next_recv.wrapping_add(1);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<C: Connection> std::fmt::Debug for ChannelMux<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut channels: Vec<_> = self
+            .channels
+            .iter()
+            .map(|(id, c)| (id.0, c.reliability))
+            .collect();
+        channels.sort_by_key(|(id, _)| *id);
+        f.debug_struct("ChannelMux")
+            .field("channels", &channels)
+            .field("ready", &self.ready.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Address;
+    use std::sync::Mutex;
+
+    #[derive(Debug)]
+    struct Closed;
+
+    impl std::fmt::Display for Closed {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "connection closed")
+        }
+    }
+
+    impl std::error::Error for Closed {}
+
+    /// Connection that receives what it sends, in the order of `queue`
+    #[derive(Default)]
+    struct Loopback {
+        queue: Mutex<VecDeque<Vec<u8>>>,
+    }
+
+    impl Connection for Loopback {
+        type Error = Closed;
+
+        fn send_reliable(&self, data: &[u8]) -> std::result::Result<(), Closed> {
+            self.queue.lock().unwrap().push_back(data.to_vec());
+            Ok(())
+        }
+
+        fn send_unreliable(&self, data: &[u8]) -> std::result::Result<(), Closed> {
+            self.send_reliable(data)
+        }
+
+        fn recv(&self) -> std::result::Result<Option<Vec<u8>>, Closed> {
+            Ok(self.queue.lock().unwrap().pop_front())
+        }
+
+        fn is_connected(&self) -> bool {
+            true
+        }
+
+        fn remote_addr(&self) -> Option<Address> {
+            None
+        }
+
+        fn close(&self) -> std::result::Result<(), Closed> {
+            Ok(())
+        }
+    }
+
+    const STATE: ChannelId = ChannelId(0);
+    const CHAT: ChannelId = ChannelId(1);
+
+    fn mux() -> ChannelMux<Loopback> {
+        ChannelMux::new(Loopback::default())
+            .with_channel(STATE, Reliability::UnreliableSequenced)
+            .with_channel(CHAT, Reliability::ReliableOrdered)
+    }
+
+    fn drain(mux: &mut ChannelMux<Loopback>) -> Vec<(ChannelId, Vec<u8>)> {
+        std::iter::from_fn(|| mux.recv().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_channels_share_connection() {
+        let mut mux = mux();
+        mux.send(STATE, b"s0").unwrap();
+        mux.send(CHAT, b"hi").unwrap();
+        assert_eq!(
+            drain(&mut mux),
+            [(STATE, b"s0".to_vec()), (CHAT, b"hi".to_vec())]
+        );
+        assert!(matches!(
+            mux.send(ChannelId(9), b"?"),
+            Err(Error::UnknownChannel(9))
+        ));
+    }
+
+    #[test]
+    fn test_reordered_packets() {
+        let mut mux = mux();
+        for message in [b"a", b"b", b"c"] {
+            mux.send(STATE, message).unwrap();
+            mux.send(CHAT, message).unwrap();
+        }
+        // The connection delivers them backwards
+        let mut queue = mux.connection().queue.lock().unwrap();
+        let reversed: VecDeque<_> = queue.drain(..).rev().collect();
+        *queue = reversed;
+        drop(queue);
+
+        // The newest state is delivered and older ones dropped; chat waits
+        // for its first message, then comes out in order
+        assert_eq!(
+            drain(&mut mux),
+            [
+                (STATE, b"c".to_vec()),
+                (CHAT, b"a".to_vec()),
+                (CHAT, b"b".to_vec()),
+                (CHAT, b"c".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_receive_window() {
+        let mut mux = mux();
+        for i in 0..=RECV_WINDOW {
+            mux.send(CHAT, &i.to_le_bytes()).unwrap();
+        }
+        // The first packet is late, so the rest wait until the last, which
+        // is beyond the window
+        let first = mux.connection().queue.lock().unwrap().pop_front().unwrap();
+        assert!(matches!(
+            mux.recv(),
+            Err(Error::WindowExceeded { channel: 1, seq, next: 0 }) if seq == RECV_WINDOW
+        ));
+        assert_eq!(mux.channels[&CHAT].pending.len(), RECV_WINDOW as usize - 1);
+
+        // Everything within the window is still delivered, and a duplicate
+        // of a delivered packet is not an error
+        mux.connection()
+            .queue
+            .lock()
+            .unwrap()
+            .push_back(first.clone());
+        let received = drain(&mut mux);
+        assert_eq!(received.len(), RECV_WINDOW as usize);
+        assert_eq!(received.last().unwrap().1, (RECV_WINDOW - 1).to_le_bytes());
+        assert!(mux.channels[&CHAT].pending.is_empty());
+        mux.connection().queue.lock().unwrap().push_back(first);
+        assert!(drain(&mut mux).is_empty());
+    }
+
+    #[test]
+    fn test_sequence_wraps() {
+        assert!(at_or_after(0, u32::MAX));
+        assert!(at_or_after(5, 5));
+        assert!(!at_or_after(u32::MAX, 0));
+    }
+}
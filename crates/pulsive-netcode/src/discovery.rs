@@ -0,0 +1,402 @@
+//! LAN server discovery
+//!
+//! Servers run a [`Beacon`] that periodically multicasts a small
+//! [`ServerDescriptor`] (name, players, tick rate, version) on the local
+//! network. Clients call [`discover`], which listens in the background for
+//! the given time and collects every server heard, enough to fill a local
+//! server list:
+//!
+//! ```rust,ignore
+//! // Server
+//! let mut beacon = Beacon::new(
+//!     ServerDescriptor::new("Friday night", 7777).with_players(3, 8),
+//!     DiscoveryConfig::default(),
+//! )?;
+//! loop {
+//!     beacon.update(Instant::now())?;
+//!     // ...
+//! }
+//!
+//! // Client
+//! let discovery = discover(Duration::from_secs(2));
+//! // ... keep rendering the menu ...
+//! for server in discovery.wait()? {
+//!     println!("{} at {}", server.descriptor.name, server.game_addr());
+//! }
+//! ```
+//!
+//! Discovery binds the beacon port, so only one client per host can
+//! discover at a time.
+
+use crate::{Error, Result};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Marks a datagram as a pulsive beacon
+const MAGIC: &[u8; 4] = b"PLSV";
+
+/// Version of the beacon wire format
+const FORMAT_VERSION: u8 = 1;
+
+/// Longest server name a beacon can carry, in bytes
+pub const MAX_SERVER_NAME: usize = 64;
+
+/// What a server announces about itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerDescriptor {
+    /// Display name, at most [`MAX_SERVER_NAME`] bytes
+    pub name: String,
+    /// Port the game itself listens on
+    pub port: u16,
+    /// Players connected
+    pub players: u32,
+    /// Player capacity
+    pub max_players: u32,
+    /// Ticks per second
+    pub tick_rate: u32,
+    /// Game or protocol version, for filtering incompatible servers
+    pub version: u32,
+}
+
+impl ServerDescriptor {
+    /// Create a descriptor for a server listening on `port`
+    pub fn new(name: impl Into<String>, port: u16) -> Self {
+        Self {
+            name: name.into(),
+            port,
+            players: 0,
+            max_players: 0,
+            tick_rate: 0,
+            version: 0,
+        }
+    }
+
+    /// Set the player count and capacity
+    pub fn with_players(mut self, players: u32, max_players: u32) -> Self {
+        self.players = players;
+        self.max_players = max_players;
+        self
+    }
+
+    /// Set the tick rate
+    pub fn with_tick_rate(mut self, tick_rate: u32) -> Self {
+        self.tick_rate = tick_rate;
+        self
+    }
+
+    /// Set the version
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Encode as a beacon datagram
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        if self.name.len() > MAX_SERVER_NAME {
+            return Err(Error::Serialization(format!(
+                "server name is {} bytes, at most {} fit in a beacon",
+                self.name.len(),
+                MAX_SERVER_NAME
+            )));
+        }
+        let mut data = Vec::with_capacity(24 + self.name.len());
+        data.extend_from_slice(MAGIC);
+        data.push(FORMAT_VERSION);
+        data.extend_from_slice(&self.version.to_le_bytes());
+        data.extend_from_slice(&self.port.to_le_bytes());
+        data.extend_from_slice(&self.players.to_le_bytes());
+        data.extend_from_slice(&self.max_players.to_le_bytes());
+        data.extend_from_slice(&self.tick_rate.to_le_bytes());
+        data.push(self.name.len() as u8);
+        data.extend_from_slice(self.name.as_bytes());
+        Ok(data)
+    }
+
+    /// Decode a beacon datagram
+    ///
+    /// Returns None for datagrams that are not beacons of this format, such
+    /// as other traffic on the same port.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let rest = data.strip_prefix(MAGIC)?;
+        let (&format, rest) = rest.split_first()?;
+        if format != FORMAT_VERSION {
+            return None;
+        }
+        let mut reader = Reader(rest);
+        let version = u32::from_le_bytes(reader.take()?);
+        let port = u16::from_le_bytes(reader.take()?);
+        let players = u32::from_le_bytes(reader.take()?);
+        let max_players = u32::from_le_bytes(reader.take()?);
+        let tick_rate = u32::from_le_bytes(reader.take()?);
+        let [len] = reader.take()?;
+        let name = reader.0.get(..len as usize)?;
+        Some(Self {
+            name: String::from_utf8(name.to_vec()).ok()?,
+            port,
+            players,
+            max_players,
+            tick_rate,
+            version,
+        })
+    }
+}
+
+/// Reads fixed-size fields off the front of a slice
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (field, rest) = self.0.split_first_chunk::<N>()?;
+        self.0 = rest;
+        Some(*field)
+    }
+}
+
+/// Where beacons are sent and how often
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiscoveryConfig {
+    /// Multicast group and port beacons are sent to
+    ///
+    /// A unicast address also works, for a beacon aimed at one host.
+    pub group: SocketAddrV4,
+    /// How often a beacon announces itself
+    pub interval: Duration,
+}
+
+impl DiscoveryConfig {
+    /// Set the group address
+    pub fn with_group(mut self, group: SocketAddrV4) -> Self {
+        self.group = group;
+        self
+    }
+
+    /// Set the announce interval
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            group: SocketAddrV4::new(Ipv4Addr::new(239, 255, 80, 76), 47800),
+            interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Announces a server on the local network
+#[derive(Debug)]
+pub struct Beacon {
+    socket: UdpSocket,
+    descriptor: ServerDescriptor,
+    config: DiscoveryConfig,
+    last_sent: Option<Instant>,
+}
+
+impl Beacon {
+    /// Create a beacon for a server
+    pub fn new(descriptor: ServerDescriptor, config: DiscoveryConfig) -> Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).map_err(io_error)?;
+        if config.group.ip().is_multicast() {
+            // Stay on the local network, and reach clients on this host
+            socket.set_multicast_ttl_v4(1).map_err(io_error)?;
+            socket.set_multicast_loop_v4(true).map_err(io_error)?;
+        }
+        Ok(Self {
+            socket,
+            descriptor,
+            config,
+            last_sent: None,
+        })
+    }
+
+    /// Get the announced descriptor
+    pub fn descriptor(&self) -> &ServerDescriptor {
+        &self.descriptor
+    }
+
+    /// Change the announced descriptor (when players join or leave, say)
+    ///
+    /// Takes effect at the next announcement.
+    pub fn set_descriptor(&mut self, descriptor: ServerDescriptor) {
+        self.descriptor = descriptor;
+    }
+
+    /// Announce the server now
+    pub fn announce(&mut self) -> Result<()> {
+        let data = self.descriptor.encode()?;
+        self.socket
+            .send_to(&data, self.config.group)
+            .map_err(io_error)?;
+        Ok(())
+    }
+
+    /// Announce the server if the interval has passed since the last time
+    ///
+    /// Returns whether it announced.
+    pub fn update(&mut self, now: Instant) -> Result<bool> {
+        let due = self
+            .last_sent
+            .is_none_or(|sent| now.saturating_duration_since(sent) >= self.config.interval);
+        if due {
+            self.announce()?;
+            self.last_sent = Some(now);
+        }
+        Ok(due)
+    }
+}
+
+/// A server heard during discovery
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredServer {
+    /// Address the beacon came from
+    pub addr: SocketAddr,
+    /// The newest descriptor heard
+    pub descriptor: ServerDescriptor,
+}
+
+impl DiscoveredServer {
+    /// Get the address to connect to the game
+    pub fn game_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.addr.ip(), self.descriptor.port)
+    }
+}
+
+/// A discovery running in the background, started by [`discover`]
+#[derive(Debug)]
+pub struct Discovery {
+    handle: JoinHandle<Result<Vec<DiscoveredServer>>>,
+}
+
+impl Discovery {
+    /// Check if the discovery has finished, so [`wait`](Self::wait) won't
+    /// block
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Wait for the discovery to finish and get the servers heard, in the
+    /// order first heard
+    pub fn wait(self) -> Result<Vec<DiscoveredServer>> {
+        self.handle
+            .join()
+            .map_err(|_| Error::Transport("discovery thread panicked".to_string()))?
+    }
+}
+
+/// Listen for server beacons for `timeout`, in the background
+pub fn discover(timeout: Duration) -> Discovery {
+    discover_with_config(DiscoveryConfig::default(), timeout)
+}
+
+/// Listen for server beacons for `timeout`, with a custom config
+pub fn discover_with_config(config: DiscoveryConfig, timeout: Duration) -> Discovery {
+    Discovery {
+        handle: std::thread::spawn(move || listen(config, timeout)),
+    }
+}
+
+fn listen(config: DiscoveryConfig, timeout: Duration) -> Result<Vec<DiscoveredServer>> {
+    let group = config.group;
+    let socket = if group.ip().is_multicast() {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, group.port())).map_err(io_error)?;
+        socket
+            .join_multicast_v4(group.ip(), &Ipv4Addr::UNSPECIFIED)
+            .map_err(io_error)?;
+        socket
+    } else {
+        UdpSocket::bind(group).map_err(io_error)?
+    };
+
+    let deadline = Instant::now() + timeout;
+    let mut servers: Vec<DiscoveredServer> = Vec::new();
+    let mut buf = [0u8; 512];
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            break;
+        }
+        socket.set_read_timeout(Some(left)).map_err(io_error)?;
+        let (len, addr) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                continue
+            }
+            Err(e) => return Err(io_error(e)),
+        };
+        let Some(descriptor) = ServerDescriptor::decode(&buf[..len]) else {
+            continue;
+        };
+        let server = DiscoveredServer { addr, descriptor };
+        match servers
+            .iter_mut()
+            .find(|s| s.game_addr() == server.game_addr())
+        {
+            Some(known) => *known = server,
+            None => servers.push(server),
+        }
+    }
+    Ok(servers)
+}
+
+fn io_error(e: std::io::Error) -> Error {
+    Error::Transport(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptor() -> ServerDescriptor {
+        ServerDescriptor::new("Friday night", 7777)
+            .with_players(3, 8)
+            .with_tick_rate(30)
+            .with_version(2)
+    }
+
+    #[test]
+    fn test_descriptor_roundtrip() {
+        let data = descriptor().encode().unwrap();
+        assert_eq!(ServerDescriptor::decode(&data), Some(descriptor()));
+
+        assert_eq!(ServerDescriptor::decode(&data[..data.len() - 1]), None);
+        assert_eq!(ServerDescriptor::decode(b"hello"), None);
+        assert!(ServerDescriptor::new("x".repeat(65), 1).encode().is_err());
+    }
+
+    #[test]
+    fn test_discover_on_loopback() {
+        // Find a free port, then aim the beacon at it directly
+        let port = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = DiscoveryConfig::default()
+            .with_group(SocketAddrV4::new(Ipv4Addr::LOCALHOST, port))
+            .with_interval(Duration::from_millis(10));
+
+        let discovery = discover_with_config(config, Duration::from_millis(300));
+        let mut beacon = Beacon::new(descriptor(), config).unwrap();
+        while !discovery.is_finished() {
+            beacon.update(Instant::now()).unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        let servers = discovery.wait().unwrap();
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].descriptor, descriptor());
+        assert_eq!(
+            servers[0].game_addr(),
+            SocketAddr::from((Ipv4Addr::LOCALHOST, 7777))
+        );
+    }
+}
@@ -32,6 +32,18 @@ pub enum Error {
     /// Serialization error
     #[error("Serialization error: {0}")]
     Serialization(String),
+
+    /// Message on a channel that was not registered
+    #[error("Unknown channel {0}")]
+    UnknownChannel(u8),
+
+    /// A reliable packet arrived too far ahead of the next one to deliver
+    #[error("Channel {channel}: packet {seq} is beyond the receive window at {next}")]
+    WindowExceeded { channel: u8, seq: u32, next: u32 },
+
+    /// The peer refused the handshake
+    #[error("Handshake rejected: {0}")]
+    HandshakeRejected(crate::Rejection),
 }
 
 /// Result type for netcode operations
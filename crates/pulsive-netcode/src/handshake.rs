@@ -0,0 +1,412 @@
+//! Protocol versioning and capability negotiation
+//!
+//! Peers running different protocol versions or different game content
+//! don't fail loudly: they desync a few ticks in. A [`Handshake`] is the
+//! first message a client sends, carrying:
+//!
+//! - the netcode protocol version ([`PROTOCOL_VERSION`])
+//! - a hash of the game content (`GameDefs::content_hash` in pulsive-script)
+//! - the [`Features`] the peer supports, and those it requires
+//!
+//! The server checks it against its own with [`Handshake::accept`] and
+//! answers with a [`HandshakeReply`]: the features both sides agreed on, or
+//! a structured [`Rejection`] the client can show to the player.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! let local = Handshake::new(defs.content_hash()).with_features(Features::from_bits(0b11));
+//!
+//! // Server, on a new connection
+//! let remote = Handshake::decode(&conn.recv()?.unwrap())?;
+//! let reply = HandshakeReply::from(local.accept(&remote));
+//! conn.send_reliable(&reply.encode())?;
+//!
+//! // Client
+//! conn.send_reliable(&local.encode())?;
+//! let features = HandshakeReply::decode(&reply_bytes)?.into_result()?;
+//! ```
+
+use crate::{Error, Result};
+
+/// Version of the netcode protocol, bumped on incompatible changes
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Marks a handshake message
+const HANDSHAKE_MAGIC: &[u8; 4] = b"PLHS";
+
+/// Marks a handshake reply
+const REPLY_MAGIC: &[u8; 4] = b"PLHR";
+
+/// Set of optional protocol features, as bits
+///
+/// The meaning of each bit is up to the game; both peers must agree on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Features(u64);
+
+impl Features {
+    /// No features
+    pub const NONE: Features = Features(0);
+
+    /// Create a set from its bits
+    pub fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    /// Get the bits of the set
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Check if the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Check if every feature of `other` is in this set
+    pub fn contains(&self, other: Features) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Get the features in both sets
+    pub fn intersection(&self, other: Features) -> Features {
+        Features(self.0 & other.0)
+    }
+
+    /// Get the features of this set missing from `other`
+    pub fn difference(&self, other: Features) -> Features {
+        Features(self.0 & !other.0)
+    }
+}
+
+impl std::ops::BitOr for Features {
+    type Output = Features;
+
+    fn bitor(self, other: Features) -> Features {
+        Features(self.0 | other.0)
+    }
+}
+
+/// First message of a connection, describing a peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Handshake {
+    /// Netcode protocol version
+    pub protocol_version: u32,
+    /// Hash of the game content
+    pub content_hash: u64,
+    /// Features the peer supports
+    pub features: Features,
+    /// Features the peer refuses to play without (a subset of `features`)
+    pub required: Features,
+}
+
+impl Handshake {
+    /// Create a handshake for this protocol version and the given content
+    pub fn new(content_hash: u64) -> Self {
+        Self {
+            protocol_version: PROTOCOL_VERSION,
+            content_hash,
+            features: Features::NONE,
+            required: Features::NONE,
+        }
+    }
+
+    /// Set the supported features
+    pub fn with_features(mut self, features: Features) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Set the required features, also marking them supported
+    pub fn with_required(mut self, required: Features) -> Self {
+        self.required = required;
+        self.features = self.features | required;
+        self
+    }
+
+    /// Check a remote peer's handshake against this one
+    ///
+    /// Returns the features both peers support, or why the remote peer
+    /// can't play with this one.
+    pub fn accept(&self, remote: &Handshake) -> std::result::Result<Features, Rejection> {
+        if remote.protocol_version != self.protocol_version {
+            return Err(Rejection::ProtocolVersion {
+                local: self.protocol_version,
+                remote: remote.protocol_version,
+            });
+        }
+        if remote.content_hash != self.content_hash {
+            return Err(Rejection::ContentMismatch {
+                local: self.content_hash,
+                remote: remote.content_hash,
+            });
+        }
+        let missing = self.required.difference(remote.features);
+        if !missing.is_empty() {
+            return Err(Rejection::MissingFeatures(missing));
+        }
+        let unsupported = remote.required.difference(self.features);
+        if !unsupported.is_empty() {
+            return Err(Rejection::UnsupportedFeatures(unsupported));
+        }
+        Ok(self.features.intersection(remote.features))
+    }
+
+    /// Encode for sending
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(32);
+        data.extend_from_slice(HANDSHAKE_MAGIC);
+        data.extend_from_slice(&self.protocol_version.to_le_bytes());
+        data.extend_from_slice(&self.content_hash.to_le_bytes());
+        data.extend_from_slice(&self.features.0.to_le_bytes());
+        data.extend_from_slice(&self.required.0.to_le_bytes());
+        data
+    }
+
+    /// Decode a received handshake
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data, HANDSHAKE_MAGIC, "handshake")?;
+        // The version comes first and never moves, so any peer can read it
+        let protocol_version = u32::from_le_bytes(reader.take()?);
+        Ok(Self {
+            protocol_version,
+            content_hash: u64::from_le_bytes(reader.take()?),
+            features: Features(u64::from_le_bytes(reader.take()?)),
+            required: Features(u64::from_le_bytes(reader.take()?)),
+        })
+    }
+}
+
+/// Why a peer was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// The peers speak different protocol versions
+    ProtocolVersion {
+        /// Version of the rejecting peer
+        local: u32,
+        /// Version of the rejected peer
+        remote: u32,
+    },
+    /// The peers run different game content
+    ContentMismatch {
+        /// Content hash of the rejecting peer
+        local: u64,
+        /// Content hash of the rejected peer
+        remote: u64,
+    },
+    /// The rejected peer lacks features the rejecting peer requires
+    MissingFeatures(Features),
+    /// The rejected peer requires features the rejecting peer lacks
+    UnsupportedFeatures(Features),
+    /// The rejecting peer refused for its own reasons (full, banned, ...)
+    Refused(u32),
+}
+
+impl std::fmt::Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rejection::ProtocolVersion { local, remote } => write!(
+                f,
+                "protocol version {} is incompatible with version {}",
+                remote, local
+            ),
+            Rejection::ContentMismatch { local, remote } => write!(
+                f,
+                "game content {:016x} differs from {:016x}",
+                remote, local
+            ),
+            Rejection::MissingFeatures(features) => {
+                write!(f, "missing required features {:#x}", features.0)
+            }
+            Rejection::UnsupportedFeatures(features) => {
+                write!(f, "requires unsupported features {:#x}", features.0)
+            }
+            Rejection::Refused(code) => write!(f, "refused with code {}", code),
+        }
+    }
+}
+
+/// Answer to a [`Handshake`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakeReply {
+    /// The peer may play, with these features
+    Accepted(Features),
+    /// The peer was refused
+    Rejected(Rejection),
+}
+
+impl From<std::result::Result<Features, Rejection>> for HandshakeReply {
+    fn from(result: std::result::Result<Features, Rejection>) -> Self {
+        match result {
+            Ok(features) => HandshakeReply::Accepted(features),
+            Err(rejection) => HandshakeReply::Rejected(rejection),
+        }
+    }
+}
+
+impl HandshakeReply {
+    /// Get the agreed features, or the rejection as an error
+    pub fn into_result(self) -> Result<Features> {
+        match self {
+            HandshakeReply::Accepted(features) => Ok(features),
+            HandshakeReply::Rejected(rejection) => Err(Error::HandshakeRejected(rejection)),
+        }
+    }
+
+    /// Encode for sending
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(24);
+        data.extend_from_slice(REPLY_MAGIC);
+        let (tag, a, b) = match *self {
+            HandshakeReply::Accepted(features) => (0u8, features.0, 0),
+            HandshakeReply::Rejected(Rejection::ProtocolVersion { local, remote }) => {
+                (1, local as u64, remote as u64)
+            }
+            HandshakeReply::Rejected(Rejection::ContentMismatch { local, remote }) => {
+                (2, local, remote)
+            }
+            HandshakeReply::Rejected(Rejection::MissingFeatures(features)) => (3, features.0, 0),
+            HandshakeReply::Rejected(Rejection::UnsupportedFeatures(features)) => {
+                (4, features.0, 0)
+            }
+            HandshakeReply::Rejected(Rejection::Refused(code)) => (5, code as u64, 0),
+        };
+        data.push(tag);
+        data.extend_from_slice(&a.to_le_bytes());
+        data.extend_from_slice(&b.to_le_bytes());
+        data
+    }
+
+    /// Decode a received reply
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data, REPLY_MAGIC, "handshake reply")?;
+        let [tag] = reader.take()?;
+        let a = u64::from_le_bytes(reader.take()?);
+        let b = u64::from_le_bytes(reader.take()?);
+        Ok(match tag {
+            0 => HandshakeReply::Accepted(Features(a)),
+            1 => HandshakeReply::Rejected(Rejection::ProtocolVersion {
+                local: a as u32,
+                remote: b as u32,
+            }),
+            2 => HandshakeReply::Rejected(Rejection::ContentMismatch {
+                local: a,
+                remote: b,
+            }),
+            3 => HandshakeReply::Rejected(Rejection::MissingFeatures(Features(a))),
+            4 => HandshakeReply::Rejected(Rejection::UnsupportedFeatures(Features(a))),
+            5 => HandshakeReply::Rejected(Rejection::Refused(a as u32)),
+            _ => {
+                return Err(Error::Serialization(format!(
+                    "unknown handshake reply {}",
+                    tag
+                )))
+            }
+        })
+    }
+}
+
+/// Reads fixed-size fields of a message after its magic
+struct Reader<'a> {
+    data: &'a [u8],
+    what: &'static str,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], magic: &[u8; 4], what: &'static str) -> Result<Self> {
+        let data = data
+            .strip_prefix(magic)
+            .ok_or_else(|| Error::Serialization(format!("not a {}", what)))?;
+        Ok(Self { data, what })
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let (field, rest) = self
+            .data
+            .split_first_chunk::<N>()
+            .ok_or_else(|| Error::Serialization(format!("truncated {}", self.what)))?;
+        self.data = rest;
+        Ok(*field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPLAYS: Features = Features(0b01);
+    const VOICE: Features = Features(0b10);
+
+    #[test]
+    fn test_accept() {
+        let server = Handshake::new(42).with_features(REPLAYS | VOICE);
+        let client = Handshake::new(42).with_features(REPLAYS);
+        assert_eq!(server.accept(&client), Ok(REPLAYS));
+
+        let old = Handshake {
+            protocol_version: 0,
+            ..client
+        };
+        assert_eq!(
+            server.accept(&old),
+            Err(Rejection::ProtocolVersion {
+                local: PROTOCOL_VERSION,
+                remote: 0
+            })
+        );
+        assert_eq!(
+            server.accept(&Handshake::new(7)),
+            Err(Rejection::ContentMismatch {
+                local: 42,
+                remote: 7
+            })
+        );
+
+        let strict = Handshake::new(42).with_required(VOICE);
+        assert_eq!(
+            strict.accept(&client),
+            Err(Rejection::MissingFeatures(VOICE))
+        );
+        assert_eq!(
+            Handshake::new(42).accept(&strict),
+            Err(Rejection::UnsupportedFeatures(VOICE))
+        );
+        assert_eq!(server.accept(&strict), Ok(VOICE));
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let handshake = Handshake::new(u64::MAX).with_required(VOICE);
+        assert_eq!(Handshake::decode(&handshake.encode()).unwrap(), handshake);
+        assert!(Handshake::decode(&handshake.encode()[..10]).is_err());
+        assert!(Handshake::decode(b"nope").is_err());
+
+        for reply in [
+            HandshakeReply::Accepted(REPLAYS),
+            HandshakeReply::Rejected(Rejection::ProtocolVersion {
+                local: 2,
+                remote: 1,
+            }),
+            HandshakeReply::Rejected(Rejection::ContentMismatch {
+                local: 1,
+                remote: u64::MAX,
+            }),
+            HandshakeReply::Rejected(Rejection::MissingFeatures(VOICE)),
+            HandshakeReply::Rejected(Rejection::UnsupportedFeatures(VOICE)),
+            HandshakeReply::Rejected(Rejection::Refused(503)),
+        ] {
+            assert_eq!(HandshakeReply::decode(&reply.encode()).unwrap(), reply);
+        }
+
+        let error = HandshakeReply::Rejected(Rejection::ContentMismatch {
+            local: 1,
+            remote: 2,
+        })
+        .into_result()
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Handshake rejected: game content 0000000000000002 differs from 0000000000000001"
+        );
+    }
+}
@@ -1,10 +1,16 @@
 //! Input buffering for network synchronization
 //!
 //! Manages pending inputs that have been sent to the server but not yet confirmed.
+//!
+//! [`InputTiming`] tunes the latency trade-off of rollback sessions. Input
+//! delay holds local inputs back a few ticks, so remote inputs for the same
+//! tick usually arrive in time and fewer ticks are rolled back; the
+//! rollback window caps how far back a rollback may go.
 
 use pulsive_core::Msg;
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::time::Duration;
 
 /// An entry in the input buffer
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +34,78 @@ impl InputEntry {
     }
 }
 
+/// How many ticks local inputs are delayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputDelay {
+    /// Always delay by this many ticks
+    Fixed(u64),
+    /// Delay by the one-way latency measured with
+    /// [`InputBuffer::update_rtt`], within bounds
+    ///
+    /// Latency beyond `max` is left to rollback.
+    Auto {
+        /// Fewest ticks of delay
+        min: u64,
+        /// Most ticks of delay
+        max: u64,
+    },
+}
+
+impl Default for InputDelay {
+    fn default() -> Self {
+        InputDelay::Fixed(0)
+    }
+}
+
+/// Input delay and rollback window of a session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputTiming {
+    /// How local inputs are delayed
+    pub delay: InputDelay,
+    /// Most ticks a rollback may rewind (None for no limit)
+    pub rollback_window: Option<u64>,
+    /// Length of a tick, to turn RTT into ticks
+    pub tick_duration: Duration,
+}
+
+impl InputTiming {
+    /// Set the input delay
+    pub fn with_delay(mut self, delay: InputDelay) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Set the rollback window in ticks
+    pub fn with_rollback_window(mut self, ticks: u64) -> Self {
+        self.rollback_window = Some(ticks);
+        self
+    }
+
+    /// Set the tick length
+    pub fn with_tick_duration(mut self, tick_duration: Duration) -> Self {
+        self.tick_duration = tick_duration;
+        self
+    }
+
+    /// Get the delay to start with, before any RTT is measured
+    fn initial_delay(&self) -> u64 {
+        match self.delay {
+            InputDelay::Fixed(ticks) => ticks,
+            InputDelay::Auto { min, .. } => min,
+        }
+    }
+}
+
+impl Default for InputTiming {
+    fn default() -> Self {
+        Self {
+            delay: InputDelay::default(),
+            rollback_window: None,
+            tick_duration: Duration::from_secs(1) / 60,
+        }
+    }
+}
+
 /// Buffer for managing pending inputs
 ///
 /// Stores inputs that have been sent to the server but not yet confirmed.
@@ -40,6 +118,10 @@ pub struct InputBuffer {
     capacity: usize,
     /// Last tick that was acknowledged by the server
     last_acknowledged_tick: u64,
+    /// Input delay and rollback window
+    timing: InputTiming,
+    /// Current input delay in ticks
+    delay: u64,
 }
 
 impl InputBuffer {
@@ -49,7 +131,49 @@ impl InputBuffer {
             inputs: VecDeque::with_capacity(capacity),
             capacity,
             last_acknowledged_tick: 0,
+            timing: InputTiming::default(),
+            delay: 0,
+        }
+    }
+
+    /// Set the input delay and rollback window
+    pub fn with_timing(mut self, timing: InputTiming) -> Self {
+        self.set_timing(timing);
+        self
+    }
+
+    /// Set the input delay and rollback window
+    ///
+    /// Resets the current delay to the fixed or minimum delay.
+    pub fn set_timing(&mut self, timing: InputTiming) {
+        self.timing = timing;
+        self.delay = timing.initial_delay();
+    }
+
+    /// Get the input delay and rollback window
+    pub fn timing(&self) -> &InputTiming {
+        &self.timing
+    }
+
+    /// Get the current input delay in ticks
+    pub fn input_delay(&self) -> u64 {
+        self.delay
+    }
+
+    /// Adapt an automatic input delay to a round-trip time sample
+    ///
+    /// Returns the new delay; fixed delays don't change.
+    pub fn update_rtt(&mut self, rtt: Duration) -> u64 {
+        if let InputDelay::Auto { min, max } = self.timing.delay {
+            let tick = self.timing.tick_duration.as_secs_f64();
+            let one_way = if tick > 0.0 {
+                (rtt.as_secs_f64() / 2.0 / tick).ceil() as u64
+            } else {
+                max
+            };
+            self.delay = one_way.clamp(min, max.max(min));
         }
+        self.delay
     }
 
     /// Add an input to the buffer
@@ -63,6 +187,45 @@ impl InputBuffer {
         Ok(())
     }
 
+    /// Add a local input made at `current_tick`, delayed by the input delay
+    ///
+    /// Returns the tick the input is scheduled for. An input is never
+    /// scheduled before an earlier one, even when the delay shrinks.
+    pub fn push_local(&mut self, current_tick: u64, msg: Msg) -> crate::Result<u64> {
+        let tick = (current_tick + self.delay).max(self.newest_tick().unwrap_or(0));
+        self.push(tick, msg)?;
+        Ok(tick)
+    }
+
+    /// Get the inputs scheduled for a tick
+    pub fn inputs_at(&self, tick: u64) -> impl Iterator<Item = &InputEntry> {
+        self.inputs.iter().filter(move |e| e.tick == tick)
+    }
+
+    /// Check that a rollback from `current_tick` to `target` fits the
+    /// rollback window
+    pub fn check_rollback(&self, current_tick: u64, target: u64) -> crate::Result<()> {
+        match self.timing.rollback_window {
+            Some(window) if current_tick.saturating_sub(target) > window => {
+                Err(crate::Error::RollbackTooFar {
+                    target,
+                    oldest: current_tick.saturating_sub(window),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check if the session should wait rather than simulate `current_tick`
+    ///
+    /// True when the tick is further past the last acknowledged tick than
+    /// the rollback window could rewind.
+    pub fn should_stall(&self, current_tick: u64) -> bool {
+        self.timing
+            .rollback_window
+            .is_some_and(|window| current_tick.saturating_sub(self.last_acknowledged_tick) > window)
+    }
+
     /// Acknowledge all inputs up to and including the given tick
     ///
     /// Removes acknowledged inputs from the buffer.
@@ -190,4 +353,44 @@ mod tests {
         assert!(buffer.is_full());
         assert!(buffer.push(4, make_msg(4)).is_err());
     }
+
+    #[test]
+    fn test_input_delay() {
+        let timing = InputTiming::default()
+            .with_delay(InputDelay::Fixed(2))
+            .with_rollback_window(4);
+        let mut buffer = InputBuffer::new(10).with_timing(timing);
+
+        assert_eq!(buffer.push_local(10, make_msg(10)).unwrap(), 12);
+        assert_eq!(buffer.inputs_at(12).count(), 1);
+        assert_eq!(buffer.update_rtt(Duration::from_millis(500)), 2);
+
+        assert!(buffer.check_rollback(12, 8).is_ok());
+        assert!(matches!(
+            buffer.check_rollback(12, 7),
+            Err(crate::Error::RollbackTooFar { oldest: 8, .. })
+        ));
+        buffer.acknowledge(6);
+        assert!(!buffer.should_stall(10));
+        assert!(buffer.should_stall(11));
+    }
+
+    #[test]
+    fn test_auto_delay() {
+        let timing = InputTiming::default()
+            .with_delay(InputDelay::Auto { min: 1, max: 4 })
+            .with_tick_duration(Duration::from_millis(10));
+        let mut buffer = InputBuffer::new(10).with_timing(timing);
+        assert_eq!(buffer.input_delay(), 1);
+
+        // 50ms one way is 5 ticks, capped at 4
+        assert_eq!(buffer.update_rtt(Duration::from_millis(100)), 4);
+        assert_eq!(buffer.push_local(0, make_msg(0)).unwrap(), 4);
+
+        // A shorter delay never schedules before the earlier input
+        assert_eq!(buffer.update_rtt(Duration::from_millis(30)), 2);
+        assert_eq!(buffer.push_local(1, make_msg(1)).unwrap(), 4);
+        assert_eq!(buffer.push_local(3, make_msg(3)).unwrap(), 5);
+        assert_eq!(buffer.update_rtt(Duration::ZERO), 1);
+    }
 }
@@ -2,8 +2,174 @@
 //!
 //! Interpolates between two model states to produce smooth visual transitions,
 //! even when the simulation runs at a lower tick rate than the render rate.
+//!
+//! Not every property should be lerped: counters, enums and flags must
+//! snap, and angles must take the short way around. An
+//! [`InterpolationPolicies`] registry picks an [`InterpolationPolicy`] per
+//! entity kind and property name pattern:
+//!
+//! ```rust,ignore
+//! let policies = InterpolationPolicies::new()
+//!     .with_policy("*_count", InterpolationPolicy::Snap)
+//!     .with_kind_policy("ship", "heading", InterpolationPolicy::AngleLerp)
+//!     .with_kind_policy("door", "state", InterpolationPolicy::Step);
+//! let interpolator = Interpolator::new().with_policies(policies);
+//! ```
+
+use pulsive_core::{DefId, Model, StateHistory, StateInterpolation, Value};
+use std::f64::consts::{PI, TAU};
+
+/// How a property moves between two states
+#[derive(Debug, Clone, Copy, Default)]
+pub enum InterpolationPolicy {
+    /// Blend numbers linearly; integers are rounded, other values snap
+    #[default]
+    Lerp,
+    /// Blend angles in radians along the shorter arc
+    AngleLerp,
+    /// Keep the previous value until the current state is reached
+    Step,
+    /// Jump to the current value at once
+    Snap,
+    /// Blend with a custom function of (previous, current, alpha)
+    Custom(fn(&Value, &Value, f64) -> Value),
+}
+
+impl InterpolationPolicy {
+    /// Interpolate between two values
+    pub fn apply(&self, prev: &Value, curr: &Value, alpha: f64) -> Value {
+        match self {
+            InterpolationPolicy::Lerp => Interpolator::interpolate_value(prev, curr, alpha),
+            InterpolationPolicy::AngleLerp => match (prev.as_float(), curr.as_float()) {
+                (Some(p), Some(c)) => {
+                    let mut delta = (c - p).rem_euclid(TAU);
+                    if delta > PI {
+                        delta -= TAU;
+                    }
+                    Value::Float(p + delta * alpha)
+                }
+                _ => curr.clone(),
+            },
+            InterpolationPolicy::Step => {
+                if alpha >= 1.0 {
+                    curr.clone()
+                } else {
+                    prev.clone()
+                }
+            }
+            InterpolationPolicy::Snap => curr.clone(),
+            InterpolationPolicy::Custom(f) => f(prev, curr, alpha),
+        }
+    }
+}
+
+/// A policy for the properties matching a pattern
+#[derive(Debug, Clone)]
+struct PolicyRule {
+    /// Entity kind the rule applies to, or None for every entity and global
+    kind: Option<DefId>,
+    pattern: String,
+    policy: InterpolationPolicy,
+}
+
+/// Interpolation policies by entity kind and property name
+///
+/// Patterns are property names where `*` matches any run of characters
+/// (`"*"`, `"color_*"`, `"*_id"`). When several rules match, the one added
+/// last wins; properties no rule matches use the default policy.
+#[derive(Debug, Clone, Default)]
+pub struct InterpolationPolicies {
+    default: InterpolationPolicy,
+    rules: Vec<PolicyRule>,
+}
+
+impl InterpolationPolicies {
+    /// Create a registry that lerps everything
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the policy for properties no rule matches
+    pub fn with_default(mut self, policy: InterpolationPolicy) -> Self {
+        self.default = policy;
+        self
+    }
+
+    /// Add a policy for matching properties of every entity and global
+    pub fn with_policy(mut self, pattern: impl Into<String>, policy: InterpolationPolicy) -> Self {
+        self.set_policy(pattern, policy);
+        self
+    }
+
+    /// Add a policy for matching properties of one entity kind
+    pub fn with_kind_policy(
+        mut self,
+        kind: impl Into<DefId>,
+        pattern: impl Into<String>,
+        policy: InterpolationPolicy,
+    ) -> Self {
+        self.set_kind_policy(kind, pattern, policy);
+        self
+    }
+
+    /// Add a policy for matching properties of every entity and global
+    pub fn set_policy(&mut self, pattern: impl Into<String>, policy: InterpolationPolicy) {
+        self.rules.push(PolicyRule {
+            kind: None,
+            pattern: pattern.into(),
+            policy,
+        });
+    }
 
-use pulsive_core::{Model, StateHistory, StateInterpolation, Value};
+    /// Add a policy for matching properties of one entity kind
+    pub fn set_kind_policy(
+        &mut self,
+        kind: impl Into<DefId>,
+        pattern: impl Into<String>,
+        policy: InterpolationPolicy,
+    ) {
+        self.rules.push(PolicyRule {
+            kind: Some(kind.into()),
+            pattern: pattern.into(),
+            policy,
+        });
+    }
+
+    /// Get the policy for a property of an entity kind, or of the globals
+    /// when `kind` is None
+    pub fn get(&self, kind: Option<&DefId>, property: &str) -> InterpolationPolicy {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| {
+                rule.kind.as_ref().is_none_or(|k| Some(k) == kind)
+                    && matches_pattern(&rule.pattern, property)
+            })
+            .map_or(self.default, |rule| rule.policy)
+    }
+}
+
+/// Match a name against a pattern where `*` matches any run of characters
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // split always yields at least one part
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No wildcard
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
 
 /// Interpolator for smooth state transitions
 ///
@@ -15,6 +181,8 @@ pub struct Interpolator {
     prev_state: Option<(u64, Model)>,
     /// Current state (target)
     curr_state: Option<(u64, Model)>,
+    /// How each property is interpolated
+    policies: InterpolationPolicies,
 }
 
 impl Interpolator {
@@ -23,9 +191,26 @@ impl Interpolator {
         Self {
             prev_state: None,
             curr_state: None,
+            policies: InterpolationPolicies::new(),
         }
     }
 
+    /// Set the interpolation policies
+    pub fn with_policies(mut self, policies: InterpolationPolicies) -> Self {
+        self.policies = policies;
+        self
+    }
+
+    /// Get the interpolation policies
+    pub fn policies(&self) -> &InterpolationPolicies {
+        &self.policies
+    }
+
+    /// Get the interpolation policies for modification
+    pub fn policies_mut(&mut self) -> &mut InterpolationPolicies {
+        &mut self.policies
+    }
+
     /// Update with a new authoritative state
     pub fn push_state(&mut self, tick: u64, model: Model) {
         // Shift current to previous
@@ -41,7 +226,9 @@ impl Interpolator {
     /// - 0.5 = halfway between
     pub fn interpolate(&self, alpha: f32) -> Option<Model> {
         match (&self.prev_state, &self.curr_state) {
-            (Some((_, prev)), Some((_, curr))) => Some(Self::interpolate_models(prev, curr, alpha)),
+            (Some((_, prev)), Some((_, curr))) => {
+                Some(Self::interpolate_models(prev, curr, alpha, &self.policies))
+            }
             (None, Some((_, curr))) => Some(curr.clone()),
             (Some((_, prev)), None) => Some(prev.clone()),
             (None, None) => None,
@@ -70,28 +257,38 @@ impl Interpolator {
             (offset / range).clamp(0.0, 1.0)
         };
 
-        Some(Self::interpolate_models(before, after, base_alpha))
+        Some(Self::interpolate_models(
+            before,
+            after,
+            base_alpha,
+            &self.policies,
+        ))
     }
 
     /// Interpolate between two models
     ///
-    /// This is a basic implementation that interpolates numeric properties.
-    /// For more complex interpolation (positions, rotations), users should
-    /// implement their own interpolation logic.
-    fn interpolate_models(prev: &Model, curr: &Model, alpha: f32) -> Model {
+    /// Each property is interpolated with the policy `policies` gives it.
+    /// Properties missing from the previous state take the current value.
+    pub(crate) fn interpolate_models(
+        prev: &Model,
+        curr: &Model,
+        alpha: f32,
+        policies: &InterpolationPolicies,
+    ) -> Model {
         let mut result = curr.clone();
         let alpha_f64 = alpha as f64;
 
         // Interpolate entity properties
         for entity in result.entities_mut().iter_mut() {
             let entity_id = entity.id;
+            let kind = entity.kind.clone();
 
             // Try to find corresponding entity in previous state
             if let Some(prev_entity) = prev.entities().get(entity_id) {
-                // Interpolate numeric properties
                 for (key, curr_value) in entity.properties.iter_mut() {
                     if let Some(prev_value) = prev_entity.get(key) {
-                        *curr_value = Self::interpolate_value(prev_value, curr_value, alpha_f64);
+                        let policy = policies.get(Some(&kind), key);
+                        *curr_value = policy.apply(prev_value, curr_value, alpha_f64);
                     }
                 }
             }
@@ -100,7 +297,8 @@ impl Interpolator {
         // Interpolate global properties
         for (key, curr_value) in result.globals_mut().iter_mut() {
             if let Some(prev_value) = prev.globals().get(key) {
-                *curr_value = Self::interpolate_value(prev_value, curr_value, alpha_f64);
+                let policy = policies.get(None, key);
+                *curr_value = policy.apply(prev_value, curr_value, alpha_f64);
             }
         }
 
@@ -137,6 +335,8 @@ impl Interpolator {
     }
 
     /// Reset the interpolator
+    ///
+    /// Keeps the policies.
     pub fn reset(&mut self) {
         self.prev_state = None;
         self.curr_state = None;
@@ -195,4 +395,71 @@ mod tests {
             Some(5.0)
         );
     }
+
+    #[test]
+    fn test_policies() {
+        fn fixed(_: &Value, _: &Value, _: f64) -> Value {
+            Value::Int(-1)
+        }
+
+        let policies = InterpolationPolicies::new()
+            .with_policy("*_count", InterpolationPolicy::Snap)
+            .with_kind_policy("ship", "heading", InterpolationPolicy::AngleLerp)
+            .with_kind_policy("ship", "sail*", InterpolationPolicy::Step)
+            .with_kind_policy("ship", "crew_count", InterpolationPolicy::Custom(fixed));
+        let mut interpolator = Interpolator::new().with_policies(policies);
+
+        let mut prev = Model::new();
+        prev.set_global("gold_count", 0.0);
+        prev.set_global("heading", 0.0);
+        let ship = prev.entities_mut().create("ship");
+        ship.set("heading", 350f64.to_radians());
+        ship.set("sails", 0.0);
+        ship.set("crew_count", 10);
+        ship.set("x", 0.0);
+        let id = ship.id;
+
+        let mut curr = prev.clone();
+        curr.set_global("gold_count", 10.0);
+        curr.set_global("heading", 10.0);
+        let ship = curr.entities_mut().get_mut(id).unwrap();
+        ship.set("heading", 10f64.to_radians());
+        ship.set("sails", 1.0);
+        ship.set("crew_count", 20);
+        ship.set("x", 10.0);
+
+        interpolator.push_state(0, prev);
+        interpolator.push_state(1, curr);
+        let view = interpolator.interpolate(0.5).unwrap();
+
+        let global = |key| view.get_global(key).and_then(Value::as_float);
+        assert_eq!(global("gold_count"), Some(10.0));
+        // Kind rules don't apply to globals
+        assert_eq!(global("heading"), Some(5.0));
+
+        let ship = view.entities().get(id).unwrap();
+        let heading = ship.get("heading").and_then(Value::as_float).unwrap();
+        assert!((heading - 360f64.to_radians()).abs() < 1e-9);
+        assert_eq!(ship.get("sails"), Some(&Value::Float(0.0)));
+        assert_eq!(ship.get("crew_count"), Some(&Value::Int(-1)));
+        assert_eq!(ship.get("x"), Some(&Value::Float(5.0)));
+
+        interpolator.reset();
+        assert!(matches!(
+            interpolator.policies().get(None, "gold_count"),
+            InterpolationPolicy::Snap
+        ));
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("*", "anything"));
+        assert!(matches_pattern("hp", "hp"));
+        assert!(!matches_pattern("hp", "hp_max"));
+        assert!(matches_pattern("color_*", "color_r"));
+        assert!(matches_pattern("*_id", "owner_id"));
+        assert!(matches_pattern("a*b*c", "a_xb_yc"));
+        assert!(!matches_pattern("a*b*c", "a_xc_yb"));
+        assert!(!matches_pattern("ab*ba", "aba"));
+    }
 }
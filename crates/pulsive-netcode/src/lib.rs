@@ -4,9 +4,18 @@
 //!
 //! - **Prediction**: Apply inputs locally before server confirmation
 //! - **Reconciliation**: Correct local state when server state differs
-//! - **Interpolation**: Smooth rendering between discrete states
-//! - **Input Buffering**: Queue and manage pending commands
+//! - **Interpolation**: Smooth rendering between discrete states, with per-property policies
+//! - **Input Buffering**: Queue and manage pending commands, with input delay
 //! - **Authority**: Client/server state ownership
+//! - **Observation**: Delayed, read-only views for spectators
+//! - **Channels**: Multiplexed messages with per-channel reliability
+//! - **Lifecycle**: Connection states, keepalives, timeouts and peer events
+//! - **Discovery**: LAN server beacons for local server lists
+//! - **Handshake**: Protocol version, content and feature checks between peers
+//! - **Stats**: RTT, loss, bandwidth and misprediction metrics per connection
+//! - **NAT Traversal**: Hole punching through a rendezvous server, with relay fallback
+//!   and retransmitted reliable sends
+//! - **Recording**: Match journals for replay and auditing (feature `journal`)
 //!
 //! # Architecture
 //!
@@ -52,18 +61,44 @@
 //! }
 //! ```
 
+mod channel;
+mod discovery;
 mod error;
+mod handshake;
 mod input_buffer;
 mod interpolation;
+mod lifecycle;
+mod nat;
+mod observer;
 mod prediction;
 mod reconciliation;
+#[cfg(feature = "journal")]
+mod recorder;
+mod reliable;
+mod scope;
+mod stats;
 mod transport;
 
+pub use channel::{ChannelId, ChannelMux, Reliability, RECV_WINDOW};
+pub use discovery::{
+    discover, discover_with_config, Beacon, DiscoveredServer, Discovery, DiscoveryConfig,
+    ServerDescriptor, MAX_SERVER_NAME,
+};
 pub use error::{Error, Result};
-pub use input_buffer::{InputBuffer, InputEntry};
-pub use interpolation::Interpolator;
+pub use handshake::{Features, Handshake, HandshakeReply, Rejection, PROTOCOL_VERSION};
+pub use input_buffer::{InputBuffer, InputDelay, InputEntry, InputTiming};
+pub use interpolation::{InterpolationPolicies, InterpolationPolicy, Interpolator};
+pub use lifecycle::{
+    ConnectionState, LifecycleConfig, ManagedConnection, PEER_CONNECTED, PEER_DISCONNECTED,
+};
+pub use nat::{HolePuncher, NatConfig, NatConnection, NatState, RendezvousServer};
+pub use observer::{ObserverConfig, ObserverSession};
 pub use prediction::PredictionEngine;
-pub use reconciliation::Reconciler;
+pub use reconciliation::{Correction, Reconciler, Tolerances};
+#[cfg(feature = "journal")]
+pub use recorder::{MatchRecorder, MATCH_METADATA_KEY};
+pub use scope::PredictionScope;
+pub use stats::{misprediction, ConnectionStats};
 pub use transport::{Address, Connection, Transport};
 
 // Re-export core trait for convenience
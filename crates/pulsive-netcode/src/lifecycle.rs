@@ -0,0 +1,587 @@
+//! Connection lifecycle: handshake, keepalive, timeouts and events
+//!
+//! A raw [`Connection`] only says whether its socket is open. A
+//! [`ManagedConnection`] wraps it in a state machine that knows whether the
+//! peer is actually there:
+//!
+//! ```text
+//! Connecting ──(packet from peer)──▶ Connected ──(disconnect)──▶ Disconnecting
+//!     │                                 │   │                        │
+//!     └──(silence)──▶ TimedOut ◀──(silence) └──(peer closes)──▶ Disconnected
+//! ```
+//!
+//! Both sides send a keepalive ping whenever they have sent nothing for
+//! [`LifecycleConfig::keepalive_interval`], so a silent peer is a lost peer
+//! after [`LifecycleConfig::timeout`].
+//!
+//! Transitions can be observed with [`ManagedConnection::on_transition`]
+//! callbacks, or taken as pulsive events ([`PEER_CONNECTED`],
+//! [`PEER_DISCONNECTED`]) to push into the runtime so game logic reacts to
+//! them declaratively.
+//!
+//! `ManagedConnection` is itself a [`Connection`], carrying application data
+//! only while connected, so it can sit under a [`crate::ChannelMux`].
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! let conn = ManagedConnection::new(socket, LifecycleConfig::default());
+//!
+//! // Game loop
+//! conn.update(Instant::now())?;
+//! for msg in conn.take_events(model.current_tick()) {
+//!     runtime.send(msg); // handled by `peer_connected` / `peer_disconnected` events
+//! }
+//! ```
+
+use crate::{Address, Connection, Error, Result};
+use pulsive_core::{EntityRef, Msg};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Event emitted when the peer is reached
+pub const PEER_CONNECTED: &str = "peer_connected";
+
+/// Event emitted when a connected peer is lost, with a `reason` param of
+/// `"closed"` or `"timeout"`
+pub const PEER_DISCONNECTED: &str = "peer_disconnected";
+
+/// Application data
+const PACKET_DATA: u8 = 0;
+/// Keepalive, answered with a pong
+const PACKET_PING: u8 = 1;
+/// Answer to a keepalive
+const PACKET_PONG: u8 = 2;
+/// Graceful disconnect, answered with a close
+const PACKET_CLOSE: u8 = 3;
+
+/// State of a [`ManagedConnection`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConnectionState {
+    /// Waiting to hear from the peer
+    Connecting,
+    /// The peer is responsive
+    Connected,
+    /// Closing, waiting for the peer to acknowledge
+    Disconnecting,
+    /// Closed gracefully, by either side
+    Disconnected,
+    /// The peer went silent for longer than the timeout
+    TimedOut,
+}
+
+impl ConnectionState {
+    /// Check if the connection is over (disconnected or timed out)
+    pub fn is_closed(&self) -> bool {
+        matches!(self, Self::Disconnected | Self::TimedOut)
+    }
+}
+
+/// Timing of a [`ManagedConnection`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LifecycleConfig {
+    /// How long the peer may stay silent before the connection times out
+    ///
+    /// Also bounds how long a connection waits for a close to be
+    /// acknowledged.
+    pub timeout: Duration,
+    /// How long to go without sending before sending a keepalive ping
+    ///
+    /// Keep it well below the timeout, so a lost ping or two is tolerated.
+    pub keepalive_interval: Duration,
+}
+
+impl LifecycleConfig {
+    /// Set the timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the keepalive interval
+    pub fn with_keepalive_interval(mut self, interval: Duration) -> Self {
+        self.keepalive_interval = interval;
+        self
+    }
+}
+
+impl Default for LifecycleConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            keepalive_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Callback for state transitions, given the old and new states
+type TransitionCallback = Box<dyn FnMut(ConnectionState, ConnectionState) + Send>;
+
+/// Mutable state of a [`ManagedConnection`]
+struct Lifecycle {
+    state: ConnectionState,
+    /// When the current state was entered
+    since: Instant,
+    last_recv: Instant,
+    last_send: Option<Instant>,
+    /// Whether the peer was ever connected, so losing it is an event
+    was_connected: bool,
+    /// Application data received, not yet returned by `recv`
+    inbox: VecDeque<Vec<u8>>,
+    /// Transitions not yet passed to the callbacks
+    transitions: Vec<(ConnectionState, ConnectionState)>,
+    /// Events not yet taken: event ID and disconnect reason
+    events: Vec<(&'static str, Option<&'static str>)>,
+}
+
+impl Lifecycle {
+    fn transition(&mut self, to: ConnectionState, now: Instant) {
+        let from = self.state;
+        if from == to {
+            return;
+        }
+        self.state = to;
+        self.since = now;
+        self.transitions.push((from, to));
+        match to {
+            ConnectionState::Connected => {
+                self.was_connected = true;
+                self.events.push((PEER_CONNECTED, None));
+            }
+            ConnectionState::Disconnected if self.was_connected => {
+                self.events.push((PEER_DISCONNECTED, Some("closed")));
+            }
+            ConnectionState::TimedOut if self.was_connected => {
+                self.events.push((PEER_DISCONNECTED, Some("timeout")));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A connection with a lifecycle state machine
+///
+/// See the [module docs](self) for the states and events. Drive it by
+/// calling [`update`](Self::update) every frame; [`Connection::recv`] also
+/// updates it.
+pub struct ManagedConnection<C: Connection> {
+    connection: C,
+    config: LifecycleConfig,
+    lifecycle: Mutex<Lifecycle>,
+    callbacks: Mutex<Vec<TransitionCallback>>,
+}
+
+impl<C: Connection> ManagedConnection<C> {
+    /// Wrap a connection and start connecting
+    pub fn new(connection: C, config: LifecycleConfig) -> Self {
+        let now = Instant::now();
+        Self {
+            connection,
+            config,
+            lifecycle: Mutex::new(Lifecycle {
+                state: ConnectionState::Connecting,
+                since: now,
+                last_recv: now,
+                last_send: None,
+                was_connected: false,
+                inbox: VecDeque::new(),
+                transitions: Vec::new(),
+                events: Vec::new(),
+            }),
+            callbacks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Get the config
+    pub fn config(&self) -> &LifecycleConfig {
+        &self.config
+    }
+
+    /// Get the underlying connection
+    pub fn connection(&self) -> &C {
+        &self.connection
+    }
+
+    /// Get the current state
+    pub fn state(&self) -> ConnectionState {
+        self.lifecycle.lock().unwrap().state
+    }
+
+    /// Register a callback for state transitions
+    ///
+    /// Called from [`update`](Self::update) with the old and new states,
+    /// including for transitions made since the last update (by
+    /// [`disconnect`](Self::disconnect)).
+    pub fn on_transition(
+        &self,
+        callback: impl FnMut(ConnectionState, ConnectionState) + Send + 'static,
+    ) {
+        self.callbacks.lock().unwrap().push(Box::new(callback));
+    }
+
+    /// Process received packets and timers as of `now`
+    ///
+    /// Answers pings and closes, queues application data for `recv`, sends
+    /// keepalives, and times out a silent peer. Returns the state after the
+    /// update.
+    pub fn update(&self, now: Instant) -> Result<ConnectionState> {
+        let (state, transitions) = {
+            let mut lifecycle = self.lifecycle.lock().unwrap();
+            let result = self.update_locked(&mut lifecycle, now);
+            (result, std::mem::take(&mut lifecycle.transitions))
+        };
+        if !transitions.is_empty() {
+            let mut callbacks = self.callbacks.lock().unwrap();
+            for (from, to) in transitions {
+                for callback in callbacks.iter_mut() {
+                    callback(from, to);
+                }
+            }
+        }
+        state
+    }
+
+    fn update_locked(&self, lifecycle: &mut Lifecycle, now: Instant) -> Result<ConnectionState> {
+        while !lifecycle.state.is_closed() {
+            let Some(packet) = self.connection.recv().map_err(transport_error)? else {
+                break;
+            };
+            lifecycle.last_recv = now;
+            if lifecycle.state == ConnectionState::Connecting {
+                lifecycle.transition(ConnectionState::Connected, now);
+            }
+            match packet.split_first() {
+                Some((&PACKET_DATA, data)) => lifecycle.inbox.push_back(data.to_vec()),
+                Some((&PACKET_PING, _)) => self.send_control(lifecycle, PACKET_PONG, now)?,
+                Some((&PACKET_PONG, _)) => {}
+                Some((&PACKET_CLOSE, _)) => {
+                    if lifecycle.state != ConnectionState::Disconnecting {
+                        self.send_control(lifecycle, PACKET_CLOSE, now)?;
+                    }
+                    self.close_as(lifecycle, ConnectionState::Disconnected, now);
+                }
+                _ => {
+                    return Err(Error::Serialization(format!(
+                        "unknown lifecycle packet {:?}",
+                        packet.first()
+                    )))
+                }
+            }
+        }
+
+        match lifecycle.state {
+            ConnectionState::Connecting | ConnectionState::Connected => {
+                if !self.connection.is_connected() {
+                    self.close_as(lifecycle, ConnectionState::Disconnected, now);
+                } else if now.saturating_duration_since(lifecycle.last_recv) > self.config.timeout {
+                    self.close_as(lifecycle, ConnectionState::TimedOut, now);
+                } else if self.keepalive_due(lifecycle, now) {
+                    self.send_control(lifecycle, PACKET_PING, now)?;
+                }
+            }
+            ConnectionState::Disconnecting => {
+                if !self.connection.is_connected()
+                    || now.saturating_duration_since(lifecycle.since) > self.config.timeout
+                {
+                    self.close_as(lifecycle, ConnectionState::Disconnected, now);
+                } else if self.keepalive_due(lifecycle, now) {
+                    // The close may have been lost
+                    self.send_control(lifecycle, PACKET_CLOSE, now)?;
+                }
+            }
+            ConnectionState::Disconnected | ConnectionState::TimedOut => {}
+        }
+        Ok(lifecycle.state)
+    }
+
+    /// Start a graceful disconnect
+    ///
+    /// The connection is `Disconnecting` until the peer acknowledges, or for
+    /// at most the timeout. Does nothing if already disconnecting or closed.
+    pub fn disconnect(&self) -> Result<()> {
+        let mut lifecycle = self.lifecycle.lock().unwrap();
+        if matches!(
+            lifecycle.state,
+            ConnectionState::Connecting | ConnectionState::Connected
+        ) {
+            let now = Instant::now();
+            self.send_control(&mut lifecycle, PACKET_CLOSE, now)?;
+            lifecycle.transition(ConnectionState::Disconnecting, now);
+        }
+        Ok(())
+    }
+
+    /// Take the lifecycle events since the last call, as pulsive event
+    /// messages for `tick`
+    ///
+    /// Events target [`EntityRef::Global`] and carry a `peer` param with the
+    /// remote address, when known.
+    pub fn take_events(&self, tick: u64) -> Vec<Msg> {
+        let events = std::mem::take(&mut self.lifecycle.lock().unwrap().events);
+        let peer = self.connection.remote_addr().map(|addr| addr.to_string());
+        events
+            .into_iter()
+            .map(|(event, reason)| {
+                let mut msg = Msg::event(event, EntityRef::Global, tick);
+                if let Some(peer) = &peer {
+                    msg = msg.with_param("peer", peer.as_str());
+                }
+                if let Some(reason) = reason {
+                    msg = msg.with_param("reason", reason);
+                }
+                msg
+            })
+            .collect()
+    }
+
+    fn keepalive_due(&self, lifecycle: &Lifecycle, now: Instant) -> bool {
+        lifecycle.last_send.is_none_or(|sent| {
+            now.saturating_duration_since(sent) >= self.config.keepalive_interval
+        })
+    }
+
+    fn send_control(&self, lifecycle: &mut Lifecycle, kind: u8, now: Instant) -> Result<()> {
+        lifecycle.last_send = Some(now);
+        self.connection
+            .send_unreliable(&[kind])
+            .map_err(transport_error)
+    }
+
+    fn close_as(&self, lifecycle: &mut Lifecycle, state: ConnectionState, now: Instant) {
+        // The peer is gone or has agreed to close; a failure to close our
+        // end changes nothing
+        let _ = self.connection.close();
+        lifecycle.inbox.clear();
+        lifecycle.transition(state, now);
+    }
+
+    fn send_data(&self, data: &[u8], reliable: bool) -> Result<()> {
+        let mut lifecycle = self.lifecycle.lock().unwrap();
+        if lifecycle.state != ConnectionState::Connected {
+            return Err(Error::Transport(format!(
+                "cannot send while {:?}",
+                lifecycle.state
+            )));
+        }
+        lifecycle.last_send = Some(Instant::now());
+        let mut packet = Vec::with_capacity(data.len() + 1);
+        packet.push(PACKET_DATA);
+        packet.extend_from_slice(data);
+        let sent = if reliable {
+            self.connection.send_reliable(&packet)
+        } else {
+            self.connection.send_unreliable(&packet)
+        };
+        sent.map_err(transport_error)
+    }
+}
+
+fn transport_error(e: impl std::error::Error) -> Error {
+    Error::Transport(e.to_string())
+}
+
+impl<C: Connection> Connection for ManagedConnection<C> {
+    type Error = Error;
+
+    fn send_reliable(&self, data: &[u8]) -> Result<()> {
+        self.send_data(data, true)
+    }
+
+    fn send_unreliable(&self, data: &[u8]) -> Result<()> {
+        self.send_data(data, false)
+    }
+
+    fn recv(&self) -> Result<Option<Vec<u8>>> {
+        self.update(Instant::now())?;
+        Ok(self.lifecycle.lock().unwrap().inbox.pop_front())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.state() == ConnectionState::Connected
+    }
+
+    fn remote_addr(&self) -> Option<Address> {
+        self.connection.remote_addr()
+    }
+
+    fn close(&self) -> Result<()> {
+        self.disconnect()
+    }
+}
+
+impl<C: Connection> std::fmt::Debug for ManagedConnection<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ManagedConnection")
+            .field("state", &self.state())
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::Value;
+    use std::sync::Arc;
+
+    #[derive(Debug)]
+    struct Closed;
+
+    impl std::fmt::Display for Closed {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "connection closed")
+        }
+    }
+
+    impl std::error::Error for Closed {}
+
+    type Queue = Arc<Mutex<VecDeque<Vec<u8>>>>;
+
+    /// One end of an in-memory connection
+    struct Pipe {
+        outgoing: Queue,
+        incoming: Queue,
+    }
+
+    fn pipe() -> (Pipe, Pipe) {
+        let (a, b) = (Queue::default(), Queue::default());
+        (
+            Pipe {
+                outgoing: a.clone(),
+                incoming: b.clone(),
+            },
+            Pipe {
+                outgoing: b,
+                incoming: a,
+            },
+        )
+    }
+
+    impl Connection for Pipe {
+        type Error = Closed;
+
+        fn send_reliable(&self, data: &[u8]) -> std::result::Result<(), Closed> {
+            self.outgoing.lock().unwrap().push_back(data.to_vec());
+            Ok(())
+        }
+
+        fn send_unreliable(&self, data: &[u8]) -> std::result::Result<(), Closed> {
+            self.send_reliable(data)
+        }
+
+        fn recv(&self) -> std::result::Result<Option<Vec<u8>>, Closed> {
+            Ok(self.incoming.lock().unwrap().pop_front())
+        }
+
+        fn is_connected(&self) -> bool {
+            true
+        }
+
+        fn remote_addr(&self) -> Option<Address> {
+            Some("peer".into())
+        }
+
+        fn close(&self) -> std::result::Result<(), Closed> {
+            Ok(())
+        }
+    }
+
+    fn connect(now: Instant) -> (ManagedConnection<Pipe>, ManagedConnection<Pipe>) {
+        let (a, b) = pipe();
+        let a = ManagedConnection::new(a, LifecycleConfig::default());
+        let b = ManagedConnection::new(b, LifecycleConfig::default());
+        assert_eq!(a.update(now).unwrap(), ConnectionState::Connecting);
+        assert_eq!(b.update(now).unwrap(), ConnectionState::Connected);
+        assert_eq!(a.update(now).unwrap(), ConnectionState::Connected);
+        (a, b)
+    }
+
+    #[test]
+    fn test_connect_and_disconnect() {
+        let now = Instant::now();
+        let (a, b) = connect(now);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        a.on_transition(move |from, to| log.lock().unwrap().push((from, to)));
+
+        let events = a.take_events(7);
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].event_id.as_ref().unwrap().as_str(),
+            PEER_CONNECTED
+        );
+        assert_eq!(events[0].tick, 7);
+        assert_eq!(
+            events[0].params.get("peer"),
+            Some(&Value::String("peer".to_string()))
+        );
+
+        a.send_reliable(b"hello").unwrap();
+        assert_eq!(b.recv().unwrap(), Some(b"hello".to_vec()));
+        assert_eq!(b.recv().unwrap(), None);
+
+        a.disconnect().unwrap();
+        assert_eq!(a.state(), ConnectionState::Disconnecting);
+        assert!(a.send_reliable(b"late").is_err());
+        assert_eq!(b.update(now).unwrap(), ConnectionState::Disconnected);
+        assert_eq!(a.update(now).unwrap(), ConnectionState::Disconnected);
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                (ConnectionState::Connected, ConnectionState::Disconnecting),
+                (
+                    ConnectionState::Disconnecting,
+                    ConnectionState::Disconnected
+                ),
+            ]
+        );
+
+        let events = b.take_events(8);
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[1].event_id.as_ref().unwrap().as_str(),
+            PEER_DISCONNECTED
+        );
+        assert_eq!(
+            events[1].params.get("reason"),
+            Some(&Value::String("closed".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_keepalive_and_timeout() {
+        let now = Instant::now();
+        let (a, b) = connect(now);
+        a.take_events(0);
+        let config = LifecycleConfig::default();
+
+        // Keepalives hold the connection open while both sides update
+        let mut t = now;
+        for _ in 0..20 {
+            t += config.keepalive_interval;
+            assert_eq!(a.update(t).unwrap(), ConnectionState::Connected);
+            assert_eq!(b.update(t).unwrap(), ConnectionState::Connected);
+        }
+        assert!(t - now > config.timeout);
+
+        // `b` goes silent, after `a` reads its last pong
+        a.update(t).unwrap();
+        t += config.timeout + Duration::from_millis(1);
+        assert_eq!(a.update(t).unwrap(), ConnectionState::TimedOut);
+        let events = a.take_events(1);
+        assert_eq!(
+            events[0].params.get("reason"),
+            Some(&Value::String("timeout".to_string()))
+        );
+
+        // A peer never reached times out without events
+        let (c, _d) = pipe();
+        let c = ManagedConnection::new(c, config);
+        assert_eq!(
+            c.update(Instant::now() + config.timeout * 2).unwrap(),
+            ConnectionState::TimedOut
+        );
+        assert!(c.take_events(0).is_empty());
+    }
+}
@@ -0,0 +1,853 @@
+//! NAT traversal: hole punching with a relay fallback
+//!
+//! Two players behind home routers can't reach each other directly: neither
+//! knows the other's public address, and each router drops packets it
+//! didn't see a request for. A small [`RendezvousServer`] on a public host
+//! fixes both:
+//!
+//! 1. Each peer's [`HolePuncher`] registers a session key (a lobby code,
+//!    say) with the server, which answers with the address it saw the
+//!    packet come from, STUN-style
+//! 2. Once both peers of a session registered, the server tells each the
+//!    other's public address
+//! 3. Both peers send punch packets to each other at once, so each router
+//!    sees outgoing traffic before the other peer's packets arrive
+//! 4. If no punch gets through in time (symmetric NATs, strict firewalls),
+//!    the peers fall back to relaying packets through the server
+//!
+//! ```text
+//!  Peer A ──register──▶ Rendezvous ◀──register── Peer B
+//!         ◀─observed,peer─        ─observed,peer─▶
+//!  Peer A ◀═════════════ punch ═════════════▶ Peer B
+//!  Peer A ──relay──▶ Rendezvous ──relay──▶ Peer B   (fallback)
+//! ```
+//!
+//! Either way the result is a [`NatConnection`], a [`Connection`] over the
+//! same UDP socket:
+//!
+//! ```rust,ignore
+//! let socket = UdpSocket::bind("0.0.0.0:0")?;
+//! let mut puncher = HolePuncher::new(socket, rendezvous_addr, lobby_code, NatConfig::default())?;
+//! loop {
+//!     match puncher.update(Instant::now())? {
+//!         NatState::Direct | NatState::Relayed => break,
+//!         NatState::Failed => return Err(...),
+//!         _ => std::thread::sleep(Duration::from_millis(10)),
+//!     }
+//! }
+//! let connection = ManagedConnection::new(puncher.into_connection()?, LifecycleConfig::default());
+//! ```
+//!
+//! Reliable sends are numbered, acknowledged by the peer and resent every
+//! [`NatConfig::resend_interval`] until they are, on either route. Resends
+//! and acknowledgements are handled by [`Connection::recv`], so keep
+//! polling it while reliable data is in flight.
+//!
+//! The server forgets peers that go quiet, so relayed connections need
+//! regular traffic; [`crate::ManagedConnection`] keepalives are enough.
+
+use crate::reliable::{Frame, ReliableLink};
+use crate::transport::{Address, Connection};
+use crate::{Error, Result};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Marks a datagram as a NAT traversal packet
+const MAGIC: &[u8; 4] = b"PLNT";
+
+/// Largest datagram received
+const MAX_DATAGRAM: usize = 65_536;
+
+const REGISTER: u8 = 0;
+const OBSERVED: u8 = 1;
+const PEER: u8 = 2;
+const PUNCH: u8 = 3;
+const PUNCH_ACK: u8 = 4;
+const RELAY: u8 = 5;
+const DATA: u8 = 6;
+
+/// A NAT traversal packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Packet<'a> {
+    /// Peer to server: join a session
+    Register { session: u64 },
+    /// Server to peer: the address the registration came from
+    Observed(SocketAddr),
+    /// Server to peer: the other peer's address
+    Peer(SocketAddr),
+    /// Peer to peer: open a hole
+    Punch { session: u64 },
+    /// Peer to peer: a punch got through
+    PunchAck { session: u64 },
+    /// Through the server: game data
+    Relay { session: u64, payload: &'a [u8] },
+    /// Peer to peer: game data
+    Data(&'a [u8]),
+}
+
+impl<'a> Packet<'a> {
+    fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(32);
+        data.extend_from_slice(MAGIC);
+        match *self {
+            Packet::Register { session } => {
+                data.push(REGISTER);
+                data.extend_from_slice(&session.to_le_bytes());
+            }
+            Packet::Observed(addr) => {
+                data.push(OBSERVED);
+                put_addr(&mut data, addr);
+            }
+            Packet::Peer(addr) => {
+                data.push(PEER);
+                put_addr(&mut data, addr);
+            }
+            Packet::Punch { session } => {
+                data.push(PUNCH);
+                data.extend_from_slice(&session.to_le_bytes());
+            }
+            Packet::PunchAck { session } => {
+                data.push(PUNCH_ACK);
+                data.extend_from_slice(&session.to_le_bytes());
+            }
+            Packet::Relay { session, payload } => {
+                data.push(RELAY);
+                data.extend_from_slice(&session.to_le_bytes());
+                data.extend_from_slice(payload);
+            }
+            Packet::Data(payload) => {
+                data.push(DATA);
+                data.extend_from_slice(payload);
+            }
+        }
+        data
+    }
+
+    fn decode(data: &'a [u8]) -> Option<Self> {
+        let rest = data.strip_prefix(MAGIC)?;
+        let (&kind, rest) = rest.split_first()?;
+        let mut reader = Reader(rest);
+        Some(match kind {
+            REGISTER => Packet::Register {
+                session: u64::from_le_bytes(reader.take()?),
+            },
+            OBSERVED => Packet::Observed(reader.addr()?),
+            PEER => Packet::Peer(reader.addr()?),
+            PUNCH => Packet::Punch {
+                session: u64::from_le_bytes(reader.take()?),
+            },
+            PUNCH_ACK => Packet::PunchAck {
+                session: u64::from_le_bytes(reader.take()?),
+            },
+            RELAY => Packet::Relay {
+                session: u64::from_le_bytes(reader.take()?),
+                payload: reader.0,
+            },
+            DATA => Packet::Data(rest),
+            _ => return None,
+        })
+    }
+}
+
+fn put_addr(data: &mut Vec<u8>, addr: SocketAddr) {
+    match addr.ip() {
+        IpAddr::V4(ip) => {
+            data.push(4);
+            data.extend_from_slice(&ip.octets());
+        }
+        IpAddr::V6(ip) => {
+            data.push(6);
+            data.extend_from_slice(&ip.octets());
+        }
+    }
+    data.extend_from_slice(&addr.port().to_le_bytes());
+}
+
+/// Reads fixed-size fields off the front of a slice
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (field, rest) = self.0.split_first_chunk::<N>()?;
+        self.0 = rest;
+        Some(*field)
+    }
+
+    fn addr(&mut self) -> Option<SocketAddr> {
+        let ip = match self.take()? {
+            [4] => IpAddr::V4(Ipv4Addr::from(self.take::<4>()?)),
+            [6] => IpAddr::V6(Ipv6Addr::from(self.take::<16>()?)),
+            _ => return None,
+        };
+        Some(SocketAddr::new(ip, u16::from_le_bytes(self.take()?)))
+    }
+}
+
+/// Receive a datagram without blocking
+fn recv_from(socket: &UdpSocket, buf: &mut [u8]) -> Result<Option<(usize, SocketAddr)>> {
+    loop {
+        match socket.recv_from(buf) {
+            Ok(received) => return Ok(Some(received)),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(None),
+            // A previous send was refused (ICMP port unreachable), which is
+            // expected while punching
+            Err(e) if e.kind() == std::io::ErrorKind::ConnectionReset => continue,
+            Err(e) => return Err(io_error(e)),
+        }
+    }
+}
+
+fn send_to(socket: &UdpSocket, packet: Packet<'_>, target: SocketAddr) -> Result<()> {
+    socket.send_to(&packet.encode(), target).map_err(io_error)?;
+    Ok(())
+}
+
+fn io_error(e: std::io::Error) -> Error {
+    Error::Transport(e.to_string())
+}
+
+/// A peer registered with the rendezvous server
+#[derive(Debug, Clone, Copy)]
+struct Member {
+    addr: SocketAddr,
+    last_seen: Instant,
+}
+
+/// Introduces peers to each other and relays for those that can't punch
+///
+/// Runs on a host both peers can reach. Each session holds two peers; later
+/// registrations for a full session only learn their public address.
+#[derive(Debug)]
+pub struct RendezvousServer {
+    socket: UdpSocket,
+    sessions: HashMap<u64, Vec<Member>>,
+    idle_timeout: Duration,
+}
+
+impl RendezvousServer {
+    /// Create a server listening on `addr`
+    pub fn bind(addr: impl ToSocketAddrs) -> Result<Self> {
+        let socket = UdpSocket::bind(addr).map_err(io_error)?;
+        socket.set_nonblocking(true).map_err(io_error)?;
+        Ok(Self {
+            socket,
+            sessions: HashMap::new(),
+            idle_timeout: Duration::from_secs(30),
+        })
+    }
+
+    /// Set how long a silent peer stays registered (default 30s)
+    pub fn with_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Get the address the server listens on
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        self.socket.local_addr().map_err(io_error)
+    }
+
+    /// Get the number of sessions with registered peers
+    pub fn session_count(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Handle every pending packet and forget idle peers
+    pub fn update(&mut self, now: Instant) -> Result<()> {
+        let mut buf = [0u8; MAX_DATAGRAM];
+        while let Some((len, from)) = recv_from(&self.socket, &mut buf)? {
+            match Packet::decode(&buf[..len]) {
+                Some(Packet::Register { session }) => self.register(session, from, now)?,
+                Some(Packet::Relay { session, payload }) => {
+                    self.relay(session, payload, from, now)?
+                }
+                _ => {}
+            }
+        }
+
+        let idle_timeout = self.idle_timeout;
+        self.sessions.retain(|_, members| {
+            members.retain(|m| now.saturating_duration_since(m.last_seen) < idle_timeout);
+            !members.is_empty()
+        });
+        Ok(())
+    }
+
+    fn register(&mut self, session: u64, from: SocketAddr, now: Instant) -> Result<()> {
+        send_to(&self.socket, Packet::Observed(from), from)?;
+        let members = self.sessions.entry(session).or_default();
+        let joined = match members.iter().position(|m| m.addr == from) {
+            Some(index) => {
+                members[index].last_seen = now;
+                false
+            }
+            None if members.len() < 2 => {
+                members.push(Member {
+                    addr: from,
+                    last_seen: now,
+                });
+                true
+            }
+            None => return Ok(()),
+        };
+        if let Some(other) = members.iter().find(|m| m.addr != from) {
+            send_to(&self.socket, Packet::Peer(other.addr), from)?;
+            // The first peer is waiting to hear about the second
+            if joined {
+                send_to(&self.socket, Packet::Peer(from), other.addr)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn relay(
+        &mut self,
+        session: u64,
+        payload: &[u8],
+        from: SocketAddr,
+        now: Instant,
+    ) -> Result<()> {
+        let Some(members) = self.sessions.get_mut(&session) else {
+            return Ok(());
+        };
+        let Some(index) = members.iter().position(|m| m.addr == from) else {
+            return Ok(());
+        };
+        members[index].last_seen = now;
+        if let Some(other) = members.iter().find(|m| m.addr != from) {
+            send_to(&self.socket, Packet::Relay { session, payload }, other.addr)?;
+        }
+        Ok(())
+    }
+}
+
+/// Timing and fallback settings for a [`HolePuncher`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NatConfig {
+    /// How often to register with the rendezvous server until it answers
+    pub register_interval: Duration,
+    /// How often to send punch packets
+    pub punch_interval: Duration,
+    /// How long to punch before giving up
+    pub punch_timeout: Duration,
+    /// Try punching a hole at all; turn off to always relay
+    pub punch: bool,
+    /// Relay through the rendezvous server when punching fails
+    pub relay: bool,
+    /// How long a connection waits for a reliable send to be acknowledged
+    /// before sending it again
+    pub resend_interval: Duration,
+}
+
+impl NatConfig {
+    /// Set the registration interval
+    pub fn with_register_interval(mut self, interval: Duration) -> Self {
+        self.register_interval = interval;
+        self
+    }
+
+    /// Set the punch interval
+    pub fn with_punch_interval(mut self, interval: Duration) -> Self {
+        self.punch_interval = interval;
+        self
+    }
+
+    /// Set how long to punch before giving up
+    pub fn with_punch_timeout(mut self, timeout: Duration) -> Self {
+        self.punch_timeout = timeout;
+        self
+    }
+
+    /// Enable or disable hole punching
+    pub fn with_punch(mut self, punch: bool) -> Self {
+        self.punch = punch;
+        self
+    }
+
+    /// Enable or disable the relay fallback
+    pub fn with_relay(mut self, relay: bool) -> Self {
+        self.relay = relay;
+        self
+    }
+
+    /// Set how long to wait for an acknowledgement before resending
+    pub fn with_resend_interval(mut self, interval: Duration) -> Self {
+        self.resend_interval = interval;
+        self
+    }
+}
+
+impl Default for NatConfig {
+    fn default() -> Self {
+        Self {
+            register_interval: Duration::from_millis(500),
+            punch_interval: Duration::from_millis(50),
+            punch_timeout: Duration::from_secs(5),
+            punch: true,
+            relay: true,
+            resend_interval: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Progress of a [`HolePuncher`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NatState {
+    /// Waiting for the rendezvous server to answer
+    Registering,
+    /// Registered, waiting for the other peer to register
+    WaitingForPeer,
+    /// Sending punch packets to the other peer
+    Punching,
+    /// A punch got through; packets go straight to the peer
+    Direct,
+    /// Punching failed or was disabled; packets go through the server
+    Relayed,
+    /// Punching failed and relaying is disabled
+    Failed,
+}
+
+impl NatState {
+    /// Check if the peers can exchange packets
+    pub fn is_ready(&self) -> bool {
+        matches!(self, Self::Direct | Self::Relayed)
+    }
+}
+
+/// Finds a route to a peer through NATs, on the socket the game will use
+#[derive(Debug)]
+pub struct HolePuncher {
+    socket: UdpSocket,
+    rendezvous: SocketAddr,
+    session: u64,
+    config: NatConfig,
+    state: NatState,
+    public_addr: Option<SocketAddr>,
+    peer: Option<SocketAddr>,
+    last_sent: Option<Instant>,
+    punch_started: Option<Instant>,
+}
+
+impl HolePuncher {
+    /// Create a puncher meeting the peer registering the same `session` at
+    /// the rendezvous server
+    ///
+    /// The socket is switched to non-blocking. The hole is only open for
+    /// this socket, so keep using it for the game.
+    pub fn new(
+        socket: UdpSocket,
+        rendezvous: SocketAddr,
+        session: u64,
+        config: NatConfig,
+    ) -> Result<Self> {
+        socket.set_nonblocking(true).map_err(io_error)?;
+        Ok(Self {
+            socket,
+            rendezvous,
+            session,
+            config,
+            state: NatState::Registering,
+            public_addr: None,
+            peer: None,
+            last_sent: None,
+            punch_started: None,
+        })
+    }
+
+    /// Get the config
+    pub fn config(&self) -> &NatConfig {
+        &self.config
+    }
+
+    /// Get the current state
+    pub fn state(&self) -> NatState {
+        self.state
+    }
+
+    /// Get this peer's address as the rendezvous server sees it
+    pub fn public_addr(&self) -> Option<SocketAddr> {
+        self.public_addr
+    }
+
+    /// Get the other peer's address, once known
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer
+    }
+
+    /// Handle received packets and send whatever is due
+    ///
+    /// Call regularly until the state [`is_ready`](NatState::is_ready) or
+    /// [`Failed`](NatState::Failed).
+    pub fn update(&mut self, now: Instant) -> Result<NatState> {
+        let mut buf = [0u8; MAX_DATAGRAM];
+        while let Some((len, from)) = recv_from(&self.socket, &mut buf)? {
+            if let Some(packet) = Packet::decode(&buf[..len]) {
+                self.handle(packet, from, now)?;
+            }
+        }
+
+        match self.state {
+            NatState::Registering | NatState::WaitingForPeer => {
+                if self.due(now, self.config.register_interval) {
+                    let register = Packet::Register {
+                        session: self.session,
+                    };
+                    send_to(&self.socket, register, self.rendezvous)?;
+                    self.last_sent = Some(now);
+                }
+            }
+            NatState::Punching => {
+                let started = self.punch_started.unwrap_or(now);
+                if now.saturating_duration_since(started) >= self.config.punch_timeout {
+                    self.state = if self.config.relay {
+                        NatState::Relayed
+                    } else {
+                        NatState::Failed
+                    };
+                } else if let Some(peer) = self.peer {
+                    if self.due(now, self.config.punch_interval) {
+                        let punch = Packet::Punch {
+                            session: self.session,
+                        };
+                        send_to(&self.socket, punch, peer)?;
+                        self.last_sent = Some(now);
+                    }
+                }
+            }
+            NatState::Direct | NatState::Relayed | NatState::Failed => {}
+        }
+        Ok(self.state)
+    }
+
+    fn due(&self, now: Instant, interval: Duration) -> bool {
+        self.last_sent
+            .is_none_or(|sent| now.saturating_duration_since(sent) >= interval)
+    }
+
+    fn handle(&mut self, packet: Packet<'_>, from: SocketAddr, now: Instant) -> Result<()> {
+        let waiting = matches!(self.state, NatState::Registering | NatState::WaitingForPeer);
+        match packet {
+            Packet::Observed(addr) if from == self.rendezvous => {
+                self.public_addr = Some(addr);
+                if self.state == NatState::Registering {
+                    self.state = NatState::WaitingForPeer;
+                }
+            }
+            Packet::Peer(addr) if from == self.rendezvous && waiting => {
+                self.peer = Some(addr);
+                if self.config.punch {
+                    self.state = NatState::Punching;
+                    self.punch_started = Some(now);
+                    self.last_sent = None;
+                } else {
+                    self.state = if self.config.relay {
+                        NatState::Relayed
+                    } else {
+                        NatState::Failed
+                    };
+                }
+            }
+            Packet::Punch { session } if session == self.session => {
+                // Answer even once done, in case our ack was lost
+                let ack = Packet::PunchAck {
+                    session: self.session,
+                };
+                send_to(&self.socket, ack, from)?;
+                // The peer's punch can beat the server's introduction
+                if self.config.punch && (waiting || self.state == NatState::Punching) {
+                    self.connect_direct(from);
+                }
+            }
+            Packet::PunchAck { session }
+                if session == self.session && self.state == NatState::Punching =>
+            {
+                self.connect_direct(from);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Use the address the punch came from, which can differ from the one
+    /// the server saw
+    fn connect_direct(&mut self, from: SocketAddr) {
+        self.peer = Some(from);
+        self.state = NatState::Direct;
+    }
+
+    /// Turn a ready puncher into a connection to the peer
+    pub fn into_connection(self) -> Result<NatConnection> {
+        let (Some(peer), true) = (self.peer, self.state.is_ready()) else {
+            return Err(Error::Transport(format!(
+                "no route to the peer yet ({:?})",
+                self.state
+            )));
+        };
+        Ok(NatConnection {
+            socket: self.socket,
+            peer,
+            rendezvous: self.rendezvous,
+            session: self.session,
+            relayed: self.state == NatState::Relayed,
+            open: AtomicBool::new(true),
+            link: Mutex::new(ReliableLink::new(self.config.resend_interval)),
+        })
+    }
+}
+
+/// A connection to a peer found by a [`HolePuncher`]
+///
+/// Sends straight to the peer or through the rendezvous server, and accepts
+/// both, since the peers may not agree on whether the punch worked.
+/// Reliable sends are resent until acknowledged, from
+/// [`recv`](Connection::recv); see the [module docs](self).
+#[derive(Debug)]
+pub struct NatConnection {
+    socket: UdpSocket,
+    peer: SocketAddr,
+    rendezvous: SocketAddr,
+    session: u64,
+    relayed: bool,
+    open: AtomicBool,
+    link: Mutex<ReliableLink>,
+}
+
+impl NatConnection {
+    /// Check if packets go through the rendezvous server
+    pub fn is_relayed(&self) -> bool {
+        self.relayed
+    }
+
+    /// Get the peer's address
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer
+    }
+
+    /// Get the number of reliable sends the peer has not acknowledged yet
+    pub fn unacked(&self) -> usize {
+        self.link.lock().unwrap().unacked()
+    }
+
+    /// Send a frame on the route to the peer
+    fn send_frame(&self, frame: &[u8]) -> Result<()> {
+        if self.relayed {
+            let relay = Packet::Relay {
+                session: self.session,
+                payload: frame,
+            };
+            send_to(&self.socket, relay, self.rendezvous)
+        } else {
+            send_to(&self.socket, Packet::Data(frame), self.peer)
+        }
+    }
+
+    /// Send the reliable frames that are due
+    fn flush(&self, link: &mut ReliableLink) -> Result<()> {
+        for frame in link.due(Instant::now()) {
+            self.send_frame(&frame)?;
+        }
+        Ok(())
+    }
+}
+
+impl Connection for NatConnection {
+    type Error = Error;
+
+    fn send_reliable(&self, data: &[u8]) -> Result<()> {
+        if !self.is_connected() {
+            return Err(Error::Transport("connection closed".to_string()));
+        }
+        let mut link = self.link.lock().unwrap();
+        link.push(data);
+        self.flush(&mut link)
+    }
+
+    fn send_unreliable(&self, data: &[u8]) -> Result<()> {
+        if !self.is_connected() {
+            return Err(Error::Transport("connection closed".to_string()));
+        }
+        self.send_frame(&Frame::Unreliable(data).encode())
+    }
+
+    fn recv(&self) -> Result<Option<Vec<u8>>> {
+        let mut link = self.link.lock().unwrap();
+        self.flush(&mut link)?;
+        if let Some(data) = link.pop() {
+            return Ok(Some(data));
+        }
+
+        let mut buf = [0u8; MAX_DATAGRAM];
+        while let Some((len, from)) = recv_from(&self.socket, &mut buf)? {
+            let frame = match Packet::decode(&buf[..len]) {
+                Some(Packet::Data(frame)) if from == self.peer => frame,
+                Some(Packet::Relay { session, payload })
+                    if from == self.rendezvous && session == self.session =>
+                {
+                    payload
+                }
+                Some(Packet::Punch { session }) if session == self.session => {
+                    let ack = Packet::PunchAck {
+                        session: self.session,
+                    };
+                    send_to(&self.socket, ack, from)?;
+                    continue;
+                }
+                _ => continue,
+            };
+            match Frame::decode(frame) {
+                Some(Frame::Unreliable(data)) => return Ok(Some(data.to_vec())),
+                Some(Frame::Reliable { seq, payload }) => {
+                    if link.received(seq, payload) {
+                        self.send_frame(&Frame::Ack { seq }.encode())?;
+                    }
+                    if let Some(data) = link.pop() {
+                        return Ok(Some(data));
+                    }
+                }
+                Some(Frame::Ack { seq }) => {
+                    // Acknowledgements can make room for queued sends
+                    link.acked(seq);
+                    self.flush(&mut link)?;
+                }
+                None => {}
+            }
+        }
+        Ok(None)
+    }
+
+    fn is_connected(&self) -> bool {
+        self.open.load(Ordering::Relaxed)
+    }
+
+    fn remote_addr(&self) -> Option<Address> {
+        Some(Address::Socket(self.peer))
+    }
+
+    fn close(&self) -> Result<()> {
+        self.open.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn puncher(rendezvous: SocketAddr, config: NatConfig) -> HolePuncher {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        HolePuncher::new(socket, rendezvous, 42, config).unwrap()
+    }
+
+    /// Pump everything until both peers are done
+    fn connect(config: NatConfig) -> (NatConnection, NatConnection, RendezvousServer) {
+        let mut server = RendezvousServer::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let mut a = puncher(addr, config);
+        let mut b = puncher(addr, config);
+        for _ in 0..500 {
+            let now = Instant::now();
+            let a_state = a.update(now).unwrap();
+            server.update(now).unwrap();
+            let b_state = b.update(now).unwrap();
+            server.update(now).unwrap();
+            if a_state.is_ready() && b_state.is_ready() {
+                assert_eq!(a.public_addr(), Some(a.socket.local_addr().unwrap()));
+                return (
+                    a.into_connection().unwrap(),
+                    b.into_connection().unwrap(),
+                    server,
+                );
+            }
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        panic!("peers never connected: {:?} {:?}", a.state(), b.state());
+    }
+
+    fn exchange(from: &NatConnection, to: &NatConnection, server: &mut RendezvousServer) {
+        from.send_unreliable(b"hello").unwrap();
+        for _ in 0..500 {
+            server.update(Instant::now()).unwrap();
+            if let Some(data) = to.recv().unwrap() {
+                assert_eq!(data, b"hello");
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        panic!("packet never arrived");
+    }
+
+    /// Send reliable messages and pump both sides until all are delivered
+    /// and acknowledged
+    fn exchange_reliable(from: &NatConnection, to: &NatConnection, server: &mut RendezvousServer) {
+        let messages: Vec<Vec<u8>> = (0..20u32).map(|i| i.to_le_bytes().to_vec()).collect();
+        for message in &messages {
+            from.send_reliable(message).unwrap();
+        }
+        let mut received = Vec::new();
+        for _ in 0..500 {
+            server.update(Instant::now()).unwrap();
+            received.extend(std::iter::from_fn(|| to.recv().unwrap()));
+            server.update(Instant::now()).unwrap();
+            assert_eq!(from.recv().unwrap(), None);
+            if received.len() == messages.len() && from.unacked() == 0 {
+                assert_eq!(received, messages);
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        panic!(
+            "{} of {} reliable messages arrived, {} unacknowledged",
+            received.len(),
+            messages.len(),
+            from.unacked()
+        );
+    }
+
+    #[test]
+    fn test_packet_roundtrip() {
+        let v6: SocketAddr = "[::1]:7777".parse().unwrap();
+        let packets = [
+            Packet::Register { session: 7 },
+            Packet::Observed("1.2.3.4:5".parse().unwrap()),
+            Packet::Peer(v6),
+            Packet::Relay {
+                session: 7,
+                payload: b"abc",
+            },
+            Packet::Data(b""),
+        ];
+        for packet in packets {
+            assert_eq!(Packet::decode(&packet.encode()), Some(packet));
+        }
+        assert_eq!(Packet::decode(b"PLNT\x02\x05"), None);
+    }
+
+    #[test]
+    fn test_punch_on_loopback() {
+        let (a, b, mut server) = connect(NatConfig::default());
+        assert!(!a.is_relayed() && !b.is_relayed());
+        exchange(&a, &b, &mut server);
+        exchange(&b, &a, &mut server);
+        exchange_reliable(&a, &b, &mut server);
+        exchange_reliable(&b, &a, &mut server);
+    }
+
+    #[test]
+    fn test_relay_fallback() {
+        let (a, b, mut server) = connect(NatConfig::default().with_punch(false));
+        assert!(a.is_relayed() && b.is_relayed());
+        exchange(&a, &b, &mut server);
+        exchange(&b, &a, &mut server);
+        exchange_reliable(&a, &b, &mut server);
+        exchange_reliable(&b, &a, &mut server);
+
+        // Relaying keeps the session alive; silence ends it
+        server
+            .update(Instant::now() + Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(server.session_count(), 0);
+    }
+}
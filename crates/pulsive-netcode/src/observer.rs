@@ -0,0 +1,306 @@
+//! Spectator/observer sessions
+//!
+//! An observer receives authoritative state from the server but never sends
+//! inputs, so it needs no input buffer and no prediction. It simply buffers
+//! incoming states and renders an interpolated view, optionally delayed by a
+//! fixed number of ticks.
+//!
+//! The delay serves two purposes:
+//! - It keeps enough states buffered to always interpolate (no ghosting)
+//! - It prevents spectators from relaying live information to players
+//!
+//! Typical uses are replay casting, admin dashboards, and kill-cams.
+
+use crate::{InterpolationPolicies, Interpolator};
+use pulsive_core::{Model, StateHistory};
+
+/// Configuration for an observer session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObserverConfig {
+    /// How many ticks behind the newest received state the view is rendered
+    ///
+    /// A delay of 0 renders the newest state as soon as it arrives.
+    pub delay_ticks: u64,
+}
+
+impl ObserverConfig {
+    /// Create a config with the given view delay
+    pub fn with_delay(delay_ticks: u64) -> Self {
+        Self { delay_ticks }
+    }
+}
+
+impl Default for ObserverConfig {
+    fn default() -> Self {
+        Self { delay_ticks: 2 }
+    }
+}
+
+/// Read-only session for spectators and observers
+///
+/// Receives authoritative states and produces an interpolated view at
+/// `newest_tick - delay_ticks`. Unlike [`crate::PredictionEngine`], it has no
+/// input buffer and never predicts or reconciles.
+///
+/// Generic over `H: StateHistory` to allow different storage backends.
+pub struct ObserverSession<H: StateHistory> {
+    /// Received authoritative states
+    history: H,
+    /// Session configuration
+    config: ObserverConfig,
+    /// Newest tick received from the server
+    newest_tick: Option<u64>,
+    /// How each property of the view is interpolated
+    policies: InterpolationPolicies,
+}
+
+impl<H: StateHistory> ObserverSession<H> {
+    /// Create a new observer session with the default config
+    pub fn new(history: H) -> Self {
+        Self::with_config(history, ObserverConfig::default())
+    }
+
+    /// Create a new observer session with a custom config
+    pub fn with_config(history: H, config: ObserverConfig) -> Self {
+        Self {
+            history,
+            config,
+            newest_tick: None,
+            policies: InterpolationPolicies::new(),
+        }
+    }
+
+    /// Set how the view interpolates each property
+    pub fn set_policies(&mut self, policies: InterpolationPolicies) {
+        self.policies = policies;
+    }
+
+    /// Get the interpolation policies
+    pub fn policies(&self) -> &InterpolationPolicies {
+        &self.policies
+    }
+
+    /// Receive an authoritative state from the server
+    ///
+    /// States may arrive out of order; they are stored by tick. States older
+    /// than what the view still needs are discarded from the history.
+    pub fn push_state(&mut self, tick: u64, model: &Model) {
+        self.history.save_state(tick, model);
+        self.newest_tick = Some(self.newest_tick.map_or(tick, |t| t.max(tick)));
+
+        // Keep the nearest state at or before the view tick for interpolation
+        if let Some(view_tick) = self.view_tick() {
+            if let Some((keep_from, _)) = self.history.get_nearest_before(view_tick) {
+                self.history.clear_before(keep_from);
+            }
+        }
+    }
+
+    /// Get the tick currently being rendered
+    ///
+    /// Returns `None` until the first state has been received.
+    pub fn view_tick(&self) -> Option<u64> {
+        self.newest_tick
+            .map(|t| t.saturating_sub(self.config.delay_ticks))
+    }
+
+    /// Get the interpolated view state
+    ///
+    /// `sub_tick_fraction` is the progress between `view_tick` and the next
+    /// tick (0.0 to 1.0), typically the render accumulator of the client loop.
+    pub fn view(&self, sub_tick_fraction: f32) -> Option<Model> {
+        let view_tick = self.view_tick()?;
+        let fraction = sub_tick_fraction.clamp(0.0, 1.0);
+
+        let Some((before_tick, before)) = self.history.get_nearest_before(view_tick) else {
+            // Nothing at or before the view tick yet: show the oldest state we have
+            return self
+                .history
+                .get_nearest_after(view_tick)
+                .map(|(_, model)| model.clone());
+        };
+
+        match self.history.get_nearest_after(view_tick + 1) {
+            Some((after_tick, after)) => {
+                let range = (after_tick - before_tick) as f32;
+                let offset = (view_tick - before_tick) as f32 + fraction;
+                let alpha = (offset / range).clamp(0.0, 1.0);
+                Some(Interpolator::interpolate_models(
+                    before,
+                    after,
+                    alpha,
+                    &self.policies,
+                ))
+            }
+            None => Some(before.clone()),
+        }
+    }
+
+    /// Check whether enough states are buffered to honour the configured delay
+    ///
+    /// Returns `false` while the session is still filling its buffer.
+    pub fn is_buffered(&self) -> bool {
+        match (self.history.tick_range(), self.newest_tick) {
+            (Some((oldest, _)), Some(newest)) => newest - oldest >= self.config.delay_ticks,
+            _ => false,
+        }
+    }
+
+    /// Get the newest tick received from the server
+    pub fn newest_tick(&self) -> Option<u64> {
+        self.newest_tick
+    }
+
+    /// Get the configured view delay in ticks
+    pub fn delay_ticks(&self) -> u64 {
+        self.config.delay_ticks
+    }
+
+    /// Change the view delay
+    ///
+    /// Increasing the delay only takes effect once enough older states are
+    /// still available; states already discarded cannot be recovered.
+    pub fn set_delay_ticks(&mut self, delay_ticks: u64) {
+        self.config.delay_ticks = delay_ticks;
+    }
+
+    /// Get the session configuration
+    pub fn config(&self) -> &ObserverConfig {
+        &self.config
+    }
+
+    /// Get access to the state history
+    pub fn history(&self) -> &H {
+        &self.history
+    }
+
+    /// Reset the session
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.newest_tick = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Simple in-memory history for testing
+    struct TestHistory {
+        states: Vec<(u64, Model)>,
+    }
+
+    impl TestHistory {
+        fn new() -> Self {
+            Self { states: Vec::new() }
+        }
+    }
+
+    impl StateHistory for TestHistory {
+        fn save_state(&mut self, tick: u64, model: &Model) {
+            self.states.retain(|(t, _)| *t != tick);
+            self.states.push((tick, model.clone()));
+            self.states.sort_by_key(|(t, _)| *t);
+        }
+
+        fn get_state(&self, tick: u64) -> Option<&Model> {
+            self.states.iter().find(|(t, _)| *t == tick).map(|(_, m)| m)
+        }
+
+        fn get_nearest_before(&self, tick: u64) -> Option<(u64, &Model)> {
+            self.states
+                .iter()
+                .filter(|(t, _)| *t <= tick)
+                .max_by_key(|(t, _)| *t)
+                .map(|(t, m)| (*t, m))
+        }
+
+        fn get_nearest_after(&self, tick: u64) -> Option<(u64, &Model)> {
+            self.states
+                .iter()
+                .filter(|(t, _)| *t >= tick)
+                .min_by_key(|(t, _)| *t)
+                .map(|(t, m)| (*t, m))
+        }
+
+        fn clear_before(&mut self, tick: u64) {
+            self.states.retain(|(t, _)| *t >= tick);
+        }
+
+        fn clear(&mut self) {
+            self.states.clear();
+        }
+
+        fn capacity(&self) -> Option<usize> {
+            None
+        }
+
+        fn len(&self) -> usize {
+            self.states.len()
+        }
+
+        fn tick_range(&self) -> Option<(u64, u64)> {
+            let min = self.states.first().map(|(t, _)| *t)?;
+            let max = self.states.last().map(|(t, _)| *t)?;
+            Some((min, max))
+        }
+    }
+
+    fn model_with_value(value: f64) -> Model {
+        let mut model = Model::new();
+        model.set_global("value", value);
+        model
+    }
+
+    #[test]
+    fn test_delayed_view() {
+        let mut session =
+            ObserverSession::with_config(TestHistory::new(), ObserverConfig::with_delay(2));
+        assert!(session.view(0.0).is_none());
+
+        for tick in 0..5 {
+            session.push_state(tick, &model_with_value(tick as f64 * 10.0));
+        }
+
+        assert_eq!(session.newest_tick(), Some(4));
+        assert_eq!(session.view_tick(), Some(2));
+        assert!(session.is_buffered());
+
+        let view = session.view(0.5).unwrap();
+        assert_eq!(
+            view.get_global("value").and_then(|v| v.as_float()),
+            Some(25.0)
+        );
+    }
+
+    #[test]
+    fn test_old_states_discarded() {
+        let mut session =
+            ObserverSession::with_config(TestHistory::new(), ObserverConfig::with_delay(1));
+
+        for tick in 0..10 {
+            session.push_state(tick, &model_with_value(tick as f64));
+        }
+
+        // Only the view tick and newer are kept
+        assert_eq!(session.history().tick_range(), Some((8, 9)));
+    }
+
+    #[test]
+    fn test_not_buffered_until_delay_filled() {
+        let mut session =
+            ObserverSession::with_config(TestHistory::new(), ObserverConfig::with_delay(3));
+        session.push_state(0, &model_with_value(0.0));
+        assert!(!session.is_buffered());
+
+        // Still renders the only available state
+        let view = session.view(0.0).unwrap();
+        assert_eq!(
+            view.get_global("value").and_then(|v| v.as_float()),
+            Some(0.0)
+        );
+
+        session.push_state(3, &model_with_value(3.0));
+        assert!(session.is_buffered());
+    }
+}
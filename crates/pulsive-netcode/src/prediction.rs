@@ -1,10 +1,13 @@
 //! Client-side prediction engine
 //!
 //! Applies inputs locally before server confirmation for responsive gameplay.
-//! Works with any StateHistory implementation for state storage.
+//! Works with any StateHistory implementation for state storage. A
+//! [`PredictionScope`] limits prediction to part of the model.
 
-use crate::{InputBuffer, InputEntry, Result};
+use crate::stats::misprediction;
+use crate::{ConnectionStats, InputBuffer, InputEntry, PredictionScope, Result};
 use pulsive_core::{Model, Msg, Runtime, StateHistory};
+use std::time::Instant;
 
 /// Client-side prediction engine
 ///
@@ -22,6 +25,10 @@ pub struct PredictionEngine<H: StateHistory> {
     last_server_tick: u64,
     /// Current predicted tick (may be ahead of server)
     predicted_tick: u64,
+    /// Network and prediction metrics
+    stats: ConnectionStats,
+    /// Entities predicted locally (None predicts the whole model)
+    scope: Option<PredictionScope>,
 }
 
 impl<H: StateHistory> PredictionEngine<H> {
@@ -32,6 +39,8 @@ impl<H: StateHistory> PredictionEngine<H> {
             input_buffer: InputBuffer::new(256), // Default capacity
             last_server_tick: 0,
             predicted_tick: 0,
+            stats: ConnectionStats::new(),
+            scope: None,
         }
     }
 
@@ -42,6 +51,8 @@ impl<H: StateHistory> PredictionEngine<H> {
             input_buffer: InputBuffer::new(capacity),
             last_server_tick: 0,
             predicted_tick: 0,
+            stats: ConnectionStats::new(),
+            scope: None,
         }
     }
 
@@ -51,7 +62,7 @@ impl<H: StateHistory> PredictionEngine<H> {
     /// for potential replay during reconciliation.
     pub fn predict(&mut self, model: &mut Model, runtime: &mut Runtime, input: Msg) -> Result<()> {
         // Save current state before prediction
-        self.history.save_state(self.predicted_tick, model);
+        self.save_state(self.predicted_tick, model);
 
         // Buffer the input for reconciliation
         self.input_buffer.push(self.predicted_tick, input.clone())?;
@@ -72,7 +83,7 @@ impl<H: StateHistory> PredictionEngine<H> {
     /// hasn't provided input this frame.
     pub fn advance(&mut self, model: &mut Model, runtime: &mut Runtime) {
         // Save state
-        self.history.save_state(self.predicted_tick, model);
+        self.save_state(self.predicted_tick, model);
 
         // Run one tick
         runtime.tick(model);
@@ -99,7 +110,7 @@ impl<H: StateHistory> PredictionEngine<H> {
         // Check if we need to reconcile
         if server_tick >= self.predicted_tick {
             // Server is ahead or equal, just use server state
-            *model = server_state.clone();
+            model.restore(server_state.clone());
             self.predicted_tick = server_tick;
             return Ok(false);
         }
@@ -108,14 +119,20 @@ impl<H: StateHistory> PredictionEngine<H> {
         let our_state = self.history.get_state(server_tick);
 
         // Compare states (simple comparison - can be made more sophisticated)
-        let needs_reconcile = match our_state {
-            Some(our) => !Self::states_match(our, server_state),
-            None => true, // No state means we need to reconcile
+        let mismatch = match (our_state, &self.scope) {
+            (Some(our), Some(scope)) => scope.compare(our, server_state),
+            (Some(our), None) => {
+                (!Self::states_match(our, server_state)).then(|| misprediction(our, server_state))
+            }
+            (None, _) => Some(0.0), // No state means we need to reconcile
         };
+        let needs_reconcile = mismatch.is_some();
+
+        if let Some(magnitude) = mismatch {
+            self.stats.record_correction(Instant::now(), magnitude);
 
-        if needs_reconcile {
             // Rollback to server state
-            *model = server_state.clone();
+            model.restore(server_state.clone());
 
             // Clear history up to server tick
             self.history.clear_before(server_tick);
@@ -128,7 +145,7 @@ impl<H: StateHistory> PredictionEngine<H> {
                 .collect();
 
             for input in inputs_to_replay {
-                self.history.save_state(input.tick, model);
+                self.save_state(input.tick, model);
                 runtime.send(input.msg);
                 runtime.process_queue(model);
             }
@@ -141,9 +158,22 @@ impl<H: StateHistory> PredictionEngine<H> {
                 .unwrap_or(server_tick);
         }
 
+        // Outside the scope, the server state wins even over replayed inputs
+        if let Some(scope) = &self.scope {
+            scope.merge_unscoped(model, server_state);
+        }
+
         Ok(needs_reconcile)
     }
 
+    /// Save a state to the history, only the scoped part if there's a scope
+    fn save_state(&mut self, tick: u64, model: &Model) {
+        match &self.scope {
+            Some(scope) => self.history.save_state(tick, &scope.snapshot(model)),
+            None => self.history.save_state(tick, model),
+        }
+    }
+
     /// Compare two states for equality
     ///
     /// This is a simple comparison. For production use, you may want
@@ -164,6 +194,24 @@ impl<H: StateHistory> PredictionEngine<H> {
         true
     }
 
+    /// Predict only the entities in a scope
+    ///
+    /// Takes effect for states saved from now on; call it before
+    /// predicting, or after a [`reset`](Self::reset).
+    pub fn set_scope(&mut self, scope: PredictionScope) {
+        self.scope = Some(scope);
+    }
+
+    /// Predict the whole model again
+    pub fn clear_scope(&mut self) {
+        self.scope = None;
+    }
+
+    /// Get the prediction scope
+    pub fn scope(&self) -> Option<&PredictionScope> {
+        self.scope.as_ref()
+    }
+
     /// Get the current predicted tick
     pub fn predicted_tick(&self) -> u64 {
         self.predicted_tick
@@ -194,12 +242,27 @@ impl<H: StateHistory> PredictionEngine<H> {
         &mut self.history
     }
 
+    /// Get the connection's network and prediction metrics
+    ///
+    /// Corrections are recorded by [`reconcile`](Self::reconcile); packets
+    /// and RTT samples are up to the transport code, through
+    /// [`stats_mut`](Self::stats_mut).
+    pub fn stats(&self) -> &ConnectionStats {
+        &self.stats
+    }
+
+    /// Get mutable access to the metrics, to record packets and RTT samples
+    pub fn stats_mut(&mut self) -> &mut ConnectionStats {
+        &mut self.stats
+    }
+
     /// Reset the prediction engine
     pub fn reset(&mut self) {
         self.history.clear();
         self.input_buffer.clear();
         self.last_server_tick = 0;
         self.predicted_tick = 0;
+        self.stats.reset();
     }
 }
 
@@ -306,4 +369,72 @@ mod tests {
         assert!(!reconciled);
         assert_eq!(engine.predicted_tick(), 5);
     }
+
+    #[test]
+    fn test_reconcile_records_stats() {
+        let history = TestHistory::new();
+        let mut engine = PredictionEngine::new(history);
+        let mut model = Model::new();
+        let mut runtime = Runtime::new();
+
+        for _ in 0..3 {
+            engine.advance(&mut model, &mut runtime);
+        }
+
+        // The server disagrees about tick 1
+        let mut server_state = engine.history().get_state(1).unwrap().clone();
+        server_state.set_global("gold", 2.0);
+        let reconciled = engine
+            .reconcile(&mut model, &mut runtime, &server_state, 1)
+            .unwrap();
+
+        assert!(reconciled);
+        assert_eq!(engine.stats().corrections(), 1);
+        assert_eq!(engine.stats().misprediction(), 1.0);
+    }
+
+    #[test]
+    fn test_scoped_prediction() {
+        let history = TestHistory::new();
+        let mut engine = PredictionEngine::new(history);
+        engine.set_scope(PredictionScope::new().with_kind("player"));
+        let mut model = Model::new();
+        let mut runtime = Runtime::new();
+        let player = model.entities_mut().create("player").id;
+        let npc = model.entities_mut().create("npc").id;
+        model.entities_mut().get_mut(player).unwrap().set("x", 0.0);
+
+        for _ in 0..3 {
+            engine.advance(&mut model, &mut runtime);
+        }
+        // Only the player is saved
+        assert_eq!(engine.history().get_state(1).unwrap().entities().len(), 1);
+
+        // The server moved the NPC: no correction, but the NPC follows it
+        let mut server_state = model.clone();
+        server_state
+            .entities_mut()
+            .get_mut(npc)
+            .unwrap()
+            .set("x", 7.0);
+        let reconciled = engine
+            .reconcile(&mut model, &mut runtime, &server_state, 1)
+            .unwrap();
+        assert!(!reconciled);
+        let x = |model: &Model, id| model.entities().get(id).unwrap().get_number("x");
+        assert_eq!(x(&model, npc), Some(7.0));
+
+        // The server moved the player: a correction
+        server_state
+            .entities_mut()
+            .get_mut(player)
+            .unwrap()
+            .set("x", 2.0);
+        let reconciled = engine
+            .reconcile(&mut model, &mut runtime, &server_state, 1)
+            .unwrap();
+        assert!(reconciled);
+        assert_eq!(x(&model, player), Some(2.0));
+        assert_eq!(engine.stats().misprediction(), 2.0);
+    }
 }
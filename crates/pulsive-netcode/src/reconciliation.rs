@@ -1,9 +1,104 @@
 //! Server state reconciliation
 //!
 //! Handles correcting client state when server authoritative state arrives.
+//!
+//! Small differences, like float drift in positions, need not trigger a
+//! correction: [`Tolerances`] sets how far each property may be off. With
+//! [`Reconciler::correct_selective`], only the entities that differ beyond
+//! their tolerance are corrected; the rest keep their predicted values, so
+//! a correction doesn't make the whole scene jitter.
 
 use crate::Result;
-use pulsive_core::{Model, Msg, Runtime, StateHistory};
+use pulsive_core::{EntityId, Model, Msg, Runtime, StateHistory, Value, ValueMap};
+use std::collections::HashMap;
+
+/// Tolerance for properties without one of their own
+const DEFAULT_TOLERANCE: f64 = 1e-6;
+
+/// How far predicted numbers may be from the server's before they count as
+/// mispredicted
+///
+/// Tolerances apply to entity properties and globals by key; values that
+/// aren't numbers must match exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tolerances {
+    /// Tolerance for keys without one of their own
+    default: f64,
+    /// Tolerance by key
+    properties: HashMap<String, f64>,
+}
+
+impl Default for Tolerances {
+    fn default() -> Self {
+        Self {
+            default: DEFAULT_TOLERANCE,
+            properties: HashMap::new(),
+        }
+    }
+}
+
+impl Tolerances {
+    /// Create tolerances that only allow float rounding differences
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the tolerance for keys without one of their own
+    pub fn with_default(mut self, tolerance: f64) -> Self {
+        self.default = tolerance;
+        self
+    }
+
+    /// Set the tolerance for a key
+    pub fn with_property(mut self, key: impl Into<String>, tolerance: f64) -> Self {
+        self.set_property(key, tolerance);
+        self
+    }
+
+    /// Set the tolerance for a key
+    pub fn set_property(&mut self, key: impl Into<String>, tolerance: f64) {
+        self.properties.insert(key.into(), tolerance);
+    }
+
+    /// Get the tolerance for a key
+    pub fn get(&self, key: &str) -> f64 {
+        self.properties.get(key).copied().unwrap_or(self.default)
+    }
+
+    /// Check if two values of a key are within its tolerance
+    pub fn values_within(&self, key: &str, a: &Value, b: &Value) -> bool {
+        match (a.as_float(), b.as_float()) {
+            (Some(x), Some(y)) => (x - y).abs() <= self.get(key),
+            _ => a == b,
+        }
+    }
+
+    /// Check if two maps have the same keys, with values within tolerance
+    pub fn maps_within(&self, a: &ValueMap, b: &ValueMap) -> bool {
+        a.len() == b.len()
+            && a.iter().all(|(key, value)| {
+                b.get(key)
+                    .is_some_and(|other| self.values_within(key.as_str(), value, other))
+            })
+    }
+}
+
+/// What a selective correction corrected
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Correction {
+    /// Entities that differed from the server beyond tolerance, including
+    /// ones only one side has, in ID order
+    pub entities: Vec<EntityId>,
+    /// Whether the globals differed
+    pub globals: bool,
+}
+
+impl Correction {
+    /// Check if nothing needed correcting
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty() && !self.globals
+    }
+}
 
 /// Reconciler for applying server corrections
 ///
@@ -14,6 +109,8 @@ pub struct Reconciler<H: StateHistory> {
     history: H,
     /// Last confirmed server tick
     last_server_tick: u64,
+    /// How far predictions may be off before they are corrected
+    tolerances: Tolerances,
 }
 
 impl<H: StateHistory> Reconciler<H> {
@@ -22,6 +119,7 @@ impl<H: StateHistory> Reconciler<H> {
         Self {
             history,
             last_server_tick: 0,
+            tolerances: Tolerances::default(),
         }
     }
 
@@ -30,7 +128,7 @@ impl<H: StateHistory> Reconciler<H> {
     /// Replaces the local state with the server state and clears
     /// history before the server tick.
     pub fn apply_correction(&mut self, model: &mut Model, server_state: &Model, server_tick: u64) {
-        *model = server_state.clone();
+        model.restore(server_state.clone());
         self.history.clear_before(server_tick);
         self.last_server_tick = server_tick;
     }
@@ -42,13 +140,13 @@ impl<H: StateHistory> Reconciler<H> {
     pub fn rollback(&self, model: &mut Model, target_tick: u64) -> Result<u64> {
         // Try exact tick first
         if let Some(state) = self.history.get_state(target_tick) {
-            *model = state.clone();
+            model.restore(state.clone());
             return Ok(target_tick);
         }
 
         // Fall back to nearest before
         if let Some((actual_tick, state)) = self.history.get_nearest_before(target_tick) {
-            *model = state.clone();
+            model.restore(state.clone());
             return Ok(actual_tick);
         }
 
@@ -77,6 +175,112 @@ impl<H: StateHistory> Reconciler<H> {
         Ok(())
     }
 
+    /// Find what differs between a predicted state and the server's,
+    /// beyond tolerance
+    pub fn diverged(&self, predicted: &Model, server_state: &Model) -> Correction {
+        let mut entities: Vec<EntityId> = server_state
+            .entities()
+            .iter()
+            .filter(|theirs| match predicted.entities().get(theirs.id) {
+                Some(ours) => {
+                    ours.kind != theirs.kind
+                        || ours.flags != theirs.flags
+                        || ours.tags != theirs.tags
+                        || !self
+                            .tolerances
+                            .maps_within(&ours.properties, &theirs.properties)
+                }
+                None => true,
+            })
+            .map(|e| e.id)
+            .chain(
+                predicted
+                    .entities()
+                    .ids()
+                    .filter(|&id| server_state.entities().get(id).is_none()),
+            )
+            .collect();
+        entities.sort();
+        Correction {
+            entities,
+            globals: !self
+                .tolerances
+                .maps_within(predicted.globals(), server_state.globals()),
+        }
+    }
+
+    /// Correct only what the server disagrees with
+    ///
+    /// Compares the predicted state saved at `server_tick` with the
+    /// server's. If anything differs beyond tolerance, the predicted state
+    /// is patched with the server's versions of what differs, the inputs
+    /// since then are replayed on it, and only the differing entities (and
+    /// globals) are copied into `model`. Everything else keeps its predicted
+    /// values.
+    ///
+    /// Fails if no state was saved at `server_tick`; fall back to
+    /// [`apply_correction`](Self::apply_correction) then.
+    pub fn correct_selective(
+        &mut self,
+        model: &mut Model,
+        runtime: &mut Runtime,
+        server_state: &Model,
+        server_tick: u64,
+        inputs: &[Msg],
+    ) -> Result<Correction> {
+        let predicted = self
+            .history
+            .get_state(server_tick)
+            .ok_or(crate::Error::StateNotFound(server_tick))?;
+        let correction = self.diverged(predicted, server_state);
+
+        if !correction.is_empty() {
+            // Re-simulate from the prediction with the server's corrections
+            let mut scratch = predicted.clone();
+            for &id in &correction.entities {
+                match server_state.entities().get(id) {
+                    Some(entity) => scratch.entities_mut().insert(entity.clone()),
+                    None => {
+                        scratch.entities_mut().remove(id);
+                    }
+                }
+            }
+            if correction.globals {
+                *scratch.globals_mut() = server_state.globals().clone();
+            }
+            for input in inputs {
+                runtime.send(input.clone());
+                runtime.process_queue(&mut scratch);
+            }
+
+            for &id in &correction.entities {
+                match scratch.entities().get(id) {
+                    Some(entity) => model.entities_mut().insert(entity.clone()),
+                    None => {
+                        model.entities_mut().remove(id);
+                    }
+                }
+            }
+            if correction.globals {
+                *model.globals_mut() = scratch.globals().clone();
+            }
+        }
+
+        self.history.clear_before(server_tick);
+        self.last_server_tick = server_tick;
+        Ok(correction)
+    }
+
+    /// Get the tolerances
+    pub fn tolerances(&self) -> &Tolerances {
+        &self.tolerances
+    }
+
+    /// Set the tolerances
+    pub fn set_tolerances(&mut self, tolerances: Tolerances) {
+        self.tolerances = tolerances;
+    }
+
     /// Save the current state
     pub fn save_state(&mut self, tick: u64, model: &Model) {
         self.history.save_state(tick, model);
@@ -181,9 +385,9 @@ pub mod compare {
 
         // Hash globals (sorted for consistency)
         let mut globals: Vec<_> = model.globals().iter().collect();
-        globals.sort_by_key(|(k, _)| *k);
+        globals.sort_by_key(|(k, _)| k.as_str());
         for (key, value) in globals {
-            key.hash(&mut hasher);
+            key.as_str().hash(&mut hasher);
             // Hash value representation
             format!("{:?}", value).hash(&mut hasher);
         }
@@ -301,6 +505,52 @@ mod tests {
         assert_eq!(target.get_global("tick").and_then(|v| v.as_int()), Some(5));
     }
 
+    #[test]
+    fn test_correct_selective() {
+        let mut predicted = Model::new();
+        let a = predicted.entities_mut().create("unit").id;
+        let b = predicted.entities_mut().create("unit").id;
+        for id in [a, b] {
+            predicted.entities_mut().get_mut(id).unwrap().set("x", 1.0);
+        }
+        let mut history = TestHistory::new();
+        history.save_state(5, &predicted);
+
+        // `a` drifted a little, `b` a lot
+        let mut server_state = predicted.clone();
+        let mut drift = |id, x: f64| server_state.entities_mut().get_mut(id).unwrap().set("x", x);
+        drift(a, 1.005);
+        drift(b, 3.0);
+
+        let mut reconciler = Reconciler::new(history);
+        assert_eq!(
+            reconciler.diverged(&predicted, &server_state).entities,
+            [a, b]
+        );
+        reconciler.set_tolerances(Tolerances::new().with_property("x", 0.01));
+
+        // The client has predicted further since
+        let mut model = predicted.clone();
+        for id in [a, b] {
+            model.entities_mut().get_mut(id).unwrap().set("x", 1.5);
+        }
+        let mut runtime = Runtime::new();
+        let correction = reconciler
+            .correct_selective(&mut model, &mut runtime, &server_state, 5, &[])
+            .unwrap();
+
+        assert_eq!(correction.entities, [b]);
+        assert!(!correction.globals);
+        let x = |id| model.entities().get(id).unwrap().get_number("x");
+        assert_eq!(x(a), Some(1.5));
+        assert_eq!(x(b), Some(3.0));
+        assert_eq!(reconciler.last_server_tick(), 5);
+
+        assert!(reconciler
+            .correct_selective(&mut model, &mut runtime, &server_state, 9, &[])
+            .is_err());
+    }
+
     #[test]
     fn test_state_comparison() {
         let mut a = Model::new();
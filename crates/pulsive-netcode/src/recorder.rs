@@ -0,0 +1,181 @@
+//! Recording multiplayer matches as journals
+//!
+//! The server is the one place that sees every accepted input in the order
+//! it applied them. A [`MatchRecorder`] on the server session writes those
+//! authoritative inputs, tick boundaries and periodic snapshots into a
+//! [`Journal`], the same format pulsive-journal's `Replayer` and `Auditor`
+//! and the Godot time-travel panel read.
+//!
+//! # Example
+//!
+//! ```rust,ignore
+//! let mut recorder = MatchRecorder::new();
+//! recorder.start(&model);
+//!
+//! // Server loop
+//! for (tick, msg) in accepted_inputs {
+//!     recorder.record_input(tick, &msg);
+//!     runtime.send(msg);
+//! }
+//! runtime.tick(&mut model);
+//! recorder.end_tick(&model);
+//! recorder.stream_to(&mut writer)?; // optional: persist as the match goes
+//!
+//! // Match over
+//! let journal = recorder.finish(&model);
+//! ```
+
+use pulsive_core::{Journal, JournalConfig, JournalSink, Model, Msg};
+
+/// Metadata key marking the start and end of a match
+pub const MATCH_METADATA_KEY: &str = "match";
+
+/// Records a match's authoritative inputs and snapshots into a journal
+#[derive(Debug, Clone)]
+pub struct MatchRecorder {
+    journal: Journal,
+    /// Entries already passed to `stream_to`
+    streamed: usize,
+    /// Tick of the newest snapshot
+    last_snapshot: Option<u64>,
+}
+
+impl MatchRecorder {
+    /// Create a recorder with the default journal config
+    pub fn new() -> Self {
+        Self::with_config(JournalConfig::default())
+    }
+
+    /// Create a recorder with a custom journal config
+    ///
+    /// Recording is always enabled. Set
+    /// [`JournalConfig::snapshot_interval`] to trade journal size for how
+    /// fast a replay can seek.
+    pub fn with_config(mut config: JournalConfig) -> Self {
+        config.recording_enabled = true;
+        Self {
+            journal: Journal::with_config(config),
+            streamed: 0,
+            last_snapshot: None,
+        }
+    }
+
+    /// Start the match: snapshot the initial state and mark the start
+    pub fn start(&mut self, model: &Model) {
+        let tick = model.current_tick();
+        self.journal
+            .record_metadata(tick, MATCH_METADATA_KEY, "start");
+        self.snapshot(model);
+    }
+
+    /// Record an input the server accepted, for the tick it is applied in
+    pub fn record_input(&mut self, tick: u64, msg: &Msg) {
+        self.journal.record_message(tick, msg.clone());
+    }
+
+    /// Record other match events (a player joining, say) for auditing
+    pub fn record_metadata(&mut self, tick: u64, key: impl Into<String>, value: impl Into<String>) {
+        self.journal.record_metadata(tick, key, value);
+    }
+
+    /// Close the tick the model just ran, snapshotting it if due
+    pub fn end_tick(&mut self, model: &Model) {
+        let tick = model.current_tick();
+        self.journal.record_tick(tick);
+        if self.journal.should_snapshot(tick) {
+            self.snapshot(model);
+        }
+    }
+
+    fn snapshot(&mut self, model: &Model) {
+        if self.last_snapshot != Some(model.current_tick()) {
+            self.journal.take_snapshot(model);
+            self.last_snapshot = Some(model.current_tick());
+        }
+    }
+
+    /// Pass the entries recorded since the last call to a sink, such as a
+    /// journal file writer
+    pub fn stream_to<S: JournalSink + ?Sized>(&mut self, sink: &mut S) -> Result<(), S::Error> {
+        self.streamed = self.journal.sync_to(sink, self.streamed)?;
+        Ok(())
+    }
+
+    /// Get the journal recorded so far
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    /// End the match: snapshot the final state, mark the end, and return the
+    /// journal
+    ///
+    /// Call [`stream_to`](Self::stream_to) before this to stream the last
+    /// entries; the returned journal has them all.
+    pub fn finish(mut self, model: &Model) -> Journal {
+        self.snapshot(model);
+        self.journal
+            .record_metadata(model.current_tick(), MATCH_METADATA_KEY, "end");
+        self.journal
+    }
+}
+
+impl Default for MatchRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::{JournalEntry, Runtime, Snapshot};
+
+    #[derive(Default)]
+    struct Sink {
+        entries: usize,
+        snapshots: usize,
+    }
+
+    impl JournalSink for Sink {
+        type Error = ();
+
+        fn append_entry(&mut self, _entry: &JournalEntry) -> Result<(), ()> {
+            self.entries += 1;
+            Ok(())
+        }
+
+        fn append_snapshot(&mut self, _snapshot: &Snapshot) -> Result<(), ()> {
+            self.snapshots += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_record_match() {
+        let config = JournalConfig {
+            snapshot_interval: 2,
+            ..JournalConfig::default()
+        };
+        let mut recorder = MatchRecorder::with_config(config);
+        let mut model = Model::new();
+        let mut runtime = Runtime::new();
+        let mut sink = Sink::default();
+        recorder.start(&model);
+
+        for _ in 0..4 {
+            let tick = model.current_tick() + 1;
+            recorder.record_input(tick, &Msg::event("move", Default::default(), tick));
+            runtime.tick(&mut model);
+            recorder.end_tick(&model);
+            recorder.stream_to(&mut sink).unwrap();
+        }
+        let journal = recorder.finish(&model);
+
+        assert_eq!(journal.messages().count(), 4);
+        // Start, then ticks 2 and 4; the end is already covered by tick 4
+        let ticks: Vec<u64> = journal.snapshots().iter().map(|s| s.tick).collect();
+        assert_eq!(ticks, [0, 2, 4]);
+        assert_eq!(sink.snapshots, 3);
+        assert_eq!(sink.entries + 1, journal.entries().len());
+    }
+}
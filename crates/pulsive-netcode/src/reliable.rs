@@ -0,0 +1,293 @@
+//! Reliable, ordered delivery over an unreliable datagram route
+//!
+//! Used by [`crate::NatConnection`], whose UDP socket can lose, duplicate
+//! and reorder packets. Every payload sent on the route is a [`Frame`]:
+//!
+//! - `Unreliable`: passed through as is
+//! - `Reliable`: numbered, resent until the peer acknowledges it, and
+//!   delivered by the peer in the order sent
+//! - `Ack`: acknowledges one reliable frame
+//!
+//! A [`ReliableLink`] holds one side's state and does no I/O itself: the
+//! connection hands it what arrives and sends what it says is due. At most
+//! [`WINDOW`] frames are in flight, counted from the oldest unacknowledged
+//! one, and the receiver holds at most [`WINDOW`] frames ahead of the next
+//! to deliver, so a sender that respects the window is never dropped;
+//! frames beyond it are left unacknowledged and resent later.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Most reliable frames in flight, and held out of order by the receiver
+pub(crate) const WINDOW: u32 = 256;
+
+const UNRELIABLE: u8 = 0;
+const RELIABLE: u8 = 1;
+const ACK: u8 = 2;
+
+/// A payload on a reliable link's route
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Frame<'a> {
+    /// Best-effort data
+    Unreliable(&'a [u8]),
+    /// Data resent until acknowledged
+    Reliable { seq: u32, payload: &'a [u8] },
+    /// Acknowledges a reliable frame
+    Ack { seq: u32 },
+}
+
+impl<'a> Frame<'a> {
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        match *self {
+            Frame::Unreliable(payload) => {
+                let mut data = Vec::with_capacity(1 + payload.len());
+                data.push(UNRELIABLE);
+                data.extend_from_slice(payload);
+                data
+            }
+            Frame::Reliable { seq, payload } => {
+                let mut data = Vec::with_capacity(5 + payload.len());
+                data.push(RELIABLE);
+                data.extend_from_slice(&seq.to_le_bytes());
+                data.extend_from_slice(payload);
+                data
+            }
+            Frame::Ack { seq } => {
+                let mut data = Vec::with_capacity(5);
+                data.push(ACK);
+                data.extend_from_slice(&seq.to_le_bytes());
+                data
+            }
+        }
+    }
+
+    pub(crate) fn decode(data: &'a [u8]) -> Option<Self> {
+        let (&kind, rest) = data.split_first()?;
+        Some(match kind {
+            UNRELIABLE => Frame::Unreliable(rest),
+            RELIABLE => {
+                let (seq, payload) = rest.split_first_chunk::<4>()?;
+                Frame::Reliable {
+                    seq: u32::from_le_bytes(*seq),
+                    payload,
+                }
+            }
+            ACK => Frame::Ack {
+                seq: u32::from_le_bytes(*rest.first_chunk::<4>()?),
+            },
+            _ => return None,
+        })
+    }
+}
+
+/// A reliable frame waiting for its acknowledgement
+#[derive(Debug)]
+struct Outgoing {
+    /// The encoded frame
+    frame: Vec<u8>,
+    /// When it was last sent, `None` until it first fits in the window
+    sent_at: Option<Instant>,
+}
+
+/// One side of a reliable link: retransmission and in-order delivery
+#[derive(Debug)]
+pub(crate) struct ReliableLink {
+    resend_interval: Duration,
+    /// Sequence number of the first entry of `outgoing`
+    base: u32,
+    /// Frames from `base` on; acknowledged ones are `None` until every
+    /// frame before them is acknowledged too
+    outgoing: VecDeque<Option<Outgoing>>,
+    /// Sequence number of the next frame to deliver
+    next_recv: u32,
+    /// Frames received ahead of `next_recv`, within `WINDOW`
+    pending: HashMap<u32, Vec<u8>>,
+    /// Payloads released in order, not yet taken
+    ready: VecDeque<Vec<u8>>,
+}
+
+impl ReliableLink {
+    /// Create a link resending unacknowledged frames every `resend_interval`
+    pub(crate) fn new(resend_interval: Duration) -> Self {
+        Self {
+            resend_interval,
+            base: 0,
+            outgoing: VecDeque::new(),
+            next_recv: 0,
+            pending: HashMap::new(),
+            ready: VecDeque::new(),
+        }
+    }
+
+    /// Queue a payload for reliable delivery; it goes out with the next
+    /// [`due`](Self::due) that finds room in the window
+    pub(crate) fn push(&mut self, payload: &[u8]) {
+        let seq = self.base.wrapping_add(self.outgoing.len() as u32);
+        self.outgoing.push_back(Some(Outgoing {
+            frame: Frame::Reliable { seq, payload }.encode(),
+            sent_at: None,
+        }));
+    }
+
+    /// Get the frames to send now: new ones that fit in the window, and
+    /// those unacknowledged for `resend_interval`
+    pub(crate) fn due(&mut self, now: Instant) -> Vec<Vec<u8>> {
+        let resend_interval = self.resend_interval;
+        self.outgoing
+            .iter_mut()
+            .take(WINDOW as usize)
+            .flatten()
+            .filter(|out| {
+                out.sent_at
+                    .is_none_or(|sent| now.saturating_duration_since(sent) >= resend_interval)
+            })
+            .map(|out| {
+                out.sent_at = Some(now);
+                out.frame.clone()
+            })
+            .collect()
+    }
+
+    /// Handle the peer's acknowledgement of frame `seq`
+    pub(crate) fn acked(&mut self, seq: u32) {
+        let index = seq.wrapping_sub(self.base) as usize;
+        if let Some(entry) = self.outgoing.get_mut(index) {
+            *entry = None;
+        }
+        while let Some(None) = self.outgoing.front() {
+            self.outgoing.pop_front();
+            self.base = self.base.wrapping_add(1);
+        }
+    }
+
+    /// Handle a reliable frame from the peer
+    ///
+    /// Returns whether to acknowledge it: duplicates are acknowledged again
+    /// in case the first acknowledgement was lost, frames beyond the window
+    /// are not, so the peer resends them.
+    pub(crate) fn received(&mut self, seq: u32, payload: &[u8]) -> bool {
+        let offset = seq.wrapping_sub(self.next_recv);
+        if (offset as i32) < 0 {
+            return true;
+        }
+        if offset >= WINDOW {
+            return false;
+        }
+        self.pending.insert(seq, payload.to_vec());
+        while let Some(payload) = self.pending.remove(&self.next_recv) {
+            self.ready.push_back(payload);
+            self.next_recv = self.next_recv.wrapping_add(1);
+        }
+        true
+    }
+
+    /// Take the next payload to deliver
+    pub(crate) fn pop(&mut self) -> Option<Vec<u8>> {
+        self.ready.pop_front()
+    }
+
+    /// Get the number of reliable frames not yet acknowledged
+    pub(crate) fn unacked(&self) -> usize {
+        self.outgoing.iter().flatten().count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESEND: Duration = Duration::from_millis(100);
+
+    /// Deliver frames to a link, returning the acknowledgements it sends
+    fn deliver(link: &mut ReliableLink, frames: &[Vec<u8>]) -> Vec<u32> {
+        let mut acks = Vec::new();
+        for frame in frames {
+            match Frame::decode(frame) {
+                Some(Frame::Reliable { seq, payload }) => {
+                    if link.received(seq, payload) {
+                        acks.push(seq);
+                    }
+                }
+                other => panic!("unexpected frame {:?}", other),
+            }
+        }
+        acks
+    }
+
+    #[test]
+    fn test_frame_roundtrip() {
+        let frames = [
+            Frame::Unreliable(b"state"),
+            Frame::Reliable {
+                seq: u32::MAX,
+                payload: b"chat",
+            },
+            Frame::Ack { seq: 7 },
+        ];
+        for frame in frames {
+            assert_eq!(Frame::decode(&frame.encode()), Some(frame));
+        }
+        assert_eq!(Frame::decode(b"\x02\x01"), None);
+        assert_eq!(Frame::decode(b""), None);
+    }
+
+    #[test]
+    fn test_resends_lost_frames() {
+        let start = Instant::now();
+        let mut sender = ReliableLink::new(RESEND);
+        let mut receiver = ReliableLink::new(RESEND);
+        for message in [b"a", b"b", b"c"] {
+            sender.push(message);
+        }
+
+        // The first frame is lost, the others arrive and wait for it
+        let sent = sender.due(start);
+        assert_eq!(sent.len(), 3);
+        for seq in deliver(&mut receiver, &sent[1..]) {
+            sender.acked(seq);
+        }
+        assert_eq!(receiver.pop(), None);
+        assert_eq!(sender.unacked(), 1);
+
+        // Nothing is resent early; once due, only the lost frame is
+        assert!(sender.due(start + RESEND / 2).is_empty());
+        let resent = sender.due(start + RESEND);
+        assert_eq!(resent, sent[..1]);
+        for seq in deliver(&mut receiver, &resent) {
+            sender.acked(seq);
+        }
+        assert_eq!(sender.unacked(), 0);
+        let received: Vec<_> = std::iter::from_fn(|| receiver.pop()).collect();
+        assert_eq!(received, [b"a", b"b", b"c"]);
+
+        // A duplicate is acknowledged again but not delivered twice
+        assert_eq!(deliver(&mut receiver, &sent[..1]), [0]);
+        assert_eq!(receiver.pop(), None);
+    }
+
+    #[test]
+    fn test_window() {
+        let now = Instant::now();
+        let mut sender = ReliableLink::new(RESEND);
+        let mut receiver = ReliableLink::new(RESEND);
+        for i in 0..=WINDOW {
+            sender.push(&i.to_le_bytes());
+        }
+
+        // Only a window's worth goes out until the oldest is acknowledged
+        let sent = sender.due(now);
+        assert_eq!(sent.len(), WINDOW as usize);
+        let acks = deliver(&mut receiver, &sent);
+        sender.acked(acks[1]);
+        assert!(sender.due(now).is_empty());
+        sender.acked(acks[0]);
+        let last = sender.due(now);
+        assert_eq!(last.len(), 1);
+        assert_eq!(deliver(&mut receiver, &last), [WINDOW]);
+
+        // The receiver leaves frames beyond its window unacknowledged
+        let mut late = ReliableLink::new(RESEND);
+        assert!(!late.received(WINDOW, b"early"));
+        assert!(late.pending.is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+//! Prediction scopes: predicting only part of the model
+//!
+//! A client usually only predicts its own units; everything else is shown
+//! as the server sent it. A [`PredictionScope`] names those entities, by ID
+//! or by kind. A [`crate::PredictionEngine`] with a scope:
+//!
+//! - saves only the scoped entities in its history, so predicting doesn't
+//!   copy the whole model
+//! - compares only the scoped entities with server states
+//! - takes everything outside the scope, globals included, from the latest
+//!   server state, and keeps replayed inputs from changing it
+
+use crate::stats::misprediction;
+use pulsive_core::{DefId, Entity, EntityId, IndexMap, Model, ValueMap};
+use std::collections::HashSet;
+
+/// Differences smaller than this are float noise, not mispredictions
+const EPSILON: f64 = 1e-6;
+
+/// Entities a [`crate::PredictionEngine`] predicts locally
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PredictionScope {
+    entities: HashSet<EntityId>,
+    kinds: HashSet<DefId>,
+}
+
+impl PredictionScope {
+    /// Create an empty scope
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an entity to the scope
+    pub fn with_entity(mut self, id: EntityId) -> Self {
+        self.entities.insert(id);
+        self
+    }
+
+    /// Add every entity of a kind to the scope
+    pub fn with_kind(mut self, kind: impl Into<DefId>) -> Self {
+        self.kinds.insert(kind.into());
+        self
+    }
+
+    /// Add an entity to the scope
+    pub fn add_entity(&mut self, id: EntityId) {
+        self.entities.insert(id);
+    }
+
+    /// Remove an entity from the scope
+    ///
+    /// It stays in scope if its kind is.
+    pub fn remove_entity(&mut self, id: EntityId) -> bool {
+        self.entities.remove(&id)
+    }
+
+    /// Add every entity of a kind to the scope
+    pub fn add_kind(&mut self, kind: impl Into<DefId>) {
+        self.kinds.insert(kind.into());
+    }
+
+    /// Remove a kind from the scope
+    pub fn remove_kind(&mut self, kind: &DefId) -> bool {
+        self.kinds.remove(kind)
+    }
+
+    /// Check if an entity is in the scope
+    pub fn contains(&self, entity: &Entity) -> bool {
+        self.entities.contains(&entity.id) || self.kinds.contains(&entity.kind)
+    }
+
+    /// Check if the scope is empty
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty() && self.kinds.is_empty()
+    }
+
+    /// Copy the scoped entities of a model, with its clock and RNG but no
+    /// globals or actors
+    pub fn snapshot(&self, model: &Model) -> Model {
+        let ids = model
+            .entities()
+            .iter()
+            .filter(|e| self.contains(e))
+            .map(|e| e.id);
+        Model::from_snapshot_data(
+            model.entities().subset(ids),
+            ValueMap::new(),
+            model.time.clone(),
+            model.rng.clone(),
+            IndexMap::new(),
+        )
+    }
+
+    /// Replace everything outside the scope with the server's state
+    pub fn merge_unscoped(&self, model: &mut Model, server: &Model) {
+        *model.globals_mut() = server.globals().clone();
+        let stale: Vec<EntityId> = model
+            .entities()
+            .iter()
+            .filter(|e| !self.contains(e))
+            .map(|e| e.id)
+            .collect();
+        let entities = model.entities_mut();
+        for id in stale {
+            entities.remove(id);
+        }
+        for entity in server.entities().iter().filter(|e| !self.contains(e)) {
+            entities.insert(entity.clone());
+        }
+    }
+
+    /// Compare a scoped snapshot with a server state, in the scope
+    ///
+    /// Returns the misprediction magnitude if they differ.
+    pub(crate) fn compare(&self, snapshot: &Model, server: &Model) -> Option<f64> {
+        let server = self.snapshot(server);
+        let magnitude = misprediction(snapshot, &server);
+        let flags_differ = snapshot.entities().iter().any(|ours| {
+            server
+                .entities()
+                .get(ours.id)
+                .is_some_and(|theirs| theirs.flags != ours.flags || theirs.tags != ours.tags)
+        });
+        (magnitude > EPSILON || flags_differ).then_some(magnitude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_and_merge() {
+        let mut model = Model::new();
+        model.set_global("weather", "rain");
+        let player = model.entities_mut().create("player").id;
+        let npc = model.entities_mut().create("npc").id;
+        let scope = PredictionScope::new().with_kind("player");
+
+        let snapshot = scope.snapshot(&model);
+        assert_eq!(snapshot.entities().len(), 1);
+        assert!(snapshot.entities().get(player).is_some());
+        assert!(snapshot.globals().is_empty());
+
+        let mut server = model.clone();
+        server.set_global("weather", "sun");
+        server.entities_mut().remove(npc);
+        let merchant = server.entities_mut().create("merchant").id;
+        server.entities_mut().get_mut(player).unwrap().set("x", 4.0);
+
+        scope.merge_unscoped(&mut model, &server);
+        assert_eq!(model.get_global("weather").unwrap().as_str(), Some("sun"));
+        assert!(model.entities().get(npc).is_none());
+        assert!(model.entities().get(merchant).is_some());
+        // The player is predicted locally, so the server's copy is ignored
+        assert_eq!(model.entities().get(player).unwrap().get("x"), None);
+        // An added property counts 1
+        assert_eq!(scope.compare(&snapshot, &server), Some(1.0));
+    }
+}
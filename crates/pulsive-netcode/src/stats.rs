@@ -0,0 +1,285 @@
+//! Per-connection network and prediction metrics
+//!
+//! [`ConnectionStats`] accumulates what a netcode debug overlay shows:
+//! round-trip time and jitter, packet loss, bandwidth, and how often and how
+//! badly the client mispredicts. The transport layer records packets and
+//! RTT samples; [`crate::PredictionEngine`] records its own corrections and
+//! exposes the stats with [`stats`](crate::PredictionEngine::stats).
+//!
+//! RTT and jitter are smoothed the way TCP smooths them (RFC 6298), so a
+//! single late packet doesn't make the numbers jump.
+
+use pulsive_core::{Model, Value, ValueMap};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Window over which corrections per second are counted
+const CORRECTION_WINDOW: Duration = Duration::from_secs(1);
+
+/// Weight of a new sample in the smoothed averages
+const SMOOTHING: f64 = 1.0 / 8.0;
+
+/// Weight of a new sample in the jitter average
+const JITTER_SMOOTHING: f64 = 1.0 / 4.0;
+
+/// Network and prediction metrics of one connection
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionStats {
+    rtt: Option<Duration>,
+    jitter: Duration,
+    packets_sent: u64,
+    packets_received: u64,
+    packets_lost: u64,
+    bytes_sent: u64,
+    bytes_received: u64,
+    /// Times of the corrections within the window
+    recent_corrections: VecDeque<Instant>,
+    corrections: u64,
+    misprediction: f64,
+}
+
+impl ConnectionStats {
+    /// Create empty stats
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a round-trip time sample
+    pub fn record_rtt(&mut self, sample: Duration) {
+        match self.rtt {
+            None => {
+                self.rtt = Some(sample);
+                self.jitter = sample / 2;
+            }
+            Some(rtt) => {
+                let deviation = rtt.abs_diff(sample).as_secs_f64();
+                self.jitter = Duration::from_secs_f64(
+                    self.jitter.as_secs_f64() * (1.0 - JITTER_SMOOTHING)
+                        + deviation * JITTER_SMOOTHING,
+                );
+                self.rtt = Some(Duration::from_secs_f64(
+                    rtt.as_secs_f64() * (1.0 - SMOOTHING) + sample.as_secs_f64() * SMOOTHING,
+                ));
+            }
+        }
+    }
+
+    /// Record a packet sent
+    pub fn record_sent(&mut self, bytes: usize) {
+        self.packets_sent += 1;
+        self.bytes_sent += bytes as u64;
+    }
+
+    /// Record a packet received
+    pub fn record_received(&mut self, bytes: usize) {
+        self.packets_received += 1;
+        self.bytes_received += bytes as u64;
+    }
+
+    /// Record packets known to be lost (a gap in sequence numbers, say)
+    pub fn record_lost(&mut self, packets: u64) {
+        self.packets_lost += packets;
+    }
+
+    /// Record a reconciliation correction and how far the prediction was off
+    ///
+    /// See [`misprediction`] for a magnitude of two models.
+    pub fn record_correction(&mut self, now: Instant, magnitude: f64) {
+        self.corrections += 1;
+        self.recent_corrections.push_back(now);
+        self.prune_corrections(now);
+        self.misprediction = if self.corrections == 1 {
+            magnitude
+        } else {
+            self.misprediction * (1.0 - SMOOTHING) + magnitude * SMOOTHING
+        };
+    }
+
+    fn prune_corrections(&mut self, now: Instant) {
+        while let Some(&oldest) = self.recent_corrections.front() {
+            if now.saturating_duration_since(oldest) < CORRECTION_WINDOW {
+                break;
+            }
+            self.recent_corrections.pop_front();
+        }
+    }
+
+    /// Get the smoothed round-trip time (None before the first sample)
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+
+    /// Get the smoothed RTT variation
+    pub fn jitter(&self) -> Duration {
+        self.jitter
+    }
+
+    /// Get the fraction of packets lost, from 0 to 1
+    ///
+    /// Lost packets over packets received plus lost.
+    pub fn packet_loss(&self) -> f64 {
+        let expected = self.packets_received + self.packets_lost;
+        if expected == 0 {
+            0.0
+        } else {
+            self.packets_lost as f64 / expected as f64
+        }
+    }
+
+    /// Get the number of packets sent
+    pub fn packets_sent(&self) -> u64 {
+        self.packets_sent
+    }
+
+    /// Get the number of packets received
+    pub fn packets_received(&self) -> u64 {
+        self.packets_received
+    }
+
+    /// Get the number of packets lost
+    pub fn packets_lost(&self) -> u64 {
+        self.packets_lost
+    }
+
+    /// Get the bytes sent
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    /// Get the bytes received
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received
+    }
+
+    /// Get the total number of corrections
+    pub fn corrections(&self) -> u64 {
+        self.corrections
+    }
+
+    /// Get the number of corrections in the second before `now`
+    pub fn corrections_per_second(&self, now: Instant) -> f64 {
+        self.recent_corrections
+            .iter()
+            .filter(|&&at| now.saturating_duration_since(at) < CORRECTION_WINDOW)
+            .count() as f64
+    }
+
+    /// Get the smoothed average misprediction magnitude
+    pub fn misprediction(&self) -> f64 {
+        self.misprediction
+    }
+
+    /// Reset all stats
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Measure how far a predicted model is from the authoritative one
+///
+/// Sums the absolute differences of numeric globals and entity properties.
+/// Any other value that differs, missing or not, counts 1, as does an
+/// entity present in only one model.
+pub fn misprediction(predicted: &Model, authoritative: &Model) -> f64 {
+    let mut total = map_distance(predicted.globals(), authoritative.globals());
+    for entity in authoritative.entities().iter() {
+        total += match predicted.entities().get(entity.id) {
+            Some(ours) => map_distance(&ours.properties, &entity.properties),
+            None => 1.0,
+        };
+    }
+    for entity in predicted.entities().iter() {
+        if authoritative.entities().get(entity.id).is_none() {
+            total += 1.0;
+        }
+    }
+    total
+}
+
+fn map_distance(a: &ValueMap, b: &ValueMap) -> f64 {
+    let mut total = 0.0;
+    for (key, value) in b.iter() {
+        total += value_distance(a.get(key), Some(value));
+    }
+    for (key, value) in a.iter() {
+        if b.get(key).is_none() {
+            total += value_distance(Some(value), None);
+        }
+    }
+    total
+}
+
+fn value_distance(a: Option<&Value>, b: Option<&Value>) -> f64 {
+    match (a.and_then(Value::as_float), b.and_then(Value::as_float)) {
+        (Some(x), Some(y)) => (x - y).abs(),
+        _ if a == b => 0.0,
+        _ => 1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rtt_and_loss() {
+        let mut stats = ConnectionStats::new();
+        assert_eq!(stats.rtt(), None);
+
+        stats.record_rtt(Duration::from_millis(100));
+        assert_eq!(stats.rtt(), Some(Duration::from_millis(100)));
+        assert_eq!(stats.jitter(), Duration::from_millis(50));
+
+        // One spike moves the average an eighth of the way
+        stats.record_rtt(Duration::from_millis(180));
+        let rtt = stats.rtt().unwrap().as_secs_f64();
+        assert!((rtt - 0.110).abs() < 1e-9);
+
+        for _ in 0..3 {
+            stats.record_received(100);
+        }
+        stats.record_lost(1);
+        stats.record_sent(40);
+        assert_eq!(stats.packet_loss(), 0.25);
+        assert_eq!((stats.bytes_received(), stats.bytes_sent()), (300, 40));
+
+        stats.reset();
+        assert_eq!(stats.packets_received(), 0);
+    }
+
+    #[test]
+    fn test_corrections() {
+        let mut stats = ConnectionStats::new();
+        let start = Instant::now();
+        stats.record_correction(start, 4.0);
+        stats.record_correction(start + Duration::from_millis(500), 12.0);
+        assert_eq!(stats.misprediction(), 5.0);
+        assert_eq!(
+            stats.corrections_per_second(start + Duration::from_millis(600)),
+            2.0
+        );
+        assert_eq!(
+            stats.corrections_per_second(start + Duration::from_millis(1200)),
+            1.0
+        );
+        assert_eq!(stats.corrections(), 2);
+    }
+
+    #[test]
+    fn test_misprediction() {
+        let mut predicted = Model::new();
+        predicted.set_global("gold", 10.0);
+        let unit = predicted.entities_mut().create("unit");
+        unit.set("x", 3.0);
+        unit.set("name", "scout");
+        let mut authoritative = predicted.clone();
+        assert_eq!(misprediction(&predicted, &authoritative), 0.0);
+
+        authoritative.set_global("gold", 12.5);
+        let id = authoritative.entities().iter().next().unwrap().id;
+        let unit = authoritative.entities_mut().get_mut(id).unwrap();
+        unit.set("x", 2.0);
+        unit.set("name", "spy");
+        assert_eq!(misprediction(&predicted, &authoritative), 4.5);
+    }
+}
@@ -32,6 +32,15 @@ impl From<&str> for Address {
     }
 }
 
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Address::Socket(addr) => write!(f, "{}", addr),
+            Address::Custom(addr) => write!(f, "{}", addr),
+        }
+    }
+}
+
 /// Connectionless transport trait (e.g., UDP)
 ///
 /// Used for sending individual packets without connection state.
@@ -60,6 +69,9 @@ pub trait Connection: Send + Sync {
     type Error: std::error::Error + Send + Sync + 'static;
 
     /// Send data reliably (guaranteed delivery, ordered)
+    ///
+    /// Transports that cannot guarantee delivery return an error rather
+    /// than sending unreliably.
     fn send_reliable(&self, data: &[u8]) -> Result<(), Self::Error>;
 
     /// Send data unreliably (best effort, may be reordered or lost)
@@ -0,0 +1,412 @@
+//! Branching state history
+//!
+//! A [`BranchingHistory`] stores several alternative timelines. Forking at a
+//! tick creates a new branch that shares every state up to and including
+//! that tick with its parent, so only states saved after the fork are stored
+//! again. This is what an editor needs to explore "what if this event fired
+//! differently": fork at the event tick, re-run the simulation on the new
+//! branch, then compare it with the original.
+//!
+//! The [`StateHistory`] impl operates on the current branch, so a branching
+//! history can be passed to the replayer or netcode like any other history.
+
+use crate::{DeltaState, Error, Result};
+use pulsive_core::{Model, StateHistory};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Identifier of a branch in a [`BranchingHistory`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BranchId(u32);
+
+impl BranchId {
+    /// The root branch every history starts with
+    pub const MAIN: BranchId = BranchId(0);
+
+    /// Get the raw branch number
+    pub fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Display for BranchId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "branch#{}", self.0)
+    }
+}
+
+/// A single timeline
+#[derive(Debug)]
+struct Branch<S> {
+    /// Parent branch and the tick this branch was forked at
+    parent: Option<(BranchId, u64)>,
+    /// States saved on this branch
+    states: BTreeMap<u64, S>,
+    /// States before this tick are no longer visible on this branch
+    floor: u64,
+}
+
+impl<S> Branch<S> {
+    fn new(parent: Option<(BranchId, u64)>, floor: u64) -> Self {
+        Self {
+            parent,
+            states: BTreeMap::new(),
+            floor,
+        }
+    }
+}
+
+/// State history with forkable timelines
+///
+/// Each branch sees its own states plus its parent's states up to the fork
+/// tick. States saved on a branch at or before its fork tick shadow the
+/// parent's.
+///
+/// # Example
+///
+/// ```rust
+/// use pulsive_core::{Model, StateHistory};
+/// use pulsive_rollback_buffer::{BranchId, BranchingHistory};
+///
+/// let mut history = BranchingHistory::<Model>::new();
+/// let mut model = Model::new();
+/// for tick in 0..10 {
+///     model.set_global("gold", tick as i64);
+///     history.save_state(tick, &model);
+/// }
+///
+/// // Explore an alternative from tick 5 onwards
+/// let what_if = history.fork(5);
+/// history.checkout(what_if).unwrap();
+/// model.set_global("gold", 100i64);
+/// history.save_state(6, &model);
+///
+/// // States up to the fork are shared, later ones are per branch
+/// let gold = |branch, tick| history.state_at(branch, tick)?.get_global("gold")?.as_int();
+/// assert_eq!(gold(what_if, 3), Some(3));
+/// assert_eq!(gold(what_if, 6), Some(100));
+/// assert_eq!(gold(BranchId::MAIN, 6), Some(6));
+/// assert_eq!(gold(what_if, 8), None);
+/// ```
+#[derive(Debug)]
+pub struct BranchingHistory<S = Model> {
+    /// All branches, indexed by branch ID
+    branches: Vec<Branch<S>>,
+    /// Branch used by the [`StateHistory`] impl
+    current: BranchId,
+}
+
+impl<S: Clone> BranchingHistory<S> {
+    /// Create a history with only the main branch
+    pub fn new() -> Self {
+        Self {
+            branches: vec![Branch::new(None, 0)],
+            current: BranchId::MAIN,
+        }
+    }
+
+    /// Get the branch the [`StateHistory`] impl operates on
+    pub fn current(&self) -> BranchId {
+        self.current
+    }
+
+    /// Make a branch the current branch
+    pub fn checkout(&mut self, branch: BranchId) -> Result<()> {
+        self.branch(branch)?;
+        self.current = branch;
+        Ok(())
+    }
+
+    /// Fork the current branch at a tick
+    ///
+    /// The new branch shares all states up to and including `tick` with the
+    /// current branch. The current branch is not changed.
+    pub fn fork(&mut self, tick: u64) -> BranchId {
+        let parent = self.current;
+        let floor = self.branches[parent.0 as usize].floor;
+        self.branches.push(Branch::new(Some((parent, tick)), floor));
+        BranchId(self.branches.len() as u32 - 1)
+    }
+
+    /// Fork an arbitrary branch at a tick
+    pub fn fork_from(&mut self, branch: BranchId, tick: u64) -> Result<BranchId> {
+        let floor = self.branch(branch)?.floor;
+        self.branches.push(Branch::new(Some((branch, tick)), floor));
+        Ok(BranchId(self.branches.len() as u32 - 1))
+    }
+
+    /// Iterate all branch IDs in creation order
+    pub fn branches(&self) -> impl Iterator<Item = BranchId> + '_ {
+        (0..self.branches.len() as u32).map(BranchId)
+    }
+
+    /// Get the number of branches
+    pub fn branch_count(&self) -> usize {
+        self.branches.len()
+    }
+
+    /// Get the parent of a branch and the tick it was forked at
+    pub fn parent(&self, branch: BranchId) -> Option<(BranchId, u64)> {
+        self.branch(branch).ok()?.parent
+    }
+
+    /// Get the state of a branch at an exact tick
+    pub fn state_at(&self, branch: BranchId, tick: u64) -> Option<&S> {
+        let b = self.branch(branch).ok()?;
+        if tick < b.floor {
+            return None;
+        }
+        match (b.states.get(&tick), b.parent) {
+            (Some(state), _) => Some(state),
+            (None, Some((parent, fork_tick))) if tick <= fork_tick => self.state_at(parent, tick),
+            _ => None,
+        }
+    }
+
+    /// Get the last tick up to which two branches share their history
+    ///
+    /// Returns `None` for the same branch. States saved on a branch at or
+    /// before its fork tick are not taken into account.
+    pub fn fork_point(&self, a: BranchId, b: BranchId) -> Option<u64> {
+        if a == b {
+            return None;
+        }
+        let path_a = self.ancestry(a);
+        let path_b = self.ancestry(b);
+
+        // Each path lists (branch, visible up to) from the branch to the root
+        let (common_a, common_b) = path_a.iter().enumerate().find_map(|(i, (branch, _))| {
+            path_b
+                .iter()
+                .position(|(other, _)| other == branch)
+                .map(|j| (i, j))
+        })?;
+        Some(path_a[common_a].1.min(path_b[common_b].1))
+    }
+
+    /// Get the branch's ancestors with the last tick visible from the branch
+    fn ancestry(&self, branch: BranchId) -> Vec<(BranchId, u64)> {
+        let mut path = vec![(branch, u64::MAX)];
+        let mut limit = u64::MAX;
+        let mut next = self.parent(branch);
+        while let Some((parent, fork_tick)) = next {
+            limit = limit.min(fork_tick);
+            path.push((parent, limit));
+            next = self.parent(parent);
+        }
+        path
+    }
+
+    fn branch(&self, branch: BranchId) -> Result<&Branch<S>> {
+        self.branches
+            .get(branch.0 as usize)
+            .ok_or(Error::UnknownBranch(branch.0))
+    }
+
+    /// Nearest visible state at or before `tick`, ignoring states after `limit`
+    fn nearest_before(&self, branch: BranchId, tick: u64, limit: u64) -> Option<(u64, &S)> {
+        let b = &self.branches[branch.0 as usize];
+        let tick = tick.min(limit);
+        let own = b.states.range(..=tick).next_back();
+        let inherited = b
+            .parent
+            .and_then(|(parent, fork_tick)| self.nearest_before(parent, tick, fork_tick));
+        let nearest = match (own, inherited) {
+            (Some((t, s)), Some((pt, _))) if *t >= pt => Some((*t, s)),
+            (_, Some(inherited)) => Some(inherited),
+            (own, None) => own.map(|(t, s)| (*t, s)),
+        };
+        nearest.filter(|(t, _)| *t >= b.floor)
+    }
+
+    /// Nearest visible state at or after `tick`, ignoring states after `limit`
+    fn nearest_after(&self, branch: BranchId, tick: u64, limit: u64) -> Option<(u64, &S)> {
+        let b = &self.branches[branch.0 as usize];
+        let tick = tick.max(b.floor);
+        if tick > limit {
+            return None;
+        }
+        let own = b.states.range(tick..=limit).next();
+        let inherited = b
+            .parent
+            .and_then(|(parent, fork_tick)| self.nearest_after(parent, tick, fork_tick.min(limit)));
+        match (own, inherited) {
+            (Some((t, s)), Some((pt, _))) if *t <= pt => Some((*t, s)),
+            (_, Some(inherited)) => Some(inherited),
+            (own, None) => own.map(|(t, s)| (*t, s)),
+        }
+    }
+
+    /// Iterate the ticks visible on a branch in order
+    fn visible_ticks(&self, branch: BranchId, limit: u64) -> Vec<u64> {
+        let b = &self.branches[branch.0 as usize];
+        let mut ticks: Vec<u64> = b.states.range(b.floor..=limit).map(|(t, _)| *t).collect();
+        if let Some((parent, fork_tick)) = b.parent {
+            ticks.extend(
+                self.visible_ticks(parent, fork_tick.min(limit))
+                    .into_iter()
+                    .filter(|t| *t >= b.floor),
+            );
+            ticks.sort_unstable();
+            ticks.dedup();
+        }
+        ticks
+    }
+}
+
+impl<S: DeltaState> BranchingHistory<S> {
+    /// Diff the states of two branches at a tick
+    ///
+    /// Returns the diff that turns branch `a`'s state into branch `b`'s, or
+    /// `None` if either branch has no state at `tick`.
+    pub fn diff(&self, a: BranchId, b: BranchId, tick: u64) -> Option<S::Diff> {
+        Some(S::diff(self.state_at(a, tick)?, self.state_at(b, tick)?))
+    }
+}
+
+impl<S: Clone> Default for BranchingHistory<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Clone> StateHistory<S> for BranchingHistory<S> {
+    fn save_state(&mut self, tick: u64, state: &S) {
+        let branch = &mut self.branches[self.current.0 as usize];
+        branch.states.insert(tick, state.clone());
+    }
+
+    fn get_state(&self, tick: u64) -> Option<&S> {
+        self.state_at(self.current, tick)
+    }
+
+    fn get_nearest_before(&self, tick: u64) -> Option<(u64, &S)> {
+        self.nearest_before(self.current, tick, u64::MAX)
+    }
+
+    fn get_nearest_after(&self, tick: u64) -> Option<(u64, &S)> {
+        self.nearest_after(self.current, tick, u64::MAX)
+    }
+
+    /// Clear states before `tick` on the current branch
+    ///
+    /// States shared with the parent stay available to other branches.
+    fn clear_before(&mut self, tick: u64) {
+        let branch = &mut self.branches[self.current.0 as usize];
+        branch.floor = branch.floor.max(tick);
+        branch.states = branch.states.split_off(&tick);
+    }
+
+    /// Remove all branches and states, leaving an empty main branch
+    fn clear(&mut self) {
+        self.branches.truncate(1);
+        self.branches[0] = Branch::new(None, 0);
+        self.current = BranchId::MAIN;
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+
+    fn len(&self) -> usize {
+        self.visible_ticks(self.current, u64::MAX).len()
+    }
+
+    fn tick_range(&self) -> Option<(u64, u64)> {
+        let (oldest, _) = self.nearest_after(self.current, 0, u64::MAX)?;
+        let (newest, _) = self.nearest_before(self.current, u64::MAX, u64::MAX)?;
+        Some((oldest, newest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model_with_gold(gold: i64) -> Model {
+        let mut model = Model::new();
+        model.set_global("gold", gold);
+        model
+    }
+
+    fn gold(state: Option<&Model>) -> Option<i64> {
+        state?.get_global("gold")?.as_int()
+    }
+
+    fn history_with_main(ticks: u64) -> BranchingHistory {
+        let mut history = BranchingHistory::new();
+        for tick in 0..ticks {
+            history.save_state(tick, &model_with_gold(tick as i64));
+        }
+        history
+    }
+
+    #[test]
+    fn test_fork_shares_prefix() {
+        let mut history = history_with_main(10);
+        let branch = history.fork(4);
+        history.checkout(branch).unwrap();
+        history.save_state(5, &model_with_gold(500));
+        history.save_state(6, &model_with_gold(600));
+
+        assert_eq!(gold(history.get_state(4)), Some(4));
+        assert_eq!(gold(history.get_state(5)), Some(500));
+        assert!(history.get_state(7).is_none());
+        assert_eq!(history.len(), 7);
+        assert_eq!(history.tick_range(), Some((0, 6)));
+        assert_eq!(history.get_nearest_before(100).map(|(t, _)| t), Some(6));
+
+        // Main branch is untouched
+        assert_eq!(gold(history.state_at(BranchId::MAIN, 5)), Some(5));
+        assert_eq!(history.parent(branch), Some((BranchId::MAIN, 4)));
+    }
+
+    #[test]
+    fn test_nested_forks() {
+        let mut history = history_with_main(10);
+        let a = history.fork(6);
+        history.checkout(a).unwrap();
+        history.save_state(7, &model_with_gold(70));
+        let b = history.fork(7);
+        history.checkout(b).unwrap();
+        history.save_state(8, &model_with_gold(80));
+
+        assert_eq!(gold(history.get_state(3)), Some(3));
+        assert_eq!(gold(history.get_state(7)), Some(70));
+        assert_eq!(gold(history.get_state(8)), Some(80));
+        assert_eq!(history.get_nearest_after(9).map(|(t, _)| t), None);
+        assert_eq!(history.fork_point(a, b), Some(7));
+        assert_eq!(history.fork_point(b, BranchId::MAIN), Some(6));
+        assert_eq!(history.fork_point(a, a), None);
+    }
+
+    #[test]
+    fn test_diff_branches() {
+        let mut history = history_with_main(5);
+        let branch = history.fork(2);
+        history.checkout(branch).unwrap();
+        history.save_state(3, &model_with_gold(300));
+
+        let diff = history.diff(BranchId::MAIN, branch, 3).unwrap();
+        assert_eq!(diff.changed_globals.len(), 1);
+        assert!(history.diff(BranchId::MAIN, branch, 2).unwrap().is_empty());
+        assert!(history.diff(BranchId::MAIN, branch, 4).is_none());
+    }
+
+    #[test]
+    fn test_clear_before_keeps_parent_states() {
+        let mut history = history_with_main(10);
+        let branch = history.fork(5);
+        history.checkout(branch).unwrap();
+        history.clear_before(3);
+
+        assert!(history.get_state(2).is_none());
+        assert_eq!(history.tick_range(), Some((3, 5)));
+        assert_eq!(gold(history.state_at(BranchId::MAIN, 2)), Some(2));
+
+        assert!(history.checkout(BranchId(42)).is_err());
+        history.clear();
+        assert_eq!(history.branch_count(), 1);
+        assert!(history.is_empty());
+    }
+}
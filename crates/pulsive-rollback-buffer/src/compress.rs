@@ -0,0 +1,49 @@
+//! Compression of old frames
+//!
+//! With the `compression` feature, keyframes older than a configurable age
+//! are serialized with bincode and compressed with LZ4. They are
+//! decompressed transparently when accessed.
+
+#[cfg(feature = "compression")]
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Compression settings and functions for a state type
+#[cfg_attr(not(feature = "compression"), allow(dead_code))]
+pub(crate) struct Codec<S> {
+    /// Compress keyframes this many ticks older than the newest tick
+    pub after_ticks: u64,
+    pub compress: fn(&S) -> Option<Vec<u8>>,
+    pub decompress: fn(&[u8]) -> Option<S>,
+}
+
+impl<S> Clone for Codec<S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S> Copy for Codec<S> {}
+
+#[cfg(feature = "compression")]
+impl<S: Serialize + DeserializeOwned> Codec<S> {
+    /// bincode + LZ4 codec
+    pub fn lz4(after_ticks: u64) -> Self {
+        Self {
+            after_ticks,
+            compress: compress::<S>,
+            decompress: decompress::<S>,
+        }
+    }
+}
+
+#[cfg(feature = "compression")]
+fn compress<S: Serialize>(state: &S) -> Option<Vec<u8>> {
+    let raw = bincode::serialize(state).ok()?;
+    Some(lz4_flex::compress_prepend_size(&raw))
+}
+
+#[cfg(feature = "compression")]
+fn decompress<S: DeserializeOwned>(bytes: &[u8]) -> Option<S> {
+    let raw = lz4_flex::decompress_size_prepended(bytes).ok()?;
+    bincode::deserialize(&raw).ok()
+}
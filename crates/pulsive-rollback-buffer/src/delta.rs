@@ -0,0 +1,78 @@
+//! Delta support for buffered state types
+//!
+//! Implement [`DeltaState`] for a state type to enable delta mode in
+//! [`crate::RollbackBuffer`]. [`Model`] implements it using [`ModelDiff`].
+
+use crate::size;
+use pulsive_core::{Model, ModelDiff};
+use std::fmt::Debug;
+
+/// A state type that can be stored as diffs between frames
+pub trait DeltaState: Clone {
+    /// The diff type produced by [`DeltaState::diff`]
+    type Diff: Debug + Send + 'static;
+
+    /// Compute the diff that turns `base` into `target`
+    fn diff(base: &Self, target: &Self) -> Self::Diff;
+
+    /// Apply a diff produced by [`DeltaState::diff`] to its base state
+    fn apply_diff(&mut self, diff: &Self::Diff);
+
+    /// Estimate the memory used by a full state in bytes
+    fn estimated_bytes(&self) -> usize;
+
+    /// Estimate the memory used by a diff in bytes
+    fn diff_estimated_bytes(diff: &Self::Diff) -> usize;
+}
+
+impl DeltaState for Model {
+    type Diff = ModelDiff;
+
+    fn diff(base: &Self, target: &Self) -> ModelDiff {
+        ModelDiff::between(base, target)
+    }
+
+    fn apply_diff(&mut self, diff: &ModelDiff) {
+        diff.apply(self);
+    }
+
+    fn estimated_bytes(&self) -> usize {
+        size::model_bytes(self)
+    }
+
+    fn diff_estimated_bytes(diff: &ModelDiff) -> usize {
+        size::diff_bytes(diff)
+    }
+}
+
+/// Type-erased diff stored in a delta frame
+pub(crate) trait StoredDiff<S>: Debug + Send {
+    fn apply(&self, state: &mut S);
+    fn estimated_bytes(&self) -> usize;
+}
+
+struct Diff<S: DeltaState>(S::Diff);
+
+impl<S: DeltaState> Debug for Diff<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<S: DeltaState + 'static> StoredDiff<S> for Diff<S> {
+    fn apply(&self, state: &mut S) {
+        state.apply_diff(&self.0);
+    }
+
+    fn estimated_bytes(&self) -> usize {
+        S::diff_estimated_bytes(&self.0)
+    }
+}
+
+/// Function computing a stored diff from `base` to `target`
+pub(crate) type Differ<S> = fn(&S, &S) -> Box<dyn StoredDiff<S>>;
+
+/// Differ for a [`DeltaState`] type
+pub(crate) fn differ<S: DeltaState + 'static>(base: &S, target: &S) -> Box<dyn StoredDiff<S>> {
+    Box::new(Diff::<S>(S::diff(base, target)))
+}
@@ -0,0 +1,26 @@
+//! Error types for the rollback buffer
+
+use thiserror::Error;
+
+/// Errors that can occur in rollback buffer operations
+#[derive(Debug, Error)]
+pub enum Error {
+    /// IO error
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// Serialization error
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+
+    /// Backend-specific error
+    #[error("Backend error: {0}")]
+    Backend(String),
+
+    /// Branch does not exist in a branching history
+    #[error("Unknown branch: {0}")]
+    UnknownBranch(u32),
+}
+
+/// Result type for rollback buffer operations
+pub type Result<T> = std::result::Result<T, Error>;
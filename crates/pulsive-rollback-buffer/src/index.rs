@@ -0,0 +1,120 @@
+//! Sorted tick index
+//!
+//! Maps stored ticks to ring slots, kept sorted by tick so lookups are
+//! binary searches. Ticks usually arrive in increasing order and leave from
+//! the oldest end, which makes inserts and removals O(1) in the common case.
+
+use std::collections::VecDeque;
+
+/// Sorted `(tick, slot)` pairs
+#[derive(Debug, Default)]
+pub(crate) struct TickIndex {
+    entries: VecDeque<(u64, usize)>,
+}
+
+impl TickIndex {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    fn position(&self, tick: u64) -> Result<usize, usize> {
+        self.entries.binary_search_by_key(&tick, |(t, _)| *t)
+    }
+
+    /// Get the slot of an exact tick
+    pub fn get(&self, tick: u64) -> Option<usize> {
+        self.position(tick).ok().map(|pos| self.entries[pos].1)
+    }
+
+    /// Get the newest entry strictly before `tick`
+    pub fn prev(&self, tick: u64) -> Option<(u64, usize)> {
+        let pos = self.entries.partition_point(|(t, _)| *t < tick);
+        pos.checked_sub(1).map(|pos| self.entries[pos])
+    }
+
+    /// Get the oldest entry strictly after `tick`
+    pub fn next(&self, tick: u64) -> Option<(u64, usize)> {
+        let pos = self.entries.partition_point(|(t, _)| *t <= tick);
+        self.entries.get(pos).copied()
+    }
+
+    /// Insert a tick or update the slot of an existing one
+    pub fn insert(&mut self, tick: u64, slot: usize) {
+        match self.position(tick) {
+            Ok(pos) => self.entries[pos].1 = slot,
+            Err(pos) if pos == self.entries.len() => self.entries.push_back((tick, slot)),
+            Err(pos) => self.entries.insert(pos, (tick, slot)),
+        }
+    }
+
+    /// Remove a tick
+    pub fn remove(&mut self, tick: u64) {
+        if let Ok(pos) = self.position(tick) {
+            if pos == 0 {
+                self.entries.pop_front();
+            } else {
+                self.entries.remove(pos);
+            }
+        }
+    }
+
+    /// Remove all ticks before `tick`, returning how many were removed
+    pub fn remove_before(&mut self, tick: u64) -> usize {
+        let pos = self.entries.partition_point(|(t, _)| *t < tick);
+        self.entries.drain(..pos);
+        pos
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Oldest and newest tick
+    pub fn range(&self) -> Option<(u64, u64)> {
+        Some((self.entries.front()?.0, self.entries.back()?.0))
+    }
+
+    /// Iterate entries with `from <= tick < to` in tick order
+    pub fn between(&self, from: u64, to: u64) -> impl Iterator<Item = (u64, usize)> + '_ {
+        let start = self.entries.partition_point(|(t, _)| *t < from);
+        let end = self.entries.partition_point(|(t, _)| *t < to);
+        self.entries.range(start..end.max(start)).copied()
+    }
+
+    /// Iterate entries in tick order
+    pub fn iter(&self) -> impl Iterator<Item = (u64, usize)> + '_ {
+        self.entries.iter().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sorted_lookups() {
+        let mut index = TickIndex::default();
+        index.insert(30, 0);
+        index.insert(10, 1);
+        index.insert(20, 2);
+
+        assert_eq!(index.get(20), Some(2));
+        assert_eq!(index.get(25), None);
+        assert_eq!(index.prev(20), Some((10, 1)));
+        assert_eq!(index.prev(10), None);
+        assert_eq!(index.next(20), Some((30, 0)));
+        assert_eq!(index.next(30), None);
+        assert_eq!(index.range(), Some((10, 30)));
+        assert_eq!(
+            index.between(10, 30).collect::<Vec<_>>(),
+            vec![(10, 1), (20, 2)]
+        );
+
+        index.remove(10);
+        assert_eq!(index.range(), Some((20, 30)));
+        assert_eq!(index.remove_before(30), 1);
+        assert_eq!(index.iter().collect::<Vec<_>>(), vec![(30, 0)]);
+    }
+}
@@ -6,9 +6,13 @@
 //! # Features
 //!
 //! - **Bounded memory**: Fixed-size ring buffer, no unbounded growth
-//! - **O(1) insertion**: Constant time to save new states
-//! - **Fast lookup**: Quick access to recent states
+//! - **O(1) insertion**: Constant time to save new states in increasing tick order
+//! - **Fast lookup**: O(log n) exact and nearest-tick lookups, O(1) tick range
 //! - **Automatic eviction**: Old states are automatically removed
+//! - **Delta compression**: Optional keyframes + diffs to reduce memory usage
+//! - **Frame compression**: Optional LZ4 compression of old frames (`compression` feature)
+//! - **Spillover**: Optionally persist evicted frames to disk or pulsive-db
+//! - **Branching**: [`BranchingHistory`] keeps forkable what-if timelines
 //!
 //! # Example
 //!
@@ -36,26 +40,137 @@
 //! }
 //! ```
 
+mod branch;
+mod compress;
+mod delta;
+mod error;
+mod index;
+mod size;
+mod spill;
+
+pub use branch::{BranchId, BranchingHistory};
+pub use delta::DeltaState;
+pub use error::{Error, Result};
+#[cfg(feature = "persistence")]
+pub use spill::DirectoryBackend;
+pub use spill::{SpillBackend, SpilloverPolicy};
+
+use compress::Codec;
+use delta::{Differ, StoredDiff};
+use index::TickIndex;
 use pulsive_core::{Model, StateHistory};
+use spill::LoadedStates;
+use std::cell::{Cell, OnceCell, RefCell};
+use std::fmt;
+
+/// A stored frame
+enum Frame<S> {
+    /// Full state
+    Keyframe(S),
+    /// Diff from the next stored frame back to this frame's state
+    ///
+    /// The reconstructed state is cached on first access so it can be
+    /// returned by reference.
+    Delta {
+        diff: Box<dyn StoredDiff<S>>,
+        cache: OnceCell<S>,
+    },
+    /// Serialized and compressed full state
+    ///
+    /// The decompressed state is cached on first access.
+    Compressed { bytes: Vec<u8>, cache: OnceCell<S> },
+}
 
-/// A ring buffer for storing recent model states
+impl<S> Frame<S> {
+    fn is_delta(&self) -> bool {
+        matches!(self, Frame::Delta { .. })
+    }
+}
+
+/// A ring buffer for storing recent states
+///
+/// Generic over the stored state type `S`, which defaults to [`Model`]. Any
+/// `Clone` type can be buffered with [`RollbackBuffer::with_capacity`], e.g.
+/// custom per-player state or render snapshots.
 ///
 /// Optimized for real-time applications where only recent history is needed.
 /// Older states are automatically evicted when the buffer is full.
-#[derive(Debug)]
-pub struct RollbackBuffer {
-    /// Ring buffer storage: (tick, model)
+///
+/// Slots are assigned in save order, independent of tick values, so the
+/// `capacity` most recently saved ticks are always retrievable no matter how
+/// sparse they are. Re-saving a tick that is already stored replaces it and
+/// makes it the most recently saved entry.
+///
+/// # Delta mode
+///
+/// With a keyframe interval `K > 1` (see [`RollbackBuffer::with_keyframe_interval`]),
+/// only every Kth frame is stored in full. The frames in between store a
+/// diff against the next newer frame and are reconstructed on demand. Delta
+/// mode is available for state types implementing [`DeltaState`].
+///
+/// - The newest frame is always a keyframe, so saving and rolling back to
+///   recent ticks stays cheap
+/// - Evicting the oldest frame never invalidates other frames
+/// - Reconstructing a frame applies at most `K - 1` diffs
+///
+/// # Memory budget
+///
+/// Frame count is a poor proxy for memory when state sizes vary. With
+/// [`RollbackBuffer::with_memory_budget`], the oldest saved states are also
+/// evicted whenever the estimated memory usage exceeds the budget. The newest
+/// state is always kept, even if it alone exceeds the budget.
+///
+/// # Compression
+///
+/// With the `compression` feature, [`RollbackBuffer::with_compression`]
+/// serializes and LZ4-compresses keyframes once they are older than a given
+/// number of ticks. Recent frames stay uncompressed for fast rollback, while
+/// old frames take much less memory and are decompressed on access.
+///
+/// # Spillover
+///
+/// With [`RollbackBuffer::with_spillover`], evicted frames are written to a
+/// [`SpillBackend`] instead of being dropped. [`StateHistory::get_state`]
+/// falls back to the backend for ticks no longer in memory.
+pub struct RollbackBuffer<S: Clone = Model> {
+    /// Ring buffer storage in save order: (tick, frame)
     /// None means the slot is empty
-    states: Vec<Option<(u64, Model)>>,
-    /// Current write position in the ring buffer
+    states: Vec<Option<(u64, Frame<S>)>>,
+    /// Tick to slot index, sorted by tick
+    slots: TickIndex,
+    /// Current write position in the ring buffer (one past the newest entry)
     head: usize,
     /// Number of states currently stored
     count: usize,
     /// Capacity (max states)
     capacity: usize,
+    /// Store a full keyframe every this many frames (1 = no deltas)
+    keyframe_interval: usize,
+    /// Diff function for delta mode
+    differ: Option<Differ<S>>,
+    /// Memory estimator for full states
+    estimate: fn(&S) -> usize,
+    /// Maximum estimated memory usage in bytes
+    memory_budget: Option<usize>,
+    /// Estimated memory usage of stored frames (tracked in budget mode only)
+    used_bytes: usize,
+    /// Compression of old frames
+    codec: Option<Codec<S>>,
+    /// Keyframes before this tick have been considered for compression
+    compressed_before: u64,
+    /// Where evicted frames go
+    spillover: Option<SpilloverPolicy<S>>,
+    /// States loaded back from the spillover backend
+    loaded: LoadedStates<S>,
+    /// Number of frames written to the spillover backend
+    spilled: usize,
+    /// Number of failed spillover backend operations
+    spill_errors: Cell<usize>,
+    /// Slots whose frame cached a state since the caches were last cleared
+    cached: RefCell<Vec<usize>>,
 }
 
-impl RollbackBuffer {
+impl RollbackBuffer<Model> {
     /// Create a new rollback buffer with the given capacity
     ///
     /// # Arguments
@@ -71,18 +186,242 @@ impl RollbackBuffer {
     /// let buffer = RollbackBuffer::new(128);
     /// ```
     pub fn new(capacity: usize) -> Self {
+        Self::with_capacity(capacity).with_size_estimator(Model::estimated_bytes)
+    }
+}
+
+impl<S: DeltaState + 'static> RollbackBuffer<S> {
+    /// Create a new rollback buffer that stores deltas between keyframes
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - Maximum number of states to store
+    /// * `keyframe_interval` - Store a full model every this many frames
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use pulsive_core::Model;
+    /// use pulsive_rollback_buffer::RollbackBuffer;
+    ///
+    /// // Full model every 8 frames, diffs in between
+    /// let buffer = RollbackBuffer::<Model>::with_keyframe_interval(128, 8);
+    /// ```
+    pub fn with_keyframe_interval(capacity: usize, keyframe_interval: usize) -> Self {
+        assert!(
+            keyframe_interval > 0,
+            "Keyframe interval must be greater than 0"
+        );
+        let mut buffer = Self::with_capacity(capacity).with_size_estimator(S::estimated_bytes);
+        buffer.keyframe_interval = keyframe_interval;
+        buffer.differ = Some(delta::differ::<S>);
+        buffer
+    }
+}
+
+impl<S: Clone> RollbackBuffer<S> {
+    /// Create a new rollback buffer for any cloneable state type
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use pulsive_core::StateHistory;
+    /// use pulsive_rollback_buffer::RollbackBuffer;
+    ///
+    /// #[derive(Clone)]
+    /// struct PlayerState {
+    ///     position: (f32, f32),
+    /// }
+    ///
+    /// let mut buffer = RollbackBuffer::<PlayerState>::with_capacity(64);
+    /// buffer.save_state(0, &PlayerState { position: (1.0, 2.0) });
+    /// assert_eq!(buffer.get_state(0).unwrap().position, (1.0, 2.0));
+    /// ```
+    pub fn with_capacity(capacity: usize) -> Self {
         assert!(capacity > 0, "Capacity must be greater than 0");
         Self {
             states: (0..capacity).map(|_| None).collect(),
+            slots: TickIndex::with_capacity(capacity),
             head: 0,
             count: 0,
             capacity,
+            keyframe_interval: 1,
+            differ: None,
+            estimate: std::mem::size_of_val::<S>,
+            memory_budget: None,
+            used_bytes: 0,
+            codec: None,
+            compressed_before: 0,
+            spillover: None,
+            loaded: LoadedStates::new(),
+            spilled: 0,
+            spill_errors: Cell::new(0),
+            cached: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Also evict the oldest states when estimated memory exceeds a budget
+    ///
+    /// Memory is estimated with the state type's size estimator (see
+    /// [`RollbackBuffer::with_size_estimator`]), so set an accurate one for
+    /// types with heap data.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use pulsive_rollback_buffer::RollbackBuffer;
+    ///
+    /// // At most 1024 frames or ~16 MiB, whichever is reached first
+    /// let buffer = RollbackBuffer::new(1024).with_memory_budget(16 * 1024 * 1024);
+    /// ```
+    pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = Some(bytes);
+        self.used_bytes = self
+            .states
+            .iter()
+            .flatten()
+            .map(|(_, frame)| self.frame_bytes(frame))
+            .sum();
+        self
+    }
+
+    /// Get the memory budget in bytes, if any
+    pub fn memory_budget(&self) -> Option<usize> {
+        self.memory_budget
+    }
+
+    /// Compress keyframes older than `after_ticks` behind the newest tick
+    ///
+    /// Frames are serialized with bincode and compressed with LZ4, then
+    /// transparently decompressed when accessed. Delta frames are already
+    /// small and are left as they are.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use pulsive_rollback_buffer::RollbackBuffer;
+    ///
+    /// // Keep the last 16 ticks uncompressed for fast rollback
+    /// let buffer = RollbackBuffer::new(1024).with_compression(16);
+    /// ```
+    #[cfg(feature = "compression")]
+    pub fn with_compression(mut self, after_ticks: u64) -> Self
+    where
+        S: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        self.codec = Some(Codec::lz4(after_ticks));
+        self
+    }
+
+    /// Write evicted frames to a backend instead of dropping them
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use pulsive_rollback_buffer::{DirectoryBackend, RollbackBuffer, SpilloverPolicy};
+    ///
+    /// // Keep 2 seconds in memory, and one frame per second on disk
+    /// let backend = DirectoryBackend::new("replay-frames")?;
+    /// let buffer = RollbackBuffer::new(120)
+    ///     .with_spillover(SpilloverPolicy::new(backend).every_nth(60));
+    /// ```
+    pub fn with_spillover(mut self, policy: SpilloverPolicy<S>) -> Self {
+        self.spillover = Some(policy);
+        self
+    }
+
+    /// Get the spillover policy, if any
+    pub fn spillover(&self) -> Option<&SpilloverPolicy<S>> {
+        self.spillover.as_ref()
+    }
+
+    /// Evict an occupied slot, spilling its state if a policy is set
+    fn evict_at(&mut self, index: usize) {
+        if let Some(mut policy) = self.spillover.take() {
+            let tick = self.slot(index).0;
+            if policy.should_spill(tick) {
+                match policy.backend.store(tick, self.model_at(index)) {
+                    Ok(()) => self.spilled += 1,
+                    Err(_) => self.spill_errors.set(self.spill_errors.get() + 1),
+                }
+            }
+            self.spillover = Some(policy);
+        }
+        self.take_at(index);
+        self.count -= 1;
+    }
+
+    /// Load a spilled state from the backend
+    fn load_spilled(&self, tick: u64) -> Option<&S> {
+        let policy = self.spillover.as_ref()?;
+        self.loaded
+            .get_or_load(tick, || match policy.backend.load(tick) {
+                Ok(state) => state,
+                Err(_) => {
+                    self.spill_errors.set(self.spill_errors.get() + 1);
+                    None
+                }
+            })
+    }
+
+    /// Use a custom memory estimator for stored states
+    ///
+    /// By default only the inline size of `S` is counted, which ignores
+    /// heap allocations. Used for [`BufferStats::estimated_bytes`].
+    pub fn with_size_estimator(mut self, estimate: fn(&S) -> usize) -> Self {
+        self.estimate = estimate;
+        self
+    }
+
+    /// Estimate the memory used by a stored frame
+    fn frame_bytes(&self, frame: &Frame<S>) -> usize {
+        match frame {
+            Frame::Keyframe(state) => (self.estimate)(state),
+            Frame::Delta { diff, .. } => diff.estimated_bytes(),
+            Frame::Compressed { bytes, .. } => std::mem::size_of::<Frame<S>>() + bytes.len(),
+        }
+    }
+
+    /// Track the memory of a frame entering (`added`) or leaving the buffer
+    fn track_bytes(&mut self, frame: &Frame<S>, added: bool) {
+        if self.memory_budget.is_none() {
+            return;
+        }
+        let bytes = self.frame_bytes(frame);
+        if added {
+            self.used_bytes += bytes;
+        } else {
+            self.used_bytes = self.used_bytes.saturating_sub(bytes);
         }
     }
 
-    /// Get the index for a given tick (if it would be in the buffer)
-    fn tick_to_index(&self, tick: u64) -> usize {
-        (tick as usize) % self.capacity
+    /// Replace the frame in an occupied slot
+    fn replace_frame(&mut self, index: usize, frame: Frame<S>) {
+        self.track_bytes(&frame, true);
+        if let Some((_, slot)) = &mut self.states[index] {
+            let old = std::mem::replace(slot, frame);
+            self.track_bytes(&old, false);
+        }
+    }
+
+    /// Evict the oldest saved states until memory is within budget
+    fn enforce_budget(&mut self) {
+        let Some(budget) = self.memory_budget else {
+            return;
+        };
+        while self.used_bytes > budget && self.count > 1 {
+            self.evict_at(self.tail());
+        }
+    }
+
+    /// Get the keyframe interval (1 means every frame is stored in full)
+    pub fn keyframe_interval(&self) -> usize {
+        self.keyframe_interval
+    }
+
+    /// Get the slot index of the oldest saved entry
+    fn tail(&self) -> usize {
+        (self.head + self.capacity - self.count) % self.capacity
     }
 
     /// Check if a tick is within the current valid range
@@ -100,92 +439,369 @@ impl RollbackBuffer {
         }
     }
 
+    /// Get the slot index holding exactly the given tick
+    fn index_of(&self, tick: u64) -> Option<usize> {
+        self.slots.get(tick)
+    }
+
+    /// Get the slot index of the newest stored tick strictly before `tick`
+    fn prev_index(&self, tick: u64) -> Option<usize> {
+        self.slots.prev(tick).map(|(_, i)| i)
+    }
+
+    /// Get the slot index of the oldest stored tick strictly after `tick`
+    fn next_index(&self, tick: u64) -> Option<usize> {
+        self.slots.next(tick).map(|(_, i)| i)
+    }
+
+    fn slot(&self, index: usize) -> &(u64, Frame<S>) {
+        self.states[index].as_ref().expect("slot must be occupied")
+    }
+
+    /// Get the state stored in an occupied slot, reconstructing deltas
+    fn model_at(&self, index: usize) -> &S {
+        match &self.slot(index).1 {
+            Frame::Keyframe(model) => model,
+            Frame::Delta { cache, .. } => cache.get_or_init(|| {
+                self.cached.borrow_mut().push(index);
+                self.reconstruct(index)
+            }),
+            Frame::Compressed { bytes, cache } => cache.get_or_init(|| {
+                self.cached.borrow_mut().push(index);
+                self.decompress(bytes)
+            }),
+        }
+    }
+
+    /// Decompress a compressed frame's state
+    fn decompress(&self, bytes: &[u8]) -> S {
+        let codec = self.codec.expect("compressed frame requires a codec");
+        (codec.decompress)(bytes).expect("compressed frame must decode")
+    }
+
+    /// Compress keyframes that are now old enough
+    fn compress_old_frames(&mut self) {
+        let (Some(codec), Some((_, newest))) = (self.codec, self.slots.range()) else {
+            return;
+        };
+        let threshold = newest.saturating_sub(codec.after_ticks);
+        if threshold <= self.compressed_before {
+            return;
+        }
+
+        let targets: Vec<usize> = self
+            .slots
+            .between(self.compressed_before, threshold)
+            .map(|(_, index)| index)
+            .collect();
+        for index in targets {
+            self.compress_at(index);
+        }
+        self.compressed_before = threshold;
+    }
+
+    /// Compress the frame in an occupied slot if it is a keyframe
+    fn compress_at(&mut self, index: usize) {
+        let Some(codec) = self.codec else {
+            return;
+        };
+        if let Frame::Keyframe(state) = &self.slot(index).1 {
+            if let Some(bytes) = (codec.compress)(state) {
+                self.replace_frame(
+                    index,
+                    Frame::Compressed {
+                        bytes,
+                        cache: OnceCell::new(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Rebuild the state of a delta frame from the next keyframe
+    fn reconstruct(&self, index: usize) -> S {
+        // Walk forward to the nearest full (or already reconstructed) state
+        let mut diffs = Vec::new();
+        let mut current = index;
+        let mut model = loop {
+            let (tick, frame) = self.slot(current);
+            match frame {
+                Frame::Keyframe(model) => break model.clone(),
+                Frame::Delta { cache, .. } if current != index && cache.get().is_some() => {
+                    break cache.get().unwrap().clone()
+                }
+                Frame::Compressed { bytes, cache } => {
+                    break cache
+                        .get()
+                        .cloned()
+                        .unwrap_or_else(|| self.decompress(bytes))
+                }
+                Frame::Delta { diff, .. } => {
+                    diffs.push(diff);
+                    current = self
+                        .next_index(*tick)
+                        .expect("delta frame must have a newer frame");
+                }
+            }
+        };
+
+        // Apply diffs from newest back to the requested frame
+        for diff in diffs.into_iter().rev() {
+            diff.apply(&mut model);
+        }
+        model
+    }
+
+    /// Turn a delta frame into a keyframe
+    fn materialize(&mut self, index: usize) {
+        if !self.slot(index).1.is_delta() {
+            return;
+        }
+        let model = match &mut self.states[index] {
+            Some((_, Frame::Delta { cache, .. })) => cache.take(),
+            _ => None,
+        }
+        .unwrap_or_else(|| self.reconstruct(index));
+        self.replace_frame(index, Frame::Keyframe(model));
+    }
+
+    /// Take the frame out of an occupied slot, keeping the frames that
+    /// depend on it valid
+    ///
+    /// The slot is left empty and removed from the tick index.
+    fn take_at(&mut self, index: usize) {
+        // Only the previous frame can be a delta against this one
+        if self.keyframe_interval > 1 {
+            if let Some(prev) = self.prev_index(self.slot(index).0) {
+                self.materialize(prev);
+            }
+        }
+        if let Some((tick, frame)) = self.states[index].take() {
+            self.slots.remove(tick);
+            self.track_bytes(&frame, false);
+        }
+    }
+
+    /// Move every entry saved after `index` one slot back, freeing the
+    /// newest slot
+    fn close_gap(&mut self, index: usize) {
+        let newest = (self.head + self.capacity - 1) % self.capacity;
+        let mut current = index;
+        while current != newest {
+            let next = (current + 1) % self.capacity;
+            let entry = self.states[next].take();
+            if let Some((tick, _)) = &entry {
+                self.slots.insert(*tick, current);
+            }
+            self.states[current] = entry;
+            current = next;
+        }
+    }
+
+    /// Rewrite the ring so live entries are contiguous, oldest first
+    fn compact(&mut self) {
+        let tail = self.tail();
+        let entries: Vec<_> = (0..self.capacity)
+            .filter_map(|offset| self.states[(tail + offset) % self.capacity].take())
+            .collect();
+        self.slots.clear();
+        self.count = entries.len();
+        self.head = self.count % self.capacity;
+        for (index, entry) in entries.into_iter().enumerate() {
+            self.slots.insert(entry.0, index);
+            self.states[index] = Some(entry);
+        }
+    }
+
+    /// Count the delta frames chained directly before the given tick
+    fn deltas_before(&self, tick: u64) -> usize {
+        let mut run = 0;
+        let mut current = tick;
+        while let Some(prev) = self.prev_index(current) {
+            let (t, frame) = self.slot(prev);
+            if !frame.is_delta() {
+                break;
+            }
+            run += 1;
+            current = *t;
+        }
+        run
+    }
+
+    /// Drop states cached by delta and compressed frames, and states
+    /// loaded back from the spillover backend
+    ///
+    /// Only the slots recorded in `cached` are visited, so this costs
+    /// nothing when no cache was filled. Must run before entries move
+    /// between slots.
+    fn clear_cache(&mut self) {
+        self.loaded.clear();
+        for index in self.cached.get_mut().drain(..) {
+            if let Some((_, Frame::Delta { cache, .. } | Frame::Compressed { cache, .. })) =
+                &mut self.states[index]
+            {
+                cache.take();
+            }
+        }
+    }
+
     /// Get all stored states as an iterator (oldest to newest)
-    pub fn iter(&self) -> impl Iterator<Item = (u64, &Model)> {
-        // Collect valid states and sort by tick
-        let mut states: Vec<_> = self
-            .states
+    pub fn iter(&self) -> impl Iterator<Item = (u64, &S)> {
+        self.slots
             .iter()
-            .filter_map(|s| s.as_ref().map(|(t, m)| (*t, m)))
-            .collect();
-        states.sort_by_key(|(t, _)| *t);
-        states.into_iter()
+            .map(|(tick, index)| (tick, self.model_at(index)))
     }
 
     /// Get statistics about the buffer
     pub fn stats(&self) -> BufferStats {
         let (oldest, newest) = self.tick_range().unwrap_or((0, 0));
+        let mut keyframes = 0;
+        let mut deltas = 0;
+        let mut compressed = 0;
+        let mut estimated_bytes = 0;
+        for (_, frame) in self.states.iter().flatten() {
+            estimated_bytes += self.frame_bytes(frame);
+            match frame {
+                Frame::Keyframe(_) => keyframes += 1,
+                Frame::Delta { .. } => deltas += 1,
+                Frame::Compressed { .. } => compressed += 1,
+            }
+        }
         BufferStats {
             capacity: self.capacity,
             count: self.count,
             oldest_tick: oldest,
             newest_tick: newest,
+            keyframes,
+            deltas,
+            compressed,
+            estimated_bytes,
+            memory_budget: self.memory_budget,
+            spilled: self.spilled,
+            spill_errors: self.spill_errors.get(),
         }
     }
 }
 
-impl StateHistory for RollbackBuffer {
-    fn save_state(&mut self, tick: u64, model: &Model) {
-        // Calculate index for this tick
-        let index = self.tick_to_index(tick);
+impl<S: Clone> StateHistory<S> for RollbackBuffer<S> {
+    fn save_state(&mut self, tick: u64, model: &S) {
+        self.clear_cache();
 
-        // Check if we're overwriting an existing state
-        let was_empty = self.states[index].is_none();
+        let index = if let Some(existing) = self.index_of(tick) {
+            // Re-saving a tick: drop the old version and make it the newest
+            self.take_at(existing);
+            self.close_gap(existing);
+            (self.head + self.capacity - 1) % self.capacity
+        } else {
+            // Evict the oldest saved entry if the buffer is full
+            if self.count == self.capacity {
+                self.evict_at(self.head);
+            }
+            let index = self.head;
+            self.head = (self.head + 1) % self.capacity;
+            self.count += 1;
+            index
+        };
+
+        // The previous frame may be a delta against the frame after this tick
+        let prev = if self.differ.is_some() && self.keyframe_interval > 1 {
+            self.prev_index(tick)
+        } else {
+            None
+        };
+        if let Some(prev) = prev {
+            self.materialize(prev);
+        }
 
         // Store the state
-        self.states[index] = Some((tick, model.clone()));
+        let frame = Frame::Keyframe(model.clone());
+        self.track_bytes(&frame, true);
+        self.states[index] = Some((tick, frame));
+        self.slots.insert(tick, index);
 
-        // Update count if we used a new slot
-        if was_empty && self.count < self.capacity {
-            self.count += 1;
+        // Turn the previous frame into a delta against this one unless it
+        // has to stay a keyframe to bound the reconstruction chain
+        if let Some(prev) = prev {
+            let prev_tick = self.slot(prev).0;
+            if self.deltas_before(prev_tick) + 1 < self.keyframe_interval {
+                if let (Frame::Keyframe(prev_model), Some(differ)) =
+                    (&self.slot(prev).1, self.differ)
+                {
+                    let diff = differ(model, prev_model);
+                    self.replace_frame(
+                        prev,
+                        Frame::Delta {
+                            diff,
+                            cache: OnceCell::new(),
+                        },
+                    );
+                }
+            }
         }
 
-        // Update head to point to next slot
-        self.head = (index + 1) % self.capacity;
+        // Frames re-saved behind the compression watermark are compressed
+        // right away, the others once they age past it
+        if tick < self.compressed_before {
+            self.compress_at(index);
+        }
+        self.compress_old_frames();
+
+        self.enforce_budget();
     }
 
-    fn get_state(&self, tick: u64) -> Option<&Model> {
-        let index = self.tick_to_index(tick);
-        self.states[index]
-            .as_ref()
-            .filter(|(t, _)| *t == tick)
-            .map(|(_, m)| m)
+    fn get_state(&self, tick: u64) -> Option<&S> {
+        match self.index_of(tick) {
+            Some(index) => Some(self.model_at(index)),
+            None => self.load_spilled(tick),
+        }
     }
 
-    fn get_nearest_before(&self, tick: u64) -> Option<(u64, &Model)> {
-        self.states
-            .iter()
-            .filter_map(|s| s.as_ref())
-            .filter(|(t, _)| *t <= tick)
-            .max_by_key(|(t, _)| *t)
-            .map(|(t, m)| (*t, m))
+    fn get_nearest_before(&self, tick: u64) -> Option<(u64, &S)> {
+        let index = self.index_of(tick).or_else(|| self.prev_index(tick))?;
+        Some((self.slot(index).0, self.model_at(index)))
     }
 
-    fn get_nearest_after(&self, tick: u64) -> Option<(u64, &Model)> {
-        self.states
-            .iter()
-            .filter_map(|s| s.as_ref())
-            .filter(|(t, _)| *t >= tick)
-            .min_by_key(|(t, _)| *t)
-            .map(|(t, m)| (*t, m))
+    fn get_nearest_after(&self, tick: u64) -> Option<(u64, &S)> {
+        let index = self.index_of(tick).or_else(|| self.next_index(tick))?;
+        Some((self.slot(index).0, self.model_at(index)))
     }
 
     fn clear_before(&mut self, tick: u64) {
-        for state in &mut self.states {
-            if let Some((t, _)) = state {
-                if *t < tick {
-                    *state = None;
-                    self.count = self.count.saturating_sub(1);
+        if let Some(policy) = &mut self.spillover {
+            if policy.backend.remove_before(tick).is_err() {
+                self.spill_errors.set(self.spill_errors.get() + 1);
+            }
+        }
+        self.clear_cache();
+
+        // Deltas only depend on newer frames, so dropping older ones is safe
+        if self.slots.remove_before(tick) == 0 {
+            return;
+        }
+        for index in 0..self.capacity {
+            if matches!(&self.states[index], Some((t, _)) if *t < tick) {
+                if let Some((_, frame)) = self.states[index].take() {
+                    self.track_bytes(&frame, false);
                 }
             }
         }
+        self.compact();
     }
 
     fn clear(&mut self) {
+        if let Some(policy) = &mut self.spillover {
+            if policy.backend.clear().is_err() {
+                self.spill_errors.set(self.spill_errors.get() + 1);
+            }
+        }
+        self.clear_cache();
         for state in &mut self.states {
             *state = None;
         }
+        self.slots.clear();
         self.count = 0;
+        self.used_bytes = 0;
+        self.compressed_before = 0;
         self.head = 0;
     }
 
@@ -198,23 +814,18 @@ impl StateHistory for RollbackBuffer {
     }
 
     fn tick_range(&self) -> Option<(u64, u64)> {
-        if self.count == 0 {
-            return None;
-        }
-
-        let mut min_tick = u64::MAX;
-        let mut max_tick = 0u64;
-
-        for (t, _) in self.states.iter().flatten() {
-            min_tick = min_tick.min(*t);
-            max_tick = max_tick.max(*t);
-        }
+        self.slots.range()
+    }
+}
 
-        if min_tick == u64::MAX {
-            None
-        } else {
-            Some((min_tick, max_tick))
-        }
+impl<S: Clone> fmt::Debug for RollbackBuffer<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RollbackBuffer")
+            .field("capacity", &self.capacity)
+            .field("count", &self.count)
+            .field("keyframe_interval", &self.keyframe_interval)
+            .field("tick_range", &self.tick_range())
+            .finish_non_exhaustive()
     }
 }
 
@@ -235,6 +846,20 @@ pub struct BufferStats {
     pub oldest_tick: u64,
     /// Newest tick in the buffer
     pub newest_tick: u64,
+    /// Number of frames stored as full models
+    pub keyframes: usize,
+    /// Number of frames stored as diffs
+    pub deltas: usize,
+    /// Number of frames stored compressed
+    pub compressed: usize,
+    /// Estimated memory used by stored frames in bytes
+    pub estimated_bytes: usize,
+    /// Memory budget in bytes, if any
+    pub memory_budget: Option<usize>,
+    /// Number of evicted frames written to the spillover backend
+    pub spilled: usize,
+    /// Number of failed spillover backend operations
+    pub spill_errors: usize,
 }
 
 impl BufferStats {
@@ -251,6 +876,12 @@ impl BufferStats {
     pub fn fill_ratio(&self) -> f32 {
         self.count as f32 / self.capacity as f32
     }
+
+    /// Get the fraction of the memory budget in use, if a budget is set
+    pub fn budget_ratio(&self) -> Option<f32> {
+        self.memory_budget
+            .map(|budget| self.estimated_bytes as f32 / budget.max(1) as f32)
+    }
 }
 
 #[cfg(test)]
@@ -392,4 +1023,328 @@ mod tests {
         assert_eq!(stats.newest_tick, 30);
         assert_eq!(stats.tick_range(), 20);
     }
+
+    #[test]
+    fn test_custom_state_type() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct PlayerState {
+            x: f32,
+        }
+
+        let mut buffer = RollbackBuffer::<PlayerState>::with_capacity(2);
+        buffer.save_state(1, &PlayerState { x: 1.0 });
+        buffer.save_state(2, &PlayerState { x: 2.0 });
+        buffer.save_state(3, &PlayerState { x: 3.0 });
+
+        assert!(buffer.get_state(1).is_none());
+        assert_eq!(buffer.get_state(2), Some(&PlayerState { x: 2.0 }));
+        assert_eq!(
+            buffer.get_nearest_before(10),
+            Some((3, &PlayerState { x: 3.0 }))
+        );
+        assert_eq!(buffer.stats().count, 2);
+    }
+
+    #[test]
+    fn test_memory_budget_eviction() {
+        let mut buffer = RollbackBuffer::new(64);
+        let mut model = Model::new();
+        for _ in 0..20 {
+            model.entities_mut().create("unit").set("hp", 100.0f64);
+        }
+        buffer.save_state(0, &model);
+        let per_state = buffer.stats().estimated_bytes;
+
+        let mut buffer = RollbackBuffer::new(64).with_memory_budget(per_state * 5);
+        for tick in 0..20 {
+            buffer.save_state(tick, &model);
+        }
+
+        // Frame capacity allows 64, the budget only 5
+        assert_eq!(buffer.len(), 5);
+        assert_eq!(buffer.tick_range(), Some((15, 19)));
+        let stats = buffer.stats();
+        assert!(stats.estimated_bytes <= per_state * 5);
+        assert_eq!(stats.memory_budget, Some(per_state * 5));
+        assert!(stats.budget_ratio().unwrap() <= 1.0);
+
+        // The newest state is kept even if it exceeds the budget alone
+        let mut tiny = RollbackBuffer::new(64).with_memory_budget(1);
+        tiny.save_state(0, &model);
+        tiny.save_state(1, &model);
+        assert_eq!(tiny.len(), 1);
+        assert!(tiny.get_state(1).is_some());
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_compression_of_old_frames() {
+        let mut full = RollbackBuffer::new(64);
+        let mut buffer = RollbackBuffer::new(64).with_compression(4);
+        for tick in 0..20 {
+            let mut model = model_at_tick(tick);
+            for _ in 0..50 {
+                model.entities_mut().create("unit").set("hp", 100.0f64);
+            }
+            full.save_state(tick, &model);
+            buffer.save_state(tick, &model);
+        }
+
+        let stats = buffer.stats();
+        assert_eq!(stats.compressed, 15);
+        assert_eq!(stats.keyframes, 5);
+        assert!(stats.estimated_bytes < full.stats().estimated_bytes);
+
+        // Compressed frames decompress transparently
+        for tick in 0..20 {
+            let state = buffer.get_state(tick).unwrap();
+            assert_eq!(state.get_global("tick"), Some(&(tick as i64).into()));
+            assert_eq!(state.entities().len(), 51);
+        }
+
+        // Re-saving an old tick keeps it compressed
+        buffer.save_state(2, &model_at_tick(200));
+        assert_eq!(buffer.stats().compressed, 15);
+        assert_eq!(
+            buffer.get_state(2).unwrap().get_global("tick"),
+            Some(&200i64.into())
+        );
+    }
+
+    #[derive(Default)]
+    struct MemoryBackend {
+        frames: std::sync::Arc<std::sync::Mutex<Vec<(u64, Model)>>>,
+    }
+
+    impl SpillBackend<Model> for MemoryBackend {
+        fn store(&mut self, tick: u64, state: &Model) -> Result<()> {
+            self.frames.lock().unwrap().push((tick, state.clone()));
+            Ok(())
+        }
+
+        fn load(&self, tick: u64) -> Result<Option<Model>> {
+            let frames = self.frames.lock().unwrap();
+            Ok(frames
+                .iter()
+                .find(|(t, _)| *t == tick)
+                .map(|(_, m)| m.clone()))
+        }
+
+        fn remove_before(&mut self, tick: u64) -> Result<()> {
+            self.frames.lock().unwrap().retain(|(t, _)| *t >= tick);
+            Ok(())
+        }
+
+        fn clear(&mut self) -> Result<()> {
+            self.frames.lock().unwrap().clear();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_spillover_to_backend() {
+        let backend = MemoryBackend::default();
+        let frames = backend.frames.clone();
+        let mut buffer = RollbackBuffer::with_keyframe_interval(4, 2)
+            .with_spillover(SpilloverPolicy::new(backend).every_nth(2));
+
+        for tick in 0..10 {
+            buffer.save_state(tick, &model_at_tick(tick));
+        }
+
+        // Even evicted ticks were spilled, including reconstructed deltas
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(buffer.stats().spilled, 3);
+        assert_eq!(frames.lock().unwrap().len(), 3);
+
+        // Spilled ticks are loaded back transparently
+        assert_eq!(
+            buffer.get_state(2).unwrap().get_global("tick"),
+            Some(&2i64.into())
+        );
+        assert!(buffer.get_state(3).is_none());
+        assert!(buffer.get_state(9).is_some());
+
+        buffer.clear_before(4);
+        assert!(buffer.get_state(2).is_none());
+        assert!(buffer.get_state(4).is_some());
+        assert_eq!(frames.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_sparse_ticks_do_not_collide() {
+        let mut buffer = RollbackBuffer::new(64);
+        let model = Model::new();
+
+        // 10 and 74 map to the same slot with modulo indexing
+        buffer.save_state(10, &model);
+        buffer.save_state(74, &model);
+
+        assert_eq!(buffer.len(), 2);
+        assert!(buffer.get_state(10).is_some());
+        assert!(buffer.get_state(74).is_some());
+    }
+
+    #[test]
+    fn test_evicts_oldest_saved() {
+        let mut buffer = RollbackBuffer::new(3);
+        let model = Model::new();
+
+        buffer.save_state(100, &model);
+        buffer.save_state(5, &model);
+        buffer.save_state(1000, &model);
+
+        // Re-saving makes tick 100 the most recently saved
+        buffer.save_state(100, &model);
+        assert_eq!(buffer.len(), 3);
+
+        buffer.save_state(7, &model);
+        assert!(buffer.get_state(5).is_none());
+        assert!(buffer.get_state(100).is_some());
+        assert!(buffer.get_state(1000).is_some());
+        assert!(buffer.get_state(7).is_some());
+        assert_eq!(buffer.len(), 3);
+    }
+
+    #[test]
+    fn test_clear_before_frees_slots() {
+        let mut buffer = RollbackBuffer::new(4);
+        let model = Model::new();
+
+        for tick in 0..4 {
+            buffer.save_state(tick, &model);
+        }
+        buffer.clear_before(2);
+        assert_eq!(buffer.len(), 2);
+
+        // Freed slots are reused before evicting anything
+        buffer.save_state(4, &model);
+        buffer.save_state(5, &model);
+        assert_eq!(buffer.len(), 4);
+        assert_eq!(buffer.tick_range(), Some((2, 5)));
+        assert!(buffer.get_state(2).is_some());
+    }
+
+    fn model_at_tick(tick: u64) -> Model {
+        let mut model = Model::new();
+        let id = model.entities_mut().create("unit").id;
+        model
+            .entities_mut()
+            .get_mut(id)
+            .unwrap()
+            .set("x", tick as f64);
+        model.set_global("tick", tick as i64);
+        model
+    }
+
+    #[test]
+    fn test_delta_mode_reconstructs_states() {
+        let mut buffer = RollbackBuffer::with_keyframe_interval(16, 4);
+        for tick in 0..10 {
+            buffer.save_state(tick, &model_at_tick(tick));
+        }
+
+        for tick in 0..10 {
+            let state = buffer.get_state(tick).unwrap();
+            assert_eq!(state.get_global("tick"), Some(&(tick as i64).into()));
+            let unit = state.entities().iter().next().unwrap();
+            assert_eq!(unit.get_number("x"), Some(tick as f64));
+        }
+
+        let (tick, state) = buffer.get_nearest_before(100).unwrap();
+        assert_eq!(tick, 9);
+        assert_eq!(state.get_global("tick"), Some(&9i64.into()));
+
+        // Newest is a keyframe, plus one keyframe per interval
+        let stats = buffer.stats();
+        assert_eq!(stats.keyframes, 3);
+        assert_eq!(stats.deltas, 7);
+    }
+
+    #[test]
+    fn test_save_clears_only_filled_caches() {
+        let mut buffer = RollbackBuffer::with_keyframe_interval(16, 4);
+        for tick in 0..10 {
+            buffer.save_state(tick, &model_at_tick(tick));
+        }
+        assert!(buffer.cached.borrow().is_empty());
+
+        // Reading deltas fills their caches, and the next save drops them
+        for tick in 0..3 {
+            buffer.get_state(tick).unwrap();
+        }
+        assert_eq!(buffer.cached.borrow().len(), 3);
+        buffer.save_state(10, &model_at_tick(10));
+        assert!(buffer.cached.borrow().is_empty());
+        assert!(buffer
+            .states
+            .iter()
+            .flatten()
+            .all(|(_, frame)| match frame {
+                Frame::Delta { cache, .. } => cache.get().is_none(),
+                _ => true,
+            }));
+        assert_eq!(
+            buffer.get_state(1).unwrap().get_global("tick"),
+            Some(&1i64.into())
+        );
+    }
+
+    #[test]
+    fn test_delta_mode_eviction_and_overwrite() {
+        let mut buffer = RollbackBuffer::with_keyframe_interval(4, 3);
+        for tick in 0..8 {
+            buffer.save_state(tick, &model_at_tick(tick));
+        }
+        assert!(buffer.get_state(3).is_none());
+        for tick in 4..8 {
+            assert_eq!(
+                buffer.get_state(tick).unwrap().get_global("tick"),
+                Some(&(tick as i64).into())
+            );
+        }
+
+        // Overwrite a tick in the middle (e.g. after a rollback)
+        buffer.save_state(5, &model_at_tick(50));
+        assert_eq!(
+            buffer.get_state(4).unwrap().get_global("tick"),
+            Some(&4i64.into())
+        );
+        assert_eq!(
+            buffer.get_state(5).unwrap().get_global("tick"),
+            Some(&50i64.into())
+        );
+        assert_eq!(
+            buffer.get_state(6).unwrap().get_global("tick"),
+            Some(&6i64.into())
+        );
+        assert_eq!(buffer.len(), 4);
+    }
+
+    #[test]
+    fn test_delta_mode_uses_less_memory() {
+        let mut full = RollbackBuffer::new(32);
+        let mut delta = RollbackBuffer::with_keyframe_interval(32, 8);
+
+        let mut model = Model::new();
+        for _ in 0..50 {
+            model.entities_mut().create("unit").set("hp", 100.0f64);
+        }
+        let first = model.entities().ids().next().unwrap();
+        for tick in 0..32 {
+            model
+                .entities_mut()
+                .get_mut(first)
+                .unwrap()
+                .set("hp", tick as f64);
+            full.save_state(tick, &model);
+            delta.save_state(tick, &model);
+        }
+
+        let full_stats = full.stats();
+        let delta_stats = delta.stats();
+        assert_eq!(full_stats.deltas, 0);
+        assert!(delta_stats.deltas > 0);
+        assert!(delta_stats.estimated_bytes * 4 < full_stats.estimated_bytes);
+    }
 }
@@ -0,0 +1,76 @@
+//! Approximate memory usage of stored states
+//!
+//! Estimates are based on `size_of` for fixed-size parts plus the heap
+//! allocations of strings, lists and maps. Property names are interned, so
+//! map keys count as a [`Symbol`] each. Structural sharing between
+//! frames (`Arc`-shared entity stores and globals) is not taken into
+//! account, so the estimate is an upper bound.
+
+use pulsive_core::{Entity, Model, ModelDiff, Symbol, Value, ValueMap};
+use std::mem::{size_of, size_of_val};
+
+/// Estimate the memory used by a full model
+pub fn model_bytes(model: &Model) -> usize {
+    size_of::<Model>()
+        + model.entities().iter().map(entity_bytes).sum::<usize>()
+        + value_map_bytes(model.globals())
+        + model
+            .actors
+            .values()
+            .map(|ctx| size_of_val(ctx) + ctx.controlled_entities.len() * 8)
+            .sum::<usize>()
+}
+
+/// Estimate the memory used by a model diff
+pub fn diff_bytes(diff: &ModelDiff) -> usize {
+    size_of::<ModelDiff>()
+        + diff
+            .changed_entities
+            .iter()
+            .map(entity_bytes)
+            .sum::<usize>()
+        + diff.removed_entities.len() * 8
+        + diff
+            .changed_globals
+            .iter()
+            .map(|(_, value)| size_of::<Symbol>() + value_bytes(value))
+            .sum::<usize>()
+        + diff.removed_globals.len() * size_of::<Symbol>()
+        + diff
+            .actors
+            .values()
+            .map(|ctx| size_of_val(ctx) + ctx.controlled_entities.len() * 8)
+            .sum::<usize>()
+}
+
+fn entity_bytes(entity: &Entity) -> usize {
+    size_of::<Entity>()
+        + entity.kind.as_str().len()
+        + value_map_bytes(&entity.properties)
+        + entity
+            .flags
+            .iter()
+            .map(|flag| size_of_val(flag) + flag.as_str().len())
+            .sum::<usize>()
+        + entity
+            .tags
+            .iter()
+            .map(|tag| size_of::<String>() + tag.len())
+            .sum::<usize>()
+}
+
+fn value_map_bytes(map: &ValueMap) -> usize {
+    map.iter()
+        .map(|(_, value)| size_of::<Symbol>() + value_bytes(value))
+        .sum()
+}
+
+fn value_bytes(value: &Value) -> usize {
+    size_of::<Value>()
+        + match value {
+            Value::String(s) => s.len(),
+            Value::List(items) => items.iter().map(value_bytes).sum(),
+            Value::Map(map) => value_map_bytes(map),
+            _ => 0,
+        }
+}
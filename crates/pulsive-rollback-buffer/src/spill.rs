@@ -0,0 +1,240 @@
+//! Spilling evicted frames to persistent storage
+//!
+//! A [`SpilloverPolicy`] writes frames evicted from a [`crate::RollbackBuffer`]
+//! to a [`SpillBackend`], so long sessions keep their full history without
+//! holding it in memory. `get_state` transparently falls back to the backend
+//! for ticks that are no longer in memory.
+//!
+//! Backends:
+//! - [`DirectoryBackend`]: one file per frame in a directory (`persistence` feature)
+//! - `pulsive_db::Store`: frames stored in the pulsive database
+
+use crate::Result;
+use std::cell::OnceCell;
+
+/// Storage for frames evicted from a rollback buffer
+pub trait SpillBackend<S> {
+    /// Store the state for a tick, replacing any previous state
+    fn store(&mut self, tick: u64, state: &S) -> Result<()>;
+
+    /// Load the state for a tick, if stored
+    fn load(&self, tick: u64) -> Result<Option<S>>;
+
+    /// Remove all states before the given tick
+    fn remove_before(&mut self, tick: u64) -> Result<()>;
+
+    /// Remove all stored states
+    fn clear(&mut self) -> Result<()>;
+}
+
+/// What to do with frames evicted from the buffer
+pub struct SpilloverPolicy<S> {
+    /// Where evicted frames are written
+    pub(crate) backend: Box<dyn SpillBackend<S> + Send>,
+    /// Only spill ticks that are a multiple of this
+    pub(crate) every_nth: u64,
+}
+
+impl<S> SpilloverPolicy<S> {
+    /// Spill every evicted frame to the given backend
+    pub fn new(backend: impl SpillBackend<S> + Send + 'static) -> Self {
+        Self {
+            backend: Box::new(backend),
+            every_nth: 1,
+        }
+    }
+
+    /// Only spill ticks that are a multiple of `n`
+    ///
+    /// Useful for long replays where a sparse history is enough, e.g. one
+    /// frame per second at 60 ticks per second.
+    pub fn every_nth(mut self, n: u64) -> Self {
+        assert!(n > 0, "Spill interval must be greater than 0");
+        self.every_nth = n;
+        self
+    }
+
+    /// Check whether an evicted tick should be spilled
+    pub(crate) fn should_spill(&self, tick: u64) -> bool {
+        tick.is_multiple_of(self.every_nth)
+    }
+
+    /// Get the backend
+    pub fn backend(&self) -> &dyn SpillBackend<S> {
+        self.backend.as_ref()
+    }
+}
+
+/// States loaded back from a backend, kept until the buffer is next modified
+///
+/// An append-only list, so loaded states can be handed out by reference
+/// from `&self` methods.
+pub(crate) struct LoadedStates<S> {
+    head: OnceCell<Box<Loaded<S>>>,
+}
+
+struct Loaded<S> {
+    tick: u64,
+    state: S,
+    next: OnceCell<Box<Loaded<S>>>,
+}
+
+impl<S> LoadedStates<S> {
+    pub fn new() -> Self {
+        Self {
+            head: OnceCell::new(),
+        }
+    }
+
+    /// Get a loaded state, loading and keeping it if not loaded yet
+    pub fn get_or_load(&self, tick: u64, load: impl FnOnce() -> Option<S>) -> Option<&S> {
+        let mut cell = &self.head;
+        while let Some(node) = cell.get() {
+            if node.tick == tick {
+                return Some(&node.state);
+            }
+            cell = &node.next;
+        }
+        let state = load()?;
+        let node = cell.get_or_init(|| {
+            Box::new(Loaded {
+                tick,
+                state,
+                next: OnceCell::new(),
+            })
+        });
+        Some(&node.state)
+    }
+
+    pub fn clear(&mut self) {
+        // Unlink iteratively to avoid deep recursive drops
+        let mut next = self.head.take();
+        while let Some(mut node) = next {
+            next = node.next.take();
+        }
+    }
+}
+
+#[cfg(feature = "persistence")]
+pub use directory::DirectoryBackend;
+
+#[cfg(feature = "persistence")]
+mod directory {
+    use super::SpillBackend;
+    use crate::{Error, Result};
+    use serde::{de::DeserializeOwned, Serialize};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    const EXTENSION: &str = "frame";
+
+    /// Stores each frame as a bincode file named `<tick>.frame` in a directory
+    #[derive(Debug, Clone)]
+    pub struct DirectoryBackend {
+        dir: PathBuf,
+    }
+
+    impl DirectoryBackend {
+        /// Use the given directory, creating it if needed
+        pub fn new(dir: impl AsRef<Path>) -> Result<Self> {
+            fs::create_dir_all(dir.as_ref())?;
+            Ok(Self {
+                dir: dir.as_ref().to_path_buf(),
+            })
+        }
+
+        /// Get the directory frames are stored in
+        pub fn dir(&self) -> &Path {
+            &self.dir
+        }
+
+        fn path(&self, tick: u64) -> PathBuf {
+            self.dir.join(format!("{tick}.{EXTENSION}"))
+        }
+
+        /// Ticks of all stored frame files
+        fn stored_ticks(&self) -> Result<Vec<u64>> {
+            let mut ticks = Vec::new();
+            for entry in fs::read_dir(&self.dir)? {
+                let path = entry?.path();
+                if path.extension().and_then(|e| e.to_str()) != Some(EXTENSION) {
+                    continue;
+                }
+                if let Some(tick) = path
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .and_then(|s| s.parse().ok())
+                {
+                    ticks.push(tick);
+                }
+            }
+            Ok(ticks)
+        }
+    }
+
+    impl<S: Serialize + DeserializeOwned> SpillBackend<S> for DirectoryBackend {
+        fn store(&mut self, tick: u64, state: &S) -> Result<()> {
+            let bytes =
+                bincode::serialize(state).map_err(|e| Error::Serialization(e.to_string()))?;
+            fs::write(self.path(tick), bytes)?;
+            Ok(())
+        }
+
+        fn load(&self, tick: u64) -> Result<Option<S>> {
+            let bytes = match fs::read(self.path(tick)) {
+                Ok(bytes) => bytes,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(e) => return Err(e.into()),
+            };
+            bincode::deserialize(&bytes)
+                .map(Some)
+                .map_err(|e| Error::Serialization(e.to_string()))
+        }
+
+        fn remove_before(&mut self, tick: u64) -> Result<()> {
+            for stored in self.stored_ticks()? {
+                if stored < tick {
+                    fs::remove_file(self.path(stored))?;
+                }
+            }
+            Ok(())
+        }
+
+        fn clear(&mut self) -> Result<()> {
+            for stored in self.stored_ticks()? {
+                fs::remove_file(self.path(stored))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(test, feature = "persistence"))]
+mod tests {
+    use super::*;
+    use pulsive_core::Model;
+
+    #[test]
+    fn test_directory_backend_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("pulsive-spill-{}", std::process::id()));
+        let mut backend = DirectoryBackend::new(&dir).unwrap();
+
+        let mut model = Model::new();
+        model.set_global("gold", 42i64);
+        for tick in [5, 10, 15] {
+            SpillBackend::<Model>::store(&mut backend, tick, &model).unwrap();
+        }
+
+        let loaded: Option<Model> = backend.load(10).unwrap();
+        assert_eq!(loaded.unwrap().get_global("gold"), Some(&42i64.into()));
+        assert!(SpillBackend::<Model>::load(&backend, 11).unwrap().is_none());
+
+        SpillBackend::<Model>::remove_before(&mut backend, 10).unwrap();
+        assert!(SpillBackend::<Model>::load(&backend, 5).unwrap().is_none());
+        assert!(SpillBackend::<Model>::load(&backend, 15).unwrap().is_some());
+
+        SpillBackend::<Model>::clear(&mut backend).unwrap();
+        assert!(SpillBackend::<Model>::load(&backend, 15).unwrap().is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
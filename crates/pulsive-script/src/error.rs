@@ -11,6 +11,14 @@ pub enum Error {
     #[error("RON parse error: {0}")]
     Ron(#[from] ron::error::SpannedError),
 
+    #[cfg(feature = "json")]
+    #[error("JSON parse error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[cfg(feature = "yaml")]
+    #[error("YAML parse error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
     #[error("Invalid schema: {0}")]
     InvalidSchema(String),
 
@@ -19,6 +27,22 @@ pub enum Error {
 
     #[error("Duplicate definition: {0}")]
     DuplicateDefinition(String),
+
+    #[error("Expression error at {line}:{column}: {message}")]
+    Expression {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+
+    #[error("Database error: {0}")]
+    Database(#[from] pulsive_db::Error),
+
+    #[error("Package error: {0}")]
+    Package(String),
+
+    #[error("File watch error: {0}")]
+    Watch(String),
 }
 
 /// Result type alias
@@ -0,0 +1,230 @@
+//! Expression strings in RON scripts
+//!
+//! Conditions and effect values may be written as expression strings
+//! instead of nested [`Expr`] trees:
+//!
+//! ```ron
+//! (
+//!     id: "harvest",
+//!     trigger: Some("global.gold < 100 && not has_flag(famine)"),
+//!     immediate: [
+//!         ModifyGlobal(property: "gold", op: Add, value: "param.yield * 1.5"),
+//!     ],
+//! )
+//! ```
+//!
+//! Before deserializing, the loader compiles every string in an expression
+//! field ([`EXPR_FIELDS`]) with [`Expr::parse`] and splices the resulting
+//! tree back into the RON source. Parse errors are reported with their line
+//! and column in the original file. JSON and YAML content is compiled the
+//! same way after parsing ([`crate::Format`]).
+
+use crate::error::{Error, Result};
+use pulsive_core::Expr;
+use std::borrow::Cow;
+
+/// Fields holding an [`Expr`] whose string values are compiled
+///
+/// Text fields such as `Log(message: ...)` are left alone, so their
+/// strings stay plain text.
+pub const EXPR_FIELDS: &[&str] = &["trigger", "condition", "value", "delay_ticks", "filter"];
+
+/// Replace expression strings in RON source with their compiled form
+///
+/// Returns the source unchanged (borrowed) if it has no expression strings.
+pub(crate) fn compile_expressions(source: &str) -> Result<Cow<'_, str>> {
+    let mut output = String::new();
+    let mut copied = 0;
+    // Field name seen before the last `:`, and whether we are inside its
+    // `Some(`
+    let mut last_ident: Option<&str> = None;
+    let mut field: Option<&str> = None;
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '/' if source[start..].starts_with("//") => {
+                while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+            }
+            '/' if source[start..].starts_with("/*") => {
+                chars.next();
+                for (i, c) in chars.by_ref() {
+                    if source[..i + c.len_utf8()].ends_with("*/") {
+                        break;
+                    }
+                }
+            }
+            c if c.is_whitespace() => {}
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                while let Some((i, c)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+                    end = i + c.len_utf8();
+                }
+                let ident = &source[start..end];
+                if field.is_some() && ident == "Some" {
+                    continue;
+                }
+                last_ident = Some(ident);
+                field = None;
+            }
+            ':' => {
+                field = last_ident.take().filter(|name| EXPR_FIELDS.contains(name));
+            }
+            '(' if field.is_some() => {}
+            '"' => {
+                let mut end = source.len();
+                while let Some((i, c)) = chars.next() {
+                    match c {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '"' => {
+                            end = i + 1;
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                if field.take().is_some() {
+                    let literal = &source[start..end];
+                    output.push_str(&source[copied..start]);
+                    output.push_str(&compile(source, start, literal)?);
+                    copied = end;
+                }
+                last_ident = None;
+            }
+            _ => {
+                last_ident = None;
+                field = None;
+            }
+        }
+    }
+
+    if copied == 0 {
+        return Ok(Cow::Borrowed(source));
+    }
+    output.push_str(&source[copied..]);
+    Ok(Cow::Owned(output))
+}
+
+/// Compile one RON string literal at `offset` into RON for its expression
+fn compile(source: &str, offset: usize, literal: &str) -> Result<String> {
+    let (line, column) = line_column(source, offset);
+    let text: String = ron::from_str(literal)?;
+    let expr = Expr::parse(&text).map_err(|e| Error::Expression {
+        line: line + e.line - 1,
+        // Columns on the literal's first line are offset by the opening quote
+        column: if e.line == 1 {
+            column + e.column
+        } else {
+            e.column
+        },
+        message: e.message,
+    })?;
+
+    let mut compiled = ron::to_string(&expr).map_err(|e| Error::InvalidSchema(e.to_string()))?;
+    // Keep later line numbers intact when the string spanned several lines
+    compiled.extend(std::iter::repeat_n('\n', literal.matches('\n').count()));
+    Ok(compiled)
+}
+
+/// Replace expression strings in a parsed JSON (or YAML) document with their
+/// compiled form
+///
+/// `source` is the original text, used to locate parse errors.
+#[cfg(feature = "json")]
+pub(crate) fn compile_json_expressions(value: &mut serde_json::Value, source: &str) -> Result<()> {
+    use serde_json::Value as Json;
+
+    match value {
+        Json::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match value {
+                    Json::String(text) if EXPR_FIELDS.contains(&key.as_str()) => {
+                        let expr = Expr::parse(text).map_err(|e| {
+                            // Best effort: the first occurrence of the text
+                            let (line, column) = source
+                                .find(text.as_str())
+                                .map(|offset| line_column(source, offset))
+                                .unwrap_or((1, 1));
+                            Error::Expression {
+                                line: line + e.line - 1,
+                                column: if e.line == 1 {
+                                    column + e.column - 1
+                                } else {
+                                    e.column
+                                },
+                                message: e.message,
+                            }
+                        })?;
+                        *value = serde_json::to_value(&expr)?;
+                    }
+                    value => compile_json_expressions(value, source)?,
+                }
+            }
+        }
+        Json::Array(items) => {
+            for item in items {
+                compile_json_expressions(item, source)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Loader;
+    use pulsive_core::{DefId, EntityRef, Model, Msg, Runtime};
+
+    #[test]
+    fn test_expression_strings() {
+        let content = r#"(events: [(
+    id: "harvest",
+    name: "Harvest",
+    // value: "not compiled"
+    trigger: Some("global.gold < 100 && not (param.yield <= 0)"),
+    immediate: [
+        ModifyGlobal(property: "gold", op: Add, value: "param.yield * 1.5"),
+        Log(level: Info, message: Literal(String("gold + 1"))),
+    ],
+)])"#;
+        let mut loader = Loader::new();
+        loader.load_events_str(content).unwrap();
+        let defs = loader.finish();
+        let event = defs.get_event(&DefId::new("harvest")).unwrap();
+        assert!(matches!(event.trigger, Some(Expr::And(_))));
+
+        let mut runtime = Runtime::new();
+        runtime.on_event(event.to_handler());
+        let mut model = Model::new();
+        model.set_global("gold", 0.0);
+        let msg = Msg::event("harvest", EntityRef::Global, 0).with_param("yield", 4.0);
+        runtime.update(&mut model, msg);
+        assert_eq!(
+            model.get_global("gold").and_then(|v| v.as_float()),
+            Some(6.0)
+        );
+    }
+
+    #[test]
+    fn test_expression_error_location() {
+        let content = "(events: [(\n    id: \"tax\",\n    name: \"Tax\",\n    trigger: Some(\"global.gold >> 5\"),\n)])";
+        let mut loader = Loader::new();
+        match loader.load_events_str(content) {
+            Err(Error::Expression { line, column, .. }) => {
+                assert_eq!((line, column), (4, 33));
+            }
+            other => panic!("expected an expression error, got {:?}", other),
+        }
+    }
+}
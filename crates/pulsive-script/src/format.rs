@@ -0,0 +1,320 @@
+//! Content formats and conversion
+//!
+//! Content is written in RON by default. With the `json` and `yaml`
+//! features the loader also reads `.json` and `.yaml`/`.yml` files with the
+//! same schema types. Enum values are written as single-key maps, and
+//! expression fields accept expression strings as in RON:
+//!
+//! ```yaml
+//! events:
+//!   - id: harvest
+//!     name: Harvest
+//!     trigger: "global.gold < 100"
+//!     immediate:
+//!       - ModifyGlobal: {property: gold, op: Add, value: "param.yield * 1.5"}
+//! ```
+//!
+//! YAML may also use tags for enum values (`!ModifyGlobal {...}`).
+//!
+//! [`convert`] and [`convert_file`] translate content between formats, so
+//! content pipelines can keep their files in whichever format they prefer.
+
+use crate::error::{Error, Result};
+use crate::expressions::compile_expressions;
+use crate::schema::curve::CurveDefs;
+use crate::schema::effect_template::EffectTemplateDefs;
+use crate::schema::entity::EntityTypeDefs;
+use crate::schema::event::{EventDefs, EventPoolDef, EventPoolDefs};
+use crate::schema::resource::ResourceDefs;
+use crate::schema::{
+    CurveDef, EffectTemplateDef, EntityTypeDef, EventDef, LocalizationDef, ResourceDef,
+};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// Serialization format of a content file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// RON (`.ron`)
+    Ron,
+    /// JSON (`.json`)
+    #[cfg(feature = "json")]
+    Json,
+    /// YAML (`.yaml`, `.yml`)
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+impl Format {
+    /// Get the format for a file extension, if it is enabled
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "ron" => Some(Self::Ron),
+            #[cfg(feature = "json")]
+            "json" => Some(Self::Json),
+            #[cfg(feature = "yaml")]
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    /// Get the format of a file from its extension
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        Self::from_extension(path.as_ref().extension()?.to_str()?)
+    }
+
+    /// Get the usual file extension
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Ron => "ron",
+            #[cfg(feature = "json")]
+            Self::Json => "json",
+            #[cfg(feature = "yaml")]
+            Self::Yaml => "yaml",
+        }
+    }
+}
+
+/// Convert content from one format to another
+///
+/// The content is parsed into its schema types, so expression strings come
+/// out as expression trees and comments are not kept.
+pub fn convert(content: &str, from: Format, to: Format) -> Result<String> {
+    Content::parse(content, from, "")?.write(to)
+}
+
+/// Convert a content file, picking both formats from the file extensions
+pub fn convert_file(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    let format = |path: &Path| {
+        Format::from_path(path)
+            .ok_or_else(|| Error::InvalidSchema(format!("Unsupported content format: {:?}", path)))
+    };
+    let filename = from.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let content = Content::parse(&fs::read_to_string(from)?, format(from)?, filename)?;
+    fs::write(to, content.write(format(to)?)?)?;
+    Ok(())
+}
+
+/// The parsed content of one file
+#[derive(Serialize)]
+#[serde(untagged)]
+pub(crate) enum Content {
+    Resources(ResourceDefs),
+    Events(EventDefs),
+    EntityTypes(EntityTypeDefs),
+    Curves(CurveDefs),
+    EventPools(EventPoolDefs),
+    EffectTemplates(EffectTemplateDefs),
+    Localization(LocalizationDef),
+    Resource(ResourceDef),
+    Event(EventDef),
+    EntityType(EntityTypeDef),
+    Curve(CurveDef),
+    EventPool(EventPoolDef),
+    EffectTemplate(EffectTemplateDef),
+}
+
+impl Content {
+    /// Parse content, using the file name to pick the definition kind
+    pub(crate) fn parse(content: &str, format: Format, filename: &str) -> Result<Self> {
+        match format {
+            Format::Ron => Self::parse_ron(content, filename),
+            #[cfg(feature = "json")]
+            Format::Json => Self::from_json(serde_json::from_str(content)?, content),
+            #[cfg(feature = "yaml")]
+            Format::Yaml => Self::from_json(yaml_to_json(serde_yaml::from_str(content)?)?, content),
+        }
+    }
+
+    fn parse_ron(content: &str, filename: &str) -> Result<Self> {
+        // Try to determine the type based on content or filename
+        if filename.contains("locali")
+            || (content.contains("language:") && content.contains("strings:"))
+        {
+            Ok(Self::Localization(ron::from_str(content)?))
+        } else if filename.contains("pool") || content.contains("event_pools:") {
+            // Before events: pools list their events too
+            Ok(Self::EventPools(ron::from_str(&compile_expressions(
+                content,
+            )?)?))
+        } else if filename.contains("template") || content.contains("effect_templates:") {
+            // Before events: template effects may emit events
+            Ok(Self::EffectTemplates(ron::from_str(&compile_expressions(
+                content,
+            )?)?))
+        } else if filename.contains("resource") || content.contains("resources:") {
+            Ok(Self::Resources(ron::from_str(content)?))
+        } else if filename.contains("event") || content.contains("events:") {
+            Ok(Self::Events(ron::from_str(&compile_expressions(content)?)?))
+        } else if filename.contains("entity") || content.contains("entity_types:") {
+            Ok(Self::EntityTypes(ron::from_str(content)?))
+        } else if filename.contains("curve") || content.contains("curves:") {
+            Ok(Self::Curves(ron::from_str(content)?))
+        } else {
+            // Try each kind, then single definitions
+            let compiled = compile_expressions(content)?;
+            ron::from_str(content)
+                .map(Self::Resources)
+                .or_else(|_| ron::from_str(&compiled).map(Self::Events))
+                .or_else(|_| ron::from_str(content).map(Self::EntityTypes))
+                .or_else(|_| Self::parse_ron_single(content))
+        }
+    }
+
+    /// Parse a single RON definition
+    fn parse_ron_single(content: &str) -> Result<Self> {
+        let compiled = compile_expressions(content)?;
+        ron::from_str(content)
+            .map(Self::Resource)
+            .or_else(|_| ron::from_str(&compiled).map(Self::Event))
+            .or_else(|_| ron::from_str(content).map(Self::EntityType))
+            .or_else(|_| ron::from_str(content).map(Self::Curve))
+            .or_else(|_| ron::from_str(&compiled).map(Self::EventPool))
+            .or_else(|_| ron::from_str(&compiled).map(Self::EffectTemplate))
+            .map_err(|_| {
+                Error::InvalidSchema("Could not parse as any known definition type".to_string())
+            })
+    }
+
+    /// Pick the definition kind from the top-level keys
+    #[cfg(feature = "json")]
+    fn from_json(mut value: serde_json::Value, source: &str) -> Result<Self> {
+        use serde::Deserialize;
+
+        crate::expressions::compile_json_expressions(&mut value, source)?;
+        let has = |key: &str| value.get(key).is_some();
+        let content = if has("language") && has("strings") {
+            Self::Localization(LocalizationDef::deserialize(&value)?)
+        } else if has("event_pools") {
+            Self::EventPools(EventPoolDefs::deserialize(&value)?)
+        } else if has("effect_templates") {
+            Self::EffectTemplates(EffectTemplateDefs::deserialize(&value)?)
+        } else if has("resources") {
+            Self::Resources(ResourceDefs::deserialize(&value)?)
+        } else if has("events") {
+            Self::Events(EventDefs::deserialize(&value)?)
+        } else if has("entity_types") {
+            Self::EntityTypes(EntityTypeDefs::deserialize(&value)?)
+        } else if has("curves") {
+            Self::Curves(CurveDefs::deserialize(&value)?)
+        } else {
+            ResourceDef::deserialize(&value)
+                .map(Self::Resource)
+                .or_else(|_| EventDef::deserialize(&value).map(Self::Event))
+                .or_else(|_| EntityTypeDef::deserialize(&value).map(Self::EntityType))
+                .or_else(|_| CurveDef::deserialize(&value).map(Self::Curve))
+                .or_else(|_| EventPoolDef::deserialize(&value).map(Self::EventPool))
+                .or_else(|_| EffectTemplateDef::deserialize(&value).map(Self::EffectTemplate))
+                .map_err(|_| {
+                    Error::InvalidSchema("Could not parse as any known definition type".to_string())
+                })?
+        };
+        Ok(content)
+    }
+
+    /// Write the content in a format
+    pub(crate) fn write(&self, format: Format) -> Result<String> {
+        match format {
+            Format::Ron => ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                .map_err(|e| Error::InvalidSchema(e.to_string())),
+            #[cfg(feature = "json")]
+            Format::Json => Ok(serde_json::to_string_pretty(self)?),
+            // Through JSON values, so enums are written as maps rather than
+            // (nested) tags
+            #[cfg(feature = "yaml")]
+            Format::Yaml => Ok(serde_yaml::to_string(&serde_json::to_value(self)?)?),
+        }
+    }
+}
+
+/// Convert a YAML document to JSON, turning tagged values into single-key
+/// maps
+#[cfg(feature = "yaml")]
+fn yaml_to_json(value: serde_yaml::Value) -> Result<serde_json::Value> {
+    use serde_json::Value as Json;
+    use serde_yaml::Value as Yaml;
+
+    Ok(match value {
+        Yaml::Null => Json::Null,
+        Yaml::Bool(b) => Json::Bool(b),
+        Yaml::Number(n) => serde_json::to_value(n)?,
+        Yaml::String(s) => Json::String(s),
+        Yaml::Sequence(items) => {
+            Json::Array(items.into_iter().map(yaml_to_json).collect::<Result<_>>()?)
+        }
+        Yaml::Mapping(map) => Json::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        Yaml::String(s) => s,
+                        Yaml::Number(n) => n.to_string(),
+                        Yaml::Bool(b) => b.to_string(),
+                        other => {
+                            return Err(Error::InvalidSchema(format!(
+                                "Unsupported YAML map key: {:?}",
+                                other
+                            )))
+                        }
+                    };
+                    Ok((key, yaml_to_json(value)?))
+                })
+                .collect::<Result<_>>()?,
+        ),
+        Yaml::Tagged(tagged) => {
+            let tag = tagged.tag.to_string();
+            let mut object = serde_json::Map::new();
+            object.insert(
+                tag.trim_start_matches('!').to_string(),
+                yaml_to_json(tagged.value)?,
+            );
+            Json::Object(object)
+        }
+    })
+}
+
+#[cfg(all(test, feature = "yaml"))]
+mod tests {
+    use super::*;
+    use crate::Loader;
+    use pulsive_core::{DefId, Expr};
+
+    #[test]
+    fn test_json_and_yaml_content() {
+        let json = r#"{"events": [{
+    "id": "harvest",
+    "name": "Harvest",
+    "trigger": "global.gold < 100",
+    "immediate": [{"ModifyGlobal": {"property": "gold", "op": "Add", "value": "param.yield * 1.5"}}]
+}]}"#;
+        let yaml = "
+resources:
+  - id: gold
+    name: Gold
+    base_value: 2.0
+";
+        let mut loader = Loader::new();
+        loader.load_str(json, Format::Json).unwrap();
+        loader.load_str(yaml, Format::Yaml).unwrap();
+        let defs = loader.finish();
+        let event = defs.get_event(&DefId::new("harvest")).unwrap();
+        assert!(matches!(event.trigger, Some(Expr::Lt(_, _))));
+        assert_eq!(
+            defs.get_resource(&DefId::new("gold")).unwrap().base_value,
+            2.0
+        );
+        assert_eq!(defs.sources.events[&DefId::new("harvest")].line, 2);
+
+        // RON -> YAML -> JSON -> RON keeps the definitions
+        let ron = r#"(events: [(id: "tax", name: "Tax", trigger: Some("global.gold >= 10"))])"#;
+        let yaml = convert(ron, Format::Ron, Format::Yaml).unwrap();
+        let json = convert(&yaml, Format::Yaml, Format::Json).unwrap();
+        let ron = convert(&json, Format::Json, Format::Ron).unwrap();
+        let mut loader = Loader::new();
+        loader.load_events_str(&ron).unwrap();
+        let defs = loader.finish();
+        let tax = defs.get_event(&DefId::new("tax")).unwrap();
+        assert!(matches!(tax.trigger, Some(Expr::Ge(_, _))));
+    }
+}
@@ -0,0 +1,178 @@
+//! Installing game definitions into a runtime
+//!
+//! [`GameDefs::install`] wires loaded definitions into a [`Runtime`] and
+//! [`Model`]:
+//! - every event gets an [`EventHandler`](pulsive_core::EventHandler)
+//!   applying its immediate effects
+//! - events with a mean time to happen also get a tick handler that fires
+//!   them at random ([`EventDef::to_mtth_handler`](crate::EventDef::to_mtth_handler))
+//! - resources are registered to flow every tick
+//!   ([`ResourceDef::to_resource`](crate::ResourceDef::to_resource)); global
+//!   ones start at their base value
+//! - entity types become spawn templates with their property defaults, and
+//!   their property definitions become schemas checked at write time
+//! - curves are registered for `Expr::Curve` to sample
+//! - effect templates are registered for `Effect::Invoke` to run
+//!   ([`EffectTemplateDef::to_template`](crate::EffectTemplateDef::to_template))
+//! - event pools get a tick handler picking random events
+//!   ([`EventPoolDef::to_handler`](crate::EventPoolDef::to_handler))
+
+use crate::loader::{sorted, GameDefs};
+use crate::schema::EntityTypeDef;
+use pulsive_core::{DefId, Model, Runtime, ValueMap};
+
+impl GameDefs {
+    /// Register handlers, templates, property schemas, curves and resources
+    /// for these definitions
+    ///
+    /// Resource globals that already have a value (e.g. from a loaded save)
+    /// are left alone. Effect templates that would invoke themselves are
+    /// skipped; [`validate`](GameDefs::validate) reports them.
+    pub fn install(&self, runtime: &mut Runtime, model: &mut Model) {
+        for (id, resource) in sorted(&self.resources) {
+            if resource.holder.is_none() && model.get_global(id.as_str()).is_none() {
+                model.set_global(id.as_str(), resource.base_value);
+            }
+            runtime.register_resource(resource.to_resource());
+        }
+
+        for (id, _) in sorted(&self.entity_types) {
+            runtime.register_template(id.clone(), self.entity_template(id));
+            for entity_type in self.entity_type_chain(id) {
+                for property in &entity_type.properties {
+                    model.schemas_mut().register(
+                        id.clone(),
+                        property.name.clone(),
+                        property.to_schema(),
+                    );
+                }
+            }
+        }
+
+        for (id, curve) in sorted(&self.curves) {
+            runtime.register_curve(id.clone(), curve.to_curve());
+        }
+
+        for (id, template) in sorted(&self.effect_templates) {
+            let _ = runtime.register_effect_template(id.clone(), template.to_template());
+        }
+
+        for (_, event) in sorted(&self.events) {
+            runtime.on_event(event.to_handler());
+            if let Some(handler) = event.to_mtth_handler() {
+                runtime.on_tick(handler);
+            }
+        }
+
+        for (_, pool) in sorted(&self.event_pools) {
+            runtime.on_tick(pool.to_handler(&self.events));
+        }
+    }
+
+    /// Default properties for an entity type, including those inherited
+    /// through `extends`
+    ///
+    /// Parent defaults come first and are overridden by the child's.
+    /// Property defaults are applied before the type's `defaults` list.
+    pub fn entity_template(&self, id: &DefId) -> ValueMap {
+        let mut template = ValueMap::new();
+        for entity_type in self.entity_type_chain(id) {
+            for property in &entity_type.properties {
+                if let Some(default) = &property.default {
+                    template.insert(property.name.clone(), default.clone());
+                }
+            }
+            for (key, value) in &entity_type.defaults {
+                template.insert(key.clone(), value.clone());
+            }
+        }
+        template
+    }
+
+    /// An entity type and its ancestors through `extends`, root first
+    fn entity_type_chain(&self, id: &DefId) -> Vec<&EntityTypeDef> {
+        // Walk up the inheritance chain, stopping at unknown or repeated
+        // types
+        let mut chain: Vec<&EntityTypeDef> = Vec::new();
+        let mut current = self.entity_types.get(id);
+        while let Some(entity_type) = current {
+            if chain.iter().any(|t| t.id == entity_type.id) {
+                break;
+            }
+            chain.push(entity_type);
+            current = entity_type
+                .extends
+                .as_ref()
+                .and_then(|parent| self.entity_types.get(parent));
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Loader;
+    use pulsive_core::{DefId, EntityRef, Model, Msg, Runtime, Value};
+
+    fn defs() -> crate::GameDefs {
+        let mut loader = Loader::new();
+        loader
+            .load_resources_str(r#"(resources: [(id: "gold", name: "Gold", base_value: 50.0)])"#)
+            .unwrap();
+        loader
+            .load_entity_types_str(
+                r#"(entity_types: [
+    (id: "unit", name: "Unit", properties: [(name: "hp", property_type: Int, default: Some(Int(10)))]),
+    (id: "knight", name: "Knight", extends: Some("unit"), defaults: [("armor", Int(3))]),
+])"#,
+            )
+            .unwrap();
+        loader
+            .load_events_str(
+                r#"(events: [
+    (id: "recruit", name: "Recruit", immediate: [SpawnEntity(kind: "knight", properties: [])]),
+    (
+        id: "windfall",
+        name: "Windfall",
+        mtth: Some((ticks: 1)),
+        fire_only_once: true,
+        immediate: [ModifyGlobal(property: "gold", op: Add, value: "100")],
+    ),
+])"#,
+            )
+            .unwrap();
+        loader.finish()
+    }
+
+    #[test]
+    fn test_install() {
+        let defs = defs();
+        let mut runtime = Runtime::new();
+        let mut model = Model::new();
+        defs.install(&mut runtime, &mut model);
+
+        assert_eq!(model.get_global("gold"), Some(&Value::Float(50.0)));
+        assert!(runtime.has_event_handler(&DefId::new("recruit")));
+
+        // Spawned knights inherit unit defaults
+        runtime.send(Msg::event("recruit", EntityRef::Global, 0));
+        let result = runtime.process_queue(&mut model);
+        let knight = model
+            .entities()
+            .get(result.effect_result.spawned[0])
+            .unwrap();
+        assert_eq!(knight.get("hp"), Some(&Value::Int(10)));
+        assert_eq!(knight.get("armor"), Some(&Value::Int(3)));
+        assert!(model.schemas().get(&DefId::new("knight"), "hp").is_some());
+
+        // An MTTH of one tick always fires, but only once
+        for _ in 0..3 {
+            runtime.tick(&mut model);
+        }
+        assert_eq!(
+            model.get_global("gold").and_then(|v| v.as_float()),
+            Some(150.0)
+        );
+    }
+}
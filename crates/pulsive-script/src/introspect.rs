@@ -0,0 +1,1006 @@
+//! Schema introspection for editors
+//!
+//! [`GameDefs::introspect`] describes every schema type, effect kind,
+//! expression variant and expression-string function, plus the loaded
+//! definitions, so external editors (and the Godot plugin) can offer
+//! autocompletion and validation without linking against pulsive. With the
+//! `json` feature, [`Introspection::to_json`] emits it as JSON:
+//!
+//! ```text
+//! {
+//!   "version": "0.1.0",
+//!   "schema": [{"name": "ResourceDef", "kind": "struct", "fields": [...]}, ...],
+//!   "effects": [{"name": "ModifyGlobal", "fields": [...]}, ...],
+//!   "definitions": [{"kind": "event", "id": "harvest", ...}, ...],
+//!   ...
+//! }
+//! ```
+
+use crate::expressions::EXPR_FIELDS;
+use crate::loader::{sorted, GameDefs};
+use crate::validate::SourceLocation;
+use pulsive_core::EXPR_FUNCTIONS;
+use serde::Serialize;
+
+/// A field of a struct or enum variant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldInfo {
+    /// Field name (position for tuple variants)
+    pub name: String,
+    /// Rust-style type, e.g. `Option<Expr>`
+    pub type_name: String,
+    /// Whether the field must be given
+    pub required: bool,
+    /// Description
+    pub description: String,
+}
+
+/// A variant of an enum
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VariantInfo {
+    /// Variant name as written in content
+    pub name: String,
+    /// Description
+    pub description: String,
+    /// Fields of struct and tuple variants
+    pub fields: Vec<FieldInfo>,
+}
+
+/// A schema type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TypeInfo {
+    /// Type name
+    pub name: String,
+    /// `struct` or `enum`
+    pub kind: String,
+    /// Description
+    pub description: String,
+    /// Fields (structs)
+    pub fields: Vec<FieldInfo>,
+    /// Variants (enums)
+    pub variants: Vec<VariantInfo>,
+}
+
+/// A function of the expression-string syntax
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FunctionInfo {
+    /// Function name
+    pub name: String,
+    /// Example call, e.g. `clamp(x, min, max)`
+    pub signature: String,
+    /// Description
+    pub description: String,
+}
+
+/// A loaded definition
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DefinitionInfo {
+    /// Definition kind (`resource`, `event`, `entity_type`, `curve`,
+    /// `event_pool` or `effect_template`)
+    pub kind: String,
+    /// Definition ID
+    pub id: String,
+    /// Display name, if the kind has one
+    pub name: Option<String>,
+    /// Where the definition was loaded from
+    pub location: Option<SourceLocation>,
+}
+
+/// Machine-readable description of the schema and loaded content
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Introspection {
+    /// pulsive-script version
+    pub version: String,
+    /// Schema types (definitions and the enums they use)
+    pub schema: Vec<TypeInfo>,
+    /// Effect kinds
+    pub effects: Vec<VariantInfo>,
+    /// Expression variants
+    pub expressions: Vec<VariantInfo>,
+    /// Expression-string functions
+    pub functions: Vec<FunctionInfo>,
+    /// Fields that accept expression strings
+    pub expression_fields: Vec<String>,
+    /// Loaded definitions, by kind then ID
+    pub definitions: Vec<DefinitionInfo>,
+    /// Loaded localization keys, sorted
+    pub localization_keys: Vec<String>,
+}
+
+impl Introspection {
+    /// Describe the schema, without any loaded definitions
+    pub fn schema() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            schema: STRUCTS
+                .iter()
+                .map(|(name, description, fields)| TypeInfo {
+                    name: name.to_string(),
+                    kind: "struct".to_string(),
+                    description: description.to_string(),
+                    fields: fields.iter().map(struct_field).collect(),
+                    variants: Vec::new(),
+                })
+                .chain(ENUMS.iter().map(|(name, description, variants)| TypeInfo {
+                    name: name.to_string(),
+                    kind: "enum".to_string(),
+                    description: description.to_string(),
+                    fields: Vec::new(),
+                    variants: variants.iter().map(variant).collect(),
+                }))
+                .collect(),
+            effects: EFFECTS.iter().map(variant).collect(),
+            expressions: EXPRESSIONS.iter().map(variant).collect(),
+            functions: EXPR_FUNCTIONS
+                .iter()
+                .map(|(name, signature, description)| FunctionInfo {
+                    name: name.to_string(),
+                    signature: signature.to_string(),
+                    description: description.to_string(),
+                })
+                .collect(),
+            expression_fields: EXPR_FIELDS.iter().map(|f| f.to_string()).collect(),
+            definitions: Vec::new(),
+            localization_keys: Vec::new(),
+        }
+    }
+
+    /// Get a schema type by name
+    pub fn schema_type(&self, name: &str) -> Option<&TypeInfo> {
+        self.schema.iter().find(|t| t.name == name)
+    }
+
+    /// Serialize as pretty-printed JSON
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> crate::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl GameDefs {
+    /// Describe the schema and these definitions
+    pub fn introspect(&self) -> Introspection {
+        let mut introspection = Introspection::schema();
+        let sources = &self.sources;
+        let mut add = |kind: &str, id: &pulsive_core::DefId, name: Option<&String>, location| {
+            introspection.definitions.push(DefinitionInfo {
+                kind: kind.to_string(),
+                id: id.to_string(),
+                name: name.cloned(),
+                location: Option::<&SourceLocation>::cloned(location),
+            });
+        };
+
+        for (id, def) in sorted(&self.resources) {
+            add("resource", id, Some(&def.name), sources.resources.get(id));
+        }
+        for (id, def) in sorted(&self.events) {
+            add("event", id, Some(&def.name), sources.events.get(id));
+        }
+        for (id, def) in sorted(&self.entity_types) {
+            add(
+                "entity_type",
+                id,
+                Some(&def.name),
+                sources.entity_types.get(id),
+            );
+        }
+        for (id, _) in sorted(&self.curves) {
+            add("curve", id, None, sources.curves.get(id));
+        }
+        for (id, _) in sorted(&self.event_pools) {
+            add("event_pool", id, None, sources.event_pools.get(id));
+        }
+        for (id, _) in sorted(&self.effect_templates) {
+            add(
+                "effect_template",
+                id,
+                None,
+                sources.effect_templates.get(id),
+            );
+        }
+
+        introspection.localization_keys = self
+            .localization
+            .keys()
+            .into_iter()
+            .map(String::from)
+            .collect();
+        introspection
+    }
+}
+
+type StructTable = (&'static str, &'static str, &'static [Field]);
+type EnumTable = (&'static str, &'static str, &'static [Variant]);
+/// Name, type, required, description
+type Field = (&'static str, &'static str, bool, &'static str);
+/// Name, description, fields (name and type; all required)
+type Variant = (
+    &'static str,
+    &'static str,
+    &'static [(&'static str, &'static str)],
+);
+
+fn struct_field((name, type_name, required, description): &Field) -> FieldInfo {
+    FieldInfo {
+        name: name.to_string(),
+        type_name: type_name.to_string(),
+        required: *required,
+        description: description.to_string(),
+    }
+}
+
+fn variant((name, description, fields): &Variant) -> VariantInfo {
+    VariantInfo {
+        name: name.to_string(),
+        description: description.to_string(),
+        fields: fields
+            .iter()
+            .map(|(name, type_name)| FieldInfo {
+                name: name.to_string(),
+                type_name: type_name.to_string(),
+                required: !type_name.starts_with("Option<"),
+                description: String::new(),
+            })
+            .collect(),
+    }
+}
+
+const STRUCTS: &[StructTable] = &[
+    (
+        "ResourceDef",
+        "A resource type (e.g. gold, manpower)",
+        &[
+            ("id", "DefId", true, "Unique identifier"),
+            ("name", "String", true, "Display name"),
+            ("description", "String", false, "Description"),
+            ("base_value", "f64", false, "Base value (default 1.0)"),
+            ("tradeable", "bool", false, "Whether it can be traded"),
+            ("decay_rate", "f64", false, "Decay rate per tick"),
+            ("min_value", "Option<f64>", false, "Minimum value"),
+            ("max_value", "Option<f64>", false, "Maximum value"),
+            ("icon", "Option<String>", false, "Icon identifier"),
+            ("color", "Option<String>", false, "Color (hex string)"),
+            (
+                "holder",
+                "Option<DefId>",
+                false,
+                "Entity kind holding it (global if unset)",
+            ),
+            ("income", "Vec<Expr>", false, "Income sources per tick"),
+            ("upkeep", "Vec<Expr>", false, "Upkeep sources per tick"),
+            (
+                "on_overflow",
+                "OverflowPolicy",
+                false,
+                "What happens past the maximum",
+            ),
+            (
+                "on_underflow",
+                "OverflowPolicy",
+                false,
+                "What happens below the minimum",
+            ),
+        ],
+    ),
+    (
+        "EventDef",
+        "A game event",
+        &[
+            ("id", "DefId", true, "Unique identifier"),
+            ("name", "String", true, "Display name"),
+            ("description", "String", false, "Description"),
+            (
+                "trigger",
+                "Option<Expr>",
+                false,
+                "Condition for the event to fire",
+            ),
+            (
+                "mtth",
+                "Option<MeanTimeToHappen>",
+                false,
+                "Mean time to happen",
+            ),
+            ("weight", "f64", false, "Weight for random selection"),
+            ("fire_only_once", "bool", false, "Fire only once per target"),
+            ("target_kind", "Option<DefId>", false, "Target entity kind"),
+            (
+                "immediate",
+                "Vec<Effect>",
+                false,
+                "Effects applied when fired",
+            ),
+            (
+                "options",
+                "Vec<EventOption>",
+                false,
+                "Options to choose from",
+            ),
+            ("category", "Option<DefId>", false, "Category for grouping"),
+            ("icon", "Option<String>", false, "Icon identifier"),
+        ],
+    ),
+    (
+        "MeanTimeToHappen",
+        "Mean time to happen of a random event",
+        &[
+            ("ticks", "u64", true, "Base number of ticks"),
+            (
+                "modifiers",
+                "Vec<MtthModifier>",
+                false,
+                "Conditional factors",
+            ),
+        ],
+    ),
+    (
+        "MtthModifier",
+        "A factor applied to the mean time to happen",
+        &[
+            ("condition", "Expr", true, "When the factor applies"),
+            ("factor", "f64", true, "Multiplier for the time"),
+        ],
+    ),
+    (
+        "EventOption",
+        "An option of an event",
+        &[
+            ("id", "String", true, "ID within the event"),
+            ("text", "String", true, "Display text"),
+            (
+                "condition",
+                "Option<Expr>",
+                false,
+                "When the option is available",
+            ),
+            ("effects", "Vec<Effect>", false, "Effects when chosen"),
+            ("ai_weight", "f64", false, "AI weight for choosing it"),
+        ],
+    ),
+    (
+        "EventPoolDef",
+        "A weighted pool of random events",
+        &[
+            ("id", "DefId", true, "Unique identifier"),
+            ("description", "String", false, "Description"),
+            ("target_kind", "Option<DefId>", false, "Target entity kind"),
+            (
+                "trigger",
+                "Option<Expr>",
+                false,
+                "Condition for the pool to roll",
+            ),
+            (
+                "chance",
+                "f64",
+                false,
+                "Chance per tick that an event is picked",
+            ),
+            ("events", "Vec<PoolEntry>", false, "Events in the pool"),
+        ],
+    ),
+    (
+        "PoolEntry",
+        "An event in an event pool",
+        &[
+            ("event", "DefId", true, "Event to fire"),
+            ("weight", "f64", false, "Weight for random selection"),
+            ("condition", "Option<Expr>", false, "Eligibility condition"),
+            ("cooldown", "u64", false, "Ticks before it can fire again"),
+            ("max_fires", "Option<u32>", false, "Maximum number of fires"),
+        ],
+    ),
+    (
+        "EntityTypeDef",
+        "An entity type (e.g. nation, province)",
+        &[
+            ("id", "DefId", true, "Unique identifier"),
+            ("name", "String", true, "Display name"),
+            ("description", "String", false, "Description"),
+            ("properties", "Vec<PropertyDef>", false, "Property schemas"),
+            ("defaults", "Vec<(String, Value)>", false, "Default values"),
+            ("extends", "Option<DefId>", false, "Parent entity type"),
+            ("category", "Option<DefId>", false, "Category for grouping"),
+        ],
+    ),
+    (
+        "PropertyDef",
+        "A property of an entity type",
+        &[
+            ("name", "String", true, "Property name"),
+            ("property_type", "PropertyType", true, "Property type"),
+            ("required", "bool", false, "Whether it is required"),
+            ("default", "Option<Value>", false, "Default value"),
+            ("min", "Option<f64>", false, "Minimum (numeric types)"),
+            ("max", "Option<f64>", false, "Maximum (numeric types)"),
+            (
+                "allowed",
+                "Vec<Value>",
+                false,
+                "Allowed values (any if empty)",
+            ),
+            ("description", "String", false, "Description"),
+        ],
+    ),
+    (
+        "CurveDef",
+        "A value curve, sampled with Curve",
+        &[
+            ("id", "DefId", true, "Unique identifier"),
+            ("description", "String", false, "Description"),
+            (
+                "interpolation",
+                "Interpolation",
+                false,
+                "Interpolation between points",
+            ),
+            (
+                "points",
+                "Vec<(f64, f64)>",
+                true,
+                "(input, output) key points",
+            ),
+        ],
+    ),
+    (
+        "EffectTemplateDef",
+        "A reusable block of effects, run with Invoke",
+        &[
+            ("id", "DefId", true, "Unique identifier"),
+            ("description", "String", false, "Description"),
+            ("params", "Vec<String>", false, "Required parameters"),
+            (
+                "defaults",
+                "Vec<(String, Value)>",
+                false,
+                "Optional parameters with their defaults",
+            ),
+            ("effects", "Vec<Effect>", true, "Effects to run"),
+        ],
+    ),
+    (
+        "LocalizationDef",
+        "Localized strings for one language",
+        &[
+            ("language", "String", true, "Language code"),
+            ("strings", "Map<String, String>", false, "Strings by key"),
+        ],
+    ),
+    (
+        "PackageManifest",
+        "A content package manifest (package.ron)",
+        &[
+            ("name", "String", true, "Unique package name"),
+            ("version", "String", false, "Package version"),
+            ("description", "String", false, "Description"),
+            (
+                "dependencies",
+                "Vec<String>",
+                false,
+                "Packages loaded first",
+            ),
+            (
+                "load_after",
+                "Vec<String>",
+                false,
+                "Packages loaded first if present",
+            ),
+        ],
+    ),
+];
+
+const ENUMS: &[EnumTable] = &[
+    (
+        "Value",
+        "A dynamic value",
+        &[
+            ("Null", "No value", &[]),
+            ("Bool", "Boolean", &[("0", "bool")]),
+            ("Int", "Integer", &[("0", "i64")]),
+            ("Float", "Floating point number", &[("0", "f64")]),
+            ("String", "Text", &[("0", "String")]),
+            ("EntityRef", "Entity ID", &[("0", "u64")]),
+            ("List", "List of values", &[("0", "Vec<Value>")]),
+            (
+                "Map",
+                "Map of values by key",
+                &[("0", "Map<String, Value>")],
+            ),
+        ],
+    ),
+    (
+        "EntityRef",
+        "A reference to an entity",
+        &[
+            ("None", "No target", &[]),
+            ("Entity", "Entity by ID", &[("0", "u64")]),
+            ("Global", "The global target", &[]),
+            ("ByDef", "Entity by definition ID", &[("0", "DefId")]),
+            ("Kind", "Every entity of a kind", &[("0", "DefId")]),
+            ("Set", "A set of entities by ID", &[("0", "Vec<u64>")]),
+        ],
+    ),
+    (
+        "ModifyOp",
+        "Operation applied by Modify effects",
+        &[
+            ("Set", "Set to the value", &[]),
+            ("Add", "Add the value", &[]),
+            ("Sub", "Subtract the value", &[]),
+            ("Mul", "Multiply by the value", &[]),
+            ("Div", "Divide by the value", &[]),
+            ("Min", "Keep the smaller value", &[]),
+            ("Max", "Keep the larger value", &[]),
+        ],
+    ),
+    (
+        "LogLevel",
+        "Log level",
+        &[
+            ("Debug", "", &[]),
+            ("Info", "", &[]),
+            ("Warn", "", &[]),
+            ("Error", "", &[]),
+        ],
+    ),
+    (
+        "OverflowPolicy",
+        "What happens to a resource holding past a bound",
+        &[
+            ("Clamp", "Keep it at the bound", &[]),
+            (
+                "Spill",
+                "Keep it at the bound and emit an event with the excess",
+                &[],
+            ),
+            ("Allow", "Let it pass the bound (debt)", &[]),
+        ],
+    ),
+    (
+        "PropertyType",
+        "Type of an entity property",
+        &[
+            ("Bool", "", &[]),
+            ("Int", "", &[]),
+            ("Float", "", &[]),
+            ("String", "", &[]),
+            ("EntityRef", "", &[]),
+            ("DefRef", "", &[]),
+            ("List", "List of a type", &[("0", "PropertyType")]),
+            ("Map", "", &[]),
+        ],
+    ),
+    (
+        "Interpolation",
+        "Interpolation between curve points",
+        &[
+            ("Linear", "Straight lines between points", &[]),
+            ("Step", "Hold each value until the next point", &[]),
+            ("Bezier", "Smooth ease between points", &[]),
+        ],
+    ),
+];
+
+const EFFECTS: &[Variant] = &[
+    (
+        "SetProperty",
+        "Set a property on the target entity",
+        &[("property", "String"), ("value", "Expr")],
+    ),
+    (
+        "ModifyProperty",
+        "Modify a numeric property on the target entity",
+        &[
+            ("property", "String"),
+            ("op", "ModifyOp"),
+            ("value", "Expr"),
+        ],
+    ),
+    (
+        "SetEntityProperty",
+        "Set a property on a specific entity",
+        &[
+            ("target", "EntityRef"),
+            ("property", "String"),
+            ("value", "Expr"),
+        ],
+    ),
+    (
+        "ModifyEntityProperty",
+        "Modify a numeric property on a specific entity",
+        &[
+            ("target", "EntityRef"),
+            ("property", "String"),
+            ("op", "ModifyOp"),
+            ("value", "Expr"),
+        ],
+    ),
+    (
+        "SetGlobal",
+        "Set a global property",
+        &[("property", "String"), ("value", "Expr")],
+    ),
+    (
+        "ModifyGlobal",
+        "Modify a global numeric property",
+        &[
+            ("property", "String"),
+            ("op", "ModifyOp"),
+            ("value", "Expr"),
+        ],
+    ),
+    (
+        "AddFlag",
+        "Add a flag to the target entity",
+        &[("0", "DefId")],
+    ),
+    (
+        "RemoveFlag",
+        "Remove a flag from the target entity",
+        &[("0", "DefId")],
+    ),
+    (
+        "AddFlagFor",
+        "Add a flag to the target entity that expires after a number of ticks",
+        &[("flag", "DefId"), ("duration_ticks", "Expr")],
+    ),
+    (
+        "AddEntityFlag",
+        "Add a flag to a specific entity",
+        &[("target", "EntityRef"), ("flag", "DefId")],
+    ),
+    (
+        "RemoveEntityFlag",
+        "Remove a flag from a specific entity",
+        &[("target", "EntityRef"), ("flag", "DefId")],
+    ),
+    (
+        "AddTag",
+        "Add a tag to the target entity",
+        &[("0", "String")],
+    ),
+    (
+        "RemoveTag",
+        "Remove a tag from the target entity",
+        &[("0", "String")],
+    ),
+    (
+        "SpawnEntity",
+        "Spawn a new entity",
+        &[("kind", "DefId"), ("properties", "Vec<(String, Expr)>")],
+    ),
+    ("DestroyTarget", "Destroy the target entity", &[]),
+    (
+        "DestroyEntity",
+        "Destroy a specific entity",
+        &[("0", "EntityRef")],
+    ),
+    (
+        "EmitEvent",
+        "Emit an event",
+        &[
+            ("event", "DefId"),
+            ("target", "EntityRef"),
+            ("params", "Vec<(String, Expr)>"),
+        ],
+    ),
+    (
+        "ScheduleEvent",
+        "Schedule an event for a future tick",
+        &[
+            ("event", "DefId"),
+            ("target", "EntityRef"),
+            ("delay_ticks", "Expr"),
+            ("params", "Vec<(String, Expr)>"),
+        ],
+    ),
+    (
+        "If",
+        "Apply effects conditionally",
+        &[
+            ("condition", "Expr"),
+            ("then_effects", "Vec<Effect>"),
+            ("else_effects", "Vec<Effect>"),
+        ],
+    ),
+    ("Sequence", "Apply several effects", &[("0", "Vec<Effect>")]),
+    (
+        "ForEachEntity",
+        "Apply effects to each entity of a kind",
+        &[
+            ("kind", "DefId"),
+            ("filter", "Option<Expr>"),
+            ("effects", "Vec<Effect>"),
+        ],
+    ),
+    (
+        "RandomChoice",
+        "Apply one branch, chosen by weight",
+        &[("choices", "Vec<(Expr, Vec<Effect>)>")],
+    ),
+    (
+        "Invoke",
+        "Run an effect template with arguments",
+        &[("template", "DefId"), ("args", "Vec<(String, Expr)>")],
+    ),
+    (
+        "Log",
+        "Log a message",
+        &[("level", "LogLevel"), ("message", "Expr")],
+    ),
+    (
+        "Notify",
+        "Send a notification to the UI",
+        &[
+            ("kind", "DefId"),
+            ("title", "Expr"),
+            ("message", "Expr"),
+            ("target", "EntityRef"),
+        ],
+    ),
+];
+
+const EXPRESSIONS: &[Variant] = &[
+    ("Literal", "A literal value", &[("0", "Value")]),
+    (
+        "Property",
+        "Property of the target entity",
+        &[("0", "String")],
+    ),
+    (
+        "EntityProperty",
+        "Property of a specific entity",
+        &[("0", "EntityRef"), ("1", "String")],
+    ),
+    ("Global", "Global property", &[("0", "String")]),
+    (
+        "Param",
+        "Parameter of the current context",
+        &[("0", "String")],
+    ),
+    ("Add", "Sum", &[("0", "Expr"), ("1", "Expr")]),
+    ("Sub", "Difference", &[("0", "Expr"), ("1", "Expr")]),
+    ("Mul", "Product", &[("0", "Expr"), ("1", "Expr")]),
+    ("Div", "Quotient", &[("0", "Expr"), ("1", "Expr")]),
+    ("Mod", "Remainder", &[("0", "Expr"), ("1", "Expr")]),
+    ("Neg", "Negation", &[("0", "Expr")]),
+    ("Abs", "Absolute value", &[("0", "Expr")]),
+    (
+        "Min",
+        "Smaller of two values",
+        &[("0", "Expr"), ("1", "Expr")],
+    ),
+    (
+        "Max",
+        "Larger of two values",
+        &[("0", "Expr"), ("1", "Expr")],
+    ),
+    (
+        "Clamp",
+        "Value clamped between min and max",
+        &[("0", "Expr"), ("1", "Expr"), ("2", "Expr")],
+    ),
+    ("Floor", "Round down", &[("0", "Expr")]),
+    ("Ceil", "Round up", &[("0", "Expr")]),
+    ("Round", "Round to nearest", &[("0", "Expr")]),
+    ("Eq", "Equal", &[("0", "Expr"), ("1", "Expr")]),
+    ("Ne", "Not equal", &[("0", "Expr"), ("1", "Expr")]),
+    ("Lt", "Less than", &[("0", "Expr"), ("1", "Expr")]),
+    ("Le", "Less than or equal", &[("0", "Expr"), ("1", "Expr")]),
+    ("Gt", "Greater than", &[("0", "Expr"), ("1", "Expr")]),
+    (
+        "Ge",
+        "Greater than or equal",
+        &[("0", "Expr"), ("1", "Expr")],
+    ),
+    ("And", "All are true", &[("0", "Vec<Expr>")]),
+    ("Or", "At least one is true", &[("0", "Vec<Expr>")]),
+    ("Not", "Logical not", &[("0", "Expr")]),
+    (
+        "If",
+        "If-then-else",
+        &[("0", "Expr"), ("1", "Expr"), ("2", "Expr")],
+    ),
+    ("HasFlag", "Target has a flag", &[("0", "DefId")]),
+    ("HasTag", "Target has a tag", &[("0", "String")]),
+    ("EntityExists", "Entity exists", &[("0", "EntityRef")]),
+    (
+        "CountEntities",
+        "Number of entities of a kind",
+        &[("0", "DefId")],
+    ),
+    ("Random", "Random float in [0, 1)", &[]),
+    (
+        "RandomRange",
+        "Random float in [min, max)",
+        &[("0", "Expr"), ("1", "Expr")],
+    ),
+    (
+        "RandomInt",
+        "Random integer in [min, max]",
+        &[("0", "Expr"), ("1", "Expr")],
+    ),
+    (
+        "WeightedRandom",
+        "Random index, weighted",
+        &[("0", "Vec<Expr>")],
+    ),
+    (
+        "Curve",
+        "Sample a curve",
+        &[("id", "DefId"), ("input", "Expr")],
+    ),
+    (
+        "Income",
+        "Income per tick of a resource for the target",
+        &[("0", "DefId")],
+    ),
+    (
+        "Upkeep",
+        "Upkeep per tick of a resource for the target",
+        &[("0", "DefId")],
+    ),
+    (
+        "TicksSince",
+        "Ticks since the target gained a flag",
+        &[("0", "DefId")],
+    ),
+    (
+        "ValueAt",
+        "A target property some ticks ago",
+        &[("0", "String"), ("1", "Expr")],
+    ),
+    (
+        "MovingAvg",
+        "Average of a target property over a window of ticks",
+        &[("0", "String"), ("1", "Expr")],
+    ),
+    ("Concat", "Concatenate as text", &[("0", "Vec<Expr>")]),
+    (
+        "Format",
+        "Substitute values into a template",
+        &[("0", "String"), ("1", "Vec<Expr>")],
+    ),
+    (
+        "Localized",
+        "Localized text with parameters",
+        &[("0", "String"), ("1", "Vec<(String, Expr)>")],
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Loader;
+    use serde::de::{self, Deserialize, Deserializer, Visitor};
+    use std::fmt;
+
+    /// Names serde knows for a type: struct fields or enum variants
+    #[derive(Debug)]
+    struct Names(&'static [&'static str]);
+
+    impl fmt::Display for Names {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{:?}", self.0)
+        }
+    }
+
+    impl std::error::Error for Names {}
+
+    impl de::Error for Names {
+        fn custom<T: fmt::Display>(_: T) -> Self {
+            Names(&[])
+        }
+    }
+
+    /// Deserializer that stops at the first struct or enum, reporting its
+    /// names as the error
+    struct Capture;
+
+    impl<'de> Deserializer<'de> for Capture {
+        type Error = Names;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Names> {
+            Err(Names(&[]))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            fields: &'static [&'static str],
+            _: V,
+        ) -> Result<V::Value, Names> {
+            Err(Names(fields))
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            variants: &'static [&'static str],
+            _: V,
+        ) -> Result<V::Value, Names> {
+            Err(Names(variants))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map identifier ignored_any
+        }
+    }
+
+    fn names<'de, T: Deserialize<'de>>() -> Vec<&'static str> {
+        match T::deserialize(Capture) {
+            Ok(_) => Vec::new(),
+            Err(Names(names)) => names.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_tables_match_schema() {
+        use crate::schema::entity::{PropertyDef, PropertyType};
+        use crate::schema::event::{EventOption, MeanTimeToHappen, MtthModifier, PoolEntry};
+        use crate::schema::*;
+        use pulsive_core::{
+            effect::LogLevel, Effect, EntityRef, Expr, Interpolation, ModifyOp, OverflowPolicy,
+        };
+
+        let schema = Introspection::schema();
+        let listed = |name: &str| -> Vec<String> {
+            let info = schema.schema_type(name).unwrap();
+            info.fields
+                .iter()
+                .map(|f| f.name.clone())
+                .chain(info.variants.iter().map(|v| v.name.clone()))
+                .collect()
+        };
+        let cases: Vec<(&str, Vec<&str>)> = vec![
+            ("ResourceDef", names::<ResourceDef>()),
+            ("EventDef", names::<EventDef>()),
+            ("MeanTimeToHappen", names::<MeanTimeToHappen>()),
+            ("MtthModifier", names::<MtthModifier>()),
+            ("EventOption", names::<EventOption>()),
+            ("EventPoolDef", names::<crate::EventPoolDef>()),
+            ("PoolEntry", names::<PoolEntry>()),
+            ("EntityTypeDef", names::<EntityTypeDef>()),
+            ("PropertyDef", names::<PropertyDef>()),
+            ("CurveDef", names::<CurveDef>()),
+            ("EffectTemplateDef", names::<EffectTemplateDef>()),
+            ("LocalizationDef", names::<LocalizationDef>()),
+            ("PackageManifest", names::<crate::PackageManifest>()),
+            ("EntityRef", names::<EntityRef>()),
+            ("ModifyOp", names::<ModifyOp>()),
+            ("LogLevel", names::<LogLevel>()),
+            ("OverflowPolicy", names::<OverflowPolicy>()),
+            ("PropertyType", names::<PropertyType>()),
+            ("Interpolation", names::<Interpolation>()),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(listed(name), expected, "{}", name);
+        }
+
+        let variants = |infos: &[VariantInfo]| -> Vec<String> {
+            infos.iter().map(|v| v.name.clone()).collect()
+        };
+        assert_eq!(variants(&schema.effects), names::<Effect>());
+        assert_eq!(variants(&schema.expressions), names::<Expr>());
+    }
+
+    #[test]
+    fn test_introspect_definitions() {
+        let mut loader = Loader::new();
+        loader
+            .load_resources_str(r#"(resources: [(id: "gold", name: "Gold")])"#)
+            .unwrap();
+        loader
+            .load_localization_str(r#"(language: "en", strings: {"gold.name": "Gold"})"#)
+            .unwrap();
+        let introspection = loader.finish().introspect();
+        let gold = &introspection.definitions[0];
+        assert_eq!((gold.kind.as_str(), gold.id.as_str()), ("resource", "gold"));
+        assert_eq!(gold.location.as_ref().map(|l| l.line), Some(1));
+        assert_eq!(introspection.localization_keys, vec!["gold.name"]);
+        assert!(introspection.functions.iter().any(|f| f.name == "curve"));
+    }
+}
@@ -1,17 +1,69 @@
 //! Pulsive Script - RON loader and schema definitions
 //!
 //! Loads game content from RON files:
-//! - Resource definitions
-//! - Event definitions with conditions and effects
+//! - Resource definitions, with holders, caps, income and upkeep
+//! - Event definitions with conditions and effects (as enum trees or
+//!   expression strings like `"global.gold >= 100"`)
+//! - Event pools: weighted random events with cooldowns and fire limits
 //! - Entity type schemas
+//! - Value curves (piecewise linear, step or Bézier) for `Expr::Curve`
+//! - Effect templates: reusable effect blocks with parameters, run with
+//!   `Effect::Invoke`
+//! - Localization tables (per-language key to string files)
+//!
+//! Enable the `json` or `yaml` feature to load the same schema from JSON or
+//! YAML files, and to [`convert`] content between formats.
+//!
+//! [`GameDefs::install`] registers the definitions' handlers, resources
+//! and entity templates on a runtime. Definitions can be hot
+//! reloaded into a running simulation; enable the `watch` feature to reload
+//! automatically when files change.
+//!
+//! Several content directories can be loaded together as packages (mods),
+//! with later packages overriding or patching earlier definitions.
+//!
+//! Loaded definitions can be validated for undefined references, type
+//! mismatches and cyclic event chains, with diagnostics pointing at the
+//! offending file, line and field.
+//!
+//! Definitions can be imported into and exported from a database with
+//! [`DefinitionStore`], so servers can treat the database as the source of
+//! truth while content is still authored in files.
+//!
+//! [`GameDefs::introspect`] describes the schema, effect kinds, expression
+//! functions and loaded definitions for editors and tooling.
 
 mod error;
+mod expressions;
+mod format;
+mod install;
+mod introspect;
 mod loader;
+pub mod package;
+mod reload;
 mod schema;
+mod store;
+mod validate;
 
 pub use error::{Error, Result};
+pub use expressions::EXPR_FIELDS;
+pub use format::{convert, convert_file, Format};
+pub use introspect::{
+    DefinitionInfo, FieldInfo, FunctionInfo, Introspection, TypeInfo, VariantInfo,
+};
 pub use loader::{GameDefs, Loader};
+pub use package::{Package, PackageManifest, PackageSet};
+#[cfg(feature = "watch")]
+pub use reload::DefsWatcher;
+pub use reload::{DefChanges, DefsDiff};
+pub use schema::curve::CurveDefs;
+pub use schema::effect_template::EffectTemplateDefs;
 pub use schema::entity::{EntityTypeDefs, PropertyDef, PropertyType};
-pub use schema::event::{EventDefs, EventOption, MeanTimeToHappen, MtthModifier};
+pub use schema::event::{
+    EventDefs, EventOption, EventPoolDef, EventPoolDefs, MeanTimeToHappen, MtthModifier, PoolEntry,
+};
+pub use schema::localization::{Localization, LocalizationDef};
 pub use schema::resource::ResourceDefs;
-pub use schema::{EntityTypeDef, EventDef, ResourceDef};
+pub use schema::{CurveDef, EffectTemplateDef, EntityTypeDef, EventDef, ResourceDef};
+pub use store::DefinitionStore;
+pub use validate::{DefSources, Diagnostic, Severity, SourceLocation};
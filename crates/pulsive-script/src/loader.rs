@@ -1,14 +1,25 @@
-//! RON script loader
+//! Script loader
 
 use crate::error::{Error, Result};
-use crate::schema::{EntityTypeDef, EventDef, ResourceDef};
-use pulsive_core::DefId;
+use crate::expressions::compile_expressions;
+use crate::format::{Content, Format};
+use crate::schema::curve::CurveDefs;
+use crate::schema::effect_template::EffectTemplateDefs;
+use crate::schema::entity::EntityTypeDefs;
+use crate::schema::event::{EventDefs, EventPoolDef, EventPoolDefs};
+use crate::schema::resource::ResourceDefs;
+use crate::schema::{
+    CurveDef, EffectTemplateDef, EntityTypeDef, EventDef, Localization, LocalizationDef,
+    ResourceDef,
+};
+use crate::validate::{DefSources, SourceLocation};
+use pulsive_core::{DefId, Value, ValueMap};
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Loaded game definitions
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct GameDefs {
     /// Resource definitions by ID
     pub resources: HashMap<DefId, ResourceDef>,
@@ -16,6 +27,16 @@ pub struct GameDefs {
     pub events: HashMap<DefId, EventDef>,
     /// Entity type definitions by ID
     pub entity_types: HashMap<DefId, EntityTypeDef>,
+    /// Curve definitions by ID
+    pub curves: HashMap<DefId, CurveDef>,
+    /// Event pool definitions by ID
+    pub event_pools: HashMap<DefId, EventPoolDef>,
+    /// Effect template definitions by ID
+    pub effect_templates: HashMap<DefId, EffectTemplateDef>,
+    /// Localized strings
+    pub localization: Localization,
+    /// Where each definition was loaded from (for diagnostics)
+    pub sources: DefSources,
 }
 
 impl GameDefs {
@@ -38,11 +59,103 @@ impl GameDefs {
     pub fn get_entity_type(&self, id: &DefId) -> Option<&EntityTypeDef> {
         self.entity_types.get(id)
     }
+
+    /// Get a curve definition
+    pub fn get_curve(&self, id: &DefId) -> Option<&CurveDef> {
+        self.curves.get(id)
+    }
+
+    /// Get an event pool definition
+    pub fn get_event_pool(&self, id: &DefId) -> Option<&EventPoolDef> {
+        self.event_pools.get(id)
+    }
+
+    /// Get an effect template definition
+    pub fn get_effect_template(&self, id: &DefId) -> Option<&EffectTemplateDef> {
+        self.effect_templates.get(id)
+    }
+
+    /// Render a localization key in a language, substituting `{name}`
+    /// placeholders with parameters
+    pub fn localize(&self, key: &str, language: &str, params: &ValueMap) -> String {
+        self.localization.localize(key, language, params)
+    }
+
+    /// Render a value (e.g. a notification title) in a language
+    pub fn localize_value(&self, value: &Value, language: &str) -> String {
+        self.localization.render(value, language)
+    }
+
+    /// Hash the gameplay definitions, so peers can check they run the same
+    /// content
+    ///
+    /// Covers resources, events, entity types, curves, event pools and
+    /// effect templates, in ID order. Localization and source locations are left out, since they
+    /// can't cause a desync. The hash (64-bit FNV-1a) is the same across
+    /// platforms and builds.
+    pub fn content_hash(&self) -> u64 {
+        let mut hash = ContentHasher::new();
+        hash.defs("resources", &self.resources);
+        hash.defs("events", &self.events);
+        hash.defs("entity_types", &self.entity_types);
+        hash.defs("curves", &self.curves);
+        hash.defs("event_pools", &self.event_pools);
+        hash.defs("effect_templates", &self.effect_templates);
+        hash.0
+    }
 }
 
-/// Loader for RON game scripts
+/// 64-bit FNV-1a over serialized definitions
+struct ContentHasher(u64);
+
+impl ContentHasher {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+        // Separator, so adjacent fields can't run together
+        self.0 ^= 0xff;
+        self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+    }
+
+    fn defs<T: serde::Serialize>(&mut self, kind: &str, defs: &HashMap<DefId, T>) {
+        self.write(kind.as_bytes());
+        let mut ids: Vec<&DefId> = defs.keys().collect();
+        ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        for id in ids {
+            self.write(id.as_str().as_bytes());
+            let def = ron::to_string(&defs[id]).expect("definitions serialize to RON");
+            self.write(def.as_bytes());
+        }
+    }
+}
+
+/// Iterate a definition map in ID order (for stable output)
+pub(crate) fn sorted<T>(map: &HashMap<DefId, T>) -> Vec<(&DefId, &T)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+    entries
+}
+
+/// List a directory in name order (for stable output)
+pub(crate) fn read_dir_sorted(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut entries = fs::read_dir(path)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    entries.sort();
+    Ok(entries)
+}
+
+/// Loader for game scripts (RON, or JSON and YAML with their features)
 pub struct Loader {
     defs: GameDefs,
+    /// File currently being loaded (for source locations)
+    current_file: Option<PathBuf>,
 }
 
 impl Loader {
@@ -50,134 +163,222 @@ impl Loader {
     pub fn new() -> Self {
         Self {
             defs: GameDefs::new(),
+            current_file: None,
         }
     }
 
-    /// Load a single RON file
+    /// Load a single content file, picking the format from its extension
+    ///
+    /// Files without a known extension are read as RON.
     pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<()> {
         let path = path.as_ref();
         let content = fs::read_to_string(path)?;
+        self.load_file_str(path, &content)
+    }
 
-        // Try to determine the type based on content or filename
-        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-
-        if filename.contains("resource") || content.contains("resources:") {
-            self.load_resources_str(&content)?;
-        } else if filename.contains("event") || content.contains("events:") {
-            self.load_events_str(&content)?;
-        } else if filename.contains("entity") || content.contains("entity_types:") {
-            self.load_entity_types_str(&content)?;
-        } else {
-            // Try each format
-            if let Ok(()) = self.load_resources_str(&content) {
-                return Ok(());
-            }
-            if let Ok(()) = self.load_events_str(&content) {
-                return Ok(());
-            }
-            if let Ok(()) = self.load_entity_types_str(&content) {
-                return Ok(());
-            }
+    /// Load the content of a file read elsewhere (e.g. from a packed game
+    /// archive), picking the format and kind from its path
+    pub fn load_file_str(&mut self, path: impl AsRef<Path>, content: &str) -> Result<()> {
+        let path = path.as_ref();
+        self.current_file = Some(path.to_path_buf());
+        let result = self.load_content(path, content);
+        self.current_file = None;
+        result
+    }
 
-            // Try as single definitions
-            self.load_single_definition(&content)?;
-        }
+    /// Load the content of a file, picking the definition kind
+    fn load_content(&mut self, path: &Path, content: &str) -> Result<()> {
+        let format = Format::from_path(path).unwrap_or(Format::Ron);
+        let filename = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        self.add(Content::parse(content, format, filename)?, content)
+    }
 
-        Ok(())
+    /// Load definitions of any kind from a string in a format
+    pub fn load_str(&mut self, content: &str, format: Format) -> Result<()> {
+        self.add(Content::parse(content, format, "")?, content)
     }
 
     /// Load resources from a RON string
     pub fn load_resources_str(&mut self, content: &str) -> Result<()> {
-        #[derive(serde::Deserialize)]
-        struct ResourceFile {
-            resources: Vec<ResourceDef>,
-        }
+        let file: ResourceDefs = ron::from_str(content)?;
+        self.add(Content::Resources(file), content)
+    }
+
+    /// Load events from a RON string
+    pub fn load_events_str(&mut self, content: &str) -> Result<()> {
+        let file: EventDefs = ron::from_str(&compile_expressions(content)?)?;
+        self.add(Content::Events(file), content)
+    }
+
+    /// Load entity types from a RON string
+    pub fn load_entity_types_str(&mut self, content: &str) -> Result<()> {
+        let file: EntityTypeDefs = ron::from_str(content)?;
+        self.add(Content::EntityTypes(file), content)
+    }
 
-        let file: ResourceFile = ron::from_str(content)?;
-        for resource in file.resources {
-            let id = resource.id.clone();
-            if self.defs.resources.contains_key(&id) {
-                return Err(Error::DuplicateDefinition(id.to_string()));
+    /// Load curves from a RON string
+    pub fn load_curves_str(&mut self, content: &str) -> Result<()> {
+        let file: CurveDefs = ron::from_str(content)?;
+        self.add(Content::Curves(file), content)
+    }
+
+    /// Load event pools from a RON string
+    pub fn load_event_pools_str(&mut self, content: &str) -> Result<()> {
+        let file: EventPoolDefs = ron::from_str(&compile_expressions(content)?)?;
+        self.add(Content::EventPools(file), content)
+    }
+
+    /// Load effect templates from a RON string
+    pub fn load_effect_templates_str(&mut self, content: &str) -> Result<()> {
+        let file: EffectTemplateDefs = ron::from_str(&compile_expressions(content)?)?;
+        self.add(Content::EffectTemplates(file), content)
+    }
+
+    /// Load localized strings from a RON string
+    pub fn load_localization_str(&mut self, content: &str) -> Result<()> {
+        let def: LocalizationDef = ron::from_str(content)?;
+        self.add(Content::Localization(def), content)
+    }
+
+    /// Add parsed content, locating its definitions in `source`
+    fn add(&mut self, content: Content, source: &str) -> Result<()> {
+        match content {
+            Content::Resources(file) => {
+                for resource in file.resources {
+                    self.add_resource(resource, source)?;
+                }
+            }
+            Content::Events(file) => {
+                for event in file.events {
+                    self.add_event(event, source)?;
+                }
+            }
+            Content::EntityTypes(file) => {
+                for entity_type in file.entity_types {
+                    self.add_entity_type(entity_type, source)?;
+                }
+            }
+            Content::Curves(file) => {
+                for curve in file.curves {
+                    self.add_curve(curve, source)?;
+                }
+            }
+            Content::EventPools(file) => {
+                for pool in file.event_pools {
+                    self.add_event_pool(pool, source)?;
+                }
+            }
+            Content::EffectTemplates(file) => {
+                for template in file.effect_templates {
+                    self.add_effect_template(template, source)?;
+                }
             }
-            self.defs.resources.insert(id, resource);
+            Content::Localization(def) => self.add_localization(def)?,
+            Content::Resource(resource) => self.add_resource(resource, source)?,
+            Content::Event(event) => self.add_event(event, source)?,
+            Content::EntityType(entity_type) => self.add_entity_type(entity_type, source)?,
+            Content::Curve(curve) => self.add_curve(curve, source)?,
+            Content::EventPool(pool) => self.add_event_pool(pool, source)?,
+            Content::EffectTemplate(template) => self.add_effect_template(template, source)?,
         }
         Ok(())
     }
 
-    /// Load events from a RON string
-    pub fn load_events_str(&mut self, content: &str) -> Result<()> {
-        #[derive(serde::Deserialize)]
-        struct EventFile {
-            events: Vec<EventDef>,
+    fn add_resource(&mut self, resource: ResourceDef, source: &str) -> Result<()> {
+        let id = resource.id.clone();
+        if self.defs.resources.contains_key(&id) {
+            return Err(Error::DuplicateDefinition(id.to_string()));
         }
+        let location = self.locate(source, &id);
+        self.defs.sources.resources.insert(id.clone(), location);
+        self.defs.resources.insert(id, resource);
+        Ok(())
+    }
 
-        let file: EventFile = ron::from_str(content)?;
-        for event in file.events {
-            let id = event.id.clone();
-            if self.defs.events.contains_key(&id) {
-                return Err(Error::DuplicateDefinition(id.to_string()));
-            }
-            self.defs.events.insert(id, event);
+    fn add_event(&mut self, event: EventDef, source: &str) -> Result<()> {
+        let id = event.id.clone();
+        if self.defs.events.contains_key(&id) {
+            return Err(Error::DuplicateDefinition(id.to_string()));
         }
+        let location = self.locate(source, &id);
+        self.defs.sources.events.insert(id.clone(), location);
+        self.defs.events.insert(id, event);
         Ok(())
     }
 
-    /// Load entity types from a RON string
-    pub fn load_entity_types_str(&mut self, content: &str) -> Result<()> {
-        #[derive(serde::Deserialize)]
-        struct EntityTypeFile {
-            entity_types: Vec<EntityTypeDef>,
+    fn add_entity_type(&mut self, entity_type: EntityTypeDef, source: &str) -> Result<()> {
+        let id = entity_type.id.clone();
+        if self.defs.entity_types.contains_key(&id) {
+            return Err(Error::DuplicateDefinition(id.to_string()));
         }
+        let location = self.locate(source, &id);
+        self.defs.sources.entity_types.insert(id.clone(), location);
+        self.defs.entity_types.insert(id, entity_type);
+        Ok(())
+    }
 
-        let file: EntityTypeFile = ron::from_str(content)?;
-        for entity_type in file.entity_types {
-            let id = entity_type.id.clone();
-            if self.defs.entity_types.contains_key(&id) {
-                return Err(Error::DuplicateDefinition(id.to_string()));
-            }
-            self.defs.entity_types.insert(id, entity_type);
+    fn add_curve(&mut self, curve: CurveDef, source: &str) -> Result<()> {
+        let id = curve.id.clone();
+        if self.defs.curves.contains_key(&id) {
+            return Err(Error::DuplicateDefinition(id.to_string()));
         }
+        let location = self.locate(source, &id);
+        self.defs.sources.curves.insert(id.clone(), location);
+        self.defs.curves.insert(id, curve);
         Ok(())
     }
 
-    /// Try to load a single definition
-    fn load_single_definition(&mut self, content: &str) -> Result<()> {
-        // Try as single resource
-        if let Ok(resource) = ron::from_str::<ResourceDef>(content) {
-            let id = resource.id.clone();
-            if self.defs.resources.contains_key(&id) {
-                return Err(Error::DuplicateDefinition(id.to_string()));
-            }
-            self.defs.resources.insert(id, resource);
-            return Ok(());
+    fn add_event_pool(&mut self, pool: EventPoolDef, source: &str) -> Result<()> {
+        let id = pool.id.clone();
+        if self.defs.event_pools.contains_key(&id) {
+            return Err(Error::DuplicateDefinition(id.to_string()));
         }
+        let location = self.locate(source, &id);
+        self.defs.sources.event_pools.insert(id.clone(), location);
+        self.defs.event_pools.insert(id, pool);
+        Ok(())
+    }
 
-        // Try as single event
-        if let Ok(event) = ron::from_str::<EventDef>(content) {
-            let id = event.id.clone();
-            if self.defs.events.contains_key(&id) {
-                return Err(Error::DuplicateDefinition(id.to_string()));
-            }
-            self.defs.events.insert(id, event);
-            return Ok(());
+    fn add_effect_template(&mut self, template: EffectTemplateDef, source: &str) -> Result<()> {
+        let id = template.id.clone();
+        if self.defs.effect_templates.contains_key(&id) {
+            return Err(Error::DuplicateDefinition(id.to_string()));
         }
+        let location = self.locate(source, &id);
+        self.defs
+            .sources
+            .effect_templates
+            .insert(id.clone(), location);
+        self.defs.effect_templates.insert(id, template);
+        Ok(())
+    }
 
-        // Try as single entity type
-        if let Ok(entity_type) = ron::from_str::<EntityTypeDef>(content) {
-            let id = entity_type.id.clone();
-            if self.defs.entity_types.contains_key(&id) {
-                return Err(Error::DuplicateDefinition(id.to_string()));
-            }
-            self.defs.entity_types.insert(id, entity_type);
-            return Ok(());
+    fn add_localization(&mut self, def: LocalizationDef) -> Result<()> {
+        if let Some(key) = def
+            .strings
+            .keys()
+            .find(|key| self.defs.localization.contains(&def.language, key))
+        {
+            return Err(Error::DuplicateDefinition(format!(
+                "{} string {}",
+                def.language, key
+            )));
         }
+        self.defs.localization.add(def);
+        Ok(())
+    }
 
-        Err(Error::InvalidSchema(
-            "Could not parse as any known definition type".to_string(),
-        ))
+    /// Find where a definition with `id` is declared in `content`
+    fn locate(&self, content: &str, id: &DefId) -> SourceLocation {
+        let (line, column) = find_id(content, id.as_str()).unwrap_or((1, 1));
+        SourceLocation {
+            file: self.current_file.clone(),
+            line,
+            column,
+        }
     }
 
-    /// Load all RON files from a directory
+    /// Load all content files from a directory
     pub fn load_directory(&mut self, path: impl AsRef<Path>) -> Result<()> {
         let path = path.as_ref();
 
@@ -192,7 +393,7 @@ impl Loader {
             let entry = entry?;
             let file_path = entry.path();
 
-            if file_path.extension().map(|e| e == "ron").unwrap_or(false) {
+            if Format::from_path(&file_path).is_some() {
                 self.load_file(&file_path)?;
             } else if file_path.is_dir() {
                 // Recursively load subdirectories
@@ -220,6 +421,29 @@ impl Default for Loader {
     }
 }
 
+/// Find the 1-based line and column of an `id: "<id>"` field
+///
+/// Also matches JSON (`"id": "<id>"`) and bare YAML (`id: <id>`) fields.
+fn find_id(content: &str, id: &str) -> Option<(usize, usize)> {
+    let offset = content.match_indices(id).find_map(|(offset, _)| {
+        let after = content[offset + id.len()..].chars().next();
+        if after.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+        let quoted = content[..offset].ends_with(['"', '\'']);
+        let start = if quoted { offset - 1 } else { offset };
+        let before = content[..start].trim_end();
+        let before = before.strip_suffix(':')?.trim_end();
+        before
+            .trim_end_matches('"')
+            .ends_with("id")
+            .then_some(start)
+    })?;
+    let line_start = content[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = content[..offset].matches('\n').count() + 1;
+    Some((line, content[line_start..offset].chars().count() + 1))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -252,6 +476,27 @@ mod tests {
         assert!(defs.get_resource(&DefId::new("manpower")).is_some());
     }
 
+    #[test]
+    fn test_content_hash() {
+        let load = |base_value: f64| {
+            let mut loader = Loader::new();
+            loader
+                .load_resources_str(&format!(
+                    r#"(resources: [
+                        (id: "gold", name: "Gold", base_value: {:?}),
+                        (id: "iron", name: "Iron", base_value: 1.0),
+                    ])"#,
+                    base_value
+                ))
+                .unwrap();
+            loader.finish()
+        };
+
+        assert_eq!(load(1.0).content_hash(), load(1.0).content_hash());
+        assert_ne!(load(1.0).content_hash(), load(2.0).content_hash());
+        assert_ne!(load(1.0).content_hash(), GameDefs::new().content_hash());
+    }
+
     #[test]
     fn test_load_single_resource() {
         let content = r#"
@@ -264,9 +509,28 @@ mod tests {
         "#;
 
         let mut loader = Loader::new();
-        loader.load_single_definition(content).unwrap();
+        loader.load_str(content, Format::Ron).unwrap();
+
+        let defs = loader.finish();
+        assert!(defs.get_resource(&DefId::new("gold")).is_some());
+    }
+
+    #[test]
+    fn test_load_file_str() {
+        let content = r#"(id: "gold", name: "Gold", base_value: 1.0)"#;
+
+        let mut loader = Loader::new();
+        loader
+            .load_file_str("res://content/resources/gold.ron", content)
+            .unwrap();
 
         let defs = loader.finish();
         assert!(defs.get_resource(&DefId::new("gold")).is_some());
+        let location = &defs.sources.resources[&DefId::new("gold")];
+        assert_eq!(
+            location.file.as_deref(),
+            Some(Path::new("res://content/resources/gold.ron"))
+        );
+        assert_eq!(location.line, 1);
     }
 }
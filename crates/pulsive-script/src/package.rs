@@ -0,0 +1,404 @@
+//! Content packages and mods
+//!
+//! A package is a content directory with a `package.ron` manifest:
+//!
+//! ```ron
+//! (
+//!     name: "better_harvests",
+//!     version: "1.2.0",
+//!     dependencies: ["base"],
+//!     load_after: ["economy_tweaks"],
+//! )
+//! ```
+//!
+//! A [`PackageSet`] loads several packages in dependency order. Definitions
+//! from later packages are combined with earlier ones per definition kind:
+//!
+//! | Kind            | Redefinition by a later package                        |
+//! |-----------------|--------------------------------------------------------|
+//! | Resource        | replaces the earlier definition                        |
+//! | Event           | replaces the earlier definition                        |
+//! | Entity type     | patches it: properties merge by name, defaults by key, |
+//! |                 | and the other fields are replaced                      |
+//! | Curve           | replaces the earlier definition                        |
+//! | Event pool      | replaces the earlier definition                        |
+//! | Effect template | replaces the earlier definition                        |
+//! | Localization    | replaces strings key by key                            |
+
+use crate::error::{Error, Result};
+use crate::format::Format;
+use crate::loader::{read_dir_sorted, GameDefs, Loader};
+use crate::schema::EntityTypeDef;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of a package manifest
+pub const MANIFEST_FILE: &str = "package.ron";
+
+/// Package manifest (`package.ron`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageManifest {
+    /// Unique package name
+    pub name: String,
+    /// Package version
+    #[serde(default)]
+    pub version: String,
+    /// Description
+    #[serde(default)]
+    pub description: String,
+    /// Packages that must be present and are loaded first
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// Packages loaded first if present (optional ordering)
+    #[serde(default)]
+    pub load_after: Vec<String>,
+}
+
+impl PackageManifest {
+    /// Create a manifest with no dependencies
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            description: String::new(),
+            dependencies: Vec::new(),
+            load_after: Vec::new(),
+        }
+    }
+
+    /// Add a dependency
+    pub fn with_dependency(mut self, name: impl Into<String>) -> Self {
+        self.dependencies.push(name.into());
+        self
+    }
+}
+
+/// A content package: a manifest and the directory it describes
+#[derive(Debug, Clone)]
+pub struct Package {
+    /// The package manifest
+    pub manifest: PackageManifest,
+    /// Directory holding the package content
+    pub root: PathBuf,
+}
+
+impl Package {
+    /// Open a package directory, reading its manifest
+    pub fn open(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        let content = fs::read_to_string(root.join(MANIFEST_FILE))?;
+        let manifest = ron::from_str(&content)?;
+        Ok(Self { manifest, root })
+    }
+
+    /// Get the package name
+    pub fn name(&self) -> &str {
+        &self.manifest.name
+    }
+
+    /// Load the package's definitions (every content file except the
+    /// manifest)
+    pub fn load(&self) -> Result<GameDefs> {
+        let mut loader = Loader::new();
+        load_content_dir(&mut loader, &self.root, true)?;
+        Ok(loader.finish())
+    }
+}
+
+fn load_content_dir(loader: &mut Loader, dir: &Path, is_root: bool) -> Result<()> {
+    for path in read_dir_sorted(dir)? {
+        if path.is_dir() {
+            load_content_dir(loader, &path, false)?;
+        } else if Format::from_path(&path).is_some()
+            && !(is_root && path.file_name().is_some_and(|n| n == MANIFEST_FILE))
+        {
+            loader.load_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// A set of packages loaded together in dependency order
+#[derive(Debug, Clone, Default)]
+pub struct PackageSet {
+    packages: Vec<Package>,
+}
+
+impl PackageSet {
+    /// Create an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a package
+    ///
+    /// Among packages with no ordering constraints between them, those
+    /// added first load first.
+    pub fn add(&mut self, package: Package) -> Result<()> {
+        if self.get(package.name()).is_some() {
+            return Err(Error::DuplicateDefinition(format!(
+                "package {}",
+                package.name()
+            )));
+        }
+        self.packages.push(package);
+        Ok(())
+    }
+
+    /// Open and add a package directory
+    pub fn add_dir(&mut self, root: impl AsRef<Path>) -> Result<()> {
+        self.add(Package::open(root)?)
+    }
+
+    /// Add every subdirectory of `dir` that has a manifest, in name order
+    pub fn discover(&mut self, dir: impl AsRef<Path>) -> Result<usize> {
+        let mut added = 0;
+        for path in read_dir_sorted(dir.as_ref())? {
+            if path.join(MANIFEST_FILE).is_file() {
+                self.add_dir(&path)?;
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    /// Get a package by name
+    pub fn get(&self, name: &str) -> Option<&Package> {
+        self.packages.iter().find(|p| p.name() == name)
+    }
+
+    /// Get the packages in the order they were added
+    pub fn packages(&self) -> &[Package] {
+        &self.packages
+    }
+
+    /// Resolve the load order
+    ///
+    /// Fails if a dependency is missing or dependencies form a cycle.
+    pub fn load_order(&self) -> Result<Vec<&Package>> {
+        let index = |name: &str| self.packages.iter().position(|p| p.name() == name);
+
+        // Packages each package must load after
+        let mut after: Vec<Vec<usize>> = Vec::with_capacity(self.packages.len());
+        for package in &self.packages {
+            let mut before = Vec::new();
+            for dependency in &package.manifest.dependencies {
+                let i = index(dependency).ok_or_else(|| {
+                    Error::Package(format!(
+                        "`{}` depends on missing package `{}`",
+                        package.name(),
+                        dependency
+                    ))
+                })?;
+                before.push(i);
+            }
+            before.extend(package.manifest.load_after.iter().filter_map(|n| index(n)));
+            after.push(before);
+        }
+
+        let mut order = Vec::with_capacity(self.packages.len());
+        let mut loaded = vec![false; self.packages.len()];
+        while order.len() < self.packages.len() {
+            // First package (in insertion order) whose predecessors are loaded
+            let next = (0..self.packages.len())
+                .find(|&i| !loaded[i] && after[i].iter().all(|&j| loaded[j]));
+            let Some(i) = next else {
+                let stuck: Vec<&str> = (0..self.packages.len())
+                    .filter(|&i| !loaded[i])
+                    .map(|i| self.packages[i].name())
+                    .collect();
+                return Err(Error::Package(format!(
+                    "cyclic package dependencies between {}",
+                    stuck.join(", ")
+                )));
+            };
+            loaded[i] = true;
+            order.push(&self.packages[i]);
+        }
+        Ok(order)
+    }
+
+    /// Load every package in load order, combining their definitions
+    pub fn load(&self) -> Result<GameDefs> {
+        let mut defs = GameDefs::new();
+        for package in self.load_order()? {
+            defs.merge(package.load()?);
+        }
+        Ok(defs)
+    }
+}
+
+impl GameDefs {
+    /// Combine definitions from a later package into these
+    ///
+    /// Resources, events, curves, event pools and effect templates are
+    /// replaced; entity types are patched (see the
+    /// [module docs](crate::package)).
+    pub fn merge(&mut self, later: GameDefs) {
+        self.resources.extend(later.resources);
+        self.events.extend(later.events);
+        self.curves.extend(later.curves);
+        self.event_pools.extend(later.event_pools);
+        self.effect_templates.extend(later.effect_templates);
+        for (id, entity_type) in later.entity_types {
+            match self.entity_types.get_mut(&id) {
+                Some(existing) => existing.patch(entity_type),
+                None => {
+                    self.entity_types.insert(id, entity_type);
+                }
+            }
+        }
+
+        self.localization.merge(later.localization);
+        self.sources.resources.extend(later.sources.resources);
+        self.sources.events.extend(later.sources.events);
+        self.sources.entity_types.extend(later.sources.entity_types);
+        self.sources.curves.extend(later.sources.curves);
+        self.sources.event_pools.extend(later.sources.event_pools);
+        self.sources
+            .effect_templates
+            .extend(later.sources.effect_templates);
+    }
+}
+
+impl EntityTypeDef {
+    /// Patch this definition with a later one
+    ///
+    /// Properties are merged by name and defaults by key, with the later
+    /// definition winning. Name, description, parent and category are
+    /// replaced when the later definition sets them.
+    pub fn patch(&mut self, later: EntityTypeDef) {
+        if !later.name.is_empty() {
+            self.name = later.name;
+        }
+        if !later.description.is_empty() {
+            self.description = later.description;
+        }
+        if later.extends.is_some() {
+            self.extends = later.extends;
+        }
+        if later.category.is_some() {
+            self.category = later.category;
+        }
+
+        for property in later.properties {
+            match self.properties.iter_mut().find(|p| p.name == property.name) {
+                Some(existing) => *existing = property,
+                None => self.properties.push(property),
+            }
+        }
+        for (key, value) in later.defaults {
+            match self.defaults.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, existing)) => *existing = value,
+                None => self.defaults.push((key, value)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::{DefId, Value};
+
+    /// Write a package into a fresh temporary directory
+    fn write_package(dir: &Path, manifest: &str, files: &[(&str, &str)]) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(dir.join(MANIFEST_FILE), manifest).unwrap();
+        for (name, content) in files {
+            fs::write(dir.join(name), content).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_load_order_and_overrides() {
+        let root = std::env::temp_dir().join(format!("pulsive-packages-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        // Discovered in name order: "a_mod" before "base", but it depends on it
+        write_package(
+            &root.join("a_mod"),
+            r#"(name: "mod", version: "0.1.0", dependencies: ["base"])"#,
+            &[
+                (
+                    "resources.ron",
+                    r#"(resources: [(id: "gold", name: "Shiny Gold", base_value: 5.0)])"#,
+                ),
+                (
+                    "entities.ron",
+                    r#"(entity_types: [(id: "nation", name: "", defaults: [("prestige", Int(1))])])"#,
+                ),
+            ],
+        );
+        write_package(
+            &root.join("base"),
+            r#"(name: "base", version: "1.0.0")"#,
+            &[
+                (
+                    "resources.ron",
+                    r#"(resources: [(id: "gold", name: "Gold", base_value: 1.0)])"#,
+                ),
+                (
+                    "entities.ron",
+                    r#"(entity_types: [(id: "nation", name: "Nation", defaults: [("gold", Float(10.0))])])"#,
+                ),
+            ],
+        );
+
+        let mut packages = PackageSet::new();
+        assert_eq!(packages.discover(&root).unwrap(), 2);
+        let order: Vec<&str> = packages
+            .load_order()
+            .unwrap()
+            .iter()
+            .map(|p| p.name())
+            .collect();
+        assert_eq!(order, vec!["base", "mod"]);
+
+        let defs = packages.load().unwrap();
+        let gold = defs.get_resource(&DefId::new("gold")).unwrap();
+        assert_eq!(gold.base_value, 5.0);
+        let nation = defs.get_entity_type(&DefId::new("nation")).unwrap();
+        assert_eq!(nation.name, "Nation");
+        assert_eq!(
+            nation.defaults,
+            vec![
+                ("gold".to_string(), Value::Float(10.0)),
+                ("prestige".to_string(), Value::Int(1)),
+            ]
+        );
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_dependency_errors() {
+        let package = |manifest: PackageManifest| Package {
+            manifest,
+            root: PathBuf::new(),
+        };
+
+        let mut missing = PackageSet::new();
+        missing
+            .add(package(
+                PackageManifest::new("mod", "1.0").with_dependency("base"),
+            ))
+            .unwrap();
+        assert!(matches!(missing.load_order(), Err(Error::Package(_))));
+
+        let mut cyclic = PackageSet::new();
+        cyclic
+            .add(package(
+                PackageManifest::new("a", "1.0").with_dependency("b"),
+            ))
+            .unwrap();
+        cyclic
+            .add(package(
+                PackageManifest::new("b", "1.0").with_dependency("a"),
+            ))
+            .unwrap();
+        assert!(cyclic.load_order().is_err());
+        assert!(cyclic
+            .add(package(PackageManifest::new("a", "2.0")))
+            .is_err());
+    }
+}
@@ -0,0 +1,347 @@
+//! Hot reload of game definitions
+//!
+//! Reloaded definitions are diffed against the ones currently in use, and
+//! only the event handlers of added, removed or changed events are
+//! re-registered on the live [`Runtime`], so a running simulation keeps
+//! its model and queued messages. With the `watch` feature,
+//! [`Loader::watch`] reloads automatically when files change on disk.
+
+use crate::error::Result;
+use crate::loader::{sorted, GameDefs, Loader};
+use pulsive_core::{DefId, Runtime};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Changes to one kind of definition
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DefChanges {
+    /// Definitions only in the new set
+    pub added: Vec<DefId>,
+    /// Definitions only in the old set
+    pub removed: Vec<DefId>,
+    /// Definitions present in both whose content changed
+    pub updated: Vec<DefId>,
+}
+
+impl DefChanges {
+    /// Check if nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+
+    fn between<T: Serialize>(old: &HashMap<DefId, T>, new: &HashMap<DefId, T>) -> Self {
+        let mut changes = Self::default();
+        for (id, def) in new {
+            match old.get(id) {
+                None => changes.added.push(id.clone()),
+                Some(old_def) if ron_repr(old_def) != ron_repr(def) => {
+                    changes.updated.push(id.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        changes.removed = old
+            .keys()
+            .filter(|id| !new.contains_key(*id))
+            .cloned()
+            .collect();
+
+        // HashMap order is arbitrary; keep reports stable
+        for ids in [
+            &mut changes.added,
+            &mut changes.removed,
+            &mut changes.updated,
+        ] {
+            ids.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        }
+        changes
+    }
+}
+
+/// Differences between two sets of game definitions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DefsDiff {
+    /// Resource changes
+    pub resources: DefChanges,
+    /// Event changes
+    pub events: DefChanges,
+    /// Entity type changes
+    pub entity_types: DefChanges,
+    /// Curve changes
+    pub curves: DefChanges,
+    /// Event pool changes
+    pub event_pools: DefChanges,
+    /// Effect template changes
+    pub effect_templates: DefChanges,
+}
+
+impl DefsDiff {
+    /// Check if nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+            && self.events.is_empty()
+            && self.entity_types.is_empty()
+            && self.curves.is_empty()
+            && self.event_pools.is_empty()
+            && self.effect_templates.is_empty()
+    }
+}
+
+/// Compare definitions through their serialized form (schema types do not
+/// implement `PartialEq`)
+fn ron_repr<T: Serialize>(value: &T) -> String {
+    ron::to_string(value).unwrap_or_default()
+}
+
+impl GameDefs {
+    /// Compute the changes needed to go from these definitions to `new`
+    pub fn diff(&self, new: &GameDefs) -> DefsDiff {
+        DefsDiff {
+            resources: DefChanges::between(&self.resources, &new.resources),
+            events: DefChanges::between(&self.events, &new.events),
+            entity_types: DefChanges::between(&self.entity_types, &new.entity_types),
+            curves: DefChanges::between(&self.curves, &new.curves),
+            event_pools: DefChanges::between(&self.event_pools, &new.event_pools),
+            effect_templates: DefChanges::between(&self.effect_templates, &new.effect_templates),
+        }
+    }
+
+    /// Replace these definitions with `new`, updating a live runtime
+    ///
+    /// Handlers of removed and updated events are unregistered (including
+    /// handlers for those events registered by other code), then handlers
+    /// for added and updated events are registered from the new
+    /// definitions. Unchanged events keep their handlers. If any entity
+    /// type changed, spawn templates are rebuilt (a changed parent affects
+    /// its children). Added and updated resources, curves and effect
+    /// templates are re-registered and removed ones unregistered. Event pool handlers embed their events'
+    /// effects, so they are all rebuilt if any pool or event changed.
+    pub fn reload(&mut self, new: GameDefs, runtime: &mut Runtime) -> DefsDiff {
+        let diff = self.diff(&new);
+
+        for id in diff.events.removed.iter().chain(&diff.events.updated) {
+            runtime.remove_event_handlers(id);
+            if let Some(def) = self.events.get(id) {
+                runtime.remove_tick_handler(&def.mtth_handler_id());
+            }
+        }
+        for id in diff.events.added.iter().chain(&diff.events.updated) {
+            if let Some(def) = new.events.get(id) {
+                runtime.on_event(def.to_handler());
+                if let Some(handler) = def.to_mtth_handler() {
+                    runtime.on_tick(handler);
+                }
+            }
+        }
+
+        if !diff.entity_types.is_empty() {
+            for id in &diff.entity_types.removed {
+                runtime.remove_template(id);
+            }
+            for id in new.entity_types.keys() {
+                runtime.register_template(id.clone(), new.entity_template(id));
+            }
+        }
+
+        if !diff.event_pools.is_empty() || !diff.events.is_empty() {
+            for pool in self.event_pools.values() {
+                runtime.remove_tick_handler(&pool.handler_id());
+            }
+            for (_, pool) in sorted(&new.event_pools) {
+                runtime.on_tick(pool.to_handler(&new.events));
+            }
+        }
+
+        for id in &diff.resources.removed {
+            runtime.remove_resource(id);
+        }
+        for id in diff.resources.added.iter().chain(&diff.resources.updated) {
+            if let Some(def) = new.resources.get(id) {
+                runtime.register_resource(def.to_resource());
+            }
+        }
+
+        for id in &diff.curves.removed {
+            runtime.remove_curve(id);
+        }
+        for id in diff.curves.added.iter().chain(&diff.curves.updated) {
+            if let Some(def) = new.curves.get(id) {
+                runtime.register_curve(id.clone(), def.to_curve());
+            }
+        }
+
+        for id in &diff.effect_templates.removed {
+            runtime.remove_effect_template(id);
+        }
+        for id in diff
+            .effect_templates
+            .added
+            .iter()
+            .chain(&diff.effect_templates.updated)
+        {
+            if let Some(def) = new.effect_templates.get(id) {
+                let _ = runtime.register_effect_template(id.clone(), def.to_template());
+            }
+        }
+
+        *self = new;
+        diff
+    }
+}
+
+impl Loader {
+    /// Load definitions from a file or a directory of content files
+    pub fn load_path(path: impl AsRef<Path>) -> Result<GameDefs> {
+        let path = path.as_ref();
+        let mut loader = Loader::new();
+        if path.is_dir() {
+            loader.load_directory(path)?;
+        } else {
+            loader.load_file(path)?;
+        }
+        Ok(loader.finish())
+    }
+}
+
+#[cfg(feature = "watch")]
+pub use watch::DefsWatcher;
+
+#[cfg(feature = "watch")]
+mod watch {
+    use super::*;
+    use crate::error::Error;
+    use crate::format::Format;
+    use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+    use std::path::PathBuf;
+    use std::sync::mpsc::{channel, Receiver};
+
+    /// Watches a content path and reloads definitions when content files
+    /// change
+    pub struct DefsWatcher {
+        path: PathBuf,
+        events: Receiver<notify::Result<notify::Event>>,
+        _watcher: RecommendedWatcher,
+    }
+
+    impl DefsWatcher {
+        /// Get the watched path
+        pub fn path(&self) -> &Path {
+            &self.path
+        }
+
+        /// Check for file changes and reload if any content file changed
+        ///
+        /// Never blocks. Returns the freshly loaded definitions, or `None`
+        /// if nothing relevant changed since the last poll.
+        pub fn poll(&mut self) -> Result<Option<GameDefs>> {
+            let mut changed = false;
+            while let Ok(event) = self.events.try_recv() {
+                let event = event.map_err(|e| Error::Watch(e.to_string()))?;
+                changed |= !event.kind.is_access()
+                    && event.paths.iter().any(|p| Format::from_path(p).is_some());
+            }
+            if !changed {
+                return Ok(None);
+            }
+            Loader::load_path(&self.path).map(Some)
+        }
+
+        /// Poll for changes and apply them to live definitions and runtime
+        ///
+        /// If the changed files fail to load, the error is returned and
+        /// the current definitions stay in place.
+        pub fn poll_reload(
+            &mut self,
+            defs: &mut GameDefs,
+            runtime: &mut Runtime,
+        ) -> Result<Option<DefsDiff>> {
+            Ok(self.poll()?.map(|new| defs.reload(new, runtime)))
+        }
+    }
+
+    impl Loader {
+        /// Watch a file or directory for changes
+        pub fn watch(path: impl AsRef<Path>) -> Result<DefsWatcher> {
+            let path = path.as_ref().to_path_buf();
+            let (sender, events) = channel();
+            let mut watcher =
+                notify::recommended_watcher(sender).map_err(|e| Error::Watch(e.to_string()))?;
+            watcher
+                .watch(&path, RecursiveMode::Recursive)
+                .map_err(|e| Error::Watch(e.to_string()))?;
+            Ok(DefsWatcher {
+                path,
+                events,
+                _watcher: watcher,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulsive_core::{Effect, EntityRef, Expr, Model, Msg};
+
+    fn load(events: &str) -> GameDefs {
+        let mut loader = Loader::new();
+        loader.load_events_str(events).unwrap();
+        loader.finish()
+    }
+
+    fn gold_event(id: &str, amount: f64) -> String {
+        format!(
+            r#"(id: "{}", name: "{}", immediate: [ModifyGlobal(property: "gold", op: Add, value: Literal(Float({:?})))])"#,
+            id, id, amount
+        )
+    }
+
+    #[test]
+    fn test_diff_and_reload() {
+        let old = load(&format!(
+            "(events: [{}, {}])",
+            gold_event("harvest", 5.0),
+            gold_event("tax", 1.0)
+        ));
+        let new = load(&format!(
+            "(events: [{}, {}])",
+            gold_event("harvest", 10.0),
+            gold_event("trade", 2.0)
+        ));
+
+        let mut runtime = Runtime::new();
+        let mut defs = GameDefs::new();
+        defs.reload(old, &mut runtime);
+        assert!(runtime.has_event_handler(&DefId::new("tax")));
+
+        let diff = defs.reload(new, &mut runtime);
+        assert_eq!(diff.events.added, vec![DefId::new("trade")]);
+        assert_eq!(diff.events.removed, vec![DefId::new("tax")]);
+        assert_eq!(diff.events.updated, vec![DefId::new("harvest")]);
+        assert!(diff.resources.is_empty());
+        assert!(!runtime.has_event_handler(&DefId::new("tax")));
+
+        // The updated handler replaced the old one instead of adding to it
+        let mut model = Model::new();
+        runtime.update(&mut model, Msg::event("harvest", EntityRef::Global, 0));
+        assert_eq!(
+            model.get_global("gold").and_then(|v| v.as_float()),
+            Some(10.0)
+        );
+        assert!(defs.diff(&defs.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_event_def_handler() {
+        let mut def = crate::EventDef::new("famine", "Famine");
+        def.trigger = Some(Expr::lit(true));
+        def.immediate.push(Effect::SetGlobal {
+            property: "hungry".to_string(),
+            value: Expr::lit(true),
+        });
+        let handler = def.to_handler();
+        assert_eq!(handler.event_id, DefId::new("famine"));
+        assert_eq!(handler.effects.len(), 1);
+    }
+}
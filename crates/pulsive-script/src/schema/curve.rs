@@ -0,0 +1,98 @@
+//! Curve definition schema
+
+use pulsive_core::{Curve, DefId, Interpolation};
+use serde::{Deserialize, Serialize};
+
+/// Definition of a value curve, sampled with `Expr::Curve`
+///
+/// ```ron
+/// (
+///     id: "tax_efficiency",
+///     interpolation: Linear,
+///     points: [(0.0, 1.0), (10.0, 0.8), (50.0, 0.4)],
+/// )
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurveDef {
+    /// Unique identifier for this curve
+    pub id: DefId,
+    /// Description
+    #[serde(default)]
+    pub description: String,
+    /// Interpolation between points (Linear, Step or Bezier)
+    #[serde(default)]
+    pub interpolation: Interpolation,
+    /// `(input, output)` key points
+    pub points: Vec<(f64, f64)>,
+}
+
+impl CurveDef {
+    /// Create a new curve definition
+    pub fn new(
+        id: impl Into<DefId>,
+        interpolation: Interpolation,
+        points: Vec<(f64, f64)>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            description: String::new(),
+            interpolation,
+            points,
+        }
+    }
+
+    /// Build the runtime curve
+    pub fn to_curve(&self) -> Curve {
+        Curve::new(self.interpolation, self.points.clone())
+    }
+}
+
+/// A collection of curve definitions
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CurveDefs {
+    pub curves: Vec<CurveDef>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Loader;
+    use pulsive_core::{DefId, EntityRef, Model, Msg, Runtime};
+
+    #[test]
+    fn test_curve_in_expression() {
+        let mut loader = Loader::new();
+        loader
+            .load_curves_str(
+                r#"(curves: [(id: "tax_efficiency", points: [(0.0, 1.0), (10.0, 0.5)])])"#,
+            )
+            .unwrap();
+        loader
+            .load_events_str(
+                r#"(events: [(
+    id: "tax",
+    name: "Tax",
+    immediate: [ModifyGlobal(property: "gold", op: Add, value: "100 * curve(tax_efficiency, param.size)")],
+)])"#,
+            )
+            .unwrap();
+        let defs = loader.finish();
+        assert_eq!(
+            defs.get_curve(&DefId::new("tax_efficiency"))
+                .unwrap()
+                .to_curve()
+                .sample(5.0),
+            0.75
+        );
+
+        let mut runtime = Runtime::new();
+        let mut model = Model::new();
+        model.set_global("gold", 0.0);
+        defs.install(&mut runtime, &mut model);
+        let msg = Msg::event("tax", EntityRef::Global, 0).with_param("size", 4.0);
+        runtime.update(&mut model, msg);
+        assert_eq!(
+            model.get_global("gold").and_then(|v| v.as_float()),
+            Some(80.0)
+        );
+    }
+}
@@ -0,0 +1,119 @@
+//! Effect template definition schema
+
+use pulsive_core::{DefId, Effect, EffectTemplate, Value};
+use serde::{Deserialize, Serialize};
+
+/// Definition of a reusable effect template, run with `Effect::Invoke`
+///
+/// ```ron
+/// (
+///     id: "gain_gold",
+///     params: ["amount"],
+///     defaults: [("reason", String("tax"))],
+///     effects: [
+///         ModifyGlobal(property: "gold", op: Add, value: "param.amount"),
+///     ],
+/// )
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectTemplateDef {
+    /// Unique identifier for this template
+    pub id: DefId,
+    /// Description
+    #[serde(default)]
+    pub description: String,
+    /// Required parameters
+    #[serde(default)]
+    pub params: Vec<String>,
+    /// Optional parameters with their default values
+    #[serde(default)]
+    pub defaults: Vec<(String, Value)>,
+    /// Effects to run, reading parameters as `param.<name>`
+    pub effects: Vec<Effect>,
+}
+
+impl EffectTemplateDef {
+    /// Create a new effect template definition
+    pub fn new(id: impl Into<DefId>, effects: Vec<Effect>) -> Self {
+        Self {
+            id: id.into(),
+            description: String::new(),
+            params: Vec::new(),
+            defaults: Vec::new(),
+            effects,
+        }
+    }
+
+    /// Build the runtime template
+    pub fn to_template(&self) -> EffectTemplate {
+        let template = self
+            .params
+            .iter()
+            .fold(EffectTemplate::new(self.effects.clone()), |t, name| {
+                t.with_param(name.clone())
+            });
+        self.defaults.iter().fold(template, |t, (name, default)| {
+            t.with_default(name.clone(), default.clone())
+        })
+    }
+}
+
+/// A collection of effect template definitions
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct EffectTemplateDefs {
+    pub effect_templates: Vec<EffectTemplateDef>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Loader;
+    use pulsive_core::{DefId, EntityRef, Model, Msg, Runtime};
+
+    #[test]
+    fn test_invoke_template() {
+        let mut loader = Loader::new();
+        loader
+            .load_effect_templates_str(
+                r#"(effect_templates: [(
+    id: "gain_gold",
+    params: ["amount"],
+    defaults: [("bonus", Float(1.0))],
+    effects: [ModifyGlobal(property: "gold", op: Add, value: "param.amount * param.bonus")],
+)])"#,
+            )
+            .unwrap();
+        loader
+            .load_events_str(
+                r#"(events: [(
+    id: "tax",
+    name: "Tax",
+    immediate: [
+        Invoke(template: "gain_gold", args: [("amount", Param("size"))]),
+        Invoke(template: "gain_gold", args: [("amount", Literal(Float(5.0))), ("bonus", Literal(Float(2.0)))]),
+    ],
+)])"#,
+            )
+            .unwrap();
+        let defs = loader.finish();
+        assert_eq!(
+            defs.get_effect_template(&DefId::new("gain_gold"))
+                .unwrap()
+                .to_template()
+                .params
+                .len(),
+            2
+        );
+        assert!(defs.validate().is_empty());
+
+        let mut runtime = Runtime::new();
+        let mut model = Model::new();
+        model.set_global("gold", 0.0);
+        defs.install(&mut runtime, &mut model);
+        let msg = Msg::event("tax", EntityRef::Global, 0).with_param("size", 4.0);
+        runtime.update(&mut model, msg);
+        assert_eq!(
+            model.get_global("gold").and_then(|v| v.as_float()),
+            Some(14.0)
+        );
+    }
+}
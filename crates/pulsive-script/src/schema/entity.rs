@@ -1,6 +1,6 @@
 //! Entity type definition schema
 
-use pulsive_core::{DefId, Value};
+use pulsive_core::{DefId, PropertySchema, Value, ValueType};
 use serde::{Deserialize, Serialize};
 
 /// Definition of an entity type (e.g., nation, province, army)
@@ -46,6 +46,9 @@ pub struct PropertyDef {
     /// Maximum value (for numeric types)
     #[serde(default)]
     pub max: Option<f64>,
+    /// Values the property may take (empty allows any)
+    #[serde(default)]
+    pub allowed: Vec<Value>,
     /// Description
     #[serde(default)]
     pub description: String,
@@ -95,6 +98,7 @@ impl PropertyDef {
             default: None,
             min: None,
             max: None,
+            allowed: Vec::new(),
             description: String::new(),
         }
     }
@@ -108,6 +112,7 @@ impl PropertyDef {
             default: None,
             min: None,
             max: None,
+            allowed: Vec::new(),
             description: String::new(),
         }
     }
@@ -121,6 +126,7 @@ impl PropertyDef {
             default: None,
             min: None,
             max: None,
+            allowed: Vec::new(),
             description: String::new(),
         }
     }
@@ -136,6 +142,31 @@ impl PropertyDef {
         self.default = Some(value.into());
         self
     }
+
+    /// Set the values the property may take
+    pub fn with_allowed<V: Into<Value>>(mut self, allowed: impl IntoIterator<Item = V>) -> Self {
+        self.allowed = allowed.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Build the runtime schema checking writes to this property
+    pub fn to_schema(&self) -> PropertySchema {
+        let value_type = match self.property_type {
+            PropertyType::Bool => ValueType::Bool,
+            PropertyType::Int => ValueType::Int,
+            PropertyType::Float => ValueType::Float,
+            PropertyType::String | PropertyType::DefRef => ValueType::String,
+            PropertyType::EntityRef => ValueType::EntityRef,
+            PropertyType::List(_) => ValueType::List,
+            PropertyType::Map => ValueType::Map,
+        };
+        PropertySchema {
+            value_type: Some(value_type),
+            min: self.min,
+            max: self.max,
+            allowed: self.allowed.clone(),
+        }
+    }
 }
 
 /// A collection of entity type definitions
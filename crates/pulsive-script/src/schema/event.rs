@@ -1,7 +1,8 @@
 //! Event definition schema
 
-use pulsive_core::{DefId, Effect, Expr};
+use pulsive_core::{DefId, Effect, EventHandler, Expr, ModifyOp, TickHandler, Value};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Definition of a game event
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -105,6 +106,81 @@ impl EventDef {
             icon: None,
         }
     }
+
+    /// Build the runtime handler that applies this event's immediate effects
+    ///
+    /// The trigger becomes the handler condition. Options need a choice and
+    /// are not part of the handler.
+    pub fn to_handler(&self) -> EventHandler {
+        EventHandler {
+            event_id: self.id.clone(),
+            condition: self.trigger.clone(),
+            effects: self.immediate.clone(),
+            priority: 0,
+        }
+    }
+
+    /// ID of the tick handler built by [`to_mtth_handler`](Self::to_mtth_handler)
+    pub fn mtth_handler_id(&self) -> DefId {
+        DefId::new(format!("{}.mtth", self.id))
+    }
+
+    /// Build the tick handler that fires this event at random, if it has a
+    /// mean time to happen
+    ///
+    /// Every tick, each eligible target fires with probability
+    /// `1 / mtth`, where `mtth` is the base ticks multiplied by the factor
+    /// of every modifier whose condition holds. Firing applies the
+    /// immediate effects. Events with `fire_only_once` mark their target
+    /// with a `fired:<id>` flag (or a global of that name when untargeted)
+    /// and are skipped once marked.
+    pub fn to_mtth_handler(&self) -> Option<TickHandler> {
+        let mtth = self.mtth.as_ref()?;
+
+        let mut ticks = Expr::lit(mtth.ticks.max(1) as f64);
+        for modifier in &mtth.modifiers {
+            let factor = Expr::If(
+                Box::new(modifier.condition.clone()),
+                Box::new(Expr::lit(modifier.factor)),
+                Box::new(Expr::lit(1.0)),
+            );
+            ticks = Expr::Mul(Box::new(ticks), Box::new(factor));
+        }
+        let roll = Expr::Lt(
+            Box::new(Expr::Random),
+            Box::new(Expr::Div(Box::new(Expr::lit(1.0)), Box::new(ticks))),
+        );
+
+        let mut conditions: Vec<Expr> = self.trigger.iter().cloned().collect();
+        let mut effects = self.immediate.clone();
+        if self.fire_only_once {
+            let marker = format!("fired:{}", self.id);
+            if self.target_kind.is_some() {
+                conditions.push(Expr::Not(Box::new(Expr::HasFlag(DefId::new(
+                    marker.as_str(),
+                )))));
+                effects.push(Effect::AddFlag(DefId::new(marker)));
+            } else {
+                conditions.push(Expr::Ne(
+                    Box::new(Expr::global(marker.as_str())),
+                    Box::new(Expr::lit(true)),
+                ));
+                effects.push(Effect::SetGlobal {
+                    property: marker,
+                    value: Expr::Literal(Value::Bool(true)),
+                });
+            }
+        }
+        conditions.push(roll);
+
+        Some(TickHandler {
+            id: self.mtth_handler_id(),
+            condition: Some(Expr::And(conditions)),
+            target_kind: self.target_kind.clone(),
+            effects,
+            priority: 0,
+        })
+    }
 }
 
 impl EventOption {
@@ -126,6 +202,210 @@ pub struct EventDefs {
     pub events: Vec<EventDef>,
 }
 
+/// A weighted pool of events, at most one of which fires per target each
+/// tick
+///
+/// ```ron
+/// (
+///     id: "province_events",
+///     target_kind: Some("province"),
+///     chance: 0.05,
+///     events: [
+///         (event: "harvest_festival", weight: 3.0, cooldown: 50),
+///         (event: "peasant_revolt", condition: Some("unrest > 5"), max_fires: Some(1)),
+///     ],
+/// )
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventPoolDef {
+    /// Unique identifier for this pool
+    pub id: DefId,
+    /// Description
+    #[serde(default)]
+    pub description: String,
+    /// Target entity kind; untargeted pools roll once per tick
+    #[serde(default)]
+    pub target_kind: Option<DefId>,
+    /// Condition for the pool to roll at all
+    #[serde(default)]
+    pub trigger: Option<Expr>,
+    /// Chance per tick (and target) that an event is picked
+    #[serde(default = "default_chance")]
+    pub chance: f64,
+    /// Events in the pool
+    #[serde(default)]
+    pub events: Vec<PoolEntry>,
+}
+
+fn default_chance() -> f64 {
+    1.0
+}
+
+/// An event in an [`EventPoolDef`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolEntry {
+    /// Event to fire
+    pub event: DefId,
+    /// Weight for random selection among eligible events
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    /// Eligibility condition, evaluated against the target
+    #[serde(default)]
+    pub condition: Option<Expr>,
+    /// Ticks after firing before the event is eligible again (per target)
+    #[serde(default)]
+    pub cooldown: u64,
+    /// Maximum number of times the event fires (per target)
+    #[serde(default)]
+    pub max_fires: Option<u32>,
+}
+
+impl PoolEntry {
+    /// Create a pool entry with weight 1
+    pub fn new(event: impl Into<DefId>) -> Self {
+        Self {
+            event: event.into(),
+            weight: 1.0,
+            condition: None,
+            cooldown: 0,
+            max_fires: None,
+        }
+    }
+}
+
+impl EventPoolDef {
+    /// Create an empty, untargeted pool that rolls every tick
+    pub fn new(id: impl Into<DefId>) -> Self {
+        Self {
+            id: id.into(),
+            description: String::new(),
+            target_kind: None,
+            trigger: None,
+            chance: 1.0,
+            events: Vec::new(),
+        }
+    }
+
+    /// ID of the tick handler built by [`to_handler`](Self::to_handler)
+    pub fn handler_id(&self) -> DefId {
+        DefId::new(format!("{}.pool", self.id))
+    }
+
+    /// Build the tick handler that fires events from this pool
+    ///
+    /// Every tick, for each target, the pool rolls against `chance` and
+    /// picks one eligible event by weight, applying its immediate effects.
+    /// An event is eligible when its trigger and the entry condition hold,
+    /// it is off cooldown and below its fire limit. Cooldowns and fire
+    /// counts are kept in `pool:<pool>:<event>:cooldown` and `...:fires`
+    /// properties on the target (globals when untargeted). Entries for
+    /// events missing from `events` are skipped.
+    pub fn to_handler(&self, events: &HashMap<DefId, EventDef>) -> TickHandler {
+        let targeted = self.target_kind.is_some();
+        let modify = |property: String, op: ModifyOp, value: f64| {
+            let value = Expr::lit(value);
+            if targeted {
+                Effect::ModifyProperty {
+                    property,
+                    op,
+                    value,
+                }
+            } else {
+                Effect::ModifyGlobal {
+                    property,
+                    op,
+                    value,
+                }
+            }
+        };
+        let read = |property: String| {
+            if targeted {
+                Expr::prop(property)
+            } else {
+                Expr::global(property)
+            }
+        };
+
+        let mut effects = Vec::new();
+        let mut choices = Vec::new();
+        for entry in &self.events {
+            let Some(event) = events.get(&entry.event) else {
+                continue;
+            };
+            let key = |field: &str| format!("pool:{}:{}:{}", self.id, entry.event, field);
+            let mut conditions: Vec<Expr> = event
+                .trigger
+                .iter()
+                .chain(&entry.condition)
+                .cloned()
+                .collect();
+            let mut fired = event.immediate.clone();
+
+            if entry.cooldown > 0 {
+                // Count down every tick, never below zero (missing is zero)
+                effects.push(modify(key("cooldown"), ModifyOp::Sub, 1.0));
+                effects.push(modify(key("cooldown"), ModifyOp::Max, 0.0));
+                conditions.push(Expr::Le(
+                    Box::new(read(key("cooldown"))),
+                    Box::new(Expr::lit(0.0)),
+                ));
+                fired.push(modify(
+                    key("cooldown"),
+                    ModifyOp::Set,
+                    entry.cooldown as f64,
+                ));
+            }
+            if let Some(max_fires) = entry.max_fires {
+                // Adding zero initializes the count so it can be compared
+                effects.push(modify(key("fires"), ModifyOp::Add, 0.0));
+                conditions.push(Expr::Lt(
+                    Box::new(read(key("fires"))),
+                    Box::new(Expr::lit(max_fires as f64)),
+                ));
+                fired.push(modify(key("fires"), ModifyOp::Add, 1.0));
+            }
+
+            let weight = if conditions.is_empty() {
+                Expr::lit(entry.weight)
+            } else {
+                Expr::If(
+                    Box::new(Expr::And(conditions)),
+                    Box::new(Expr::lit(entry.weight)),
+                    Box::new(Expr::lit(0.0)),
+                )
+            };
+            choices.push((weight, fired));
+        }
+
+        let mut conditions: Vec<Expr> = self.trigger.iter().cloned().collect();
+        if self.chance < 1.0 {
+            conditions.push(Expr::Lt(
+                Box::new(Expr::Random),
+                Box::new(Expr::lit(self.chance)),
+            ));
+        }
+        effects.push(Effect::If {
+            condition: Expr::And(conditions),
+            then_effects: vec![Effect::RandomChoice { choices }],
+            else_effects: Vec::new(),
+        });
+
+        TickHandler {
+            id: self.handler_id(),
+            condition: None,
+            target_kind: self.target_kind.clone(),
+            effects,
+            priority: 0,
+        }
+    }
+}
+
+/// A collection of event pool definitions
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct EventPoolDefs {
+    pub event_pools: Vec<EventPoolDef>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -136,4 +416,57 @@ mod tests {
         assert_eq!(event.id.as_str(), "peasant_uprising");
         assert_eq!(event.weight, 1.0);
     }
+
+    #[test]
+    fn test_event_pool() {
+        let mut loader = crate::Loader::new();
+        loader
+            .load_events_str(
+                r#"(events: [
+    (id: "festival", name: "Festival", immediate: [ModifyProperty(property: "festivals", op: Add, value: "1")]),
+    (id: "revolt", name: "Revolt", immediate: [ModifyProperty(property: "revolts", op: Add, value: "1")]),
+])"#,
+            )
+            .unwrap();
+        loader
+            .load_event_pools_str(
+                r#"(event_pools: [(
+    id: "province_events",
+    target_kind: Some("province"),
+    events: [
+        (event: "festival", weight: 3.0, cooldown: 3),
+        (event: "revolt", condition: Some("unrest > 5"), max_fires: Some(1)),
+    ],
+)])"#,
+            )
+            .unwrap();
+        let defs = loader.finish();
+
+        let mut runtime = pulsive_core::Runtime::new();
+        let mut model = pulsive_core::Model::new();
+        defs.install(&mut runtime, &mut model);
+        let calm = model.entities_mut().create("province").id;
+        let restless = {
+            let province = model.entities_mut().create("province");
+            province.set("unrest", 10.0);
+            province.id
+        };
+        for _ in 0..10 {
+            runtime.tick(&mut model);
+        }
+
+        let count = |id, property: &str| {
+            model
+                .entities()
+                .get(id)
+                .and_then(|e| e.get_number(property))
+                .unwrap_or(0.0)
+        };
+        // Only the festival is eligible, every third tick
+        assert_eq!(count(calm, "festivals"), 4.0);
+        assert_eq!(count(calm, "revolts"), 0.0);
+        // The revolt fires once, at the latest while the festival cools down
+        assert_eq!(count(restless, "revolts"), 1.0);
+        assert!(count(restless, "festivals") <= 4.0);
+    }
 }
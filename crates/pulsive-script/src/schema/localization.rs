@@ -0,0 +1,217 @@
+//! Localization table schema
+
+use pulsive_core::{Value, ValueMap};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Localized strings for one language
+///
+/// Strings may reference parameters as `{name}`:
+///
+/// ```ron
+/// (
+///     language: "en",
+///     strings: {
+///         "event.harvest.title": "A Bountiful Harvest",
+///         "event.harvest.message": "The granaries gain {amount} grain.",
+///     },
+/// )
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalizationDef {
+    /// Language code (e.g. "en", "de")
+    pub language: String,
+    /// Strings by key
+    #[serde(default)]
+    pub strings: HashMap<String, String>,
+}
+
+/// Localized strings for every loaded language
+#[derive(Debug, Clone, Default)]
+pub struct Localization {
+    /// Strings by language, then key
+    tables: HashMap<String, HashMap<String, String>>,
+    /// Language used when a key is missing in the requested one
+    fallback: Option<String>,
+}
+
+impl Localization {
+    /// Create an empty localization
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add strings for a language, replacing existing keys
+    pub fn add(&mut self, def: LocalizationDef) {
+        self.tables
+            .entry(def.language)
+            .or_default()
+            .extend(def.strings);
+    }
+
+    /// Merge another localization in, its strings taking precedence
+    pub fn merge(&mut self, other: Localization) {
+        for (language, strings) in other.tables {
+            self.tables.entry(language).or_default().extend(strings);
+        }
+        if other.fallback.is_some() {
+            self.fallback = other.fallback;
+        }
+    }
+
+    /// Set the language used for keys missing in the requested language
+    pub fn set_fallback(&mut self, language: impl Into<String>) {
+        self.fallback = Some(language.into());
+    }
+
+    /// Get the fallback language
+    pub fn fallback(&self) -> Option<&str> {
+        self.fallback.as_deref()
+    }
+
+    /// Check if a language has a string for a key
+    pub fn contains(&self, language: &str, key: &str) -> bool {
+        self.tables
+            .get(language)
+            .is_some_and(|strings| strings.contains_key(key))
+    }
+
+    /// Get the loaded languages, sorted
+    pub fn languages(&self) -> Vec<&str> {
+        let mut languages: Vec<&str> = self.tables.keys().map(String::as_str).collect();
+        languages.sort();
+        languages
+    }
+
+    /// Get the strings of every language, sorted by language
+    pub fn to_defs(&self) -> Vec<LocalizationDef> {
+        let mut defs: Vec<LocalizationDef> = self
+            .tables
+            .iter()
+            .map(|(language, strings)| LocalizationDef {
+                language: language.clone(),
+                strings: strings.clone(),
+            })
+            .collect();
+        defs.sort_by(|a, b| a.language.cmp(&b.language));
+        defs
+    }
+
+    /// Get every key of any language, sorted
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = self
+            .tables
+            .values()
+            .flat_map(|strings| strings.keys().map(String::as_str))
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    /// Check if no strings are loaded
+    pub fn is_empty(&self) -> bool {
+        self.tables.values().all(HashMap::is_empty)
+    }
+
+    /// Get the string for a key, trying the fallback language second
+    pub fn get(&self, key: &str, language: &str) -> Option<&str> {
+        let lookup = |language: &str| self.tables.get(language)?.get(key);
+        lookup(language)
+            .or_else(|| lookup(self.fallback.as_deref()?))
+            .map(String::as_str)
+    }
+
+    /// Render a key with parameters substituted for `{name}` placeholders
+    ///
+    /// Missing keys render as the key itself.
+    pub fn localize(&self, key: &str, language: &str, params: &ValueMap) -> String {
+        let mut text = self.get(key, language).unwrap_or(key).to_string();
+        for (name, value) in params {
+            let placeholder = format!("{{{}}}", name);
+            if text.contains(&placeholder) {
+                text = text.replace(&placeholder, &self.render(value, language));
+            }
+        }
+        text
+    }
+
+    /// Render a value as text in a language
+    ///
+    /// [`Value::localized`] text is looked up, strings are used as is and
+    /// other values are formatted.
+    pub fn render(&self, value: &Value, language: &str) -> String {
+        match value {
+            Value::String(s) => s.clone(),
+            Value::Null => String::new(),
+            value => match value.as_localized() {
+                Some((key, params)) => self.localize(key, language, params),
+                None => value.to_string(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Loader;
+    use pulsive_core::{
+        DefId, Effect, EntityRef, EventHandler, Expr, Model, Msg, Runtime, Value, ValueMap,
+    };
+
+    #[test]
+    fn test_localize() {
+        let mut loader = Loader::new();
+        loader
+            .load_localization_str(
+                r#"(language: "en", strings: {"harvest.title": "Harvest", "harvest.message": "Gained {amount} grain"})"#,
+            )
+            .unwrap();
+        loader
+            .load_localization_str(r#"(language: "de", strings: {"harvest.title": "Ernte"})"#)
+            .unwrap();
+        assert!(loader
+            .load_localization_str(r#"(language: "de", strings: {"harvest.title": "Ernte!"})"#)
+            .is_err());
+        let mut defs = loader.finish();
+        defs.localization.set_fallback("en");
+
+        let mut params = ValueMap::new();
+        params.insert("amount".to_string(), Value::Int(5));
+        assert_eq!(defs.localize("harvest.title", "de", &params), "Ernte");
+        assert_eq!(
+            defs.localize("harvest.message", "de", &params),
+            "Gained 5 grain"
+        );
+        assert_eq!(defs.localize("missing", "de", &params), "missing");
+
+        // Notifications carry localized text for the UI to render
+        let mut runtime = Runtime::new();
+        runtime.on_event(EventHandler {
+            event_id: DefId::new("harvest"),
+            condition: None,
+            effects: vec![Effect::Notify {
+                kind: DefId::new("info"),
+                title: Expr::localized("harvest.title"),
+                message: Expr::Localized(
+                    "harvest.message".to_string(),
+                    vec![("amount".to_string(), Expr::param("amount"))],
+                ),
+                target: EntityRef::Global,
+            }],
+            priority: 0,
+        });
+        let msg = Msg::event("harvest", EntityRef::Global, 0).with_param("amount", 3i64);
+        let result = runtime.update(&mut Model::new(), msg);
+        let notification = &result.effect_result.notifications[0];
+        assert_eq!(notification.title, "harvest.title");
+        assert_eq!(
+            defs.localize_value(&notification.title_value, "de"),
+            "Ernte"
+        );
+        assert_eq!(
+            defs.localize_value(&notification.message_value, "en"),
+            "Gained 3 grain"
+        );
+    }
+}
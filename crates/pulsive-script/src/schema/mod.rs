@@ -1,9 +1,15 @@
 //! Schema definitions for RON scripts
 
+pub mod curve;
+pub mod effect_template;
 pub mod entity;
 pub mod event;
+pub mod localization;
 pub mod resource;
 
+pub use curve::CurveDef;
+pub use effect_template::EffectTemplateDef;
 pub use entity::EntityTypeDef;
 pub use event::EventDef;
+pub use localization::{Localization, LocalizationDef};
 pub use resource::ResourceDef;
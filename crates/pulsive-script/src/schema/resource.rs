@@ -1,6 +1,6 @@
 //! Resource definition schema
 
-use pulsive_core::DefId;
+use pulsive_core::{DefId, Expr, OverflowPolicy, Resource};
 use serde::{Deserialize, Serialize};
 
 /// Definition of a resource type (e.g., gold, manpower, grain)
@@ -34,6 +34,21 @@ pub struct ResourceDef {
     /// Color for UI (hex string)
     #[serde(default)]
     pub color: Option<String>,
+    /// Entity kind holding it (None for a global)
+    #[serde(default)]
+    pub holder: Option<DefId>,
+    /// Sources of income per tick, summed per holder
+    #[serde(default)]
+    pub income: Vec<Expr>,
+    /// Sources of upkeep per tick, summed per holder
+    #[serde(default)]
+    pub upkeep: Vec<Expr>,
+    /// What happens past the maximum
+    #[serde(default)]
+    pub on_overflow: OverflowPolicy,
+    /// What happens below the minimum
+    #[serde(default)]
+    pub on_underflow: OverflowPolicy,
 }
 
 fn default_base_value() -> f64 {
@@ -54,8 +69,39 @@ impl ResourceDef {
             max_value: None,
             icon: None,
             color: None,
+            holder: None,
+            income: Vec::new(),
+            upkeep: Vec::new(),
+            on_overflow: OverflowPolicy::Clamp,
+            on_underflow: OverflowPolicy::Clamp,
         }
     }
+
+    /// Convert to a runtime resource
+    ///
+    /// The decay rate becomes an upkeep source proportional to the holding.
+    /// Without a minimum value the holding has no floor.
+    pub fn to_resource(&self) -> Resource {
+        let mut resource = Resource::new(self.id.clone())
+            .with_floor(self.min_value.unwrap_or(f64::NEG_INFINITY))
+            .with_overflow(self.on_overflow)
+            .with_underflow(self.on_underflow);
+        resource.holder = self.holder.clone();
+        resource.capacity = self.max_value.map(Expr::lit);
+        resource.income = self.income.clone();
+        resource.upkeep = self.upkeep.clone();
+        if self.decay_rate != 0.0 {
+            let holding = match self.holder {
+                Some(_) => Expr::prop(self.id.as_str()),
+                None => Expr::global(self.id.as_str()),
+            };
+            resource.upkeep.push(Expr::Mul(
+                Box::new(holding),
+                Box::new(Expr::lit(self.decay_rate)),
+            ));
+        }
+        resource
+    }
 }
 
 /// A collection of resource definitions
@@ -87,4 +133,28 @@ mod tests {
         assert_eq!(def.name, "Gold");
         assert!(def.tradeable);
     }
+
+    #[test]
+    fn test_to_resource() {
+        let ron_str = r#"
+        (
+            id: "grain",
+            name: "Grain",
+            decay_rate: 0.1,
+            max_value: Some(500.0),
+            holder: Some("city"),
+            income: [Mul(Property("farms"), Literal(Float(2.0)))],
+            on_overflow: Spill,
+        )
+        "#;
+
+        let def: ResourceDef = ron::from_str(ron_str).unwrap();
+        let resource = def.to_resource();
+        assert_eq!(resource.holder, Some(DefId::new("city")));
+        assert_eq!(resource.floor, f64::NEG_INFINITY);
+        assert_eq!(resource.on_overflow, OverflowPolicy::Spill);
+        assert_eq!(resource.income.len(), 1);
+        // The decay joins the upkeep
+        assert_eq!(resource.upkeep.len(), 1);
+    }
 }
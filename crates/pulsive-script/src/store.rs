@@ -0,0 +1,174 @@
+//! Import and export of definitions to a database
+//!
+//! Servers can keep definitions in a [`pulsive_db::Store`] as their source of
+//! truth while content is still authored in files:
+//!
+//! ```rust,ignore
+//! use pulsive_script::DefinitionStore;
+//!
+//! let mut loader = Loader::new();
+//! loader.load_directory("content")?;
+//! store.import_defs(loader.defs())?;
+//! let defs = store.export_defs()?;
+//! ```
+//!
+//! Each definition is stored as RON text, so exported definitions match the
+//! imported ones exactly and can be written back to RON files.
+
+use crate::error::{Error, Result};
+use crate::loader::GameDefs;
+use crate::schema::localization::LocalizationDef;
+use pulsive_core::DefId;
+use pulsive_db::{DefinitionRecord, Store};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+
+const RESOURCE: &str = "resource";
+const EVENT: &str = "event";
+const ENTITY_TYPE: &str = "entity_type";
+const CURVE: &str = "curve";
+const EVENT_POOL: &str = "event_pool";
+const EFFECT_TEMPLATE: &str = "effect_template";
+const LOCALIZATION: &str = "localization";
+const LOCALIZATION_FALLBACK: &str = "localization_fallback";
+
+/// Storage of game definitions
+pub trait DefinitionStore {
+    /// Replace the stored definitions with a set of definitions
+    fn import_defs(&self, defs: &GameDefs) -> Result<()>;
+
+    /// Load the stored definitions
+    ///
+    /// Exported definitions have no source locations.
+    fn export_defs(&self) -> Result<GameDefs>;
+}
+
+impl DefinitionStore for Store {
+    fn import_defs(&self, defs: &GameDefs) -> Result<()> {
+        let mut records = Vec::new();
+        add_records(&mut records, RESOURCE, &defs.resources)?;
+        add_records(&mut records, EVENT, &defs.events)?;
+        add_records(&mut records, ENTITY_TYPE, &defs.entity_types)?;
+        add_records(&mut records, CURVE, &defs.curves)?;
+        add_records(&mut records, EVENT_POOL, &defs.event_pools)?;
+        add_records(&mut records, EFFECT_TEMPLATE, &defs.effect_templates)?;
+        for def in defs.localization.to_defs() {
+            records.push(DefinitionRecord::new(
+                LOCALIZATION,
+                def.language.clone(),
+                to_ron(&def)?,
+            ));
+        }
+        if let Some(fallback) = defs.localization.fallback() {
+            records.push(DefinitionRecord::new(LOCALIZATION_FALLBACK, fallback, ""));
+        }
+        Ok(self.replace_definitions(&records)?)
+    }
+
+    fn export_defs(&self) -> Result<GameDefs> {
+        let mut defs = GameDefs::new();
+        for record in self.definitions()? {
+            let id = DefId::new(record.id.clone());
+            match record.kind.as_str() {
+                RESOURCE => {
+                    defs.resources.insert(id, from_ron(&record)?);
+                }
+                EVENT => {
+                    defs.events.insert(id, from_ron(&record)?);
+                }
+                ENTITY_TYPE => {
+                    defs.entity_types.insert(id, from_ron(&record)?);
+                }
+                CURVE => {
+                    defs.curves.insert(id, from_ron(&record)?);
+                }
+                EVENT_POOL => {
+                    defs.event_pools.insert(id, from_ron(&record)?);
+                }
+                EFFECT_TEMPLATE => {
+                    defs.effect_templates.insert(id, from_ron(&record)?);
+                }
+                LOCALIZATION => defs.localization.add(from_ron::<LocalizationDef>(&record)?),
+                LOCALIZATION_FALLBACK => defs.localization.set_fallback(record.id),
+                kind => {
+                    return Err(Error::InvalidSchema(format!(
+                        "Unknown stored definition kind: {}",
+                        kind
+                    )))
+                }
+            }
+        }
+        Ok(defs)
+    }
+}
+
+/// Serialize definitions by ID into records of a kind
+fn add_records<T: Serialize>(
+    records: &mut Vec<DefinitionRecord>,
+    kind: &str,
+    defs: &HashMap<DefId, T>,
+) -> Result<()> {
+    for (id, def) in defs {
+        records.push(DefinitionRecord::new(kind, id.as_str(), to_ron(def)?));
+    }
+    Ok(())
+}
+
+fn to_ron<T: Serialize>(def: &T) -> Result<String> {
+    ron::ser::to_string_pretty(def, ron::ser::PrettyConfig::default())
+        .map_err(|e| Error::InvalidSchema(e.to_string()))
+}
+
+fn from_ron<T: DeserializeOwned>(record: &DefinitionRecord) -> Result<T> {
+    ron::from_str(&record.data)
+        .map_err(|e| Error::InvalidSchema(format!("Stored {} {}: {}", record.kind, record.id, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::Loader;
+
+    #[test]
+    fn test_import_export_defs() {
+        let mut loader = Loader::new();
+        loader
+            .load_resources_str(r#"(resources: [(id: "gold", name: "Gold", base_value: 1.0)])"#)
+            .unwrap();
+        loader
+            .load_events_str(
+                r#"(events: [(
+                    id: "windfall",
+                    name: "Windfall",
+                    trigger: Some("global.gold >= 100"),
+                    immediate: [ModifyGlobal(property: "gold", op: Add, value: "10")],
+                )])"#,
+            )
+            .unwrap();
+        loader
+            .load_localization_str(r#"(language: "en", strings: {"windfall": "Windfall!"})"#)
+            .unwrap();
+        let mut defs = loader.finish();
+        defs.localization.set_fallback("en");
+
+        let store = Store::in_memory().unwrap();
+        store.import_defs(&defs).unwrap();
+        let exported = store.export_defs().unwrap();
+
+        assert_eq!(exported.resources.len(), 1);
+        assert_eq!(exported.get_resource(&"gold".into()).unwrap().name, "Gold");
+        let event = exported.get_event(&"windfall".into()).unwrap();
+        assert!(event.trigger.is_some());
+        assert_eq!(event.immediate.len(), 1);
+        assert_eq!(
+            exported.localization.get("windfall", "en"),
+            Some("Windfall!")
+        );
+        assert_eq!(exported.localization.fallback(), Some("en"));
+
+        // Importing replaces the stored definitions
+        store.import_defs(&GameDefs::new()).unwrap();
+        assert!(store.export_defs().unwrap().resources.is_empty());
+    }
+}
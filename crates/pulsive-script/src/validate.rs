@@ -0,0 +1,775 @@
+//! Validation of game definitions with structured diagnostics
+//!
+//! RON parse errors and semantic problems in loaded definitions are
+//! reported as [`Diagnostic`]s carrying the file, line and column, the path
+//! to the offending field and, where possible, a suggested fix:
+//!
+//! ```text
+//! content/events.ron:12:5: error: unknown effect `ModifyGlobl`
+//!   at Effect
+//!   help: did you mean `ModifyGlobal`?
+//! ```
+//!
+//! [`GameDefs::validate`] checks references to undefined events, resources
+//! and entity types (including from event pools), default values that do not match their property type,
+//! cyclic chains of emitted events, and undefined or cyclic effect templates.
+
+use crate::error::Error;
+use crate::format::Format;
+use crate::loader::{read_dir_sorted, sorted, GameDefs, Loader};
+use crate::schema::entity::PropertyType;
+use pulsive_core::{DefId, Effect, EffectTemplates, Error as CoreError, Value};
+use ron::error::SpannedError;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Definitions will misbehave or fail to load
+    Error,
+    /// Likely a mistake, but the definitions still work
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// Position of a definition or error in a source file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceLocation {
+    /// File path (`None` for definitions loaded from strings)
+    pub file: Option<PathBuf>,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column number
+    pub column: usize,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}:{}:{}", file.display(), self.line, self.column),
+            None => write!(f, "<string>:{}:{}", self.line, self.column),
+        }
+    }
+}
+
+/// Source locations of loaded definitions by ID
+#[derive(Debug, Clone, Default)]
+pub struct DefSources {
+    /// Resource locations
+    pub resources: HashMap<DefId, SourceLocation>,
+    /// Event locations
+    pub events: HashMap<DefId, SourceLocation>,
+    /// Entity type locations
+    pub entity_types: HashMap<DefId, SourceLocation>,
+    /// Curve locations
+    pub curves: HashMap<DefId, SourceLocation>,
+    /// Event pool locations
+    pub event_pools: HashMap<DefId, SourceLocation>,
+    /// Effect template locations
+    pub effect_templates: HashMap<DefId, SourceLocation>,
+}
+
+/// A problem found while loading or validating definitions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// How serious the problem is
+    pub severity: Severity,
+    /// Where the problem is, if known
+    pub location: Option<SourceLocation>,
+    /// Path to the offending field (e.g. `events.famine.immediate[0]`)
+    pub path: String,
+    /// Description of the problem
+    pub message: String,
+    /// Suggested fix
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    /// Create an error diagnostic
+    pub fn error(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            location: None,
+            path: path.into(),
+            message: message.into(),
+            suggestion: None,
+        }
+    }
+
+    /// Create a warning diagnostic
+    pub fn warning(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(path, message)
+        }
+    }
+
+    /// Set the source location
+    pub fn at(mut self, location: Option<SourceLocation>) -> Self {
+        self.location = location;
+        self
+    }
+
+    /// Set a suggested fix
+    pub fn with_suggestion(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
+
+    /// Check if this is an error
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Convert a loader error into a diagnostic
+    pub fn from_error(error: &Error, file: Option<&Path>) -> Self {
+        let location = |line, column| SourceLocation {
+            file: file.map(Path::to_path_buf),
+            line,
+            column,
+        };
+        match error {
+            Error::Ron(spanned) => {
+                let start = spanned.span.start;
+                Self::from_ron(spanned).at(Some(location(start.line, start.col)))
+            }
+            Error::Expression {
+                line,
+                column,
+                message,
+            } => Self::error("", format!("invalid expression: {}", message))
+                .at(Some(location(*line, *column))),
+            #[cfg(feature = "json")]
+            Error::Json(e) if e.line() > 0 => {
+                Self::error("", e.to_string()).at(Some(location(e.line(), e.column())))
+            }
+            #[cfg(feature = "yaml")]
+            Error::Yaml(e) => {
+                let (line, column) = e.location().map_or((1, 1), |l| (l.line(), l.column()));
+                Self::error("", e.to_string()).at(Some(location(line, column)))
+            }
+            other => Self::error("", other.to_string()).at(file.map(|_| location(1, 1))),
+        }
+    }
+
+    fn from_ron(error: &SpannedError) -> Self {
+        use ron::Error as Ron;
+
+        match &error.code {
+            Ron::NoSuchEnumVariant {
+                expected,
+                found,
+                outer,
+            } => {
+                let kind = match outer.as_deref() {
+                    Some("Effect") => "effect",
+                    Some("Expr") => "expression",
+                    _ => "variant",
+                };
+                let diagnostic = Self::error(
+                    outer.clone().unwrap_or_default(),
+                    format!("unknown {} `{}`", kind, found),
+                );
+                match closest(found, expected.iter().copied()) {
+                    Some(name) => diagnostic.with_suggestion(format!("did you mean `{}`?", name)),
+                    None => diagnostic,
+                }
+            }
+            Ron::NoSuchStructField {
+                expected,
+                found,
+                outer,
+            } => {
+                let diagnostic = Self::error(
+                    outer.clone().unwrap_or_default(),
+                    format!("unknown field `{}`", found),
+                );
+                match closest(found, expected.iter().copied()) {
+                    Some(name) => diagnostic.with_suggestion(format!("did you mean `{}`?", name)),
+                    None => diagnostic
+                        .with_suggestion(format!("expected one of: {}", expected.join(", "))),
+                }
+            }
+            Ron::MissingStructField { field, outer } => Self::error(
+                outer.clone().unwrap_or_default(),
+                format!("missing field `{}`", field),
+            )
+            .with_suggestion(format!("add `{}: ...`", field)),
+            Ron::InvalidValueForType { expected, found } => Self::error(
+                "",
+                format!("type mismatch: expected {}, found {}", expected, found),
+            ),
+            other => Self::error("", other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(location) = &self.location {
+            write!(f, "{}: ", location)?;
+        }
+        write!(f, "{}: {}", self.severity, self.message)?;
+        if !self.path.is_empty() {
+            write!(f, "\n  at {}", self.path)?;
+        }
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\n  help: {}", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Find the candidate closest to `name`, if any is close enough to be a typo
+fn closest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(2);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// Levenshtein distance between two strings (case-insensitive)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Check whether a value fits a property type (null fits anything)
+fn value_matches(value: &Value, property_type: &PropertyType) -> bool {
+    match (value, property_type) {
+        (Value::Null, _) => true,
+        (Value::Bool(_), PropertyType::Bool) => true,
+        (Value::Int(_), PropertyType::Int | PropertyType::Float) => true,
+        (Value::Float(_), PropertyType::Float) => true,
+        (Value::String(_), PropertyType::String | PropertyType::DefRef) => true,
+        (Value::EntityRef(_), PropertyType::EntityRef) => true,
+        (Value::List(items), PropertyType::List(item_type)) => {
+            items.iter().all(|item| value_matches(item, item_type))
+        }
+        (Value::Map(_), PropertyType::Map) => true,
+        _ => false,
+    }
+}
+
+/// Walks definitions and collects diagnostics
+struct Validator<'a> {
+    defs: &'a GameDefs,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Validator<'a> {
+    fn report(&mut self, diagnostic: Diagnostic, location: Option<&SourceLocation>) {
+        self.diagnostics.push(diagnostic.at(location.cloned()));
+    }
+
+    /// Report a reference to an undefined definition, suggesting the
+    /// closest defined ID
+    fn undefined<'i>(
+        &mut self,
+        kind: &str,
+        id: &DefId,
+        defined: impl IntoIterator<Item = &'i DefId>,
+        path: &str,
+        location: Option<&SourceLocation>,
+    ) {
+        let mut diagnostic = Diagnostic::error(path, format!("undefined {} `{}`", kind, id));
+        if let Some(name) = closest(id.as_str(), defined.into_iter().map(DefId::as_str)) {
+            diagnostic = diagnostic.with_suggestion(format!("did you mean `{}`?", name));
+        }
+        self.report(diagnostic, location);
+    }
+
+    fn check_entity_kind(&mut self, kind: &DefId, path: &str, location: Option<&SourceLocation>) {
+        if !self.defs.entity_types.contains_key(kind) {
+            let defined = self.defs.entity_types.keys();
+            self.undefined("entity type", kind, defined, path, location);
+        }
+    }
+
+    fn check_effects(&mut self, effects: &[Effect], path: &str, location: Option<&SourceLocation>) {
+        for (i, effect) in effects.iter().enumerate() {
+            self.check_effect(effect, &format!("{}[{}]", path, i), location);
+        }
+    }
+
+    fn check_effect(&mut self, effect: &Effect, path: &str, location: Option<&SourceLocation>) {
+        let defs = self.defs;
+        match effect {
+            Effect::SetGlobal { property, .. } | Effect::ModifyGlobal { property, .. } => {
+                // Globals are free-form unless the content declares resources
+                let id = DefId::new(property.as_str());
+                if !defs.resources.is_empty() && !defs.resources.contains_key(&id) {
+                    let mut diagnostic = Diagnostic::warning(
+                        format!("{}.property", path),
+                        format!("global `{}` is not a defined resource", property),
+                    );
+                    let defined = defs.resources.keys().map(DefId::as_str);
+                    if let Some(name) = closest(property, defined) {
+                        diagnostic =
+                            diagnostic.with_suggestion(format!("did you mean `{}`?", name));
+                    }
+                    self.report(diagnostic, location);
+                }
+            }
+            Effect::SpawnEntity { kind, .. } => {
+                self.check_entity_kind(kind, &format!("{}.kind", path), location);
+            }
+            Effect::EmitEvent { event, .. } | Effect::ScheduleEvent { event, .. }
+                if !defs.events.contains_key(event) =>
+            {
+                let path = format!("{}.event", path);
+                self.undefined("event", event, defs.events.keys(), &path, location);
+            }
+            Effect::If {
+                then_effects,
+                else_effects,
+                ..
+            } => {
+                self.check_effects(then_effects, &format!("{}.then_effects", path), location);
+                self.check_effects(else_effects, &format!("{}.else_effects", path), location);
+            }
+            Effect::Sequence(effects) => self.check_effects(effects, path, location),
+            Effect::ForEachEntity { kind, effects, .. } => {
+                self.check_entity_kind(kind, &format!("{}.kind", path), location);
+                self.check_effects(effects, &format!("{}.effects", path), location);
+            }
+            Effect::RandomChoice { choices } => {
+                for (i, (_, effects)) in choices.iter().enumerate() {
+                    let path = format!("{}.choices[{}]", path, i);
+                    self.check_effects(effects, &path, location);
+                }
+            }
+            Effect::Invoke { template, .. } if !defs.effect_templates.contains_key(template) => {
+                let path = format!("{}.template", path);
+                let defined = defs.effect_templates.keys();
+                self.undefined("effect template", template, defined, &path, location);
+            }
+            _ => {}
+        }
+    }
+
+    fn check_events(&mut self) {
+        let defs = self.defs;
+        for (id, event) in sorted(&defs.events) {
+            let location = defs.sources.events.get(id);
+            let path = format!("events.{}", id);
+            if let Some(kind) = &event.target_kind {
+                self.check_entity_kind(kind, &format!("{}.target_kind", path), location);
+            }
+            self.check_effects(&event.immediate, &format!("{}.immediate", path), location);
+            for (i, option) in event.options.iter().enumerate() {
+                let path = format!("{}.options[{}].effects", path, i);
+                self.check_effects(&option.effects, &path, location);
+            }
+        }
+    }
+
+    fn check_event_pools(&mut self) {
+        let defs = self.defs;
+        for (id, pool) in sorted(&defs.event_pools) {
+            let location = defs.sources.event_pools.get(id);
+            let path = format!("event_pools.{}", id);
+            if let Some(kind) = &pool.target_kind {
+                self.check_entity_kind(kind, &format!("{}.target_kind", path), location);
+            }
+            for (i, entry) in pool.events.iter().enumerate() {
+                if !defs.events.contains_key(&entry.event) {
+                    let path = format!("{}.events[{}].event", path, i);
+                    self.undefined("event", &entry.event, defs.events.keys(), &path, location);
+                }
+            }
+        }
+    }
+
+    /// Check template effects, and report templates invoking themselves
+    fn check_effect_templates(&mut self) {
+        let defs = self.defs;
+        let mut templates = EffectTemplates::new();
+        for (id, def) in sorted(&defs.effect_templates) {
+            let location = defs.sources.effect_templates.get(id);
+            let path = format!("effect_templates.{}", id);
+            self.check_effects(&def.effects, &format!("{}.effects", path), location);
+            if let Err(CoreError::EffectTemplateCycle(cycle)) =
+                templates.register(id.clone(), def.to_template())
+            {
+                let chain: Vec<&str> = cycle
+                    .iter()
+                    .chain(cycle.first())
+                    .map(|id| id.as_str())
+                    .collect();
+                self.report(
+                    Diagnostic::error(
+                        path,
+                        format!("cyclic effect template: {}", chain.join(" -> ")),
+                    ),
+                    location,
+                );
+            }
+        }
+    }
+
+    fn check_entity_types(&mut self) {
+        let defs = self.defs;
+        for (id, entity_type) in sorted(&defs.entity_types) {
+            let location = defs.sources.entity_types.get(id);
+            let path = format!("entity_types.{}", id);
+            if let Some(parent) = &entity_type.extends {
+                self.check_entity_kind(parent, &format!("{}.extends", path), location);
+            }
+
+            for property in &entity_type.properties {
+                if let Some(default) = &property.default {
+                    if !value_matches(default, &property.property_type) {
+                        self.report(
+                            Diagnostic::error(
+                                format!("{}.properties.{}.default", path, property.name),
+                                format!(
+                                    "type mismatch: expected {:?}, found {:?}",
+                                    property.property_type, default
+                                ),
+                            ),
+                            location,
+                        );
+                    }
+                }
+            }
+
+            for (key, value) in &entity_type.defaults {
+                let path = format!("{}.defaults.{}", path, key);
+                match entity_type.properties.iter().find(|p| &p.name == key) {
+                    Some(property) if !value_matches(value, &property.property_type) => self
+                        .report(
+                            Diagnostic::error(
+                                path,
+                                format!(
+                                    "type mismatch: expected {:?}, found {:?}",
+                                    property.property_type, value
+                                ),
+                            ),
+                            location,
+                        ),
+                    Some(_) => {}
+                    // Properties may come from a parent type or be ad hoc
+                    None if entity_type.properties.is_empty() => {}
+                    None => {
+                        let mut diagnostic = Diagnostic::warning(
+                            path,
+                            format!("default for undeclared property `{}`", key),
+                        );
+                        let declared = entity_type.properties.iter().map(|p| p.name.as_str());
+                        if let Some(name) = closest(key, declared) {
+                            diagnostic =
+                                diagnostic.with_suggestion(format!("did you mean `{}`?", name));
+                        }
+                        self.report(diagnostic, location);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Report cycles of events emitting each other immediately
+    ///
+    /// Scheduled events are not followed: a delayed event re-scheduling
+    /// itself is the usual way to write recurring events.
+    fn check_cycles(&mut self) {
+        let defs = self.defs;
+        let graph: HashMap<&DefId, Vec<&DefId>> = defs
+            .events
+            .iter()
+            .map(|(id, event)| {
+                let mut emitted = Vec::new();
+                collect_emitted(&event.immediate, &mut emitted);
+                for option in &event.options {
+                    collect_emitted(&option.effects, &mut emitted);
+                }
+                emitted.retain(|e| defs.events.contains_key(*e));
+                emitted.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+                emitted.dedup();
+                (id, emitted)
+            })
+            .collect();
+
+        let mut done = HashSet::new();
+        let mut reported = HashSet::new();
+        for (id, _) in sorted(&defs.events) {
+            let mut stack = Vec::new();
+            find_cycles(id, &graph, &mut stack, &mut done, &mut |cycle| {
+                // Rotate so the same cycle is reported once, from its
+                // smallest ID
+                let start = (0..cycle.len())
+                    .min_by_key(|i| cycle[*i].as_str())
+                    .unwrap_or(0);
+                let mut cycle: Vec<&DefId> = cycle.to_vec();
+                cycle.rotate_left(start);
+                if reported.insert(cycle.clone()) {
+                    let chain: Vec<&str> = cycle
+                        .iter()
+                        .chain(cycle.first())
+                        .map(|id| id.as_str())
+                        .collect();
+                    self.diagnostics.push(
+                        Diagnostic::error(
+                            format!("events.{}", cycle[0]),
+                            format!("cyclic event chain: {}", chain.join(" -> ")),
+                        )
+                        .at(defs.sources.events.get(cycle[0]).cloned())
+                        .with_suggestion("use ScheduleEvent with a delay to break the cycle"),
+                    );
+                }
+            });
+        }
+    }
+}
+
+fn find_cycles<'a>(
+    id: &'a DefId,
+    graph: &HashMap<&'a DefId, Vec<&'a DefId>>,
+    stack: &mut Vec<&'a DefId>,
+    done: &mut HashSet<&'a DefId>,
+    on_cycle: &mut dyn FnMut(&[&'a DefId]),
+) {
+    if let Some(pos) = stack.iter().position(|s| *s == id) {
+        on_cycle(&stack[pos..]);
+        return;
+    }
+    if done.contains(id) {
+        return;
+    }
+    stack.push(id);
+    for next in graph.get(id).into_iter().flatten() {
+        find_cycles(next, graph, stack, done, on_cycle);
+    }
+    stack.pop();
+    done.insert(id);
+}
+
+fn collect_emitted<'a>(effects: &'a [Effect], emitted: &mut Vec<&'a DefId>) {
+    for effect in effects {
+        match effect {
+            Effect::EmitEvent { event, .. } => emitted.push(event),
+            Effect::If {
+                then_effects,
+                else_effects,
+                ..
+            } => {
+                collect_emitted(then_effects, emitted);
+                collect_emitted(else_effects, emitted);
+            }
+            Effect::Sequence(effects) | Effect::ForEachEntity { effects, .. } => {
+                collect_emitted(effects, emitted)
+            }
+            Effect::RandomChoice { choices } => {
+                for (_, effects) in choices {
+                    collect_emitted(effects, emitted);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl GameDefs {
+    /// Check the definitions for problems the loader does not catch
+    ///
+    /// Returns errors first, then warnings.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut validator = Validator {
+            defs: self,
+            diagnostics: Vec::new(),
+        };
+        validator.check_events();
+        validator.check_event_pools();
+        validator.check_effect_templates();
+        validator.check_entity_types();
+        validator.check_cycles();
+
+        let mut diagnostics = validator.diagnostics;
+        diagnostics.sort_by_key(|d| d.severity);
+        diagnostics
+    }
+}
+
+impl Loader {
+    /// Load a file or directory, collecting diagnostics instead of stopping
+    /// at the first error
+    ///
+    /// Files that fail to parse are skipped and reported; the definitions
+    /// that did load are then validated.
+    pub fn load_with_diagnostics(path: impl AsRef<Path>) -> (GameDefs, Vec<Diagnostic>) {
+        let mut loader = Loader::new();
+        let mut diagnostics = Vec::new();
+        loader.load_reporting(path.as_ref(), &mut diagnostics);
+        let defs = loader.finish();
+        diagnostics.extend(defs.validate());
+        (defs, diagnostics)
+    }
+
+    fn load_reporting(&mut self, path: &Path, diagnostics: &mut Vec<Diagnostic>) {
+        if !path.is_dir() {
+            if let Err(e) = self.load_file(path) {
+                diagnostics.push(Diagnostic::from_error(&e, Some(path)));
+            }
+            return;
+        }
+
+        let entries = match read_dir_sorted(path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                diagnostics.push(Diagnostic::from_error(&e, Some(path)));
+                return;
+            }
+        };
+        for entry in entries {
+            if entry.is_dir() || Format::from_path(&entry).is_some() {
+                self.load_reporting(&entry, diagnostics);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_error_suggestion() {
+        let content = r#"(events: [(
+    id: "harvest",
+    name: "Harvest",
+    immediate: [ModifyGlobl(property: "gold", op: Add, value: Literal(Float(5.0)))],
+)])"#;
+        let mut loader = Loader::new();
+        let error = loader.load_events_str(content).unwrap_err();
+        let diagnostic = Diagnostic::from_error(&error, Some(Path::new("events.ron")));
+
+        assert!(diagnostic.is_error());
+        assert_eq!(diagnostic.location.as_ref().map(|l| l.line), Some(4));
+        assert_eq!(diagnostic.message, "unknown effect `ModifyGlobl`");
+        assert_eq!(
+            diagnostic.suggestion.as_deref(),
+            Some("did you mean `ModifyGlobal`?")
+        );
+        assert!(diagnostic.to_string().starts_with("events.ron:4:"));
+    }
+
+    #[test]
+    fn test_undefined_references() {
+        let mut loader = Loader::new();
+        loader
+            .load_entity_types_str(
+                r#"(entity_types: [(
+    id: "nation",
+    name: "Nation",
+    properties: [(name: "gold", property_type: Float, default: Some(String("lots")))],
+)])"#,
+            )
+            .unwrap();
+        loader
+            .load_events_str(
+                r#"(events: [(
+    id: "revolt",
+    name: "Revolt",
+    target_kind: Some("natoin"),
+    immediate: [EmitEvent(event: "civil_war", target: Global, params: [])],
+)])"#,
+            )
+            .unwrap();
+        let diagnostics = loader.finish().validate();
+
+        assert_eq!(diagnostics.len(), 3);
+        let target_kind = &diagnostics[0];
+        assert_eq!(target_kind.path, "events.revolt.target_kind");
+        assert_eq!(
+            target_kind.suggestion.as_deref(),
+            Some("did you mean `nation`?")
+        );
+        assert_eq!(target_kind.location.as_ref().map(|l| l.line), Some(2));
+        assert_eq!(diagnostics[1].path, "events.revolt.immediate[0].event");
+        assert_eq!(
+            diagnostics[2].path,
+            "entity_types.nation.properties.gold.default"
+        );
+    }
+
+    #[test]
+    fn test_cyclic_event_chain() {
+        let emit = |id: &str, next: &str| {
+            format!(
+                r#"(id: "{}", name: "{}", immediate: [EmitEvent(event: "{}", target: Global, params: [])])"#,
+                id, id, next
+            )
+        };
+        let mut loader = Loader::new();
+        loader
+            .load_events_str(&format!(
+                "(events: [{}, {}, {}])",
+                emit("war", "peace"),
+                emit("peace", "war"),
+                emit("truce", "peace")
+            ))
+            .unwrap();
+        let diagnostics = loader.finish().validate();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "cyclic event chain: peace -> war -> peace"
+        );
+    }
+
+    #[test]
+    fn test_effect_template_references() {
+        let invoke = |id: &str, next: &str| {
+            format!(
+                r#"(id: "{}", effects: [Invoke(template: "{}", args: [])])"#,
+                id, next
+            )
+        };
+        let mut loader = Loader::new();
+        loader
+            .load_effect_templates_str(&format!(
+                "(effect_templates: [{}, {}, {}])",
+                invoke("raid", "pillage"),
+                invoke("pillage", "raid"),
+                invoke("siege", "asault")
+            ))
+            .unwrap();
+        let diagnostics = loader.finish().validate();
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[0].message,
+            "cyclic effect template: raid -> pillage -> raid"
+        );
+        assert_eq!(
+            diagnostics[1].path,
+            "effect_templates.siege.effects[0].template"
+        );
+        assert_eq!(diagnostics[1].message, "undefined effect template `asault`");
+    }
+}
@@ -0,0 +1,91 @@
+@tool
+extends EditorDebuggerPlugin
+## Editor side of the time-travel debugger: a timeline slider, step buttons
+## and an entity inspector, driven by messages from runtime_bridge.gd
+
+var panels := {}
+
+func _has_capture(prefix: String) -> bool:
+	return prefix == "pulsive"
+
+func _capture(message: String, data: Array, session_id: int) -> bool:
+	var panel: Control = panels.get(session_id)
+	if panel == null:
+		return false
+	match message:
+		"pulsive:state":
+			_show_state(panel, data[0])
+			return true
+		"pulsive:entity":
+			panel.get_node("Inspector").text = JSON.stringify(data[0], "  ")
+			return true
+	return false
+
+func _setup_session(session_id: int) -> void:
+	var session := get_session(session_id)
+	var panel := _build_panel(session)
+	panel.name = "Pulsive"
+	panels[session_id] = panel
+	session.add_session_tab(panel)
+	session.stopped.connect(func(): panel.get_node("Status").text = "Not running")
+
+func _build_panel(session: EditorDebuggerSession) -> Control:
+	var panel := VBoxContainer.new()
+
+	var status := Label.new()
+	status.name = "Status"
+	status.text = "Waiting for game..."
+	panel.add_child(status)
+
+	var timeline := HSlider.new()
+	timeline.name = "Timeline"
+	timeline.step = 1
+	timeline.editable = false
+	timeline.drag_ended.connect(func(_changed):
+		session.send_message("pulsive:goto_tick", [int(timeline.value)]))
+	panel.add_child(timeline)
+
+	var buttons := HBoxContainer.new()
+	panel.add_child(buttons)
+	for action in [["Record", "pulsive:enable"], ["<", "pulsive:step_back"],
+			[">", "pulsive:step_forward"], ["Live", "pulsive:live"]]:
+		var button := Button.new()
+		button.text = action[0]
+		var msg: String = action[1]
+		button.pressed.connect(func(): session.send_message(msg, []))
+		buttons.add_child(button)
+
+	var entity_id := SpinBox.new()
+	entity_id.prefix = "Entity"
+	entity_id.max_value = 1 << 31
+	buttons.add_child(entity_id)
+	var inspect := Button.new()
+	inspect.text = "Inspect"
+	inspect.pressed.connect(func():
+		session.send_message("pulsive:inspect", [int(entity_id.value)]))
+	buttons.add_child(inspect)
+
+	var inspector := TextEdit.new()
+	inspector.name = "Inspector"
+	inspector.editable = false
+	inspector.size_flags_vertical = Control.SIZE_EXPAND_FILL
+	panel.add_child(inspector)
+	return panel
+
+func _show_state(panel: Control, state: Dictionary) -> void:
+	var stats: Dictionary = state.stats
+	var timeline: HSlider = panel.get_node("Timeline")
+	if stats.is_empty():
+		panel.get_node("Status").text = "Journal disabled (press Record)"
+		timeline.editable = false
+		return
+	var viewing: int = state.history_tick
+	panel.get_node("Status").text = "Tick %d | %s | recorded %d-%d | %d messages, %d snapshots" % [
+		state.tick,
+		"live" if viewing < 0 else "viewing tick %d" % viewing,
+		stats.first_tick, stats.last_tick, stats.message_count, stats.snapshot_count,
+	]
+	timeline.editable = true
+	timeline.min_value = stats.first_tick
+	timeline.max_value = stats.last_tick
+	timeline.set_value_no_signal(state.tick if viewing < 0 else viewing)
@@ -0,0 +1,13 @@
+@tool
+extends EditorPlugin
+## Adds the "Pulsive" tab to the editor's debugger panel
+
+var debugger: EditorDebuggerPlugin
+
+func _enter_tree() -> void:
+	debugger = preload("debugger.gd").new()
+	add_debugger_plugin(debugger)
+
+func _exit_tree() -> void:
+	remove_debugger_plugin(debugger)
+	debugger = null
@@ -0,0 +1,50 @@
+extends Node
+## Game side of the time-travel debugger. Add as a child of (or point
+## engine_path at) the PulsiveEngine; it answers the editor's debugger
+## messages and reports the journal state every frame while debugging.
+
+@export var engine_path: NodePath = ^".."
+## Start recording when the game starts
+@export var record_on_start := true
+## Ticks between journal snapshots (more snapshots = faster scrubbing)
+@export var snapshot_interval := 100
+
+@onready var engine: PulsiveEngine = get_node(engine_path)
+
+func _ready() -> void:
+	if not EngineDebugger.is_active():
+		set_process(false)
+		return
+	EngineDebugger.register_message_capture("pulsive", _on_message)
+	if record_on_start:
+		engine.enable_journal(snapshot_interval)
+
+func _exit_tree() -> void:
+	if EngineDebugger.is_active():
+		EngineDebugger.unregister_message_capture("pulsive")
+
+func _process(_delta: float) -> void:
+	EngineDebugger.send_message("pulsive:state", [{
+		"tick": engine.get_tick(),
+		"history_tick": engine.get_history_tick(),
+		"stats": engine.get_journal_stats(),
+	}])
+
+func _on_message(message: String, data: Array) -> bool:
+	match message:
+		"enable":
+			if not engine.is_journal_enabled():
+				engine.enable_journal(snapshot_interval)
+		"goto_tick":
+			engine.goto_tick(data[0])
+		"step_back":
+			engine.step_back()
+		"step_forward":
+			engine.step_forward()
+		"live":
+			engine.return_to_live()
+		"inspect":
+			EngineDebugger.send_message("pulsive:entity", [engine.inspect_entity(data[0])])
+		_:
+			return false
+	return true